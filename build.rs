@@ -1,26 +1,35 @@
 fn main() {
     let mut versions = vec![];
-    if cfg!(feature = "llvm-8") {
+    if cfg!(feature = "llvm-8") || cfg!(feature = "llvm-8-no-llvm-sys") {
         versions.push(8);
     }
-    if cfg!(feature = "llvm-9") {
+    if cfg!(feature = "llvm-9") || cfg!(feature = "llvm-9-no-llvm-sys") {
         versions.push(9);
     }
-    if cfg!(feature = "llvm-10") {
+    if cfg!(feature = "llvm-10") || cfg!(feature = "llvm-10-no-llvm-sys") {
         versions.push(10);
     }
-    if cfg!(feature = "llvm-11") {
+    if cfg!(feature = "llvm-11") || cfg!(feature = "llvm-11-no-llvm-sys") {
         versions.push(11);
     }
-    if cfg!(feature = "llvm-12") {
+    if cfg!(feature = "llvm-12") || cfg!(feature = "llvm-12-no-llvm-sys") {
         versions.push(12);
     }
-    if cfg!(feature = "llvm-13") {
+    if cfg!(feature = "llvm-13") || cfg!(feature = "llvm-13-no-llvm-sys") {
         versions.push(13);
     }
-    if cfg!(feature = "llvm-14") {
+    if cfg!(feature = "llvm-14") || cfg!(feature = "llvm-14-no-llvm-sys") {
         versions.push(14);
     }
+    if cfg!(feature = "llvm-15") || cfg!(feature = "llvm-15-no-llvm-sys") {
+        versions.push(15);
+    }
+    if cfg!(feature = "llvm-16") || cfg!(feature = "llvm-16-no-llvm-sys") {
+        versions.push(16);
+    }
+    if cfg!(feature = "llvm-17") || cfg!(feature = "llvm-17-no-llvm-sys") {
+        versions.push(17);
+    }
     match versions.len() {
         0 => panic!("llvm-ir: Please select an LLVM version using a Cargo feature."),
         1 => {},
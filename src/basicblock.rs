@@ -19,10 +19,7 @@ impl BasicBlock {
         Self {
             name,
             instrs: vec![],
-            term: Terminator::Unreachable(Unreachable {
-                #[cfg(feature = "llvm-9-or-greater")]
-                debugloc: None,
-            }),
+            term: Terminator::Unreachable(Unreachable { debugloc: None }),
         }
     }
 }
@@ -31,20 +28,29 @@ impl BasicBlock {
 // from_llvm //
 // ********* //
 
+#[cfg(feature = "llvm-sys")]
 use crate::from_llvm::*;
+#[cfg(feature = "llvm-sys")]
 use crate::function::FunctionContext;
+#[cfg(feature = "llvm-sys")]
 use crate::llvm_sys::*;
+#[cfg(feature = "llvm-sys")]
 use crate::module::ModuleContext;
+#[cfg(feature = "llvm-sys")]
+use crate::name::SymbolTable;
+#[cfg(feature = "llvm-sys")]
 use llvm_sys::LLVMOpcode;
+#[cfg(feature = "llvm-sys")]
 use llvm_sys::LLVMTypeKind::LLVMVoidTypeKind;
 
+#[cfg(feature = "llvm-sys")]
 impl BasicBlock {
     pub(crate) fn from_llvm_ref(
         bb: LLVMBasicBlockRef,
         ctx: &mut ModuleContext,
         func_ctx: &mut FunctionContext,
     ) -> Self {
-        let name = Name::name_or_num(unsafe { get_bb_name(bb) }, &mut func_ctx.ctr);
+        let name = func_ctx.local_name(unsafe { get_bb_name(bb) });
         debug_assert_eq!(
             &name,
             func_ctx
@@ -70,20 +76,33 @@ impl BasicBlock {
     pub(crate) fn first_pass_names(
         bb: LLVMBasicBlockRef,
         ctr: &mut usize,
+        keep_numeric_names: bool,
+        symbols: &mut SymbolTable,
     ) -> (Name, Vec<(LLVMValueRef, Name)>) {
-        let bbname = Name::name_or_num(unsafe { get_bb_name(bb) }, ctr);
+        let bbname =
+            Name::local_name_or_num(unsafe { get_bb_name(bb) }, ctr, keep_numeric_names, symbols);
         let mut instnames = vec![];
         for inst in all_but_last(get_instructions(bb)).filter(|&i| needs_name(i)) {
             instnames.push((
                 inst,
-                Name::name_or_num(unsafe { get_value_name(inst) }, ctr),
+                Name::local_name_or_num(
+                    unsafe { get_value_name(inst) },
+                    ctr,
+                    keep_numeric_names,
+                    symbols,
+                ),
             ));
         }
         let term = unsafe { LLVMGetBasicBlockTerminator(bb) };
         if term_needs_name(term) {
             instnames.push((
                 term,
-                Name::name_or_num(unsafe { get_value_name(term) }, ctr),
+                Name::local_name_or_num(
+                    unsafe { get_value_name(term) },
+                    ctr,
+                    keep_numeric_names,
+                    symbols,
+                ),
             ));
         }
         (bbname, instnames)
@@ -91,6 +110,7 @@ impl BasicBlock {
 }
 
 // Given only the LLVMValueRef for an Instruction, determine whether it needs a name
+#[cfg(feature = "llvm-sys")]
 fn needs_name(inst: LLVMValueRef) -> bool {
     if unsafe { !get_value_name(inst).is_empty() } {
         return true; // has a string name
@@ -109,6 +129,7 @@ fn needs_name(inst: LLVMValueRef) -> bool {
 }
 
 // Given only the LLVMValueRef for a Terminator, determine whether it needs a name
+#[cfg(feature = "llvm-sys")]
 fn term_needs_name(term: LLVMValueRef) -> bool {
     if unsafe { !get_value_name(term).is_empty() } {
         return true; // has a string name
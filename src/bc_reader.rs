@@ -0,0 +1,1252 @@
+//! A reader for LLVM bitcode (`.bc` files), written in pure Rust.
+//!
+//! [`Module::from_bc_path()`](../module/struct.Module.html#method.from_bc_path)
+//! reads bitcode by calling into LLVM, which requires a system LLVM matching
+//! the selected LLVM version. The reader in this module instead decodes the
+//! bitcode itself, so that `llvm-ir` can be used on machines without LLVM
+//! installed (together with the `llvm-N-no-llvm-sys` features). It is
+//! enabled with the `bc-reader` feature, which also enables `ll-parser`.
+//!
+//! The reader decodes the bitstream container and the module's blocks, and
+//! builds the `Module` with the same machinery as
+//! [`ll_parser`](../ll_parser/index.html), so everything said there about
+//! how the result can differ from what LLVM gives also applies here. In
+//! addition:
+//!
+//! - LLVM upgrades old bitcode as it reads it. This reader performs the
+//!   upgrades which affect how the bitcode is decoded (e.g., old encodings
+//!   of attributes, `cmpxchg`, `landingpad`, and debug info), and strips debug
+//!   info with an outdated version as LLVM does, but doesn't upgrade calls to
+//!   outdated intrinsics, or the `blockaddress` arguments of old `callbr`s.
+//! - LLVM folds some constant expressions as it reads them (e.g., a
+//!   `shufflevector` of `zeroinitializer`s); this reader keeps them as they
+//!   are in the bitcode.
+//! - Metadata nodes in `AttachedMetadata::Other` are printed as LLVM prints
+//!   them only for the kinds of debug-info nodes which are attached to
+//!   functions and global variables (`DISubprogram` and
+//!   `DIGlobalVariableExpression`); other debug-info nodes are given without
+//!   their fields.
+
+mod attributes;
+mod bitstream;
+mod constants;
+mod functions;
+mod metadata;
+mod types;
+
+use crate::module::Module;
+use attributes::AttributeList;
+use bitstream::{Block, Item, Record};
+use metadata::Metadata;
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::path::Path;
+use types::{Ty, TypeId, Types};
+
+/// Read a `Module` from the LLVM bitcode (`.bc`) file at the given path.
+/// This is a drop-in replacement for `Module::from_bc_path()`.
+pub fn parse_path(path: impl AsRef<Path>) -> Result<Module, String> {
+    let path = path
+        .as_ref()
+        .to_str()
+        .expect("Did not find a valid Unicode path string");
+    let bytes = std::fs::read(path).map_err(|e| format!("{}: {}", path, e))?;
+    parse(&bytes, path.to_owned()).map_err(|e| format!("{}: {}", path, e))
+}
+
+/// Read a `Module` from the given LLVM bitcode. The `Module`'s `name` is
+/// empty.
+pub fn parse_bytes(bytes: &[u8]) -> Result<Module, String> {
+    parse(bytes, String::new())
+}
+
+fn parse(bytes: &[u8], name: String) -> Result<Module, String> {
+    let (text, metadata_kinds, opaque_pointers) = disassemble(bytes)?;
+    crate::ll_parser::parse_generated(&text, name, metadata_kinds, opaque_pointers)
+        .map_err(|e| format!("in the decoded IR at {}", e))
+}
+
+/// How deeply constants may be nested, which bounds the recursion in
+/// `Reader::constant_text()`
+const MAX_CONSTANT_DEPTH: usize = 64;
+
+// block IDs
+const MODULE_BLOCK_ID: u32 = 8;
+const PARAMATTR_BLOCK_ID: u32 = 9;
+const PARAMATTR_GROUP_BLOCK_ID: u32 = 10;
+const CONSTANTS_BLOCK_ID: u32 = 11;
+const FUNCTION_BLOCK_ID: u32 = 12;
+const VALUE_SYMTAB_BLOCK_ID: u32 = 14;
+const METADATA_BLOCK_ID: u32 = 15;
+const METADATA_ATTACHMENT_ID: u32 = 16;
+const TYPE_BLOCK_ID_NEW: u32 = 17;
+const OPERAND_BUNDLE_TAGS_BLOCK_ID: u32 = 21;
+const METADATA_KIND_BLOCK_ID: u32 = 22;
+const STRTAB_BLOCK_ID: u32 = 23;
+const SYNC_SCOPE_NAMES_BLOCK_ID: u32 = 26;
+
+// MODULE_CODE_*
+const VERSION: u32 = 1;
+const TRIPLE: u32 = 2;
+const DATALAYOUT: u32 = 3;
+const ASM: u32 = 4;
+const SECTIONNAME: u32 = 5;
+const GLOBALVAR: u32 = 7;
+const FUNCTION: u32 = 8;
+const ALIAS_OLD: u32 = 9;
+const GCNAME: u32 = 11;
+const COMDAT: u32 = 12;
+const ALIAS: u32 = 14;
+const SOURCE_FILENAME: u32 = 16;
+const IFUNC: u32 = 18;
+
+// VST_CODE_*
+const VST_ENTRY: u32 = 1;
+const VST_BBENTRY: u32 = 2;
+const VST_FNENTRY: u32 = 3;
+
+/// Decode the given bitcode into textual IR, giving the text, the names of
+/// the metadata kinds in the order of their IDs, and whether pointers are
+/// opaque
+fn disassemble(bytes: &[u8]) -> Result<(String, Vec<String>, bool), String> {
+    let blocks = bitstream::parse(bytes)?;
+    let strtab = blocks
+        .iter()
+        .filter(|block| block.id == STRTAB_BLOCK_ID)
+        .flat_map(|block| block.records())
+        .find_map(|record| record.blob.as_deref())
+        .unwrap_or(&[]);
+    let module = blocks
+        .iter()
+        .find(|block| block.id == MODULE_BLOCK_ID)
+        .ok_or("No module found in the bitcode")?;
+    let mut reader = Reader::new(strtab);
+    reader.read_module(module)?;
+    let text = reader.module_text()?;
+    Ok((
+        text,
+        reader.metadata.kind_names(),
+        reader.types.opaque_pointers,
+    ))
+}
+
+/// The kinds of global values
+#[derive(PartialEq, Clone, Copy, Debug)]
+enum GlobalKind {
+    Variable,
+    Function,
+    Alias,
+    IFunc,
+}
+
+/// A global variable, function, alias, or ifunc
+struct Global<'a> {
+    kind: GlobalKind,
+    name: String,
+    /// The operands of its record, without the strtab offset and size
+    ops: &'a [u64],
+    /// The type of the value (e.g., the function type of a function)
+    value_type: TypeId,
+    addr_space: u32,
+    /// The linkage as encoded in the record, which old bitcode also uses to
+    /// encode DLL storage classes and implicit comdats
+    raw_linkage: u64,
+}
+
+impl Global<'_> {
+    fn op(&self, i: usize) -> u64 {
+        self.ops.get(i).copied().unwrap_or(0)
+    }
+
+    fn is_declaration(&self) -> bool {
+        match self.kind {
+            GlobalKind::Variable => self.op(2) == 0,
+            GlobalKind::Function => self.op(2) != 0,
+            GlobalKind::Alias | GlobalKind::IFunc => false,
+        }
+    }
+}
+
+/// An entry of the value table
+struct Value<'a> {
+    ty: TypeId,
+    kind: ValueKind<'a>,
+}
+
+enum ValueKind<'a> {
+    /// The global value with the given index in `Reader::globals`
+    Global(usize),
+    /// A constant, whose text is only built when it's needed, since it may
+    /// refer to constants later in the table
+    Constant(&'a Record, RefCell<Option<String>>),
+    /// The local value (argument or instruction result) of the current
+    /// function with the given index
+    Local(usize),
+}
+
+struct Reader<'a> {
+    strtab: &'a [u8],
+    /// `MODULE_CODE_VERSION`: 0 for absolute value IDs, 1 for relative value
+    /// IDs, and 2 for names in the string table
+    version: u64,
+    types: Types,
+    attribute_lists: Vec<AttributeList>,
+    values: Vec<Value<'a>>,
+    globals: Vec<Global<'a>>,
+    /// The name and selection kind of each comdat
+    comdats: Vec<(String, &'static str)>,
+    section_names: Vec<String>,
+    gc_names: Vec<String>,
+    sync_scope_names: Vec<String>,
+    bundle_tags: Vec<String>,
+    metadata: Metadata,
+    source_file_name: Option<String>,
+    triple: String,
+    data_layout: String,
+    inline_asm: String,
+    /// The bodies of the defined functions, in order
+    function_blocks: Vec<&'a Block>,
+    /// The text of each function, once it's been decoded
+    function_texts: Vec<String>,
+    /// The labels of the basic blocks of each defined function (by value ID),
+    /// for `blockaddress` constants
+    block_labels: HashMap<usize, Vec<String>>,
+    /// How many calls of `constant_text` are in progress, as constants refer
+    /// to other constants, and in a malformed file may refer to themselves
+    constant_depth: Cell<usize>,
+}
+
+impl<'a> Reader<'a> {
+    fn new(strtab: &'a [u8]) -> Self {
+        Self {
+            strtab,
+            version: 0,
+            types: Types::new(),
+            attribute_lists: vec![],
+            values: vec![],
+            globals: vec![],
+            comdats: vec![],
+            section_names: vec![],
+            gc_names: vec![],
+            sync_scope_names: vec![],
+            bundle_tags: vec![],
+            metadata: Metadata::new(),
+            source_file_name: None,
+            triple: String::new(),
+            data_layout: String::new(),
+            inline_asm: String::new(),
+            function_blocks: vec![],
+            function_texts: vec![],
+            block_labels: HashMap::new(),
+            constant_depth: Cell::new(0),
+        }
+    }
+
+    fn read_module(&mut self, module: &'a Block) -> Result<(), String> {
+        let mut groups = HashMap::new();
+        let mut symtab = None;
+        for item in &module.items {
+            match item {
+                Item::Block(block) => match block.id {
+                    PARAMATTR_GROUP_BLOCK_ID => groups = attributes::parse_groups(block)?,
+                    PARAMATTR_BLOCK_ID => {
+                        self.attribute_lists = attributes::parse_lists(block, &groups)?
+                    },
+                    TYPE_BLOCK_ID_NEW => self.types.parse_block(block)?,
+                    CONSTANTS_BLOCK_ID => self.read_constants(block)?,
+                    METADATA_KIND_BLOCK_ID => self.metadata.read_kinds(block),
+                    METADATA_BLOCK_ID => self.read_metadata(block)?,
+                    VALUE_SYMTAB_BLOCK_ID => symtab = Some(block),
+                    FUNCTION_BLOCK_ID => self.function_blocks.push(block),
+                    OPERAND_BUNDLE_TAGS_BLOCK_ID => {
+                        self.bundle_tags = block.records().map(|r| r.string(0)).collect()
+                    },
+                    SYNC_SCOPE_NAMES_BLOCK_ID => {
+                        self.sync_scope_names = block.records().map(|r| r.string(0)).collect()
+                    },
+                    _ => {},
+                },
+                Item::Record(record) => self.read_module_record(record)?,
+            }
+        }
+        if let Some(symtab) = symtab {
+            self.read_module_symtab(symtab);
+        }
+        if let Some(global) = self.globals.iter().find(|g| g.name.is_empty()) {
+            return Err(format!(
+                "Unnamed {} aren't supported",
+                match global.kind {
+                    GlobalKind::Variable => "global variables",
+                    GlobalKind::Function => "functions",
+                    GlobalKind::Alias => "aliases",
+                    GlobalKind::IFunc => "ifuncs",
+                }
+            ));
+        }
+        self.metadata.finish_module();
+
+        let functions: Vec<usize> = (0 .. self.globals.len())
+            .filter(|&i| {
+                self.globals[i].kind == GlobalKind::Function && !self.globals[i].is_declaration()
+            })
+            .collect();
+        if functions.len() != self.function_blocks.len() {
+            return Err(format!(
+                "Found {} function bodies for {} defined functions",
+                self.function_blocks.len(),
+                functions.len()
+            ));
+        }
+        let blocks = std::mem::take(&mut self.function_blocks);
+        for (&global, block) in functions.iter().zip(blocks) {
+            let text = self.read_function(global, block)?;
+            self.function_texts.push(text);
+        }
+        Ok(())
+    }
+
+    fn read_module_record(&mut self, record: &'a Record) -> Result<(), String> {
+        match record.code {
+            VERSION => self.version = record.op(0),
+            TRIPLE => self.triple = record.string(0),
+            DATALAYOUT => self.data_layout = record.string(0),
+            SOURCE_FILENAME => self.source_file_name = Some(record.string(0)),
+            ASM => {
+                self.inline_asm = record.string(0);
+            },
+            SECTIONNAME => self.section_names.push(record.string(0)),
+            GCNAME => self.gc_names.push(record.string(0)),
+            COMDAT => {
+                // [strtab_offset, strtab_size, selection_kind], or
+                // [selection_kind, name] before the string table
+                let (kind, name) = if self.version >= 2 {
+                    (record.op(2), self.strtab_name(record))
+                } else {
+                    let len = record.op(1) as usize;
+                    let name = record
+                        .ops
+                        .iter()
+                        .skip(2)
+                        .take(len)
+                        .map(|&c| c as u8 as char)
+                        .collect();
+                    (record.op(0), name)
+                };
+                self.comdats.push((name, selection_kind(kind)));
+            },
+            GLOBALVAR | FUNCTION | ALIAS | ALIAS_OLD | IFUNC => self.read_global(record)?,
+            _ => {},
+        }
+        Ok(())
+    }
+
+    /// The name of a global value or comdat, from the string table
+    fn strtab_name(&self, record: &Record) -> String {
+        let (offset, size) = (record.op(0) as usize, record.op(1) as usize);
+        self.strtab
+            .get(offset .. offset + size)
+            .map(|name| String::from_utf8_lossy(name).into_owned())
+            .unwrap_or_default()
+    }
+
+    fn read_global(&mut self, record: &'a Record) -> Result<(), String> {
+        let (name, ops) = if self.version >= 2 {
+            (self.strtab_name(record), record.ops.get(2 ..).unwrap_or(&[]))
+        } else {
+            (String::new(), record.ops.as_slice())
+        };
+        let op = |i: usize| ops.get(i).copied().unwrap_or(0);
+        let (kind, value_type, addr_space, raw_linkage) = match record.code {
+            GLOBALVAR => {
+                // [type, flags, initid, linkage, ...]; the type is that of the
+                // global itself (a pointer) unless the explicit type flag is
+                // set
+                let (ty, flags) = (op(0) as TypeId, op(1));
+                let (value_type, addr_space) = if flags & 2 != 0 {
+                    (ty, (flags >> 2) as u32)
+                } else {
+                    let pointee = self
+                        .types
+                        .pointee(ty)
+                        .ok_or("Invalid type for a global variable")?;
+                    (pointee, self.types.addr_space(ty))
+                };
+                (GlobalKind::Variable, value_type, addr_space, op(3))
+            },
+            FUNCTION => {
+                // [type, callingconv, isproto, linkage, ..., addrspace, ...]
+                let ty = op(0) as TypeId;
+                let value_type = self.types.pointee(ty).unwrap_or(ty);
+                let addr_space = match ops.get(16) {
+                    Some(&addr_space) => addr_space as u32,
+                    None => data_layout_addr_space(&self.data_layout, 'P'),
+                };
+                (GlobalKind::Function, value_type, addr_space, op(3))
+            },
+            ALIAS_OLD => {
+                // [alias type (a pointer), aliasee, linkage, ...]
+                let ty = op(0) as TypeId;
+                let value_type = self.types.pointee(ty).ok_or("Invalid type for an alias")?;
+                (
+                    GlobalKind::Alias,
+                    value_type,
+                    self.types.addr_space(ty),
+                    op(2),
+                )
+            },
+            // [value type, addrspace, aliasee or resolver, linkage, ...]
+            ALIAS => (GlobalKind::Alias, op(0) as TypeId, op(1) as u32, op(3)),
+            _ => (GlobalKind::IFunc, op(0) as TypeId, op(1) as u32, op(3)),
+        };
+        let ty = self.types.pointer_to(value_type, addr_space);
+        self.values.push(Value {
+            ty,
+            kind: ValueKind::Global(self.globals.len()),
+        });
+        self.globals.push(Global {
+            kind,
+            name,
+            ops,
+            value_type,
+            addr_space,
+            raw_linkage,
+        });
+        Ok(())
+    }
+
+    /// Read the names of global values from the module-level value symbol
+    /// table (in bitcode from before the string table)
+    fn read_module_symtab(&mut self, block: &Block) {
+        // from version 2 on, names are in the string table, and the symbol
+        // table only gives the offsets of function blocks
+        if self.version >= 2 {
+            return;
+        }
+        for record in block.records() {
+            let name_start = match record.code {
+                VST_ENTRY => 1,
+                VST_FNENTRY => 2,
+                _ => continue,
+            };
+            if let Some(Value {
+                kind: ValueKind::Global(i),
+                ..
+            }) = self.values.get(record.op(0) as usize)
+            {
+                self.globals[*i].name = record.string(name_start);
+            }
+        }
+    }
+
+    // ****** //
+    // values //
+    // ****** //
+
+    fn value(&self, id: usize) -> Result<&Value<'a>, String> {
+        self.values
+            .get(id)
+            .ok_or_else(|| format!("Invalid value ID {}", id))
+    }
+
+    /// The text of a global value or constant, e.g. `@foo` or `i32 1`
+    /// without its type
+    fn constant_text(&self, id: usize) -> Result<String, String> {
+        match &self.value(id)?.kind {
+            ValueKind::Global(i) => Ok(global_name(&self.globals[*i].name)),
+            ValueKind::Constant(record, text) => {
+                if let Some(text) = &*text.borrow() {
+                    return Ok(text.clone());
+                }
+                let depth = self.constant_depth.get();
+                if depth >= MAX_CONSTANT_DEPTH {
+                    return Err(format!(
+                        "Constant {} is nested too deeply, or refers to itself",
+                        id
+                    ));
+                }
+                self.constant_depth.set(depth + 1);
+                let new_text = self.decode_constant(record, self.values[id].ty);
+                self.constant_depth.set(depth);
+                let new_text = new_text?;
+                *text.borrow_mut() = Some(new_text.clone());
+                Ok(new_text)
+            },
+            ValueKind::Local(_) => Err(format!(
+                "Value {} is used as a constant, but is local to a function",
+                id
+            )),
+        }
+    }
+
+    /// The text of a global value or constant with its type, e.g. `i32 1`
+    fn typed_constant_text(&self, id: usize) -> Result<String, String> {
+        Ok(format!(
+            "{} {}",
+            self.types.text(self.value(id)?.ty),
+            self.constant_text(id)?
+        ))
+    }
+
+    // ********** //
+    // attributes //
+    // ********** //
+
+    /// The attribute list with the given 1-based index (0 for none)
+    fn attribute_list(&self, index: u64) -> AttributeList {
+        match index {
+            0 => AttributeList::default(),
+            i => self
+                .attribute_lists
+                .get(i as usize - 1)
+                .cloned()
+                .unwrap_or_default(),
+        }
+    }
+
+    fn sync_scope_text(&self, id: u64) -> String {
+        let name = if self.sync_scope_names.is_empty() {
+            // before sync scope names, 0 meant single-threaded and 1 meant
+            // the whole system
+            if id == 0 {
+                "singlethread"
+            } else {
+                ""
+            }
+        } else {
+            self.sync_scope_names
+                .get(id as usize)
+                .map_or("", |name| name.as_str())
+        };
+        if name.is_empty() {
+            String::new()
+        } else {
+            format!(" syncscope(\"{}\")", escape(name))
+        }
+    }
+
+    // *************** //
+    // the module text //
+    // *************** //
+
+    fn module_text(&mut self) -> Result<String, String> {
+        let mut text = String::new();
+        if let Some(source_file_name) = &self.source_file_name {
+            text.push_str(&format!(
+                "source_filename = \"{}\"\n",
+                escape(source_file_name)
+            ));
+        }
+        check_data_layout_alignments(&self.data_layout)?;
+        let data_layout = upgrade_data_layout(&self.data_layout, &self.triple);
+        if !data_layout.is_empty() {
+            text.push_str(&format!(
+                "target datalayout = \"{}\"\n",
+                escape(&data_layout)
+            ));
+        }
+        if !self.triple.is_empty() {
+            text.push_str(&format!("target triple = \"{}\"\n", escape(&self.triple)));
+        }
+        if !self.inline_asm.is_empty() {
+            let asm = self
+                .inline_asm
+                .strip_suffix('\n')
+                .unwrap_or(&self.inline_asm);
+            for line in asm.split('\n') {
+                text.push_str(&format!("module asm \"{}\"\n", escape(line)));
+            }
+        }
+        text.push_str(&self.types.definitions());
+
+        let mut comdats = self.comdats.clone();
+        for global in &self.globals {
+            let has_comdat_field = match global.kind {
+                GlobalKind::Variable => global.ops.len() > 11,
+                GlobalKind::Function => global.ops.len() > 12,
+                _ => true,
+            };
+            if !has_comdat_field
+                && has_implicit_comdat(global.raw_linkage, &self.triple)
+                && !global.is_declaration()
+                && !comdats.iter().any(|(name, _)| *name == global.name)
+            {
+                comdats.push((global.name.clone(), "any"));
+            }
+        }
+        for (name, kind) in &comdats {
+            text.push_str(&format!("{} = comdat {}\n", comdat_name(name), kind));
+        }
+
+        let mut function_texts = std::mem::take(&mut self.function_texts).into_iter();
+        for kind in &[
+            GlobalKind::Variable,
+            GlobalKind::Alias,
+            GlobalKind::IFunc,
+            GlobalKind::Function,
+        ] {
+            // LLVM moves upgraded constructor and destructor lists to the end
+            let mut upgraded_structors = vec![];
+            for i in 0 .. self.globals.len() {
+                let global = &self.globals[i];
+                if global.kind != *kind {
+                    continue;
+                }
+                if global.kind == GlobalKind::Variable {
+                    if let Some(structors) = self.upgrade_structors(i)? {
+                        upgraded_structors.push(structors);
+                        continue;
+                    }
+                }
+                let global = &self.globals[i];
+                let global_text = match global.kind {
+                    GlobalKind::Variable => self.global_variable_text(i)?,
+                    GlobalKind::Alias | GlobalKind::IFunc => self.alias_text(global)?,
+                    GlobalKind::Function if global.is_declaration() => {
+                        self.function_header(i, &[], &[], None, None)?
+                    },
+                    GlobalKind::Function => function_texts.next().unwrap_or_default(),
+                };
+                text.push_str(&global_text);
+                text.push('\n');
+            }
+            for structors in upgraded_structors {
+                text.push_str(&structors);
+                text.push('\n');
+            }
+        }
+
+        text.push_str(&self.metadata.definitions());
+        Ok(self.resolve_block_addresses(text))
+    }
+
+    fn global_variable_text(&self, index: usize) -> Result<String, String> {
+        // [type, flags, initid, linkage, alignment, section, visibility,
+        //  threadlocal, unnamed_addr, externally_initialized, dllstorageclass,
+        //  comdat, attributes, preemptionspecifier, partition_offset,
+        //  partition_size, sanitizer]
+        let global = &self.globals[index];
+        let ops = global.ops;
+        let linkage = linkage_text(global.raw_linkage);
+        let is_local = is_local_linkage(linkage);
+        let mut text = format!("{} =", global_name(&global.name));
+        match (linkage, global.is_declaration()) {
+            ("", true) => text.push_str(" external"),
+            ("", false) => {},
+            (linkage, _) => {
+                text.push(' ');
+                text.push_str(linkage);
+            },
+        }
+        if ops.len() > 6 && !is_local {
+            text.push_str(visibility_text(global.op(6)));
+        }
+        text.push_str(dll_storage_class_text(global, 10));
+        if ops.len() > 7 {
+            text.push_str(thread_local_text(global.op(7)));
+        }
+        if ops.len() > 8 {
+            text.push_str(unnamed_addr_text(global.op(8)));
+        }
+        if global.addr_space != 0 {
+            text.push_str(&format!(" addrspace({})", global.addr_space));
+        }
+        if global.op(9) != 0 {
+            text.push_str(" externally_initialized");
+        }
+        text.push_str(if global.op(1) & 1 != 0 {
+            " constant "
+        } else {
+            " global "
+        });
+        text.push_str(&self.types.text(global.value_type));
+        if !global.is_declaration() {
+            text.push(' ');
+            text.push_str(&self.constant_text(global.op(2) as usize - 1)?);
+        }
+        if global.op(5) != 0 {
+            text.push_str(&format!(
+                ", section \"{}\"",
+                escape(self.section_name(global.op(5))?)
+            ));
+        }
+        if let Some(partition) = self.partition(global, 14) {
+            text.push_str(&format!(", partition \"{}\"", escape(&partition)));
+        }
+        if cfg!(feature = "llvm-15-or-greater") && ops.len() > 16 {
+            let sanitizer = global.op(16);
+            for (bit, keyword) in &[
+                (1, "no_sanitize_address"),
+                (2, "no_sanitize_hwaddress"),
+                (4, "sanitize_memtag"),
+                (8, "sanitize_address_dyninit"),
+            ] {
+                if sanitizer & bit != 0 {
+                    text.push_str(", ");
+                    text.push_str(keyword);
+                }
+            }
+        }
+        text.push_str(&self.comdat_text(global, 11, ", ")?);
+        let alignment = decode_alignment(global.op(4))?;
+        if alignment != 0 {
+            text.push_str(&format!(", align {}", alignment));
+        }
+        for attachment in self.metadata.global_attachments(index) {
+            text.push_str(", ");
+            text.push_str(&attachment);
+        }
+        if ops.len() > 12 {
+            let attrs = self.attribute_list(global.op(12));
+            text.push_str(&attributes::attrs_text(&attrs.function, &self.types, None));
+        }
+        Ok(text)
+    }
+
+    fn alias_text(&self, global: &Global) -> Result<String, String> {
+        // ALIAS: [type, addrspace, aliasee, linkage, visibility, dllstorage,
+        //  threadlocal, unnamed_addr, preemptionspecifier, partition]
+        // ALIAS_OLD: [pointer type, aliasee, linkage, visibility, ...]
+        // IFUNC: [type, addrspace, resolver, linkage, visibility,
+        //  preemptionspecifier, partition]
+        let is_old = global.ops.len() > 1 && global.value_type != global.op(0) as TypeId;
+        let first = if is_old { 1 } else { 2 };
+        let op = |i: usize| global.op(first + i);
+        let linkage = linkage_text(global.raw_linkage);
+        let mut text = format!("{} =", global_name(&global.name));
+        if !linkage.is_empty() {
+            text.push(' ');
+            text.push_str(linkage);
+        }
+        let num_ops = global.ops.len() - first;
+        if num_ops > 2 && !is_local_linkage(linkage) {
+            text.push_str(visibility_text(op(2)));
+        }
+        if global.kind == GlobalKind::Alias {
+            text.push_str(dll_storage_class_text(global, first + 3));
+            if num_ops > 4 {
+                text.push_str(thread_local_text(op(4)));
+            }
+            if num_ops > 5 {
+                text.push_str(unnamed_addr_text(op(5)));
+            }
+        }
+        text.push_str(if global.kind == GlobalKind::Alias {
+            " alias "
+        } else {
+            " ifunc "
+        });
+        text.push_str(&self.types.text(global.value_type));
+        text.push_str(", ");
+        let target = self.typed_constant_text(op(0) as usize)?;
+        let resolver_ty = format!(
+            "{}* (){}*",
+            self.types.text(global.value_type),
+            match global.addr_space {
+                0 => String::new(),
+                addr_space => format!(" addrspace({})", addr_space),
+            }
+        );
+        let resolver_ty_mismatch =
+            !self.types.opaque_pointers && !target.starts_with(&format!("{} ", resolver_ty));
+        if cfg!(feature = "llvm-14-or-greater")
+            && global.kind == GlobalKind::IFunc
+            && resolver_ty_mismatch
+        {
+            // LLVM casts resolvers of other types, from older bitcode
+            text.push_str(&format!("bitcast ({} to {})", target, resolver_ty));
+        } else {
+            text.push_str(&target);
+        }
+        let partition_index = if global.kind == GlobalKind::Alias {
+            8
+        } else {
+            5
+        };
+        if let Some(partition) = self.partition(global, first + partition_index) {
+            text.push_str(&format!(", partition \"{}\"", escape(&partition)));
+        }
+        Ok(text)
+    }
+
+    /// The header of a function: its declaration, or its definition up to
+    /// the opening `{`
+    fn function_header(
+        &self,
+        index: usize,
+        param_names: &[String],
+        attachments: &[String],
+        personality: Option<usize>,
+        subprogram: Option<&str>,
+    ) -> Result<String, String> {
+        // [type, callingconv, isproto, linkage, paramattrs, alignment,
+        //  section, visibility, gc, unnamed_addr, prologuedata,
+        //  dllstorageclass, comdat, prefixdata, personalityfn,
+        //  preemptionspecifier, addrspace, partition_offset, partition_size]
+        let global = &self.globals[index];
+        let ops = global.ops;
+        let is_declaration = global.is_declaration();
+        let linkage = linkage_text(global.raw_linkage);
+        let mut text = String::from(if is_declaration { "declare" } else { "define" });
+        if !linkage.is_empty() {
+            text.push(' ');
+            text.push_str(linkage);
+        }
+        if ops.len() > 7 && !is_local_linkage(linkage) {
+            text.push_str(visibility_text(global.op(7)));
+        }
+        text.push_str(dll_storage_class_text(global, 11));
+        let cc = global.op(1);
+        if cc != 0 {
+            text.push_str(&format!(" cc {}", cc));
+        }
+        let attrs = self.attribute_list(global.op(4));
+        let result = self
+            .types
+            .result(global.value_type)
+            .ok_or("Invalid type for a function")?;
+        text.push_str(&attributes::attrs_text(
+            &attrs.ret,
+            &self.types,
+            Some(result),
+        ));
+        text.push(' ');
+        text.push_str(&self.types.text(result));
+        text.push(' ');
+        text.push_str(&global_name(&global.name));
+        let mut params = vec![];
+        for (i, ty) in self.types.params(global.value_type).into_iter().enumerate() {
+            let mut param = self.types.text(ty);
+            param.push_str(&attributes::attrs_text(
+                attrs.param(i),
+                &self.types,
+                Some(ty),
+            ));
+            if let Some(name) = param_names.get(i) {
+                param.push(' ');
+                param.push_str(name);
+            }
+            params.push(param);
+        }
+        if let types::Ty::Function { var_arg: true, .. } = self.types.get(global.value_type)? {
+            params.push("...".into());
+        }
+        text.push_str(&format!("({})", params.join(", ")));
+        if ops.len() > 9 {
+            text.push_str(unnamed_addr_text(global.op(9)));
+        }
+        if global.addr_space != 0 {
+            text.push_str(&format!(" addrspace({})", global.addr_space));
+        }
+        text.push_str(&attributes::attrs_text(&attrs.function, &self.types, None));
+        if global.op(6) != 0 {
+            text.push_str(&format!(
+                " section \"{}\"",
+                escape(self.section_name(global.op(6))?)
+            ));
+        }
+        if let Some(partition) = self.partition(global, 17) {
+            text.push_str(&format!(" partition \"{}\"", escape(&partition)));
+        }
+        if !is_declaration {
+            text.push_str(&self.comdat_text(global, 12, " ")?);
+        }
+        let alignment = decode_alignment(global.op(5))?;
+        if alignment != 0 {
+            text.push_str(&format!(" align {}", alignment));
+        }
+        if global.op(8) != 0 {
+            let gc = self
+                .gc_names
+                .get(global.op(8) as usize - 1)
+                .ok_or("Invalid garbage collector ID")?;
+            text.push_str(&format!(" gc \"{}\"", escape(gc)));
+        }
+        if global.op(13) != 0 {
+            text.push_str(" prefix ");
+            text.push_str(&self.typed_constant_text(global.op(13) as usize - 1)?);
+        }
+        if global.op(10) != 0 {
+            text.push_str(" prologue ");
+            text.push_str(&self.typed_constant_text(global.op(10) as usize - 1)?);
+        }
+        let personality = match global.op(14) {
+            0 => personality,
+            id => Some(id as usize - 1),
+        };
+        if let Some(personality) = personality {
+            text.push_str(" personality ");
+            text.push_str(&self.typed_constant_text(personality)?);
+        }
+        let declaration_attachments;
+        let attachments = if is_declaration {
+            declaration_attachments = self.metadata.global_attachments(index);
+            &declaration_attachments
+        } else {
+            attachments
+        };
+        for attachment in attachments {
+            text.push(' ');
+            text.push_str(attachment);
+        }
+        if let Some(subprogram) = subprogram {
+            text.push_str(" !dbg ");
+            text.push_str(subprogram);
+        }
+        Ok(text)
+    }
+
+    fn section_name(&self, id: u64) -> Result<&str, String> {
+        self.section_names
+            .get(id as usize - 1)
+            .map(|name| name.as_str())
+            .ok_or_else(|| "Invalid section ID".into())
+    }
+
+    /// The partition of a global value, whose strtab offset and size are at
+    /// the given index of its record
+    fn partition(&self, global: &Global, index: usize) -> Option<String> {
+        if !cfg!(feature = "llvm-9-or-greater") || global.ops.len() <= index + 1 {
+            return None;
+        }
+        let (offset, size) = (global.op(index) as usize, global.op(index + 1) as usize);
+        if size == 0 {
+            return None;
+        }
+        self.strtab
+            .get(offset .. offset + size)
+            .map(|name| String::from_utf8_lossy(name).into_owned())
+    }
+
+    /// The comdat of a global variable or function, whose comdat ID is at
+    /// the given index of its record
+    fn comdat_text(
+        &self,
+        global: &Global,
+        index: usize,
+        separator: &str,
+    ) -> Result<String, String> {
+        let name = if global.ops.len() > index {
+            match global.op(index) {
+                0 => return Ok(String::new()),
+                id => {
+                    &self
+                        .comdats
+                        .get(id as usize - 1)
+                        .ok_or("Invalid comdat ID")?
+                        .0
+                },
+            }
+        } else if has_implicit_comdat(global.raw_linkage, &self.triple) {
+            &global.name
+        } else {
+            return Ok(String::new());
+        };
+        Ok(format!("{}comdat({})", separator, comdat_name(name)))
+    }
+
+    /// The text of `llvm.global_ctors` or `llvm.global_dtors` (at the given
+    /// index) if it's from before they had a third field, which LLVM adds.
+    /// LLVM replaces the variable, so only its linkage is kept.
+    fn upgrade_structors(&mut self, index: usize) -> Result<Option<String>, String> {
+        let global = &self.globals[index];
+        if !matches!(
+            global.name.as_str(),
+            "llvm.global_ctors" | "llvm.global_dtors"
+        ) || global.is_declaration()
+        {
+            return Ok(None);
+        }
+        let (linkage, init) = (global.raw_linkage, global.op(2) as usize - 1);
+        let fields = match self
+            .types
+            .element(global.value_type, 0)
+            .map(|element| self.types.get(element))
+        {
+            Some(Ok(Ty::LiteralStruct { elements, .. })) if elements.len() == 2 => elements.clone(),
+            _ => return Ok(None),
+        };
+        let i8_ty = self.types.int(8);
+        let i8_ptr = self.types.pointer_to(i8_ty, 0);
+        let element_ty = self
+            .types
+            .literal_struct(vec![fields[0], fields[1], i8_ptr]);
+        let structors = self
+            .aggregate_elements(init)
+            .ok_or("Invalid constructor or destructor list")?;
+        let array_ty = self.types.array_of(structors.len() as u64, element_ty);
+        let mut elements = vec![];
+        for structor in structors {
+            let fields = match self.aggregate_elements(structor) {
+                Some(ids) if ids.len() == 2 => ids
+                    .iter()
+                    .map(|&id| self.typed_constant_text(id))
+                    .collect::<Result<Vec<_>, _>>()?,
+                _ => fields
+                    .iter()
+                    .map(|&ty| format!("{} zeroinitializer", self.types.text(ty)))
+                    .collect(),
+            };
+            elements.push(format!(
+                "{} {{ {}, {}, {} null }}",
+                self.types.text(element_ty),
+                fields[0],
+                fields[1],
+                self.types.text(i8_ptr)
+            ));
+        }
+        let init = if elements.is_empty() {
+            "zeroinitializer".to_owned()
+        } else {
+            format!("[{}]", elements.join(", "))
+        };
+        Ok(Some(format!(
+            "{} ={} global {} {}",
+            global_name(&self.globals[index].name),
+            match linkage_text(linkage) {
+                "" => String::new(),
+                linkage => format!(" {}", linkage),
+            },
+            self.types.text(array_ty),
+            init
+        )))
+    }
+
+    /// Replace the placeholders which `blockaddress` constants use for basic
+    /// blocks with the blocks' labels, now that all functions are decoded
+    fn resolve_block_addresses(&self, text: String) -> String {
+        if !text.contains(BLOCK_ADDRESS_MARK) {
+            return text;
+        }
+        let mut result = String::with_capacity(text.len());
+        let mut parts = text.split(BLOCK_ADDRESS_MARK);
+        result.push_str(parts.next().unwrap_or_default());
+        while let (Some(reference), Some(rest)) = (parts.next(), parts.next()) {
+            let mut ids = reference.split(':').map(|id| id.parse::<usize>().ok());
+            let label = match (ids.next().flatten(), ids.next().flatten()) {
+                (Some(func), Some(block)) => self
+                    .block_labels
+                    .get(&func)
+                    .and_then(|labels| labels.get(block)),
+                _ => None,
+            };
+            result.push_str(label.map_or("%<invalid block>", |label| label.as_str()));
+            result.push_str(rest);
+        }
+        result
+    }
+}
+
+/// Brackets a `function:block` reference to a basic block in the text of a
+/// `blockaddress` constant, until the block's label is known
+const BLOCK_ADDRESS_MARK: char = '\u{1}';
+
+fn selection_kind(kind: u64) -> &'static str {
+    match kind {
+        2 => "exactmatch",
+        3 => "largest",
+        4 => "nodeduplicate",
+        5 => "samesize",
+        _ => "any",
+    }
+}
+
+/// The linkage keyword for a linkage as encoded in bitcode, or `""` for
+/// external linkage
+fn linkage_text(linkage: u64) -> &'static str {
+    match linkage {
+        2 => "appending",
+        3 => "internal",
+        7 => "extern_weak",
+        8 => "common",
+        9 | 13 | 14 => "private",
+        12 => "available_externally",
+        1 | 16 => "weak",
+        10 | 17 => "weak_odr",
+        4 | 18 => "linkonce",
+        11 | 19 => "linkonce_odr",
+        _ => "",
+    }
+}
+
+fn is_local_linkage(linkage: &str) -> bool {
+    linkage == "internal" || linkage == "private"
+}
+
+/// Old linkages which imply a comdat of the same name as the global, where
+/// the object format supports comdats (which Mach-O doesn't)
+fn has_implicit_comdat(linkage: u64, triple: &str) -> bool {
+    let mut parts = triple.split('-').skip(1);
+    let is_mach_o = parts.next() == Some("apple")
+        || matches!(parts.next(), Some(os) if ["darwin", "macos", "ios", "tvos", "watchos"]
+            .iter()
+            .any(|prefix| os.starts_with(prefix)));
+    matches!(linkage, 1 | 4 | 10 | 11) && !is_mach_o
+}
+
+fn visibility_text(visibility: u64) -> &'static str {
+    match visibility {
+        1 => " hidden",
+        2 => " protected",
+        _ => "",
+    }
+}
+
+/// The DLL storage class of a global, which is at the given index of its
+/// record, or (in old bitcode) encoded in its linkage
+fn dll_storage_class_text(global: &Global, index: usize) -> &'static str {
+    if is_local_linkage(linkage_text(global.raw_linkage)) {
+        return "";
+    }
+    let class = if global.ops.len() > index {
+        global.op(index)
+    } else {
+        match global.raw_linkage {
+            5 => 1,
+            6 => 2,
+            _ => 0,
+        }
+    };
+    match class {
+        1 => " dllimport",
+        2 => " dllexport",
+        _ => "",
+    }
+}
+
+fn thread_local_text(mode: u64) -> &'static str {
+    match mode {
+        0 => "",
+        2 => " thread_local(localdynamic)",
+        3 => " thread_local(initialexec)",
+        4 => " thread_local(localexec)",
+        _ => " thread_local",
+    }
+}
+
+fn unnamed_addr_text(unnamed_addr: u64) -> &'static str {
+    match unnamed_addr {
+        1 => " unnamed_addr",
+        2 => " local_unnamed_addr",
+        _ => "",
+    }
+}
+
+/// Alignments are encoded as their log2 plus 1, or 0 for none
+fn decode_alignment(encoded: u64) -> Result<u64, String> {
+    // LLVM's maximum alignment is 2^32
+    match encoded {
+        0 => Ok(0),
+        n if n <= 33 => Ok(1 << (n - 1)),
+        _ => Err("Invalid alignment value".into()),
+    }
+}
+
+/// The address space given by the data layout for the given kind (`P` for
+/// functions, `A` for allocas)
+fn data_layout_addr_space(data_layout: &str, kind: char) -> u32 {
+    data_layout
+        .split('-')
+        .filter_map(|spec| spec.strip_prefix(kind))
+        .find_map(|addr_space| addr_space.parse().ok())
+        .unwrap_or(0)
+}
+
+/// LLVM rejects data layouts with alignments (in bits) that aren't whole
+/// bytes or aren't powers of 2
+fn check_data_layout_alignments(data_layout: &str) -> Result<(), String> {
+    for spec in data_layout.split('-') {
+        let mut chunks = spec.split(':');
+        let first = chunks.next().unwrap_or_default();
+        let alignments: Vec<&str> = match first.chars().next() {
+            Some('S') => vec![&first[1 ..]],
+            Some('F') => vec![first.get(2 ..).unwrap_or_default()],
+            Some('p') => chunks.skip(1).take(2).collect(),
+            Some('i') | Some('f') | Some('v') | Some('a') => chunks.take(2).collect(),
+            _ => continue,
+        };
+        for alignment in alignments {
+            if let Ok(bits) = alignment.parse::<u64>() {
+                if bits != 0 && (bits % 8 != 0 || !(bits / 8).is_power_of_two()) {
+                    return Err(format!("Invalid alignment in data layout spec {:?}", spec));
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The data layout as LLVM upgrades it when reading bitcode. Since LLVM 10,
+/// x86 data layouts specify the address spaces for mixed pointer sizes.
+fn upgrade_data_layout(data_layout: &str, triple: &str) -> String {
+    const X86_ADDR_SPACES: &str = "-p270:32:32-p271:32:32-p272:64:64";
+    let arch = triple.split('-').next().unwrap_or_default();
+    let is_x86 = matches!(arch, "x86" | "x86_64" | "x86_64h" | "amd64")
+        || (arch.len() == 4 && arch.starts_with('i') && arch.ends_with("86"));
+    if !cfg!(feature = "llvm-10-or-greater") || !is_x86 || data_layout.contains(X86_ADDR_SPACES) {
+        return data_layout.to_owned();
+    }
+    // insert the address spaces after a prefix like `e-m:e` or `e-m:o-p:32:32`,
+    // if it's followed by the alignment of `i64` or `f64`
+    for (start, _) in data_layout.match_indices("e-m:") {
+        let mut prefix_len = start + "e-m:".len();
+        if !data_layout[prefix_len ..].starts_with(|c: char| c.is_ascii_lowercase()) {
+            continue;
+        }
+        prefix_len += 1;
+        if data_layout[prefix_len ..].starts_with("-p:32:32") {
+            prefix_len += "-p:32:32".len();
+        }
+        let rest = &data_layout[prefix_len ..];
+        if rest.starts_with("-i64:") || rest.starts_with("-f64:") {
+            return format!(
+                "{}{}{}",
+                &data_layout[start .. prefix_len],
+                X86_ADDR_SPACES,
+                rest
+            );
+        }
+    }
+    data_layout.to_owned()
+}
+
+/// Escape a string for a string literal in textual IR
+fn escape(s: &str) -> String {
+    escape_bytes(s.as_bytes())
+}
+
+fn escape_bytes(bytes: &[u8]) -> String {
+    let mut escaped = String::with_capacity(bytes.len());
+    for &b in bytes {
+        if b == b'"' || b == b'\\' || !(0x20 .. 0x7F).contains(&b) {
+            escaped.push_str(&format!("\\{:02X}", b));
+        } else {
+            escaped.push(b as char);
+        }
+    }
+    escaped
+}
+
+/// A name with the given sigil, quoted if necessary
+fn sigil_name(sigil: char, name: &str) -> String {
+    let bytes = name.as_bytes();
+    let is_name_char =
+        |c: &u8| c.is_ascii_alphanumeric() || *c == b'-' || *c == b'$' || *c == b'.' || *c == b'_';
+    let simple = !bytes.is_empty() && bytes.iter().all(is_name_char) && !bytes[0].is_ascii_digit();
+    if simple {
+        format!("{}{}", sigil, name)
+    } else {
+        format!("{}\"{}\"", sigil, escape(name))
+    }
+}
+
+fn local_name(name: &str) -> String {
+    sigil_name('%', name)
+}
+
+fn global_name(name: &str) -> String {
+    sigil_name('@', name)
+}
+
+fn comdat_name(name: &str) -> String {
+    sigil_name('$', name)
+}
@@ -0,0 +1,483 @@
+//! Attribute groups (`PARAMATTR_GROUP_BLOCK_ID`) and the attribute lists made
+//! of them (`PARAMATTR_BLOCK_ID`)
+
+use super::bitstream::Block;
+use super::escape;
+use super::types::{TypeId, Types};
+use std::collections::{BTreeMap, HashMap};
+
+/// An attribute of a function, its return value, or a parameter
+#[derive(Clone, Debug)]
+pub(super) enum Attr {
+    /// An enum or integer attribute in its textual form, e.g. `align 8`
+    Enum(String),
+    /// A type attribute, e.g. `byval(i32)`. The type is `None` in old
+    /// bitcode, where it is the pointee type of the parameter.
+    Type(&'static str, Option<TypeId>),
+    Str(String, String),
+}
+
+/// The attributes of a function or call site
+#[derive(Clone, Default, Debug)]
+pub(super) struct AttributeList {
+    pub(super) function: Vec<Attr>,
+    pub(super) ret: Vec<Attr>,
+    /// Attributes of the parameters, by 0-based parameter number. This is
+    /// sparse, as the numbers come from the bitcode and may be large.
+    pub(super) params: BTreeMap<u64, Vec<Attr>>,
+}
+
+impl AttributeList {
+    fn add(&mut self, index: u64, attrs: &[Attr]) {
+        let slot = match index {
+            0xFFFF_FFFF => &mut self.function,
+            0 => &mut self.ret,
+            i => self.params.entry(i - 1).or_default(),
+        };
+        slot.extend_from_slice(attrs);
+    }
+
+    pub(super) fn param(&self, i: usize) -> &[Attr] {
+        self.params
+            .get(&(i as u64))
+            .map_or(&[], |attrs| attrs.as_slice())
+    }
+}
+
+// PARAMATTR_CODE_* and PARAMATTR_GRP_CODE_*
+const ENTRY_OLD: u32 = 1;
+const ENTRY: u32 = 2;
+const GRP_ENTRY: u32 = 3;
+
+/// The textual names of the enum-style attributes, indexed by their
+/// bitcode kind (`ATTR_KIND_*`), which starts at 1
+const ATTR_KINDS: &[&str] = &[
+    "align",
+    "alwaysinline",
+    "byval",
+    "inlinehint",
+    "inreg",
+    "minsize",
+    "naked",
+    "nest",
+    "noalias",
+    "nobuiltin",
+    "nocapture",
+    "noduplicate",
+    "noimplicitfloat",
+    "noinline",
+    "nonlazybind",
+    "noredzone",
+    "noreturn",
+    "nounwind",
+    "optsize",
+    "readnone",
+    "readonly",
+    "returned",
+    "returns_twice",
+    "signext",
+    "alignstack",
+    "ssp",
+    "sspreq",
+    "sspstrong",
+    "sret",
+    "sanitize_address",
+    "sanitize_thread",
+    "sanitize_memory",
+    "uwtable",
+    "zeroext",
+    "builtin",
+    "cold",
+    "optnone",
+    "inalloca",
+    "nonnull",
+    "jumptable",
+    "dereferenceable",
+    "dereferenceable_or_null",
+    "convergent",
+    "safestack",
+    "argmemonly",
+    "swiftself",
+    "swifterror",
+    "norecurse",
+    "inaccessiblememonly",
+    "inaccessiblemem_or_argmemonly",
+    "allocsize",
+    "writeonly",
+    "speculatable",
+    "strictfp",
+    "sanitize_hwaddress",
+    "nocf_check",
+    "optforfuzzing",
+    "shadowcallstack",
+    "speculative_load_hardening",
+    "immarg",
+    "willreturn",
+    "nofree",
+    "nosync",
+    "sanitize_memtag",
+    "preallocated",
+    "nomerge",
+    "null_pointer_is_valid",
+    "noundef",
+    "byref",
+    "mustprogress",
+    "nocallback",
+    "hot",
+    "noprofile",
+    "vscale_range",
+    "swiftasync",
+    "nosanitize_coverage",
+    "elementtype",
+    "disable_sanitizer_instrumentation",
+    "nosanitize_bounds",
+    "allocalign",
+    "allocptr",
+    "allockind",
+    "presplitcoroutine",
+    "fn_ret_thunk_extern",
+    "skipprofile",
+    "memory",
+    "nofpclass",
+];
+
+/// The bits of the encoding used before attribute groups existed, for each
+/// attribute which can be encoded that way (other than alignments)
+const RAW_ATTRIBUTE_BITS: &[(&str, u32)] = &[
+    ("zeroext", 0),
+    ("signext", 1),
+    ("noreturn", 2),
+    ("inreg", 3),
+    ("sret", 4),
+    ("nounwind", 5),
+    ("noalias", 6),
+    ("byval", 7),
+    ("nest", 8),
+    ("readnone", 9),
+    ("readonly", 10),
+    ("noinline", 11),
+    ("alwaysinline", 12),
+    ("optsize", 13),
+    ("ssp", 14),
+    ("sspreq", 15),
+    ("nocapture", 21),
+    ("noredzone", 22),
+    ("noimplicitfloat", 23),
+    ("naked", 24),
+    ("inlinehint", 25),
+    ("returns_twice", 29),
+    ("uwtable", 30),
+    ("nonlazybind", 31),
+    ("sanitize_address", 32),
+    ("minsize", 33),
+    ("noduplicate", 34),
+    ("sspstrong", 35),
+    ("sanitize_thread", 36),
+    ("sanitize_memory", 37),
+    ("nobuiltin", 38),
+    ("returned", 39),
+    ("cold", 40),
+    ("builtin", 41),
+    ("optnone", 42),
+    ("inalloca", 43),
+    ("nonnull", 44),
+    ("jumptable", 45),
+    ("convergent", 46),
+    ("safestack", 47),
+    ("norecurse", 48),
+    ("inaccessiblememonly", 49),
+    ("inaccessiblemem_or_argmemonly", 50),
+    ("swiftself", 51),
+    ("swifterror", 52),
+    ("writeonly", 53),
+    ("speculatable", 54),
+    ("strictfp", 55),
+    ("sanitize_hwaddress", 56),
+    ("nocf_check", 57),
+    ("optforfuzzing", 58),
+    ("shadowcallstack", 59),
+    ("speculative_load_hardening", 60),
+    ("immarg", 61),
+    ("willreturn", 62),
+    ("nofree", 63),
+];
+
+/// Read the attribute groups, giving the contents and index (function,
+/// return value, or parameter) of each group
+pub(super) fn parse_groups(block: &Block) -> Result<HashMap<u64, (u64, Vec<Attr>)>, String> {
+    let mut groups = HashMap::new();
+    for record in block.records() {
+        if record.code != GRP_ENTRY {
+            continue;
+        }
+        let group_id = record.op(0);
+        let index = record.op(1);
+        let mut attrs = vec![];
+        let ops = &record.ops;
+        let invalid = || format!("Invalid attribute group record {}", group_id);
+        let op = |i: usize| ops.get(i).copied().ok_or_else(invalid);
+        let mut i = 2;
+        while i < ops.len() {
+            let kind = ops[i];
+            i += 1;
+            match kind {
+                0 | 5 => {
+                    let name = attr_name(op(i)?)?;
+                    i += 1;
+                    attrs.push(if kind == 5 || is_type_attr(name) {
+                        Attr::Type(name, None)
+                    } else {
+                        Attr::Enum(name.into())
+                    });
+                },
+                1 => {
+                    let name = attr_name(op(i)?)?;
+                    attrs.push(Attr::Enum(int_attr_text(name, op(i + 1)?)));
+                    i += 2;
+                },
+                3 | 4 => {
+                    let mut read_str = || {
+                        let mut s = String::new();
+                        while i < ops.len() && ops[i] != 0 {
+                            s.push(ops[i] as u8 as char);
+                            i += 1;
+                        }
+                        i += 1;
+                        s
+                    };
+                    let key = read_str();
+                    let value = if kind == 4 { read_str() } else { String::new() };
+                    attrs.push(Attr::Str(key, value));
+                },
+                6 => {
+                    let name = attr_name(op(i)?)?;
+                    attrs.push(Attr::Type(name, Some(op(i + 1)? as TypeId)));
+                    i += 2;
+                },
+                _ => return Err(format!("Unknown attribute encoding {}", kind)),
+            }
+        }
+        upgrade_attributes(&mut attrs);
+        groups.insert(group_id, (index, attrs));
+    }
+    Ok(groups)
+}
+
+/// Replace string attributes which LLVM turned into other attributes, as
+/// LLVM does when reading bitcode
+fn upgrade_attributes(attrs: &mut Vec<Attr>) {
+    let take = |attrs: &mut Vec<Attr>, key: &str| {
+        let i = attrs
+            .iter()
+            .position(|attr| matches!(attr, Attr::Str(k, _) if k == key))?;
+        match attrs.remove(i) {
+            Attr::Str(_, value) => Some(value),
+            _ => None,
+        }
+    };
+    if cfg!(feature = "llvm-10-or-greater") {
+        // "no-frame-pointer-elim" takes precedence over its non-leaf variant
+        let mut frame_pointer =
+            take(attrs, "no-frame-pointer-elim").map(
+                |value| {
+                    if value == "true" {
+                        "all"
+                    } else {
+                        "none"
+                    }
+                },
+            );
+        if take(attrs, "no-frame-pointer-elim-non-leaf").is_some() && frame_pointer != Some("all") {
+            frame_pointer = Some("non-leaf");
+        }
+        if let Some(frame_pointer) = frame_pointer {
+            attrs.push(Attr::Str("frame-pointer".into(), frame_pointer.into()));
+        }
+    }
+    if cfg!(feature = "llvm-11-or-greater")
+        && take(attrs, "null-pointer-is-valid").as_deref() == Some("true")
+    {
+        attrs.push(Attr::Enum("null_pointer_is_valid".into()));
+    }
+}
+
+/// Read the attribute lists, made of the given groups (or, in old bitcode,
+/// of attributes encoded as bits)
+pub(super) fn parse_lists(
+    block: &Block,
+    groups: &HashMap<u64, (u64, Vec<Attr>)>,
+) -> Result<Vec<AttributeList>, String> {
+    let mut lists = vec![];
+    for record in block.records() {
+        let mut list = AttributeList::default();
+        match record.code {
+            ENTRY => {
+                for group_id in &record.ops {
+                    if let Some((index, attrs)) = groups.get(group_id) {
+                        list.add(*index, attrs);
+                    }
+                }
+            },
+            ENTRY_OLD => {
+                for pair in record.ops.chunks(2) {
+                    if let [index, encoded] = *pair {
+                        list.add(index, &decode_raw_attributes(encoded));
+                    }
+                }
+            },
+            _ => continue,
+        }
+        lists.push(list);
+    }
+    Ok(lists)
+}
+
+fn attr_name(kind: u64) -> Result<&'static str, String> {
+    kind.checked_sub(1)
+        .and_then(|i| ATTR_KINDS.get(i as usize))
+        .copied()
+        .ok_or_else(|| format!("Unknown attribute kind {}", kind))
+}
+
+fn is_type_attr(name: &str) -> bool {
+    matches!(
+        name,
+        "byval" | "byref" | "sret" | "preallocated" | "elementtype"
+    ) || name == "inalloca" && cfg!(feature = "llvm-13-or-greater")
+}
+
+/// The textual form of an integer attribute with the given encoded value
+fn int_attr_text(name: &str, value: u64) -> String {
+    match name {
+        "align" => format!("align {}", value),
+        "alignstack" | "dereferenceable" | "dereferenceable_or_null" | "nofpclass" => {
+            format!("{}({})", name, value)
+        },
+        "allocsize" => {
+            let (elt_size, num_elts) = (value >> 32, value & 0xFFFF_FFFF);
+            if num_elts == 0xFFFF_FFFF {
+                format!("allocsize({})", elt_size)
+            } else {
+                format!("allocsize({},{})", elt_size, num_elts)
+            }
+        },
+        "vscale_range" => format!("vscale_range({},{})", value >> 32, value & 0xFFFF_FFFF),
+        "uwtable" if value == 1 => "uwtable(sync)".into(),
+        "uwtable" => "uwtable".into(),
+        "allockind" => {
+            let kinds: Vec<&str> = [
+                "alloc",
+                "realloc",
+                "free",
+                "uninitialized",
+                "zeroed",
+                "aligned",
+            ]
+            .iter()
+            .enumerate()
+            .filter(|(bit, _)| value & (1 << bit) != 0)
+            .map(|(_, kind)| *kind)
+            .collect();
+            format!("allockind(\"{}\")", kinds.join(","))
+        },
+        "memory" => {
+            let effect = |shift: u32| match (value >> shift) & 0b11 {
+                0 => "none",
+                1 => "read",
+                2 => "write",
+                _ => "readwrite",
+            };
+            format!(
+                "memory({}, argmem: {}, inaccessiblemem: {})",
+                effect(4),
+                effect(0),
+                effect(2)
+            )
+        },
+        _ => name.into(),
+    }
+}
+
+/// Decode the attributes of the encoding used before attribute groups
+/// existed
+fn decode_raw_attributes(encoded: u64) -> Vec<Attr> {
+    let mut attrs = vec![];
+    let alignment = (encoded >> 16) & 0xFFFF;
+    if alignment != 0 {
+        attrs.push(Attr::Enum(format!("align {}", alignment)));
+    }
+    let bits = ((encoded >> 32) << 21) | (encoded & 0xFFFF);
+    for &(name, bit) in RAW_ATTRIBUTE_BITS {
+        if bits & (1 << bit) != 0 {
+            attrs.push(if is_type_attr(name) {
+                Attr::Type(name, None)
+            } else {
+                Attr::Enum(name.into())
+            });
+        }
+    }
+    let stack_alignment = (bits >> 26) & 0b111;
+    if stack_alignment != 0 {
+        attrs.push(Attr::Enum(format!(
+            "alignstack({})",
+            1u64 << (stack_alignment - 1)
+        )));
+    }
+    attrs
+}
+
+/// Attributes that only apply to pointers. Like LLVM, we drop them from
+/// values of other types, which old bitcode allowed.
+const POINTER_ATTRS: &[&str] = &[
+    "align",
+    "byref",
+    "byval",
+    "dereferenceable",
+    "dereferenceable_or_null",
+    "elementtype",
+    "inalloca",
+    "nest",
+    "noalias",
+    "nocapture",
+    "nofree",
+    "nonnull",
+    "preallocated",
+    "readnone",
+    "readonly",
+    "sret",
+    "swifterror",
+    "writeonly",
+];
+
+/// The textual form of a list of attributes. Type attributes without a type
+/// get the pointee type of `ty`, if it's a typed pointer.
+pub(super) fn attrs_text(attrs: &[Attr], types: &Types, ty: Option<TypeId>) -> String {
+    let mut text = String::new();
+    let pointer_attrs_apply = match ty {
+        Some(ty) => types.is_pointer(ty),
+        None => true,
+    };
+    for attr in attrs {
+        let name = match attr {
+            Attr::Enum(attr) => attr.split(&[' ', '('][..]).next().unwrap_or(""),
+            Attr::Type(name, _) => name,
+            Attr::Str(..) => "",
+        };
+        if !pointer_attrs_apply && POINTER_ATTRS.contains(&name) {
+            continue;
+        }
+        let attr = match attr {
+            Attr::Enum(attr) => attr.clone(),
+            Attr::Type(name, Some(ty)) => format!("{}({})", name, types.text(*ty)),
+            Attr::Type(name, None) => match ty.and_then(|ty| types.pointee(ty)) {
+                Some(pointee) => format!("{}({})", name, types.text(pointee)),
+                None => (*name).into(),
+            },
+            Attr::Str(key, value) if value.is_empty() => format!("\"{}\"", escape(key)),
+            Attr::Str(key, value) => format!("\"{}\"=\"{}\"", escape(key), escape(value)),
+        };
+        text.push(' ');
+        text.push_str(&attr);
+    }
+    text
+}
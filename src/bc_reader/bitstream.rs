@@ -0,0 +1,450 @@
+//! The bitstream container format which LLVM bitcode is stored in: a tree of
+//! blocks of records, where the records may be compressed with abbreviations.
+//! See [LLVM's docs on the bitstream format](https://llvm.org/docs/BitCodeFormat.html).
+
+use std::collections::HashMap;
+use std::rc::Rc;
+
+/// A block, e.g. the module block or a function block
+#[derive(Debug)]
+pub(super) struct Block {
+    pub(super) id: u32,
+    pub(super) items: Vec<Item>,
+}
+
+#[derive(Debug)]
+pub(super) enum Item {
+    Record(Record),
+    Block(Block),
+}
+
+/// A record, consisting of a code and a list of operands. Records written
+/// with an abbreviation containing a blob have the blob in `blob`.
+#[derive(Debug)]
+pub(super) struct Record {
+    /// The position of the record in the file, in bits, for error messages
+    pub(super) offset: usize,
+    pub(super) code: u32,
+    pub(super) ops: Vec<u64>,
+    pub(super) blob: Option<Vec<u8>>,
+}
+
+impl Block {
+    /// The records directly in this block
+    pub(super) fn records(&self) -> impl Iterator<Item = &Record> {
+        self.items.iter().filter_map(|item| match item {
+            Item::Record(record) => Some(record),
+            Item::Block(_) => None,
+        })
+    }
+}
+
+impl Record {
+    /// Interpret the operands (from `start` on) as bytes, as in records
+    /// holding names or strings
+    pub(super) fn bytes(&self, start: usize) -> Vec<u8> {
+        match &self.blob {
+            Some(blob) => blob.clone(),
+            None => self
+                .ops
+                .get(start ..)
+                .unwrap_or(&[])
+                .iter()
+                .map(|&c| c as u8)
+                .collect(),
+        }
+    }
+
+    /// Like `bytes()`, but as a string
+    pub(super) fn string(&self, start: usize) -> String {
+        String::from_utf8_lossy(&self.bytes(start)).into_owned()
+    }
+
+    /// The operand at index `i`, or 0 if the record is too short
+    pub(super) fn op(&self, i: usize) -> u64 {
+        self.ops.get(i).copied().unwrap_or(0)
+    }
+}
+
+const BITCODE_MAGIC: [u8; 4] = [b'B', b'C', 0xC0, 0xDE];
+const WRAPPER_MAGIC: u32 = 0x0B17_C0DE;
+
+const END_BLOCK: u64 = 0;
+const ENTER_SUBBLOCK: u64 = 1;
+const DEFINE_ABBREV: u64 = 2;
+const UNABBREV_RECORD: u64 = 3;
+
+/// How deeply blocks may be nested. LLVM's own bitcode nests them at most a
+/// few levels deep; the limit keeps corrupt input from exhausting the stack.
+const MAX_BLOCK_DEPTH: usize = 64;
+
+const BLOCKINFO_BLOCK_ID: u32 = 0;
+const BLOCKINFO_CODE_SETBID: u32 = 1;
+
+/// Parse the top-level blocks of the bitcode in `bytes`, which may be wrapped
+/// in a bitcode wrapper header (as produced for Darwin targets)
+pub(super) fn parse(bytes: &[u8]) -> Result<Vec<Block>, String> {
+    let (start, bytes) = strip_wrapper(bytes)?;
+    if bytes.len() < 4 || bytes[.. 4] != BITCODE_MAGIC {
+        return Err("Not a bitcode file: bad magic number".into());
+    }
+    let mut reader = Reader {
+        cursor: Cursor {
+            bytes: &bytes[4 ..],
+            pos: 0,
+        },
+        base: (start + 4) * 8,
+        block_info: HashMap::new(),
+        depth: 0,
+    };
+    let mut blocks = vec![];
+    while !reader.cursor.at_end() {
+        match reader.cursor.read(2)? {
+            ENTER_SUBBLOCK => {
+                if let Some(block) = reader.read_subblock()? {
+                    blocks.push(block);
+                }
+            },
+            // a stream may be padded with zeros to a multiple of 4 bytes
+            0 if reader.cursor.rest_is_zero() => break,
+            id => return Err(format!("Unexpected abbreviation ID {} at top level", id)),
+        }
+    }
+    Ok(blocks)
+}
+
+/// Strip the wrapper header, if there is one, giving the offset of the
+/// bitcode in the file and the bitcode itself
+fn strip_wrapper(bytes: &[u8]) -> Result<(usize, &[u8]), String> {
+    let word = |i: usize| -> Option<u32> {
+        let b = bytes.get(i .. i + 4)?;
+        Some(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    };
+    if word(0) != Some(WRAPPER_MAGIC) {
+        return Ok((0, bytes));
+    }
+    let (offset, size) = match (word(8), word(12)) {
+        (Some(offset), Some(size)) => (offset as usize, size as usize),
+        _ => return Err("Truncated bitcode wrapper header".into()),
+    };
+    bytes
+        .get(offset .. offset + size)
+        .map(|bytes| (offset, bytes))
+        .ok_or_else(|| "Bitcode wrapper header points past the end of the file".into())
+}
+
+/// One operand of an abbreviation
+#[derive(Clone, Copy, Debug)]
+enum AbbrevOp {
+    Literal(u64),
+    Fixed(u32),
+    Vbr(u32),
+    /// An array, whose elements are encoded as the following operand
+    Array,
+    Char6,
+    Blob,
+}
+
+type Abbrev = Rc<Vec<AbbrevOp>>;
+
+struct Reader<'a> {
+    cursor: Cursor<'a>,
+    /// The position of the cursor's first byte in the file, in bits
+    base: usize,
+    /// Abbreviations defined in the BLOCKINFO block, for each block ID
+    block_info: HashMap<u32, Vec<Abbrev>>,
+    /// How many blocks we're inside
+    depth: usize,
+}
+
+impl<'a> Reader<'a> {
+    /// Read a block, just after its `ENTER_SUBBLOCK` abbreviation ID. Gives
+    /// `None` for the BLOCKINFO block, whose contents are only remembered.
+    fn read_subblock(&mut self) -> Result<Option<Block>, String> {
+        let id = self.cursor.read_vbr(8)? as u32;
+        let abbrev_width = self.cursor.read_vbr(4)? as u32;
+        self.cursor.align32();
+        let _num_words = self.cursor.read(32)?;
+        if abbrev_width == 0 || abbrev_width > 32 {
+            return Err(format!("Invalid abbreviation ID width {}", abbrev_width));
+        }
+        if self.depth == MAX_BLOCK_DEPTH {
+            return Err("Blocks are nested too deeply".into());
+        }
+        self.depth += 1;
+        let block = if id == BLOCKINFO_BLOCK_ID {
+            self.read_block_info(abbrev_width).map(|()| None)
+        } else {
+            self.read_block(id, abbrev_width).map(Some)
+        };
+        self.depth -= 1;
+        block
+    }
+
+    fn read_block(&mut self, id: u32, abbrev_width: u32) -> Result<Block, String> {
+        let mut abbrevs = self.block_info.get(&id).cloned().unwrap_or_default();
+        let mut items = vec![];
+        loop {
+            let offset = self.base + self.cursor.pos;
+            match self.cursor.read(abbrev_width)? {
+                END_BLOCK => {
+                    self.cursor.align32();
+                    return Ok(Block { id, items });
+                },
+                ENTER_SUBBLOCK => {
+                    if let Some(block) = self.read_subblock()? {
+                        items.push(Item::Block(block));
+                    }
+                },
+                DEFINE_ABBREV => abbrevs.push(self.read_abbrev()?),
+                UNABBREV_RECORD => {
+                    items.push(Item::Record(self.read_unabbrev_record(offset)?))
+                },
+                abbrev_id => {
+                    let abbrev = abbrevs
+                        .get(abbrev_id as usize - 4)
+                        .ok_or_else(|| format!("Invalid abbreviation ID {}", abbrev_id))?
+                        .clone();
+                    items.push(Item::Record(self.read_abbrev_record(offset, &abbrev)?));
+                },
+            }
+        }
+    }
+
+    fn read_block_info(&mut self, abbrev_width: u32) -> Result<(), String> {
+        let mut cur_id = None;
+        loop {
+            match self.cursor.read(abbrev_width)? {
+                END_BLOCK => {
+                    self.cursor.align32();
+                    return Ok(());
+                },
+                ENTER_SUBBLOCK => {
+                    self.read_subblock()?;
+                },
+                DEFINE_ABBREV => {
+                    let abbrev = self.read_abbrev()?;
+                    let id = cur_id.ok_or("Abbreviation in BLOCKINFO before SETBID")?;
+                    self.block_info.entry(id).or_default().push(abbrev);
+                },
+                UNABBREV_RECORD => {
+                    let record = self.read_unabbrev_record(0)?;
+                    if record.code == BLOCKINFO_CODE_SETBID {
+                        cur_id = Some(record.op(0) as u32);
+                    }
+                },
+                abbrev_id => {
+                    return Err(format!(
+                        "Invalid abbreviation ID {} in BLOCKINFO",
+                        abbrev_id
+                    ))
+                },
+            }
+        }
+    }
+
+    fn read_abbrev(&mut self) -> Result<Abbrev, String> {
+        let num_ops = self.cursor.read_vbr(5)?;
+        // each operand takes at least one bit
+        if num_ops > self.cursor.bits_left() {
+            return Err("Abbreviation has too many operands".into());
+        }
+        let mut ops = Vec::with_capacity(num_ops as usize);
+        let mut i = 0;
+        while i < num_ops {
+            let is_literal = self.cursor.read(1)? == 1;
+            let op = if is_literal {
+                AbbrevOp::Literal(self.cursor.read_vbr(8)?)
+            } else {
+                match self.cursor.read(3)? {
+                    1 => match self.cursor.read_vbr(5)? {
+                        0 => AbbrevOp::Literal(0),
+                        width @ 1 ..= 64 => AbbrevOp::Fixed(width as u32),
+                        width => return Err(format!("Invalid fixed width {}", width)),
+                    },
+                    2 => match self.cursor.read_vbr(5)? {
+                        0 => AbbrevOp::Literal(0),
+                        width @ 2 ..= 32 => AbbrevOp::Vbr(width as u32),
+                        width => return Err(format!("Invalid VBR width {}", width)),
+                    },
+                    3 => AbbrevOp::Array,
+                    4 => AbbrevOp::Char6,
+                    5 => AbbrevOp::Blob,
+                    encoding => return Err(format!("Invalid abbreviation encoding {}", encoding)),
+                }
+            };
+            ops.push(op);
+            i += 1;
+        }
+        Ok(Rc::new(ops))
+    }
+
+    fn read_unabbrev_record(&mut self, offset: usize) -> Result<Record, String> {
+        let code = self.cursor.read_vbr(6)? as u32;
+        let num_ops = self.cursor.read_vbr(6)?;
+        let mut ops = Vec::with_capacity(num_ops.min(1 << 16) as usize);
+        for _ in 0 .. num_ops {
+            ops.push(self.cursor.read_vbr(6)?);
+        }
+        Ok(Record {
+            offset,
+            code,
+            ops,
+            blob: None,
+        })
+    }
+
+    fn read_abbrev_record(&mut self, offset: usize, abbrev: &[AbbrevOp]) -> Result<Record, String> {
+        let mut values = vec![];
+        let mut blob = None;
+        let mut i = 0;
+        while i < abbrev.len() {
+            match abbrev[i] {
+                AbbrevOp::Array => {
+                    let elt = match abbrev.get(i + 1) {
+                        Some(AbbrevOp::Literal(_)) => {
+                            return Err("Array abbreviation with a literal element".into())
+                        },
+                        Some(&elt) => elt,
+                        None => return Err("Array abbreviation without an element".into()),
+                    };
+                    let len = self.cursor.read_vbr(6)?;
+                    // each element takes at least one bit
+                    if len > self.cursor.bits_left() {
+                        return Err("Array is longer than the rest of the bitcode".into());
+                    }
+                    for _ in 0 .. len {
+                        values.push(self.read_scalar(elt)?);
+                    }
+                    i += 2;
+                },
+                AbbrevOp::Blob => {
+                    let len = self.cursor.read_vbr(6)? as usize;
+                    self.cursor.align32();
+                    blob = Some(self.cursor.read_bytes(len)?);
+                    self.cursor.align32();
+                    i += 1;
+                },
+                op => {
+                    values.push(self.read_scalar(op)?);
+                    i += 1;
+                },
+            }
+        }
+        if values.is_empty() {
+            return Err("Abbreviated record without a code".into());
+        }
+        let code = values.remove(0) as u32;
+        Ok(Record {
+            offset,
+            code,
+            ops: values,
+            blob,
+        })
+    }
+
+    fn read_scalar(&mut self, op: AbbrevOp) -> Result<u64, String> {
+        match op {
+            AbbrevOp::Literal(value) => Ok(value),
+            AbbrevOp::Fixed(width) => self.cursor.read(width),
+            AbbrevOp::Vbr(width) => self.cursor.read_vbr(width),
+            AbbrevOp::Char6 => {
+                let c = self.cursor.read(6)? as u8;
+                Ok(u64::from(match c {
+                    0 ..= 25 => b'a' + c,
+                    26 ..= 51 => b'A' + c - 26,
+                    52 ..= 61 => b'0' + c - 52,
+                    62 => b'.',
+                    _ => b'_',
+                }))
+            },
+            AbbrevOp::Array | AbbrevOp::Blob => {
+                Err("Array or blob as an array element in abbreviation".into())
+            },
+        }
+    }
+}
+
+/// Reads bits, least significant first, from a byte slice
+pub(super) struct Cursor<'a> {
+    bytes: &'a [u8],
+    /// Position in bits
+    pos: usize,
+}
+
+impl<'a> Cursor<'a> {
+    pub(super) fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, pos: 0 }
+    }
+
+    fn at_end(&self) -> bool {
+        self.pos >= self.bytes.len() * 8
+    }
+
+    /// The number of bits left to read
+    fn bits_left(&self) -> u64 {
+        (self.bytes.len() * 8).saturating_sub(self.pos) as u64
+    }
+
+    fn rest_is_zero(&self) -> bool {
+        self.bytes[self.pos / 8 ..].iter().all(|&b| b == 0)
+    }
+
+    /// Read a `width`-bit fixed-width value
+    pub(super) fn read(&mut self, width: u32) -> Result<u64, String> {
+        if width > 64 {
+            return Err(format!("Invalid fixed width {}", width));
+        }
+        let mut value = 0u64;
+        let mut got = 0;
+        while got < width {
+            let byte = *self
+                .bytes
+                .get(self.pos / 8)
+                .ok_or("Unexpected end of bitcode")?;
+            let offset = (self.pos % 8) as u32;
+            let take = (8 - offset).min(width - got);
+            let bits = (u64::from(byte) >> offset) & ((1 << take) - 1);
+            value |= bits.checked_shl(got).unwrap_or(0);
+            got += take;
+            self.pos += take as usize;
+        }
+        Ok(value)
+    }
+
+    /// Read a variable-width value made of `width`-bit chunks
+    pub(super) fn read_vbr(&mut self, width: u32) -> Result<u64, String> {
+        if !(2 ..= 32).contains(&width) {
+            return Err(format!("Invalid VBR width {}", width));
+        }
+        let hi_bit = 1 << (width - 1);
+        let mut value = 0u64;
+        let mut shift = 0;
+        loop {
+            let piece = self.read(width)?;
+            value |= (piece & (hi_bit - 1)).checked_shl(shift).unwrap_or(0);
+            if piece & hi_bit == 0 {
+                return Ok(value);
+            }
+            shift += width - 1;
+            if shift >= 64 {
+                return Err("VBR value is too large".into());
+            }
+        }
+    }
+
+    fn align32(&mut self) {
+        self.pos = (self.pos + 31) & !31;
+    }
+
+    fn read_bytes(&mut self, len: usize) -> Result<Vec<u8>, String> {
+        let start = self.pos / 8;
+        let bytes = start
+            .checked_add(len)
+            .and_then(|end| self.bytes.get(start .. end))
+            .ok_or("Unexpected end of bitcode")?
+            .to_vec();
+        self.pos += len * 8;
+        Ok(bytes)
+    }
+}
@@ -0,0 +1,417 @@
+//! Constants (`CONSTANTS_BLOCK_ID`), which are decoded into their textual
+//! form when first used
+
+use super::bitstream::{Block, Record};
+use super::types::{Ty, TypeId};
+use super::{escape, escape_bytes, Reader, Value, ValueKind, BLOCK_ADDRESS_MARK};
+use std::cell::RefCell;
+
+// CST_CODE_*
+const SETTYPE: u32 = 1;
+const NULL: u32 = 2;
+const UNDEF: u32 = 3;
+const INTEGER: u32 = 4;
+const WIDE_INTEGER: u32 = 5;
+const FLOAT: u32 = 6;
+const AGGREGATE: u32 = 7;
+const STRING: u32 = 8;
+const CSTRING: u32 = 9;
+const CE_BINOP: u32 = 10;
+const CE_CAST: u32 = 11;
+const CE_GEP: u32 = 12;
+const CE_SELECT: u32 = 13;
+const CE_EXTRACTELT: u32 = 14;
+const CE_INSERTELT: u32 = 15;
+const CE_SHUFFLEVEC: u32 = 16;
+const CE_CMP: u32 = 17;
+const INLINEASM_OLD: u32 = 18;
+const CE_SHUFVEC_EX: u32 = 19;
+const CE_INBOUNDS_GEP: u32 = 20;
+const BLOCKADDRESS: u32 = 21;
+const DATA: u32 = 22;
+const INLINEASM_OLD2: u32 = 23;
+const CE_GEP_WITH_INRANGE_INDEX: u32 = 24;
+const CE_UNOP: u32 = 25;
+const POISON: u32 = 26;
+const DSO_LOCAL_EQUIVALENT: u32 = 27;
+const INLINEASM_OLD3: u32 = 28;
+const NO_CFI_VALUE: u32 = 29;
+const INLINEASM: u32 = 30;
+
+pub(super) const CAST_OPCODES: &[&str] = &[
+    "trunc",
+    "zext",
+    "sext",
+    "fptoui",
+    "fptosi",
+    "uitofp",
+    "sitofp",
+    "fptrunc",
+    "fpext",
+    "ptrtoint",
+    "inttoptr",
+    "bitcast",
+    "addrspacecast",
+];
+
+const BINARY_OPCODES: &[&str] = &[
+    "add", "sub", "mul", "udiv", "sdiv", "urem", "srem", "shl", "lshr", "ashr", "and", "or", "xor",
+];
+
+/// The textual opcode of a binary operator with the given bitcode opcode,
+/// applied to values of the given type
+pub(super) fn binary_opcode(opcode: u64, is_fp: bool) -> Result<&'static str, String> {
+    let name = BINARY_OPCODES
+        .get(opcode as usize)
+        .ok_or_else(|| format!("Invalid binary opcode {}", opcode))?;
+    if !is_fp {
+        return Ok(name);
+    }
+    match *name {
+        "add" => Ok("fadd"),
+        "sub" => Ok("fsub"),
+        "mul" => Ok("fmul"),
+        "sdiv" => Ok("fdiv"),
+        "srem" => Ok("frem"),
+        _ => Err(format!("Invalid floating-point binary opcode {}", opcode)),
+    }
+}
+
+/// The flags (`nuw`, `nsw`, or `exact`) of a binary operator, each with a
+/// trailing space
+pub(super) fn binary_flags(opcode: &str, flags: u64) -> String {
+    let mut text = String::new();
+    match opcode {
+        "add" | "sub" | "mul" | "shl" => {
+            if flags & 1 != 0 {
+                text.push_str("nuw ");
+            }
+            if flags & 2 != 0 {
+                text.push_str("nsw ");
+            }
+        },
+        "udiv" | "sdiv" | "lshr" | "ashr" if flags & 1 != 0 => text.push_str("exact "),
+        _ => {},
+    }
+    text
+}
+
+pub(super) fn cmp_predicate(predicate: u64) -> Result<(&'static str, &'static str), String> {
+    const FCMP: &[&str] = &[
+        "false", "oeq", "ogt", "oge", "olt", "ole", "one", "ord", "uno", "ueq", "ugt", "uge",
+        "ult", "ule", "une", "true",
+    ];
+    const ICMP: &[&str] = &[
+        "eq", "ne", "ugt", "uge", "ult", "ule", "sgt", "sge", "slt", "sle",
+    ];
+    match predicate {
+        0 ..= 15 => Ok(("fcmp", FCMP[predicate as usize])),
+        32 ..= 41 => Ok(("icmp", ICMP[predicate as usize - 32])),
+        _ => Err(format!("Invalid comparison predicate {}", predicate)),
+    }
+}
+
+/// Decode a signed value, which bitcode stores with the sign in the lowest
+/// bit
+pub(super) fn decode_signed(v: u64) -> i64 {
+    if v & 1 == 0 {
+        (v >> 1) as i64
+    } else if v != 1 {
+        -((v >> 1) as i64)
+    } else {
+        // -0, which is used for the most negative value
+        i64::MIN
+    }
+}
+
+impl<'a> Reader<'a> {
+    /// Add the constants of a constants block (of the module or of the
+    /// current function) to the value table
+    pub(super) fn read_constants(&mut self, block: &'a Block) -> Result<(), String> {
+        let mut ty = self.types.int(32);
+        for record in block.records() {
+            if record.code == SETTYPE {
+                ty = record.op(0) as TypeId;
+                self.types.get(ty)?;
+                continue;
+            }
+            self.values.push(Value {
+                ty,
+                kind: ValueKind::Constant(record, RefCell::new(None)),
+            });
+        }
+        Ok(())
+    }
+
+    /// The IDs of the elements of the aggregate constant with the given ID,
+    /// or `None` if it's not an aggregate. (Like LLVM, this gives no elements
+    /// for `zeroinitializer`.)
+    pub(super) fn aggregate_elements(&self, id: usize) -> Option<Vec<usize>> {
+        match &self.values.get(id)?.kind {
+            ValueKind::Constant(record, _) if record.code == AGGREGATE => {
+                Some(record.ops.iter().map(|&op| op as usize).collect())
+            },
+            ValueKind::Constant(record, _) if record.code == NULL => Some(vec![]),
+            _ => None,
+        }
+    }
+
+    /// The textual form (without the type) of the constant with the given
+    /// record and type
+    pub(super) fn decode_constant(&self, record: &Record, ty: TypeId) -> Result<String, String> {
+        let op = |i: usize| record.op(i);
+        let typed = |i: usize| self.typed_constant_text(op(i) as usize);
+        let text = match record.code {
+            NULL => match self.types.get(ty)? {
+                Ty::Pointer { .. } => "null".into(),
+                Ty::Token => "none".into(),
+                Ty::Void | Ty::Function { .. } | Ty::Label | Ty::Metadata => {
+                    return Err("Invalid type for a null constant".into())
+                },
+                _ => "zeroinitializer".into(),
+            },
+            UNDEF => "undef".into(),
+            POISON => "poison".into(),
+            INTEGER => {
+                let value = decode_signed(op(0));
+                match self.types.int_width(ty) {
+                    Some(1) => (value & 1 == 1).to_string(),
+                    _ => value.to_string(),
+                }
+            },
+            // only the low 64 bits are kept
+            WIDE_INTEGER => (decode_signed(op(0)) as u64).to_string(),
+            FLOAT => self.float_text(ty, op(0), op(1))?,
+            AGGREGATE => {
+                let elements = record
+                    .ops
+                    .iter()
+                    .map(|&id| self.typed_constant_text(id as usize))
+                    .collect::<Result<Vec<_>, _>>()?
+                    .join(", ");
+                match self.types.get(ty)? {
+                    Ty::Array(..) => format!("[{}]", elements),
+                    Ty::Vector { .. } => format!("<{}>", elements),
+                    Ty::LiteralStruct { packed: true, .. }
+                    | Ty::Identified {
+                        body: Some((_, true)),
+                        ..
+                    } => format!("<{{ {} }}>", elements),
+                    _ if elements.is_empty() => "{}".into(),
+                    _ => format!("{{ {} }}", elements),
+                }
+            },
+            STRING | CSTRING | DATA => {
+                let element = self.types.element(ty, 0).ok_or("Invalid type for data")?;
+                let mut values = record.ops.clone();
+                if record.code == CSTRING {
+                    values.push(0);
+                }
+                if self.types.int_width(element) == Some(8) && !self.types.is_vector(ty) {
+                    let bytes: Vec<u8> = values.iter().map(|&b| b as u8).collect();
+                    format!("c\"{}\"", escape_bytes(&bytes))
+                } else {
+                    let element_text = self.types.text(element);
+                    let elements = values
+                        .iter()
+                        .map(|&v| {
+                            let value = if self.types.is_fp(element) {
+                                self.float_text(element, v, 0)?
+                            } else {
+                                v.to_string()
+                            };
+                            Ok(format!("{} {}", element_text, value))
+                        })
+                        .collect::<Result<Vec<_>, String>>()?
+                        .join(", ");
+                    if self.types.is_vector(ty) {
+                        format!("<{}>", elements)
+                    } else {
+                        format!("[{}]", elements)
+                    }
+                }
+            },
+            CE_BINOP => {
+                // [opcode, lhs, rhs, flags]
+                let opcode = binary_opcode(op(0), self.types.is_fp(ty))?;
+                format!(
+                    "{} {}({}, {})",
+                    opcode,
+                    binary_flags(opcode, op(3)),
+                    typed(1)?,
+                    typed(2)?
+                )
+            },
+            CE_UNOP => format!("fneg ({})", typed(1)?),
+            CE_CAST => {
+                // [opcode, operand type, operand]
+                let opcode = CAST_OPCODES
+                    .get(op(0) as usize)
+                    .ok_or("Invalid cast opcode")?;
+                format!("{} ({} to {})", opcode, typed(2)?, self.types.text(ty))
+            },
+            CE_GEP | CE_INBOUNDS_GEP | CE_GEP_WITH_INRANGE_INDEX => self.gep_text(record)?,
+            CE_SELECT => format!("select ({}, {}, {})", typed(0)?, typed(1)?, typed(2)?),
+            CE_EXTRACTELT => {
+                // [vector type, vector, index type, index], or [vector type,
+                // vector, index] in old bitcode
+                let index = if record.ops.len() > 3 { 3 } else { 2 };
+                format!("extractelement ({}, {})", typed(1)?, typed(index)?)
+            },
+            CE_INSERTELT => {
+                // [vector, element, index type, index] or [vector, element,
+                // index]
+                let index = if record.ops.len() > 3 { 3 } else { 2 };
+                format!(
+                    "insertelement ({}, {}, {})",
+                    typed(0)?,
+                    typed(1)?,
+                    typed(index)?
+                )
+            },
+            CE_SHUFFLEVEC => format!(
+                "shufflevector ({}, {}, {})",
+                typed(0)?,
+                typed(1)?,
+                typed(2)?
+            ),
+            CE_SHUFVEC_EX => format!(
+                "shufflevector ({}, {}, {})",
+                typed(1)?,
+                typed(2)?,
+                typed(3)?
+            ),
+            CE_CMP => {
+                // [operand type, lhs, rhs, predicate]
+                let (opcode, predicate) = cmp_predicate(op(3))?;
+                format!("{} {} ({}, {})", opcode, predicate, typed(1)?, typed(2)?)
+            },
+            BLOCKADDRESS => {
+                // [function type, function, basic block]
+                format!(
+                    "blockaddress({}, {mark}{}:{}{mark})",
+                    self.constant_text(op(1) as usize)?,
+                    op(1),
+                    op(2),
+                    mark = BLOCK_ADDRESS_MARK
+                )
+            },
+            DSO_LOCAL_EQUIVALENT => {
+                format!(
+                    "dso_local_equivalent {}",
+                    self.constant_text(op(1) as usize)?
+                )
+            },
+            NO_CFI_VALUE => format!("no_cfi {}", self.constant_text(op(1) as usize)?),
+            INLINEASM_OLD | INLINEASM_OLD2 | INLINEASM_OLD3 | INLINEASM => {
+                self.inline_asm_text(record)?
+            },
+            code => return Err(format!("Unsupported constant code {}", code)),
+        };
+        Ok(text)
+    }
+
+    /// The text of a floating-point constant of type `ty`, with the given
+    /// bits (`high` is only used for 80- and 128-bit types)
+    fn float_text(&self, ty: TypeId, low: u64, high: u64) -> Result<String, String> {
+        Ok(match self.types.get(ty)? {
+            Ty::Half => format!("0xH{:04X}", low as u16),
+            Ty::BFloat => format!("0xR{:04X}", low as u16),
+            Ty::Float => format!("0x{:016X}", f64::from(f32::from_bits(low as u32)).to_bits()),
+            Ty::Double => format!("0x{:016X}", low),
+            Ty::X86Fp80 => format!("0xK{:04X}{:016X}", high as u16, low),
+            Ty::Fp128 => format!("0xL{:016X}{:016X}", low, high),
+            Ty::PpcFp128 => format!("0xM{:016X}{:016X}", low, high),
+            Ty::Vector { element, .. } => return self.float_text(*element, low, high),
+            _ => return Err("Invalid type for a floating-point constant".into()),
+        })
+    }
+
+    fn gep_text(&self, record: &Record) -> Result<String, String> {
+        // [source element type, (flags,) n x (type, value)]; the source
+        // element type is missing in old bitcode, where the number of
+        // operands is even
+        let mut ops = record.ops.as_slice();
+        let mut source_type = None;
+        if record.code == CE_GEP_WITH_INRANGE_INDEX || ops.len() % 2 == 1 {
+            source_type = Some(ops[0] as TypeId);
+            ops = &ops[1 ..];
+        }
+        let mut in_bounds = record.code == CE_INBOUNDS_GEP;
+        let mut in_range = None;
+        if record.code == CE_GEP_WITH_INRANGE_INDEX {
+            in_bounds = ops[0] & 1 != 0;
+            in_range = Some(ops[0] as usize >> 1);
+            ops = &ops[1 ..];
+        }
+        let mut operands = vec![];
+        for (i, pair) in ops.chunks(2).enumerate() {
+            let operand = self.typed_constant_text(pair.get(1).copied().unwrap_or(0) as usize)?;
+            if i > 0 && in_range == Some(i - 1) {
+                operands.push(format!("inrange {}", operand));
+            } else {
+                operands.push(operand);
+            }
+        }
+        let source_type = match source_type {
+            Some(ty) => ty,
+            None => {
+                let base = ops.get(1).copied().unwrap_or(0) as usize;
+                self.types
+                    .pointee(self.value(base)?.ty)
+                    .ok_or("Invalid getelementptr base")?
+            },
+        };
+        Ok(format!(
+            "getelementptr {}({}, {})",
+            if in_bounds { "inbounds " } else { "" },
+            self.types.text(source_type),
+            operands.join(", ")
+        ))
+    }
+
+    fn inline_asm_text(&self, record: &Record) -> Result<String, String> {
+        // [function type, flags, asm size, asm..., constraints size,
+        //  constraints...], where only INLINEASM has the function type
+        let ops = if record.code == INLINEASM {
+            record.ops.get(1 ..).unwrap_or(&[])
+        } else {
+            &record.ops
+        };
+        let flags = ops.first().copied().unwrap_or(0);
+        let string_at = |start: usize| -> Result<(String, usize), String> {
+            let len = *ops.get(start).ok_or("Invalid inline asm record")? as usize;
+            let bytes: Vec<u8> = ops
+                .get(start + 1 .. start + 1 + len)
+                .ok_or("Invalid inline asm record")?
+                .iter()
+                .map(|&c| c as u8)
+                .collect();
+            Ok((
+                String::from_utf8_lossy(&bytes).into_owned(),
+                start + 1 + len,
+            ))
+        };
+        let (asm, next) = string_at(1)?;
+        let (constraints, _) = string_at(next)?;
+        let mut text = String::from("asm ");
+        if flags & 1 != 0 {
+            text.push_str("sideeffect ");
+        }
+        if flags & 2 != 0 {
+            text.push_str("alignstack ");
+        }
+        if record.code != INLINEASM_OLD && (flags >> 2) & 1 != 0 {
+            text.push_str("inteldialect ");
+        }
+        if (record.code == INLINEASM_OLD3 || record.code == INLINEASM) && (flags >> 3) & 1 != 0 {
+            text.push_str("unwind ");
+        }
+        text.push_str(&format!(
+            "\"{}\", \"{}\"",
+            escape(&asm),
+            escape(&constraints)
+        ));
+        Ok(text)
+    }
+}
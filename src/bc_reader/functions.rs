@@ -0,0 +1,1433 @@
+//! Function bodies (`FUNCTION_BLOCK_ID`)
+
+use super::attributes::{self, AttributeList};
+use super::bitstream::{Block, Item, Record};
+use super::constants::{binary_flags, binary_opcode, cmp_predicate, decode_signed, CAST_OPCODES};
+use super::types::{Ty, TypeId};
+use super::{
+    data_layout_addr_space, decode_alignment, escape, local_name, Reader, Value, ValueKind,
+    CONSTANTS_BLOCK_ID, METADATA_ATTACHMENT_ID, METADATA_BLOCK_ID, VALUE_SYMTAB_BLOCK_ID,
+    VST_BBENTRY, VST_ENTRY,
+};
+
+/// The longest case range of an old `switch` which we expand into cases
+const MAX_CASE_RANGE: i64 = 1 << 16;
+
+// FUNC_CODE_*
+const DECLAREBLOCKS: u32 = 1;
+const INST_BINOP: u32 = 2;
+const INST_CAST: u32 = 3;
+const INST_GEP_OLD: u32 = 4;
+const INST_SELECT: u32 = 5;
+const INST_EXTRACTELT: u32 = 6;
+const INST_INSERTELT: u32 = 7;
+const INST_SHUFFLEVEC: u32 = 8;
+const INST_CMP: u32 = 9;
+const INST_RET: u32 = 10;
+const INST_BR: u32 = 11;
+const INST_SWITCH: u32 = 12;
+const INST_INVOKE: u32 = 13;
+const INST_UNREACHABLE: u32 = 15;
+const INST_PHI: u32 = 16;
+const INST_ALLOCA: u32 = 19;
+const INST_LOAD: u32 = 20;
+const INST_VAARG: u32 = 23;
+const INST_STORE_OLD: u32 = 24;
+const INST_EXTRACTVAL: u32 = 26;
+const INST_INSERTVAL: u32 = 27;
+const INST_CMP2: u32 = 28;
+const INST_VSELECT: u32 = 29;
+const INST_INBOUNDS_GEP_OLD: u32 = 30;
+const INST_INDIRECTBR: u32 = 31;
+const DEBUG_LOC_AGAIN: u32 = 33;
+const INST_CALL: u32 = 34;
+const DEBUG_LOC: u32 = 35;
+const INST_FENCE: u32 = 36;
+const INST_CMPXCHG_OLD: u32 = 37;
+const INST_ATOMICRMW_OLD: u32 = 38;
+const INST_RESUME: u32 = 39;
+const INST_LANDINGPAD_OLD: u32 = 40;
+const INST_LOADATOMIC: u32 = 41;
+const INST_STOREATOMIC_OLD: u32 = 42;
+const INST_GEP: u32 = 43;
+const INST_STORE: u32 = 44;
+const INST_STOREATOMIC: u32 = 45;
+const INST_CMPXCHG: u32 = 46;
+const INST_LANDINGPAD: u32 = 47;
+const INST_CLEANUPRET: u32 = 48;
+const INST_CATCHRET: u32 = 49;
+const INST_CATCHPAD: u32 = 50;
+const INST_CLEANUPPAD: u32 = 51;
+const INST_CATCHSWITCH: u32 = 52;
+const OPERAND_BUNDLE: u32 = 55;
+const INST_UNOP: u32 = 56;
+const INST_CALLBR: u32 = 57;
+const INST_FREEZE: u32 = 58;
+const INST_ATOMICRMW: u32 = 59;
+
+/// The magic number in the first operand of a `switch` with case ranges
+const SWITCH_INST_MAGIC: u64 = 0x4B5;
+
+// bits of the calling-convention operand of calls
+const CALL_TAIL: u64 = 0;
+const CALL_CCONV: u64 = 1;
+const CALL_MUSTTAIL: u64 = 14;
+const CALL_EXPLICIT_TYPE: u64 = 15;
+const CALL_NOTAIL: u64 = 16;
+const CALL_FMF: u64 = 17;
+
+const RMW_OPERATIONS: &[&str] = &[
+    "xchg",
+    "add",
+    "sub",
+    "and",
+    "nand",
+    "or",
+    "xor",
+    "max",
+    "min",
+    "umax",
+    "umin",
+    "fadd",
+    "fsub",
+    "fmax",
+    "fmin",
+    "uinc_wrap",
+    "udec_wrap",
+];
+
+/// A part of the text of an instruction. Values and blocks are only named
+/// once the whole function (including its symbol table) has been read.
+enum Piece {
+    Text(String),
+    /// The value with the given ID, without its type
+    Value(usize),
+    /// The local with the given index
+    Local(usize),
+    /// The label of the basic block with the given index
+    Block(usize),
+}
+
+struct Instruction {
+    block: usize,
+    /// The local the instruction defines, if it has a result
+    result: Option<usize>,
+    pieces: Vec<Piece>,
+    debug_loc: Option<String>,
+}
+
+/// Builds the pieces of an instruction
+#[derive(Default)]
+struct Pieces(Vec<Piece>);
+
+impl Pieces {
+    fn text(&mut self, text: impl Into<String>) -> &mut Self {
+        self.0.push(Piece::Text(text.into()));
+        self
+    }
+
+    fn value(&mut self, id: usize) -> &mut Self {
+        self.0.push(Piece::Value(id));
+        self
+    }
+
+    fn block(&mut self, index: u64) -> &mut Self {
+        self.0.push(Piece::Text("label ".into()));
+        self.0.push(Piece::Block(index as usize));
+        self
+    }
+}
+
+/// The state of the function being read
+struct Function {
+    /// The value ID of the function's first argument
+    first_local: usize,
+    /// The names of the arguments and instruction results, by local index
+    local_names: Vec<Option<String>>,
+    block_names: Vec<Option<String>>,
+    instructions: Vec<Instruction>,
+    current_block: usize,
+    /// The number of records in the function block
+    num_records: usize,
+    debug_loc: Option<String>,
+    bundles: Vec<Pieces>,
+    personality: Option<usize>,
+}
+
+impl<'a> Reader<'a> {
+    /// Read the body of the function with the given index in `globals`,
+    /// giving the text of its definition
+    pub(super) fn read_function(
+        &mut self,
+        global: usize,
+        block: &'a Block,
+    ) -> Result<String, String> {
+        let value_type = self.globals[global].value_type;
+        let mut function = Function {
+            first_local: self.values.len(),
+            local_names: vec![],
+            block_names: vec![],
+            instructions: vec![],
+            current_block: 0,
+            num_records: block.records().count(),
+            debug_loc: None,
+            bundles: vec![],
+            personality: None,
+        };
+        for ty in self.types.params(value_type) {
+            self.new_local(&mut function, ty);
+        }
+        let mut attachments = vec![];
+        for item in &block.items {
+            match item {
+                Item::Block(sub) => match sub.id {
+                    CONSTANTS_BLOCK_ID => self.read_constants(sub)?,
+                    METADATA_BLOCK_ID => self.read_metadata(sub)?,
+                    METADATA_ATTACHMENT_ID => attachments = self.read_function_attachments(sub)?,
+                    VALUE_SYMTAB_BLOCK_ID => self.read_function_symtab(&mut function, sub),
+                    _ => {},
+                },
+                Item::Record(record) => self
+                    .read_instruction(&mut function, record)
+                    .map_err(|e| format!("in function {}: {}", self.globals[global].name, e))?,
+            }
+        }
+        let text = self.function_text(global, &function, &attachments);
+        self.values.truncate(function.first_local);
+        self.metadata.finish_function();
+        let (text, block_labels) = text?;
+        self.record_block_labels(global, block_labels);
+        Ok(text)
+    }
+
+    fn new_local(&mut self, function: &mut Function, ty: TypeId) -> usize {
+        let local = function.local_names.len();
+        function.local_names.push(None);
+        self.values.push(Value {
+            ty,
+            kind: ValueKind::Local(local),
+        });
+        local
+    }
+
+    fn read_function_symtab(&self, function: &mut Function, block: &Block) {
+        for record in block.records() {
+            match record.code {
+                VST_ENTRY => {
+                    if let Some(Value {
+                        kind: ValueKind::Local(local),
+                        ..
+                    }) = self.values.get(record.op(0) as usize)
+                    {
+                        function.local_names[*local] = Some(record.string(1));
+                    }
+                },
+                VST_BBENTRY => {
+                    if let Some(name) = function.block_names.get_mut(record.op(0) as usize) {
+                        *name = Some(record.string(1));
+                    }
+                },
+                _ => {},
+            }
+        }
+    }
+
+    fn function_text(
+        &self,
+        global: usize,
+        function: &Function,
+        attachments: &[String],
+    ) -> Result<(String, Vec<String>), String> {
+        // number the unnamed values and blocks in order, as LLVM does
+        let mut next_number = 0;
+        let mut local_texts = vec![String::new(); function.local_names.len()];
+        let mut number = |name: &Option<String>| match name {
+            Some(name) => local_name(name),
+            None => {
+                next_number += 1;
+                format!("%{}", next_number - 1)
+            },
+        };
+        let num_args = self.types.params(self.globals[global].value_type).len();
+        for (local, text) in local_texts.iter_mut().enumerate().take(num_args) {
+            *text = number(&function.local_names[local]);
+        }
+        let mut block_texts = vec![String::new(); function.block_names.len()];
+        let mut current_block = None;
+        for inst in &function.instructions {
+            if current_block != Some(inst.block) {
+                current_block = Some(inst.block);
+                if let Some(text) = block_texts.get_mut(inst.block) {
+                    *text = number(&function.block_names[inst.block]);
+                }
+            }
+            if let Some(local) = inst.result {
+                local_texts[local] = number(&function.local_names[local]);
+            }
+        }
+
+        let param_names: Vec<String> = local_texts[.. num_args].to_vec();
+        let has_dbg = attachments.iter().any(|a| a.starts_with("!dbg "));
+        let subprogram = if self.metadata.strip_debug_info || has_dbg {
+            None
+        } else {
+            self.metadata
+                .function_subprograms
+                .get(&self.function_value_id(global))
+                .map(|s| s.as_str())
+        };
+        let mut text = self.function_header(
+            global,
+            &param_names,
+            attachments,
+            function.personality,
+            subprogram,
+        )?;
+        text.push_str(" {\n");
+        let mut current_block = None;
+        for inst in &function.instructions {
+            if current_block != Some(inst.block) {
+                if inst.block != 0 || function.block_names[0].is_some() {
+                    let label = &block_texts[inst.block];
+                    text.push_str(&label[1 ..]);
+                    text.push_str(":\n");
+                }
+                current_block = Some(inst.block);
+            }
+            text.push_str("  ");
+            if let Some(local) = inst.result {
+                text.push_str(&local_texts[local]);
+                text.push_str(" = ");
+            }
+            for piece in &inst.pieces {
+                match piece {
+                    Piece::Text(s) => text.push_str(s),
+                    Piece::Local(local) => text.push_str(&local_texts[*local]),
+                    Piece::Block(block) => text.push_str(
+                        block_texts
+                            .get(*block)
+                            .ok_or_else(|| format!("Invalid basic block {}", block))?,
+                    ),
+                    Piece::Value(id) => match &self.value(*id)?.kind {
+                        ValueKind::Local(local) => text.push_str(&local_texts[*local]),
+                        _ => text.push_str(&self.constant_text(*id)?),
+                    },
+                }
+            }
+            if let Some(debug_loc) = &inst.debug_loc {
+                text.push_str(", !dbg ");
+                text.push_str(debug_loc);
+            }
+            text.push('\n');
+        }
+        text.push('}');
+        Ok((text, block_texts))
+    }
+
+    fn function_value_id(&self, global: usize) -> usize {
+        self.values
+            .iter()
+            .position(|v| matches!(v.kind, ValueKind::Global(g) if g == global))
+            .unwrap_or(usize::MAX)
+    }
+
+    /// Labels of the blocks of a function, for `blockaddress` constants
+    fn record_block_labels(&mut self, global: usize, labels: Vec<String>) {
+        let id = self.function_value_id(global);
+        self.block_labels.insert(id, labels);
+    }
+
+    fn read_instruction(&mut self, function: &mut Function, record: &Record) -> Result<(), String> {
+        let mut r = Operands {
+            record,
+            next: 0,
+            relative: self.version >= 1,
+            inst_num: self.values.len(),
+        };
+        let mut p = Pieces::default();
+        // the type of the result, if any
+        let mut result = None;
+        let mut is_terminator = false;
+        match record.code {
+            DECLAREBLOCKS => {
+                // each block has at least one instruction, so there can't be
+                // more blocks than records
+                let num_blocks = record.op(0) as usize;
+                if num_blocks == 0 || num_blocks > function.num_records {
+                    return Err(format!("Invalid number of basic blocks {}", num_blocks));
+                }
+                function.block_names = vec![None; num_blocks];
+                return Ok(());
+            },
+            DEBUG_LOC => {
+                // [line, column, scope + 1, inlinedAt + 1, isImplicit]
+                let scope = self.metadata.reference(record.op(2))?;
+                let inlined_at = self.metadata.reference(record.op(3))?;
+                function.debug_loc = match scope {
+                    Some(scope) if !self.metadata.strip_debug_info => Some(self.metadata.location(
+                        record.op(0),
+                        record.op(1),
+                        &scope,
+                        inlined_at.as_deref(),
+                        record.op(4) != 0,
+                    )),
+                    _ => None,
+                };
+                if let Some(inst) = function.instructions.last_mut() {
+                    inst.debug_loc = function.debug_loc.clone();
+                }
+                return Ok(());
+            },
+            DEBUG_LOC_AGAIN => {
+                if let Some(inst) = function.instructions.last_mut() {
+                    inst.debug_loc = function.debug_loc.clone();
+                }
+                return Ok(());
+            },
+            OPERAND_BUNDLE => {
+                // [tag, n x (value, type)]
+                let tag = self
+                    .bundle_tags
+                    .get(record.op(0) as usize)
+                    .ok_or("Invalid operand bundle tag")?
+                    .clone();
+                r.next = 1;
+                let mut bundle = Pieces::default();
+                bundle.text(format!("\"{}\"(", escape(&tag)));
+                let mut first = true;
+                while !r.done() {
+                    if !first {
+                        bundle.text(", ");
+                    }
+                    first = false;
+                    let (id, ty) = r.value_type_pair(self)?;
+                    bundle.text(format!("{} ", self.types.text(ty))).value(id);
+                }
+                bundle.text(")");
+                function.bundles.push(bundle);
+                return Ok(());
+            },
+            INST_BINOP => {
+                // [lhs, rhs, opcode, flags]
+                let (lhs, ty) = r.value_type_pair(self)?;
+                let rhs = r.value()?;
+                let opcode = binary_opcode(r.next_op(), self.types.is_fp(ty))?;
+                p.text(opcode).text(" ");
+                if !r.done() {
+                    let flags = r.next_op();
+                    if self.types.is_fp(ty) {
+                        p.text(fast_math_flags(flags));
+                    } else {
+                        p.text(binary_flags(opcode, flags));
+                    }
+                }
+                p.text(format!("{} ", self.types.text(ty)))
+                    .value(lhs)
+                    .text(", ")
+                    .value(rhs);
+                result = Some(ty);
+            },
+            INST_UNOP => {
+                // [operand, opcode, flags]
+                let (op, ty) = r.value_type_pair(self)?;
+                r.next_op();
+                p.text("fneg ");
+                if !r.done() {
+                    p.text(fast_math_flags(r.next_op()));
+                }
+                p.text(format!("{} ", self.types.text(ty))).value(op);
+                result = Some(ty);
+            },
+            INST_CAST => {
+                // [operand, destination type, opcode]
+                let (op, ty) = r.value_type_pair(self)?;
+                let dest = r.next_op() as TypeId;
+                let opcode = CAST_OPCODES
+                    .get(r.next_op() as usize)
+                    .ok_or("Invalid cast opcode")?;
+                p.text(format!("{} {} ", opcode, self.types.text(ty)))
+                    .value(op)
+                    .text(format!(" to {}", self.types.text(dest)));
+                result = Some(dest);
+            },
+            INST_GEP_OLD | INST_INBOUNDS_GEP_OLD | INST_GEP => {
+                // [inbounds, source type, n x operands], without the first two
+                // in old bitcode
+                let (in_bounds, mut source) = if record.code == INST_GEP {
+                    (r.next_op() != 0, Some(r.next_op() as TypeId))
+                } else {
+                    (record.code == INST_INBOUNDS_GEP_OLD, None)
+                };
+                let mut operands = vec![];
+                while !r.done() {
+                    operands.push(r.value_type_pair(self)?);
+                }
+                let (base, base_ty) = *operands.first().ok_or("Invalid getelementptr")?;
+                let source = match source.take() {
+                    Some(source) => source,
+                    None => self
+                        .types
+                        .pointee(base_ty)
+                        .ok_or("Invalid getelementptr base")?,
+                };
+                p.text(format!(
+                    "getelementptr {}{}, {} ",
+                    if in_bounds { "inbounds " } else { "" },
+                    self.types.text(source),
+                    self.types.text(base_ty)
+                ))
+                .value(base);
+                for &(id, ty) in &operands[1 ..] {
+                    p.text(format!(", {} ", self.types.text(ty))).value(id);
+                }
+                result = Some(self.gep_result_type(source, &operands)?);
+            },
+            INST_SELECT | INST_VSELECT => {
+                // [true value, false value, condition, flags]
+                let (true_value, ty) = r.value_type_pair(self)?;
+                let false_value = r.value()?;
+                let (condition, condition_ty) = if record.code == INST_VSELECT {
+                    r.value_type_pair(self)?
+                } else {
+                    (r.value()?, self.types.int(1))
+                };
+                p.text("select ");
+                if !r.done() {
+                    p.text(fast_math_flags(r.next_op()));
+                }
+                p.text(format!("{} ", self.types.text(condition_ty)))
+                    .value(condition)
+                    .text(format!(", {} ", self.types.text(ty)))
+                    .value(true_value)
+                    .text(format!(", {} ", self.types.text(ty)))
+                    .value(false_value);
+                result = Some(ty);
+            },
+            INST_EXTRACTELT => {
+                // [vector, index]
+                let (vector, ty) = r.value_type_pair(self)?;
+                let (index, index_ty) = r.value_type_pair(self)?;
+                p.text(format!("extractelement {} ", self.types.text(ty)))
+                    .value(vector)
+                    .text(format!(", {} ", self.types.text(index_ty)))
+                    .value(index);
+                result = Some(self.types.element(ty, 0).ok_or("Invalid extractelement")?);
+            },
+            INST_INSERTELT => {
+                // [vector, element, index]
+                let (vector, ty) = r.value_type_pair(self)?;
+                let element = r.value()?;
+                let (index, index_ty) = r.value_type_pair(self)?;
+                let element_ty = self.types.element(ty, 0).ok_or("Invalid insertelement")?;
+                p.text(format!("insertelement {} ", self.types.text(ty)))
+                    .value(vector)
+                    .text(format!(", {} ", self.types.text(element_ty)))
+                    .value(element)
+                    .text(format!(", {} ", self.types.text(index_ty)))
+                    .value(index);
+                result = Some(ty);
+            },
+            INST_SHUFFLEVEC => {
+                // [vector, vector, mask]
+                let (v1, ty) = r.value_type_pair(self)?;
+                let v2 = r.value()?;
+                let (mask, mask_ty) = r.value_type_pair(self)?;
+                p.text(format!("shufflevector {} ", self.types.text(ty)))
+                    .value(v1)
+                    .text(format!(", {} ", self.types.text(ty)))
+                    .value(v2)
+                    .text(format!(", {} ", self.types.text(mask_ty)))
+                    .value(mask);
+                let element = self.types.element(ty, 0).ok_or("Invalid shufflevector")?;
+                let scalable =
+                    matches!(self.types.get(mask_ty)?, Ty::Vector { scalable: true, .. });
+                let n = self.types.num_elements(mask_ty);
+                result = Some(self.types.vector_of(element, n, scalable));
+            },
+            INST_CMP | INST_CMP2 => {
+                // [lhs, rhs, predicate, flags]
+                let (lhs, ty) = r.value_type_pair(self)?;
+                let rhs = r.value()?;
+                let (opcode, predicate) = cmp_predicate(r.next_op())?;
+                p.text(format!("{} ", opcode));
+                if !r.done() {
+                    p.text(fast_math_flags(r.next_op()));
+                }
+                p.text(format!("{} {} ", predicate, self.types.text(ty)))
+                    .value(lhs)
+                    .text(", ")
+                    .value(rhs);
+                let i1 = self.types.int(1);
+                result = Some(self.types.like_vector(ty, i1));
+            },
+            INST_RET => {
+                is_terminator = true;
+                if r.done() {
+                    p.text("ret void");
+                } else {
+                    let (value, ty) = r.value_type_pair(self)?;
+                    p.text(format!("ret {} ", self.types.text(ty))).value(value);
+                }
+            },
+            INST_BR => {
+                // [true block, false block, condition] or [block]
+                is_terminator = true;
+                if record.ops.len() == 1 {
+                    p.text("br ").block(record.op(0));
+                } else {
+                    r.next = 2;
+                    let condition = r.value()?;
+                    p.text("br i1 ")
+                        .value(condition)
+                        .text(", ")
+                        .block(record.op(0))
+                        .text(", ")
+                        .block(record.op(1));
+                }
+            },
+            INST_SWITCH => {
+                is_terminator = true;
+                self.switch_text(&mut p, &mut r)?;
+            },
+            INST_INDIRECTBR => {
+                // [address type, address, n x destinations]
+                is_terminator = true;
+                let ty = r.next_op() as TypeId;
+                let address = r.value()?;
+                p.text(format!("indirectbr {} ", self.types.text(ty)))
+                    .value(address)
+                    .text(", [");
+                for (i, &dest) in record.ops.get(2 ..).unwrap_or(&[]).iter().enumerate() {
+                    if i > 0 {
+                        p.text(", ");
+                    }
+                    p.block(dest);
+                }
+                p.text("]");
+            },
+            INST_INVOKE => {
+                // [attributes, cc, normal block, unwind block, function type,
+                //  callee, arguments...]
+                is_terminator = true;
+                let attrs = self.attribute_list(r.next_op());
+                let cc_info = r.next_op();
+                let (normal, unwind) = (r.next_op(), r.next_op());
+                let explicit_type = if (cc_info >> 13) & 1 != 0 {
+                    Some(r.next_op() as TypeId)
+                } else {
+                    None
+                };
+                p.text("invoke");
+                result = self.call_text(
+                    function,
+                    &mut p,
+                    &mut r,
+                    &attrs,
+                    cc_info & !(1 << 13),
+                    explicit_type,
+                )?;
+                p.text(" to ").block(normal).text(" unwind ").block(unwind);
+            },
+            INST_CALLBR => {
+                // [attributes, cc, default block, n, n x indirect blocks,
+                //  function type, callee, arguments...]
+                is_terminator = true;
+                let attrs = self.attribute_list(r.next_op());
+                let cc_info = r.next_op();
+                let default = r.next_op();
+                let num_indirect = r.next_op();
+                let indirect: Vec<u64> = (0 .. num_indirect).map(|_| r.next_op()).collect();
+                let explicit_type = if (cc_info >> CALL_EXPLICIT_TYPE) & 1 != 0 {
+                    Some(r.next_op() as TypeId)
+                } else {
+                    None
+                };
+                p.text("callbr");
+                result = self.call_text(
+                    function,
+                    &mut p,
+                    &mut r,
+                    &attrs,
+                    (cc_info >> CALL_CCONV) & 0x3ff,
+                    explicit_type,
+                )?;
+                p.text(" to ").block(default).text(" [");
+                for (i, &dest) in indirect.iter().enumerate() {
+                    if i > 0 {
+                        p.text(", ");
+                    }
+                    p.block(dest);
+                }
+                p.text("]");
+            },
+            INST_CALL => {
+                // [attributes, cc, fast-math flags, function type, callee,
+                //  arguments...]
+                let attrs = self.attribute_list(r.next_op());
+                let cc_info = r.next_op();
+                let fmf = if (cc_info >> CALL_FMF) & 1 != 0 {
+                    r.next_op()
+                } else {
+                    0
+                };
+                let explicit_type = if (cc_info >> CALL_EXPLICIT_TYPE) & 1 != 0 {
+                    Some(r.next_op() as TypeId)
+                } else {
+                    None
+                };
+                if (cc_info >> CALL_MUSTTAIL) & 1 != 0 {
+                    p.text("musttail ");
+                } else if (cc_info >> CALL_NOTAIL) & 1 != 0 {
+                    p.text("notail ");
+                } else if (cc_info >> CALL_TAIL) & 1 != 0 {
+                    p.text("tail ");
+                }
+                p.text("call");
+                if fmf != 0 {
+                    p.text(" ");
+                    p.text(fast_math_flags(fmf).trim_end().to_owned());
+                }
+                if self.metadata.strip_debug_info && self.calls_debug_intrinsic(record, &r) {
+                    function.bundles.clear();
+                    return Ok(());
+                }
+                result = self.call_text(
+                    function,
+                    &mut p,
+                    &mut r,
+                    &attrs,
+                    (cc_info >> CALL_CCONV) & 0x3ff,
+                    explicit_type,
+                )?;
+            },
+            INST_UNREACHABLE => {
+                is_terminator = true;
+                p.text("unreachable");
+            },
+            INST_PHI => {
+                // [type, n x (value, block), flags]
+                let ty = r.next_op() as TypeId;
+                p.text("phi ");
+                if record.ops.len() & 1 == 0 {
+                    p.text(fast_math_flags(*record.ops.last().unwrap_or(&0)));
+                }
+                p.text(self.types.text(ty));
+                let num_incoming = record.ops.len().saturating_sub(1) / 2;
+                for i in 0 .. num_incoming {
+                    let raw = record.op(1 + 2 * i);
+                    let id = if r.relative {
+                        (r.inst_num as u32).wrapping_sub(decode_signed(raw) as u32) as usize
+                    } else {
+                        raw as usize
+                    };
+                    p.text(if i == 0 { " [ " } else { ", [ " })
+                        .value(id)
+                        .text(", ");
+                    p.0.push(Piece::Block(record.op(2 + 2 * i) as usize));
+                    p.text(" ]");
+                }
+                result = Some(ty);
+            },
+            INST_LANDINGPAD | INST_LANDINGPAD_OLD => {
+                // [type, (personality,) is cleanup, n, n x (kind, value)]
+                let ty = r.next_op() as TypeId;
+                if record.code == INST_LANDINGPAD_OLD {
+                    let (personality, _) = r.value_type_pair(self)?;
+                    if function.personality.is_none() {
+                        function.personality = Some(personality);
+                    }
+                }
+                let is_cleanup = r.next_op() != 0;
+                let num_clauses = r.next_op();
+                p.text(format!("landingpad {}", self.types.text(ty)));
+                if is_cleanup {
+                    p.text(" cleanup");
+                }
+                for _ in 0 .. num_clauses {
+                    let kind = if r.next_op() == 0 { "catch" } else { "filter" };
+                    let (value, value_ty) = r.value_type_pair(self)?;
+                    p.text(format!(" {} {} ", kind, self.types.text(value_ty)))
+                        .value(value);
+                }
+                result = Some(ty);
+            },
+            INST_ALLOCA => {
+                // [instty, opty, size, packed alignment and flags, addrspace]
+                let ty = record.op(0) as TypeId;
+                let size_ty = record.op(1) as TypeId;
+                let size = record.op(2) as usize;
+                let packed = record.op(3);
+                let align = (packed & 0x1f) | (((packed >> 8) & 0x7) << 5);
+                let ty = if packed & (1 << 6) != 0 {
+                    ty
+                } else {
+                    self.types.pointee(ty).ok_or("Invalid alloca type")?
+                };
+                let addr_space = if record.ops.len() == 5 {
+                    record.op(4) as u32
+                } else {
+                    data_layout_addr_space(&self.data_layout, 'A')
+                };
+                p.text("alloca ");
+                if packed & (1 << 5) != 0 {
+                    p.text("inalloca ");
+                }
+                if packed & (1 << 7) != 0 {
+                    p.text("swifterror ");
+                }
+                p.text(format!(
+                    "{}, {} ",
+                    self.types.text(ty),
+                    self.types.text(size_ty)
+                ))
+                .value(size);
+                let align = decode_alignment(align)?;
+                if align != 0 {
+                    p.text(format!(", align {}", align));
+                }
+                if addr_space != 0 {
+                    p.text(format!(", addrspace({})", addr_space));
+                }
+                result = Some(self.types.pointer_to(ty, addr_space));
+            },
+            INST_LOAD | INST_LOADATOMIC => {
+                // [pointer, type, align, volatile(, ordering, syncscope)]
+                let (pointer, pointer_ty) = r.value_type_pair(self)?;
+                let trailing = if record.code == INST_LOAD { 2 } else { 4 };
+                let ty = if record.ops.len() == r.next + trailing + 1 {
+                    r.next_op() as TypeId
+                } else {
+                    self.types.pointee(pointer_ty).ok_or("Invalid load type")?
+                };
+                let align = decode_alignment(r.next_op())?;
+                let volatile = r.next_op() != 0;
+                p.text("load ");
+                if record.code == INST_LOADATOMIC {
+                    p.text("atomic ");
+                }
+                if volatile {
+                    p.text("volatile ");
+                }
+                p.text(format!(
+                    "{}, {} ",
+                    self.types.text(ty),
+                    self.types.text(pointer_ty)
+                ))
+                .value(pointer);
+                if record.code == INST_LOADATOMIC {
+                    let ordering = ordering_text(r.next_op())?;
+                    p.text(format!(
+                        "{} {}",
+                        self.sync_scope_text(r.next_op()),
+                        ordering
+                    ));
+                }
+                if align != 0 {
+                    p.text(format!(", align {}", align));
+                }
+                result = Some(ty);
+            },
+            INST_STORE | INST_STORE_OLD | INST_STOREATOMIC | INST_STOREATOMIC_OLD => {
+                // [pointer, value, align, volatile(, ordering, syncscope)]
+                let (pointer, pointer_ty) = r.value_type_pair(self)?;
+                let (value, ty) = if record.code == INST_STORE || record.code == INST_STOREATOMIC {
+                    r.value_type_pair(self)?
+                } else {
+                    let ty = self.types.pointee(pointer_ty).ok_or("Invalid store")?;
+                    (r.value()?, ty)
+                };
+                let align = decode_alignment(r.next_op())?;
+                let volatile = r.next_op() != 0;
+                let atomic = record.code == INST_STOREATOMIC || record.code == INST_STOREATOMIC_OLD;
+                p.text("store ");
+                if atomic {
+                    p.text("atomic ");
+                }
+                if volatile {
+                    p.text("volatile ");
+                }
+                p.text(format!("{} ", self.types.text(ty)))
+                    .value(value)
+                    .text(format!(", {} ", self.types.text(pointer_ty)))
+                    .value(pointer);
+                if atomic {
+                    let ordering = ordering_text(r.next_op())?;
+                    p.text(format!(
+                        "{} {}",
+                        self.sync_scope_text(r.next_op()),
+                        ordering
+                    ));
+                }
+                if align != 0 {
+                    p.text(format!(", align {}", align));
+                }
+            },
+            INST_CMPXCHG_OLD | INST_CMPXCHG => {
+                // [pointer, expected, replacement, volatile, success ordering,
+                //  syncscope, failure ordering, weak, align]
+                let (pointer, pointer_ty) = r.value_type_pair(self)?;
+                let (expected, ty) = if record.code == INST_CMPXCHG {
+                    r.value_type_pair(self)?
+                } else {
+                    let ty = self.types.pointee(pointer_ty).ok_or("Invalid cmpxchg")?;
+                    (r.value()?, ty)
+                };
+                let replacement = r.value()?;
+                let volatile = r.next_op() != 0;
+                let success = r.next_op();
+                let sync_scope = self.sync_scope_text(r.next_op());
+                let failure = if r.done() {
+                    strongest_failure_ordering(success)
+                } else {
+                    r.next_op()
+                };
+                let weak = !r.done() && r.next_op() != 0;
+                let align = if r.done() {
+                    0
+                } else {
+                    decode_alignment(r.next_op())?
+                };
+                p.text("cmpxchg ");
+                if weak {
+                    p.text("weak ");
+                }
+                if volatile {
+                    p.text("volatile ");
+                }
+                p.text(format!("{} ", self.types.text(pointer_ty)))
+                    .value(pointer)
+                    .text(format!(", {} ", self.types.text(ty)))
+                    .value(expected)
+                    .text(format!(", {} ", self.types.text(ty)))
+                    .value(replacement)
+                    .text(format!(
+                        "{} {} {}",
+                        sync_scope,
+                        ordering_text(success)?,
+                        ordering_text(failure)?
+                    ));
+                if align != 0 {
+                    p.text(format!(", align {}", align));
+                }
+                let i1 = self.types.int(1);
+                let pair = self.types.literal_struct(vec![ty, i1]);
+                if record.code == INST_CMPXCHG_OLD && record.ops.len() < 8 {
+                    // before weak cmpxchg, the instruction gave just the
+                    // loaded value, so extract that from the pair
+                    let hidden = function.local_names.len();
+                    function.local_names.push(None);
+                    function.instructions.push(Instruction {
+                        block: function.current_block,
+                        result: Some(hidden),
+                        pieces: p.0,
+                        debug_loc: None,
+                    });
+                    p = Pieces::default();
+                    p.text(format!("extractvalue {} ", self.types.text(pair)));
+                    p.0.push(Piece::Local(hidden));
+                    p.text(", 0");
+                    result = Some(ty);
+                } else {
+                    result = Some(pair);
+                }
+            },
+            INST_ATOMICRMW_OLD | INST_ATOMICRMW => {
+                // [pointer, value, operation, volatile, ordering, syncscope,
+                //  align]
+                let (pointer, pointer_ty) = r.value_type_pair(self)?;
+                let (value, ty) = if record.code == INST_ATOMICRMW {
+                    r.value_type_pair(self)?
+                } else {
+                    let ty = self.types.pointee(pointer_ty).ok_or("Invalid atomicrmw")?;
+                    (r.value()?, ty)
+                };
+                let operation = RMW_OPERATIONS
+                    .get(r.next_op() as usize)
+                    .ok_or("Invalid atomicrmw operation")?;
+                let volatile = r.next_op() != 0;
+                let ordering = ordering_text(r.next_op())?;
+                let sync_scope = self.sync_scope_text(r.next_op());
+                let align = if r.done() {
+                    0
+                } else {
+                    decode_alignment(r.next_op())?
+                };
+                p.text("atomicrmw ");
+                if volatile {
+                    p.text("volatile ");
+                }
+                p.text(format!("{} {} ", operation, self.types.text(pointer_ty)))
+                    .value(pointer)
+                    .text(format!(", {} ", self.types.text(ty)))
+                    .value(value)
+                    .text(format!("{} {}", sync_scope, ordering));
+                if align != 0 {
+                    p.text(format!(", align {}", align));
+                }
+                result = Some(ty);
+            },
+            INST_FENCE => {
+                // [ordering, syncscope]
+                let ordering = ordering_text(record.op(0))?;
+                p.text(format!(
+                    "fence{} {}",
+                    self.sync_scope_text(record.op(1)),
+                    ordering
+                ));
+            },
+            INST_VAARG => {
+                // [va_list type, va_list, result type]
+                let ty = r.next_op() as TypeId;
+                let list = r.value()?;
+                let result_ty = r.next_op() as TypeId;
+                p.text(format!("va_arg {} ", self.types.text(ty)))
+                    .value(list)
+                    .text(format!(", {}", self.types.text(result_ty)));
+                result = Some(result_ty);
+            },
+            INST_EXTRACTVAL | INST_INSERTVAL => {
+                // [aggregate, (value,) n x indices]
+                let (aggregate, ty) = r.value_type_pair(self)?;
+                let element = if record.code == INST_INSERTVAL {
+                    Some(r.value_type_pair(self)?)
+                } else {
+                    None
+                };
+                let mut element_ty = ty;
+                let mut indices = String::new();
+                while !r.done() {
+                    let index = r.next_op();
+                    element_ty = self
+                        .types
+                        .element(element_ty, index)
+                        .ok_or("Invalid aggregate index")?;
+                    indices.push_str(&format!(", {}", index));
+                }
+                match element {
+                    None => {
+                        p.text(format!("extractvalue {} ", self.types.text(ty)))
+                            .value(aggregate);
+                        result = Some(element_ty);
+                    },
+                    Some((value, value_ty)) => {
+                        p.text(format!("insertvalue {} ", self.types.text(ty)))
+                            .value(aggregate)
+                            .text(format!(", {} ", self.types.text(value_ty)))
+                            .value(value);
+                        result = Some(ty);
+                    },
+                }
+                p.text(indices);
+            },
+            INST_RESUME => {
+                is_terminator = true;
+                let (value, ty) = r.value_type_pair(self)?;
+                p.text(format!("resume {} ", self.types.text(ty)))
+                    .value(value);
+            },
+            INST_FREEZE => {
+                let (value, ty) = r.value_type_pair(self)?;
+                p.text(format!("freeze {} ", self.types.text(ty)))
+                    .value(value);
+                result = Some(ty);
+            },
+            INST_CLEANUPRET => {
+                // [cleanuppad, unwind block]
+                is_terminator = true;
+                let pad = r.value()?;
+                p.text("cleanupret from ").value(pad).text(" unwind ");
+                if r.done() {
+                    p.text("to caller");
+                } else {
+                    p.block(r.next_op());
+                }
+            },
+            INST_CATCHRET => {
+                // [catchpad, block]
+                is_terminator = true;
+                let pad = r.value()?;
+                p.text("catchret from ")
+                    .value(pad)
+                    .text(" to ")
+                    .block(r.next_op());
+            },
+            INST_CATCHSWITCH => {
+                // [parent pad, n, n x handlers, unwind block]
+                is_terminator = true;
+                let parent = r.value()?;
+                let num_handlers = r.next_op();
+                p.text("catchswitch within ").value(parent).text(" [");
+                for i in 0 .. num_handlers {
+                    if r.done() {
+                        return Err("Invalid catchswitch record".into());
+                    }
+                    if i > 0 {
+                        p.text(", ");
+                    }
+                    p.block(r.next_op());
+                }
+                p.text("] unwind ");
+                if r.done() {
+                    p.text("to caller");
+                } else {
+                    p.block(r.next_op());
+                }
+                result = Some(self.types.token());
+            },
+            INST_CATCHPAD | INST_CLEANUPPAD => {
+                // [parent pad, n, n x arguments]
+                let parent = r.value()?;
+                let num_args = r.next_op();
+                p.text(if record.code == INST_CATCHPAD {
+                    "catchpad within "
+                } else {
+                    "cleanuppad within "
+                })
+                .value(parent)
+                .text(" [");
+                for i in 0 .. num_args {
+                    if i > 0 {
+                        p.text(", ");
+                    }
+                    let (arg, ty) = r.value_type_pair(self)?;
+                    p.text(format!("{} ", self.types.text(ty))).value(arg);
+                }
+                p.text("]");
+                result = Some(self.types.token());
+            },
+            _ => return Ok(()),
+        }
+
+        if function.current_block >= function.block_names.len() {
+            return Err("Instruction outside of any basic block".into());
+        }
+        let result = match result {
+            Some(ty) if !self.types.is_void(ty) => Some(self.new_local(function, ty)),
+            _ => None,
+        };
+        function.instructions.push(Instruction {
+            block: function.current_block,
+            result,
+            pieces: p.0,
+            debug_loc: None,
+        });
+        if is_terminator {
+            function.current_block += 1;
+        }
+        Ok(())
+    }
+
+    /// Whether the call with the given record (whose callee is next in `r`)
+    /// calls a debug-info intrinsic
+    fn calls_debug_intrinsic(&self, record: &Record, r: &Operands) -> bool {
+        let callee = r.peek_value(record);
+        match self.values.get(callee).map(|v| &v.kind) {
+            Some(ValueKind::Global(global)) => self.globals[*global].name.starts_with("llvm.dbg."),
+            _ => false,
+        }
+    }
+
+    /// Add the part of a call, invoke, or callbr from the calling convention
+    /// to the operand bundles, giving the type of the result
+    fn call_text(
+        &mut self,
+        function: &mut Function,
+        p: &mut Pieces,
+        r: &mut Operands,
+        attrs: &AttributeList,
+        cc: u64,
+        explicit_type: Option<TypeId>,
+    ) -> Result<Option<TypeId>, String> {
+        let (callee, callee_ty) = r.value_type_pair(self)?;
+        let fn_ty = match explicit_type {
+            Some(ty) => ty,
+            None => self
+                .types
+                .pointee(callee_ty)
+                .ok_or("Callee is not a function pointer")?,
+        };
+        let result = self.types.result(fn_ty).ok_or("Invalid function type")?;
+        if cc != 0 {
+            p.text(format!(" cc {}", cc));
+        }
+        p.text(attributes::attrs_text(
+            &attrs.ret,
+            &self.types,
+            Some(result),
+        ));
+        let addr_space = self.types.addr_space(callee_ty);
+        if addr_space != 0 {
+            p.text(format!(" addrspace({})", addr_space));
+        }
+        p.text(format!(" {} ", self.types.text(fn_ty)))
+            .value(callee)
+            .text("(");
+        let params = self.types.params(fn_ty);
+        let mut num_args = 0;
+        for (i, &ty) in params.iter().enumerate() {
+            if i > 0 {
+                p.text(", ");
+            }
+            let attrs = attributes::attrs_text(attrs.param(i), &self.types, Some(ty));
+            p.text(format!("{}{} ", self.types.text(ty), attrs));
+            if self.types.is_metadata(ty) {
+                let id = r.raw_value();
+                let reference = self
+                    .metadata
+                    .reference(id as u64 + 1)?
+                    .unwrap_or_else(|| "!{}".into());
+                p.text(reference);
+            } else if self.types.is_label(ty) {
+                p.0.push(Piece::Block(r.next_op() as usize));
+            } else {
+                let value = r.value()?;
+                p.value(value);
+            }
+            num_args += 1;
+        }
+        if let Ty::Function { var_arg: true, .. } = self.types.get(fn_ty)? {
+            while !r.done() {
+                if num_args > 0 {
+                    p.text(", ");
+                }
+                let (value, ty) = r.value_type_pair(self)?;
+                let attrs = attributes::attrs_text(attrs.param(num_args), &self.types, Some(ty));
+                p.text(format!("{}{} ", self.types.text(ty), attrs))
+                    .value(value);
+                num_args += 1;
+            }
+        }
+        p.text(")");
+        p.text(attributes::attrs_text(&attrs.function, &self.types, None));
+        if !function.bundles.is_empty() {
+            p.text(" [ ");
+            for (i, bundle) in function.bundles.drain(..).enumerate() {
+                if i > 0 {
+                    p.text(", ");
+                }
+                p.0.extend(bundle.0);
+            }
+            p.text(" ]");
+        }
+        Ok(Some(result))
+    }
+
+    fn switch_text(&mut self, p: &mut Pieces, r: &mut Operands) -> Result<(), String> {
+        let record = r.record;
+        if record.op(0) >> 16 == SWITCH_INST_MAGIC {
+            // [magic, type, condition, default, n, n x (n items, n x
+            //  (is single, low(, high)), block)]
+            let ty = record.op(1) as TypeId;
+            let width = self.types.int_width(ty).unwrap_or(64);
+            r.next = 2;
+            let condition = r.value()?;
+            let default = record.op(3);
+            let num_cases = record.op(4);
+            p.text(format!("switch {} ", self.types.text(ty)))
+                .value(condition)
+                .text(", ")
+                .block(default)
+                .text(" [");
+            let mut i = 5;
+            let read_int = |i: &mut usize| {
+                let mut words = 1;
+                if width > 64 {
+                    words = record.op(*i) as usize;
+                    *i += 1;
+                }
+                let low = decode_signed(record.op(*i));
+                *i = i.saturating_add(words);
+                low
+            };
+            for _ in 0 .. num_cases {
+                if i >= record.ops.len() {
+                    return Err("Invalid switch record".into());
+                }
+                let num_items = record.op(i);
+                i += 1;
+                let mut values = vec![];
+                for _ in 0 .. num_items {
+                    if i >= record.ops.len() {
+                        return Err("Invalid switch record".into());
+                    }
+                    let is_single = record.op(i) != 0;
+                    i += 1;
+                    let low = read_int(&mut i);
+                    if is_single {
+                        values.push(low);
+                    } else {
+                        let high = read_int(&mut i);
+                        match high.checked_sub(low) {
+                            Some(len) if (0 .. MAX_CASE_RANGE).contains(&len) => {
+                                values.extend(low ..= high)
+                            },
+                            _ => return Err(format!("Invalid case range {} to {}", low, high)),
+                        }
+                    }
+                }
+                let dest = record.op(i);
+                i += 1;
+                for value in values {
+                    p.text(format!(" {} {}, ", self.types.text(ty), value))
+                        .block(dest);
+                }
+            }
+            p.text(" ]");
+        } else {
+            // [type, condition, default, n x (value, block)], where the case
+            // values are absolute value IDs
+            let ty = record.op(0) as TypeId;
+            r.next = 1;
+            let condition = r.value()?;
+            p.text(format!("switch {} ", self.types.text(ty)))
+                .value(condition)
+                .text(", ")
+                .block(record.op(2))
+                .text(" [");
+            for case in record.ops.get(3 ..).unwrap_or(&[]).chunks(2) {
+                p.text(format!(" {} ", self.types.text(ty)))
+                    .value(case[0] as usize)
+                    .text(", ")
+                    .block(case.get(1).copied().unwrap_or(0));
+            }
+            p.text(" ]");
+        }
+        Ok(())
+    }
+
+    /// The type of the result of a `getelementptr` with the given source
+    /// element type and operands
+    fn gep_result_type(
+        &mut self,
+        source: TypeId,
+        operands: &[(usize, TypeId)],
+    ) -> Result<TypeId, String> {
+        let mut ty = source;
+        for &(id, _) in operands.iter().skip(2) {
+            let index = match self.values.get(id).map(|v| &v.kind) {
+                Some(ValueKind::Constant(..)) => self.constant_text(id)?.parse().unwrap_or(0),
+                _ => 0,
+            };
+            ty = self
+                .types
+                .element(ty, index)
+                .ok_or("Invalid getelementptr index")?;
+        }
+        let base_ty = operands[0].1;
+        let pointer = self.types.pointer_to(ty, self.types.addr_space(base_ty));
+        let shape = operands
+            .iter()
+            .map(|&(_, ty)| ty)
+            .find(|&ty| self.types.is_vector(ty));
+        Ok(match shape {
+            Some(shape) => self.types.like_vector(shape, pointer),
+            None => pointer,
+        })
+    }
+}
+
+/// Reads the operands of an instruction record in order
+struct Operands<'r> {
+    record: &'r Record,
+    next: usize,
+    /// Whether value IDs are relative to the ID of the instruction
+    relative: bool,
+    /// The value ID the instruction's result would get
+    inst_num: usize,
+}
+
+impl Operands<'_> {
+    fn done(&self) -> bool {
+        self.next >= self.record.ops.len()
+    }
+
+    fn next_op(&mut self) -> u64 {
+        self.next += 1;
+        self.record.op(self.next - 1)
+    }
+
+    fn absolute(&self, raw: u64) -> usize {
+        if self.relative {
+            (self.inst_num as u32).wrapping_sub(raw as u32) as usize
+        } else {
+            raw as usize
+        }
+    }
+
+    /// The ID of the next value, whose type is known from context
+    fn value(&mut self) -> Result<usize, String> {
+        if self.done() {
+            return Err("Missing operand".into());
+        }
+        let raw = self.next_op();
+        Ok(self.absolute(raw))
+    }
+
+    /// The raw ID of the next value, as used for metadata arguments
+    fn raw_value(&mut self) -> usize {
+        let raw = self.next_op();
+        self.absolute(raw)
+    }
+
+    fn peek_value(&self, record: &Record) -> usize {
+        self.absolute(record.op(self.next))
+    }
+
+    /// The ID and type of the next value, which is followed by its type if
+    /// it's a forward reference
+    fn value_type_pair(&mut self, reader: &Reader) -> Result<(usize, TypeId), String> {
+        let id = self.value()?;
+        if id < self.inst_num {
+            Ok((id, reader.value(id)?.ty))
+        } else {
+            Ok((id, self.next_op() as TypeId))
+        }
+    }
+}
+
+/// The fast-math flags encoded in bitcode, each followed by a space
+fn fast_math_flags(flags: u64) -> String {
+    // bit 0 is the old "unsafe algebra", which implied all of them
+    let flags = if flags & 1 != 0 { 0xfe } else { flags };
+    if flags & 0xfe == 0xfe {
+        return "fast ".into();
+    }
+    let mut text = String::new();
+    for (bit, name) in &[
+        (7, "reassoc"),
+        (1, "nnan"),
+        (2, "ninf"),
+        (3, "nsz"),
+        (4, "arcp"),
+        (5, "contract"),
+        (6, "afn"),
+    ] {
+        if flags & (1 << bit) != 0 {
+            text.push_str(name);
+            text.push(' ');
+        }
+    }
+    text
+}
+
+fn ordering_text(ordering: u64) -> Result<&'static str, String> {
+    match ordering {
+        1 => Ok("unordered"),
+        2 => Ok("monotonic"),
+        3 => Ok("acquire"),
+        4 => Ok("release"),
+        5 => Ok("acq_rel"),
+        6 => Ok("seq_cst"),
+        _ => Err(format!("Invalid atomic ordering {}", ordering)),
+    }
+}
+
+/// The failure ordering of old `cmpxchg`s, which only had one ordering
+fn strongest_failure_ordering(success: u64) -> u64 {
+    match success {
+        // acq_rel -> acquire, release -> monotonic
+        5 => 3,
+        4 => 2,
+        ordering => ordering,
+    }
+}
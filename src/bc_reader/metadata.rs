@@ -0,0 +1,1176 @@
+//! Metadata (`METADATA_BLOCK_ID` and friends). Nodes are printed as textual
+//! IR, so that the parser can find debug locations and attached metadata in
+//! them; see the module docs for which kinds of nodes are printed in full.
+
+use super::bitstream::{Block, Cursor, Record};
+use super::types::TypeId;
+use super::{escape_bytes, GlobalKind, Reader, Value, ValueKind};
+use std::collections::{HashMap, HashSet};
+
+// METADATA_*
+const STRING_OLD: u32 = 1;
+const VALUE: u32 = 2;
+const NODE: u32 = 3;
+const NAME: u32 = 4;
+const DISTINCT_NODE: u32 = 5;
+const KIND: u32 = 6;
+const LOCATION: u32 = 7;
+const OLD_NODE: u32 = 8;
+const OLD_FN_NODE: u32 = 9;
+const NAMED_NODE: u32 = 10;
+const ATTACHMENT: u32 = 11;
+const GENERIC_DEBUG: u32 = 12;
+const FILE: u32 = 16;
+const COMPILE_UNIT: u32 = 20;
+const SUBPROGRAM: u32 = 21;
+const LEXICAL_BLOCK: u32 = 22;
+const LEXICAL_BLOCK_FILE: u32 = 23;
+const GLOBAL_VAR: u32 = 27;
+const EXPRESSION: u32 = 29;
+const STRINGS: u32 = 35;
+const GLOBAL_DECL_ATTACHMENT: u32 = 36;
+const GLOBAL_VAR_EXPR: u32 = 37;
+const ARG_LIST: u32 = 46;
+const ASSIGN_ID: u32 = 47;
+
+/// The current version of debug info, as given by the "Debug Info Version"
+/// module flag. LLVM drops debug info of any other version.
+const DEBUG_METADATA_VERSION: &str = "i32 3";
+
+/// The name of the specialized node which each record code from
+/// `GENERIC_DEBUG` on describes
+fn specialized_node_kind(code: u32) -> Option<&'static str> {
+    Some(match code {
+        GENERIC_DEBUG => "GenericDINode",
+        13 => "DISubrange",
+        14 => "DIEnumerator",
+        15 => "DIBasicType",
+        16 => "DIFile",
+        17 => "DIDerivedType",
+        18 => "DICompositeType",
+        19 => "DISubroutineType",
+        20 => "DICompileUnit",
+        21 => "DISubprogram",
+        22 => "DILexicalBlock",
+        23 => "DILexicalBlockFile",
+        24 => "DINamespace",
+        25 => "DITemplateTypeParameter",
+        26 => "DITemplateValueParameter",
+        27 => "DIGlobalVariable",
+        28 => "DILocalVariable",
+        29 => "DIExpression",
+        30 => "DIObjCProperty",
+        31 => "DIImportedEntity",
+        32 => "DIModule",
+        33 => "DIMacro",
+        34 => "DIMacroFile",
+        37 => "DIGlobalVariableExpression",
+        40 => "DILabel",
+        41 => "DIStringType",
+        44 => "DICommonBlock",
+        45 => "DIGenericSubrange",
+        46 => "DIArgList",
+        47 => "DIAssignID",
+        _ => return None,
+    })
+}
+
+pub(super) struct Metadata {
+    /// The name of each metadata kind, by the ID the bitcode gives it
+    kinds: HashMap<u64, String>,
+    /// How each metadata ID is referred to: `!N` for a node, `!"foo"` for a
+    /// string, `i32 7` for a value, or the whole text of a `DIExpression` or
+    /// `DIArgList`, which are printed inline
+    refs: Vec<String>,
+    /// The value each metadata ID which wraps a value wraps
+    value_ids: HashMap<usize, usize>,
+    /// The operands of the tuple nodes, by metadata ID
+    tuples: HashMap<usize, Vec<String>>,
+    /// The number of metadata IDs of the module, after which the current
+    /// function's IDs start
+    num_module_refs: usize,
+    /// The number of nodes numbered in the text so far
+    num_nodes: usize,
+    /// The definitions of the nodes, by number
+    definitions: Vec<(usize, String)>,
+    /// The metadata IDs of the operands of `!llvm.module.flags`
+    module_flags: Vec<usize>,
+    /// The attachments of global values (by index in `Reader::globals`), as
+    /// kind IDs and node references
+    global_attachments: HashMap<usize, Vec<(u64, String)>>,
+    /// Subprograms of functions (by value ID) from old bitcode, where they
+    /// were attached the other way around
+    pub(super) function_subprograms: HashMap<usize, String>,
+    /// The node numbers of `DIGlobalVariable`s from old bitcode (by ID),
+    /// whose IDs may refer to expressions made for them instead
+    upgraded_variables: HashMap<usize, usize>,
+    /// References to all `DIGlobalVariable`s
+    variables: HashSet<String>,
+    /// The compile units of subprograms (by ID) which old bitcode listed in
+    /// the compile units rather than giving them a `unit`
+    units: HashMap<usize, String>,
+    /// The numbers of the `DILocation`s created for instructions' debug
+    /// locations
+    locations: HashMap<(u64, u64, String, Option<String>, bool), usize>,
+    /// Whether debug info is dropped since it's of an outdated version
+    pub(super) strip_debug_info: bool,
+}
+
+impl Metadata {
+    pub(super) fn new() -> Self {
+        Self {
+            kinds: HashMap::new(),
+            refs: vec![],
+            value_ids: HashMap::new(),
+            tuples: HashMap::new(),
+            num_module_refs: 0,
+            num_nodes: 0,
+            definitions: vec![],
+            module_flags: vec![],
+            global_attachments: HashMap::new(),
+            function_subprograms: HashMap::new(),
+            upgraded_variables: HashMap::new(),
+            variables: HashSet::new(),
+            units: HashMap::new(),
+            locations: HashMap::new(),
+            strip_debug_info: false,
+        }
+    }
+
+    /// Read the metadata kinds of a `METADATA_KIND_BLOCK_ID` block
+    pub(super) fn read_kinds(&mut self, block: &Block) {
+        for record in block.records() {
+            if record.code == KIND {
+                self.kinds.insert(record.op(0), record.string(1));
+            }
+        }
+    }
+
+    /// The names of the metadata kinds, in the order of their IDs
+    pub(super) fn kind_names(&self) -> Vec<String> {
+        let mut kinds: Vec<(&u64, &String)> = self.kinds.iter().collect();
+        kinds.sort();
+        kinds.into_iter().map(|(_, name)| name.clone()).collect()
+    }
+
+    fn kind_name(&self, kind: u64) -> Result<&str, String> {
+        self.kinds
+            .get(&kind)
+            .map(|name| name.as_str())
+            .ok_or_else(|| format!("Invalid metadata kind {}", kind))
+    }
+
+    /// The reference to the metadata with the given ID
+    fn get(&self, id: u64) -> Result<&str, String> {
+        self.refs
+            .get(id as usize)
+            .map(|r| r.as_str())
+            .ok_or_else(|| format!("Invalid metadata ID {}", id))
+    }
+
+    /// The reference to the metadata with the given ID plus one, or `None`
+    /// for 0
+    fn get_or_null(&self, id: u64) -> Result<Option<&str>, String> {
+        match id {
+            0 => Ok(None),
+            id => self.get(id - 1).map(Some),
+        }
+    }
+
+    fn new_node(&mut self) -> usize {
+        self.num_nodes += 1;
+        self.num_nodes - 1
+    }
+
+    fn define(&mut self, node: usize, text: String) {
+        self.definitions.push((node, text));
+    }
+
+    /// The definitions of all metadata nodes
+    pub(super) fn definitions(&mut self) -> String {
+        self.definitions.sort_by_key(|(node, _)| *node);
+        self.definitions
+            .iter()
+            .map(|(node, text)| format!("!{} = {}\n", node, text))
+            .collect()
+    }
+
+    /// Finish reading the module-level metadata, deciding whether debug info
+    /// must be dropped
+    pub(super) fn finish_module(&mut self) {
+        self.num_module_refs = self.refs.len();
+        let version_ok = self.module_flags.iter().any(|id| {
+            matches!(self.tuples.get(id), Some(flag) if flag.len() == 3
+                && flag[1] == "!\"Debug Info Version\""
+                && flag[2] == DEBUG_METADATA_VERSION)
+        });
+        self.strip_debug_info = !version_ok;
+        if !self.upgraded_variables.is_empty() {
+            // with old bitcode, LLVM wraps variables attached to globals in
+            // expressions. (It also upgrades the compile units' lists of
+            // variables, which we don't print.)
+            let mut globals: Vec<usize> = self.global_attachments.keys().copied().collect();
+            globals.sort_unstable();
+            for global in globals {
+                for i in 0 .. self.global_attachments[&global].len() {
+                    let (kind, node) = &self.global_attachments[&global][i];
+                    if !self.is_dbg(*kind) || !self.variables.contains(node) {
+                        continue;
+                    }
+                    let text = format!(
+                        "distinct !DIGlobalVariableExpression(var: {}, expr: !DIExpression())",
+                        node
+                    );
+                    let expression = self.new_node();
+                    self.define(expression, text);
+                    if let Some(attachments) = self.global_attachments.get_mut(&global) {
+                        attachments[i].1 = format!("!{}", expression);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Forget the metadata of the function just read
+    pub(super) fn finish_function(&mut self) {
+        self.refs.truncate(self.num_module_refs);
+    }
+
+    /// The attachments of a global variable or function declaration, e.g.
+    /// `!dbg !7`
+    pub(super) fn global_attachments(&self, global: usize) -> Vec<String> {
+        self.global_attachments
+            .get(&global)
+            .map(|attachments| {
+                attachments
+                    .iter()
+                    .filter(|(kind, _)| !(self.strip_debug_info && self.is_dbg(*kind)))
+                    .filter_map(|(kind, node)| {
+                        Some(format!("!{} {}", self.kind_name(*kind).ok()?, node))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    fn is_dbg(&self, kind: u64) -> bool {
+        self.kinds.get(&kind).map(String::as_str) == Some("dbg")
+    }
+
+    /// The reference to the `DILocation` with the given fields, creating it
+    /// if necessary
+    pub(super) fn location(
+        &mut self,
+        line: u64,
+        column: u64,
+        scope: &str,
+        inlined_at: Option<&str>,
+        implicit: bool,
+    ) -> String {
+        let key = (
+            line,
+            column,
+            scope.to_owned(),
+            inlined_at.map(|i| i.to_owned()),
+            implicit,
+        );
+        if let Some(node) = self.locations.get(&key) {
+            return format!("!{}", node);
+        }
+        let node = self.new_node();
+        self.define(
+            node,
+            location_text(false, line, column, scope, inlined_at, implicit),
+        );
+        self.locations.insert(key, node);
+        format!("!{}", node)
+    }
+
+    /// The reference to the metadata with the given ID plus one (as in
+    /// `DEBUG_LOC` records), or `None` for 0
+    pub(super) fn reference(&self, id: u64) -> Result<Option<String>, String> {
+        Ok(self.get_or_null(id)?.map(|r| r.to_owned()))
+    }
+}
+
+impl<'a> Reader<'a> {
+    /// Read a metadata block of the module or of the current function
+    pub(super) fn read_metadata(&mut self, block: &'a Block) -> Result<(), String> {
+        // the records may refer to metadata defined later in the block, so
+        // first assign a reference to every ID, then print the nodes
+        let mut pending = vec![];
+        let mut pending_name = None;
+        for record in block.records() {
+            let id = self.metadata.refs.len();
+            let reference = match record.code {
+                STRING_OLD => format!("!\"{}\"", escape_bytes(&record.bytes(0))),
+                STRINGS => {
+                    self.read_strings(record)?;
+                    continue;
+                },
+                VALUE => self.value_reference(id, record.op(0) as TypeId, record.op(1) as usize)?,
+                OLD_FN_NODE
+                    if record.ops.len() == 2 && !self.types.is_metadata(record.op(0) as TypeId) =>
+                {
+                    self.value_reference(id, record.op(0) as TypeId, record.op(1) as usize)?
+                },
+                NAME => {
+                    pending_name = Some(record.string(0));
+                    continue;
+                },
+                NAMED_NODE => {
+                    if pending_name.take().as_deref() == Some("llvm.module.flags") {
+                        self.metadata.module_flags =
+                            record.ops.iter().map(|&id| id as usize).collect();
+                    }
+                    continue;
+                },
+                KIND => {
+                    self.metadata.kinds.insert(record.op(0), record.string(1));
+                    continue;
+                },
+                GLOBAL_DECL_ATTACHMENT => {
+                    pending.push((record, usize::MAX));
+                    continue;
+                },
+                // printed inline, but may refer to later values
+                EXPRESSION | ARG_LIST => String::new(),
+                GLOBAL_VAR if record.op(0) >> 1 == 0 => {
+                    let node = self.metadata.new_node();
+                    self.metadata.upgraded_variables.insert(id, node);
+                    format!("!{}", node)
+                },
+                NODE | DISTINCT_NODE | LOCATION | OLD_NODE | OLD_FN_NODE => {
+                    format!("!{}", self.metadata.new_node())
+                },
+                code if specialized_node_kind(code).is_some() => {
+                    format!("!{}", self.metadata.new_node())
+                },
+                _ => continue,
+            };
+            self.metadata.refs.push(reference);
+            pending.push((record, id));
+        }
+        // `DIExpression`s and `DIArgList`s only refer to values, so their
+        // text can be filled in before the nodes which refer to them
+        for &(record, id) in &pending {
+            match record.code {
+                EXPRESSION => self.metadata.refs[id] = expression_text(record),
+                ARG_LIST => {
+                    let args = record
+                        .ops
+                        .iter()
+                        .map(|&arg| self.metadata.get(arg).map(|r| r.to_owned()))
+                        .collect::<Result<Vec<_>, _>>()?;
+                    self.metadata.refs[id] = format!("!DIArgList({})", args.join(", "));
+                },
+                _ => {},
+            }
+        }
+        for &(record, id) in &pending {
+            if self.metadata.upgraded_variables.contains_key(&id) {
+                self.upgrade_global_variable(record, id)?;
+            }
+        }
+        self.upgrade_compile_unit_subprograms(&pending);
+        for (record, id) in pending {
+            self.print_metadata(record, id)?;
+        }
+        Ok(())
+    }
+
+    /// Read a `STRINGS` record: [count, offset] with a blob holding the
+    /// lengths of the strings (as VBR6s), and then, from `offset` on, the
+    /// characters
+    fn read_strings(&mut self, record: &Record) -> Result<(), String> {
+        let blob = record.blob.as_deref().ok_or("Invalid strings record")?;
+        let (count, offset) = (record.op(0) as usize, record.op(1) as usize);
+        let mut lengths = Cursor::new(blob);
+        let mut start = offset;
+        for _ in 0 .. count {
+            let len = lengths.read_vbr(6)? as usize;
+            let bytes = start
+                .checked_add(len)
+                .and_then(|end| blob.get(start .. end))
+                .ok_or("Invalid strings record")?;
+            self.metadata
+                .refs
+                .push(format!("!\"{}\"", escape_bytes(bytes)));
+            start += len;
+        }
+        Ok(())
+    }
+
+    fn value_reference(
+        &mut self,
+        md_id: usize,
+        ty: TypeId,
+        value: usize,
+    ) -> Result<String, String> {
+        self.metadata.value_ids.insert(md_id, value);
+        self.value_as_metadata(ty, value)
+    }
+
+    /// The text of a value wrapped as metadata. Function-local values (which
+    /// may not have been read yet) become `undef`, as the parser doesn't keep
+    /// them anyway.
+    fn value_as_metadata(&self, ty: TypeId, value: usize) -> Result<String, String> {
+        let text = match self.values.get(value).map(|v| &v.kind) {
+            None | Some(ValueKind::Local(_)) => "undef".into(),
+            Some(_) => self.constant_text(value)?,
+        };
+        Ok(format!("{} {}", self.types.text(ty), text))
+    }
+
+    /// Upgrade a `DIGlobalVariable` from old bitcode, which refers to the
+    /// global variable it describes, to its constant value, or to an
+    /// expression, rather than being referred to by a
+    /// `DIGlobalVariableExpression`. Its ID refers to the expression made for
+    /// it, if any, unless that's attached to the global variable.
+    fn upgrade_global_variable(&mut self, record: &Record, id: usize) -> Result<(), String> {
+        let variable = self.metadata.upgraded_variables[&id];
+        let expression_id = match record.op(9).checked_sub(1) {
+            Some(expression_id) => expression_id as usize,
+            None => return Ok(()),
+        };
+        let value = self.metadata.value_ids.get(&expression_id).copied();
+        let value = match value {
+            Some(v) => Some((v, self.value(v)?)),
+            None => None,
+        };
+        let (expression, attach_to) = match value {
+            Some((
+                _,
+                Value {
+                    kind: ValueKind::Global(global),
+                    ..
+                },
+            )) if self.globals[*global].kind == GlobalKind::Variable => {
+                ("!DIExpression()".to_owned(), Some(*global))
+            },
+            Some((
+                v,
+                Value {
+                    kind: ValueKind::Constant(..),
+                    ty,
+                },
+            )) if self.types.int_width(*ty).is_some() => {
+                let bits = self.types.int_width(*ty).unwrap_or(64);
+                let text = self.constant_text(v)?;
+                let value = match text.as_str() {
+                    "true" => 1,
+                    "false" => 0,
+                    text => text.parse::<i64>().unwrap_or(0) as u64,
+                };
+                let value = if bits < 64 {
+                    value & ((1 << bits) - 1)
+                } else {
+                    value
+                };
+                (
+                    format!("!DIExpression(DW_OP_constu, {}, DW_OP_stack_value)", value),
+                    None,
+                )
+            },
+            Some(_) => return Ok(()),
+            None => (self.metadata.get(expression_id as u64)?.to_owned(), None),
+        };
+        let node = self.metadata.new_node();
+        self.metadata.define(
+            node,
+            format!(
+                "distinct !DIGlobalVariableExpression(var: !{}, expr: {})",
+                variable, expression
+            ),
+        );
+        match attach_to {
+            Some(global) => self
+                .metadata
+                .global_attachments
+                .entry(global)
+                .or_default()
+                .push((0, format!("!{}", node))),
+            None => self.metadata.refs[id] = format!("!{}", node),
+        }
+        Ok(())
+    }
+
+    /// Print the node defined by the given record
+    fn print_metadata(&mut self, record: &Record, id: usize) -> Result<(), String> {
+        let md = &self.metadata;
+        let op = |i: usize| record.op(i);
+        let distinct = op(0) & 1 != 0;
+        let text = match record.code {
+            NODE | DISTINCT_NODE => {
+                let operands = record
+                    .ops
+                    .iter()
+                    .map(|&op| Ok(md.get_or_null(op)?.unwrap_or("null").to_owned()))
+                    .collect::<Result<Vec<_>, String>>()?;
+                let text = format!(
+                    "{}!{{{}}}",
+                    if record.code == DISTINCT_NODE {
+                        "distinct "
+                    } else {
+                        ""
+                    },
+                    operands.join(", ")
+                );
+                self.metadata.tuples.insert(id, operands);
+                text
+            },
+            OLD_NODE | OLD_FN_NODE => {
+                // [n x [type, value]]
+                let mut operands = vec![];
+                for pair in record.ops.chunks(2) {
+                    let (ty, value) = (pair[0] as TypeId, pair.get(1).copied().unwrap_or(0));
+                    operands.push(if self.types.is_metadata(ty) {
+                        md.get(value)?.to_owned()
+                    } else if self.types.is_void(ty) {
+                        "null".to_owned()
+                    } else {
+                        self.value_as_metadata(ty, value as usize)?
+                    });
+                }
+                let text = format!("!{{{}}}", operands.join(", "));
+                self.metadata.tuples.insert(id, operands);
+                text
+            },
+            LOCATION => {
+                // [distinct, line, column, scope, inlinedAt + 1, isImplicit]
+                location_text(
+                    distinct,
+                    op(1),
+                    op(2),
+                    md.get(op(3))?,
+                    md.get_or_null(op(4))?,
+                    op(5) != 0,
+                )
+            },
+            FILE => {
+                // [distinct, filename, directory, checksum kind, checksum,
+                //  source]
+                let mut fields = Fields::new(md);
+                fields.string("filename", op(1), false)?;
+                fields.string("directory", op(2), false)?;
+                if record.ops.len() > 4 && op(3) != 0 && op(4) != 0 {
+                    let kind = match op(3) {
+                        1 => "CSK_MD5",
+                        2 => "CSK_SHA1",
+                        _ => "CSK_SHA256",
+                    };
+                    fields.raw("checksumkind", kind.into());
+                    fields.string("checksum", op(4), false)?;
+                }
+                if record.ops.len() > 5 && op(5) != 0 {
+                    fields.string("source", op(5), false)?;
+                }
+                fields.finish(distinct, "DIFile")
+            },
+            LEXICAL_BLOCK => {
+                // [distinct, scope, file, line, column]
+                let mut fields = Fields::new(md);
+                fields.node("scope", op(1), false)?;
+                fields.node("file", op(2), true)?;
+                fields.int("line", op(3) as i64, true);
+                fields.int("column", op(4) as i64, true);
+                fields.finish(distinct, "DILexicalBlock")
+            },
+            LEXICAL_BLOCK_FILE => {
+                // [distinct, scope, file, discriminator]
+                let mut fields = Fields::new(md);
+                fields.node("scope", op(1), false)?;
+                fields.node("file", op(2), true)?;
+                fields.int("discriminator", op(3) as i64, false);
+                fields.finish(distinct, "DILexicalBlockFile")
+            },
+            SUBPROGRAM => self.subprogram_text(record, id)?,
+            GLOBAL_VAR => {
+                // [distinct | version << 1, scope, name, linkageName, file,
+                //  line, type, isLocal, isDefinition, ...]
+                let mut fields = Fields::new(md);
+                fields.string("name", op(2), true)?;
+                fields.string("linkageName", op(3), true)?;
+                fields.node("scope", op(1), false)?;
+                fields.node("file", op(4), true)?;
+                fields.int("line", op(5) as i64, true);
+                fields.node("type", op(6), true)?;
+                fields.raw("isLocal", (op(7) != 0).to_string());
+                fields.raw("isDefinition", (op(8) != 0).to_string());
+                let text = fields.finish(distinct, "DIGlobalVariable");
+                if let Some(&node) = self.metadata.upgraded_variables.get(&id) {
+                    self.metadata.variables.insert(format!("!{}", node));
+                    self.metadata.define(node, text);
+                    return Ok(());
+                }
+                self.metadata
+                    .variables
+                    .insert(self.metadata.refs[id].clone());
+                text
+            },
+            GLOBAL_VAR_EXPR => {
+                // [distinct, var, expr]
+                let mut fields = Fields::new(md);
+                fields.node("var", op(1), false)?;
+                fields.node("expr", op(2), false)?;
+                fields.finish(distinct, "DIGlobalVariableExpression")
+            },
+            GLOBAL_DECL_ATTACHMENT => {
+                // [value ID, n x [kind, node]]
+                let global = match self.value(op(0) as usize)?.kind {
+                    ValueKind::Global(global) => global,
+                    _ => return Err("Metadata attachment on a non-global value".into()),
+                };
+                let attachments = record
+                    .ops
+                    .get(1 ..)
+                    .unwrap_or(&[])
+                    .chunks(2)
+                    .map(|pair| {
+                        Ok((
+                            pair[0],
+                            md.get(pair.get(1).copied().unwrap_or(0))?.to_owned(),
+                        ))
+                    })
+                    .collect::<Result<Vec<_>, String>>()?;
+                self.metadata
+                    .global_attachments
+                    .entry(global)
+                    .or_default()
+                    .extend(attachments);
+                return Ok(());
+            },
+            ASSIGN_ID => "distinct !DIAssignID()".into(),
+            code => match specialized_node_kind(code) {
+                Some(kind) if code != EXPRESSION && code != ARG_LIST => {
+                    format!("{}!{}()", if distinct { "distinct " } else { "" }, kind)
+                },
+                _ => return Ok(()),
+            },
+        };
+        let node = match self.metadata.refs[id].strip_prefix('!') {
+            Some(node) => node.parse().map_err(|_| "Invalid metadata node")?,
+            None => return Ok(()),
+        };
+        if record.code == SUBPROGRAM {
+            self.upgrade_subprogram(record, id);
+        }
+        self.metadata.define(node, text);
+        Ok(())
+    }
+
+    /// In old bitcode, subprograms refer to their functions. Record the
+    /// subprogram of the function, to attach it to the function instead.
+    fn upgrade_subprogram(&mut self, record: &Record, id: usize) {
+        let has_sp_flags = record.op(0) & 4 != 0;
+        let has_unit = record.op(0) & 2 != 0;
+        if has_sp_flags || has_unit || record.ops.len() < 19 {
+            return;
+        }
+        let function = record
+            .op(15)
+            .checked_sub(1)
+            .and_then(|id| self.metadata.value_ids.get(&(id as usize)));
+        if let Some(&function) = function {
+            let reference = self.metadata.refs[id].clone();
+            self.metadata
+                .function_subprograms
+                .entry(function)
+                .or_insert(reference);
+        }
+    }
+
+    /// In old bitcode, compile units list their subprograms (at index 11).
+    /// LLVM makes those subprograms refer to their compile units instead.
+    fn upgrade_compile_unit_subprograms(&mut self, records: &[(&Record, usize)]) {
+        let tuples: HashMap<usize, &Record> = records
+            .iter()
+            .filter(|(record, _)| record.code == NODE)
+            .map(|&(record, id)| (id, record))
+            .collect();
+        for &(record, id) in records {
+            if record.code != COMPILE_UNIT || record.op(11) == 0 {
+                continue;
+            }
+            if let Some(subprograms) = tuples.get(&(record.op(11) as usize - 1)) {
+                for &subprogram in &subprograms.ops {
+                    if let Some(subprogram) = subprogram.checked_sub(1) {
+                        let unit = self.metadata.refs[id].clone();
+                        self.metadata.units.insert(subprogram as usize, unit);
+                    }
+                }
+            }
+        }
+    }
+
+    fn subprogram_text(&self, record: &Record, id: usize) -> Result<String, String> {
+        let op = |i: usize| record.op(i);
+        let has_sp_flags = op(0) & 4 != 0;
+        let has_unit = op(0) & 2 != 0;
+        let mut flags = if has_sp_flags { op(11) } else { op(13) };
+        const OLD_MAIN_SUBPROGRAM: u64 = 1 << 21;
+        let old_main_subprogram = flags & OLD_MAIN_SUBPROGRAM != 0;
+        flags &= !OLD_MAIN_SUBPROGRAM;
+        let mut sp_flags = if has_sp_flags {
+            op(9)
+        } else {
+            // isLocal, isDefinition, isOptimized, and virtuality were
+            // separate fields
+            (op(11) & 3)
+                | if op(7) != 0 { 4 } else { 0 }
+                | if op(8) != 0 { 8 } else { 0 }
+                | if op(14) != 0 { 16 } else { 0 }
+        };
+        if old_main_subprogram {
+            sp_flags |= 256;
+        }
+        let distinct = op(0) & 1 != 0 || sp_flags & 8 != 0;
+        let (offset_a, mut offset_b) = if has_sp_flags { (0, 0) } else { (2, 2) };
+        let mut has_this_adjustment = true;
+        let mut has_thrown_types = true;
+        let (mut has_annotations, mut has_target_func_name) = (false, false);
+        let len = record.ops.len();
+        if !has_sp_flags {
+            if len >= 19 {
+                offset_b += 1;
+            }
+            has_this_adjustment = len >= 20;
+            has_thrown_types = len >= 21;
+        } else {
+            has_annotations = len >= 19;
+            has_target_func_name = len >= 20;
+        }
+
+        let md = &self.metadata;
+        let mut fields = Fields::new(md);
+        fields.string("name", op(2), true)?;
+        fields.string("linkageName", op(3), true)?;
+        fields.node("scope", op(1), false)?;
+        fields.node("file", op(4), true)?;
+        fields.int("line", op(5) as i64, true);
+        fields.node("type", op(6), true)?;
+        fields.int("scopeLine", op(7 + offset_a) as i64, true);
+        fields.node("containingType", op(8 + offset_a), true)?;
+        let virtual_index = op(10 + offset_a);
+        if sp_flags & 3 != 0 || virtual_index != 0 {
+            fields.int("virtualIndex", virtual_index as i64, false);
+        }
+        if has_this_adjustment {
+            fields.int(
+                "thisAdjustment",
+                op(16 + offset_b) as i64 as i32 as i64,
+                true,
+            );
+        }
+        if flags != 0 {
+            fields.raw("flags", di_flags_text(flags));
+        }
+        if sp_flags != 0 {
+            fields.raw("spFlags", sp_flags_text(sp_flags));
+        }
+        if has_unit {
+            fields.node("unit", op(12 + offset_b), true)?;
+        } else if let Some(unit) = md.units.get(&id) {
+            fields.raw("unit", unit.clone());
+        }
+        fields.node("templateParams", op(13 + offset_b), true)?;
+        fields.node("declaration", op(14 + offset_b), true)?;
+        fields.node("retainedNodes", op(15 + offset_b), true)?;
+        if has_thrown_types {
+            fields.node("thrownTypes", op(17 + offset_b), true)?;
+        }
+        if has_annotations {
+            fields.node("annotations", op(18 + offset_b), true)?;
+        }
+        if has_target_func_name {
+            fields.string("targetFuncName", op(19 + offset_b), true)?;
+        }
+        Ok(fields.finish(distinct, "DISubprogram"))
+    }
+
+    /// Read a function's `METADATA_ATTACHMENT_ID` block, giving the function's
+    /// own attachments (e.g. `!dbg !7`). Attachments of instructions other
+    /// than `!dbg` aren't represented in `llvm-ir`.
+    pub(super) fn read_function_attachments(&self, block: &Block) -> Result<Vec<String>, String> {
+        let mut attachments = vec![];
+        for record in block.records() {
+            // [n x [kind, node]] for the function, or [instruction, n x
+            // [kind, node]] for an instruction
+            if record.code != ATTACHMENT || record.ops.len() % 2 != 0 {
+                continue;
+            }
+            for pair in record.ops.chunks(2) {
+                if self.metadata.strip_debug_info && self.metadata.is_dbg(pair[0]) {
+                    continue;
+                }
+                attachments.push(format!(
+                    "!{} {}",
+                    self.metadata.kind_name(pair[0])?,
+                    self.metadata.get(pair[1])?
+                ));
+            }
+        }
+        Ok(attachments)
+    }
+}
+
+/// Builds the list of fields of a specialized node, as LLVM prints them
+struct Fields<'m> {
+    metadata: &'m Metadata,
+    fields: Vec<String>,
+}
+
+impl<'m> Fields<'m> {
+    fn new(metadata: &'m Metadata) -> Self {
+        Self {
+            metadata,
+            fields: vec![],
+        }
+    }
+
+    fn raw(&mut self, name: &str, value: String) {
+        self.fields.push(format!("{}: {}", name, value));
+    }
+
+    /// A string field, whose metadata ID plus one is given
+    fn string(&mut self, name: &str, id: u64, skip_empty: bool) -> Result<(), String> {
+        let value = match self.metadata.get_or_null(id)? {
+            Some(value) => value.strip_prefix('!').unwrap_or(value),
+            None => "\"\"",
+        };
+        if !(skip_empty && value == "\"\"") {
+            self.raw(name, value.to_owned());
+        }
+        Ok(())
+    }
+
+    /// A field referring to the metadata whose ID plus one is given
+    fn node(&mut self, name: &str, id: u64, skip_null: bool) -> Result<(), String> {
+        match self.metadata.get_or_null(id)? {
+            Some(value) => {
+                let value = value.to_owned();
+                self.raw(name, value)
+            },
+            None if !skip_null => self.raw(name, "null".into()),
+            None => {},
+        }
+        Ok(())
+    }
+
+    fn int(&mut self, name: &str, value: i64, skip_zero: bool) {
+        if !(skip_zero && value == 0) {
+            self.raw(name, value.to_string());
+        }
+    }
+
+    fn finish(self, distinct: bool, kind: &str) -> String {
+        format!(
+            "{}!{}({})",
+            if distinct { "distinct " } else { "" },
+            kind,
+            self.fields.join(", ")
+        )
+    }
+}
+
+fn location_text(
+    distinct: bool,
+    line: u64,
+    column: u64,
+    scope: &str,
+    inlined_at: Option<&str>,
+    implicit: bool,
+) -> String {
+    let mut text = format!(
+        "{}!DILocation(line: {}",
+        if distinct { "distinct " } else { "" },
+        line
+    );
+    if column != 0 {
+        text.push_str(&format!(", column: {}", column));
+    }
+    text.push_str(&format!(", scope: {}", scope));
+    if let Some(inlined_at) = inlined_at {
+        text.push_str(&format!(", inlinedAt: {}", inlined_at));
+    }
+    if implicit {
+        text.push_str(", isImplicitCode: true");
+    }
+    text.push(')');
+    text
+}
+
+fn di_flags_text(mut flags: u64) -> String {
+    let mut names = vec![];
+    match flags & 3 {
+        1 => names.push("DIFlagPrivate"),
+        2 => names.push("DIFlagProtected"),
+        3 => names.push("DIFlagPublic"),
+        _ => {},
+    }
+    flags &= !3;
+    match flags & (3 << 16) {
+        0 => {},
+        r if r == 1 << 16 => names.push("DIFlagSingleInheritance"),
+        r if r == 2 << 16 => names.push("DIFlagMultipleInheritance"),
+        _ => names.push("DIFlagVirtualInheritance"),
+    }
+    flags &= !(3 << 16);
+    const INDIRECT_VIRTUAL_BASE: u64 = (1 << 2) | (1 << 5);
+    if flags & INDIRECT_VIRTUAL_BASE == INDIRECT_VIRTUAL_BASE {
+        names.push("DIFlagIndirectVirtualBase");
+        flags &= !INDIRECT_VIRTUAL_BASE;
+    }
+    let bit_names: &[(u32, &str)] = &[
+        (2, "DIFlagFwdDecl"),
+        (3, "DIFlagAppleBlock"),
+        (
+            4,
+            if cfg!(feature = "llvm-10-or-greater") {
+                "DIFlagReservedBit4"
+            } else {
+                "DIFlagBlockByrefStruct"
+            },
+        ),
+        (5, "DIFlagVirtual"),
+        (6, "DIFlagArtificial"),
+        (7, "DIFlagExplicit"),
+        (8, "DIFlagPrototyped"),
+        (9, "DIFlagObjcClassComplete"),
+        (10, "DIFlagObjectPointer"),
+        (11, "DIFlagVector"),
+        (12, "DIFlagStaticMember"),
+        (13, "DIFlagLValueReference"),
+        (14, "DIFlagRValueReference"),
+        (15, "DIFlagExportSymbols"),
+        (18, "DIFlagIntroducedVirtual"),
+        (19, "DIFlagBitField"),
+        (20, "DIFlagNoReturn"),
+        (22, "DIFlagTypePassByValue"),
+        (23, "DIFlagTypePassByReference"),
+        (
+            24,
+            if cfg!(feature = "llvm-9-or-greater") {
+                "DIFlagEnumClass"
+            } else {
+                "DIFlagFixedEnum"
+            },
+        ),
+        (25, "DIFlagThunk"),
+        (
+            26,
+            if cfg!(feature = "llvm-10-or-greater") {
+                "DIFlagNonTrivial"
+            } else {
+                "DIFlagTrivial"
+            },
+        ),
+        (27, "DIFlagBigEndian"),
+        (28, "DIFlagLittleEndian"),
+        (29, "DIFlagAllCallsDescribed"),
+    ];
+    for (bit, name) in bit_names {
+        if flags & (1 << bit) != 0 {
+            names.push(name);
+            flags &= !(1 << bit);
+        }
+    }
+    let mut text = names.join(" | ");
+    if flags != 0 || names.is_empty() {
+        if !names.is_empty() {
+            text.push_str(" | ");
+        }
+        text.push_str(&flags.to_string());
+    }
+    text
+}
+
+fn sp_flags_text(mut flags: u64) -> String {
+    let mut names = vec![];
+    match flags & 3 {
+        1 => names.push("DISPFlagVirtual"),
+        2 => names.push("DISPFlagPureVirtual"),
+        _ => {},
+    }
+    flags &= !3;
+    let bit_names: &[(u32, &str)] = &[
+        (2, "DISPFlagLocalToUnit"),
+        (3, "DISPFlagDefinition"),
+        (4, "DISPFlagOptimized"),
+        (5, "DISPFlagPure"),
+        (6, "DISPFlagElemental"),
+        (7, "DISPFlagRecursive"),
+        (8, "DISPFlagMainSubprogram"),
+        (9, "DISPFlagDeleted"),
+        (11, "DISPFlagObjCDirect"),
+    ];
+    for (bit, name) in bit_names {
+        if flags & (1 << bit) != 0 {
+            names.push(name);
+            flags &= !(1 << bit);
+        }
+    }
+    let mut text = names.join(" | ");
+    if flags != 0 || names.is_empty() {
+        if !names.is_empty() {
+            text.push_str(" | ");
+        }
+        text.push_str(&flags.to_string());
+    }
+    text
+}
+
+// DW_OP_*
+const DW_OP_DEREF: u64 = 0x06;
+const DW_OP_CONSTU: u64 = 0x10;
+const DW_OP_MINUS: u64 = 0x1c;
+const DW_OP_PLUS: u64 = 0x22;
+const DW_OP_PLUS_UCONST: u64 = 0x23;
+const DW_OP_BIT_PIECE: u64 = 0x9d;
+const DW_OP_LLVM_FRAGMENT: u64 = 0x1000;
+const DW_OP_LLVM_CONVERT: u64 = 0x1001;
+
+/// The name and number of arguments of a DWARF expression operation
+fn dwarf_op(op: u64) -> Option<(String, usize)> {
+    let named = |name: &str, args: usize| Some((format!("DW_OP_{}", name), args));
+    match op {
+        0x30 ..= 0x4f => named(&format!("lit{}", op - 0x30), 0),
+        0x50 ..= 0x6f => named(&format!("reg{}", op - 0x50), 0),
+        0x70 ..= 0x8f => named(&format!("breg{}", op - 0x70), 1),
+        0x06 => named("deref", 0),
+        0x10 => named("constu", 1),
+        0x11 => named("consts", 1),
+        0x12 => named("dup", 0),
+        0x14 => named("over", 0),
+        0x16 => named("swap", 0),
+        0x1a => named("and", 0),
+        0x1b => named("div", 0),
+        0x1c => named("minus", 0),
+        0x1e => named("mul", 0),
+        0x1f => named("neg", 0),
+        0x20 => named("not", 0),
+        0x21 => named("or", 0),
+        0x22 => named("plus", 0),
+        0x23 => named("plus_uconst", 1),
+        0x24 => named("shl", 0),
+        0x25 => named("shr", 0),
+        0x26 => named("shra", 0),
+        0x27 => named("xor", 0),
+        0x29 => named("eq", 0),
+        0x2a => named("ge", 0),
+        0x2b => named("gt", 0),
+        0x2c => named("le", 0),
+        0x2d => named("lt", 0),
+        0x2e => named("ne", 0),
+        0x90 => named("regx", 1),
+        0x92 => named("bregx", 2),
+        0x94 => named("deref_size", 1),
+        0x96 => named("nop", 0),
+        0x9c => named("call_frame_cfa", 0),
+        0x9f => named("stack_value", 0),
+        0xa3 => named("entry_value", 1),
+        0xa8 => named("convert", 1),
+        0xe0 => named("GNU_push_tls_address", 0),
+        0xf3 => named("GNU_entry_value", 1),
+        0x1000 => named("LLVM_fragment", 2),
+        0x1001 => named("LLVM_convert", 2),
+        0x1002 => named("LLVM_tag_offset", 1),
+        0x1003 => named("LLVM_entry_value", 1),
+        0x1004 => named("LLVM_implicit_pointer", 0),
+        0x1005 => named("LLVM_arg", 1),
+        _ => None,
+    }
+}
+
+/// The name of a DWARF base type encoding, as used by `DW_OP_LLVM_convert`
+fn dwarf_encoding(encoding: u64) -> Option<&'static str> {
+    Some(match encoding {
+        0x01 => "DW_ATE_address",
+        0x02 => "DW_ATE_boolean",
+        0x03 => "DW_ATE_complex_float",
+        0x04 => "DW_ATE_float",
+        0x05 => "DW_ATE_signed",
+        0x06 => "DW_ATE_signed_char",
+        0x07 => "DW_ATE_unsigned",
+        0x08 => "DW_ATE_unsigned_char",
+        0x10 => "DW_ATE_UTF",
+        _ => return None,
+    })
+}
+
+/// The text of a `DIExpression` record: [distinct | version << 1, ops],
+/// upgrading the ops from old versions as LLVM does
+fn expression_text(record: &Record) -> String {
+    let version = record.op(0) >> 1;
+    let mut ops: Vec<u64> = record.ops.get(1 ..).unwrap_or(&[]).to_vec();
+    let n = ops.len();
+    if version == 0 && n >= 3 && ops[n - 3] == DW_OP_BIT_PIECE {
+        ops[n - 3] = DW_OP_LLVM_FRAGMENT;
+    }
+    if version <= 1 && ops.first() == Some(&DW_OP_DEREF) {
+        // move the leading `DW_OP_deref` to the end, before any fragment
+        let end = if n >= 3 && ops[n - 3] == DW_OP_LLVM_FRAGMENT {
+            n - 3
+        } else {
+            n
+        };
+        ops[.. end].rotate_left(1);
+    }
+    if version <= 2 {
+        let mut upgraded = vec![];
+        let mut rest = &ops[..];
+        while !rest.is_empty() {
+            let size = match rest[0] {
+                DW_OP_CONSTU | DW_OP_MINUS | DW_OP_PLUS => 2,
+                DW_OP_LLVM_FRAGMENT => 3,
+                _ => 1,
+            }
+            .min(rest.len());
+            let args = &rest[1 .. size];
+            match rest[0] {
+                DW_OP_PLUS => {
+                    upgraded.push(DW_OP_PLUS_UCONST);
+                    upgraded.extend_from_slice(args);
+                },
+                DW_OP_MINUS => {
+                    upgraded.push(DW_OP_CONSTU);
+                    upgraded.extend_from_slice(args);
+                    upgraded.push(DW_OP_MINUS);
+                },
+                op => {
+                    upgraded.push(op);
+                    upgraded.extend_from_slice(args);
+                },
+            }
+            rest = &rest[size ..];
+        }
+        ops = upgraded;
+    }
+
+    let mut parts = vec![];
+    let mut i = 0;
+    while i < ops.len() {
+        match dwarf_op(ops[i]) {
+            Some((name, num_args)) if i + num_args < ops.len() => {
+                parts.push(name);
+                let args = &ops[i + 1 ..= i + num_args];
+                if ops[i] == DW_OP_LLVM_CONVERT {
+                    parts.push(args[0].to_string());
+                    match dwarf_encoding(args[1]) {
+                        Some(encoding) => parts.push(encoding.into()),
+                        None => parts.push(args[1].to_string()),
+                    }
+                } else {
+                    parts.extend(args.iter().map(|a| a.to_string()));
+                }
+                i += 1 + num_args;
+            },
+            _ => {
+                // not a valid expression: LLVM prints the raw elements
+                parts = ops.iter().map(|op| op.to_string()).collect();
+                break;
+            },
+        }
+    }
+    format!("!DIExpression({})", parts.join(", "))
+}
@@ -0,0 +1,555 @@
+//! The type table (`TYPE_BLOCK_ID_NEW`)
+
+use super::bitstream::Block;
+use super::{escape, local_name};
+
+pub(super) type TypeId = usize;
+
+#[derive(PartialEq, Clone, Debug)]
+pub(super) enum Ty {
+    Void,
+    Half,
+    BFloat,
+    Float,
+    Double,
+    X86Fp80,
+    Fp128,
+    PpcFp128,
+    Label,
+    Metadata,
+    X86Mmx,
+    X86Amx,
+    Token,
+    Int(u32),
+    /// `pointee` is `None` for opaque pointers
+    Pointer {
+        pointee: Option<TypeId>,
+        addr_space: u32,
+    },
+    Function {
+        result: TypeId,
+        params: Vec<TypeId>,
+        var_arg: bool,
+    },
+    /// An identified struct. Unnamed identified structs are printed as
+    /// numbered types (e.g. `%0`). `body` is `None` for opaque structs.
+    Identified {
+        name: Result<String, usize>,
+        body: Option<(Vec<TypeId>, bool)>,
+    },
+    LiteralStruct {
+        elements: Vec<TypeId>,
+        packed: bool,
+    },
+    Array(u64, TypeId),
+    Vector {
+        num_elements: u64,
+        element: TypeId,
+        scalable: bool,
+    },
+    TargetExt {
+        name: String,
+        types: Vec<TypeId>,
+        ints: Vec<u64>,
+    },
+}
+
+pub(super) struct Types {
+    types: Vec<Ty>,
+    /// Whether pointers are printed as `ptr`, regardless of whether the
+    /// bitcode records their pointee types
+    pub(super) opaque_pointers: bool,
+}
+
+// TYPE_CODE_*
+const NUMENTRY: u32 = 1;
+const VOID: u32 = 2;
+const FLOAT: u32 = 3;
+const DOUBLE: u32 = 4;
+const LABEL: u32 = 5;
+const OPAQUE: u32 = 6;
+const INTEGER: u32 = 7;
+const POINTER: u32 = 8;
+const FUNCTION_OLD: u32 = 9;
+const HALF: u32 = 10;
+const ARRAY: u32 = 11;
+const VECTOR: u32 = 12;
+const X86_FP80: u32 = 13;
+const FP128: u32 = 14;
+const PPC_FP128: u32 = 15;
+const METADATA: u32 = 16;
+const X86_MMX: u32 = 17;
+const STRUCT_ANON: u32 = 18;
+const STRUCT_NAME: u32 = 19;
+const STRUCT_NAMED: u32 = 20;
+const FUNCTION: u32 = 21;
+const TOKEN: u32 = 22;
+const BFLOAT: u32 = 23;
+const X86_AMX: u32 = 24;
+const OPAQUE_POINTER: u32 = 25;
+const TARGET_TYPE: u32 = 26;
+
+/// How deeply types (other than identified structs) may be nested, which
+/// bounds the recursion through them
+const MAX_TYPE_DEPTH: usize = 128;
+
+impl Types {
+    pub(super) fn new() -> Self {
+        Self {
+            types: vec![],
+            opaque_pointers: cfg!(feature = "llvm-16-or-greater"),
+        }
+    }
+
+    /// Read the type table. Pointers are opaque if the selected LLVM version
+    /// would make them opaque when reading this bitcode.
+    pub(super) fn parse_block(&mut self, block: &Block) -> Result<(), String> {
+        let mut pending_name = None;
+        let mut num_unnamed = 0;
+        let mut seen_typed_pointer = false;
+        let mut seen_opaque_pointer = false;
+        for record in block.records() {
+            let op = |i: usize| record.op(i) as TypeId;
+            let ty = match record.code {
+                NUMENTRY => {
+                    // the count comes from the file, so it's only a hint
+                    self.types.reserve(record.op(0).min(1 << 16) as usize);
+                    continue;
+                },
+                STRUCT_NAME => {
+                    pending_name = Some(record.string(0));
+                    continue;
+                },
+                VOID => Ty::Void,
+                FLOAT => Ty::Float,
+                DOUBLE => Ty::Double,
+                LABEL => Ty::Label,
+                HALF => Ty::Half,
+                BFLOAT => Ty::BFloat,
+                X86_FP80 => Ty::X86Fp80,
+                FP128 => Ty::Fp128,
+                PPC_FP128 => Ty::PpcFp128,
+                METADATA => Ty::Metadata,
+                X86_MMX => Ty::X86Mmx,
+                X86_AMX => Ty::X86Amx,
+                TOKEN => Ty::Token,
+                INTEGER => match record.op(0) {
+                    // LLVM's limit on the width of integer types
+                    bits @ 1 ..= 0x80_0000 => Ty::Int(bits as u32),
+                    bits => return Err(format!("Invalid integer type width {}", bits)),
+                },
+                POINTER => {
+                    seen_typed_pointer = true;
+                    Ty::Pointer {
+                        pointee: Some(op(0)),
+                        addr_space: record.op(1) as u32,
+                    }
+                },
+                OPAQUE_POINTER => {
+                    seen_opaque_pointer = true;
+                    Ty::Pointer {
+                        pointee: None,
+                        addr_space: record.op(0) as u32,
+                    }
+                },
+                FUNCTION_OLD => Ty::Function {
+                    var_arg: record.op(0) != 0,
+                    result: op(2),
+                    params: record.ops.iter().skip(3).map(|&t| t as TypeId).collect(),
+                },
+                FUNCTION => Ty::Function {
+                    var_arg: record.op(0) != 0,
+                    result: op(1),
+                    params: record.ops.iter().skip(2).map(|&t| t as TypeId).collect(),
+                },
+                ARRAY => Ty::Array(record.op(0), op(1)),
+                VECTOR => Ty::Vector {
+                    num_elements: record.op(0),
+                    element: op(1),
+                    scalable: record.op(2) != 0,
+                },
+                STRUCT_ANON => Ty::LiteralStruct {
+                    packed: record.op(0) != 0,
+                    elements: record.ops.iter().skip(1).map(|&t| t as TypeId).collect(),
+                },
+                STRUCT_NAMED | OPAQUE => {
+                    let name = match pending_name.take().filter(|n| !n.is_empty()) {
+                        Some(name) => Ok(name),
+                        None => {
+                            num_unnamed += 1;
+                            Err(num_unnamed - 1)
+                        },
+                    };
+                    let body = if record.code == OPAQUE {
+                        None
+                    } else {
+                        Some((
+                            record.ops.iter().skip(1).map(|&t| t as TypeId).collect(),
+                            record.op(0) != 0,
+                        ))
+                    };
+                    Ty::Identified { name, body }
+                },
+                TARGET_TYPE => {
+                    let num_types = record.op(0) as usize;
+                    let types = record
+                        .ops
+                        .get(1 ..)
+                        .and_then(|ops| ops.get(.. num_types))
+                        .ok_or("Invalid target extension type record")?;
+                    Ty::TargetExt {
+                        name: pending_name.take().unwrap_or_default(),
+                        types: types.iter().map(|&t| t as TypeId).collect(),
+                        ints: record.ops[1 + num_types ..].to_vec(),
+                    }
+                },
+                code => return Err(format!("Unknown type code {}", code)),
+            };
+            self.types.push(ty);
+        }
+        self.check_references()?;
+        self.opaque_pointers = if cfg!(feature = "llvm-16-or-greater") {
+            true
+        } else if cfg!(feature = "llvm-15-or-greater") {
+            seen_opaque_pointer || !seen_typed_pointer
+        } else {
+            seen_opaque_pointer
+        };
+        Ok(())
+    }
+
+    /// Check that the types only refer to types in the table, and that any
+    /// forward reference is to an identified struct, as LLVM requires. This
+    /// means that following the references of anything other than an
+    /// identified struct always ends, so that the rest of the reader can
+    /// recurse through types, once it's checked that they aren't nested too
+    /// deeply.
+    fn check_references(&self) -> Result<(), String> {
+        // how deeply each type is nested, not counting the bodies of
+        // identified structs, which are referred to by name
+        let mut depths = Vec::with_capacity(self.types.len());
+        for (id, ty) in self.types.iter().enumerate() {
+            let refs: Vec<TypeId> = match ty {
+                Ty::Pointer {
+                    pointee: Some(pointee),
+                    ..
+                } => vec![*pointee],
+                Ty::Function { result, params, .. } => std::iter::once(*result)
+                    .chain(params.iter().copied())
+                    .collect(),
+                Ty::Identified {
+                    body: Some((elements, _)),
+                    ..
+                } => elements.clone(),
+                Ty::LiteralStruct { elements, .. } => elements.clone(),
+                Ty::Array(_, element) => vec![*element],
+                Ty::Vector { element, .. } => vec![*element],
+                Ty::TargetExt { types, .. } => types.clone(),
+                _ => vec![],
+            };
+            let mut depth = 1;
+            for r in refs {
+                match self.types.get(r) {
+                    None => return Err(format!("Invalid type ID {} in type {}", r, id)),
+                    Some(Ty::Identified { .. }) => {},
+                    Some(_) if r >= id => {
+                        return Err(format!("Invalid forward reference to type {}", r))
+                    },
+                    Some(_) => depth = depth.max(depths[r] + 1),
+                }
+            }
+            if depth > MAX_TYPE_DEPTH {
+                return Err(format!("Type {} is nested too deeply", id));
+            }
+            depths.push(depth);
+        }
+        Ok(())
+    }
+
+    pub(super) fn get(&self, id: TypeId) -> Result<&Ty, String> {
+        self.types
+            .get(id)
+            .ok_or_else(|| format!("Invalid type ID {}", id))
+    }
+
+    /// Find the given type in the table, adding it if necessary
+    fn intern(&mut self, ty: Ty) -> TypeId {
+        match self.types.iter().position(|t| *t == ty) {
+            Some(id) => id,
+            None => {
+                self.types.push(ty);
+                self.types.len() - 1
+            },
+        }
+    }
+
+    pub(super) fn int(&mut self, bits: u32) -> TypeId {
+        self.intern(Ty::Int(bits))
+    }
+
+    pub(super) fn token(&mut self) -> TypeId {
+        self.intern(Ty::Token)
+    }
+
+    pub(super) fn pointer_to(&mut self, pointee: TypeId, addr_space: u32) -> TypeId {
+        self.intern(Ty::Pointer {
+            pointee: Some(pointee),
+            addr_space,
+        })
+    }
+
+    pub(super) fn array_of(&mut self, num_elements: u64, element: TypeId) -> TypeId {
+        self.intern(Ty::Array(num_elements, element))
+    }
+
+    pub(super) fn literal_struct(&mut self, elements: Vec<TypeId>) -> TypeId {
+        self.intern(Ty::LiteralStruct {
+            elements,
+            packed: false,
+        })
+    }
+
+    /// A vector type with the same number of elements as `shape` (if it's a
+    /// vector), or `element` itself
+    pub(super) fn like_vector(&mut self, shape: TypeId, element: TypeId) -> TypeId {
+        match self.types.get(shape) {
+            Some(&Ty::Vector {
+                num_elements,
+                scalable,
+                ..
+            }) => self.intern(Ty::Vector {
+                num_elements,
+                element,
+                scalable,
+            }),
+            _ => element,
+        }
+    }
+
+    pub(super) fn vector_of(
+        &mut self,
+        element: TypeId,
+        num_elements: u64,
+        scalable: bool,
+    ) -> TypeId {
+        self.intern(Ty::Vector {
+            num_elements,
+            element,
+            scalable,
+        })
+    }
+
+    pub(super) fn is_vector(&self, id: TypeId) -> bool {
+        matches!(self.types.get(id), Some(Ty::Vector { .. }))
+    }
+
+    pub(super) fn is_fp(&self, id: TypeId) -> bool {
+        match self.types.get(id) {
+            Some(Ty::Half) | Some(Ty::BFloat) | Some(Ty::Float) | Some(Ty::Double)
+            | Some(Ty::X86Fp80) | Some(Ty::Fp128) | Some(Ty::PpcFp128) => true,
+            Some(&Ty::Vector { element, .. }) => self.is_fp(element),
+            _ => false,
+        }
+    }
+
+    pub(super) fn is_void(&self, id: TypeId) -> bool {
+        matches!(self.types.get(id), Some(Ty::Void))
+    }
+
+    pub(super) fn is_metadata(&self, id: TypeId) -> bool {
+        matches!(self.types.get(id), Some(Ty::Metadata))
+    }
+
+    pub(super) fn is_label(&self, id: TypeId) -> bool {
+        matches!(self.types.get(id), Some(Ty::Label))
+    }
+
+    /// Whether this is a pointer or a vector of pointers
+    pub(super) fn is_pointer(&self, id: TypeId) -> bool {
+        match self.types.get(id) {
+            Some(Ty::Pointer { .. }) => true,
+            Some(&Ty::Vector { element, .. }) => self.is_pointer(element),
+            _ => false,
+        }
+    }
+
+    /// The pointee type of a typed pointer (or vector of typed pointers)
+    pub(super) fn pointee(&self, id: TypeId) -> Option<TypeId> {
+        match self.types.get(id)? {
+            Ty::Pointer { pointee, .. } => *pointee,
+            Ty::Vector { element, .. } => self.pointee(*element),
+            _ => None,
+        }
+    }
+
+    pub(super) fn addr_space(&self, id: TypeId) -> u32 {
+        match self.types.get(id) {
+            Some(Ty::Pointer { addr_space, .. }) => *addr_space,
+            Some(Ty::Vector { element, .. }) => self.addr_space(*element),
+            _ => 0,
+        }
+    }
+
+    /// The result type of a function type
+    pub(super) fn result(&self, id: TypeId) -> Option<TypeId> {
+        match self.types.get(id)? {
+            Ty::Function { result, .. } => Some(*result),
+            _ => None,
+        }
+    }
+
+    pub(super) fn params(&self, id: TypeId) -> Vec<TypeId> {
+        match self.types.get(id) {
+            Some(Ty::Function { params, .. }) => params.clone(),
+            _ => vec![],
+        }
+    }
+
+    /// The type of element `index` of the struct, array, or vector type `id`
+    pub(super) fn element(&self, id: TypeId, index: u64) -> Option<TypeId> {
+        match self.types.get(id)? {
+            Ty::Identified {
+                body: Some((elements, _)),
+                ..
+            }
+            | Ty::LiteralStruct { elements, .. } => elements.get(index as usize).copied(),
+            Ty::Array(_, element) | Ty::Vector { element, .. } => Some(*element),
+            _ => None,
+        }
+    }
+
+    /// The number of elements of an array or vector type
+    pub(super) fn num_elements(&self, id: TypeId) -> u64 {
+        match self.types.get(id) {
+            Some(Ty::Array(n, _)) => *n,
+            Some(Ty::Vector { num_elements, .. }) => *num_elements,
+            Some(Ty::Identified {
+                body: Some((elements, _)),
+                ..
+            })
+            | Some(Ty::LiteralStruct { elements, .. }) => elements.len() as u64,
+            _ => 0,
+        }
+    }
+
+    /// The bit width of an integer type (or vector of integers), if it is one
+    pub(super) fn int_width(&self, id: TypeId) -> Option<u32> {
+        match self.types.get(id)? {
+            Ty::Int(bits) => Some(*bits),
+            Ty::Vector { element, .. } => self.int_width(*element),
+            _ => None,
+        }
+    }
+
+    /// The textual form of a type, e.g. `i32` or `{ i8*, i64 }`
+    pub(super) fn text(&self, id: TypeId) -> String {
+        let ty = match self.types.get(id) {
+            Some(ty) => ty,
+            None => return format!("<invalid type {}>", id),
+        };
+        let list = |ids: &[TypeId]| {
+            ids.iter()
+                .map(|&t| self.text(t))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        match ty {
+            Ty::Void => "void".into(),
+            Ty::Half => "half".into(),
+            Ty::BFloat => "bfloat".into(),
+            Ty::Float => "float".into(),
+            Ty::Double => "double".into(),
+            Ty::X86Fp80 => "x86_fp80".into(),
+            Ty::Fp128 => "fp128".into(),
+            Ty::PpcFp128 => "ppc_fp128".into(),
+            Ty::Label => "label".into(),
+            Ty::Metadata => "metadata".into(),
+            Ty::X86Mmx => "x86_mmx".into(),
+            Ty::X86Amx => "x86_amx".into(),
+            Ty::Token => "token".into(),
+            Ty::Int(bits) => format!("i{}", bits),
+            Ty::Pointer {
+                pointee,
+                addr_space,
+            } => {
+                let addr_space = if *addr_space == 0 {
+                    String::new()
+                } else {
+                    format!(" addrspace({})", addr_space)
+                };
+                match pointee {
+                    Some(pointee) if !self.opaque_pointers => {
+                        format!("{}{}*", self.text(*pointee), addr_space)
+                    },
+                    _ => format!("ptr{}", addr_space),
+                }
+            },
+            Ty::Function {
+                result,
+                params,
+                var_arg,
+            } => {
+                let mut params = list(params);
+                if *var_arg {
+                    if !params.is_empty() {
+                        params.push_str(", ");
+                    }
+                    params.push_str("...");
+                }
+                format!("{} ({})", self.text(*result), params)
+            },
+            Ty::Identified { name: Ok(name), .. } => local_name(name),
+            Ty::Identified { name: Err(n), .. } => format!("%{}", n),
+            Ty::LiteralStruct { elements, packed } => struct_body(&list(elements), *packed),
+            Ty::Array(n, element) => format!("[{} x {}]", n, self.text(*element)),
+            Ty::Vector {
+                num_elements,
+                element,
+                scalable,
+            } => format!(
+                "<{}{} x {}>",
+                if *scalable { "vscale x " } else { "" },
+                num_elements,
+                self.text(*element)
+            ),
+            Ty::TargetExt { name, types, ints } => {
+                let mut params = vec![format!("\"{}\"", escape(name))];
+                params.extend(types.iter().map(|&t| self.text(t)));
+                params.extend(ints.iter().map(|i| i.to_string()));
+                format!("target({})", params.join(", "))
+            },
+        }
+    }
+
+    /// The definitions of the identified struct types, e.g.
+    /// `%pair = type { i32, i32 }`
+    pub(super) fn definitions(&self) -> String {
+        let mut text = String::new();
+        for (id, ty) in self.types.iter().enumerate() {
+            if let Ty::Identified { body, .. } = ty {
+                let body = match body {
+                    Some((elements, packed)) => struct_body(
+                        &elements
+                            .iter()
+                            .map(|&t| self.text(t))
+                            .collect::<Vec<_>>()
+                            .join(", "),
+                        *packed,
+                    ),
+                    None => "opaque".into(),
+                };
+                text.push_str(&format!("{} = type {}\n", self.text(id), body));
+            }
+        }
+        text
+    }
+}
+
+fn struct_body(elements: &str, packed: bool) -> String {
+    match (elements.is_empty(), packed) {
+        (true, false) => "{}".into(),
+        (true, true) => "<{}>".into(),
+        (false, false) => format!("{{ {} }}", elements),
+        (false, true) => format!("<{{ {} }}>", elements),
+    }
+}
@@ -1,14 +1,17 @@
-use crate::name::Name;
 use crate::predicates::*;
 use crate::types::{FPType, NamedStructDef, Type, TypeRef, Typed, Types};
+use std::collections::hash_map::{DefaultHasher, Entry};
+use std::collections::HashMap;
 use std::convert::TryFrom;
 use std::fmt::{self, Display};
+use std::hash::{Hash, Hasher};
+use std::mem;
 use std::ops::Deref;
 use std::sync::Arc;
 
 /// See [LLVM 14 docs on Constants](https://releases.llvm.org/14.0.0/docs/LangRef.html#constants).
 /// Constants can be either values, or expressions involving other constants (see [LLVM 14 docs on Constant Expressions](https://releases.llvm.org/14.0.0/docs/LangRef.html#constant-expressions)).
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
 pub enum Constant {
     Int {
         /// Number of bits in the constant integer
@@ -36,6 +39,8 @@ pub enum Constant {
     /// A zero-initialized array or struct (or scalar).
     AggregateZero(TypeRef),
     Struct {
+        /// If the constant is of a named struct type (e.g., `%struct.Foo`),
+        /// the name of that struct type; or `None` for a literal struct type
         name: Option<String>, // llvm-hs-pure has Option<Name> here, but I don't think struct types can be numbered
         values: Vec<ConstantRef>,
         is_packed: bool,
@@ -48,7 +53,6 @@ pub enum Constant {
     /// `Undef` can be used anywhere a constant is expected. See [LLVM 14 docs on Undefined Values](https://releases.llvm.org/14.0.0/docs/LangRef.html#undefined-values)
     Undef(TypeRef),
     /// See [LLVM 14 docs on Poison Values](https://releases.llvm.org/14.0.0/docs/LangRef.html#undefined-values)
-    #[cfg(feature = "llvm-12-or-greater")]
     Poison(TypeRef),
     /// The address of the given (non-entry) [`BasicBlock`](../struct.BasicBlock.html). See [LLVM 14 docs on Addresses of Basic Blocks](https://releases.llvm.org/14.0.0/docs/LangRef.html#addresses-of-basic-blocks).
     /// `BlockAddress` needs more fields, but the necessary getter functions are apparently not exposed in the LLVM C API (only the C++ API)
@@ -61,9 +65,11 @@ pub enum Constant {
     },
     TokenNone,
 
-    // Constants can also be expressed as operations applied to other constants:
+    // Constants can also be expressed as operations applied to other constants.
+    // LLVM has been removing some of these constant expressions; those never
+    // appear in `Module`s from LLVM versions without them.
 
-    // Integer binary ops
+    // Integer binary ops (division and remainder were removed in LLVM 15)
     Add(Add),
     Sub(Sub),
     Mul(Mul),
@@ -80,7 +86,7 @@ pub enum Constant {
     LShr(LShr),
     AShr(AShr),
 
-    // Floating-point ops
+    // Floating-point ops (removed in LLVM 15)
     FAdd(FAdd),
     FSub(FSub),
     FMul(FMul),
@@ -92,7 +98,7 @@ pub enum Constant {
     InsertElement(InsertElement),
     ShuffleVector(ShuffleVector),
 
-    // Aggregate ops
+    // Aggregate ops (removed in LLVM 15)
     ExtractValue(ExtractValue),
     InsertValue(InsertValue),
 
@@ -114,7 +120,7 @@ pub enum Constant {
     BitCast(BitCast),
     AddrSpaceCast(AddrSpaceCast),
 
-    // Other ops
+    // Other ops (`select` was removed in LLVM 17)
     ICmp(ICmp),
     FCmp(FCmp),
     Select(Select),
@@ -124,11 +130,10 @@ pub enum Constant {
 /// Rust only has `f32` and `f64` floating-point types, and furthermore,
 /// it's not clear how to get 16-, 80-, or 128-bit FP constant values through
 /// the LLVM C API (the getters seem to only be exposed in the C++ API?)
-#[derive(PartialEq, Clone, Debug)]
+#[derive(Clone, Debug)]
 #[allow(non_camel_case_types)]
 pub enum Float {
-    Half, // TODO perhaps Half(u16)
-    #[cfg(feature = "llvm-11-or-greater")]
+    Half,   // TODO perhaps Half(u16)
     BFloat, // TODO perhaps BFloat(u16)
     Single(f32),
     Double(f64),
@@ -137,11 +142,36 @@ pub enum Float {
     PPC_FP128, // TODO perhaps PPC_FP128((u64, u64)) with the most-significant bits on the left
 }
 
+// `Float`s are compared bitwise, as LLVM does when it uniques constants: so
+// `0.0` and `-0.0` are different constants, and a NaN is equal to itself.
+// This also means they can be `Eq` and `Hash`, which `Constants` relies on.
+impl PartialEq for Float {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Float::Single(a), Float::Single(b)) => a.to_bits() == b.to_bits(),
+            (Float::Double(a), Float::Double(b)) => a.to_bits() == b.to_bits(),
+            _ => mem::discriminant(self) == mem::discriminant(other),
+        }
+    }
+}
+
+impl Eq for Float {}
+
+impl Hash for Float {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        mem::discriminant(self).hash(state);
+        match self {
+            Float::Single(s) => s.to_bits().hash(state),
+            Float::Double(d) => d.to_bits().hash(state),
+            _ => {},
+        }
+    }
+}
+
 impl Typed for Float {
     fn get_type(&self, types: &Types) -> TypeRef {
         types.fp(match self {
             Float::Half => FPType::Half,
-            #[cfg(feature = "llvm-11-or-greater")]
             Float::BFloat => FPType::BFloat,
             Float::Single(_) => FPType::Single,
             Float::Double(_) => FPType::Double,
@@ -156,7 +186,6 @@ impl Display for Float {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             Float::Half => write!(f, "half"),
-            #[cfg(feature = "llvm-11-or-greater")]
             Float::BFloat => write!(f, "bfloat"),
             Float::Single(s) => write!(f, "float {}", s),
             Float::Double(d) => write!(f, "double {}", d),
@@ -175,7 +204,8 @@ impl Typed for Constant {
             Constant::Float(f) => types.type_of(f),
             Constant::Null(t) => t.clone(),
             Constant::AggregateZero(t) => t.clone(),
-            Constant::Struct { values, is_packed, .. } => types.struct_of(
+            Constant::Struct { name: Some(name), .. } => types.named_struct(name),
+            Constant::Struct { name: None, values, is_packed } => types.struct_of(
                 values.iter().map(|v| types.type_of(v)).collect(),
                 *is_packed,
             ),
@@ -183,22 +213,15 @@ impl Typed for Constant {
                 element_type.clone(),
                 elements.len(),
             ),
-            #[cfg(feature="llvm-11-or-greater")]
             Constant::Vector(v) => types.vector_of(
                 types.type_of(&v[0]),
                 v.len(),
                 false, // I don't think it's possible (at least as of LLVM 11) to have a constant of scalable vector type?
             ),
-            #[cfg(feature="llvm-10-or-lower")]
-            Constant::Vector(v) => types.vector_of(
-                types.type_of(&v[0]),
-                v.len(),
-            ),
             Constant::Undef(t) => t.clone(),
-            #[cfg(feature="llvm-12-or-greater")]
             Constant::Poison(t) => t.clone(),
             Constant::BlockAddress { .. } => types.label_type(),
-            Constant::GlobalReference { ty, .. } => types.pointer_to(ty.clone()),
+            Constant::GlobalReference { ty, .. } => types.pointer_to_value_in_addr_space(ty.clone(), 0),
             Constant::TokenNone => types.token_type(),
             Constant::Add(a) => types.type_of(a),
             Constant::Sub(s) => types.type_of(s),
@@ -333,7 +356,6 @@ impl Display for Constant {
                 Ok(())
             },
             Constant::Undef(ty) => write!(f, "{} undef", ty),
-            #[cfg(feature = "llvm-12-or-greater")]
             Constant::Poison(ty) => write!(f, "{} poison", ty),
             Constant::BlockAddress => write!(f, "blockaddr"),
             Constant::GlobalReference { name, ty } => {
@@ -397,16 +419,29 @@ impl Display for Constant {
 /// Most importantly, it implements `AsRef<Constant>` and `Deref<Target = Constant>`.
 /// It also has a cheap `Clone` -- only the reference is cloned, not the
 /// underlying `Constant`.
+///
+/// The `ConstantRef`s in a `Module` are interned by its
+/// [`Constants`](struct.Constants.html), so equal constants in the same
+/// `Module` share one `Constant`, and comparing them is just a pointer
+/// comparison. `ConstantRef`s from different `Module`s (or from
+/// `ConstantRef::new()`) are still equal if their `Constant`s are.
 //
 // `Arc` is used rather than `Rc` so that `Module` can remain `Sync`.
 // This is important because it allows multiple threads to simultaneously access
 // a single (immutable) `Module`.
-#[derive(PartialEq, Clone, Debug)]
-pub struct ConstantRef(Arc<Constant>);
+#[derive(Clone)]
+pub struct ConstantRef(Arc<ConstantData>);
+
+struct ConstantData {
+    /// Hash of `constant`, computed once so that hashing or comparing a
+    /// `ConstantRef` doesn't have to look at the whole `Constant`
+    hash: u64,
+    constant: Constant,
+}
 
 impl AsRef<Constant> for ConstantRef {
     fn as_ref(&self) -> &Constant {
-        self.0.as_ref()
+        &self.0.constant
     }
 }
 
@@ -414,7 +449,22 @@ impl Deref for ConstantRef {
     type Target = Constant;
 
     fn deref(&self) -> &Constant {
-        self.0.deref()
+        &self.0.constant
+    }
+}
+
+impl PartialEq for ConstantRef {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+            || (self.0.hash == other.0.hash && self.0.constant == other.0.constant)
+    }
+}
+
+impl Eq for ConstantRef {}
+
+impl Hash for ConstantRef {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u64(self.0.hash)
     }
 }
 
@@ -426,18 +476,137 @@ impl Typed for ConstantRef {
 
 impl Display for ConstantRef {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", &self.0)
+        write!(f, "{}", &self.0.constant)
+    }
+}
+
+impl fmt::Debug for ConstantRef {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("ConstantRef")
+            .field(&self.0.constant)
+            .finish()
     }
 }
 
 impl ConstantRef {
     /// Construct a new `ConstantRef` by consuming the given owned `Constant`.
+    /// This `ConstantRef` isn't interned; to share the `Constant` with an
+    /// equal one already in a `Module`, use
+    /// [`Constants::get_for_constant()`](struct.Constants.html#method.get_for_constant)
+    /// instead.
     //
-    // Internal users should get `ConstantRef`s from the `ModuleContext` cache
-    // instead if possible, so that if we already have that `Constant`
-    // somewhere, we can just give you a new `ConstantRef` to that `Constant`.
+    // Internal users should get `ConstantRef`s from a `ConstantsBuilder`
+    // instead, so that if we already have that `Constant` somewhere, we can
+    // just give you a new `ConstantRef` to that `Constant`.
     pub fn new(c: Constant) -> Self {
-        Self(Arc::new(c))
+        Self(Arc::new(ConstantData {
+            hash: hash_constant(&c),
+            constant: c,
+        }))
+    }
+}
+
+fn hash_constant(c: &Constant) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    c.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Holds a reference to all of the `Constant`s used in the `Module`, and
+/// facilitates lookups so you can get the `Module`'s `ConstantRef` to the
+/// `Constant` you want.
+//
+// Like `Types`, this is immutable; it is created from
+// `ConstantsBuilder::build()`. The map is behind an `Arc` so that building a
+// `Constants` while still converting (e.g., for `LazyModule`) is cheap.
+#[derive(Clone, Default)]
+pub struct Constants {
+    constants: Arc<HashMap<u64, ConstantRef>>,
+}
+
+impl Constants {
+    /// Get the `Module`'s `ConstantRef` to the given `Constant`, or `None`
+    /// if the `Module` doesn't use that `Constant`
+    pub fn get(&self, c: &Constant) -> Option<ConstantRef> {
+        self.constants
+            .get(&hash_constant(c))
+            .filter(|interned| ***interned == *c)
+            .cloned()
+    }
+
+    /// Get a `ConstantRef` to the given `Constant`.
+    ///
+    /// If the `Module` doesn't use that `Constant`, this still constructs it
+    /// and gives you a `ConstantRef`; you'll just be the sole owner of that
+    /// `Constant` object.
+    pub fn get_for_constant(&self, c: &Constant) -> ConstantRef {
+        self.get(c).unwrap_or_else(|| ConstantRef::new(c.clone()))
+    }
+
+    /// The number of distinct `Constant`s in the `Module`
+    pub fn len(&self) -> usize {
+        self.constants.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.constants.is_empty()
+    }
+}
+
+impl fmt::Debug for Constants {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Constants({} constants)", self.len())
+    }
+}
+
+/// Interns `Constant`s as they are converted, so that equal `Constant`s share
+/// one `ConstantRef`. Consumed by `build()` to produce the `Module`'s
+/// `Constants`.
+#[derive(Clone, Default)]
+pub(crate) struct ConstantsBuilder {
+    /// Map from the hash of each `Constant` to its `ConstantRef`. In the
+    /// unlikely event that two different `Constant`s have the same hash, the
+    /// second is just not interned.
+    constants: Arc<HashMap<u64, ConstantRef>>,
+}
+
+// (unused if neither `llvm-sys` nor `ll-parser` is enabled)
+#[allow(dead_code)]
+impl ConstantsBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get a `ConstantRef` to the given `Constant`, shared with any equal
+    /// `Constant` we've seen before
+    pub fn intern(&mut self, c: Constant) -> ConstantRef {
+        let constantref = ConstantRef::new(c);
+        match Arc::make_mut(&mut self.constants).entry(constantref.0.hash) {
+            Entry::Occupied(oentry) if *oentry.get() == constantref => oentry.get().clone(),
+            Entry::Occupied(_) => constantref,
+            Entry::Vacant(ventry) => ventry.insert(constantref).clone(),
+        }
+    }
+
+    /// Consumes the `ConstantsBuilder`, producing a `Constants`.
+    /// This should be done when no new constants are expected to be added.
+    pub fn build(self) -> Constants {
+        Constants {
+            constants: self.constants,
+        }
+    }
+
+    /// Add all the constants in `other`, a clone of this `ConstantsBuilder`
+    /// which has been used to convert functions on another thread, which
+    /// aren't in this `ConstantsBuilder` yet
+    #[cfg(feature = "parallel")]
+    pub fn absorb(&mut self, other: Self) {
+        let constants = Arc::make_mut(&mut self.constants);
+        for (hash, constantref) in other.constants.iter() {
+            constants
+                .entry(*hash)
+                .or_insert_with(|| constantref.clone());
+        }
     }
 }
 
@@ -501,7 +670,7 @@ macro_rules! impl_binop {
 // Use on binops where the result type is the same as both operand types
 // (and the Display impl doesn't need to show any more information other than the operands)
 macro_rules! binop_same_type {
-    ($expr:ty, $dispname:expr) => {
+    ($expr:ty, $dispname:expr $(, $flag:ident)*) => {
         impl_binop!($expr, $dispname);
 
         impl Typed for $expr {
@@ -514,7 +683,13 @@ macro_rules! binop_same_type {
 
         impl Display for $expr {
             fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-                write!(f, "{} ({}, {})", $dispname, &self.operand0, &self.operand1)
+                write!(f, "{}", $dispname)?;
+                $(
+                    if self.$flag {
+                        write!(f, " {}", stringify!($flag))?;
+                    }
+                )*
+                write!(f, " ({}, {})", &self.operand0, &self.operand1)
             }
         }
     };
@@ -523,7 +698,7 @@ macro_rules! binop_same_type {
 // Use on binops where the result type is the same as the first operand type
 // (and the Display impl doesn't need to show any more information other than the operands)
 macro_rules! binop_left_type {
-    ($expr:ty, $dispname:expr) => {
+    ($expr:ty, $dispname:expr $(, $flag:ident)*) => {
         impl_binop!($expr, $dispname);
 
         impl Typed for $expr {
@@ -534,7 +709,13 @@ macro_rules! binop_left_type {
 
         impl Display for $expr {
             fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-                write!(f, "{} ({}, {})", $dispname, &self.operand0, &self.operand1)
+                write!(f, "{}", $dispname)?;
+                $(
+                    if self.$flag {
+                        write!(f, " {}", stringify!($flag))?;
+                    }
+                )*
+                write!(f, " ({}, {})", &self.operand0, &self.operand1)
             }
         }
     };
@@ -565,60 +746,60 @@ macro_rules! unop_explicitly_typed {
     };
 }
 
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
 pub struct Add {
     pub operand0: ConstantRef,
     pub operand1: ConstantRef,
-    // pub nsw: bool,  // getters for these seem to not be exposed in the LLVM C API, only in the C++ one
-    // pub nuw: bool,  // getters for these seem to not be exposed in the LLVM C API, only in the C++ one
+    pub nsw: bool,
+    pub nuw: bool,
 }
 
 impl_constexpr!(Add, Add);
-binop_same_type!(Add, "add");
+binop_same_type!(Add, "add", nuw, nsw);
 
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
 pub struct Sub {
     pub operand0: ConstantRef,
     pub operand1: ConstantRef,
-    // pub nsw: bool,  // getters for these seem to not be exposed in the LLVM C API, only in the C++ one
-    // pub nuw: bool,  // getters for these seem to not be exposed in the LLVM C API, only in the C++ one
+    pub nsw: bool,
+    pub nuw: bool,
 }
 
 impl_constexpr!(Sub, Sub);
-binop_same_type!(Sub, "sub");
+binop_same_type!(Sub, "sub", nuw, nsw);
 
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
 pub struct Mul {
     pub operand0: ConstantRef,
     pub operand1: ConstantRef,
-    // pub nsw: bool,  // getters for these seem to not be exposed in the LLVM C API, only in the C++ one
-    // pub nuw: bool,  // getters for these seem to not be exposed in the LLVM C API, only in the C++ one
+    pub nsw: bool,
+    pub nuw: bool,
 }
 
 impl_constexpr!(Mul, Mul);
-binop_same_type!(Mul, "mul");
+binop_same_type!(Mul, "mul", nuw, nsw);
 
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
 pub struct UDiv {
     pub operand0: ConstantRef,
     pub operand1: ConstantRef,
-    // pub exact: bool,  // getters for these seem to not be exposed in the LLVM C API, only in the C++ one
+    pub exact: bool,
 }
 
 impl_constexpr!(UDiv, UDiv);
-binop_same_type!(UDiv, "udiv");
+binop_same_type!(UDiv, "udiv", exact);
 
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
 pub struct SDiv {
     pub operand0: ConstantRef,
     pub operand1: ConstantRef,
-    // pub exact: bool,  // getters for these seem to not be exposed in the LLVM C API, only in the C++ one
+    pub exact: bool,
 }
 
 impl_constexpr!(SDiv, SDiv);
-binop_same_type!(SDiv, "sdiv");
+binop_same_type!(SDiv, "sdiv", exact);
 
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
 pub struct URem {
     pub operand0: ConstantRef,
     pub operand1: ConstantRef,
@@ -627,7 +808,7 @@ pub struct URem {
 impl_constexpr!(URem, URem);
 binop_same_type!(URem, "urem");
 
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
 pub struct SRem {
     pub operand0: ConstantRef,
     pub operand1: ConstantRef,
@@ -636,7 +817,7 @@ pub struct SRem {
 impl_constexpr!(SRem, SRem);
 binop_same_type!(SRem, "srem");
 
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
 pub struct And {
     pub operand0: ConstantRef,
     pub operand1: ConstantRef,
@@ -645,7 +826,7 @@ pub struct And {
 impl_constexpr!(And, And);
 binop_same_type!(And, "and");
 
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
 pub struct Or {
     pub operand0: ConstantRef,
     pub operand1: ConstantRef,
@@ -654,7 +835,7 @@ pub struct Or {
 impl_constexpr!(Or, Or);
 binop_same_type!(Or, "or");
 
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
 pub struct Xor {
     pub operand0: ConstantRef,
     pub operand1: ConstantRef,
@@ -663,38 +844,38 @@ pub struct Xor {
 impl_constexpr!(Xor, Xor);
 binop_same_type!(Xor, "xor");
 
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
 pub struct Shl {
     pub operand0: ConstantRef,
     pub operand1: ConstantRef,
-    // pub nsw: bool,  // getters for these seem to not be exposed in the LLVM C API, only in the C++ one
-    // pub nuw: bool,  // getters for these seem to not be exposed in the LLVM C API, only in the C++ one
+    pub nsw: bool,
+    pub nuw: bool,
 }
 
 impl_constexpr!(Shl, Shl);
-binop_left_type!(Shl, "shl");
+binop_left_type!(Shl, "shl", nuw, nsw);
 
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
 pub struct LShr {
     pub operand0: ConstantRef,
     pub operand1: ConstantRef,
-    // pub exact: bool,  // getters for these seem to not be exposed in the LLVM C API, only in the C++ one
+    pub exact: bool,
 }
 
 impl_constexpr!(LShr, LShr);
-binop_left_type!(LShr, "lshr");
+binop_left_type!(LShr, "lshr", exact);
 
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
 pub struct AShr {
     pub operand0: ConstantRef,
     pub operand1: ConstantRef,
-    // pub exact: bool,  // getters for these seem to not be exposed in the LLVM C API, only in the C++ one
+    pub exact: bool,
 }
 
 impl_constexpr!(AShr, AShr);
-binop_left_type!(AShr, "ashr");
+binop_left_type!(AShr, "ashr", exact);
 
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
 pub struct FAdd {
     pub operand0: ConstantRef,
     pub operand1: ConstantRef,
@@ -703,7 +884,7 @@ pub struct FAdd {
 impl_constexpr!(FAdd, FAdd);
 binop_same_type!(FAdd, "fadd");
 
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
 pub struct FSub {
     pub operand0: ConstantRef,
     pub operand1: ConstantRef,
@@ -712,7 +893,7 @@ pub struct FSub {
 impl_constexpr!(FSub, FSub);
 binop_same_type!(FSub, "fsub");
 
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
 pub struct FMul {
     pub operand0: ConstantRef,
     pub operand1: ConstantRef,
@@ -721,7 +902,7 @@ pub struct FMul {
 impl_constexpr!(FMul, FMul);
 binop_same_type!(FMul, "fmul");
 
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
 pub struct FDiv {
     pub operand0: ConstantRef,
     pub operand1: ConstantRef,
@@ -730,7 +911,7 @@ pub struct FDiv {
 impl_constexpr!(FDiv, FDiv);
 binop_same_type!(FDiv, "fdiv");
 
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
 pub struct FRem {
     pub operand0: ConstantRef,
     pub operand1: ConstantRef,
@@ -739,7 +920,7 @@ pub struct FRem {
 impl_constexpr!(FRem, FRem);
 binop_same_type!(FRem, "frem");
 
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
 pub struct ExtractElement {
     pub vector: ConstantRef,
     pub index: ConstantRef,
@@ -765,7 +946,7 @@ impl Display for ExtractElement {
     }
 }
 
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
 pub struct InsertElement {
     pub vector: ConstantRef,
     pub element: ConstantRef,
@@ -790,7 +971,7 @@ impl Display for InsertElement {
     }
 }
 
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
 pub struct ShuffleVector {
     pub operand0: ConstantRef,
     pub operand1: ConstantRef,
@@ -806,16 +987,11 @@ impl Typed for ShuffleVector {
         debug_assert_eq!(ty, types.type_of(&self.operand1));
         match ty.as_ref() {
             Type::VectorType { element_type, .. } => match types.type_of(&self.mask).as_ref() {
-                #[cfg(feature = "llvm-11-or-greater")]
                 Type::VectorType {
                     num_elements,
                     scalable,
                     ..
                 } => types.vector_of(element_type.clone(), *num_elements, *scalable),
-                #[cfg(feature = "llvm-10-or-lower")]
-                Type::VectorType { num_elements, .. } => {
-                    types.vector_of(element_type.clone(), *num_elements)
-                },
                 ty => panic!(
                     "Expected a ShuffleVector mask to be VectorType, got {:?}",
                     ty
@@ -839,7 +1015,7 @@ impl Display for ShuffleVector {
     }
 }
 
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
 pub struct ExtractValue {
     pub aggregate: ConstantRef,
     pub indices: Vec<u32>,
@@ -884,7 +1060,7 @@ impl Display for ExtractValue {
     }
 }
 
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
 pub struct InsertValue {
     pub aggregate: ConstantRef,
     pub element: ConstantRef,
@@ -910,18 +1086,27 @@ impl Display for InsertValue {
     }
 }
 
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
 pub struct GetElementPtr {
     pub address: ConstantRef,
     pub indices: Vec<ConstantRef>,
     pub in_bounds: bool,
+    /// The type which the first index steps over. With typed pointers, this
+    /// is also the pointee type of `address`.
+    pub source_element_type: TypeRef,
 }
 
 impl_constexpr!(GetElementPtr, GetElementPtr);
 
 impl Typed for GetElementPtr {
     fn get_type(&self, types: &Types) -> TypeRef {
-        gep_type(types.type_of(&self.address), self.indices.iter(), types)
+        let address_type = types.type_of(&self.address);
+        match address_type.as_ref() {
+            Type::OpaquePointerType { addr_space } => {
+                types.opaque_pointer_in_addr_space(*addr_space)
+            },
+            _ => gep_type(address_type, self.indices.iter(), types),
+        }
     }
 }
 
@@ -982,7 +1167,7 @@ impl Display for GetElementPtr {
     }
 }
 
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
 pub struct Trunc {
     pub operand: ConstantRef,
     pub to_type: TypeRef,
@@ -991,7 +1176,7 @@ pub struct Trunc {
 impl_constexpr!(Trunc, Trunc);
 unop_explicitly_typed!(Trunc, "trunc");
 
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
 pub struct ZExt {
     pub operand: ConstantRef,
     pub to_type: TypeRef,
@@ -1000,7 +1185,7 @@ pub struct ZExt {
 impl_constexpr!(ZExt, ZExt);
 unop_explicitly_typed!(ZExt, "zext");
 
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
 pub struct SExt {
     pub operand: ConstantRef,
     pub to_type: TypeRef,
@@ -1009,7 +1194,7 @@ pub struct SExt {
 impl_constexpr!(SExt, SExt);
 unop_explicitly_typed!(SExt, "sext");
 
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
 pub struct FPTrunc {
     pub operand: ConstantRef,
     pub to_type: TypeRef,
@@ -1018,7 +1203,7 @@ pub struct FPTrunc {
 impl_constexpr!(FPTrunc, FPTrunc);
 unop_explicitly_typed!(FPTrunc, "fptrunc");
 
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
 pub struct FPExt {
     pub operand: ConstantRef,
     pub to_type: TypeRef,
@@ -1027,7 +1212,7 @@ pub struct FPExt {
 impl_constexpr!(FPExt, FPExt);
 unop_explicitly_typed!(FPExt, "fpext");
 
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
 pub struct FPToUI {
     pub operand: ConstantRef,
     pub to_type: TypeRef,
@@ -1036,7 +1221,7 @@ pub struct FPToUI {
 impl_constexpr!(FPToUI, FPToUI);
 unop_explicitly_typed!(FPToUI, "fptoui");
 
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
 pub struct FPToSI {
     pub operand: ConstantRef,
     pub to_type: TypeRef,
@@ -1045,7 +1230,7 @@ pub struct FPToSI {
 impl_constexpr!(FPToSI, FPToSI);
 unop_explicitly_typed!(FPToSI, "fptosi");
 
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
 pub struct UIToFP {
     pub operand: ConstantRef,
     pub to_type: TypeRef,
@@ -1054,7 +1239,7 @@ pub struct UIToFP {
 impl_constexpr!(UIToFP, UIToFP);
 unop_explicitly_typed!(UIToFP, "uitofp");
 
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
 pub struct SIToFP {
     pub operand: ConstantRef,
     pub to_type: TypeRef,
@@ -1063,7 +1248,7 @@ pub struct SIToFP {
 impl_constexpr!(SIToFP, SIToFP);
 unop_explicitly_typed!(SIToFP, "sitofp");
 
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
 pub struct PtrToInt {
     pub operand: ConstantRef,
     pub to_type: TypeRef,
@@ -1072,7 +1257,7 @@ pub struct PtrToInt {
 impl_constexpr!(PtrToInt, PtrToInt);
 unop_explicitly_typed!(PtrToInt, "ptrtoint");
 
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
 pub struct IntToPtr {
     pub operand: ConstantRef,
     pub to_type: TypeRef,
@@ -1081,7 +1266,7 @@ pub struct IntToPtr {
 impl_constexpr!(IntToPtr, IntToPtr);
 unop_explicitly_typed!(IntToPtr, "inttoptr");
 
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
 pub struct BitCast {
     pub operand: ConstantRef,
     pub to_type: TypeRef,
@@ -1090,7 +1275,7 @@ pub struct BitCast {
 impl_constexpr!(BitCast, BitCast);
 unop_explicitly_typed!(BitCast, "bitcast");
 
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
 pub struct AddrSpaceCast {
     pub operand: ConstantRef,
     pub to_type: TypeRef,
@@ -1099,7 +1284,7 @@ pub struct AddrSpaceCast {
 impl_constexpr!(AddrSpaceCast, AddrSpaceCast);
 unop_explicitly_typed!(AddrSpaceCast, "addrspacecast");
 
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
 pub struct ICmp {
     pub predicate: IntPredicate,
     pub operand0: ConstantRef,
@@ -1114,14 +1299,11 @@ impl Typed for ICmp {
         let ty = types.type_of(&self.operand0);
         debug_assert_eq!(ty, types.type_of(&self.operand1));
         match ty.as_ref() {
-            #[cfg(feature = "llvm-11-or-greater")]
             Type::VectorType {
                 num_elements,
                 scalable,
                 ..
             } => types.vector_of(types.bool(), *num_elements, *scalable),
-            #[cfg(feature = "llvm-10-or-lower")]
-            Type::VectorType { num_elements, .. } => types.vector_of(types.bool(), *num_elements),
             _ => types.bool(),
         }
     }
@@ -1137,7 +1319,7 @@ impl Display for ICmp {
     }
 }
 
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
 pub struct FCmp {
     pub predicate: FPPredicate,
     pub operand0: ConstantRef,
@@ -1152,14 +1334,11 @@ impl Typed for FCmp {
         let ty = types.type_of(&self.operand0);
         debug_assert_eq!(ty, types.type_of(&self.operand1));
         match ty.as_ref() {
-            #[cfg(feature = "llvm-11-or-greater")]
             Type::VectorType {
                 num_elements,
                 scalable,
                 ..
             } => types.vector_of(types.bool(), *num_elements, *scalable),
-            #[cfg(feature = "llvm-10-or-lower")]
-            Type::VectorType { num_elements, .. } => types.vector_of(types.bool(), *num_elements),
             _ => types.bool(),
         }
     }
@@ -1175,7 +1354,7 @@ impl Display for FCmp {
     }
 }
 
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
 pub struct Select {
     pub condition: ConstantRef,
     pub true_value: ConstantRef,
@@ -1206,19 +1385,26 @@ impl Display for Select {
 // from_llvm //
 // ********* //
 
+#[cfg(feature = "llvm-sys")]
+use crate::from_llvm::*;
+#[cfg(feature = "llvm-sys")]
 use crate::llvm_sys::*;
+#[cfg(feature = "llvm-sys")]
 use crate::module::ModuleContext;
-use std::collections::hash_map::Entry;
+#[cfg(feature = "llvm-sys")]
+use crate::name::Name;
 
+#[cfg(feature = "llvm-sys")]
 impl Constant {
     pub(crate) fn from_llvm_ref(constant: LLVMValueRef, ctx: &mut ModuleContext) -> ConstantRef {
-        if let Some(constantref) = ctx.constants.get(&constant) {
+        if let Some(constantref) = ctx.llvm_constant_map.get(&constant) {
             return constantref.clone();
         }
         let parsed = Self::parse_from_llvm_ref(constant, ctx);
-        match ctx.constants.entry(constant) {
+        let constantref = ctx.constants.intern(parsed);
+        match ctx.llvm_constant_map.entry(constant) {
             Entry::Occupied(_) => panic!("This case should have been handled above"),
-            Entry::Vacant(ventry) => ventry.insert(ConstantRef::new(parsed)).clone(),
+            Entry::Vacant(ventry) => ventry.insert(constantref).clone(),
         }
     }
 
@@ -1244,7 +1430,6 @@ impl Constant {
                 match ctx.types.type_from_llvm_ref( unsafe { LLVMTypeOf(constant) } ).as_ref() {
                     Type::FPType(fptype) => Constant::Float(match fptype {
                         FPType::Half => Float::Half,
-                        #[cfg(feature="llvm-11-or-greater")]
                         FPType::BFloat => Float::BFloat,
                         FPType::Single => Float::Single( unsafe {
                             let mut b = 0;
@@ -1264,13 +1449,13 @@ impl Constant {
                 }
             },
             LLVMValueKind::LLVMConstantStructValueKind => {
-                let (num_elements, is_packed) = match ctx.types.type_from_llvm_ref( unsafe { LLVMTypeOf(constant) } ).as_ref() {
-                    Type::StructType { element_types, is_packed } => (element_types.len(), *is_packed),
+                let (name, num_elements, is_packed) = match ctx.types.type_from_llvm_ref( unsafe { LLVMTypeOf(constant) } ).as_ref() {
+                    Type::StructType { element_types, is_packed } => (None, element_types.len(), *is_packed),
                     Type::NamedStructType { name } => match ctx.types.named_struct_def(name) {
                         NamedStructDef::Opaque => panic!("Constant of opaque struct type (struct name {:?})", name),
                         NamedStructDef::Defined(ty) => match ty.as_ref() {
                             Type::StructType { element_types, is_packed } => {
-                                (element_types.len(), *is_packed)
+                                (Some(name.clone()), element_types.len(), *is_packed)
                             },
                             ty => panic!("Expected NamedStructDef inner type to be a StructType, but it actually is a {:?}", ty),
                         },
@@ -1278,7 +1463,7 @@ impl Constant {
                     ty => panic!("Expected Constant::Struct to have type StructType or NamedStructType; got {:?}", ty),
                 };
                 Constant::Struct {
-                    name: None,  // --TODO not yet implemented: Constant::Struct name
+                    name,
                     values: {
                         (0 .. num_elements).map(|i| {
                             Constant::from_llvm_ref( unsafe { LLVMGetOperand(constant, i as u32) }, ctx)
@@ -1309,7 +1494,7 @@ impl Constant {
                     Type::ArrayType { element_type, num_elements } => Constant::Array {
                         element_type: element_type.clone(),
                         elements: {
-                            (0 .. *num_elements).map(|i| Constant::from_llvm_ref( unsafe { LLVMGetElementAsConstant(constant, i as u32) }, ctx)).collect()
+                            (0 .. *num_elements).map(|i| Constant::from_llvm_ref(ctx.with_context_lock(|| unsafe { get_element_as_constant(constant, i as u32) }), ctx)).collect()
                         },
                     },
                     ty => panic!("Expected ConstantDataArray to have type Type::ArrayType; got {:?}", ty),
@@ -1318,7 +1503,7 @@ impl Constant {
             LLVMValueKind::LLVMConstantDataVectorValueKind => {
                 match ctx.types.type_from_llvm_ref( unsafe { LLVMTypeOf(constant) } ).as_ref() {
                     Type::VectorType { num_elements, .. } => Constant::Vector(
-                        (0 .. *num_elements).map(|i| Constant::from_llvm_ref( unsafe { LLVMGetElementAsConstant(constant, i as u32) }, ctx)).collect()
+                        (0 .. *num_elements).map(|i| Constant::from_llvm_ref(ctx.with_context_lock(|| unsafe { get_element_as_constant(constant, i as u32) }), ctx)).collect()
                     ),
                     ty => panic!("Expected ConstantDataVector to have type Type::VectorType; got {:?}", ty),
                 }
@@ -1348,9 +1533,13 @@ impl Constant {
                     LLVMOpcode::LLVMAdd => Constant::Add(Add::from_llvm_ref(constant, ctx)),
                     LLVMOpcode::LLVMSub => Constant::Sub(Sub::from_llvm_ref(constant, ctx)),
                     LLVMOpcode::LLVMMul => Constant::Mul(Mul::from_llvm_ref(constant, ctx)),
+                    #[cfg(feature = "llvm-14-or-lower")]
                     LLVMOpcode::LLVMUDiv => Constant::UDiv(UDiv::from_llvm_ref(constant, ctx)),
+                    #[cfg(feature = "llvm-14-or-lower")]
                     LLVMOpcode::LLVMSDiv => Constant::SDiv(SDiv::from_llvm_ref(constant, ctx)),
+                    #[cfg(feature = "llvm-14-or-lower")]
                     LLVMOpcode::LLVMURem => Constant::URem(URem::from_llvm_ref(constant, ctx)),
+                    #[cfg(feature = "llvm-14-or-lower")]
                     LLVMOpcode::LLVMSRem => Constant::SRem(SRem::from_llvm_ref(constant, ctx)),
                     LLVMOpcode::LLVMAnd => Constant::And(And::from_llvm_ref(constant, ctx)),
                     LLVMOpcode::LLVMOr => Constant::Or(Or::from_llvm_ref(constant, ctx)),
@@ -1358,15 +1547,22 @@ impl Constant {
                     LLVMOpcode::LLVMShl => Constant::Shl(Shl::from_llvm_ref(constant, ctx)),
                     LLVMOpcode::LLVMLShr => Constant::LShr(LShr::from_llvm_ref(constant, ctx)),
                     LLVMOpcode::LLVMAShr => Constant::AShr(AShr::from_llvm_ref(constant, ctx)),
+                    #[cfg(feature = "llvm-14-or-lower")]
                     LLVMOpcode::LLVMFAdd => Constant::FAdd(FAdd::from_llvm_ref(constant, ctx)),
+                    #[cfg(feature = "llvm-14-or-lower")]
                     LLVMOpcode::LLVMFSub => Constant::FSub(FSub::from_llvm_ref(constant, ctx)),
+                    #[cfg(feature = "llvm-14-or-lower")]
                     LLVMOpcode::LLVMFMul => Constant::FMul(FMul::from_llvm_ref(constant, ctx)),
+                    #[cfg(feature = "llvm-14-or-lower")]
                     LLVMOpcode::LLVMFDiv => Constant::FDiv(FDiv::from_llvm_ref(constant, ctx)),
+                    #[cfg(feature = "llvm-14-or-lower")]
                     LLVMOpcode::LLVMFRem => Constant::FRem(FRem::from_llvm_ref(constant, ctx)),
                     LLVMOpcode::LLVMExtractElement => Constant::ExtractElement(ExtractElement::from_llvm_ref(constant, ctx)),
                     LLVMOpcode::LLVMInsertElement => Constant::InsertElement(InsertElement::from_llvm_ref(constant, ctx)),
                     LLVMOpcode::LLVMShuffleVector => Constant::ShuffleVector(ShuffleVector::from_llvm_ref(constant, ctx)),
+                    #[cfg(feature = "llvm-14-or-lower")]
                     LLVMOpcode::LLVMExtractValue => Constant::ExtractValue(ExtractValue::from_llvm_ref(constant, ctx)),
+                    #[cfg(feature = "llvm-14-or-lower")]
                     LLVMOpcode::LLVMInsertValue => Constant::InsertValue(InsertValue::from_llvm_ref(constant, ctx)),
                     LLVMOpcode::LLVMGetElementPtr => Constant::GetElementPtr(GetElementPtr::from_llvm_ref(constant, ctx)),
                     LLVMOpcode::LLVMTrunc => Constant::Trunc(Trunc::from_llvm_ref(constant, ctx)),
@@ -1384,6 +1580,7 @@ impl Constant {
                     LLVMOpcode::LLVMAddrSpaceCast => Constant::AddrSpaceCast(AddrSpaceCast::from_llvm_ref(constant, ctx)),
                     LLVMOpcode::LLVMICmp => Constant::ICmp(ICmp::from_llvm_ref(constant, ctx)),
                     LLVMOpcode::LLVMFCmp => Constant::FCmp(FCmp::from_llvm_ref(constant, ctx)),
+                    #[cfg(feature = "llvm-16-or-lower")]
                     LLVMOpcode::LLVMSelect => Constant::Select(Select::from_llvm_ref(constant, ctx)),
                     opcode => panic!("ConstantExpr has unexpected opcode {:?}", opcode),
                 }
@@ -1391,7 +1588,7 @@ impl Constant {
             _ if unsafe { !LLVMIsAGlobalValue(constant).is_null() } => {
                 Constant::GlobalReference {
                     name: match ctx.global_names.get(&constant) {
-                        Some(Name::Name(n)) => (**n).clone(),
+                        Some(Name::Name(n)) => n.to_string(),
                         Some(Name::Number(n)) => panic!("Expected global variable or function to have a real name, not a number {}", n),
                         None => {
                             let names: Vec<_> = ctx.global_names.values().collect();
@@ -1407,38 +1604,98 @@ impl Constant {
 }
 
 macro_rules! binop_from_llvm {
-    ($expr:ident) => {
+    ($expr:ident $(, $flag:ident)*) => {
+        #[cfg(feature = "llvm-sys")]
         impl $expr {
             pub(crate) fn from_llvm_ref(expr: LLVMValueRef, ctx: &mut ModuleContext) -> Self {
                 assert_eq!(unsafe { LLVMGetNumOperands(expr) }, 2);
+                #[allow(unused_variables)]
+                let keywords = flag_keywords(expr, &[$(stringify!($flag)),*], ctx);
                 Self {
                     operand0: Constant::from_llvm_ref(unsafe { LLVMGetOperand(expr, 0) }, ctx),
                     operand1: Constant::from_llvm_ref(unsafe { LLVMGetOperand(expr, 1) }, ctx),
+                    $($flag: keywords.iter().any(|keyword| keyword == stringify!($flag)),)*
                 }
             }
         }
     };
 }
 
-binop_from_llvm!(Add);
-binop_from_llvm!(Sub);
-binop_from_llvm!(Mul);
-binop_from_llvm!(UDiv);
-binop_from_llvm!(SDiv);
+/// `nsw`, `nuw`, and `exact` aren't exposed in the LLVM C API (only the C++
+/// one; LLVM 17's getters only work on instructions), so we get them from the
+/// constant expression's textual form, which looks like
+/// `i64 add nuw nsw (i64 ptrtoint (...), i64 1)`. Unlike an instruction, a
+/// constant expression is printed without numbering the rest of the module, so
+/// this is cheap. If the expression can't have any `flags`, we skip it anyway.
+#[cfg(feature = "llvm-sys")]
+fn flag_keywords(expr: LLVMValueRef, flags: &[&str], ctx: &ModuleContext) -> Vec<String> {
+    if flags.is_empty() {
+        return vec![];
+    }
+    // printing can create constants in the `LLVMContext`
+    let text = ctx.with_context_lock(|| unsafe { print_to_owned_string(expr) });
+    let opcode = unsafe { LLVMGetConstOpcode(expr) };
+    let opcode = format!(" {} ", opcode_keyword(opcode));
+    match text.find(&opcode) {
+        Some(i) => leading_keywords(&text[i + 1 ..]),
+        None => vec![],
+    }
+}
+
+#[cfg(feature = "llvm-sys")]
+fn opcode_keyword(opcode: llvm_sys::LLVMOpcode) -> &'static str {
+    use llvm_sys::LLVMOpcode;
+    match opcode {
+        LLVMOpcode::LLVMAdd => "add",
+        LLVMOpcode::LLVMSub => "sub",
+        LLVMOpcode::LLVMMul => "mul",
+        LLVMOpcode::LLVMUDiv => "udiv",
+        LLVMOpcode::LLVMSDiv => "sdiv",
+        LLVMOpcode::LLVMShl => "shl",
+        LLVMOpcode::LLVMLShr => "lshr",
+        LLVMOpcode::LLVMAShr => "ashr",
+        opcode => panic!("Constant expression with opcode {:?} can't have flags", opcode),
+    }
+}
+
+binop_from_llvm!(Add, nsw, nuw);
+binop_from_llvm!(Sub, nsw, nuw);
+binop_from_llvm!(Mul, nsw, nuw);
+#[cfg(feature = "llvm-sys")]
+#[cfg(feature = "llvm-14-or-lower")]
+binop_from_llvm!(UDiv, exact);
+#[cfg(feature = "llvm-sys")]
+#[cfg(feature = "llvm-14-or-lower")]
+binop_from_llvm!(SDiv, exact);
+#[cfg(feature = "llvm-sys")]
+#[cfg(feature = "llvm-14-or-lower")]
 binop_from_llvm!(URem);
+#[cfg(feature = "llvm-sys")]
+#[cfg(feature = "llvm-14-or-lower")]
 binop_from_llvm!(SRem);
 binop_from_llvm!(And);
 binop_from_llvm!(Or);
 binop_from_llvm!(Xor);
-binop_from_llvm!(Shl);
-binop_from_llvm!(LShr);
-binop_from_llvm!(AShr);
+binop_from_llvm!(Shl, nsw, nuw);
+binop_from_llvm!(LShr, exact);
+binop_from_llvm!(AShr, exact);
+#[cfg(feature = "llvm-sys")]
+#[cfg(feature = "llvm-14-or-lower")]
 binop_from_llvm!(FAdd);
+#[cfg(feature = "llvm-sys")]
+#[cfg(feature = "llvm-14-or-lower")]
 binop_from_llvm!(FSub);
+#[cfg(feature = "llvm-sys")]
+#[cfg(feature = "llvm-14-or-lower")]
 binop_from_llvm!(FMul);
+#[cfg(feature = "llvm-sys")]
+#[cfg(feature = "llvm-14-or-lower")]
 binop_from_llvm!(FDiv);
+#[cfg(feature = "llvm-sys")]
+#[cfg(feature = "llvm-14-or-lower")]
 binop_from_llvm!(FRem);
 
+#[cfg(feature = "llvm-sys")]
 impl ExtractElement {
     pub(crate) fn from_llvm_ref(expr: LLVMValueRef, ctx: &mut ModuleContext) -> Self {
         assert_eq!(unsafe { LLVMGetNumOperands(expr) }, 2);
@@ -1449,6 +1706,7 @@ impl ExtractElement {
     }
 }
 
+#[cfg(feature = "llvm-sys")]
 impl InsertElement {
     pub(crate) fn from_llvm_ref(expr: LLVMValueRef, ctx: &mut ModuleContext) -> Self {
         assert_eq!(unsafe { LLVMGetNumOperands(expr) }, 3);
@@ -1460,6 +1718,7 @@ impl InsertElement {
     }
 }
 
+#[cfg(feature = "llvm-sys")]
 impl ShuffleVector {
     #[cfg(feature = "llvm-10-or-lower")]
     pub(crate) fn from_llvm_ref(expr: LLVMValueRef, ctx: &mut ModuleContext) -> Self {
@@ -1480,6 +1739,7 @@ impl ShuffleVector {
     }
 }
 
+#[cfg(all(feature = "llvm-sys", feature = "llvm-14-or-lower"))]
 impl ExtractValue {
     pub(crate) fn from_llvm_ref(expr: LLVMValueRef, ctx: &mut ModuleContext) -> Self {
         assert_eq!(unsafe { LLVMGetNumOperands(expr) }, 2);
@@ -1494,6 +1754,7 @@ impl ExtractValue {
     }
 }
 
+#[cfg(all(feature = "llvm-sys", feature = "llvm-14-or-lower"))]
 impl InsertValue {
     pub(crate) fn from_llvm_ref(expr: LLVMValueRef, ctx: &mut ModuleContext) -> Self {
         assert_eq!(unsafe { LLVMGetNumOperands(expr) }, 3);
@@ -1509,6 +1770,7 @@ impl InsertValue {
     }
 }
 
+#[cfg(feature = "llvm-sys")]
 impl GetElementPtr {
     pub(crate) fn from_llvm_ref(expr: LLVMValueRef, ctx: &mut ModuleContext) -> Self {
         Self {
@@ -1520,6 +1782,9 @@ impl GetElementPtr {
                     .collect()
             },
             in_bounds: unsafe { LLVMIsInBounds(expr) } != 0,
+            source_element_type: ctx
+                .types
+                .type_from_llvm_ref(unsafe { get_gep_source_element_type(expr) }),
         }
     }
 }
@@ -1528,6 +1793,7 @@ impl GetElementPtr {
 //   knowing only their operands.
 macro_rules! typed_unop_from_llvm {
     ($expr:ident) => {
+        #[cfg(feature = "llvm-sys")]
         impl $expr {
             pub(crate) fn from_llvm_ref(expr: LLVMValueRef, ctx: &mut ModuleContext) -> Self {
                 assert_eq!(unsafe { LLVMGetNumOperands(expr) }, 1);
@@ -1554,6 +1820,7 @@ typed_unop_from_llvm!(IntToPtr);
 typed_unop_from_llvm!(BitCast);
 typed_unop_from_llvm!(AddrSpaceCast);
 
+#[cfg(feature = "llvm-sys")]
 impl ICmp {
     pub(crate) fn from_llvm_ref(expr: LLVMValueRef, ctx: &mut ModuleContext) -> Self {
         assert_eq!(unsafe { LLVMGetNumOperands(expr) }, 2);
@@ -1565,6 +1832,7 @@ impl ICmp {
     }
 }
 
+#[cfg(feature = "llvm-sys")]
 impl FCmp {
     pub(crate) fn from_llvm_ref(expr: LLVMValueRef, ctx: &mut ModuleContext) -> Self {
         assert_eq!(unsafe { LLVMGetNumOperands(expr) }, 2);
@@ -1576,6 +1844,7 @@ impl FCmp {
     }
 }
 
+#[cfg(all(feature = "llvm-sys", feature = "llvm-16-or-lower"))]
 impl Select {
     pub(crate) fn from_llvm_ref(expr: LLVMValueRef, ctx: &mut ModuleContext) -> Self {
         assert_eq!(unsafe { LLVMGetNumOperands(expr) }, 3);
@@ -0,0 +1,676 @@
+//! A generic framework for dataflow analyses over a `Function`.
+//!
+//! An analysis implements [`Analysis`](trait.Analysis.html): it chooses a
+//! [`Lattice`](trait.Lattice.html) for its states, a
+//! [`Direction`](enum.Direction.html), and transfer functions for
+//! instructions, terminators, and CFG edges. [`analyze()`](fn.analyze.html)
+//! runs the analysis to a fixpoint and returns
+//! [`DataflowResults`](struct.DataflowResults.html), which give the state at
+//! the entry and exit of each block, and before and after each instruction.
+//!
+//! `Phi`s are treated as taking effect on the incoming edges of their block:
+//! the framework never passes a `Phi` to `transfer_instruction()`, but
+//! instead calls `transfer_phis()` once per incoming edge, with the `Phi`s of
+//! the destination block and the name of the predecessor. Consequently, the
+//! state at a block's entry is the state just after its `Phi`s.
+//!
+//! The indirect destinations of a `CallBr` aren't available (see
+//! `CallBr::other_labels`), so the only edge from a `CallBr` is to its
+//! `return_label`. A block which is reached only through an indirect
+//! destination has no predecessors as far as the framework is concerned: a
+//! `Forward` analysis sees `bottom()` at its entry, and a `Backward`
+//! analysis doesn't propagate its entry state to the `CallBr`.
+//!
+//! [`Liveness`](struct.Liveness.html) and
+//! [`ReachingDefinitions`](struct.ReachingDefinitions.html) are provided as
+//! ready-made analyses.
+
+use crate::basicblock::BasicBlock;
+use crate::constant::ConstantRef;
+use crate::function::Function;
+use crate::instruction::Instruction;
+use crate::name::Name;
+use crate::operand::Operand;
+use crate::terminator::Terminator;
+use either::Either;
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+use std::hash::Hash;
+
+/// The states of a dataflow analysis.
+pub trait Lattice: Clone + PartialEq {
+    /// Replace `self` with the least upper bound of `self` and `other`.
+    /// Returns `true` if `self` changed.
+    fn join(&mut self, other: &Self) -> bool;
+}
+
+/// `false` is bottom; join is logical or
+impl Lattice for bool {
+    fn join(&mut self, other: &Self) -> bool {
+        let changed = !*self && *other;
+        *self |= *other;
+        changed
+    }
+}
+
+/// The empty set is bottom; join is union
+impl<T: Clone + Eq + Hash> Lattice for HashSet<T> {
+    fn join(&mut self, other: &Self) -> bool {
+        let len = self.len();
+        self.extend(other.iter().cloned());
+        self.len() != len
+    }
+}
+
+/// The empty set is bottom; join is union
+impl<T: Clone + Ord> Lattice for BTreeSet<T> {
+    fn join(&mut self, other: &Self) -> bool {
+        let len = self.len();
+        self.extend(other.iter().cloned());
+        self.len() != len
+    }
+}
+
+/// Which way an analysis propagates information
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Hash)]
+pub enum Direction {
+    /// From each block's entry to its exit, and from predecessors to
+    /// successors
+    Forward,
+    /// From each block's exit to its entry, and from successors to
+    /// predecessors
+    Backward,
+}
+
+/// A CFG edge, from the terminator of one block to another block
+#[derive(PartialEq, Clone, Debug)]
+pub struct Edge<'a> {
+    /// Name of the block whose terminator this edge leaves from
+    pub from: &'a Name,
+    /// Name of the destination block
+    pub to: &'a Name,
+    pub kind: EdgeKind<'a>,
+}
+
+/// What condition, if any, is known to hold along an `Edge`
+#[derive(PartialEq, Clone, Debug)]
+pub enum EdgeKind<'a> {
+    /// The edge of a `Br`
+    Unconditional,
+    /// An edge of a `CondBr`, which is taken when `condition` is `taken`
+    Branch { condition: &'a Operand, taken: bool },
+    /// A case of a `Switch`, which is taken when `operand` is `value`
+    SwitchCase {
+        operand: &'a Operand,
+        value: &'a ConstantRef,
+    },
+    /// The default destination of a `Switch`, which is taken when `operand`
+    /// matches none of the `case_values`
+    SwitchDefault {
+        operand: &'a Operand,
+        case_values: Vec<&'a ConstantRef>,
+    },
+    /// An edge of any other terminator (e.g., `Invoke` or `IndirectBr`)
+    Other,
+}
+
+impl<'a> Edge<'a> {
+    /// Get the outgoing edges of the given block. A block may have several
+    /// edges to the same destination, e.g., a `CondBr` whose `true_dest`
+    /// and `false_dest` are the same.
+    ///
+    /// For a `CallBr`, this gives only the edge to its `return_label`, as its
+    /// indirect destinations aren't available.
+    pub fn outgoing(bb: &'a BasicBlock) -> Vec<Edge<'a>> {
+        let edge = |to, kind| Edge {
+            from: &bb.name,
+            to,
+            kind,
+        };
+        match &bb.term {
+            Terminator::Ret(_) | Terminator::Resume(_) | Terminator::Unreachable(_) => vec![],
+            Terminator::Br(br) => vec![edge(&br.dest, EdgeKind::Unconditional)],
+            Terminator::CondBr(condbr) => vec![
+                edge(
+                    &condbr.true_dest,
+                    EdgeKind::Branch {
+                        condition: &condbr.condition,
+                        taken: true,
+                    },
+                ),
+                edge(
+                    &condbr.false_dest,
+                    EdgeKind::Branch {
+                        condition: &condbr.condition,
+                        taken: false,
+                    },
+                ),
+            ],
+            Terminator::Switch(switch) => switch
+                .dests
+                .iter()
+                .map(|(value, dest)| {
+                    edge(
+                        dest,
+                        EdgeKind::SwitchCase {
+                            operand: &switch.operand,
+                            value,
+                        },
+                    )
+                })
+                .chain(std::iter::once(edge(
+                    &switch.default_dest,
+                    EdgeKind::SwitchDefault {
+                        operand: &switch.operand,
+                        case_values: switch.dests.iter().map(|(value, _)| value).collect(),
+                    },
+                )))
+                .collect(),
+            Terminator::IndirectBr(ibr) => ibr
+                .possible_dests
+                .iter()
+                .map(|dest| edge(dest, EdgeKind::Other))
+                .collect(),
+            Terminator::Invoke(invoke) => vec![
+                edge(&invoke.return_label, EdgeKind::Other),
+                edge(&invoke.exception_label, EdgeKind::Other),
+            ],
+            Terminator::CleanupRet(cleanupret) => cleanupret
+                .unwind_dest
+                .iter()
+                .map(|dest| edge(dest, EdgeKind::Other))
+                .collect(),
+            Terminator::CatchRet(catchret) => vec![edge(&catchret.successor, EdgeKind::Other)],
+            Terminator::CatchSwitch(catchswitch) => catchswitch
+                .catch_handlers
+                .iter()
+                .chain(catchswitch.default_unwind_dest.iter())
+                .map(|dest| edge(dest, EdgeKind::Other))
+                .collect(),
+            // the indirect destinations aren't available; see `CallBr::other_labels`
+            Terminator::CallBr(callbr) => vec![edge(&callbr.return_label, EdgeKind::Other)],
+        }
+    }
+}
+
+/// A dataflow analysis, to be run with [`analyze()`](fn.analyze.html).
+///
+/// For a `Forward` analysis, the transfer functions map the state before an
+/// instruction (or edge) to the state after it. For a `Backward` analysis,
+/// they map the state after it to the state before it.
+pub trait Analysis<'a> {
+    type Domain: Lattice;
+
+    fn direction(&self) -> Direction;
+
+    /// The state at the entry of the function (for a `Forward` analysis),
+    /// or at the exit of each block without successors (for a `Backward`
+    /// analysis)
+    fn boundary(&self, func: &'a Function) -> Self::Domain;
+
+    /// The least state, from which all other states are computed
+    fn bottom(&self, func: &'a Function) -> Self::Domain;
+
+    /// Transfer function for a non-`Phi` instruction
+    fn transfer_instruction(&self, inst: &'a Instruction, state: &mut Self::Domain);
+
+    /// Transfer function for a terminator. The default does nothing.
+    fn transfer_terminator(&self, _term: &'a Terminator, _state: &mut Self::Domain) {}
+
+    /// Transfer function for the `Phi`s at the start of a block, on the edge
+    /// from the block named `pred`. `phis` are all `Instruction::Phi`, and
+    /// take effect simultaneously.
+    ///
+    /// The default passes each `Phi` to `transfer_instruction()`, in
+    /// program order for a `Forward` analysis or in reverse for a `Backward`
+    /// one.
+    fn transfer_phis(&self, phis: &'a [Instruction], _pred: &'a Name, state: &mut Self::Domain) {
+        match self.direction() {
+            Direction::Forward => {
+                for phi in phis {
+                    self.transfer_instruction(phi, state);
+                }
+            },
+            Direction::Backward => {
+                for phi in phis.iter().rev() {
+                    self.transfer_instruction(phi, state);
+                }
+            },
+        }
+    }
+
+    /// Transfer function for a CFG edge. This is applied between the
+    /// terminator of `edge.from` and the `Phi`s of `edge.to`, and may refine
+    /// the state using what is known along the edge (e.g., `EdgeKind::Branch`
+    /// tells which way a condition went). The default does nothing.
+    fn transfer_edge(&self, _edge: &Edge<'a>, _state: &mut Self::Domain) {}
+}
+
+/// The fixpoint of an `Analysis` over a `Function`
+pub struct DataflowResults<'a, A: Analysis<'a>> {
+    analysis: A,
+    func: &'a Function,
+    block_indices: HashMap<&'a Name, usize>,
+    entry_states: HashMap<&'a Name, A::Domain>,
+    exit_states: HashMap<&'a Name, A::Domain>,
+}
+
+/// Run the given analysis over the given function to a fixpoint.
+///
+/// The analysis's transfer functions must be monotone, and its `Domain`
+/// must not have infinite ascending chains, or this may not terminate.
+pub fn analyze<'a, A: Analysis<'a>>(func: &'a Function, analysis: A) -> DataflowResults<'a, A> {
+    let block_indices: HashMap<&'a Name, usize> = func
+        .basic_blocks
+        .iter()
+        .enumerate()
+        .map(|(i, bb)| (&bb.name, i))
+        .collect();
+    let get_bb = |name: &Name| -> &'a BasicBlock {
+        match block_indices.get(name) {
+            Some(&i) => &func.basic_blocks[i],
+            None => panic!("Function {} has no block {}", func.name, name),
+        }
+    };
+    let mut succs: HashMap<&'a Name, Vec<Edge<'a>>> = HashMap::new();
+    let mut preds: HashMap<&'a Name, Vec<Edge<'a>>> = HashMap::new();
+    for bb in &func.basic_blocks {
+        for edge in Edge::outgoing(bb) {
+            preds.entry(edge.to).or_default().push(edge.clone());
+            succs.entry(&bb.name).or_default().push(edge);
+        }
+    }
+    let no_edges = Vec::new();
+    let direction = analysis.direction();
+    let bottom = analysis.bottom(func);
+    let mut entry_states: HashMap<&'a Name, A::Domain> = HashMap::new();
+    let mut exit_states: HashMap<&'a Name, A::Domain> = HashMap::new();
+
+    // every block is processed at least once; then a block is reprocessed
+    // whenever an input to it changes
+    let mut worklist: VecDeque<&'a BasicBlock> = match direction {
+        Direction::Forward => func.basic_blocks.iter().collect(),
+        Direction::Backward => func.basic_blocks.iter().rev().collect(),
+    };
+    let mut on_worklist: HashSet<&'a Name> = func.basic_blocks.iter().map(|bb| &bb.name).collect();
+    while let Some(bb) = worklist.pop_front() {
+        on_worklist.remove(&bb.name);
+        let phis = leading_phis(bb);
+        let body = &bb.instrs[phis.len() ..];
+        let (changed, next): (bool, Vec<&'a Name>) = match direction {
+            Direction::Forward => {
+                let incoming = preds.get(&bb.name).unwrap_or(&no_edges);
+                let mut state = if std::ptr::eq(bb, &func.basic_blocks[0]) {
+                    analysis.boundary(func)
+                } else {
+                    bottom.clone()
+                };
+                for edge in incoming {
+                    if let Some(pred_state) = exit_states.get(edge.from) {
+                        let mut edge_state = pred_state.clone();
+                        analysis.transfer_edge(edge, &mut edge_state);
+                        analysis.transfer_phis(phis, edge.from, &mut edge_state);
+                        state.join(&edge_state);
+                    }
+                }
+                entry_states.insert(&bb.name, state.clone());
+                for inst in body {
+                    analysis.transfer_instruction(inst, &mut state);
+                }
+                analysis.transfer_terminator(&bb.term, &mut state);
+                let changed = exit_states.get(&bb.name) != Some(&state);
+                exit_states.insert(&bb.name, state);
+                let outgoing = succs.get(&bb.name).unwrap_or(&no_edges);
+                (changed, outgoing.iter().map(|edge| edge.to).collect())
+            },
+            Direction::Backward => {
+                let outgoing = succs.get(&bb.name).unwrap_or(&no_edges);
+                let mut state = if outgoing.is_empty() {
+                    analysis.boundary(func)
+                } else {
+                    bottom.clone()
+                };
+                for edge in outgoing {
+                    if let Some(succ_state) = entry_states.get(edge.to) {
+                        let mut edge_state = succ_state.clone();
+                        analysis.transfer_phis(
+                            leading_phis(get_bb(edge.to)),
+                            &bb.name,
+                            &mut edge_state,
+                        );
+                        analysis.transfer_edge(edge, &mut edge_state);
+                        state.join(&edge_state);
+                    }
+                }
+                exit_states.insert(&bb.name, state.clone());
+                analysis.transfer_terminator(&bb.term, &mut state);
+                for inst in body.iter().rev() {
+                    analysis.transfer_instruction(inst, &mut state);
+                }
+                let changed = entry_states.get(&bb.name) != Some(&state);
+                entry_states.insert(&bb.name, state);
+                let incoming = preds.get(&bb.name).unwrap_or(&no_edges);
+                (changed, incoming.iter().map(|edge| edge.from).collect())
+            },
+        };
+        if changed {
+            for name in next {
+                if on_worklist.insert(name) {
+                    worklist.push_back(get_bb(name));
+                }
+            }
+        }
+    }
+
+    DataflowResults {
+        analysis,
+        func,
+        block_indices,
+        entry_states,
+        exit_states,
+    }
+}
+
+/// The `Phi`s at the start of the given block
+fn leading_phis(bb: &BasicBlock) -> &[Instruction] {
+    let num_phis = bb
+        .instrs
+        .iter()
+        .take_while(|inst| matches!(inst, Instruction::Phi(_)))
+        .count();
+    &bb.instrs[.. num_phis]
+}
+
+impl<'a, A: Analysis<'a>> DataflowResults<'a, A> {
+    /// Get the analysis which produced these results
+    pub fn analysis(&self) -> &A {
+        &self.analysis
+    }
+
+    /// The state at the entry of the block with the given name (just after
+    /// its `Phi`s)
+    pub fn block_entry(&self, block: &Name) -> &A::Domain {
+        self.entry_states
+            .get(block)
+            .unwrap_or_else(|| panic!("Function {} has no block {}", self.func.name, block))
+    }
+
+    /// The state at the exit of the block with the given name (just after
+    /// its terminator)
+    pub fn block_exit(&self, block: &Name) -> &A::Domain {
+        self.exit_states
+            .get(block)
+            .unwrap_or_else(|| panic!("Function {} has no block {}", self.func.name, block))
+    }
+
+    /// The state just before the instruction at index `index` in the block
+    /// with the given name, in program order. `index == instrs.len()` refers
+    /// to the terminator. For `Phi`s, this is the block's entry state.
+    pub fn before(&self, block: &Name, index: usize) -> A::Domain {
+        let (bb, num_phis) = self.locate(block, index);
+        match self.analysis.direction() {
+            Direction::Forward => {
+                let mut state = self.block_entry(block).clone();
+                for inst in &bb.instrs[num_phis .. index.max(num_phis)] {
+                    self.analysis.transfer_instruction(inst, &mut state);
+                }
+                state
+            },
+            Direction::Backward => {
+                let mut state = self.after(block, index);
+                if index == bb.instrs.len() {
+                    self.analysis.transfer_terminator(&bb.term, &mut state);
+                } else if index >= num_phis {
+                    self.analysis
+                        .transfer_instruction(&bb.instrs[index], &mut state);
+                }
+                state
+            },
+        }
+    }
+
+    /// The state just after the instruction at index `index` in the block
+    /// with the given name, in program order. `index == instrs.len()` refers
+    /// to the terminator. For `Phi`s, this is the block's entry state.
+    pub fn after(&self, block: &Name, index: usize) -> A::Domain {
+        let (bb, num_phis) = self.locate(block, index);
+        match self.analysis.direction() {
+            Direction::Forward => {
+                let mut state = self.before(block, index);
+                if index == bb.instrs.len() {
+                    self.analysis.transfer_terminator(&bb.term, &mut state);
+                } else if index >= num_phis {
+                    self.analysis
+                        .transfer_instruction(&bb.instrs[index], &mut state);
+                }
+                state
+            },
+            Direction::Backward => {
+                let mut state = self.block_exit(block).clone();
+                if index == bb.instrs.len() {
+                    return state;
+                }
+                self.analysis.transfer_terminator(&bb.term, &mut state);
+                for inst in bb.instrs[(index + 1).max(num_phis) ..].iter().rev() {
+                    self.analysis.transfer_instruction(inst, &mut state);
+                }
+                state
+            },
+        }
+    }
+
+    fn locate(&self, block: &Name, index: usize) -> (&'a BasicBlock, usize) {
+        let bb = match self.block_indices.get(block) {
+            Some(&i) => &self.func.basic_blocks[i],
+            None => panic!("Function {} has no block {}", self.func.name, block),
+        };
+        assert!(
+            index <= bb.instrs.len(),
+            "Block {} has no instruction {}",
+            block,
+            index
+        );
+        (bb, leading_phis(bb).len())
+    }
+}
+
+/// Liveness of local variables, as a `Backward` analysis. The state is the
+/// set of names of variables which are live, i.e., which may be used later
+/// before being redefined.
+///
+/// A `Phi`'s incoming value is live only on the edge from the corresponding
+/// predecessor, not at the entry of the `Phi`'s block.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Liveness;
+
+impl<'a> Analysis<'a> for Liveness {
+    type Domain = HashSet<&'a Name>;
+
+    fn direction(&self) -> Direction {
+        Direction::Backward
+    }
+
+    fn boundary(&self, _func: &'a Function) -> Self::Domain {
+        HashSet::new()
+    }
+
+    fn bottom(&self, _func: &'a Function) -> Self::Domain {
+        HashSet::new()
+    }
+
+    fn transfer_instruction(&self, inst: &'a Instruction, state: &mut Self::Domain) {
+        if let Some(dest) = inst.try_get_result() {
+            state.remove(dest);
+        }
+        state.extend(locals(instruction_operands(inst)));
+    }
+
+    fn transfer_terminator(&self, term: &'a Terminator, state: &mut Self::Domain) {
+        if let Some(result) = term.try_get_result() {
+            state.remove(result);
+        }
+        state.extend(locals(terminator_operands(term)));
+    }
+
+    fn transfer_phis(&self, phis: &'a [Instruction], pred: &'a Name, state: &mut Self::Domain) {
+        for inst in phis {
+            if let Instruction::Phi(phi) = inst {
+                state.remove(&phi.dest);
+            }
+        }
+        for inst in phis {
+            if let Instruction::Phi(phi) = inst {
+                let incoming = phi
+                    .incoming_values
+                    .iter()
+                    .filter(|(_, from)| from == pred)
+                    .map(|(op, _)| op);
+                state.extend(locals(incoming));
+            }
+        }
+    }
+}
+
+/// Reaching definitions, as a `Forward` analysis. The state is the set of
+/// names of variables (including parameters) whose definitions may reach
+/// the current point.
+///
+/// Since LLVM IR is in SSA form, each name has exactly one definition, so
+/// this is the set of names defined on some path to the current point.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ReachingDefinitions;
+
+impl<'a> Analysis<'a> for ReachingDefinitions {
+    type Domain = HashSet<&'a Name>;
+
+    fn direction(&self) -> Direction {
+        Direction::Forward
+    }
+
+    fn boundary(&self, func: &'a Function) -> Self::Domain {
+        func.parameters.iter().map(|param| &param.name).collect()
+    }
+
+    fn bottom(&self, _func: &'a Function) -> Self::Domain {
+        HashSet::new()
+    }
+
+    fn transfer_instruction(&self, inst: &'a Instruction, state: &mut Self::Domain) {
+        if let Some(dest) = inst.try_get_result() {
+            state.insert(dest);
+        }
+    }
+
+    fn transfer_terminator(&self, term: &'a Terminator, state: &mut Self::Domain) {
+        if let Some(result) = term.try_get_result() {
+            state.insert(result);
+        }
+    }
+}
+
+/// Names of the local variables among the given operands
+fn locals<'a>(operands: impl IntoIterator<Item = &'a Operand>) -> impl Iterator<Item = &'a Name> {
+    operands.into_iter().filter_map(|op| match op {
+        Operand::LocalOperand { name, .. } => Some(name),
+        _ => None,
+    })
+}
+
+/// All of the operands used by the given instruction
+fn instruction_operands(inst: &Instruction) -> Vec<&Operand> {
+    match inst {
+        Instruction::Add(i) => vec![&i.operand0, &i.operand1],
+        Instruction::Sub(i) => vec![&i.operand0, &i.operand1],
+        Instruction::Mul(i) => vec![&i.operand0, &i.operand1],
+        Instruction::UDiv(i) => vec![&i.operand0, &i.operand1],
+        Instruction::SDiv(i) => vec![&i.operand0, &i.operand1],
+        Instruction::URem(i) => vec![&i.operand0, &i.operand1],
+        Instruction::SRem(i) => vec![&i.operand0, &i.operand1],
+        Instruction::And(i) => vec![&i.operand0, &i.operand1],
+        Instruction::Or(i) => vec![&i.operand0, &i.operand1],
+        Instruction::Xor(i) => vec![&i.operand0, &i.operand1],
+        Instruction::Shl(i) => vec![&i.operand0, &i.operand1],
+        Instruction::LShr(i) => vec![&i.operand0, &i.operand1],
+        Instruction::AShr(i) => vec![&i.operand0, &i.operand1],
+        Instruction::FAdd(i) => vec![&i.operand0, &i.operand1],
+        Instruction::FSub(i) => vec![&i.operand0, &i.operand1],
+        Instruction::FMul(i) => vec![&i.operand0, &i.operand1],
+        Instruction::FDiv(i) => vec![&i.operand0, &i.operand1],
+        Instruction::FRem(i) => vec![&i.operand0, &i.operand1],
+        Instruction::FNeg(i) => vec![&i.operand],
+        Instruction::ExtractElement(i) => vec![&i.vector, &i.index],
+        Instruction::InsertElement(i) => vec![&i.vector, &i.element, &i.index],
+        Instruction::ShuffleVector(i) => vec![&i.operand0, &i.operand1],
+        Instruction::ExtractValue(i) => vec![&i.aggregate],
+        Instruction::InsertValue(i) => vec![&i.aggregate, &i.element],
+        Instruction::Alloca(i) => vec![&i.num_elements],
+        Instruction::Load(i) => vec![&i.address],
+        Instruction::Store(i) => vec![&i.address, &i.value],
+        Instruction::Fence(_) => vec![],
+        Instruction::CmpXchg(i) => vec![&i.address, &i.expected, &i.replacement],
+        Instruction::AtomicRMW(i) => vec![&i.address, &i.value],
+        Instruction::GetElementPtr(i) => std::iter::once(&i.address).chain(&i.indices).collect(),
+        Instruction::Trunc(i) => vec![&i.operand],
+        Instruction::ZExt(i) => vec![&i.operand],
+        Instruction::SExt(i) => vec![&i.operand],
+        Instruction::FPTrunc(i) => vec![&i.operand],
+        Instruction::FPExt(i) => vec![&i.operand],
+        Instruction::FPToUI(i) => vec![&i.operand],
+        Instruction::FPToSI(i) => vec![&i.operand],
+        Instruction::UIToFP(i) => vec![&i.operand],
+        Instruction::SIToFP(i) => vec![&i.operand],
+        Instruction::PtrToInt(i) => vec![&i.operand],
+        Instruction::IntToPtr(i) => vec![&i.operand],
+        Instruction::BitCast(i) => vec![&i.operand],
+        Instruction::AddrSpaceCast(i) => vec![&i.operand],
+        Instruction::ICmp(i) => vec![&i.operand0, &i.operand1],
+        Instruction::FCmp(i) => vec![&i.operand0, &i.operand1],
+        Instruction::Phi(i) => i.incoming_values.iter().map(|(op, _)| op).collect(),
+        Instruction::Select(i) => vec![&i.condition, &i.true_value, &i.false_value],
+        Instruction::Freeze(i) => vec![&i.operand],
+        Instruction::Call(i) => call_operands(
+            &i.function,
+            &i.arguments,
+            i.operand_bundles.iter().flat_map(|bundle| &bundle.inputs),
+        ),
+        Instruction::VAArg(i) => vec![&i.arg_list],
+        Instruction::LandingPad(_) => vec![],
+        Instruction::CatchPad(i) => std::iter::once(&i.catch_switch).chain(&i.args).collect(),
+        Instruction::CleanupPad(i) => std::iter::once(&i.parent_pad).chain(&i.args).collect(),
+    }
+}
+
+/// All of the operands used by the given terminator
+fn terminator_operands(term: &Terminator) -> Vec<&Operand> {
+    match term {
+        Terminator::Ret(t) => t.return_operand.iter().collect(),
+        Terminator::Br(_) => vec![],
+        Terminator::CondBr(t) => vec![&t.condition],
+        Terminator::Switch(t) => vec![&t.operand],
+        Terminator::IndirectBr(t) => vec![&t.operand],
+        Terminator::Invoke(t) => call_operands(
+            &t.function,
+            &t.arguments,
+            t.operand_bundles.iter().flat_map(|bundle| &bundle.inputs),
+        ),
+        Terminator::Resume(t) => vec![&t.operand],
+        Terminator::Unreachable(_) => vec![],
+        Terminator::CleanupRet(t) => vec![&t.cleanup_pad],
+        Terminator::CatchRet(t) => vec![&t.catch_pad],
+        Terminator::CatchSwitch(t) => vec![&t.parent_pad],
+        Terminator::CallBr(t) => call_operands(&t.function, &t.arguments, std::iter::empty()),
+    }
+}
+
+fn call_operands<'a, F, A>(
+    function: &'a Either<F, Operand>,
+    arguments: &'a [(Operand, A)],
+    bundle_inputs: impl Iterator<Item = &'a Operand>,
+) -> Vec<&'a Operand> {
+    function
+        .as_ref()
+        .right()
+        .into_iter()
+        .chain(arguments.iter().map(|(op, _)| op))
+        .chain(bundle_inputs)
+        .collect()
+}
@@ -88,9 +88,12 @@ pub trait HasDebugLoc {
 // from_llvm //
 // ********* //
 
+#[cfg(all(feature = "llvm-sys", feature = "llvm-9-or-greater"))]
 use crate::from_llvm::*;
+#[cfg(feature = "llvm-sys")]
 use crate::llvm_sys::*;
 
+#[cfg(all(feature = "llvm-sys", feature = "llvm-9-or-greater"))]
 impl DebugLoc {
     /// `value`: must represent an Instruction, Terminator, GlobalVariable, or Function
     ///
@@ -120,3 +123,15 @@ impl DebugLoc {
         }
     }
 }
+
+// The LLVM C API can't get debug locations before LLVM 9
+#[cfg(all(feature = "llvm-sys", feature = "llvm-8-or-lower"))]
+impl DebugLoc {
+    pub(crate) fn from_llvm_no_col(_value: LLVMValueRef) -> Option<Self> {
+        None
+    }
+
+    pub(crate) fn from_llvm_with_col(_value: LLVMValueRef) -> Option<Self> {
+        None
+    }
+}
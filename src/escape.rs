@@ -0,0 +1,39 @@
+//! LLVM's escaping of quoted strings and names in textual IR, in which bytes
+//! other than printable ASCII (and `"` and `\`) are written as `\XX` in hex.
+//! Shared by the `llvm-sys` conversion, which reads some properties from
+//! LLVM's textual form, and the `.ll` parser.
+
+/// Process the `\\` and `\XX` escapes in the contents of a quoted string or
+/// name. Anything else, including a `\` not followed by two hex digits, is
+/// kept as is.
+pub fn unescape_bytes(raw: &[u8]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(raw.len());
+    let mut i = 0;
+    while i < raw.len() {
+        if raw[i] == b'\\' {
+            if raw.get(i + 1) == Some(&b'\\') {
+                bytes.push(b'\\');
+                i += 2;
+                continue;
+            }
+            let escaped = raw
+                .get(i + 1 .. i + 3)
+                .and_then(|hex| std::str::from_utf8(hex).ok())
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+            if let Some(byte) = escaped {
+                bytes.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        bytes.push(raw[i]);
+        i += 1;
+    }
+    bytes
+}
+
+/// Like `unescape_bytes()`, but for text: any bytes which aren't valid UTF-8
+/// are replaced with `U+FFFD`
+pub fn unescape(raw: impl AsRef<[u8]>) -> String {
+    String::from_utf8_lossy(&unescape_bytes(raw.as_ref())).into_owned()
+}
@@ -1,7 +1,11 @@
+pub use crate::escape::unescape;
 pub use crate::iterators::*;
 use crate::llvm_sys::*;
+use std::collections::HashMap;
 use std::ffi::CStr;
+use std::ops::Range;
 use std::os::raw::c_char;
+use std::sync::Arc;
 
 // We convert all LLVM strings to owned Strings (which involves a copy)
 // partly because we intend to serialize/deserialize our ASTs eventually
@@ -96,6 +100,295 @@ wrap_with_len_maybe_null!(
     get_debugloc_directory
 );
 
+/// Some properties of instructions (e.g., `musttail` or fast-math flags) are
+/// not exposed in the LLVM C API, only the C++ one. For those, we fall back on
+/// LLVM's textual representation of the instruction.
+///
+/// Printing an instruction, function, or global makes LLVM number every value
+/// in the module first, so where possible, get the text from a `ModuleText`
+/// instead of calling this. Constants (other than globals) are cheap to print.
+pub unsafe fn print_to_owned_string(val: LLVMValueRef) -> String {
+    debug_assert!(!val.is_null());
+    let ptr = LLVMPrintValueToString(val);
+    let s = raw_to_string(ptr);
+    LLVMDisposeMessage(ptr);
+    s
+}
+
+/// LLVM's textual form of a whole module, printed once and split up by global
+/// variable and function, so that we don't have to print those one at a time
+/// (see notes on `print_to_owned_string()`)
+pub struct ModuleText {
+    /// Map from each global variable to the line defining it
+    // We use LLVMValueRef as a *const, even though it's technically a *mut
+    #[allow(clippy::mutable_key_type)]
+    globals: HashMap<LLVMValueRef, String>,
+    /// Map from each function to its textual form
+    // We use LLVMValueRef as a *const, even though it's technically a *mut
+    #[allow(clippy::mutable_key_type)]
+    functions: HashMap<LLVMValueRef, Arc<FunctionText>>,
+    /// The lines defining attribute groups, e.g., `attributes #0 = { ... }`
+    attribute_groups: Vec<String>,
+}
+
+impl ModuleText {
+    /// Print the given module. Functions whose bodies haven't been read yet
+    /// (see `LazyModule`) are printed without them.
+    pub unsafe fn print(module: LLVMModuleRef) -> Self {
+        let ptr = LLVMPrintModuleToString(module);
+        let text = raw_to_string(ptr);
+        LLVMDisposeMessage(ptr);
+
+        // LLVM prints the global variables first, then aliases and ifuncs
+        // (whose lines also start with `@`), then the functions, in order
+        let mut remaining_globals = get_globals(module);
+        let mut remaining_functions = get_functions(module);
+        let mut globals = HashMap::new();
+        let mut functions = HashMap::new();
+        let mut attribute_groups = vec![];
+        let mut lines = text.lines();
+        while let Some(line) = lines.next() {
+            if line.starts_with('@') {
+                if let Some(global) = remaining_globals.next() {
+                    globals.insert(global, line.to_owned());
+                }
+            } else if line.starts_with("define ") || line.starts_with("declare ") {
+                let func = match remaining_functions.next() {
+                    Some(func) => func,
+                    None => break,
+                };
+                let mut func_text = String::from(line);
+                if line.starts_with("define ") {
+                    for line in lines.by_ref() {
+                        func_text.push('\n');
+                        func_text.push_str(line);
+                        if line == "}" {
+                            break;
+                        }
+                    }
+                }
+                functions.insert(func, Arc::new(FunctionText::new(func_text)));
+            } else if line.starts_with("attributes #") {
+                attribute_groups.push(line.to_owned());
+            }
+        }
+        Self {
+            globals,
+            functions,
+            attribute_groups,
+        }
+    }
+
+    /// Get the line defining the given global variable
+    pub fn global(&self, global: LLVMValueRef) -> Option<&str> {
+        self.globals.get(&global).map(String::as_str)
+    }
+
+    /// Get the textual form of the given function
+    pub fn function(&self, func: LLVMValueRef) -> Option<&Arc<FunctionText>> {
+        self.functions.get(&func)
+    }
+
+    /// The lines defining attribute groups, e.g., `attributes #0 = { ... }`
+    pub fn attribute_groups(&self) -> impl Iterator<Item = &str> {
+        self.attribute_groups.iter().map(String::as_str)
+    }
+}
+
+/// LLVM's textual form of one function, indexed by line
+pub struct FunctionText {
+    text: String,
+    /// Where the header (e.g. `define i32 @f(i32 %x) {`) is in `text`
+    header: Range<usize>,
+    /// Where each of the function's instructions is in `text`, in order
+    instructions: Vec<Range<usize>>,
+}
+
+impl FunctionText {
+    /// Index the textual form of a function, which has a body unless the
+    /// function is a declaration (or its body hasn't been read yet)
+    pub fn new(text: String) -> Self {
+        let mut header = None;
+        let mut instructions = vec![];
+        for line in text.lines() {
+            let start = line.as_ptr() as usize - text.as_ptr() as usize;
+            let range = start .. start + line.len();
+            if header.is_none() {
+                // skip any comments, e.g. `; Function Attrs: ...`
+                if !line.is_empty() && !line.starts_with(';') {
+                    header = Some(range);
+                }
+            } else if line == "}" {
+                break;
+            } else if line.len() > 2
+                && line.starts_with("  ")
+                && !line[2 ..].starts_with(&[' ', ']'][..])
+            {
+                // instructions are indented by two spaces. Labels aren't
+                // indented, and continuation lines (e.g., the cases of a
+                // `switch`, or its closing `]`) are indented further or start
+                // with `]`.
+                instructions.push(range);
+            }
+        }
+        Self {
+            header: header.unwrap_or(0 .. 0),
+            instructions,
+            text,
+        }
+    }
+
+    /// The function's header, which has everything except the body
+    pub fn header(&self) -> &str {
+        &self.text[self.header.clone()]
+    }
+
+    /// Whether the text includes the function's body
+    pub fn has_body(&self) -> bool {
+        !self.instructions.is_empty()
+    }
+
+    /// The number of instructions in the function's body
+    pub fn num_instructions(&self) -> usize {
+        self.instructions.len()
+    }
+
+    /// Get the text of the function's `i`th instruction
+    pub fn instruction(&self, i: usize) -> Option<&str> {
+        self.instructions
+            .get(i)
+            .map(|range| &self.text[range.clone()])
+    }
+}
+
+/// Get the keywords which LLVM prints at the start of the given textual
+/// instruction, after the `%name =` if there is one, and before the first
+/// operand or non-keyword token.
+///
+/// For instance, for `%3 = tail call fast float @f(float %2)`, this returns
+/// `["tail", "call", "fast", "float"]`.
+pub fn leading_keywords(text: &str) -> Vec<String> {
+    let mut text = text.trim_start();
+    if text.starts_with('%') {
+        // skip the `%name = `. Quoted names can contain spaces, but not `"`
+        // (that's escaped as `\22`)
+        let name_end = if text[1 ..].starts_with('"') {
+            text[2 ..].find('"').map(|i| i + 3)
+        } else {
+            text.find(' ')
+        };
+        text = match name_end {
+            Some(i) => text[i ..].trim_start().trim_start_matches('=').trim_start(),
+            None => "",
+        };
+    }
+    text.split(' ')
+        .take_while(|tok| {
+            !tok.is_empty() && tok.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        })
+        .map(String::from)
+        .collect()
+}
+
+/// If `token` is a quoted string (possibly followed by a `,`), get its
+/// unescaped contents
+pub fn unquote(token: &str) -> Option<String> {
+    let token = token.trim_end_matches(',');
+    if token.len() >= 2 && token.starts_with('"') && token.ends_with('"') {
+        Some(unescape(&token[1 .. token.len() - 1]))
+    } else {
+        None
+    }
+}
+
+/// Split (one line of) LLVM's textual IR into whitespace-separated tokens,
+/// where quoted strings (which can't contain a raw `"`; that's escaped as
+/// `\22`) are kept whole
+pub fn tokens_outside_quotes(text: &str) -> Vec<&str> {
+    let mut tokens = vec![];
+    let mut in_quotes = false;
+    let mut start = None;
+    for (i, c) in text.char_indices() {
+        if c == '"' {
+            in_quotes = !in_quotes;
+        }
+        if c.is_whitespace() && !in_quotes {
+            if let Some(start) = start.take() {
+                tokens.push(&text[start .. i]);
+            }
+        } else if start.is_none() {
+            start = Some(i);
+        }
+    }
+    if let Some(start) = start {
+        tokens.push(&text[start ..]);
+    }
+    tokens
+}
+
+/// Replace the addresses (e.g., `<0x55d0c1a2b3c0>`) which LLVM prints for
+/// metadata nodes it can't number with `!?`, so that the text doesn't depend
+/// on where the nodes happen to be in memory
+pub fn without_node_addresses(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut in_quotes = false;
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        if c == '"' {
+            in_quotes = !in_quotes;
+        } else if !in_quotes && rest.starts_with("<0x") {
+            let len = rest[3 ..]
+                .find(|c: char| !c.is_ascii_hexdigit())
+                .map(|i| i + 3);
+            if let Some(len) = len.filter(|&len| rest[len ..].starts_with('>')) {
+                result.push_str("!?");
+                rest = &rest[len + 1 ..];
+                continue;
+            }
+        }
+        result.push(c);
+        rest = &rest[c.len_utf8() ..];
+    }
+    result
+}
+
+/// Get the source element type of a GEP, either an instruction or a constant
+/// expression. With opaque pointers, this can't be recovered from the GEP's
+/// address operand.
+pub unsafe fn get_gep_source_element_type(gep: LLVMValueRef) -> LLVMTypeRef {
+    #[cfg(feature = "llvm-14-or-greater")]
+    {
+        LLVMGetGEPSourceElementType(gep)
+    }
+    #[cfg(feature = "llvm-13-or-lower")]
+    {
+        // The getter was added to the LLVM C API in LLVM 14. Before that, all
+        // pointers are typed, so we use the pointee type of the address (or of
+        // the address's elements, if the address is a vector of pointers)
+        use llvm_sys::LLVMTypeKind;
+        let mut ty = LLVMTypeOf(LLVMGetOperand(gep, 0));
+        if LLVMGetTypeKind(ty) != LLVMTypeKind::LLVMPointerTypeKind {
+            ty = LLVMGetElementType(ty);
+        }
+        LLVMGetElementType(ty)
+    }
+}
+
+/// Get the element at the given index of a `ConstantDataArray` or
+/// `ConstantDataVector`
+pub unsafe fn get_element_as_constant(constant: LLVMValueRef, idx: u32) -> LLVMValueRef {
+    // `LLVMGetElementAsConstant` is deprecated in favor of
+    // `LLVMGetAggregateElement` as of LLVM 15
+    #[cfg(feature = "llvm-15-or-greater")]
+    {
+        LLVMGetAggregateElement(constant, idx)
+    }
+    #[cfg(feature = "llvm-14-or-lower")]
+    {
+        LLVMGetElementAsConstant(constant, idx)
+    }
+}
+
 // Panics if the LLVMValueRef is not a basic block
 pub unsafe fn op_to_bb(op: LLVMValueRef) -> LLVMBasicBlockRef {
     assert!(LLVMValueIsBasicBlock(op) != 0);
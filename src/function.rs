@@ -1,6 +1,5 @@
-#[cfg(feature = "llvm-9-or-greater")]
 use crate::debugloc::{DebugLoc, HasDebugLoc};
-use crate::module::{Comdat, DLLStorageClass, Linkage, Visibility};
+use crate::module::{Comdat, DLLStorageClass, Linkage, MetadataAttachment, Visibility};
 use crate::types::{TypeRef, Typed, Types};
 use crate::{BasicBlock, ConstantRef, Name};
 
@@ -12,7 +11,11 @@ pub struct Function {
     pub is_var_arg: bool,
     pub return_type: TypeRef,
     pub basic_blocks: Vec<BasicBlock>,
-    pub function_attributes: Vec<FunctionAttribute>, // llvm-hs-pure has Vec<Either<GroupID, FunctionAttribute>>, but I'm not sure how the GroupID ones come about
+    /// The function's attributes, as an index into
+    /// `Module::function_attribute_groups`; or `None` if the function has no
+    /// function attributes. Use `Module::get_function_attributes()` to get the
+    /// attributes themselves.
+    pub function_attribute_group: Option<GroupID>,
     pub return_attributes: Vec<ParameterAttribute>,
     pub linkage: Linkage,
     pub visibility: Visibility,
@@ -23,12 +26,15 @@ pub struct Function {
     pub alignment: u32,
     /// See [LLVM 14 docs on Garbage Collector Strategy Names](https://releases.llvm.org/14.0.0/docs/LangRef.html#gc)
     pub garbage_collector_name: Option<String>,
-    // pub prefix: Option<ConstantRef>,  // appears to not be exposed in the LLVM C API, only the C++ API
+    /// See [LLVM 14 docs on Prefix Data](https://releases.llvm.org/14.0.0/docs/LangRef.html#prefix-data)
+    pub prefix: Option<ConstantRef>,
+    /// See [LLVM 14 docs on Prologue Data](https://releases.llvm.org/14.0.0/docs/LangRef.html#prologue-data)
+    pub prologue: Option<ConstantRef>,
     /// Personalities are used for exception handling. See [LLVM 14 docs on Personality Function](https://releases.llvm.org/14.0.0/docs/LangRef.html#personalityfn)
     pub personality_function: Option<ConstantRef>,
-    #[cfg(feature = "llvm-9-or-greater")]
     pub debugloc: Option<DebugLoc>,
-    // --TODO not yet implemented-- pub metadata: Vec<(String, MetadataRef<MetadataNode>)>,
+    /// Metadata attached to the function, e.g., `!type` or `!prof`
+    pub metadata: Vec<MetadataAttachment>,
 }
 
 impl Typed for Function {
@@ -41,7 +47,6 @@ impl Typed for Function {
     }
 }
 
-#[cfg(feature = "llvm-9-or-greater")]
 impl HasDebugLoc for Function {
     fn get_debug_loc(&self) -> &Option<DebugLoc> {
         &self.debugloc
@@ -62,7 +67,7 @@ impl Function {
             is_var_arg: false,
             return_type: Types::blank_for_testing().void(),
             basic_blocks: vec![],
-            function_attributes: vec![],
+            function_attribute_group: None,
             return_attributes: vec![],
             linkage: Linkage::Private,
             visibility: Visibility::Default,
@@ -72,9 +77,35 @@ impl Function {
             comdat: None,
             alignment: 4,
             garbage_collector_name: None,
+            prefix: None,
+            prologue: None,
             personality_function: None,
-            #[cfg(feature = "llvm-9-or-greater")]
             debugloc: None,
+            metadata: vec![],
+        }
+    }
+
+    /// Call `f` on the `function_attribute_group` of the function, and then
+    /// on those of its call sites, in order
+    #[cfg(all(feature = "llvm-sys", feature = "parallel"))]
+    pub(crate) fn for_each_function_attribute_group(
+        &mut self,
+        mut f: impl FnMut(&mut Option<GroupID>),
+    ) {
+        use crate::{Instruction, Terminator};
+
+        f(&mut self.function_attribute_group);
+        for bb in &mut self.basic_blocks {
+            for instr in &mut bb.instrs {
+                if let Instruction::Call(call) = instr {
+                    f(&mut call.function_attribute_group);
+                }
+            }
+            match &mut bb.term {
+                Terminator::Invoke(invoke) => f(&mut invoke.function_attribute_group),
+                Terminator::CallBr(callbr) => f(&mut callbr.function_attribute_group),
+                _ => {},
+            }
         }
     }
 }
@@ -86,6 +117,11 @@ pub struct FunctionDeclaration {
     pub parameters: Vec<Parameter>,
     pub is_var_arg: bool,
     pub return_type: TypeRef,
+    /// The function's attributes, as an index into
+    /// `Module::function_attribute_groups`; or `None` if the function has no
+    /// function attributes. Use `Module::get_function_attributes()` to get the
+    /// attributes themselves.
+    pub function_attribute_group: Option<GroupID>,
     pub return_attributes: Vec<ParameterAttribute>,
     pub linkage: Linkage,
     pub visibility: Visibility,
@@ -94,7 +130,6 @@ pub struct FunctionDeclaration {
     pub alignment: u32,
     /// See [LLVM 14 docs on Garbage Collector Strategy Names](https://releases.llvm.org/14.0.0/docs/LangRef.html#gc)
     pub garbage_collector_name: Option<String>,
-    #[cfg(feature = "llvm-9-or-greater")]
     pub debugloc: Option<DebugLoc>,
 }
 
@@ -163,47 +198,77 @@ pub enum CallingConvention {
 }
 
 /// See [LLVM 14 docs on Function Attributes](https://releases.llvm.org/14.0.0/docs/LangRef.html#fnattrs)
-#[derive(PartialEq, Eq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
 pub enum FunctionAttribute {
     AlignStack(u64),
     AllocSize {
         elt_size: u32,
         num_elts: Option<u32>,
     },
+    /// See [LLVM 15 docs on the `allockind` attribute](https://releases.llvm.org/15.0.0/docs/LangRef.html#function-attributes)
+    AllocKind {
+        alloc: bool,
+        realloc: bool,
+        free: bool,
+        uninitialized: bool,
+        zeroed: bool,
+        aligned: bool,
+    },
     AlwaysInline,
     Builtin,
     Cold,
     Convergent,
+    /// See [LLVM 14 docs on the `disable_sanitizer_instrumentation` attribute](https://releases.llvm.org/14.0.0/docs/LangRef.html#function-attributes)
+    DisableSanitizerInstrumentation,
+    /// See [LLVM 15 docs on the `fn_ret_thunk_extern` attribute](https://releases.llvm.org/15.0.0/docs/LangRef.html#function-attributes)
+    FnRetThunkExtern,
+    Hot,
     InaccessibleMemOnly,
     InaccessibleMemOrArgMemOnly,
     InlineHint,
     JumpTable,
+    /// Replaces `ReadNone`, `ReadOnly`, `WriteOnly`, `ArgMemOnly`,
+    /// `InaccessibleMemOnly`, and `InaccessibleMemOrArgMemOnly` as of LLVM 16.
+    /// Gives the effect the function may have on each kind of memory location.
+    /// See [LLVM 16 docs on the `memory` attribute](https://releases.llvm.org/16.0.0/docs/LangRef.html#function-attributes)
+    Memory {
+        /// Memory pointed to by the function's pointer arguments
+        argmem: MemoryEffect,
+        /// Memory not accessible by the current module
+        inaccessiblemem: MemoryEffect,
+        /// All other memory
+        other: MemoryEffect,
+    },
     MinimizeSize,
+    /// See [LLVM 14 docs on the `mustprogress` attribute](https://releases.llvm.org/14.0.0/docs/LangRef.html#function-attributes)
+    MustProgress,
     Naked,
     NoBuiltin,
+    /// See [LLVM 14 docs on the `nocallback` attribute](https://releases.llvm.org/14.0.0/docs/LangRef.html#function-attributes)
+    NoCallback,
     NoCFCheck,
     NoDuplicate,
-    #[cfg(feature = "llvm-9-or-greater")]
     NoFree,
     NoImplicitFloat,
     NoInline,
-    #[cfg(feature = "llvm-11-or-greater")]
     NoMerge,
+    NoProfile,
+    NoSanitizeBounds,
+    NoSanitizeCoverage,
     NonLazyBind,
     NoRedZone,
     NoReturn,
     NoRecurse,
-    #[cfg(feature = "llvm-9-or-greater")]
     WillReturn,
     ReturnsTwice,
-    #[cfg(feature = "llvm-9-or-greater")]
     NoSync,
     NoUnwind,
-    #[cfg(feature = "llvm-11-or-greater")]
     NullPointerIsValid,
     OptForFuzzing,
     OptNone,
     OptSize,
+    /// See [LLVM 15 docs on the `presplitcoroutine` attribute](https://releases.llvm.org/15.0.0/docs/LangRef.html#function-attributes)
+    PresplitCoroutine,
     ReadNone,
     ReadOnly,
     WriteOnly,
@@ -213,21 +278,71 @@ pub enum FunctionAttribute {
     SanitizeMemory,
     SanitizeThread,
     SanitizeHWAddress,
-    #[cfg(feature = "llvm-9-or-greater")]
     SanitizeMemTag,
     ShadowCallStack,
+    /// See [LLVM 16 docs on the `skipprofile` attribute](https://releases.llvm.org/16.0.0/docs/LangRef.html#function-attributes)
+    SkipProfile,
     SpeculativeLoadHardening,
     Speculatable,
     StackProtect,
     StackProtectReq,
     StackProtectStrong,
     StrictFP,
-    UWTable,
+    /// See [LLVM 15 docs on the `uwtable` attribute](https://releases.llvm.org/15.0.0/docs/LangRef.html#function-attributes).
+    /// Before LLVM 15 the kind wasn't recorded, and is always
+    /// `UWTableKind::Async`.
+    UWTable(UWTableKind),
+    /// See [LLVM 14 docs on the `vscale_range` attribute](https://releases.llvm.org/14.0.0/docs/LangRef.html#function-attributes)
+    VScaleRange {
+        min: u32,
+        /// `None` means the maximum is unbounded
+        max: Option<u32>,
+    },
     StringAttribute {
         kind: String,
         value: String, // for no value, use ""
     },
-    UnknownAttribute, // this is used if we get a value not in the above list
+    /// This is used if we get an enum attribute not in the above list.
+    UnknownAttribute {
+        /// The attribute's name as it appears in LLVM IR, e.g. `"nocallback"`.
+        /// If even LLVM's name for the attribute couldn't be determined, this
+        /// is the numeric LLVM attribute kind, e.g. `"93"`.
+        kind: String,
+        /// The attribute's integer value, or 0 for attributes without one
+        value: u64,
+    },
+}
+
+/// The kind of unwind table required by the `uwtable` function attribute;
+/// see `FunctionAttribute::UWTable`
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Hash)]
+pub enum UWTableKind {
+    /// `uwtable(sync)`
+    Sync,
+    /// `uwtable(async)`, or just `uwtable`
+    Async,
+}
+
+/// The effect a function may have on a kind of memory location; see
+/// `FunctionAttribute::Memory`
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Hash)]
+pub enum MemoryEffect {
+    None,
+    Read,
+    Write,
+    ReadWrite,
+}
+
+impl MemoryEffect {
+    /// Whether this effect includes reading
+    pub fn may_read(self) -> bool {
+        matches!(self, Self::Read | Self::ReadWrite)
+    }
+
+    /// Whether this effect includes writing
+    pub fn may_write(self) -> bool {
+        matches!(self, Self::Write | Self::ReadWrite)
+    }
 }
 
 /// `ParameterAttribute`s can apply to function parameters as well as function return types.
@@ -237,65 +352,350 @@ pub enum ParameterAttribute {
     ZeroExt,
     SignExt,
     InReg,
-    #[cfg(feature = "llvm-11-or-lower")]
-    ByVal,
-    #[cfg(feature = "llvm-12-or-greater")]
-    ByVal(TypeRef),
-    #[cfg(feature = "llvm-11")]
-    Preallocated,
-    #[cfg(feature = "llvm-12-or-greater")]
-    Preallocated(TypeRef),
-    #[cfg(feature = "llvm-12-or-lower")]
-    InAlloca,
-    #[cfg(feature = "llvm-13-or-greater")]
-    InAlloca(TypeRef),
-    #[cfg(feature = "llvm-11-or-lower")]
-    SRet,
-    #[cfg(feature = "llvm-12-or-greater")]
-    SRet(TypeRef),
+    /// The type is `None` for IR from LLVM 11 or lower, where `byval` didn't
+    /// carry a type
+    ByVal(Option<TypeRef>),
+    /// See [LLVM 14 docs on the `byref` attribute](https://releases.llvm.org/14.0.0/docs/LangRef.html#parameter-attributes).
+    /// The type is `None` for IR from LLVM 11, where `byref` didn't carry a
+    /// type
+    ByRef(Option<TypeRef>),
+    /// The type is `None` for IR from LLVM 11, where `preallocated` didn't
+    /// carry a type
+    Preallocated(Option<TypeRef>),
+    /// The type is `None` for IR from LLVM 12 or lower, where `inalloca`
+    /// didn't carry a type
+    InAlloca(Option<TypeRef>),
+    /// The type is `None` for IR from LLVM 11 or lower, where `sret` didn't
+    /// carry a type
+    SRet(Option<TypeRef>),
+    ElementType(TypeRef),
     Alignment(u64),
     NoAlias,
     NoCapture,
-    #[cfg(feature = "llvm-9-or-greater")]
+    /// The value is a bitmask of `FPClassTest`, giving the floating-point
+    /// classes the value can't be. See [LLVM 17 docs on the `nofpclass` attribute](https://releases.llvm.org/17.0.1/docs/LangRef.html#parameter-attributes)
+    NoFPClass(u32),
     NoFree,
     Nest,
     Returned,
     NonNull,
     Dereferenceable(u64),
     DereferenceableOrNull(u64),
+    ReadNone,
+    ReadOnly,
+    WriteOnly,
     SwiftSelf,
     SwiftError,
+    SwiftAsync,
     ImmArg,
-    #[cfg(feature = "llvm-11-or-greater")]
     NoUndef,
+    AllocAlign,
+    AllocPtr,
     StringAttribute {
         kind: String,
         value: String, // for no value, use ""
     },
-    UnknownAttribute, // this is used if we get an EnumAttribute not in the above list; or, for LLVM 11 or lower, also for some TypeAttributes (due to C API limitations)
-    #[cfg(feature = "llvm-12-or-greater")]
-    UnknownTypeAttribute(TypeRef), // this is used if we get a TypeAttribute not in the above list
+    /// This is used if we get an enum attribute not in the above list; or, for
+    /// LLVM 11 or lower, also for type attributes not in the above list (due
+    /// to C API limitations)
+    UnknownAttribute {
+        /// The attribute's name as it appears in LLVM IR, e.g. `"nofpclass"`.
+        /// If even LLVM's name for the attribute couldn't be determined, this
+        /// is the numeric LLVM attribute kind, e.g. `"93"`.
+        kind: String,
+        /// The attribute's integer value, or 0 for attributes without one
+        value: u64,
+    },
+    /// This is used if we get a type attribute not in the above list
+    UnknownTypeAttribute {
+        /// The attribute's name as it appears in LLVM IR, as for
+        /// `UnknownAttribute`
+        kind: String,
+        ty: TypeRef,
+    },
 }
 
+/// Identifies a `FunctionAttributeGroup`; it is the group's index in
+/// `Module::function_attribute_groups`
 pub type GroupID = usize;
 
+impl FunctionAttribute {
+    /// Get the `FunctionAttribute` for the enum-style attribute with the
+    /// given name (as it appears in LLVM IR) and integer value, or `None` if
+    /// it's not one we have a variant for
+    #[cfg(any(feature = "llvm-sys", feature = "ll-parser", feature = "bc-reader"))]
+    pub(crate) fn from_enum_name(name: &str, value: u64) -> Option<Self> {
+        Some(match name {
+            "alignstack" => Self::AlignStack(value),
+            "allocsize" => {
+                // looking at the LLVM implementation as of this writing
+                // (near the top of Attributes.cpp),
+                // the elt_size value is the upper 32 bits, and the num_elts value
+                // is the lower 32 bits, or the sentinel -1 for None
+                let elt_size = (value >> 32) as u32;
+                let num_elts = match (value & 0xFFFF_FFFF) as u32 {
+                    0xFFFF_FFFF => None,
+                    val => Some(val),
+                };
+                Self::AllocSize { elt_size, num_elts }
+            },
+            "allockind" => {
+                // the value is a bitmask of `AllocFnKind` (see Attributes.h)
+                Self::AllocKind {
+                    alloc: value & (1 << 0) != 0,
+                    realloc: value & (1 << 1) != 0,
+                    free: value & (1 << 2) != 0,
+                    uninitialized: value & (1 << 3) != 0,
+                    zeroed: value & (1 << 4) != 0,
+                    aligned: value & (1 << 5) != 0,
+                }
+            },
+            "alwaysinline" => Self::AlwaysInline,
+            "builtin" => Self::Builtin,
+            "cold" => Self::Cold,
+            "convergent" => Self::Convergent,
+            "disable_sanitizer_instrumentation" => Self::DisableSanitizerInstrumentation,
+            "fn_ret_thunk_extern" => Self::FnRetThunkExtern,
+            "hot" => Self::Hot,
+            "inaccessiblememonly" => Self::InaccessibleMemOnly,
+            "inaccessiblemem_or_argmemonly" => Self::InaccessibleMemOrArgMemOnly,
+            "inlinehint" => Self::InlineHint,
+            "jumptable" => Self::JumpTable,
+            "memory" => {
+                // looking at the LLVM implementation as of this writing
+                // (`MemoryEffects` in ModRef.h), the value has two bits
+                // of `ModRefInfo` for each location, starting from the
+                // low bits: argmem, inaccessiblemem, other
+                Self::Memory {
+                    argmem: MemoryEffect::from_mod_ref_bits(value),
+                    inaccessiblemem: MemoryEffect::from_mod_ref_bits(value >> 2),
+                    other: MemoryEffect::from_mod_ref_bits(value >> 4),
+                }
+            },
+            "minsize" => Self::MinimizeSize,
+            "mustprogress" => Self::MustProgress,
+            "naked" => Self::Naked,
+            "nobuiltin" => Self::NoBuiltin,
+            "nocallback" => Self::NoCallback,
+            "nocf_check" => Self::NoCFCheck,
+            "noduplicate" => Self::NoDuplicate,
+            "nofree" => Self::NoFree,
+            "noimplicitfloat" => Self::NoImplicitFloat,
+            "noinline" => Self::NoInline,
+            "nomerge" => Self::NoMerge,
+            "noprofile" => Self::NoProfile,
+            "nosanitize_bounds" => Self::NoSanitizeBounds,
+            "nosanitize_coverage" => Self::NoSanitizeCoverage,
+            "nonlazybind" => Self::NonLazyBind,
+            "noredzone" => Self::NoRedZone,
+            "noreturn" => Self::NoReturn,
+            "norecurse" => Self::NoRecurse,
+            "willreturn" => Self::WillReturn,
+            "returns_twice" => Self::ReturnsTwice,
+            "nosync" => Self::NoSync,
+            "nounwind" => Self::NoUnwind,
+            "null_pointer_is_valid" => Self::NullPointerIsValid,
+            "optforfuzzing" => Self::OptForFuzzing,
+            "optnone" => Self::OptNone,
+            "optsize" => Self::OptSize,
+            "presplitcoroutine" => Self::PresplitCoroutine,
+            "readnone" => Self::ReadNone,
+            "readonly" => Self::ReadOnly,
+            "writeonly" => Self::WriteOnly,
+            "argmemonly" => Self::ArgMemOnly,
+            "safestack" => Self::SafeStack,
+            "sanitize_address" => Self::SanitizeAddress,
+            "sanitize_memory" => Self::SanitizeMemory,
+            "sanitize_thread" => Self::SanitizeThread,
+            "sanitize_hwaddress" => Self::SanitizeHWAddress,
+            "sanitize_memtag" => Self::SanitizeMemTag,
+            "shadowcallstack" => Self::ShadowCallStack,
+            "skipprofile" => Self::SkipProfile,
+            "speculative_load_hardening" => Self::SpeculativeLoadHardening,
+            "speculatable" => Self::Speculatable,
+            "ssp" => Self::StackProtect,
+            "sspreq" => Self::StackProtectReq,
+            "sspstrong" => Self::StackProtectStrong,
+            "strictfp" => Self::StrictFP,
+            "uwtable" => {
+                // the value is a `UWTableKind` (see CodeGen.h): 1 for
+                // sync, 2 for async; before LLVM 15 there's no value and
+                // the table is always async
+                match value {
+                    1 => Self::UWTable(UWTableKind::Sync),
+                    _ => Self::UWTable(UWTableKind::Async),
+                }
+            },
+            "vscale_range" => {
+                // looking at the LLVM implementation as of this writing
+                // (`unpackVScaleRangeArgs` in Attributes.cpp), the min is
+                // the upper 32 bits, and the max is the lower 32 bits, or
+                // 0 if unbounded
+                let min = (value >> 32) as u32;
+                let max = match (value & 0xFFFF_FFFF) as u32 {
+                    0 => None,
+                    val => Some(val),
+                };
+                Self::VScaleRange { min, max }
+            },
+            _ => return None,
+        })
+    }
+}
+
+impl ParameterAttribute {
+    /// Get the `ParameterAttribute` for the enum-style attribute with the
+    /// given name (as it appears in LLVM IR) and integer value, or `None` if
+    /// it's not one we have a variant for
+    #[cfg(any(feature = "llvm-sys", feature = "ll-parser", feature = "bc-reader"))]
+    pub(crate) fn from_enum_name(name: &str, value: u64) -> Option<Self> {
+        Some(match name {
+            "zeroext" => Self::ZeroExt,
+            "signext" => Self::SignExt,
+            "inreg" => Self::InReg,
+            "byval" => Self::ByVal(None),
+            "byref" => Self::ByRef(None),
+            "preallocated" => Self::Preallocated(None),
+            "inalloca" => Self::InAlloca(None),
+            "sret" => Self::SRet(None),
+            "align" => Self::Alignment(value),
+            "noalias" => Self::NoAlias,
+            "nocapture" => Self::NoCapture,
+            "nofpclass" => Self::NoFPClass(value as u32),
+            "nofree" => Self::NoFree,
+            "nest" => Self::Nest,
+            "returned" => Self::Returned,
+            "nonnull" => Self::NonNull,
+            "dereferenceable" => Self::Dereferenceable(value),
+            "dereferenceable_or_null" => Self::DereferenceableOrNull(value),
+            "readnone" => Self::ReadNone,
+            "readonly" => Self::ReadOnly,
+            "writeonly" => Self::WriteOnly,
+            "swiftself" => Self::SwiftSelf,
+            "swifterror" => Self::SwiftError,
+            "swiftasync" => Self::SwiftAsync,
+            "immarg" => Self::ImmArg,
+            "noundef" => Self::NoUndef,
+            "allocalign" => Self::AllocAlign,
+            "allocptr" => Self::AllocPtr,
+            _ => return None,
+        })
+    }
+
+    /// Get the `ParameterAttribute` for the type attribute with the given name
+    /// (as it appears in LLVM IR) and type, or `None` if it's not one we have
+    /// a variant for
+    #[cfg(any(feature = "llvm-sys", feature = "ll-parser", feature = "bc-reader"))]
+    #[cfg_attr(feature = "llvm-11-or-lower", allow(dead_code))]
+    pub(crate) fn from_type_name(name: &str, ty: TypeRef) -> Option<Self> {
+        Some(match name {
+            "byval" => Self::ByVal(Some(ty)),
+            "byref" => Self::ByRef(Some(ty)),
+            "preallocated" => Self::Preallocated(Some(ty)),
+            "inalloca" => Self::InAlloca(Some(ty)),
+            "sret" => Self::SRet(Some(ty)),
+            "elementtype" => Self::ElementType(ty),
+            _ => return None,
+        })
+    }
+}
+
+impl MemoryEffect {
+    /// Decode the low two bits of `bits`, which are an LLVM `ModRefInfo`
+    #[cfg(any(feature = "llvm-sys", feature = "ll-parser", feature = "bc-reader"))]
+    fn from_mod_ref_bits(bits: u64) -> Self {
+        match bits & 0b11 {
+            0 => Self::None,
+            1 => Self::Read,
+            2 => Self::Write,
+            _ => Self::ReadWrite,
+        }
+    }
+}
+
+impl CallingConvention {
+    /// The calling convention with the given number. LLVM guarantees that
+    /// these numbers are stable.
+    #[cfg(any(feature = "llvm-sys", feature = "ll-parser", feature = "bc-reader"))]
+    #[allow(clippy::cognitive_complexity)]
+    #[rustfmt::skip] // each calling convention on one line, even if lines get a little long
+    pub(crate) fn from_u32(u: u32) -> Self {
+        match u {
+            0 => CallingConvention::C,
+            8 => CallingConvention::Fast,
+            9 => CallingConvention::Cold,
+            10 => CallingConvention::GHC,
+            11 => CallingConvention::HiPE,
+            12 => CallingConvention::WebKit_JS,
+            13 => CallingConvention::AnyReg,
+            14 => CallingConvention::PreserveMost,
+            15 => CallingConvention::PreserveAll,
+            16 => CallingConvention::Swift,
+            17 => CallingConvention::CXX_FastTLS,
+            64 => CallingConvention::X86_StdCall,
+            65 => CallingConvention::X86_FastCall,
+            92 => CallingConvention::X86_RegCall,
+            70 => CallingConvention::X86_ThisCall,
+            80 => CallingConvention::X86_VectorCall,
+            83 => CallingConvention::X86_Intr,
+            78 => CallingConvention::X86_64_SysV,
+            66 => CallingConvention::ARM_APCS,
+            67 => CallingConvention::ARM_AAPCS,
+            68 => CallingConvention::ARM_AAPCS_VFP,
+            69 => CallingConvention::MSP430_INTR,
+            94 => CallingConvention::MSP430_Builtin,
+            71 => CallingConvention::PTX_Kernel,
+            72 => CallingConvention::PTX_Device,
+            75 => CallingConvention::SPIR_FUNC,
+            76 => CallingConvention::SPIR_KERNEL,
+            77 => CallingConvention::Intel_OCL_BI,
+            79 => CallingConvention::Win64,
+            81 => CallingConvention::HHVM,
+            82 => CallingConvention::HHVM_C,
+            84 => CallingConvention::AVR_Intr,
+            85 => CallingConvention::AVR_Signal,
+            86 => CallingConvention::AVR_Builtin,
+            90 => CallingConvention::AMDGPU_CS,
+            96 => CallingConvention::AMDGPU_ES,
+            88 => CallingConvention::AMDGPU_GS,
+            93 => CallingConvention::AMDGPU_HS,
+            95 => CallingConvention::AMDGPU_LS,
+            89 => CallingConvention::AMDGPU_PS,
+            87 => CallingConvention::AMDGPU_VS,
+            91 => CallingConvention::AMDGPU_Kernel,
+            _ => CallingConvention::Numbered(u),
+        }
+    }
+}
+
 // ********* //
 // from_llvm //
 // ********* //
 
+#[cfg(feature = "llvm-sys")]
 use crate::constant::Constant;
+#[cfg(feature = "llvm-sys")]
 use crate::from_llvm::*;
+#[cfg(feature = "llvm-sys")]
 use crate::llvm_sys::*;
+#[cfg(feature = "llvm-sys")]
 use crate::module::ModuleContext;
-#[cfg(feature = "llvm-12-or-greater")]
+#[cfg(feature = "llvm-sys")]
+use crate::name::SymbolTable;
+#[cfg(all(feature = "llvm-sys", feature = "llvm-12-or-greater"))]
 use crate::types::TypesBuilder;
-use llvm_sys::comdat::*;
+#[cfg(feature = "llvm-sys")]
 use llvm_sys::{LLVMAttributeFunctionIndex, LLVMAttributeReturnIndex};
+#[cfg(feature = "llvm-sys")]
 use std::collections::HashMap;
+#[cfg(feature = "llvm-sys")]
 use std::ffi::CString;
+#[cfg(feature = "llvm-sys")]
+use std::sync::Arc;
 
 /// This struct contains data used when translating from llvm-sys into our data
 /// structures. The data here is local to a particular Function.
+#[cfg(feature = "llvm-sys")]
 pub(crate) struct FunctionContext<'a> {
     /// Map from llvm-sys basic block to its `Name`
     // We use LLVMBasicBlockRef as a *const, even though it's technically a *mut
@@ -307,24 +707,96 @@ pub(crate) struct FunctionContext<'a> {
     pub val_names: &'a HashMap<LLVMValueRef, Name>,
     /// this counter is used to number parameters, variables, and basic blocks that aren't named
     pub ctr: usize,
+    /// copied from the module's `ParseOptions`
+    pub skip_debug_info: bool,
+    /// copied from the module's `ParseOptions`
+    pub keep_numeric_names: bool,
+    /// Interns the string names of the function's local values and basic
+    /// blocks, so that each use of a value shares its name with the value
+    pub symbols: SymbolTable,
+    /// The function being converted
+    pub func: LLVMValueRef,
+    /// The function's textual form, fetched the first time it's needed; see
+    /// `instruction_text()`
+    pub text: Option<Arc<FunctionText>>,
+    /// Map from each instruction to its position in `text`
+    // We use LLVMValueRef as a *const, even though it's technically a *mut
+    #[allow(clippy::mutable_key_type)]
+    instruction_indices: HashMap<LLVMValueRef, usize>,
+}
+
+#[cfg(feature = "llvm-sys")]
+impl<'a> FunctionContext<'a> {
+    /// Get the `Name` of a local value or basic block with the given LLVM name
+    pub(crate) fn local_name(&mut self, s: String) -> Name {
+        Name::local_name_or_num(s, &mut self.ctr, self.keep_numeric_names, &mut self.symbols)
+    }
+
+    /// Get the `DebugLoc` of an instruction or terminator
+    pub(crate) fn debugloc(&self, value: LLVMValueRef) -> Option<DebugLoc> {
+        if self.skip_debug_info {
+            None
+        } else {
+            DebugLoc::from_llvm_with_col(value)
+        }
+    }
+
+    /// Get the textual form of the function (see `ModuleContext::function_text()`)
+    pub(crate) fn function_text(&mut self, ctx: &ModuleContext) -> Arc<FunctionText> {
+        let func = self.func;
+        Arc::clone(self.text.get_or_insert_with(|| ctx.function_text(func)))
+    }
+
+    /// Get the textual form of an instruction or terminator of the function,
+    /// for properties which the LLVM C API has no getters for
+    pub(crate) fn instruction_text(&mut self, inst: LLVMValueRef, ctx: &ModuleContext) -> String {
+        let func = self.func;
+        let text = self.function_text(ctx);
+        if self.instruction_indices.is_empty() {
+            self.instruction_indices = get_basic_blocks(func)
+                .flat_map(get_instructions)
+                .enumerate()
+                .map(|(i, inst)| (inst, i))
+                .collect();
+        }
+        let line = if self.instruction_indices.len() == text.num_instructions() {
+            self.instruction_indices
+                .get(&inst)
+                .and_then(|&i| text.instruction(i))
+        } else {
+            None
+        };
+        match line {
+            Some(line) => line.to_owned(),
+            // the text didn't line up with the instructions, so we fall back
+            // on printing just this one
+            None => ctx.with_context_lock(|| unsafe { print_to_owned_string(inst) }),
+        }
+    }
 }
 
+#[cfg(feature = "llvm-sys")]
 impl FunctionDeclaration {
     pub(crate) fn from_llvm_ref(func: LLVMValueRef, ctx: &mut ModuleContext) -> Self {
         let func = unsafe { LLVMIsAFunction(func) };
         assert!(!func.is_null());
         debug!("Processing func {:?}", unsafe { get_value_name(func) });
 
-        let (decl, _) = FunctionDeclaration::from_llvm_ref_internal(func, ctx);
+        let (decl, _) =
+            FunctionDeclaration::from_llvm_ref_internal(func, ctx, &mut SymbolTable::new());
         decl
     }
 
     /// this helper is shared by `FunctionDeclaration` and `Function`. It
     /// provides the whole `FunctionDeclaration`, and also the value of the
     /// `local_ctr` after parameters are processed (which is needed by
-    /// `Function`).
-    fn from_llvm_ref_internal(func: LLVMValueRef, ctx: &mut ModuleContext) -> (Self, usize) {
-        let functy = unsafe { LLVMGetElementType(LLVMTypeOf(func)) }; // for some reason the TypeOf a function is <pointer to function> and not just <function> so we have to deref it like this
+    /// `Function`). The names of the parameters are interned in `symbols`.
+    fn from_llvm_ref_internal(
+        func: LLVMValueRef,
+        ctx: &mut ModuleContext,
+        symbols: &mut SymbolTable,
+    ) -> (Self, usize) {
+        let functy = unsafe { LLVMGlobalGetValueType(func) }; // the TypeOf a function is <pointer to function> and not just <function>, and with opaque pointers we can't deref that
         let mut local_ctr = 0; // this counter is used to number parameters, variables, and basic blocks that aren't named
         let decl = Self {
             name: unsafe { get_value_name(func) },
@@ -332,9 +804,16 @@ impl FunctionDeclaration {
                 let parameters: Vec<Parameter> = get_parameters(func)
                     .enumerate()
                     .map(|(i, p)| Parameter {
-                        name: Name::name_or_num(unsafe { get_value_name(p) }, &mut local_ctr),
+                        name: Name::local_name_or_num(
+                            unsafe { get_value_name(p) },
+                            &mut local_ctr,
+                            ctx.options.keep_numeric_names,
+                            symbols,
+                        ),
                         ty: ctx.types.type_from_llvm_ref(unsafe { LLVMTypeOf(p) }),
-                        attributes: {
+                        attributes: if ctx.options.skip_attributes {
+                            vec![]
+                        } else {
                             let param_num = i + 1; // https://docs.rs/llvm-sys/100.0.1/llvm_sys/type.LLVMAttributeIndex.html indicates that parameter numbers are 1-indexed here; see issue #4
                             let num_attrs =
                                 unsafe { LLVMGetAttributeCountAtIndex(func, param_num as u32) };
@@ -369,7 +848,31 @@ impl FunctionDeclaration {
             return_type: ctx
                 .types
                 .type_from_llvm_ref(unsafe { LLVMGetReturnType(functy) }),
-            return_attributes: {
+            function_attribute_group: if ctx.options.skip_attributes {
+                None
+            } else {
+                let num_attrs =
+                    unsafe { LLVMGetAttributeCountAtIndex(func, LLVMAttributeFunctionIndex) };
+                let mut attrs: Vec<LLVMAttributeRef> = Vec::with_capacity(num_attrs as usize);
+                if num_attrs > 0 {
+                    unsafe {
+                        LLVMGetAttributesAtIndex(
+                            func,
+                            LLVMAttributeFunctionIndex,
+                            attrs.as_mut_ptr(),
+                        );
+                        attrs.set_len(num_attrs as usize);
+                    };
+                }
+                let attrs = attrs
+                    .into_iter()
+                    .map(|attr| FunctionAttribute::from_llvm_ref(attr, &ctx.attrsdata))
+                    .collect();
+                ctx.intern_function_attributes(attrs)
+            },
+            return_attributes: if ctx.options.skip_attributes {
+                vec![]
+            } else {
                 let num_attrs =
                     unsafe { LLVMGetAttributeCountAtIndex(func, LLVMAttributeReturnIndex) };
                 if num_attrs > 0 {
@@ -405,13 +908,17 @@ impl FunctionDeclaration {
             }),
             alignment: unsafe { LLVMGetAlignment(func) },
             garbage_collector_name: unsafe { get_gc(func) },
-            #[cfg(feature = "llvm-9-or-greater")]
-            debugloc: DebugLoc::from_llvm_no_col(func),
+            debugloc: if ctx.options.skip_debug_info {
+                None
+            } else {
+                DebugLoc::from_llvm_no_col(func)
+            },
         };
         (decl, local_ctr)
     }
 }
 
+#[cfg(feature = "llvm-sys")]
 impl Function {
     pub(crate) fn from_llvm_ref(func: LLVMValueRef, ctx: &mut ModuleContext) -> Self {
         let func = unsafe { LLVMIsAFunction(func) };
@@ -421,8 +928,9 @@ impl Function {
         // `Function` is a strict superset of the information in
         // `FunctionDeclaration`, so we start by collecting all of the
         // information shared by `FunctionDeclaration`, reusing that code
+        let mut symbols = SymbolTable::new();
         let (decl, ctr_val_after_parameters) =
-            FunctionDeclaration::from_llvm_ref_internal(func, ctx);
+            FunctionDeclaration::from_llvm_ref_internal(func, ctx, &mut symbols);
 
         // Functions require two passes over their bodies.
         // First we make a pass just to map `LLVMBasicBlockRef`s to `Name`s and `LLVMValueRef`s to `Name`s.
@@ -431,9 +939,21 @@ impl Function {
         //   `LLVMBasicBlockRef`s and/or `LLVMValueRef`s which we wouldn't have
         //   seen before if we tried to do everything in one pass, and therefore
         //   we wouldn't necessarily know what `Name` the block or value had yet.
+        // If the `ParseOptions` leave out the body, there's nothing to map.
+        let convert_body = ctx.options.converts_body_of(&decl.name);
+        let keep_numeric_names = ctx.options.keep_numeric_names;
         let mut local_ctr = ctr_val_after_parameters; // this counter is used to number parameters, variables, and basic blocks that aren't named
         let bbresults: Vec<_> = get_basic_blocks(func)
-            .map(|bb| (bb, BasicBlock::first_pass_names(bb, &mut local_ctr)))
+            .filter(|_| convert_body)
+            .map(|bb| {
+                let names = BasicBlock::first_pass_names(
+                    bb,
+                    &mut local_ctr,
+                    keep_numeric_names,
+                    &mut symbols,
+                );
+                (bb, names)
+            })
             .collect();
         // We use LLVMBasicBlockRef as a *const, even though it's technically a *mut
         #[allow(clippy::mutable_key_type)]
@@ -454,8 +974,47 @@ impl Function {
             bb_names: &bb_names,
             val_names: &val_names,
             ctr: ctr_val_after_parameters, // restart the local_ctr; the second pass should number everything exactly the same though
+            skip_debug_info: ctx.options.skip_debug_info,
+            keep_numeric_names,
+            symbols,
+            func,
+            text: None,
+            instruction_indices: HashMap::new(),
         };
 
+        let comdat = Comdat::from_llvm_global(func, &decl.name, || {
+            func_ctx.function_text(ctx).header().to_owned()
+        });
+        if let Some(comdat) = &comdat {
+            ctx.add_comdat_member(comdat, &decl.name, true);
+        }
+
+        // Prefix and prologue data aren't exposed in the LLVM C API. They are
+        // operands of the function (along with the personality function), but
+        // unset ones are filled with a placeholder, so we check the function's
+        // header to see which ones are actually set. The header is also needed
+        // for metadata attachments, so we get it if the function has any of
+        // these.
+        let num_operands = unsafe { LLVMGetNumOperands(func) };
+        let header = if num_operands > 0 || MetadataAttachment::has_any(func) {
+            func_ctx.function_text(ctx).header().to_owned()
+        } else {
+            String::new()
+        };
+        let header_tokens = tokens_outside_quotes(&header);
+        let mut get_data = |keyword: &str, operand: u32| {
+            if num_operands > operand as i32 && header_tokens.contains(&keyword) {
+                Some(Constant::from_llvm_ref(
+                    unsafe { LLVMGetOperand(func, operand) },
+                    ctx,
+                ))
+            } else {
+                None
+            }
+        };
+        let prefix = get_data("prefix", 1);
+        let prologue = get_data("prologue", 2);
+
         Self {
             name: decl.name,
             parameters: decl.parameters,
@@ -463,46 +1022,22 @@ impl Function {
             return_type: decl.return_type,
             basic_blocks: {
                 get_basic_blocks(func)
+                    .filter(|_| convert_body)
                     .map(|bb| BasicBlock::from_llvm_ref(bb, ctx, &mut func_ctx))
                     .collect()
             },
-            function_attributes: {
-                let num_attrs =
-                    unsafe { LLVMGetAttributeCountAtIndex(func, LLVMAttributeFunctionIndex) };
-                if num_attrs > 0 {
-                    let mut attrs: Vec<LLVMAttributeRef> = Vec::with_capacity(num_attrs as usize);
-                    unsafe {
-                        LLVMGetAttributesAtIndex(
-                            func,
-                            LLVMAttributeFunctionIndex,
-                            attrs.as_mut_ptr(),
-                        );
-                        attrs.set_len(num_attrs as usize);
-                    };
-                    attrs
-                        .into_iter()
-                        .map(|attr| FunctionAttribute::from_llvm_ref(attr, &ctx.attrsdata))
-                        .collect()
-                } else {
-                    vec![]
-                }
-            },
+            function_attribute_group: decl.function_attribute_group,
             return_attributes: decl.return_attributes,
             linkage: decl.linkage,
             visibility: decl.visibility,
             dll_storage_class: decl.dll_storage_class,
             calling_convention: decl.calling_convention,
             section: unsafe { get_section(func) },
-            comdat: {
-                let comdat = unsafe { LLVMGetComdat(func) };
-                if comdat.is_null() {
-                    None
-                } else {
-                    Some(Comdat::from_llvm_ref(comdat))
-                }
-            },
+            comdat,
             alignment: decl.alignment,
             garbage_collector_name: decl.garbage_collector_name,
+            prefix,
+            prologue,
             personality_function: {
                 if unsafe { LLVMHasPersonalityFn(func) } != 0 {
                     Some(Constant::from_llvm_ref(
@@ -513,87 +1048,208 @@ impl Function {
                     None
                 }
             },
-            #[cfg(feature = "llvm-9-or-greater")]
             debugloc: decl.debugloc,
-            // metadata: unimplemented!("Function.metadata"),
+            metadata: MetadataAttachment::from_llvm_global(func, &header, ctx),
         }
     }
-}
 
-impl CallingConvention {
-    #[allow(clippy::cognitive_complexity)]
-    #[rustfmt::skip] // each calling convention on one line, even if lines get a little long
-    pub(crate) fn from_u32(u: u32) -> Self {
-        use llvm_sys::LLVMCallConv;
-        match u {
-            _ if u == LLVMCallConv::LLVMCCallConv as u32 => CallingConvention::C,
-            _ if u == LLVMCallConv::LLVMFastCallConv as u32 => CallingConvention::Fast,
-            _ if u == LLVMCallConv::LLVMColdCallConv as u32 => CallingConvention::Cold,
-            _ if u == LLVMCallConv::LLVMGHCCallConv as u32 => CallingConvention::GHC,
-            _ if u == LLVMCallConv::LLVMHiPECallConv as u32 => CallingConvention::HiPE,
-            _ if u == LLVMCallConv::LLVMWebKitJSCallConv as u32 => CallingConvention::WebKit_JS,
-            _ if u == LLVMCallConv::LLVMAnyRegCallConv as u32 => CallingConvention::AnyReg,
-            _ if u == LLVMCallConv::LLVMPreserveMostCallConv as u32 => CallingConvention::PreserveMost,
-            _ if u == LLVMCallConv::LLVMPreserveAllCallConv as u32 => CallingConvention::PreserveAll,
-            _ if u == LLVMCallConv::LLVMSwiftCallConv as u32 => CallingConvention::Swift,
-            _ if u == LLVMCallConv::LLVMCXXFASTTLSCallConv as u32 => CallingConvention::CXX_FastTLS,
-            _ if u == LLVMCallConv::LLVMX86StdcallCallConv as u32 => CallingConvention::X86_StdCall,
-            _ if u == LLVMCallConv::LLVMX86FastcallCallConv as u32 => CallingConvention::X86_FastCall,
-            _ if u == LLVMCallConv::LLVMX86RegCallCallConv as u32 => CallingConvention::X86_RegCall,
-            _ if u == LLVMCallConv::LLVMX86ThisCallCallConv as u32 => CallingConvention::X86_ThisCall,
-            _ if u == LLVMCallConv::LLVMX86VectorCallCallConv as u32 => CallingConvention::X86_VectorCall,
-            _ if u == LLVMCallConv::LLVMX86INTRCallConv as u32 => CallingConvention::X86_Intr,
-            _ if u == LLVMCallConv::LLVMX8664SysVCallConv as u32 => CallingConvention::X86_64_SysV,
-            _ if u == LLVMCallConv::LLVMARMAPCSCallConv as u32 => CallingConvention::ARM_APCS,
-            _ if u == LLVMCallConv::LLVMARMAAPCSCallConv as u32 => CallingConvention::ARM_AAPCS,
-            _ if u == LLVMCallConv::LLVMARMAAPCSVFPCallConv as u32 => CallingConvention::ARM_AAPCS_VFP,
-            _ if u == LLVMCallConv::LLVMMSP430INTRCallConv as u32 => CallingConvention::MSP430_INTR,
-            _ if u == LLVMCallConv::LLVMMSP430BUILTINCallConv as u32 => CallingConvention::MSP430_Builtin,
-            _ if u == LLVMCallConv::LLVMPTXKernelCallConv as u32 => CallingConvention::PTX_Kernel,
-            _ if u == LLVMCallConv::LLVMPTXDeviceCallConv as u32 => CallingConvention::PTX_Device,
-            _ if u == LLVMCallConv::LLVMSPIRFUNCCallConv as u32 => CallingConvention::SPIR_FUNC,
-            _ if u == LLVMCallConv::LLVMSPIRKERNELCallConv as u32 => CallingConvention::SPIR_KERNEL,
-            _ if u == LLVMCallConv::LLVMIntelOCLBICallConv as u32 => CallingConvention::Intel_OCL_BI,
-            _ if u == LLVMCallConv::LLVMWin64CallConv as u32 => CallingConvention::Win64,
-            _ if u == LLVMCallConv::LLVMHHVMCallConv as u32 => CallingConvention::HHVM,
-            _ if u == LLVMCallConv::LLVMHHVMCCallConv as u32 => CallingConvention::HHVM_C,
-            _ if u == LLVMCallConv::LLVMAVRINTRCallConv as u32 => CallingConvention::AVR_Intr,
-            _ if u == LLVMCallConv::LLVMAVRSIGNALCallConv as u32 => CallingConvention::AVR_Signal,
-            _ if u == LLVMCallConv::LLVMAVRBUILTINCallConv as u32 => CallingConvention::AVR_Builtin,
-            _ if u == LLVMCallConv::LLVMAMDGPUCSCallConv as u32 => CallingConvention::AMDGPU_CS,
-            _ if u == LLVMCallConv::LLVMAMDGPUESCallConv as u32 => CallingConvention::AMDGPU_ES,
-            _ if u == LLVMCallConv::LLVMAMDGPUGSCallConv as u32 => CallingConvention::AMDGPU_GS,
-            _ if u == LLVMCallConv::LLVMAMDGPUHSCallConv as u32 => CallingConvention::AMDGPU_HS,
-            _ if u == LLVMCallConv::LLVMAMDGPULSCallConv as u32 => CallingConvention::AMDGPU_LS,
-            _ if u == LLVMCallConv::LLVMAMDGPUPSCallConv as u32 => CallingConvention::AMDGPU_PS,
-            _ if u == LLVMCallConv::LLVMAMDGPUVSCallConv as u32 => CallingConvention::AMDGPU_VS,
-            _ if u == LLVMCallConv::LLVMAMDGPUKERNELCallConv as u32 => CallingConvention::AMDGPU_Kernel,
-            _ => CallingConvention::Numbered(u),
+    /// Convert the given functions, in order. With the `parallel` feature,
+    /// they are converted on `rayon`'s thread pool; the result is the same
+    /// either way.
+    pub(crate) fn from_llvm_refs(funcs: Vec<LLVMValueRef>, ctx: &mut ModuleContext) -> Vec<Self> {
+        #[cfg(feature = "parallel")]
+        {
+            if funcs.len() > 1 {
+                return Self::from_llvm_refs_in_parallel(funcs, ctx);
+            }
         }
+        funcs
+            .into_iter()
+            .map(|f| Self::from_llvm_ref(f, ctx))
+            .collect()
+    }
+
+    /// Each worker converts a contiguous run of the functions with its own
+    /// `fork()` of `ctx`, and the forks are `join()`ed back in order, which
+    /// numbers attribute groups and comdat members just as converting the
+    /// functions one by one would.
+    #[cfg(feature = "parallel")]
+    fn from_llvm_refs_in_parallel(funcs: Vec<LLVMValueRef>, ctx: &mut ModuleContext) -> Vec<Self> {
+        use rayon::prelude::*;
+
+        /// Lets LLVM values, and data keyed by them, cross threads. LLVM
+        /// allows this as long as no two threads modify the same
+        /// `LLVMContext` at once, which `ModuleContext::with_context_lock()`
+        /// prevents.
+        struct Unshared<T>(T);
+        unsafe impl<T> Send for Unshared<T> {}
+        unsafe impl<T> Sync for Unshared<T> {}
+
+        // several runs per thread, so that a few big functions don't leave
+        // the other threads idle
+        let num_runs = rayon::current_num_threads() * 4;
+        let run_len = funcs.len() / num_runs + 1;
+        let funcs = Unshared(funcs);
+        let base = Unshared(&*ctx);
+        let runs: Vec<Unshared<(Vec<Self>, ModuleContext)>> = (0 .. funcs.0.len())
+            .into_par_iter()
+            .step_by(run_len)
+            .map(|start| {
+                let end = std::cmp::min(start + run_len, funcs.0.len());
+                let mut forked = base.0.fork();
+                let converted = funcs.0[start .. end]
+                    .iter()
+                    .map(|&f| Self::from_llvm_ref(f, &mut forked))
+                    .collect();
+                Unshared((converted, forked))
+            })
+            .collect();
+
+        let mut functions = Vec::with_capacity(funcs.0.len());
+        for Unshared((mut run, forked)) in runs {
+            ctx.join(forked, &mut run);
+            functions.extend(run);
+        }
+        functions
     }
 }
 
+#[cfg(feature = "llvm-sys")]
+#[derive(Clone)]
 pub(crate) struct AttributesData {
     function_attribute_names: HashMap<u32, String>,
     param_attribute_names: HashMap<u32, String>,
+    /// Names of all the enum-style attributes known to this LLVM, including
+    /// ones we don't have first-class variants for
+    all_attribute_names: HashMap<u32, String>,
 }
 
+/// Names of all enum-style attributes (of any kind) in any LLVM version we
+/// support. Not all of these exist in every LLVM version.
+#[cfg(feature = "llvm-sys")]
+const ALL_ATTRIBUTE_NAMES: &[&str] = &[
+    "align",
+    "alignstack",
+    "allocalign",
+    "allockind",
+    "allocptr",
+    "allocsize",
+    "alwaysinline",
+    "argmemonly",
+    "builtin",
+    "byref",
+    "byval",
+    "cold",
+    "convergent",
+    "dereferenceable",
+    "dereferenceable_or_null",
+    "disable_sanitizer_instrumentation",
+    "elementtype",
+    "fn_ret_thunk_extern",
+    "hot",
+    "immarg",
+    "inaccessiblemem_or_argmemonly",
+    "inaccessiblememonly",
+    "inalloca",
+    "inlinehint",
+    "inreg",
+    "jumptable",
+    "memory",
+    "minsize",
+    "mustprogress",
+    "naked",
+    "nest",
+    "noalias",
+    "nobuiltin",
+    "nocallback",
+    "nocapture",
+    "nocf_check",
+    "noduplicate",
+    "nofpclass",
+    "nofree",
+    "noimplicitfloat",
+    "noinline",
+    "nomerge",
+    "nonlazybind",
+    "nonnull",
+    "noprofile",
+    "norecurse",
+    "noredzone",
+    "noreturn",
+    "nosanitize_bounds",
+    "nosanitize_coverage",
+    "nosync",
+    "noundef",
+    "nounwind",
+    "null_pointer_is_valid",
+    "optforfuzzing",
+    "optnone",
+    "optsize",
+    "preallocated",
+    "presplitcoroutine",
+    "readnone",
+    "readonly",
+    "returned",
+    "returns_twice",
+    "safestack",
+    "sanitize_address",
+    "sanitize_hwaddress",
+    "sanitize_memory",
+    "sanitize_memtag",
+    "sanitize_thread",
+    "shadowcallstack",
+    "signext",
+    "skipprofile",
+    "speculatable",
+    "speculative_load_hardening",
+    "sret",
+    "ssp",
+    "sspreq",
+    "sspstrong",
+    "strictfp",
+    "swiftasync",
+    "swifterror",
+    "swiftself",
+    "uwtable",
+    "vscale_range",
+    "willreturn",
+    "writeonly",
+    "zeroext",
+];
+
+#[cfg(feature = "llvm-sys")]
 impl AttributesData {
     pub fn create() -> Self {
         let function_attribute_names = [
             "alignstack",
+            #[cfg(feature = "llvm-15-or-greater")]
+            "allockind",
             "allocsize",
             "alwaysinline",
             "builtin",
             "cold",
             "convergent",
+            #[cfg(feature = "llvm-14-or-greater")]
+            "disable_sanitizer_instrumentation",
+            #[cfg(feature = "llvm-15-or-greater")]
+            "fn_ret_thunk_extern",
+            #[cfg(feature = "llvm-12-or-greater")]
+            "hot",
+            #[cfg(feature = "llvm-15-or-lower")]
             "inaccessiblememonly",
+            #[cfg(feature = "llvm-15-or-lower")]
             "inaccessiblemem_or_argmemonly",
             "inlinehint",
             "jumptable",
+            #[cfg(feature = "llvm-16-or-greater")]
+            "memory",
             "minsize",
+            #[cfg(feature = "llvm-12-or-greater")]
+            "mustprogress",
             "naked",
             "nobuiltin",
+            #[cfg(feature = "llvm-14-or-greater")]
+            "nocallback",
             "nocf_check",
             "noduplicate",
             #[cfg(feature = "llvm-9-or-greater")]
@@ -602,6 +1258,12 @@ impl AttributesData {
             "noinline",
             #[cfg(feature = "llvm-11-or-greater")]
             "nomerge",
+            #[cfg(feature = "llvm-13-or-greater")]
+            "noprofile",
+            #[cfg(feature = "llvm-16-or-greater")]
+            "nosanitize_bounds",
+            #[cfg(feature = "llvm-13-or-greater")]
+            "nosanitize_coverage",
             "nonlazybind",
             "noredzone",
             "noreturn",
@@ -617,9 +1279,15 @@ impl AttributesData {
             "optforfuzzing",
             "optnone",
             "optsize",
+            #[cfg(feature = "llvm-15-or-greater")]
+            "presplitcoroutine",
+            #[cfg(feature = "llvm-15-or-lower")]
             "readnone",
+            #[cfg(feature = "llvm-15-or-lower")]
             "readonly",
+            #[cfg(feature = "llvm-15-or-lower")]
             "writeonly",
+            #[cfg(feature = "llvm-15-or-lower")]
             "argmemonly",
             "safestack",
             "sanitize_address",
@@ -629,6 +1297,8 @@ impl AttributesData {
             #[cfg(feature = "llvm-9-or-greater")]
             "sanitize_memtag",
             "shadowcallstack",
+            #[cfg(feature = "llvm-16-or-greater")]
+            "skipprofile",
             "speculative_load_hardening",
             "speculatable",
             "ssp",
@@ -636,6 +1306,8 @@ impl AttributesData {
             "sspstrong",
             "strictfp",
             "uwtable",
+            #[cfg(feature = "llvm-12-or-greater")]
+            "vscale_range",
         ]
         .iter()
         .map(|&attrname| {
@@ -651,12 +1323,18 @@ impl AttributesData {
             "inreg",
             "byval",
             #[cfg(feature = "llvm-11-or-greater")]
+            "byref",
+            #[cfg(feature = "llvm-11-or-greater")]
             "preallocated",
             "inalloca",
             "sret",
+            #[cfg(feature = "llvm-14-or-greater")]
+            "elementtype",
             "align",
             "noalias",
             "nocapture",
+            #[cfg(feature = "llvm-17-or-greater")]
+            "nofpclass",
             #[cfg(feature = "llvm-9-or-greater")]
             "nofree",
             "nest",
@@ -664,12 +1342,21 @@ impl AttributesData {
             "nonnull",
             "dereferenceable",
             "dereferenceable_or_null",
+            "readnone",
+            "readonly",
+            "writeonly",
             "swiftself",
             "swifterror",
+            #[cfg(feature = "llvm-13-or-greater")]
+            "swiftasync",
             #[cfg(feature = "llvm-9-or-greater")]
             "immarg",
             #[cfg(feature = "llvm-11-or-greater")]
             "noundef",
+            #[cfg(feature = "llvm-15-or-greater")]
+            "allocalign",
+            #[cfg(feature = "llvm-15-or-greater")]
+            "allocptr",
         ]
         .iter()
         .map(|&attrname| {
@@ -679,9 +1366,24 @@ impl AttributesData {
             (kind, attrname.into())
         })
         .collect();
+        let all_attribute_names = ALL_ATTRIBUTE_NAMES
+            .iter()
+            .filter_map(|&attrname| {
+                let cstr = CString::new(attrname).unwrap();
+                let kind =
+                    unsafe { LLVMGetEnumAttributeKindForName(cstr.as_ptr(), attrname.len()) };
+                // a kind of 0 means this attribute doesn't exist in this LLVM version
+                if kind == 0 {
+                    None
+                } else {
+                    Some((kind, attrname.into()))
+                }
+            })
+            .collect();
         Self {
             function_attribute_names,
             param_attribute_names,
+            all_attribute_names,
         }
     }
 
@@ -696,84 +1398,33 @@ impl AttributesData {
     pub fn lookup_param_attr(&self, kind: u32) -> Option<&str> {
         self.param_attribute_names.get(&kind).map(|s| s.as_str())
     }
+
+    /// Get the string name of any enum-style attribute. If LLVM's name for it
+    /// can't be determined, falls back to the numeric kind.
+    pub fn attribute_name(&self, kind: u32) -> String {
+        match self.all_attribute_names.get(&kind) {
+            Some(name) => name.clone(),
+            None => kind.to_string(),
+        }
+    }
 }
 
+#[cfg(feature = "llvm-sys")]
 impl FunctionAttribute {
     pub(crate) fn from_llvm_ref(a: LLVMAttributeRef, attrsdata: &AttributesData) -> Self {
         if unsafe { LLVMIsEnumAttribute(a) } != 0 {
             let kind = unsafe { LLVMGetEnumAttributeKind(a) };
+            let value = unsafe { LLVMGetEnumAttributeValue(a) };
             match attrsdata.lookup_function_attr(kind) {
-                Some("alignstack") => Self::AlignStack(unsafe { LLVMGetEnumAttributeValue(a) }),
-                Some("allocsize") => {
-                    let value = unsafe { LLVMGetEnumAttributeValue(a) };
-                    // looking at the LLVM implementation as of this writing
-                    // (near the top of Attributes.cpp),
-                    // the elt_size value is the upper 32 bits, and the num_elts value
-                    // is the lower 32 bits, or the sentinel -1 for None
-                    let elt_size = (value >> 32) as u32;
-                    let num_elts = match (value & 0xFFFF_FFFF) as u32 {
-                        0xFFFF_FFFF => None,
-                        val => Some(val),
-                    };
-                    Self::AllocSize { elt_size, num_elts }
-                },
-                Some("alwaysinline") => Self::AlwaysInline,
-                Some("builtin") => Self::Builtin,
-                Some("cold") => Self::Cold,
-                Some("convergent") => Self::Convergent,
-                Some("inaccessiblememonly") => Self::InaccessibleMemOnly,
-                Some("inaccessiblemem_or_argmemonly") => Self::InaccessibleMemOrArgMemOnly,
-                Some("inlinehint") => Self::InlineHint,
-                Some("jumptable") => Self::JumpTable,
-                Some("minsize") => Self::MinimizeSize,
-                Some("naked") => Self::Naked,
-                Some("nobuiltin") => Self::NoBuiltin,
-                Some("nocf_check") => Self::NoCFCheck,
-                Some("noduplicate") => Self::NoDuplicate,
-                #[cfg(feature = "llvm-9-or-greater")]
-                Some("nofree") => Self::NoFree,
-                Some("noimplicitfloat") => Self::NoImplicitFloat,
-                Some("noinline") => Self::NoInline,
-                #[cfg(feature = "llvm-11-or-greater")]
-                Some("nomerge") => Self::NoMerge,
-                Some("nonlazybind") => Self::NonLazyBind,
-                Some("noredzone") => Self::NoRedZone,
-                Some("noreturn") => Self::NoReturn,
-                Some("norecurse") => Self::NoRecurse,
-                #[cfg(feature = "llvm-9-or-greater")]
-                Some("willreturn") => Self::WillReturn,
-                Some("returns_twice") => Self::ReturnsTwice,
-                #[cfg(feature = "llvm-9-or-greater")]
-                Some("nosync") => Self::NoSync,
-                Some("nounwind") => Self::NoUnwind,
-                #[cfg(feature = "llvm-11-or-greater")]
-                Some("null_pointer_is_valid") => Self::NullPointerIsValid,
-                Some("optforfuzzing") => Self::OptForFuzzing,
-                Some("optnone") => Self::OptNone,
-                Some("optsize") => Self::OptSize,
-                Some("readnone") => Self::ReadNone,
-                Some("readonly") => Self::ReadOnly,
-                Some("writeonly") => Self::WriteOnly,
-                Some("argmemonly") => Self::ArgMemOnly,
-                Some("safestack") => Self::SafeStack,
-                Some("sanitize_address") => Self::SanitizeAddress,
-                Some("sanitize_memory") => Self::SanitizeMemory,
-                Some("sanitize_thread") => Self::SanitizeThread,
-                Some("sanitize_hwaddress") => Self::SanitizeHWAddress,
-                #[cfg(feature = "llvm-9-or-greater")]
-                Some("sanitize_memtag") => Self::SanitizeMemTag,
-                Some("shadowcallstack") => Self::ShadowCallStack,
-                Some("speculative_load_hardening") => Self::SpeculativeLoadHardening,
-                Some("speculatable") => Self::Speculatable,
-                Some("ssp") => Self::StackProtect,
-                Some("sspreq") => Self::StackProtectReq,
-                Some("sspstrong") => Self::StackProtectStrong,
-                Some("strictfp") => Self::StrictFP,
-                Some("uwtable") => Self::UWTable,
-                Some(s) => panic!("Unhandled value from lookup_function_attr: {:?}", s),
+                Some(name) => Self::from_enum_name(name, value).unwrap_or_else(|| {
+                    panic!("Unhandled value from lookup_function_attr: {:?}", name)
+                }),
                 None => {
                     debug!("unknown enum function attr {}", kind);
-                    Self::UnknownAttribute
+                    Self::UnknownAttribute {
+                        kind: attrsdata.attribute_name(kind),
+                        value: unsafe { LLVMGetEnumAttributeValue(a) },
+                    }
                 },
             }
         } else if unsafe { LLVMIsStringAttribute(a) } != 0 {
@@ -783,11 +1434,15 @@ impl FunctionAttribute {
             }
         } else {
             debug!("Encountered an unknown function attribute: neither enum nor string");
-            Self::UnknownAttribute
+            Self::UnknownAttribute {
+                kind: attrsdata.attribute_name(unsafe { LLVMGetEnumAttributeKind(a) }),
+                value: 0,
+            }
         }
     }
 }
 
+#[cfg(feature = "llvm-sys")]
 impl ParameterAttribute {
     pub(crate) fn from_llvm_ref(
         a: LLVMAttributeRef,
@@ -796,41 +1451,17 @@ impl ParameterAttribute {
     ) -> Self {
         if unsafe { LLVMIsEnumAttribute(a) } != 0 {
             let kind = unsafe { LLVMGetEnumAttributeKind(a) };
+            let value = unsafe { LLVMGetEnumAttributeValue(a) };
             match attrsdata.lookup_param_attr(kind) {
-                Some("zeroext") => Self::ZeroExt,
-                Some("signext") => Self::SignExt,
-                Some("inreg") => Self::InReg,
-                #[cfg(feature = "llvm-11-or-lower")]
-                Some("byval") => Self::ByVal,
-                #[cfg(feature = "llvm-11")]
-                Some("preallocated") => Self::Preallocated,
-                #[cfg(feature = "llvm-12-or-lower")]
-                Some("inalloca") => Self::InAlloca,
-                #[cfg(feature = "llvm-11-or-lower")]
-                Some("sret") => Self::SRet,
-                Some("align") => Self::Alignment(unsafe { LLVMGetEnumAttributeValue(a) }),
-                Some("noalias") => Self::NoAlias,
-                Some("nocapture") => Self::NoCapture,
-                #[cfg(feature = "llvm-9-or-greater")]
-                Some("nofree") => Self::NoFree,
-                Some("nest") => Self::Nest,
-                Some("returned") => Self::Returned,
-                Some("nonnull") => Self::NonNull,
-                Some("dereferenceable") => {
-                    Self::Dereferenceable(unsafe { LLVMGetEnumAttributeValue(a) })
-                },
-                Some("dereferenceable_or_null") => {
-                    Self::DereferenceableOrNull(unsafe { LLVMGetEnumAttributeValue(a) })
-                },
-                Some("swiftself") => Self::SwiftSelf,
-                Some("swifterror") => Self::SwiftError,
-                Some("immarg") => Self::ImmArg,
-                #[cfg(feature = "llvm-11-or-greater")]
-                Some("noundef") => Self::NoUndef,
-                Some(s) => panic!("Unhandled value from lookup_param_attr: {:?}", s),
+                Some(name) => Self::from_enum_name(name, value).unwrap_or_else(|| {
+                    panic!("Unhandled value from lookup_param_attr: {:?}", name)
+                }),
                 None => {
                     debug!("unknown enum param attr {}", kind);
-                    Self::UnknownAttribute
+                    Self::UnknownAttribute {
+                        kind: attrsdata.attribute_name(kind),
+                        value: unsafe { LLVMGetEnumAttributeValue(a) },
+                    }
                 },
             }
         } else if unsafe { LLVMIsStringAttribute(a) } != 0 {
@@ -842,28 +1473,53 @@ impl ParameterAttribute {
             #[cfg(feature = "llvm-11-or-lower")]
             {
                 debug!("Encountered a type attr, which shouldn't happen on LLVM 11 or lower");
-                Self::UnknownAttribute
+                Self::from_llvm_ref_untyped(a, attrsdata)
             }
             #[cfg(feature = "llvm-12-or-greater")]
             {
                 let kind = unsafe { LLVMGetEnumAttributeKind(a) };
                 let ty = types.type_from_llvm_ref(unsafe { LLVMGetTypeAttributeValue(a) });
                 match attrsdata.lookup_param_attr(kind) {
-                    Some("byval") => Self::ByVal(ty),
-                    Some("preallocated") => Self::Preallocated(ty),
-                    #[cfg(feature = "llvm-13-or-greater")]
-                    Some("inalloca") => Self::InAlloca(ty),
-                    Some("sret") => Self::SRet(ty),
-                    Some(s) => panic!("Unhandled value from lookup_param_attr: {:?}", s),
+                    Some(name) => Self::from_type_name(name, ty.clone()).unwrap_or_else(|| {
+                        panic!("Unhandled value from lookup_param_attr: {:?}", name)
+                    }),
                     None => {
                         debug!("unknown type param attr {}", kind);
-                        Self::UnknownTypeAttribute(ty)
+                        Self::UnknownTypeAttribute {
+                            kind: attrsdata.attribute_name(kind),
+                            ty,
+                        }
                     },
                 }
             }
         } else {
-            debug!("Encountered an unknown parameter attribute: neither enum, string, nor type");
-            Self::UnknownAttribute
+            Self::from_llvm_ref_untyped(a, attrsdata)
+        }
+    }
+
+    /// Handle an attribute which is neither enum, string, nor type. On LLVM 11
+    /// or lower, the C API can't identify type attributes (or get their
+    /// types), so they end up here; we can still get their kind.
+    fn from_llvm_ref_untyped(a: LLVMAttributeRef, attrsdata: &AttributesData) -> Self {
+        let kind = unsafe { LLVMGetEnumAttributeKind(a) };
+        match attrsdata.lookup_param_attr(kind) {
+            #[cfg(feature = "llvm-11-or-lower")]
+            Some("byval") => Self::ByVal(None),
+            #[cfg(all(feature = "llvm-11-or-greater", feature = "llvm-11-or-lower"))]
+            Some("byref") => Self::ByRef(None),
+            #[cfg(all(feature = "llvm-11-or-greater", feature = "llvm-11-or-lower"))]
+            Some("preallocated") => Self::Preallocated(None),
+            #[cfg(feature = "llvm-11-or-lower")]
+            Some("sret") => Self::SRet(None),
+            _ => {
+                debug!(
+                    "Encountered an unknown parameter attribute: neither enum, string, nor type"
+                );
+                Self::UnknownAttribute {
+                    kind: attrsdata.attribute_name(kind),
+                    value: 0,
+                }
+            },
         }
     }
 
@@ -1,7 +1,8 @@
-use crate::constant::ConstantRef;
-#[cfg(feature = "llvm-9-or-greater")]
+use crate::constant::{Constant, ConstantRef};
 use crate::debugloc::{DebugLoc, HasDebugLoc};
-use crate::function::{CallingConvention, FunctionAttribute, ParameterAttribute};
+use crate::function::{CallingConvention, FunctionAttribute, GroupID, ParameterAttribute};
+use crate::intrinsic::Intrinsic;
+use crate::module::FunctionIndex;
 use crate::name::Name;
 use crate::operand::Operand;
 use crate::predicates::*;
@@ -76,7 +77,6 @@ pub enum Instruction {
     FCmp(FCmp),
     Phi(Phi),
     Select(Select),
-    #[cfg(feature = "llvm-10-or-greater")]
     Freeze(Freeze),
     Call(Call),
     VAArg(VAArg),
@@ -137,7 +137,6 @@ impl Typed for Instruction {
             Instruction::FCmp(i) => types.type_of(i),
             Instruction::Phi(i) => types.type_of(i),
             Instruction::Select(i) => types.type_of(i),
-            #[cfg(feature = "llvm-10-or-greater")]
             Instruction::Freeze(i) => types.type_of(i),
             Instruction::Call(i) => types.type_of(i),
             Instruction::VAArg(i) => types.type_of(i),
@@ -148,7 +147,6 @@ impl Typed for Instruction {
     }
 }
 
-#[cfg(feature = "llvm-9-or-greater")]
 impl HasDebugLoc for Instruction {
     fn get_debug_loc(&self) -> &Option<DebugLoc> {
         match self {
@@ -200,7 +198,6 @@ impl HasDebugLoc for Instruction {
             Instruction::FCmp(i) => i.get_debug_loc(),
             Instruction::Phi(i) => i.get_debug_loc(),
             Instruction::Select(i) => i.get_debug_loc(),
-            #[cfg(feature = "llvm-10-or-greater")]
             Instruction::Freeze(i) => i.get_debug_loc(),
             Instruction::Call(i) => i.get_debug_loc(),
             Instruction::VAArg(i) => i.get_debug_loc(),
@@ -264,7 +261,6 @@ impl Instruction {
             Instruction::FCmp(i) => Some(&i.dest),
             Instruction::Phi(i) => Some(&i.dest),
             Instruction::Select(i) => Some(&i.dest),
-            #[cfg(feature = "llvm-10-or-greater")]
             Instruction::Freeze(i) => Some(&i.dest),
             Instruction::Call(i) => i.dest.as_ref(),
             Instruction::VAArg(i) => Some(&i.dest),
@@ -325,7 +321,6 @@ impl Instruction {
             Instruction::FCmp(_) => false,
             Instruction::Phi(_) => false,
             Instruction::Select(_) => false,
-            #[cfg(feature = "llvm-10-or-greater")]
             Instruction::Freeze(_) => false,
             Instruction::Call(_) => false,
             Instruction::VAArg(_) => false,
@@ -334,6 +329,109 @@ impl Instruction {
             Instruction::CleanupPad(_) => false,
         }
     }
+
+    /// Whether the `Instruction` may read from memory.
+    ///
+    /// Like LLVM, this treats ordered (stronger than `unordered`) atomic and
+    /// volatile stores as reading memory. For calls, this takes into account
+    /// the function attributes of both the call and the called function (if
+    /// it's a direct call to a function in the indexed `Module`), and known
+    /// intrinsics.
+    /// Attributes which only restrict _which_ memory may be accessed, such as
+    /// `ArgMemOnly`, don't make this `false`.
+    ///
+    /// `functions` indexes the `Module` containing the `Instruction`; see
+    /// `Module::function_index()`.
+    pub fn may_read_memory(&self, functions: &FunctionIndex) -> bool {
+        match self {
+            Instruction::Load(_) => true,
+            Instruction::Store(i) => i.volatile || !is_unordered(&i.atomicity),
+            Instruction::Fence(_) => true,
+            Instruction::CmpXchg(_) => true,
+            Instruction::AtomicRMW(_) => true,
+            Instruction::VAArg(_) => true,
+            Instruction::Call(call) => call.memory_access(functions).0,
+            _ => false,
+        }
+    }
+
+    /// Whether the `Instruction` may write to memory.
+    ///
+    /// Like LLVM, this treats ordered (stronger than `unordered`) atomic and
+    /// volatile loads as writing memory. Calls are handled as in
+    /// `may_read_memory()`.
+    pub fn may_write_memory(&self, functions: &FunctionIndex) -> bool {
+        match self {
+            Instruction::Load(i) => i.volatile || !is_unordered(&i.atomicity),
+            Instruction::Store(_) => true,
+            Instruction::Fence(_) => true,
+            Instruction::CmpXchg(_) => true,
+            Instruction::AtomicRMW(_) => true,
+            Instruction::VAArg(_) => true,
+            Instruction::Call(call) => call.memory_access(functions).1,
+            _ => false,
+        }
+    }
+
+    /// Whether the `Instruction` may throw an exception (unwind). Only calls
+    /// can; they can't if either the call or the called function is
+    /// `NoUnwind`.
+    pub fn may_throw(&self, functions: &FunctionIndex) -> bool {
+        match self {
+            Instruction::Call(call) => !call
+                .all_function_attributes(functions)
+                .any(|attr| *attr == FunctionAttribute::NoUnwind),
+            _ => false,
+        }
+    }
+
+    /// Whether the `Instruction` may have side effects: that is, it may write
+    /// to memory, may throw, or (for calls) may not return.
+    pub fn may_have_side_effects(&self, functions: &FunctionIndex) -> bool {
+        self.may_write_memory(functions)
+            || self.may_throw(functions)
+            || !self.will_return(functions)
+    }
+
+    /// Whether the `Instruction` could be removed if its result is unused
+    /// (or it has no result). Like LLVM's `wouldInstructionBeTriviallyDead()`,
+    /// this does not check whether the result actually is unused.
+    ///
+    /// This is the case if the `Instruction` has no side effects and isn't an
+    /// exception-handling pad (`LandingPad`, `CatchPad`, or `CleanupPad`).
+    pub fn is_trivially_dead(&self, functions: &FunctionIndex) -> bool {
+        match self {
+            Instruction::LandingPad(_) => false,
+            Instruction::CatchPad(_) => false,
+            Instruction::CleanupPad(_) => false,
+            _ => !self.may_have_side_effects(functions),
+        }
+    }
+
+    /// Whether the `Instruction` always returns (i.e., doesn't loop forever or
+    /// otherwise not return). Only calls may not, unless they are `WillReturn`.
+    fn will_return(&self, functions: &FunctionIndex) -> bool {
+        match self {
+            Instruction::Call(call) => {
+                matches!(call.as_intrinsic(), Some(Intrinsic::Dbg(_)))
+                    || call
+                        .all_function_attributes(functions)
+                        .any(|attr| *attr == FunctionAttribute::WillReturn)
+            },
+            _ => true,
+        }
+    }
+}
+
+/// Whether the given atomicity is non-atomic or `unordered`
+fn is_unordered(atomicity: &Option<Atomicity>) -> bool {
+    match atomicity {
+        None => true,
+        Some(atomicity) => matches!(
+            atomicity.mem_ordering,
+            MemoryOrdering::Unordered | MemoryOrdering::NotAtomic
+        ),
+    }
 }
 
 /* --TODO not yet implemented: metadata
@@ -392,7 +490,6 @@ impl HasMetadata for Instruction {
             Instruction::FCmp(i) => &i.metadata,
             Instruction::Phi(i) => &i.metadata,
             Instruction::Select(i) => &i.metadata,
-            #[cfg(feature="llvm-10-or-greater")]
             Instruction::Freeze(i) => &i.metadata,
             Instruction::Call(i) => &i.metadata,
             Instruction::VAArg(i) => &i.metadata,
@@ -451,25 +548,24 @@ impl Instruction {
     /// [`groups::UnaryOp`](groups/enum.UnaryOp.html), without actually using
     /// `try_into()` (which would consume it)
     pub fn is_unary_op(&self) -> bool {
-        match self {
-            Instruction::AddrSpaceCast(_) => true,
-            Instruction::BitCast(_) => true,
-            Instruction::FNeg(_) => true,
-            Instruction::FPExt(_) => true,
-            Instruction::FPToSI(_) => true,
-            Instruction::FPToUI(_) => true,
-            Instruction::FPTrunc(_) => true,
-            #[cfg(feature = "llvm-10-or-greater")]
-            Instruction::Freeze(_) => true,
-            Instruction::IntToPtr(_) => true,
-            Instruction::PtrToInt(_) => true,
-            Instruction::SExt(_) => true,
-            Instruction::SIToFP(_) => true,
-            Instruction::Trunc(_) => true,
-            Instruction::UIToFP(_) => true,
-            Instruction::ZExt(_) => true,
-            _ => false,
-        }
+        matches!(
+            self,
+            Instruction::AddrSpaceCast(_)
+                | Instruction::BitCast(_)
+                | Instruction::FNeg(_)
+                | Instruction::FPExt(_)
+                | Instruction::FPToSI(_)
+                | Instruction::FPToUI(_)
+                | Instruction::FPTrunc(_)
+                | Instruction::Freeze(_)
+                | Instruction::IntToPtr(_)
+                | Instruction::PtrToInt(_)
+                | Instruction::SExt(_)
+                | Instruction::SIToFP(_)
+                | Instruction::Trunc(_)
+                | Instruction::UIToFP(_)
+                | Instruction::ZExt(_)
+        )
     }
 }
 
@@ -524,7 +620,6 @@ impl Display for Instruction {
             Instruction::FCmp(i) => write!(f, "{}", i),
             Instruction::Phi(i) => write!(f, "{}", i),
             Instruction::Select(i) => write!(f, "{}", i),
-            #[cfg(feature = "llvm-10-or-greater")]
             Instruction::Freeze(i) => write!(f, "{}", i),
             Instruction::Call(i) => write!(f, "{}", i),
             Instruction::VAArg(i) => write!(f, "{}", i),
@@ -553,7 +648,6 @@ macro_rules! impl_inst {
             }
         }
 
-        #[cfg(feature = "llvm-9-or-greater")]
         impl HasDebugLoc for $inst {
             fn get_debug_loc(&self) -> &Option<DebugLoc> {
                 &self.debugloc
@@ -599,7 +693,7 @@ macro_rules! impl_unop {
 // If possible, prefer `binop_same_type!` or `binop_left_type!`, which
 // provide additional impls
 macro_rules! impl_binop {
-    ($inst:ty, $id:ident, $dispname:expr) => {
+    ($inst:ty, $id:ident, $dispname:expr $(, $flag:ident)*) => {
         impl_hasresult!($inst);
 
         impl BinaryOp for $inst {
@@ -629,12 +723,9 @@ macro_rules! impl_binop {
 
         impl Display for $inst {
             fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-                write!(
-                    f,
-                    "{} = {} {}, {}",
-                    &self.dest, $dispname, &self.operand0, &self.operand1,
-                )?;
-                #[cfg(feature = "llvm-9-or-greater")]
+                write!(f, "{} = {}", &self.dest, $dispname)?;
+                $(self.$flag.fmt_flag(f, stringify!($flag))?;)*
+                write!(f, " {}, {}", &self.operand0, &self.operand1)?;
                 if self.debugloc.is_some() {
                     write!(f, " (with debugloc)")?;
                 }
@@ -646,7 +737,7 @@ macro_rules! impl_binop {
 
 // Use on unops where the result type is the same as the operand type
 macro_rules! unop_same_type {
-    ($inst:ty, $dispname:expr) => {
+    ($inst:ty, $dispname:expr $(, $flag:ident)*) => {
         impl_unop!($inst);
 
         impl Typed for $inst {
@@ -657,8 +748,9 @@ macro_rules! unop_same_type {
 
         impl Display for $inst {
             fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-                write!(f, "{} = {} {}", &self.dest, $dispname, &self.operand)?;
-                #[cfg(feature = "llvm-9-or-greater")]
+                write!(f, "{} = {}", &self.dest, $dispname)?;
+                $(self.$flag.fmt_flag(f, stringify!($flag))?;)*
+                write!(f, " {}", &self.operand)?;
                 if self.debugloc.is_some() {
                     write!(f, " (with debugloc)")?;
                 }
@@ -681,7 +773,6 @@ macro_rules! unop_explicitly_typed {
                     "{} = {} {} to {}",
                     &self.dest, $dispname, &self.operand, &self.to_type,
                 )?;
-                #[cfg(feature = "llvm-9-or-greater")]
                 if self.debugloc.is_some() {
                     write!(f, " (with debugloc)")?;
                 }
@@ -693,8 +784,8 @@ macro_rules! unop_explicitly_typed {
 
 // Use on binops where the result type is the same as both operand types
 macro_rules! binop_same_type {
-    ($inst:ty, $id:ident, $dispname:expr) => {
-        impl_binop!($inst, $id, $dispname);
+    ($inst:ty, $id:ident, $dispname:expr $(, $flag:ident)*) => {
+        impl_binop!($inst, $id, $dispname $(, $flag)*);
 
         impl Typed for $inst {
             fn get_type(&self, types: &Types) -> TypeRef {
@@ -708,8 +799,8 @@ macro_rules! binop_same_type {
 
 // Use on binops where the result type is the same as the first operand type
 macro_rules! binop_left_type {
-    ($inst:ty, $id:ident, $dispname:expr) => {
-        impl_binop!($inst, $id, $dispname);
+    ($inst:ty, $id:ident, $dispname:expr $(, $flag:ident)*) => {
+        impl_binop!($inst, $id, $dispname $(, $flag)*);
 
         impl Typed for $inst {
             fn get_type(&self, types: &Types) -> TypeRef {
@@ -747,15 +838,14 @@ pub struct Add {
     pub operand0: Operand,
     pub operand1: Operand,
     pub dest: Name,
-    // pub nsw: bool,  // getters for these seem to not be exposed in the LLVM C API, only in the C++ one
-    // pub nuw: bool,  // getters for these seem to not be exposed in the LLVM C API, only in the C++ one
-    #[cfg(feature = "llvm-9-or-greater")]
+    pub nsw: bool,
+    pub nuw: bool,
     pub debugloc: Option<DebugLoc>,
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
 
 impl_inst!(Add, Add);
-binop_same_type!(Add, Add, "add");
+binop_same_type!(Add, Add, "add", nuw, nsw);
 
 /// Integer subtract.
 /// See [LLVM 14 docs on the 'sub' instruction](https://releases.llvm.org/14.0.0/docs/LangRef.html#sub-instruction)
@@ -764,15 +854,14 @@ pub struct Sub {
     pub operand0: Operand,
     pub operand1: Operand,
     pub dest: Name,
-    // pub nsw: bool,  // getters for these seem to not be exposed in the LLVM C API, only in the C++ one
-    // pub nuw: bool,  // getters for these seem to not be exposed in the LLVM C API, only in the C++ one
-    #[cfg(feature = "llvm-9-or-greater")]
+    pub nsw: bool,
+    pub nuw: bool,
     pub debugloc: Option<DebugLoc>,
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
 
 impl_inst!(Sub, Sub);
-binop_same_type!(Sub, Sub, "sub");
+binop_same_type!(Sub, Sub, "sub", nuw, nsw);
 
 /// Integer multiply.
 /// See [LLVM 14 docs on the 'mul' instruction](https://releases.llvm.org/14.0.0/docs/LangRef.html#mul-instruction)
@@ -781,15 +870,14 @@ pub struct Mul {
     pub operand0: Operand,
     pub operand1: Operand,
     pub dest: Name,
-    // pub nsw: bool,  // getters for these seem to not be exposed in the LLVM C API, only in the C++ one
-    // pub nuw: bool,  // getters for these seem to not be exposed in the LLVM C API, only in the C++ one
-    #[cfg(feature = "llvm-9-or-greater")]
+    pub nsw: bool,
+    pub nuw: bool,
     pub debugloc: Option<DebugLoc>,
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
 
 impl_inst!(Mul, Mul);
-binop_same_type!(Mul, Mul, "mul");
+binop_same_type!(Mul, Mul, "mul", nuw, nsw);
 
 /// Unsigned integer divide.
 /// See [LLVM 14 docs on the 'udiv' instruction](https://releases.llvm.org/14.0.0/docs/LangRef.html#udiv-instruction)
@@ -798,14 +886,13 @@ pub struct UDiv {
     pub operand0: Operand,
     pub operand1: Operand,
     pub dest: Name,
-    // pub exact: bool,  // getters for these seem to not be exposed in the LLVM C API, only in the C++ one
-    #[cfg(feature = "llvm-9-or-greater")]
+    pub exact: bool,
     pub debugloc: Option<DebugLoc>,
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
 
 impl_inst!(UDiv, UDiv);
-binop_same_type!(UDiv, UDiv, "udiv");
+binop_same_type!(UDiv, UDiv, "udiv", exact);
 
 /// Signed integer divide.
 /// See [LLVM 14 docs on the 'sdiv' instruction](https://releases.llvm.org/14.0.0/docs/LangRef.html#sdiv-instruction)
@@ -814,14 +901,13 @@ pub struct SDiv {
     pub operand0: Operand,
     pub operand1: Operand,
     pub dest: Name,
-    // pub exact: bool,  // getters for these seem to not be exposed in the LLVM C API, only in the C++ one
-    #[cfg(feature = "llvm-9-or-greater")]
+    pub exact: bool,
     pub debugloc: Option<DebugLoc>,
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
 
 impl_inst!(SDiv, SDiv);
-binop_same_type!(SDiv, SDiv, "sdiv");
+binop_same_type!(SDiv, SDiv, "sdiv", exact);
 
 /// Unsigned integer remainder.
 /// See [LLVM 14 docs on the 'urem' instruction](https://releases.llvm.org/14.0.0/docs/LangRef.html#urem-instruction)
@@ -830,7 +916,6 @@ pub struct URem {
     pub operand0: Operand,
     pub operand1: Operand,
     pub dest: Name,
-    #[cfg(feature = "llvm-9-or-greater")]
     pub debugloc: Option<DebugLoc>,
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
@@ -845,7 +930,6 @@ pub struct SRem {
     pub operand0: Operand,
     pub operand1: Operand,
     pub dest: Name,
-    #[cfg(feature = "llvm-9-or-greater")]
     pub debugloc: Option<DebugLoc>,
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
@@ -860,7 +944,6 @@ pub struct And {
     pub operand0: Operand,
     pub operand1: Operand,
     pub dest: Name,
-    #[cfg(feature = "llvm-9-or-greater")]
     pub debugloc: Option<DebugLoc>,
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
@@ -875,7 +958,6 @@ pub struct Or {
     pub operand0: Operand,
     pub operand1: Operand,
     pub dest: Name,
-    #[cfg(feature = "llvm-9-or-greater")]
     pub debugloc: Option<DebugLoc>,
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
@@ -890,7 +972,6 @@ pub struct Xor {
     pub operand0: Operand,
     pub operand1: Operand,
     pub dest: Name,
-    #[cfg(feature = "llvm-9-or-greater")]
     pub debugloc: Option<DebugLoc>,
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
@@ -905,15 +986,14 @@ pub struct Shl {
     pub operand0: Operand,
     pub operand1: Operand,
     pub dest: Name,
-    // pub nsw: bool,  // getters for these seem to not be exposed in the LLVM C API, only in the C++ one
-    // pub nuw: bool,  // getters for these seem to not be exposed in the LLVM C API, only in the C++ one
-    #[cfg(feature = "llvm-9-or-greater")]
+    pub nsw: bool,
+    pub nuw: bool,
     pub debugloc: Option<DebugLoc>,
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
 
 impl_inst!(Shl, Shl);
-binop_left_type!(Shl, Shl, "shl");
+binop_left_type!(Shl, Shl, "shl", nuw, nsw);
 
 /// Logical shift right.
 /// See [LLVM 14 docs on the 'lshr' instruction](https://releases.llvm.org/14.0.0/docs/LangRef.html#lshr-instruction)
@@ -922,14 +1002,13 @@ pub struct LShr {
     pub operand0: Operand,
     pub operand1: Operand,
     pub dest: Name,
-    // pub exact: bool,  // getters for these seem to not be exposed in the LLVM C API, only in the C++ one
-    #[cfg(feature = "llvm-9-or-greater")]
+    pub exact: bool,
     pub debugloc: Option<DebugLoc>,
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
 
 impl_inst!(LShr, LShr);
-binop_left_type!(LShr, LShr, "lshr");
+binop_left_type!(LShr, LShr, "lshr", exact);
 
 /// Arithmetic shift right.
 /// See [LLVM 14 docs on the 'ashr' instruction](https://releases.llvm.org/14.0.0/docs/LangRef.html#ashr-instruction)
@@ -938,14 +1017,13 @@ pub struct AShr {
     pub operand0: Operand,
     pub operand1: Operand,
     pub dest: Name,
-    // pub exact: bool,  // getters for these seem to not be exposed in the LLVM C API, only in the C++ one
-    #[cfg(feature = "llvm-9-or-greater")]
+    pub exact: bool,
     pub debugloc: Option<DebugLoc>,
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
 
 impl_inst!(AShr, AShr);
-binop_left_type!(AShr, AShr, "ashr");
+binop_left_type!(AShr, AShr, "ashr", exact);
 
 /// Floating-point add.
 /// See [LLVM 14 docs on the 'fadd' instruction](https://releases.llvm.org/14.0.0/docs/LangRef.html#fadd-instruction)
@@ -954,14 +1032,13 @@ pub struct FAdd {
     pub operand0: Operand,
     pub operand1: Operand,
     pub dest: Name,
-    // pub fast_math_flags: FastMathFlags,  // getters for these seem to not be exposed in the LLVM C API, only in the C++ one
-    #[cfg(feature = "llvm-9-or-greater")]
+    pub fast_math_flags: FastMathFlags,
     pub debugloc: Option<DebugLoc>,
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
 
 impl_inst!(FAdd, FAdd);
-binop_same_type!(FAdd, FAdd, "fadd");
+binop_same_type!(FAdd, FAdd, "fadd", fast_math_flags);
 
 /// Floating-point subtract.
 /// See [LLVM 14 docs on the 'fsub' instruction](https://releases.llvm.org/14.0.0/docs/LangRef.html#fsub-instruction)
@@ -970,14 +1047,13 @@ pub struct FSub {
     pub operand0: Operand,
     pub operand1: Operand,
     pub dest: Name,
-    // pub fast_math_flags: FastMathFlags,  // getters for these seem to not be exposed in the LLVM C API, only in the C++ one
-    #[cfg(feature = "llvm-9-or-greater")]
+    pub fast_math_flags: FastMathFlags,
     pub debugloc: Option<DebugLoc>,
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
 
 impl_inst!(FSub, FSub);
-binop_same_type!(FSub, FSub, "fsub");
+binop_same_type!(FSub, FSub, "fsub", fast_math_flags);
 
 /// Floating-point multiply.
 /// See [LLVM 14 docs on the 'fmul' instruction](https://releases.llvm.org/14.0.0/docs/LangRef.html#fmul-instruction)
@@ -986,14 +1062,13 @@ pub struct FMul {
     pub operand0: Operand,
     pub operand1: Operand,
     pub dest: Name,
-    // pub fast_math_flags: FastMathFlags,  // getters for these seem to not be exposed in the LLVM C API, only in the C++ one
-    #[cfg(feature = "llvm-9-or-greater")]
+    pub fast_math_flags: FastMathFlags,
     pub debugloc: Option<DebugLoc>,
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
 
 impl_inst!(FMul, FMul);
-binop_same_type!(FMul, FMul, "fmul");
+binop_same_type!(FMul, FMul, "fmul", fast_math_flags);
 
 /// Floating-point divide.
 /// See [LLVM 14 docs on the 'fdiv' instruction](https://releases.llvm.org/14.0.0/docs/LangRef.html#fdiv-instruction)
@@ -1002,14 +1077,13 @@ pub struct FDiv {
     pub operand0: Operand,
     pub operand1: Operand,
     pub dest: Name,
-    // pub fast_math_flags: FastMathFlags,  // getters for these seem to not be exposed in the LLVM C API, only in the C++ one
-    #[cfg(feature = "llvm-9-or-greater")]
+    pub fast_math_flags: FastMathFlags,
     pub debugloc: Option<DebugLoc>,
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
 
 impl_inst!(FDiv, FDiv);
-binop_same_type!(FDiv, FDiv, "fdiv");
+binop_same_type!(FDiv, FDiv, "fdiv", fast_math_flags);
 
 /// Floating-point remainder.
 /// See [LLVM 14 docs on the 'frem' instruction](https://releases.llvm.org/14.0.0/docs/LangRef.html#frem-instruction)
@@ -1018,14 +1092,13 @@ pub struct FRem {
     pub operand0: Operand,
     pub operand1: Operand,
     pub dest: Name,
-    // pub fast_math_flags: FastMathFlags,  // getters for these seem to not be exposed in the LLVM C API, only in the C++ one
-    #[cfg(feature = "llvm-9-or-greater")]
+    pub fast_math_flags: FastMathFlags,
     pub debugloc: Option<DebugLoc>,
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
 
 impl_inst!(FRem, FRem);
-binop_same_type!(FRem, FRem, "frem");
+binop_same_type!(FRem, FRem, "frem", fast_math_flags);
 
 /// Floating-point unary negation.
 /// See [LLVM 14 docs on the 'fneg' instruction](https://releases.llvm.org/14.0.0/docs/LangRef.html#fneg-instruction)
@@ -1033,14 +1106,13 @@ binop_same_type!(FRem, FRem, "frem");
 pub struct FNeg {
     pub operand: Operand,
     pub dest: Name,
-    // pub fast_math_flags: FastMathFlags,  // getters for these seem to not be exposed in the LLVM C API, only in the C++ one
-    #[cfg(feature = "llvm-9-or-greater")]
+    pub fast_math_flags: FastMathFlags,
     pub debugloc: Option<DebugLoc>,
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
 
 impl_inst!(FNeg, FNeg);
-unop_same_type!(FNeg, "fneg");
+unop_same_type!(FNeg, "fneg", fast_math_flags);
 
 /// Get an element from a vector at a specified index.
 /// See [LLVM 14 docs on the 'extractelement' instruction](https://releases.llvm.org/14.0.0/docs/LangRef.html#extractelement-instruction)
@@ -1049,7 +1121,6 @@ pub struct ExtractElement {
     pub vector: Operand,
     pub index: Operand,
     pub dest: Name,
-    #[cfg(feature = "llvm-9-or-greater")]
     pub debugloc: Option<DebugLoc>,
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
@@ -1076,7 +1147,6 @@ impl Display for ExtractElement {
             "{} = extractelement {}, {}",
             &self.dest, &self.vector, &self.index,
         )?;
-        #[cfg(feature = "llvm-9-or-greater")]
         if self.debugloc.is_some() {
             write!(f, " (with debugloc)")?;
         }
@@ -1092,7 +1162,6 @@ pub struct InsertElement {
     pub element: Operand,
     pub index: Operand,
     pub dest: Name,
-    #[cfg(feature = "llvm-9-or-greater")]
     pub debugloc: Option<DebugLoc>,
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
@@ -1113,7 +1182,6 @@ impl Display for InsertElement {
             "{} = insertelement {}, {}, {}",
             &self.dest, &self.vector, &self.element, &self.index,
         )?;
-        #[cfg(feature = "llvm-9-or-greater")]
         if self.debugloc.is_some() {
             write!(f, " (with debugloc)")?;
         }
@@ -1129,7 +1197,6 @@ pub struct ShuffleVector {
     pub operand1: Operand,
     pub dest: Name,
     pub mask: ConstantRef,
-    #[cfg(feature = "llvm-9-or-greater")]
     pub debugloc: Option<DebugLoc>,
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
@@ -1143,16 +1210,11 @@ impl Typed for ShuffleVector {
         debug_assert_eq!(ty, types.type_of(&self.operand1));
         match ty.as_ref() {
             Type::VectorType { element_type, .. } => match types.type_of(&self.mask).as_ref() {
-                #[cfg(feature = "llvm-11-or-greater")]
                 Type::VectorType {
                     num_elements,
                     scalable,
                     ..
                 } => types.vector_of(element_type.clone(), *num_elements, *scalable),
-                #[cfg(feature = "llvm-10-or-lower")]
-                Type::VectorType { num_elements, .. } => {
-                    types.vector_of(element_type.clone(), *num_elements)
-                },
                 ty => panic!(
                     "Expected a ShuffleVector mask to be VectorType, got {:?}",
                     ty
@@ -1173,7 +1235,6 @@ impl Display for ShuffleVector {
             "{} = shufflevector {}, {}, {}",
             &self.dest, &self.operand0, &self.operand1, &self.mask,
         )?;
-        #[cfg(feature = "llvm-9-or-greater")]
         if self.debugloc.is_some() {
             write!(f, " (with debugloc)")?;
         }
@@ -1188,7 +1249,6 @@ pub struct ExtractValue {
     pub aggregate: Operand,
     pub indices: Vec<u32>,
     pub dest: Name,
-    #[cfg(feature = "llvm-9-or-greater")]
     pub debugloc: Option<DebugLoc>,
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
@@ -1234,7 +1294,6 @@ impl Display for ExtractValue {
         for idx in &self.indices[1 ..] {
             write!(f, ", {idx}")?;
         }
-        #[cfg(feature = "llvm-9-or-greater")]
         if self.debugloc.is_some() {
             write!(f, " (with debugloc)")?;
         }
@@ -1250,7 +1309,6 @@ pub struct InsertValue {
     pub element: Operand,
     pub indices: Vec<u32>,
     pub dest: Name,
-    #[cfg(feature = "llvm-9-or-greater")]
     pub debugloc: Option<DebugLoc>,
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
@@ -1277,7 +1335,6 @@ impl Display for InsertValue {
         for idx in &self.indices[1 ..] {
             write!(f, ", {idx}")?;
         }
-        #[cfg(feature = "llvm-9-or-greater")]
         if self.debugloc.is_some() {
             write!(f, " (with debugloc)")?;
         }
@@ -1293,7 +1350,6 @@ pub struct Alloca {
     pub num_elements: Operand, // llvm-hs-pure has Option<Operand>
     pub dest: Name,
     pub alignment: u32,
-    #[cfg(feature = "llvm-9-or-greater")]
     pub debugloc: Option<DebugLoc>,
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
@@ -1303,7 +1359,7 @@ impl_hasresult!(Alloca);
 
 impl Typed for Alloca {
     fn get_type(&self, types: &Types) -> TypeRef {
-        types.pointer_to(self.allocated_type.clone())
+        types.pointer_to_value_in_addr_space(self.allocated_type.clone(), 0)
     }
 }
 
@@ -1316,7 +1372,6 @@ impl Display for Alloca {
             write!(f, ", {}", &self.num_elements)?;
         }
         write!(f, ", align {}", &self.alignment)?;
-        #[cfg(feature = "llvm-9-or-greater")]
         if self.debugloc.is_some() {
             write!(f, " (with debugloc)")?;
         }
@@ -1330,10 +1385,12 @@ impl Display for Alloca {
 pub struct Load {
     pub address: Operand,
     pub dest: Name,
+    /// Type of the value being loaded. With opaque pointers, this can't be
+    /// recovered from the type of `address`.
+    pub loaded_ty: TypeRef,
     pub volatile: bool,
     pub atomicity: Option<Atomicity>,
     pub alignment: u32,
-    #[cfg(feature = "llvm-9-or-greater")]
     pub debugloc: Option<DebugLoc>,
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
@@ -1342,11 +1399,8 @@ impl_inst!(Load, Load);
 impl_hasresult!(Load);
 
 impl Typed for Load {
-    fn get_type(&self, types: &Types) -> TypeRef {
-        match types.type_of(&self.address).as_ref() {
-            Type::PointerType { pointee_type, .. } => pointee_type.clone(),
-            ty => panic!("Expected a load address to be PointerType, got {:?}", ty),
-        }
+    fn get_type(&self, _types: &Types) -> TypeRef {
+        self.loaded_ty.clone()
     }
 }
 
@@ -1367,7 +1421,6 @@ impl Display for Load {
             write!(f, " {}", a)?;
         }
         write!(f, ", align {}", &self.alignment)?;
-        #[cfg(feature = "llvm-9-or-greater")]
         if self.debugloc.is_some() {
             write!(f, " (with debugloc)")?;
         }
@@ -1384,7 +1437,6 @@ pub struct Store {
     pub volatile: bool,
     pub atomicity: Option<Atomicity>,
     pub alignment: u32,
-    #[cfg(feature = "llvm-9-or-greater")]
     pub debugloc: Option<DebugLoc>,
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
@@ -1406,7 +1458,6 @@ impl Display for Store {
             write!(f, " {}", a)?;
         }
         write!(f, ", align {}", &self.alignment)?;
-        #[cfg(feature = "llvm-9-or-greater")]
         if self.debugloc.is_some() {
             write!(f, " (with debugloc)")?;
         }
@@ -1419,7 +1470,6 @@ impl Display for Store {
 #[derive(PartialEq, Clone, Debug)]
 pub struct Fence {
     pub atomicity: Atomicity,
-    #[cfg(feature = "llvm-9-or-greater")]
     pub debugloc: Option<DebugLoc>,
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
@@ -1430,7 +1480,6 @@ void_typed!(Fence);
 impl Display for Fence {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "fence {}", &self.atomicity)?;
-        #[cfg(feature = "llvm-9-or-greater")]
         if self.debugloc.is_some() {
             write!(f, " (with debugloc)")?;
         }
@@ -1451,9 +1500,7 @@ pub struct CmpXchg {
     pub atomicity: Atomicity,
     /// This is the "failure" `MemoryOrdering`
     pub failure_memory_ordering: MemoryOrdering,
-    #[cfg(feature = "llvm-10-or-greater")]
     pub weak: bool,
-    #[cfg(feature = "llvm-9-or-greater")]
     pub debugloc: Option<DebugLoc>,
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
@@ -1472,7 +1519,6 @@ impl Typed for CmpXchg {
 impl Display for CmpXchg {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "{} = cmpxchg ", &self.dest)?;
-        #[cfg(feature = "llvm-10-or-greater")]
         if self.weak {
             write!(f, "weak ")?;
         }
@@ -1488,7 +1534,6 @@ impl Display for CmpXchg {
             &self.atomicity,
             &self.failure_memory_ordering,
         )?;
-        #[cfg(feature = "llvm-9-or-greater")]
         if self.debugloc.is_some() {
             write!(f, " (with debugloc)")?;
         }
@@ -1500,15 +1545,12 @@ impl Display for CmpXchg {
 /// See [LLVM 14 docs on the 'atomicrmw' instruction](https://releases.llvm.org/14.0.0/docs/LangRef.html#atomicrmw-instruction)
 #[derive(PartialEq, Clone, Debug)]
 pub struct AtomicRMW {
-    // the binop-getter was added to the LLVM C API in LLVM 10
-    #[cfg(feature = "llvm-10-or-greater")]
     pub operation: RMWBinOp,
     pub address: Operand,
     pub value: Operand,
     pub dest: Name,
     pub volatile: bool,
     pub atomicity: Atomicity,
-    #[cfg(feature = "llvm-9-or-greater")]
     pub debugloc: Option<DebugLoc>,
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
@@ -1518,13 +1560,9 @@ impl_hasresult!(AtomicRMW);
 
 impl Typed for AtomicRMW {
     fn get_type(&self, types: &Types) -> TypeRef {
-        match types.type_of(&self.address).as_ref() {
-            Type::PointerType { pointee_type, .. } => pointee_type.clone(),
-            ty => panic!(
-                "Expected an AtomicRMW address to be PointerType, got {:?}",
-                ty
-            ),
-        }
+        // the result is the old value at `address`, which has the same type
+        // as `value`
+        types.type_of(&self.value)
     }
 }
 
@@ -1534,10 +1572,8 @@ impl Display for AtomicRMW {
         if self.volatile {
             write!(f, "volatile ")?;
         }
-        #[cfg(feature = "llvm-10-or-greater")]
         write!(f, "{} ", &self.operation)?;
         write!(f, "{}, {} {}", &self.address, &self.value, &self.atomicity)?;
-        #[cfg(feature = "llvm-9-or-greater")]
         if self.debugloc.is_some() {
             write!(f, " (with debugloc)")?;
         }
@@ -1554,7 +1590,9 @@ pub struct GetElementPtr {
     pub indices: Vec<Operand>,
     pub dest: Name,
     pub in_bounds: bool,
-    #[cfg(feature = "llvm-9-or-greater")]
+    /// The type which the first index steps over. With typed pointers, this
+    /// is also the pointee type of `address`.
+    pub source_element_type: TypeRef,
     pub debugloc: Option<DebugLoc>,
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
@@ -1564,7 +1602,13 @@ impl_hasresult!(GetElementPtr);
 
 impl Typed for GetElementPtr {
     fn get_type(&self, types: &Types) -> TypeRef {
-        gep_type(types.type_of(&self.address), self.indices.iter(), types)
+        let address_type = types.type_of(&self.address);
+        match address_type.as_ref() {
+            Type::OpaquePointerType { addr_space } => {
+                types.opaque_pointer_in_addr_space(*addr_space)
+            },
+            _ => gep_type(address_type, self.indices.iter(), types),
+        }
     }
 }
 
@@ -1621,7 +1665,6 @@ impl Display for GetElementPtr {
         for idx in &self.indices {
             write!(f, ", {}", idx)?;
         }
-        #[cfg(feature = "llvm-9-or-greater")]
         if self.debugloc.is_some() {
             write!(f, " (with debugloc)")?;
         }
@@ -1636,7 +1679,6 @@ pub struct Trunc {
     pub operand: Operand,
     pub to_type: TypeRef,
     pub dest: Name,
-    #[cfg(feature = "llvm-9-or-greater")]
     pub debugloc: Option<DebugLoc>,
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
@@ -1651,7 +1693,6 @@ pub struct ZExt {
     pub operand: Operand,
     pub to_type: TypeRef,
     pub dest: Name,
-    #[cfg(feature = "llvm-9-or-greater")]
     pub debugloc: Option<DebugLoc>,
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
@@ -1666,7 +1707,6 @@ pub struct SExt {
     pub operand: Operand,
     pub to_type: TypeRef,
     pub dest: Name,
-    #[cfg(feature = "llvm-9-or-greater")]
     pub debugloc: Option<DebugLoc>,
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
@@ -1681,7 +1721,6 @@ pub struct FPTrunc {
     pub operand: Operand,
     pub to_type: TypeRef,
     pub dest: Name,
-    #[cfg(feature = "llvm-9-or-greater")]
     pub debugloc: Option<DebugLoc>,
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
@@ -1696,7 +1735,6 @@ pub struct FPExt {
     pub operand: Operand,
     pub to_type: TypeRef,
     pub dest: Name,
-    #[cfg(feature = "llvm-9-or-greater")]
     pub debugloc: Option<DebugLoc>,
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
@@ -1711,7 +1749,6 @@ pub struct FPToUI {
     pub operand: Operand,
     pub to_type: TypeRef,
     pub dest: Name,
-    #[cfg(feature = "llvm-9-or-greater")]
     pub debugloc: Option<DebugLoc>,
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
@@ -1726,7 +1763,6 @@ pub struct FPToSI {
     pub operand: Operand,
     pub to_type: TypeRef,
     pub dest: Name,
-    #[cfg(feature = "llvm-9-or-greater")]
     pub debugloc: Option<DebugLoc>,
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
@@ -1741,7 +1777,6 @@ pub struct UIToFP {
     pub operand: Operand,
     pub to_type: TypeRef,
     pub dest: Name,
-    #[cfg(feature = "llvm-9-or-greater")]
     pub debugloc: Option<DebugLoc>,
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
@@ -1756,7 +1791,6 @@ pub struct SIToFP {
     pub operand: Operand,
     pub to_type: TypeRef,
     pub dest: Name,
-    #[cfg(feature = "llvm-9-or-greater")]
     pub debugloc: Option<DebugLoc>,
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
@@ -1771,7 +1805,6 @@ pub struct PtrToInt {
     pub operand: Operand,
     pub to_type: TypeRef,
     pub dest: Name,
-    #[cfg(feature = "llvm-9-or-greater")]
     pub debugloc: Option<DebugLoc>,
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
@@ -1786,7 +1819,6 @@ pub struct IntToPtr {
     pub operand: Operand,
     pub to_type: TypeRef,
     pub dest: Name,
-    #[cfg(feature = "llvm-9-or-greater")]
     pub debugloc: Option<DebugLoc>,
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
@@ -1801,7 +1833,6 @@ pub struct BitCast {
     pub operand: Operand,
     pub to_type: TypeRef,
     pub dest: Name,
-    #[cfg(feature = "llvm-9-or-greater")]
     pub debugloc: Option<DebugLoc>,
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
@@ -1816,7 +1847,6 @@ pub struct AddrSpaceCast {
     pub operand: Operand,
     pub to_type: TypeRef,
     pub dest: Name,
-    #[cfg(feature = "llvm-9-or-greater")]
     pub debugloc: Option<DebugLoc>,
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
@@ -1832,7 +1862,6 @@ pub struct ICmp {
     pub operand0: Operand,
     pub operand1: Operand,
     pub dest: Name,
-    #[cfg(feature = "llvm-9-or-greater")]
     pub debugloc: Option<DebugLoc>,
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
@@ -1845,14 +1874,11 @@ impl Typed for ICmp {
         let ty = types.type_of(&self.operand0);
         debug_assert_eq!(ty, types.type_of(&self.operand1));
         match ty.as_ref() {
-            #[cfg(feature = "llvm-11-or-greater")]
             Type::VectorType {
                 num_elements,
                 scalable,
                 ..
             } => types.vector_of(types.bool(), *num_elements, *scalable),
-            #[cfg(feature = "llvm-10-or-lower")]
-            Type::VectorType { num_elements, .. } => types.vector_of(types.bool(), *num_elements),
             _ => types.bool(),
         }
     }
@@ -1865,7 +1891,6 @@ impl Display for ICmp {
             "{} = icmp {} {}, {}",
             &self.dest, &self.predicate, &self.operand0, &self.operand1,
         )?;
-        #[cfg(feature = "llvm-9-or-greater")]
         if self.debugloc.is_some() {
             write!(f, " (with debugloc)")?;
         }
@@ -1881,7 +1906,7 @@ pub struct FCmp {
     pub operand0: Operand,
     pub operand1: Operand,
     pub dest: Name,
-    #[cfg(feature = "llvm-9-or-greater")]
+    pub fast_math_flags: FastMathFlags,
     pub debugloc: Option<DebugLoc>,
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
@@ -1894,14 +1919,11 @@ impl Typed for FCmp {
         let ty = types.type_of(&self.operand0);
         debug_assert_eq!(ty, types.type_of(&self.operand1));
         match ty.as_ref() {
-            #[cfg(feature = "llvm-11-or-greater")]
             Type::VectorType {
                 num_elements,
                 scalable,
                 ..
             } => types.vector_of(types.bool(), *num_elements, *scalable),
-            #[cfg(feature = "llvm-10-or-lower")]
-            Type::VectorType { num_elements, .. } => types.vector_of(types.bool(), *num_elements),
             _ => types.bool(),
         }
     }
@@ -1909,12 +1931,13 @@ impl Typed for FCmp {
 
 impl Display for FCmp {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} = fcmp", &self.dest)?;
+        self.fast_math_flags.fmt_flag(f, "fast_math_flags")?;
         write!(
             f,
-            "{} = fcmp {} {}, {}",
-            &self.dest, &self.predicate, &self.operand0, &self.operand1,
+            " {} {}, {}",
+            &self.predicate, &self.operand0, &self.operand1,
         )?;
-        #[cfg(feature = "llvm-9-or-greater")]
         if self.debugloc.is_some() {
             write!(f, " (with debugloc)")?;
         }
@@ -1928,7 +1951,6 @@ pub struct Phi {
     pub incoming_values: Vec<(Operand, Name)>,
     pub dest: Name,
     pub to_type: TypeRef,
-    #[cfg(feature = "llvm-9-or-greater")]
     pub debugloc: Option<DebugLoc>,
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
@@ -1951,7 +1973,6 @@ impl Display for Phi {
         for (val, label) in &self.incoming_values[1 ..] {
             write!(f, ", [ {}, {} ]", val, label)?;
         }
-        #[cfg(feature = "llvm-9-or-greater")]
         if self.debugloc.is_some() {
             write!(f, " (with debugloc)")?;
         }
@@ -1967,7 +1988,6 @@ pub struct Select {
     pub true_value: Operand,
     pub false_value: Operand,
     pub dest: Name,
-    #[cfg(feature = "llvm-9-or-greater")]
     pub debugloc: Option<DebugLoc>,
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
@@ -1990,7 +2010,6 @@ impl Display for Select {
             "{} = select {}, {}, {}",
             &self.dest, &self.condition, &self.true_value, &self.false_value,
         )?;
-        #[cfg(feature = "llvm-9-or-greater")]
         if self.debugloc.is_some() {
             write!(f, " (with debugloc)")?;
         }
@@ -2000,19 +2019,15 @@ impl Display for Select {
 
 /// Stop the propagation of `undef` or `poison` values.
 /// See [LLVM 14 docs on the 'freeze' instruction](https://releases.llvm.org/14.0.0/docs/LangRef.html#freeze-instruction)
-#[cfg(feature = "llvm-10-or-greater")]
 #[derive(PartialEq, Clone, Debug)]
 pub struct Freeze {
     pub operand: Operand,
     pub dest: Name,
-    #[cfg(feature = "llvm-9-or-greater")]
     pub debugloc: Option<DebugLoc>,
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
 
-#[cfg(feature = "llvm-10-or-greater")]
 impl_inst!(Freeze, Freeze);
-#[cfg(feature = "llvm-10-or-greater")]
 unop_same_type!(Freeze, "freeze");
 
 /// Function call.
@@ -2020,13 +2035,18 @@ unop_same_type!(Freeze, "freeze");
 #[derive(PartialEq, Clone, Debug)]
 pub struct Call {
     pub function: Either<InlineAssembly, Operand>,
+    /// Type of the function being called. With opaque pointers, this can't be
+    /// recovered from the type of `function`.
+    pub function_ty: TypeRef,
     pub arguments: Vec<(Operand, Vec<ParameterAttribute>)>,
     pub return_attributes: Vec<ParameterAttribute>,
     pub dest: Option<Name>, // will be None if the `function` returns void
-    pub function_attributes: Vec<FunctionAttribute>, // llvm-hs has the equivalent of Vec<Either<GroupID, FunctionAttribute>>, but I'm not sure how the GroupID option comes up
-    pub is_tail_call: bool, // llvm-hs has the more sophisticated structure Option<TailCallKind>, but the LLVM C API just gives us true/false
+    /// Index into `Module::function_attribute_groups`, or `None` if there are no function attributes on this call
+    pub function_attribute_group: Option<GroupID>,
+    pub tail_call_kind: Option<TailCallKind>,
+    pub fast_math_flags: FastMathFlags,
+    pub operand_bundles: Vec<OperandBundle>,
     pub calling_convention: CallingConvention,
-    #[cfg(feature = "llvm-9-or-greater")]
     pub debugloc: Option<DebugLoc>,
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
@@ -2034,13 +2054,10 @@ pub struct Call {
 impl_inst!(Call, Call);
 
 impl Typed for Call {
-    fn get_type(&self, types: &Types) -> TypeRef {
-        match types.type_of(&self.function).as_ref() {
-            Type::PointerType { pointee_type, .. } => match pointee_type.as_ref() {
-                Type::FuncType { result_type, .. } => result_type.clone(),
-                ty => panic!("Expected Call's function argument to be of type pointer-to-function, got pointer-to-{:?}", ty),
-            },
-            ty => panic!("Expected Call's function argument to be of type pointer-to-function, got {:?}", ty),
+    fn get_type(&self, _types: &Types) -> TypeRef {
+        match self.function_ty.as_ref() {
+            Type::FuncType { result_type, .. } => result_type.clone(),
+            ty => panic!("Expected Call's function_ty to be a FuncType, got {:?}", ty),
         }
     }
 }
@@ -2052,12 +2069,16 @@ impl Display for Call {
         if let Some(dest) = &self.dest {
             write!(f, "{} = ", dest)?;
         }
-        if self.is_tail_call {
-            write!(f, "tail ")?;
+        if let Some(kind) = &self.tail_call_kind {
+            write!(f, "{} ", kind)?;
+        }
+        write!(f, "call ")?;
+        if !self.fast_math_flags.is_empty() {
+            write!(f, "{} ", &self.fast_math_flags)?;
         }
         write!(
             f,
-            "call {}(",
+            "{}(",
             match &self.function {
                 Either::Left(_) => "<inline assembly>".into(),
                 Either::Right(op) => format!("{}", op),
@@ -2071,7 +2092,7 @@ impl Display for Call {
             }
         }
         write!(f, ")")?;
-        #[cfg(feature = "llvm-9-or-greater")]
+        write_operand_bundles(f, &self.operand_bundles)?;
         if self.debugloc.is_some() {
             write!(f, " (with debugloc)")?;
         }
@@ -2079,6 +2100,80 @@ impl Display for Call {
     }
 }
 
+impl Call {
+    /// If this is a call to an LLVM intrinsic (a function whose name starts
+    /// with `llvm.`), get a typed view of it. Returns `None` for calls to
+    /// other functions.
+    pub fn as_intrinsic(&self) -> Option<Intrinsic<'_>> {
+        Intrinsic::from_call(self)
+    }
+
+    /// Get the function attributes which apply to this call: both those on
+    /// the call itself, and, if it's a direct call to a function defined or
+    /// declared in the indexed `Module`, those on the called function
+    fn all_function_attributes<'m>(
+        &'m self,
+        functions: &FunctionIndex<'m>,
+    ) -> impl Iterator<Item = &'m FunctionAttribute> {
+        let callee_group = match &self.function {
+            Either::Right(Operand::ConstantOperand(cref)) => match cref.as_ref() {
+                Constant::GlobalReference { name, .. } => match functions.get_func_by_name(name) {
+                    Some(func) => func.function_attribute_group,
+                    None => functions
+                        .get_func_decl_by_name(name)
+                        .and_then(|decl| decl.function_attribute_group),
+                },
+                _ => None,
+            },
+            _ => None,
+        };
+        let module = functions.module();
+        module
+            .get_function_attributes(self.function_attribute_group)
+            .iter()
+            .chain(module.get_function_attributes(callee_group))
+    }
+
+    /// Whether this call may (read, write) memory; see
+    /// `Instruction::may_read_memory()` and `Instruction::may_write_memory()`
+    fn memory_access(&self, functions: &FunctionIndex) -> (bool, bool) {
+        match self.as_intrinsic() {
+            Some(Intrinsic::Dbg(_)) => return (false, false),
+            Some(Intrinsic::Expect { .. }) => return (false, false),
+            Some(Intrinsic::WithOverflow { .. }) => return (false, false),
+            Some(Intrinsic::MemCpy { .. }) => return (true, true),
+            Some(Intrinsic::MemCpyInline { .. }) => return (true, true),
+            Some(Intrinsic::MemMove { .. }) => return (true, true),
+            Some(Intrinsic::MemSet { .. }) => return (false, true),
+            Some(Intrinsic::MemSetInline { .. }) => return (false, true),
+            _ => {},
+        }
+        let mut reads = true;
+        let mut writes = true;
+        for attr in self.all_function_attributes(functions) {
+            match attr {
+                FunctionAttribute::ReadNone => {
+                    reads = false;
+                    writes = false;
+                },
+                FunctionAttribute::ReadOnly => writes = false,
+                FunctionAttribute::WriteOnly => reads = false,
+                FunctionAttribute::Memory {
+                    argmem,
+                    inaccessiblemem,
+                    other,
+                } => {
+                    let effects = [*argmem, *inaccessiblemem, *other];
+                    reads &= effects.iter().any(|effect| effect.may_read());
+                    writes &= effects.iter().any(|effect| effect.may_write());
+                },
+                _ => {},
+            }
+        }
+        (reads, writes)
+    }
+}
+
 /// Used to access variadic arguments passed to a function.
 /// See [LLVM 14 docs on the 'va_arg' instruction](https://releases.llvm.org/14.0.0/docs/LangRef.html#va-arg-instruction)
 #[derive(PartialEq, Clone, Debug)]
@@ -2086,7 +2181,6 @@ pub struct VAArg {
     pub arg_list: Operand,
     pub cur_type: TypeRef,
     pub dest: Name,
-    #[cfg(feature = "llvm-9-or-greater")]
     pub debugloc: Option<DebugLoc>,
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
@@ -2107,7 +2201,6 @@ impl Display for VAArg {
             "{} = va_arg {}, {}",
             &self.dest, &self.arg_list, &self.cur_type,
         )?;
-        #[cfg(feature = "llvm-9-or-greater")]
         if self.debugloc.is_some() {
             write!(f, " (with debugloc)")?;
         }
@@ -2123,7 +2216,6 @@ pub struct LandingPad {
     pub clauses: Vec<LandingPadClause>,
     pub dest: Name,
     pub cleanup: bool,
-    #[cfg(feature = "llvm-9-or-greater")]
     pub debugloc: Option<DebugLoc>,
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
@@ -2143,7 +2235,6 @@ impl Display for LandingPad {
         if self.cleanup {
             write!(f, " cleanup")?;
         }
-        #[cfg(feature = "llvm-9-or-greater")]
         if self.debugloc.is_some() {
             write!(f, " (with debugloc)")?;
         }
@@ -2158,7 +2249,6 @@ pub struct CatchPad {
     pub catch_switch: Operand,
     pub args: Vec<Operand>,
     pub dest: Name,
-    #[cfg(feature = "llvm-9-or-greater")]
     pub debugloc: Option<DebugLoc>,
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
@@ -2187,7 +2277,6 @@ impl Display for CatchPad {
             }
         }
         write!(f, "]")?;
-        #[cfg(feature = "llvm-9-or-greater")]
         if self.debugloc.is_some() {
             write!(f, " (with debugloc)")?;
         }
@@ -2202,7 +2291,6 @@ pub struct CleanupPad {
     pub parent_pad: Operand,
     pub args: Vec<Operand>,
     pub dest: Name,
-    #[cfg(feature = "llvm-9-or-greater")]
     pub debugloc: Option<DebugLoc>,
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
@@ -2231,7 +2319,6 @@ impl Display for CleanupPad {
             }
         }
         write!(f, "]")?;
-        #[cfg(feature = "llvm-9-or-greater")]
         if self.debugloc.is_some() {
             write!(f, " (with debugloc)")?;
         }
@@ -2239,17 +2326,26 @@ impl Display for CleanupPad {
     }
 }
 
-/*
+/// See [LLVM 14 docs on the 'call' instruction](https://releases.llvm.org/14.0.0/docs/LangRef.html#call-instruction)
 #[derive(PartialEq, Eq, Clone, Copy, Debug)]
 pub enum TailCallKind {
     Tail,
     MustTail,
     NoTail,
 }
-*/
+
+impl Display for TailCallKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TailCallKind::Tail => write!(f, "tail"),
+            TailCallKind::MustTail => write!(f, "musttail"),
+            TailCallKind::NoTail => write!(f, "notail"),
+        }
+    }
+}
 
 /// See [LLVM 14 docs on Fast-Math Flags](https://releases.llvm.org/14.0.0/docs/LangRef.html#fastmath)
-#[derive(PartialEq, Eq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default)]
 #[allow(non_snake_case)]
 pub struct FastMathFlags {
     pub allow_reassoc: bool,
@@ -2261,6 +2357,128 @@ pub struct FastMathFlags {
     pub approx_func: bool,
 }
 
+impl FastMathFlags {
+    /// Are all of the flags set? (LLVM prints this as `fast`.)
+    pub fn is_fast(&self) -> bool {
+        self.allow_reassoc
+            && self.no_NaNs
+            && self.no_Infs
+            && self.no_signed_zeros
+            && self.allow_reciprocal
+            && self.allow_contract
+            && self.approx_func
+    }
+
+    /// Are none of the flags set?
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+impl Display for FastMathFlags {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_fast() {
+            return write!(f, "fast");
+        }
+        let flags = [
+            (self.allow_reassoc, "reassoc"),
+            (self.no_NaNs, "nnan"),
+            (self.no_Infs, "ninf"),
+            (self.no_signed_zeros, "nsz"),
+            (self.allow_reciprocal, "arcp"),
+            (self.allow_contract, "contract"),
+            (self.approx_func, "afn"),
+        ];
+        let set: Vec<&str> = flags
+            .iter()
+            .filter(|(set, _)| *set)
+            .map(|(_, name)| *name)
+            .collect();
+        write!(f, "{}", set.join(" "))
+    }
+}
+
+// Shared logic for displaying (and parsing) instruction flags like `nsw`,
+// `exact`, or fast-math flags. Not public, just an implementation convenience.
+trait Flag: Sized {
+    fn fmt_flag(&self, f: &mut fmt::Formatter, name: &str) -> fmt::Result;
+    #[cfg(feature = "llvm-sys")]
+    fn from_keywords(keywords: &[String], name: &str) -> Self;
+}
+
+impl Flag for bool {
+    fn fmt_flag(&self, f: &mut fmt::Formatter, name: &str) -> fmt::Result {
+        if *self {
+            write!(f, " {}", name)?;
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "llvm-sys")]
+    fn from_keywords(keywords: &[String], name: &str) -> Self {
+        keywords.iter().any(|keyword| keyword == name)
+    }
+}
+
+impl Flag for FastMathFlags {
+    fn fmt_flag(&self, f: &mut fmt::Formatter, _name: &str) -> fmt::Result {
+        if !self.is_empty() {
+            write!(f, " {}", self)?;
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "llvm-sys")]
+    fn from_keywords(keywords: &[String], _name: &str) -> Self {
+        FastMathFlags::from_keywords(keywords)
+    }
+}
+
+/// See [LLVM 14 docs on Operand Bundles](https://releases.llvm.org/14.0.0/docs/LangRef.html#operand-bundles)
+///
+/// Commonly seen tags include `"deopt"`, `"funclet"`, `"gc-live"`,
+/// `"gc-transition"`, `"cfguardtarget"`, `"preallocated"`, and
+/// `"clang.arc.attachedcall"`.
+#[derive(PartialEq, Clone, Debug)]
+pub struct OperandBundle {
+    pub tag: String,
+    pub inputs: Vec<Operand>,
+}
+
+impl Display for OperandBundle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "\"{}\"(", &self.tag)?;
+        for (i, input) in self.inputs.iter().enumerate() {
+            if i == self.inputs.len() - 1 {
+                write!(f, "{}", input)?;
+            } else {
+                write!(f, "{}, ", input)?;
+            }
+        }
+        write!(f, ")")
+    }
+}
+
+/// Write the operand bundles (if any) in the format LLVM uses, e.g.
+/// ` [ "deopt"(i32 1), "funclet"(token %0) ]`
+pub(crate) fn write_operand_bundles(
+    f: &mut fmt::Formatter,
+    bundles: &[OperandBundle],
+) -> fmt::Result {
+    if !bundles.is_empty() {
+        write!(f, " [ ")?;
+        for (i, bundle) in bundles.iter().enumerate() {
+            if i == bundles.len() - 1 {
+                write!(f, "{}", bundle)?;
+            } else {
+                write!(f, "{}, ", bundle)?;
+            }
+        }
+        write!(f, " ]")?;
+    }
+    Ok(())
+}
+
 /// See [LLVM 14 docs on Atomic Memory Ordering Constraints](https://releases.llvm.org/14.0.0/docs/LangRef.html#ordering)
 #[derive(PartialEq, Eq, Clone, Debug)]
 pub struct Atomicity {
@@ -2361,10 +2579,12 @@ pub enum RMWBinOp {
     Min,
     UMax,
     UMin,
-    #[cfg(feature = "llvm-10-or-greater")]
     FAdd,
-    #[cfg(feature = "llvm-10-or-greater")]
     FSub,
+    FMax,
+    FMin,
+    UIncWrap,
+    UDecWrap,
 }
 
 impl Display for RMWBinOp {
@@ -2381,10 +2601,12 @@ impl Display for RMWBinOp {
             Self::Min => write!(f, "min"),
             Self::UMax => write!(f, "umax"),
             Self::UMin => write!(f, "umin"),
-            #[cfg(feature = "llvm-10-or-greater")]
             Self::FAdd => write!(f, "fadd"),
-            #[cfg(feature = "llvm-10-or-greater")]
             Self::FSub => write!(f, "fsub"),
+            Self::FMax => write!(f, "fmax"),
+            Self::FMin => write!(f, "fmin"),
+            Self::UIncWrap => write!(f, "uinc_wrap"),
+            Self::UDecWrap => write!(f, "udec_wrap"),
         }
     }
 }
@@ -2406,24 +2628,65 @@ pub enum LandingPadClause {
 #[derive(PartialEq, Eq, Clone, Copy, Debug)]
 pub struct LandingPadClause {}
 
+impl FastMathFlags {
+    /// Get the fast-math flags present in the given instruction keywords (see
+    /// `leading_keywords()`)
+    #[cfg(any(feature = "llvm-sys", feature = "ll-parser"))]
+    pub(crate) fn from_keywords(keywords: &[String]) -> Self {
+        let mut fmf = Self::default();
+        for keyword in keywords {
+            match keyword.as_str() {
+                "fast" => {
+                    fmf = Self {
+                        allow_reassoc: true,
+                        no_NaNs: true,
+                        no_Infs: true,
+                        no_signed_zeros: true,
+                        allow_reciprocal: true,
+                        allow_contract: true,
+                        approx_func: true,
+                    }
+                },
+                "reassoc" => fmf.allow_reassoc = true,
+                "nnan" => fmf.no_NaNs = true,
+                "ninf" => fmf.no_Infs = true,
+                "nsz" => fmf.no_signed_zeros = true,
+                "arcp" => fmf.allow_reciprocal = true,
+                "contract" => fmf.allow_contract = true,
+                "afn" => fmf.approx_func = true,
+                _ => {},
+            }
+        }
+        fmf
+    }
+}
+
 // ********* //
 // from_llvm //
 // ********* //
 
-use crate::constant::Constant;
+#[cfg(feature = "llvm-sys")]
 use crate::from_llvm::*;
+#[cfg(feature = "llvm-sys")]
 use crate::function::FunctionContext;
+#[cfg(feature = "llvm-sys")]
 use crate::llvm_sys::*;
+#[cfg(feature = "llvm-sys")]
 use crate::module::ModuleContext;
+#[cfg(feature = "llvm-sys")]
 use crate::types::TypesBuilder;
+#[cfg(feature = "llvm-sys")]
 use llvm_sys::LLVMAtomicOrdering;
-#[cfg(feature = "llvm-10-or-greater")]
+#[cfg(all(feature = "llvm-sys", feature = "llvm-10-or-greater"))]
 use llvm_sys::LLVMAtomicRMWBinOp;
+#[cfg(feature = "llvm-sys")]
 use llvm_sys::LLVMOpcode;
+#[cfg(feature = "llvm-sys")]
 use llvm_sys::LLVMTypeKind::LLVMVoidTypeKind;
-#[cfg(feature = "llvm-11-or-greater")]
+#[cfg(all(feature = "llvm-sys", feature = "llvm-11-or-greater"))]
 use std::convert::TryInto;
 
+#[cfg(feature = "llvm-sys")]
 impl Instruction {
     pub(crate) fn from_llvm_ref(
         inst: LLVMValueRef,
@@ -2473,7 +2736,7 @@ impl Instruction {
             },
             LLVMOpcode::LLVMLoad => Instruction::Load(Load::from_llvm_ref(inst, ctx, func_ctx)),
             LLVMOpcode::LLVMStore => Instruction::Store(Store::from_llvm_ref(inst, ctx, func_ctx)),
-            LLVMOpcode::LLVMFence => Instruction::Fence(Fence::from_llvm_ref(inst)),
+            LLVMOpcode::LLVMFence => Instruction::Fence(Fence::from_llvm_ref(inst, ctx, func_ctx)),
             LLVMOpcode::LLVMAtomicCmpXchg => {
                 Instruction::CmpXchg(CmpXchg::from_llvm_ref(inst, ctx, func_ctx))
             },
@@ -2543,8 +2806,46 @@ impl Instruction {
     }
 }
 
+/// Flags like `nsw`, `exact`, or fast-math flags aren't exposed in the LLVM C
+/// API (only the C++ one) before LLVM 17, so we get them from the
+/// instruction's textual form (see notes on `leading_keywords()`). As of LLVM
+/// 17, there are getters for `nsw`, `nuw`, and `exact`, but still not for
+/// fast-math flags. If the instruction can't have any `flags`, we skip this.
+#[cfg(feature = "llvm-sys")]
+fn flag_keywords(
+    inst: LLVMValueRef,
+    flags: &[&str],
+    ctx: &ModuleContext,
+    func_ctx: &mut FunctionContext,
+) -> Vec<String> {
+    #[cfg(feature = "llvm-17-or-greater")]
+    {
+        let has_getter = |flag: &&str| matches!(*flag, "nsw" | "nuw" | "exact");
+        if flags.iter().all(has_getter) {
+            return flags
+                .iter()
+                .filter(|&&flag| {
+                    let set = match flag {
+                        "nsw" => unsafe { LLVMGetNSW(inst) },
+                        "nuw" => unsafe { LLVMGetNUW(inst) },
+                        _ => unsafe { LLVMGetExact(inst) },
+                    };
+                    set != 0
+                })
+                .map(|&flag| flag.to_owned())
+                .collect();
+        }
+    }
+    if flags.is_empty() {
+        vec![]
+    } else {
+        leading_keywords(&func_ctx.instruction_text(inst, ctx))
+    }
+}
+
 macro_rules! unop_from_llvm {
-    ($inst:ident) => {
+    ($inst:ident $(, $flag:ident)*) => {
+        #[cfg(feature = "llvm-sys")]
         impl $inst {
             pub(crate) fn from_llvm_ref(
                 inst: LLVMValueRef,
@@ -2552,15 +2853,17 @@ macro_rules! unop_from_llvm {
                 func_ctx: &mut FunctionContext,
             ) -> Self {
                 assert_eq!(unsafe { LLVMGetNumOperands(inst) }, 1);
+                #[allow(unused_variables)]
+                let keywords = flag_keywords(inst, &[$(stringify!($flag)),*], ctx, func_ctx);
                 Self {
                     operand: Operand::from_llvm_ref(
                         unsafe { LLVMGetOperand(inst, 0) },
                         ctx,
                         func_ctx,
                     ),
-                    dest: Name::name_or_num(unsafe { get_value_name(inst) }, &mut func_ctx.ctr),
-                    #[cfg(feature = "llvm-9-or-greater")]
-                    debugloc: DebugLoc::from_llvm_with_col(inst),
+                    dest: func_ctx.local_name(unsafe { get_value_name(inst) }),
+                    $($flag: Flag::from_keywords(&keywords, stringify!($flag)),)*
+                    debugloc: func_ctx.debugloc(inst),
                     // metadata: InstructionMetadata::from_llvm_inst(inst),
                 }
             }
@@ -2569,7 +2872,8 @@ macro_rules! unop_from_llvm {
 }
 
 macro_rules! binop_from_llvm {
-    ($inst:ident) => {
+    ($inst:ident $(, $flag:ident)*) => {
+        #[cfg(feature = "llvm-sys")]
         impl $inst {
             pub(crate) fn from_llvm_ref(
                 inst: LLVMValueRef,
@@ -2577,6 +2881,8 @@ macro_rules! binop_from_llvm {
                 func_ctx: &mut FunctionContext,
             ) -> Self {
                 assert_eq!(unsafe { LLVMGetNumOperands(inst) }, 2);
+                #[allow(unused_variables)]
+                let keywords = flag_keywords(inst, &[$(stringify!($flag)),*], ctx, func_ctx);
                 Self {
                     operand0: Operand::from_llvm_ref(
                         unsafe { LLVMGetOperand(inst, 0) },
@@ -2588,9 +2894,9 @@ macro_rules! binop_from_llvm {
                         ctx,
                         func_ctx,
                     ),
-                    dest: Name::name_or_num(unsafe { get_value_name(inst) }, &mut func_ctx.ctr),
-                    #[cfg(feature = "llvm-9-or-greater")]
-                    debugloc: DebugLoc::from_llvm_with_col(inst),
+                    dest: func_ctx.local_name(unsafe { get_value_name(inst) }),
+                    $($flag: Flag::from_keywords(&keywords, stringify!($flag)),)*
+                    debugloc: func_ctx.debugloc(inst),
                     // metadata: InstructionMetadata::from_llvm_inst(inst),
                 }
             }
@@ -2598,28 +2904,30 @@ macro_rules! binop_from_llvm {
     };
 }
 
-binop_from_llvm!(Add);
-binop_from_llvm!(Sub);
-binop_from_llvm!(Mul);
-binop_from_llvm!(UDiv);
-binop_from_llvm!(SDiv);
+binop_from_llvm!(Add, nsw, nuw);
+binop_from_llvm!(Sub, nsw, nuw);
+binop_from_llvm!(Mul, nsw, nuw);
+binop_from_llvm!(UDiv, exact);
+binop_from_llvm!(SDiv, exact);
 binop_from_llvm!(URem);
 binop_from_llvm!(SRem);
 binop_from_llvm!(And);
 binop_from_llvm!(Or);
 binop_from_llvm!(Xor);
-binop_from_llvm!(Shl);
-binop_from_llvm!(LShr);
-binop_from_llvm!(AShr);
-binop_from_llvm!(FAdd);
-binop_from_llvm!(FSub);
-binop_from_llvm!(FMul);
-binop_from_llvm!(FDiv);
-binop_from_llvm!(FRem);
-unop_from_llvm!(FNeg);
+binop_from_llvm!(Shl, nsw, nuw);
+binop_from_llvm!(LShr, exact);
+binop_from_llvm!(AShr, exact);
+binop_from_llvm!(FAdd, fast_math_flags);
+binop_from_llvm!(FSub, fast_math_flags);
+binop_from_llvm!(FMul, fast_math_flags);
+binop_from_llvm!(FDiv, fast_math_flags);
+binop_from_llvm!(FRem, fast_math_flags);
+unop_from_llvm!(FNeg, fast_math_flags);
+#[cfg(feature = "llvm-sys")]
 #[cfg(feature = "llvm-10-or-greater")]
 unop_from_llvm!(Freeze);
 
+#[cfg(feature = "llvm-sys")]
 impl ExtractElement {
     pub(crate) fn from_llvm_ref(
         inst: LLVMValueRef,
@@ -2630,14 +2938,14 @@ impl ExtractElement {
         Self {
             vector: Operand::from_llvm_ref(unsafe { LLVMGetOperand(inst, 0) }, ctx, func_ctx),
             index: Operand::from_llvm_ref(unsafe { LLVMGetOperand(inst, 1) }, ctx, func_ctx),
-            dest: Name::name_or_num(unsafe { get_value_name(inst) }, &mut func_ctx.ctr),
-            #[cfg(feature = "llvm-9-or-greater")]
-            debugloc: DebugLoc::from_llvm_with_col(inst),
+            dest: func_ctx.local_name(unsafe { get_value_name(inst) }),
+            debugloc: func_ctx.debugloc(inst),
             // metadata: InstructionMetadata::from_llvm_inst(inst),
         }
     }
 }
 
+#[cfg(feature = "llvm-sys")]
 impl InsertElement {
     pub(crate) fn from_llvm_ref(
         inst: LLVMValueRef,
@@ -2649,14 +2957,14 @@ impl InsertElement {
             vector: Operand::from_llvm_ref(unsafe { LLVMGetOperand(inst, 0) }, ctx, func_ctx),
             element: Operand::from_llvm_ref(unsafe { LLVMGetOperand(inst, 1) }, ctx, func_ctx),
             index: Operand::from_llvm_ref(unsafe { LLVMGetOperand(inst, 2) }, ctx, func_ctx),
-            dest: Name::name_or_num(unsafe { get_value_name(inst) }, &mut func_ctx.ctr),
-            #[cfg(feature = "llvm-9-or-greater")]
-            debugloc: DebugLoc::from_llvm_with_col(inst),
+            dest: func_ctx.local_name(unsafe { get_value_name(inst) }),
+            debugloc: func_ctx.debugloc(inst),
             // metadata: InstructionMetadata::from_llvm_inst(inst),
         }
     }
 }
 
+#[cfg(feature = "llvm-sys")]
 impl ShuffleVector {
     pub(crate) fn from_llvm_ref(
         inst: LLVMValueRef,
@@ -2679,35 +2987,37 @@ impl ShuffleVector {
                     Type::VectorType { num_elements, scalable, .. } => {
                         assert_eq!(*num_elements as u32, unsafe { LLVMGetNumMaskElements(inst) });
                         let undef_elem = unsafe { LLVMGetUndefMaskElem() };
-                        ConstantRef::new(Constant::Vector(
-                            (0 .. *num_elements)
-                                .map(|i| unsafe { LLVMGetMaskValue(inst, i.try_into().unwrap()) })
-                                .map(|val| if val == undef_elem {
-                                    Constant::Undef(ctx.types.i32())
+                        let elements: Vec<Constant> = (0 .. *num_elements)
+                            .map(|i| unsafe { LLVMGetMaskValue(inst, i.try_into().unwrap()) })
+                            .map(|val| if val == undef_elem {
+                                Constant::Undef(ctx.types.i32())
+                            } else {
+                                if *scalable {
+                                    assert!(val == 0, "LLVM 11+ only allows zero or undef for mask elements in a ShuffleVector on scalable vectors");
                                 } else {
-                                    if *scalable {
-                                        assert!(val == 0, "LLVM 11+ only allows zero or undef for mask elements in a ShuffleVector on scalable vectors");
-                                    } else {
-                                        assert!(val >= 0);
-                                    }
-                                    let val: u32 = val.try_into().unwrap();
-                                    Constant::Int { value: val.into(), bits: 32 }
-                                })
-                                .map(ConstantRef::new)
-                                .collect()
-                        ))
+                                    assert!(val >= 0);
+                                }
+                                let val: u32 = val.try_into().unwrap();
+                                Constant::Int { value: val.into(), bits: 32 }
+                            })
+                            .collect();
+                        let elements = elements
+                            .into_iter()
+                            .map(|element| ctx.constants.intern(element))
+                            .collect();
+                        ctx.constants.intern(Constant::Vector(elements))
                     },
                     ty => panic!("ShuffleVector: expected instruction result type to be a vector type; got {:?}", ty),
                 }
             },
-            dest: Name::name_or_num(unsafe { get_value_name(inst) }, &mut func_ctx.ctr),
-            #[cfg(feature = "llvm-9-or-greater")]
-            debugloc: DebugLoc::from_llvm_with_col(inst),
+            dest: func_ctx.local_name(unsafe { get_value_name(inst) }),
+            debugloc: func_ctx.debugloc(inst),
             // metadata: InstructionMetadata::from_llvm_inst(inst),
         }
     }
 }
 
+#[cfg(feature = "llvm-sys")]
 impl ExtractValue {
     pub(crate) fn from_llvm_ref(
         inst: LLVMValueRef,
@@ -2722,14 +3032,14 @@ impl ExtractValue {
                 let ptr = LLVMGetIndices(inst);
                 std::slice::from_raw_parts(ptr, num_indices as usize).to_vec()
             },
-            dest: Name::name_or_num(unsafe { get_value_name(inst) }, &mut func_ctx.ctr),
-            #[cfg(feature = "llvm-9-or-greater")]
-            debugloc: DebugLoc::from_llvm_with_col(inst),
+            dest: func_ctx.local_name(unsafe { get_value_name(inst) }),
+            debugloc: func_ctx.debugloc(inst),
             // metadata: InstructionMetadata::from_llvm_inst(inst),
         }
     }
 }
 
+#[cfg(feature = "llvm-sys")]
 impl InsertValue {
     pub(crate) fn from_llvm_ref(
         inst: LLVMValueRef,
@@ -2745,14 +3055,14 @@ impl InsertValue {
                 let ptr = LLVMGetIndices(inst);
                 std::slice::from_raw_parts(ptr, num_indices as usize).to_vec()
             },
-            dest: Name::name_or_num(unsafe { get_value_name(inst) }, &mut func_ctx.ctr),
-            #[cfg(feature = "llvm-9-or-greater")]
-            debugloc: DebugLoc::from_llvm_with_col(inst),
+            dest: func_ctx.local_name(unsafe { get_value_name(inst) }),
+            debugloc: func_ctx.debugloc(inst),
             // metadata: InstructionMetadata::from_llvm_inst(inst),
         }
     }
 }
 
+#[cfg(feature = "llvm-sys")]
 impl Alloca {
     pub(crate) fn from_llvm_ref(
         inst: LLVMValueRef,
@@ -2769,15 +3079,15 @@ impl Alloca {
                 ctx,
                 func_ctx,
             ),
-            dest: Name::name_or_num(unsafe { get_value_name(inst) }, &mut func_ctx.ctr),
+            dest: func_ctx.local_name(unsafe { get_value_name(inst) }),
             alignment: unsafe { LLVMGetAlignment(inst) },
-            #[cfg(feature = "llvm-9-or-greater")]
-            debugloc: DebugLoc::from_llvm_with_col(inst),
+            debugloc: func_ctx.debugloc(inst),
             // metadata: InstructionMetadata::from_llvm_inst(inst),
         }
     }
 }
 
+#[cfg(feature = "llvm-sys")]
 impl Load {
     pub(crate) fn from_llvm_ref(
         inst: LLVMValueRef,
@@ -2787,7 +3097,8 @@ impl Load {
         assert_eq!(unsafe { LLVMGetNumOperands(inst) }, 1);
         Self {
             address: Operand::from_llvm_ref(unsafe { LLVMGetOperand(inst, 0) }, ctx, func_ctx),
-            dest: Name::name_or_num(unsafe { get_value_name(inst) }, &mut func_ctx.ctr),
+            dest: func_ctx.local_name(unsafe { get_value_name(inst) }),
+            loaded_ty: ctx.types.type_from_llvm_ref(unsafe { LLVMTypeOf(inst) }),
             volatile: unsafe { LLVMGetVolatile(inst) } != 0,
             atomicity: {
                 let ordering = unsafe { LLVMGetOrdering(inst) };
@@ -2801,13 +3112,13 @@ impl Load {
                 }
             },
             alignment: unsafe { LLVMGetAlignment(inst) },
-            #[cfg(feature = "llvm-9-or-greater")]
-            debugloc: DebugLoc::from_llvm_with_col(inst),
+            debugloc: func_ctx.debugloc(inst),
             // metadata: InstructionMetadata::from_llvm_inst(inst),
         }
     }
 }
 
+#[cfg(feature = "llvm-sys")]
 impl Store {
     pub(crate) fn from_llvm_ref(
         inst: LLVMValueRef,
@@ -2831,28 +3142,29 @@ impl Store {
                 }
             },
             alignment: unsafe { LLVMGetAlignment(inst) },
-            #[cfg(feature = "llvm-9-or-greater")]
-            debugloc: DebugLoc::from_llvm_with_col(inst),
+            debugloc: func_ctx.debugloc(inst),
             // metadata: InstructionMetadata::from_llvm_inst(inst),
         }
     }
 }
 
+#[cfg(feature = "llvm-sys")]
 impl Fence {
-    pub(crate) fn from_llvm_ref(inst: LLVMValueRef) -> Self {
+    pub(crate) fn from_llvm_ref(
+        inst: LLVMValueRef,
+        ctx: &ModuleContext,
+        func_ctx: &mut FunctionContext,
+    ) -> Self {
         assert_eq!(unsafe { LLVMGetNumOperands(inst) }, 0);
         Self {
-            atomicity: Atomicity {
-                synch_scope: SynchronizationScope::from_llvm_ref(inst),
-                mem_ordering: MemoryOrdering::from_llvm(unsafe { LLVMGetOrdering(inst) }),
-            },
-            #[cfg(feature = "llvm-9-or-greater")]
-            debugloc: DebugLoc::from_llvm_with_col(inst),
+            atomicity: Atomicity::from_llvm_fence(&func_ctx.instruction_text(inst, ctx)),
+            debugloc: func_ctx.debugloc(inst),
             // metadata: InstructionMetadata::from_llvm_inst(inst),
         }
     }
 }
 
+#[cfg(feature = "llvm-sys")]
 impl CmpXchg {
     pub(crate) fn from_llvm_ref(
         inst: LLVMValueRef,
@@ -2864,7 +3176,7 @@ impl CmpXchg {
             address: Operand::from_llvm_ref(unsafe { LLVMGetOperand(inst, 0) }, ctx, func_ctx),
             expected: Operand::from_llvm_ref(unsafe { LLVMGetOperand(inst, 1) }, ctx, func_ctx),
             replacement: Operand::from_llvm_ref(unsafe { LLVMGetOperand(inst, 2) }, ctx, func_ctx),
-            dest: Name::name_or_num(unsafe { get_value_name(inst) }, &mut func_ctx.ctr),
+            dest: func_ctx.local_name(unsafe { get_value_name(inst) }),
             volatile: unsafe { LLVMGetVolatile(inst) } != 0,
             atomicity: Atomicity {
                 synch_scope: SynchronizationScope::from_llvm_ref(inst),
@@ -2877,13 +3189,19 @@ impl CmpXchg {
             }),
             #[cfg(feature = "llvm-10-or-greater")]
             weak: unsafe { LLVMGetWeak(inst) } != 0,
-            #[cfg(feature = "llvm-9-or-greater")]
-            debugloc: DebugLoc::from_llvm_with_col(inst),
+            // the C API has no getter for this before LLVM 10, so we fall
+            // back on the textual representation
+            #[cfg(feature = "llvm-9-or-lower")]
+            weak: leading_keywords(&func_ctx.instruction_text(inst, ctx))
+                .iter()
+                .any(|kw| kw == "weak"),
+            debugloc: func_ctx.debugloc(inst),
             // metadata: InstructionMetadata::from_llvm_inst(inst),
         }
     }
 }
 
+#[cfg(feature = "llvm-sys")]
 impl AtomicRMW {
     pub(crate) fn from_llvm_ref(
         inst: LLVMValueRef,
@@ -2892,24 +3210,22 @@ impl AtomicRMW {
     ) -> Self {
         assert_eq!(unsafe { LLVMGetNumOperands(inst) }, 2);
         Self {
-            // the binop-getter was added to the LLVM C API in LLVM 10
-            #[cfg(feature = "llvm-10-or-greater")]
-            operation: RMWBinOp::from_llvm(unsafe { LLVMGetAtomicRMWBinOp(inst) }),
+            operation: RMWBinOp::from_llvm_ref(inst, ctx, func_ctx),
             address: Operand::from_llvm_ref(unsafe { LLVMGetOperand(inst, 0) }, ctx, func_ctx),
             value: Operand::from_llvm_ref(unsafe { LLVMGetOperand(inst, 1) }, ctx, func_ctx),
-            dest: Name::name_or_num(unsafe { get_value_name(inst) }, &mut func_ctx.ctr),
+            dest: func_ctx.local_name(unsafe { get_value_name(inst) }),
             volatile: unsafe { LLVMGetVolatile(inst) } != 0,
             atomicity: Atomicity {
                 synch_scope: SynchronizationScope::from_llvm_ref(inst),
                 mem_ordering: MemoryOrdering::from_llvm(unsafe { LLVMGetOrdering(inst) }),
             },
-            #[cfg(feature = "llvm-9-or-greater")]
-            debugloc: DebugLoc::from_llvm_with_col(inst),
+            debugloc: func_ctx.debugloc(inst),
             // metadata: InstructionMetadata::from_llvm_inst(inst),
         }
     }
 }
 
+#[cfg(feature = "llvm-sys")]
 impl GetElementPtr {
     pub(crate) fn from_llvm_ref(
         inst: LLVMValueRef,
@@ -2926,10 +3242,12 @@ impl GetElementPtr {
                     })
                     .collect()
             },
-            dest: Name::name_or_num(unsafe { get_value_name(inst) }, &mut func_ctx.ctr),
+            dest: func_ctx.local_name(unsafe { get_value_name(inst) }),
             in_bounds: unsafe { LLVMIsInBounds(inst) } != 0,
-            #[cfg(feature = "llvm-9-or-greater")]
-            debugloc: DebugLoc::from_llvm_with_col(inst),
+            source_element_type: ctx
+                .types
+                .type_from_llvm_ref(unsafe { get_gep_source_element_type(inst) }),
+            debugloc: func_ctx.debugloc(inst),
             // metadata: InstructionMetadata::from_llvm_inst(inst),
         }
     }
@@ -2939,6 +3257,7 @@ impl GetElementPtr {
 //   knowing only their operands.
 macro_rules! typed_unop_from_llvm {
     ($inst:ident) => {
+        #[cfg(feature = "llvm-sys")]
         impl $inst {
             pub(crate) fn from_llvm_ref(
                 inst: LLVMValueRef,
@@ -2953,9 +3272,8 @@ macro_rules! typed_unop_from_llvm {
                         func_ctx,
                     ),
                     to_type: ctx.types.type_from_llvm_ref(unsafe { LLVMTypeOf(inst) }),
-                    dest: Name::name_or_num(unsafe { get_value_name(inst) }, &mut func_ctx.ctr),
-                    #[cfg(feature = "llvm-9-or-greater")]
-                    debugloc: DebugLoc::from_llvm_with_col(inst),
+                    dest: func_ctx.local_name(unsafe { get_value_name(inst) }),
+                    debugloc: func_ctx.debugloc(inst),
                     // metadata: InstructionMetadata::from_llvm_inst(inst),
                 }
             }
@@ -2977,6 +3295,7 @@ typed_unop_from_llvm!(IntToPtr);
 typed_unop_from_llvm!(BitCast);
 typed_unop_from_llvm!(AddrSpaceCast);
 
+#[cfg(feature = "llvm-sys")]
 impl ICmp {
     pub(crate) fn from_llvm_ref(
         inst: LLVMValueRef,
@@ -2988,14 +3307,14 @@ impl ICmp {
             predicate: IntPredicate::from_llvm(unsafe { LLVMGetICmpPredicate(inst) }),
             operand0: Operand::from_llvm_ref(unsafe { LLVMGetOperand(inst, 0) }, ctx, func_ctx),
             operand1: Operand::from_llvm_ref(unsafe { LLVMGetOperand(inst, 1) }, ctx, func_ctx),
-            dest: Name::name_or_num(unsafe { get_value_name(inst) }, &mut func_ctx.ctr),
-            #[cfg(feature = "llvm-9-or-greater")]
-            debugloc: DebugLoc::from_llvm_with_col(inst),
+            dest: func_ctx.local_name(unsafe { get_value_name(inst) }),
+            debugloc: func_ctx.debugloc(inst),
             // metadata: InstructionMetadata::from_llvm_inst(inst),
         }
     }
 }
 
+#[cfg(feature = "llvm-sys")]
 impl FCmp {
     pub(crate) fn from_llvm_ref(
         inst: LLVMValueRef,
@@ -3007,14 +3326,17 @@ impl FCmp {
             predicate: FPPredicate::from_llvm(unsafe { LLVMGetFCmpPredicate(inst) }),
             operand0: Operand::from_llvm_ref(unsafe { LLVMGetOperand(inst, 0) }, ctx, func_ctx),
             operand1: Operand::from_llvm_ref(unsafe { LLVMGetOperand(inst, 1) }, ctx, func_ctx),
-            dest: Name::name_or_num(unsafe { get_value_name(inst) }, &mut func_ctx.ctr),
-            #[cfg(feature = "llvm-9-or-greater")]
-            debugloc: DebugLoc::from_llvm_with_col(inst),
+            dest: func_ctx.local_name(unsafe { get_value_name(inst) }),
+            fast_math_flags: FastMathFlags::from_keywords(&leading_keywords(
+                &func_ctx.instruction_text(inst, ctx),
+            )),
+            debugloc: func_ctx.debugloc(inst),
             // metadata: InstructionMetadata::from_llvm_inst(inst),
         }
     }
 }
 
+#[cfg(feature = "llvm-sys")]
 impl Phi {
     pub(crate) fn from_llvm_ref(
         inst: LLVMValueRef,
@@ -3040,15 +3362,15 @@ impl Phi {
                     })
                     .collect()
             },
-            dest: Name::name_or_num(unsafe { get_value_name(inst) }, &mut func_ctx.ctr),
+            dest: func_ctx.local_name(unsafe { get_value_name(inst) }),
             to_type: ctx.types.type_from_llvm_ref(unsafe { LLVMTypeOf(inst) }),
-            #[cfg(feature = "llvm-9-or-greater")]
-            debugloc: DebugLoc::from_llvm_with_col(inst),
+            debugloc: func_ctx.debugloc(inst),
             // metadata: InstructionMetadata::from_llvm_inst(inst),
         }
     }
 }
 
+#[cfg(feature = "llvm-sys")]
 impl Select {
     pub(crate) fn from_llvm_ref(
         inst: LLVMValueRef,
@@ -3060,23 +3382,29 @@ impl Select {
             condition: Operand::from_llvm_ref(unsafe { LLVMGetOperand(inst, 0) }, ctx, func_ctx),
             true_value: Operand::from_llvm_ref(unsafe { LLVMGetOperand(inst, 1) }, ctx, func_ctx),
             false_value: Operand::from_llvm_ref(unsafe { LLVMGetOperand(inst, 2) }, ctx, func_ctx),
-            dest: Name::name_or_num(unsafe { get_value_name(inst) }, &mut func_ctx.ctr),
-            #[cfg(feature = "llvm-9-or-greater")]
-            debugloc: DebugLoc::from_llvm_with_col(inst),
+            dest: func_ctx.local_name(unsafe { get_value_name(inst) }),
+            debugloc: func_ctx.debugloc(inst),
             // metadata: InstructionMetadata::from_llvm_inst(inst),
         }
     }
 }
 
 // just the logic shared by Call and Invoke. Not a public struct, just an implementation convenience.
+#[cfg(feature = "llvm-sys")]
 pub(crate) struct CallInfo {
     pub function: Either<InlineAssembly, Operand>,
+    pub function_ty: TypeRef,
     pub arguments: Vec<(Operand, Vec<ParameterAttribute>)>,
     pub return_attributes: Vec<ParameterAttribute>,
-    pub function_attributes: Vec<FunctionAttribute>,
+    pub function_attribute_group: Option<GroupID>,
     pub calling_convention: CallingConvention,
+    pub operand_bundles: Vec<OperandBundle>,
+    /// Keywords at the start of the instruction's textual form, e.g.
+    /// `["tail", "call", "fast"]`. See `leading_keywords()`
+    pub keywords: Vec<String>,
 }
 
+#[cfg(feature = "llvm-sys")]
 impl CallInfo {
     // Call this function only an a Call instruction or Invoke terminator
     pub(crate) fn from_llvm_ref(
@@ -3085,6 +3413,13 @@ impl CallInfo {
         func_ctx: &mut FunctionContext,
     ) -> Self {
         use llvm_sys::{LLVMAttributeFunctionIndex, LLVMAttributeReturnIndex};
+        // The LLVM C API has no getters for operand bundles (or for the
+        // tail-call kind and fast-math flags), so we get the tags and shapes
+        // from the textual form of the instruction, and then the bundle
+        // inputs themselves from the operand list, where they immediately
+        // follow the arguments
+        let text = func_ctx.instruction_text(inst, ctx);
+        let num_args: u32 = unsafe { LLVMGetNumArgOperands(inst) } as u32;
         Self {
             function: {
                 let called_val = unsafe { LLVMGetCalledValue(inst) };
@@ -3095,8 +3430,10 @@ impl CallInfo {
                     Either::Right(Operand::from_llvm_ref(called_val, ctx, func_ctx))
                 }
             },
+            function_ty: ctx
+                .types
+                .type_from_llvm_ref(unsafe { LLVMGetCalledFunctionType(inst) }),
             arguments: {
-                let num_args: u32 = unsafe { LLVMGetNumArgOperands(inst) } as u32;
                 (0 .. num_args) // arguments are (0 .. num_args); other operands (such as the called function) are after that
                     .map(|i| {
                         let operand = Operand::from_llvm_ref(
@@ -3104,7 +3441,9 @@ impl CallInfo {
                             ctx,
                             func_ctx,
                         );
-                        let attrs = {
+                        let attrs = if ctx.options.skip_attributes {
+                            vec![]
+                        } else {
                             let num_attrs =
                                 unsafe { LLVMGetCallSiteAttributeCount(inst, (i + 1) as u32) }; // see LLVM C API (Core.h) comments on `LLVMAttributeReturnIndex` and `LLVMAttributeFunctionIndex`
                             let mut attrs: Vec<LLVMAttributeRef> =
@@ -3129,7 +3468,9 @@ impl CallInfo {
                     })
                     .collect()
             },
-            return_attributes: {
+            return_attributes: if ctx.options.skip_attributes {
+                vec![]
+            } else {
                 let num_attrs =
                     unsafe { LLVMGetCallSiteAttributeCount(inst, LLVMAttributeReturnIndex) };
                 let mut attrs: Vec<LLVMAttributeRef> = Vec::with_capacity(num_attrs as usize);
@@ -3149,7 +3490,9 @@ impl CallInfo {
                     })
                     .collect()
             },
-            function_attributes: {
+            function_attribute_group: if ctx.options.skip_attributes {
+                None
+            } else {
                 let num_attrs =
                     unsafe { LLVMGetCallSiteAttributeCount(inst, LLVMAttributeFunctionIndex) };
                 let mut attrs: Vec<LLVMAttributeRef> = Vec::with_capacity(num_attrs as usize);
@@ -3157,18 +3500,122 @@ impl CallInfo {
                     LLVMGetCallSiteAttributes(inst, LLVMAttributeFunctionIndex, attrs.as_mut_ptr());
                     attrs.set_len(num_attrs as usize);
                 };
-                attrs
+                let attrs = attrs
                     .into_iter()
                     .map(|attr| FunctionAttribute::from_llvm_ref(attr, &ctx.attrsdata))
-                    .collect()
+                    .collect();
+                ctx.intern_function_attributes(attrs)
             },
             calling_convention: CallingConvention::from_u32(unsafe {
                 LLVMGetInstructionCallConv(inst)
             }),
+            operand_bundles: {
+                // the bundle inputs come before the called function (and any
+                // destination blocks), so if the text claims more inputs than
+                // that, it wasn't parsed right
+                let max_inputs =
+                    unsafe { LLVMGetNumOperands(inst) } as usize - num_args as usize - 1;
+                let shapes = operand_bundle_shapes(&text)
+                    .filter(|shapes| shapes.iter().map(|(_, n)| n).sum::<usize>() <= max_inputs)
+                    .unwrap_or_default();
+                let mut next_operand = num_args;
+                shapes
+                    .into_iter()
+                    .map(|(tag, num_inputs)| {
+                        let inputs = (next_operand .. next_operand + num_inputs as u32)
+                            .map(|i| {
+                                Operand::from_llvm_ref(
+                                    unsafe { LLVMGetOperand(inst, i) },
+                                    ctx,
+                                    func_ctx,
+                                )
+                            })
+                            .collect();
+                        next_operand += num_inputs as u32;
+                        OperandBundle { tag, inputs }
+                    })
+                    .collect()
+            },
+            keywords: leading_keywords(&text),
         }
     }
 }
 
+/// Get the tag and number of inputs of each operand bundle in the textual
+/// form of a call-like instruction, where they look like
+/// `[ "deopt"(i32 1, i64 2), "funclet"(token %0) ]`. The LLVM C API only has
+/// getters for operand bundles as of LLVM 18.
+///
+/// Returns `None` if the bundle list doesn't parse as expected.
+#[cfg(feature = "llvm-sys")]
+fn operand_bundle_shapes(text: &str) -> Option<Vec<(String, usize)>> {
+    // Find the opening `[ "` of the bundle list. It's the only `[` at the top
+    // level which is followed by a space: array types look like `[2 x i32]`,
+    // and everything else (arguments, etc) is nested in some other brackets.
+    let mut depth = 0usize;
+    let mut in_quotes = false;
+    let mut start = None;
+    for (i, c) in text.char_indices() {
+        if in_quotes {
+            in_quotes = c != '"';
+            continue;
+        }
+        match c {
+            '"' => in_quotes = true,
+            '[' if depth == 0 && text[i ..].starts_with("[ \"") => {
+                start = Some(i + 2);
+                break;
+            },
+            '(' | '[' | '{' | '<' => depth += 1,
+            ')' | ']' | '}' | '>' => depth = depth.saturating_sub(1),
+            _ => {},
+        }
+    }
+    let mut rest = match start {
+        Some(start) => &text[start ..],
+        None => return Some(vec![]),
+    };
+
+    let mut shapes = vec![];
+    while let Some(after_quote) = rest.strip_prefix('"') {
+        let tag_end = after_quote.find('"')?;
+        let tag = unescape(&after_quote[.. tag_end]);
+        let inputs = after_quote[tag_end + 1 ..].strip_prefix('(')?;
+        // count the top-level commas up to the closing paren
+        let mut depth = 0usize;
+        let mut in_quotes = false;
+        let mut num_commas = 0;
+        let mut end = None;
+        for (i, c) in inputs.char_indices() {
+            if in_quotes {
+                in_quotes = c != '"';
+                continue;
+            }
+            match c {
+                '"' => in_quotes = true,
+                ')' if depth == 0 => {
+                    end = Some(i);
+                    break;
+                },
+                ',' if depth == 0 => num_commas += 1,
+                '(' | '[' | '{' | '<' => depth += 1,
+                ')' | ']' | '}' | '>' => depth = depth.checked_sub(1)?,
+                _ => {},
+            }
+        }
+        let end = end?;
+        let num_inputs = if inputs[.. end].trim().is_empty() {
+            0
+        } else {
+            num_commas + 1
+        };
+        shapes.push((tag, num_inputs));
+        rest = inputs[end + 1 ..].trim_start_matches(", ");
+    }
+    Some(shapes)
+}
+
+#[cfg(feature = "llvm-sys")]
 impl Call {
     pub(crate) fn from_llvm_ref(
         inst: LLVMValueRef,
@@ -3178,6 +3625,7 @@ impl Call {
         let callinfo = CallInfo::from_llvm_ref(inst, ctx, func_ctx);
         Self {
             function: callinfo.function,
+            function_ty: callinfo.function_ty,
             arguments: callinfo.arguments,
             return_attributes: callinfo.return_attributes,
             dest: if unsafe {
@@ -3186,21 +3634,25 @@ impl Call {
             } {
                 None
             } else {
-                Some(Name::name_or_num(
-                    unsafe { get_value_name(inst) },
-                    &mut func_ctx.ctr,
-                ))
+                Some(func_ctx.local_name(unsafe { get_value_name(inst) }))
             },
-            function_attributes: callinfo.function_attributes,
-            is_tail_call: unsafe { LLVMIsTailCall(inst) } != 0,
+            function_attribute_group: callinfo.function_attribute_group,
+            tail_call_kind: match callinfo.keywords.first().map(String::as_str) {
+                Some("tail") => Some(TailCallKind::Tail),
+                Some("musttail") => Some(TailCallKind::MustTail),
+                Some("notail") => Some(TailCallKind::NoTail),
+                _ => None,
+            },
+            fast_math_flags: FastMathFlags::from_keywords(&callinfo.keywords),
+            operand_bundles: callinfo.operand_bundles,
             calling_convention: callinfo.calling_convention,
-            #[cfg(feature = "llvm-9-or-greater")]
-            debugloc: DebugLoc::from_llvm_with_col(inst),
+            debugloc: func_ctx.debugloc(inst),
             // metadata: InstructionMetadata::from_llvm_inst(inst),
         }
     }
 }
 
+#[cfg(feature = "llvm-sys")]
 impl VAArg {
     pub(crate) fn from_llvm_ref(
         inst: LLVMValueRef,
@@ -3211,14 +3663,14 @@ impl VAArg {
         Self {
             arg_list: Operand::from_llvm_ref(unsafe { LLVMGetOperand(inst, 0) }, ctx, func_ctx),
             cur_type: ctx.types.type_from_llvm_ref(unsafe { LLVMTypeOf(inst) }),
-            dest: Name::name_or_num(unsafe { get_value_name(inst) }, &mut func_ctx.ctr),
-            #[cfg(feature = "llvm-9-or-greater")]
-            debugloc: DebugLoc::from_llvm_with_col(inst),
+            dest: func_ctx.local_name(unsafe { get_value_name(inst) }),
+            debugloc: func_ctx.debugloc(inst),
             // metadata: InstructionMetadata::from_llvm_inst(inst),
         }
     }
 }
 
+#[cfg(feature = "llvm-sys")]
 impl LandingPad {
     pub(crate) fn from_llvm_ref(
         inst: LLVMValueRef,
@@ -3233,15 +3685,15 @@ impl LandingPad {
                     .map(|i| LandingPadClause::from_llvm_ref(unsafe { LLVMGetClause(inst, i) }))
                     .collect()
             },
-            dest: Name::name_or_num(unsafe { get_value_name(inst) }, &mut func_ctx.ctr),
+            dest: func_ctx.local_name(unsafe { get_value_name(inst) }),
             cleanup: unsafe { LLVMIsCleanup(inst) } != 0,
-            #[cfg(feature = "llvm-9-or-greater")]
-            debugloc: DebugLoc::from_llvm_with_col(inst),
+            debugloc: func_ctx.debugloc(inst),
             // metadata: InstructionMetadata::from_llvm_inst(inst),
         }
     }
 }
 
+#[cfg(feature = "llvm-sys")]
 impl CatchPad {
     pub(crate) fn from_llvm_ref(
         inst: LLVMValueRef,
@@ -3262,14 +3714,14 @@ impl CatchPad {
                     })
                     .collect()
             },
-            dest: Name::name_or_num(unsafe { get_value_name(inst) }, &mut func_ctx.ctr),
-            #[cfg(feature = "llvm-9-or-greater")]
-            debugloc: DebugLoc::from_llvm_with_col(inst),
+            dest: func_ctx.local_name(unsafe { get_value_name(inst) }),
+            debugloc: func_ctx.debugloc(inst),
             // metadata: InstructionMetadata::from_llvm_inst(inst),
         }
     }
 }
 
+#[cfg(feature = "llvm-sys")]
 impl CleanupPad {
     pub(crate) fn from_llvm_ref(
         inst: LLVMValueRef,
@@ -3286,14 +3738,14 @@ impl CleanupPad {
                     })
                     .collect()
             },
-            dest: Name::name_or_num(unsafe { get_value_name(inst) }, &mut func_ctx.ctr),
-            #[cfg(feature = "llvm-9-or-greater")]
-            debugloc: DebugLoc::from_llvm_with_col(inst),
+            dest: func_ctx.local_name(unsafe { get_value_name(inst) }),
+            debugloc: func_ctx.debugloc(inst),
             // metadata: InstructionMetadata::from_llvm_inst(inst),
         }
     }
 }
 
+#[cfg(feature = "llvm-sys")]
 impl SynchronizationScope {
     pub(crate) fn from_llvm_ref(inst: LLVMValueRef) -> Self {
         if unsafe { LLVMIsAtomicSingleThread(inst) } != 0 {
@@ -3304,6 +3756,37 @@ impl SynchronizationScope {
     }
 }
 
+#[cfg(feature = "llvm-sys")]
+impl Atomicity {
+    /// `LLVMIsAtomicSingleThread()` and `LLVMGetOrdering()` don't actually
+    /// support `fence` instructions: they treat them as `atomicrmw`s, which
+    /// gives garbage (or traps, in LLVM 15+). So for fences we look at the
+    /// textual representation instead, e.g. `fence syncscope("singlethread") seq_cst`
+    pub(crate) fn from_llvm_fence(text: &str) -> Self {
+        // skip any trailing metadata, e.g. `, !dbg !12`
+        let text = text.split(',').next().unwrap_or_default().trim();
+        let synch_scope = if text.contains("syncscope(\"singlethread\")") {
+            SynchronizationScope::SingleThread
+        } else {
+            SynchronizationScope::System
+        };
+        let mem_ordering = match text.rsplit(' ').next() {
+            Some("unordered") => MemoryOrdering::Unordered,
+            Some("monotonic") => MemoryOrdering::Monotonic,
+            Some("acquire") => MemoryOrdering::Acquire,
+            Some("release") => MemoryOrdering::Release,
+            Some("acq_rel") => MemoryOrdering::AcquireRelease,
+            Some("seq_cst") => MemoryOrdering::SequentiallyConsistent,
+            _ => panic!("Failed to find the ordering of fence {:?}", text),
+        };
+        Self {
+            synch_scope,
+            mem_ordering,
+        }
+    }
+}
+
+#[cfg(feature = "llvm-sys")]
 impl MemoryOrdering {
     #[rustfmt::skip] // each one on one line, even if lines get a little long
     pub(crate) fn from_llvm(ao: LLVMAtomicOrdering) -> Self {
@@ -3319,8 +3802,9 @@ impl MemoryOrdering {
     }
 }
 
-#[cfg(feature = "llvm-10-or-greater")]
+#[cfg(feature = "llvm-sys")]
 impl RMWBinOp {
+    #[cfg(feature = "llvm-10-or-greater")]
     pub(crate) fn from_llvm(rmwbo: LLVMAtomicRMWBinOp) -> Self {
         match rmwbo {
             LLVMAtomicRMWBinOp::LLVMAtomicRMWBinOpXchg => Self::Xchg,
@@ -3338,10 +3822,67 @@ impl RMWBinOp {
             LLVMAtomicRMWBinOp::LLVMAtomicRMWBinOpFAdd => Self::FAdd,
             #[cfg(feature = "llvm-10-or-greater")]
             LLVMAtomicRMWBinOp::LLVMAtomicRMWBinOpFSub => Self::FSub,
+            #[cfg(feature = "llvm-15-or-greater")]
+            LLVMAtomicRMWBinOp::LLVMAtomicRMWBinOpFMax => Self::FMax,
+            #[cfg(feature = "llvm-15-or-greater")]
+            LLVMAtomicRMWBinOp::LLVMAtomicRMWBinOpFMin => Self::FMin,
         }
     }
-}
 
+    #[cfg(feature = "llvm-10-or-greater")]
+    #[allow(unused_variables)]
+    pub(crate) fn from_llvm_ref(
+        inst: LLVMValueRef,
+        ctx: &ModuleContext,
+        func_ctx: &mut FunctionContext,
+    ) -> Self {
+        // `uinc_wrap` and `udec_wrap` are missing from the C API's
+        // `LLVMAtomicRMWBinOp` enum (and LLVM asserts if asked for them), so
+        // we recognize them from the textual representation instead
+        #[cfg(feature = "llvm-16-or-greater")]
+        {
+            let keywords = leading_keywords(&func_ctx.instruction_text(inst, ctx));
+            if keywords.iter().any(|kw| kw == "uinc_wrap") {
+                return Self::UIncWrap;
+            }
+            if keywords.iter().any(|kw| kw == "udec_wrap") {
+                return Self::UDecWrap;
+            }
+        }
+        Self::from_llvm(unsafe { LLVMGetAtomicRMWBinOp(inst) })
+    }
+
+    #[cfg(feature = "llvm-9-or-lower")]
+    pub(crate) fn from_llvm_ref(
+        inst: LLVMValueRef,
+        ctx: &ModuleContext,
+        func_ctx: &mut FunctionContext,
+    ) -> Self {
+        // the binop-getter was added to the LLVM C API in LLVM 10, so before
+        // that we recognize the operation from the textual representation
+        let keywords = leading_keywords(&func_ctx.instruction_text(inst, ctx));
+        match keywords
+            .iter()
+            .map(String::as_str)
+            .find(|kw| !matches!(*kw, "atomicrmw" | "volatile"))
+        {
+            Some("xchg") => Self::Xchg,
+            Some("add") => Self::Add,
+            Some("sub") => Self::Sub,
+            Some("and") => Self::And,
+            Some("nand") => Self::Nand,
+            Some("or") => Self::Or,
+            Some("xor") => Self::Xor,
+            Some("max") => Self::Max,
+            Some("min") => Self::Min,
+            Some("umax") => Self::UMax,
+            Some("umin") => Self::UMin,
+            kw => panic!("Unexpected atomicrmw operation {:?}", kw),
+        }
+    }
+}
+
+#[cfg(feature = "llvm-sys")]
 impl InlineAssembly {
     pub(crate) fn from_llvm_ref(asm: LLVMValueRef, types: &mut TypesBuilder) -> Self {
         // The LLVM C API appears to have no way to get any information about an
@@ -3354,6 +3895,7 @@ impl InlineAssembly {
     }
 }
 
+#[cfg(feature = "llvm-sys")]
 impl LandingPadClause {
     pub(crate) fn from_llvm_ref(_lpc: LLVMValueRef) -> Self {
         // The LLVM C API has an enum `LLVMLandingPadClauseTy`, but appears not
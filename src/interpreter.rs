@@ -0,0 +1,2101 @@
+//! An interpreter which executes `llvm-ir` `Function`s directly, in pure Rust.
+//!
+//! [`Interpreter`](struct.Interpreter.html) runs over a byte-addressed
+//! [`Memory`](struct.Memory.html), in which values are laid out according to
+//! the `Module`'s [`DataLayout`](../module/struct.DataLayout.html). Calls to
+//! functions which are only declared (not defined) in the `Module` go to Rust
+//! closures registered with
+//! [`Interpreter::register_external()`](struct.Interpreter.html#method.register_external).
+//!
+//! Undefined behavior which the interpreter detects, such as division by zero
+//! or an out-of-bounds memory access, is reported as an
+//! [`InterpreterError`](enum.InterpreterError.html).
+//! The interpreter doesn't track `undef` or `poison`: `undef` and `poison`
+//! constants are treated as zero, and operations whose overflow would produce
+//! poison (e.g., an `add nsw`) simply wrap. Shifts by at least the bit width
+//! and out-of-range vector indices are reported as errors.
+//!
+//! Resources are bounded: at most 10,000 nested calls (reported as
+//! `InterpreterError::StackOverflow`), and at most 1 GiB of live allocations
+//! (reported as `InterpreterError::OutOfMemory`).
+//!
+//! Only `float` and `double` floating-point values, and integers of up to 128
+//! bits, are supported. Exception handling, variadic arguments, and inline
+//! assembly aren't supported either; executing those reports
+//! `InterpreterError::Unsupported`.
+
+use crate::basicblock::BasicBlock;
+use crate::constant::{Constant, ConstantRef, Float};
+use crate::function::Function;
+use crate::instruction::{Call, Instruction};
+use crate::intrinsic::{Intrinsic, OverflowOp};
+use crate::module::{DataLayout, Endianness, FunctionIndex, Module};
+use crate::name::Name;
+use crate::operand::Operand;
+use crate::predicates::{FPPredicate, IntPredicate};
+use crate::terminator::Terminator;
+use crate::types::{FPType, NamedStructDef, Type, TypeRef, Types};
+use either::Either;
+use std::collections::{BTreeMap, HashMap};
+use std::convert::TryFrom;
+use std::fmt::{self, Display};
+
+/// Maximum depth of nested calls before reporting
+/// `InterpreterError::StackOverflow`. The interpreter keeps its call stack on
+/// the heap rather than recursing, so this only bounds its memory use.
+const MAX_CALL_DEPTH: usize = 10_000;
+
+/// Maximum number of bytes of live allocations in a `Memory`
+const MAX_MEMORY_SIZE: u64 = 1 << 30;
+
+/// Maximum size, in bytes, of a single value which is loaded, stored, or
+/// otherwise held by the interpreter. `Value`s are much larger than the bytes
+/// they represent, so this is much smaller than `MAX_MEMORY_SIZE`.
+const MAX_VALUE_SIZE: u64 = 1 << 20;
+
+/// A runtime value in the interpreter.
+#[derive(PartialEq, Clone, Debug)]
+pub enum Value {
+    /// An integer with the given number of bits (at most 128). `value` is
+    /// zero-extended; i.e., bits above `bits` are always zero.
+    Int { bits: u32, value: u128 },
+    /// A `float`
+    Single(f32),
+    /// A `double`
+    Double(f64),
+    /// A pointer, as an address in the interpreter's `Memory`
+    Pointer(u64),
+    /// A vector value
+    Vector(Vec<Value>),
+    /// A struct or array value
+    Aggregate(Vec<Value>),
+}
+
+impl Value {
+    /// An integer with the given number of bits; `value` is truncated to fit
+    pub fn int(bits: u32, value: u128) -> Self {
+        Value::Int {
+            bits,
+            value: value & mask(bits),
+        }
+    }
+
+    /// An `i1`
+    pub fn bool(b: bool) -> Self {
+        Value::int(1, b as u128)
+    }
+
+    /// The value of an `Int`, interpreted as unsigned
+    pub fn as_u128(&self) -> Option<u128> {
+        match self {
+            Value::Int { value, .. } => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// The value of an `Int`, interpreted as signed
+    pub fn as_i128(&self) -> Option<i128> {
+        match self {
+            Value::Int { bits, value } => Some(sext(*bits, *value)),
+            _ => None,
+        }
+    }
+
+    /// The address held by a `Pointer`
+    pub fn as_pointer(&self) -> Option<u64> {
+        match self {
+            Value::Pointer(address) => Some(*address),
+            _ => None,
+        }
+    }
+}
+
+impl Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Int { bits, value } => write!(f, "i{} {}", bits, value),
+            Value::Single(s) => write!(f, "float {}", s),
+            Value::Double(d) => write!(f, "double {}", d),
+            Value::Pointer(address) => write!(f, "ptr {:#x}", address),
+            Value::Vector(elements) => {
+                write!(f, "<")?;
+                for (i, element) in elements.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", element)?;
+                }
+                write!(f, ">")
+            },
+            Value::Aggregate(elements) => {
+                write!(f, "{{ ")?;
+                for (i, element) in elements.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", element)?;
+                }
+                write!(f, " }}")
+            },
+        }
+    }
+}
+
+/// An error (usually, undefined behavior) encountered while interpreting.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum InterpreterError {
+    /// `udiv`, `sdiv`, `urem`, or `srem` by zero
+    DivisionByZero,
+    /// `sdiv` or `srem` of the minimum signed value by -1
+    DivisionOverflow,
+    /// A shift by an amount greater than or equal to the bit width
+    OversizedShift { amount: u128, bits: u32 },
+    /// A vector element index which is out of range
+    ElementIndexOutOfRange { index: u128, num_elements: usize },
+    /// A memory access through a null pointer
+    NullDereference,
+    /// A memory access which isn't entirely within a single allocation
+    OutOfBounds { address: u64, size: u64 },
+    /// A memory access to an allocation which has been freed, or to an
+    /// `alloca` whose function has returned
+    UseAfterFree { address: u64 },
+    /// `Memory::free()` of an address which isn't the start of a live
+    /// allocation
+    InvalidFree { address: u64 },
+    /// A call through a pointer which doesn't point to a function
+    NotAFunction { address: u64 },
+    /// Executed an `unreachable` terminator
+    ReachedUnreachable,
+    /// The condition of an `llvm.assume` was false
+    AssumptionViolated,
+    /// Executed `llvm.trap`
+    Trap,
+    /// Exceeded the maximum depth of nested calls
+    StackOverflow,
+    /// An allocation which would exceed the interpreter's memory, or a value
+    /// too large for the interpreter to hold
+    OutOfMemory,
+    /// A call with the wrong number of arguments
+    WrongNumberOfArguments {
+        function: String,
+        expected: usize,
+        got: usize,
+    },
+    /// Call to a function which is neither defined in the `Module` nor
+    /// registered with `Interpreter::register_external()`
+    UnknownFunction(String),
+    /// The interpreter doesn't support the given feature
+    Unsupported(String),
+    /// An error reported by an external function
+    External(String),
+    /// The IR is malformed in a way which prevents executing it, e.g., a
+    /// `Phi` with no incoming value for the block executed before it
+    Malformed(String),
+}
+
+impl Display for InterpreterError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            InterpreterError::DivisionByZero => write!(f, "division by zero"),
+            InterpreterError::DivisionOverflow => write!(f, "signed division overflow"),
+            InterpreterError::OversizedShift { amount, bits } => {
+                write!(f, "shift of an i{} by {}", bits, amount)
+            },
+            InterpreterError::ElementIndexOutOfRange {
+                index,
+                num_elements,
+            } => write!(
+                f,
+                "element index {} out of range for a vector of {} elements",
+                index, num_elements
+            ),
+            InterpreterError::NullDereference => write!(f, "null pointer dereference"),
+            InterpreterError::OutOfBounds { address, size } => write!(
+                f,
+                "out-of-bounds access of {} bytes at {:#x}",
+                size, address
+            ),
+            InterpreterError::UseAfterFree { address } => {
+                write!(f, "use after free at {:#x}", address)
+            },
+            InterpreterError::InvalidFree { address } => {
+                write!(f, "invalid free of {:#x}", address)
+            },
+            InterpreterError::NotAFunction { address } => {
+                write!(f, "call through non-function pointer {:#x}", address)
+            },
+            InterpreterError::ReachedUnreachable => write!(f, "reached unreachable"),
+            InterpreterError::AssumptionViolated => write!(f, "llvm.assume of false"),
+            InterpreterError::Trap => write!(f, "trap"),
+            InterpreterError::StackOverflow => write!(f, "stack overflow"),
+            InterpreterError::OutOfMemory => write!(f, "out of memory"),
+            InterpreterError::WrongNumberOfArguments {
+                function,
+                expected,
+                got,
+            } => write!(
+                f,
+                "call to {} with {} arguments, expected {}",
+                function, got, expected
+            ),
+            InterpreterError::UnknownFunction(name) => write!(f, "unknown function {}", name),
+            InterpreterError::Unsupported(what) => write!(f, "unsupported: {}", what),
+            InterpreterError::External(msg) => write!(f, "{}", msg),
+            InterpreterError::Malformed(what) => write!(f, "malformed IR: {}", what),
+        }
+    }
+}
+
+impl std::error::Error for InterpreterError {}
+
+pub type Result<T> = std::result::Result<T, InterpreterError>;
+
+/// A Rust implementation of a function which is declared in the `Module`.
+/// It receives the interpreter's `Memory` and the call's arguments, and
+/// returns the call's result (`None` for a `void` function).
+pub type ExternalFunction<'m> = Box<dyn FnMut(&mut Memory, &[Value]) -> Result<Option<Value>> + 'm>;
+
+/// The interpreter's byte-addressed memory.
+///
+/// Memory consists of disjoint allocations (for global variables, `alloca`s,
+/// and anything external functions allocate). Every access must lie entirely
+/// within one live allocation. Address 0 is never allocated, so it can serve
+/// as the null pointer. Live allocations may total at most 1 GiB.
+#[derive(Clone, Debug)]
+pub struct Memory {
+    /// Allocations, by base address
+    allocations: BTreeMap<u64, Allocation>,
+    /// Address at which to begin searching for space for the next allocation
+    next_address: u64,
+    /// Total size of the live allocations
+    live_size: u64,
+}
+
+#[derive(Clone, Debug)]
+struct Allocation {
+    size: u64,
+    /// The contents of the allocation; emptied when it's freed
+    bytes: Vec<u8>,
+    live: bool,
+    /// Freeing or accessing a function's allocation is an error
+    is_function: bool,
+}
+
+impl Memory {
+    fn new() -> Self {
+        Self {
+            allocations: BTreeMap::new(),
+            next_address: 0x1000,
+            live_size: 0,
+        }
+    }
+
+    /// Allocate `size` zero-initialized bytes, aligned to `align` bytes.
+    /// Returns the address of the new allocation, or
+    /// `InterpreterError::OutOfMemory` if there's no room for it.
+    pub fn allocate(&mut self, size: u64, align: u64) -> Result<u64> {
+        self.allocate_internal(size, align, false)
+    }
+
+    fn allocate_internal(&mut self, size: u64, align: u64, is_function: bool) -> Result<u64> {
+        let live_size = self
+            .live_size
+            .checked_add(size)
+            .filter(|&live_size| live_size <= MAX_MEMORY_SIZE)
+            .ok_or(InterpreterError::OutOfMemory)?;
+        let align = align.max(1);
+        let address = round_up(self.next_address, align)?;
+        // leave a gap after each allocation, so that running off the end of
+        // one allocation doesn't land in the next
+        self.next_address = address
+            .checked_add(size)
+            .and_then(|end| end.checked_add(16))
+            .ok_or(InterpreterError::OutOfMemory)?;
+        self.live_size = live_size;
+        self.allocations.insert(
+            address,
+            Allocation {
+                size,
+                bytes: vec![0; size as usize],
+                live: true,
+                is_function,
+            },
+        );
+        Ok(address)
+    }
+
+    /// Free the allocation beginning at `address`
+    pub fn free(&mut self, address: u64) -> Result<()> {
+        match self.allocations.get_mut(&address) {
+            Some(allocation) if allocation.live && !allocation.is_function => {
+                // keep the allocation's address range, so that later accesses
+                // are reported as use after free, but not its contents
+                allocation.live = false;
+                allocation.bytes = Vec::new();
+                self.live_size -= allocation.size;
+                Ok(())
+            },
+            _ => Err(InterpreterError::InvalidFree { address }),
+        }
+    }
+
+    /// Read `size` bytes beginning at `address`
+    pub fn read(&self, address: u64, size: u64) -> Result<&[u8]> {
+        if size == 0 {
+            return Ok(&[]);
+        }
+        let base = self.locate(address, size)?;
+        let offset = (address - base) as usize;
+        Ok(&self.allocations[&base].bytes[offset .. offset + size as usize])
+    }
+
+    /// Write `bytes` beginning at `address`
+    pub fn write(&mut self, address: u64, bytes: &[u8]) -> Result<()> {
+        if bytes.is_empty() {
+            return Ok(());
+        }
+        let base = self.locate(address, bytes.len() as u64)?;
+        let offset = (address - base) as usize;
+        let allocation = self.allocations.get_mut(&base).unwrap();
+        allocation.bytes[offset .. offset + bytes.len()].copy_from_slice(bytes);
+        Ok(())
+    }
+
+    /// Read the nul-terminated string beginning at `address`, not including
+    /// the nul terminator
+    pub fn read_c_string(&self, address: u64) -> Result<Vec<u8>> {
+        let mut string = Vec::new();
+        loop {
+            let byte_address = address
+                .checked_add(string.len() as u64)
+                .ok_or(InterpreterError::OutOfBounds { address, size: 1 })?;
+            match self.read(byte_address, 1)?[0] {
+                0 => return Ok(string),
+                byte => string.push(byte),
+            }
+        }
+    }
+
+    /// Copy `size` bytes from `src` to `dest`. The ranges may overlap.
+    fn copy(&mut self, dest: u64, src: u64, size: u64) -> Result<()> {
+        let bytes = self.read(src, size)?.to_vec();
+        self.write(dest, &bytes)
+    }
+
+    /// Set `size` bytes beginning at `address` to `byte`
+    fn fill(&mut self, address: u64, byte: u8, size: u64) -> Result<()> {
+        if size == 0 {
+            return Ok(());
+        }
+        let base = self.locate(address, size)?;
+        let offset = (address - base) as usize;
+        let allocation = self.allocations.get_mut(&base).unwrap();
+        for b in &mut allocation.bytes[offset .. offset + size as usize] {
+            *b = byte;
+        }
+        Ok(())
+    }
+
+    /// Get the base address of the live allocation containing all of
+    /// `address .. address + size`
+    fn locate(&self, address: u64, size: u64) -> Result<u64> {
+        if address == 0 {
+            return Err(InterpreterError::NullDereference);
+        }
+        let (&base, allocation) = self
+            .allocations
+            .range(..= address)
+            .next_back()
+            .ok_or(InterpreterError::OutOfBounds { address, size })?;
+        let in_bounds = match (address - base).checked_add(size) {
+            Some(end) => end <= allocation.size,
+            None => false,
+        };
+        if !in_bounds || allocation.is_function {
+            Err(InterpreterError::OutOfBounds { address, size })
+        } else if !allocation.live {
+            Err(InterpreterError::UseAfterFree { address })
+        } else {
+            Ok(base)
+        }
+    }
+}
+
+/// Executes the functions of a `Module`.
+pub struct Interpreter<'m> {
+    module: &'m Module,
+    /// The `Module`'s functions, by name
+    index: FunctionIndex<'m>,
+    memory: Memory,
+    /// Address of each global variable, global alias, and function
+    globals: HashMap<&'m str, u64>,
+    /// Name of the function at each function address
+    functions: HashMap<u64, &'m str>,
+    /// The basic blocks of each function which has been called, by name
+    blocks: HashMap<&'m str, HashMap<&'m Name, &'m BasicBlock>>,
+    externals: HashMap<String, ExternalFunction<'m>>,
+}
+
+/// An executing function: its local variables and allocations, and where
+/// it's up to
+struct Frame<'m> {
+    func: &'m Function,
+    /// The block being executed
+    bb: &'m BasicBlock,
+    /// Index in `bb` of the next instruction to execute
+    next_inst: usize,
+    /// Where the caller wants the result of the call
+    call_dest: Option<&'m Name>,
+    locals: HashMap<Name, Value>,
+    allocas: Vec<u64>,
+}
+
+/// A call to a function defined in the `Module`, which is yet to be entered
+struct PendingCall<'m> {
+    func: &'m Function,
+    args: Vec<Value>,
+    dest: Option<&'m Name>,
+}
+
+impl<'m> Interpreter<'m> {
+    /// Create an `Interpreter` for the given `Module`, allocating and
+    /// initializing its global variables.
+    pub fn new(module: &'m Module) -> Result<Self> {
+        let mut interpreter = Self {
+            module,
+            index: module.function_index(),
+            memory: Memory::new(),
+            globals: HashMap::new(),
+            functions: HashMap::new(),
+            blocks: HashMap::new(),
+            externals: HashMap::new(),
+        };
+        let func_names = module
+            .functions
+            .iter()
+            .map(|f| f.name.as_str())
+            .chain(module.func_declarations.iter().map(|f| f.name.as_str()));
+        for name in func_names {
+            let address = interpreter.memory.allocate_internal(0, 16, true)?;
+            interpreter.globals.insert(name, address);
+            interpreter.functions.insert(address, name);
+        }
+        let layout = interpreter.layout();
+        for var in &module.global_vars {
+            let size = layout.alloc_size(&var.value_type)?;
+            let align = layout.abi_align(&var.value_type)?.max(var.alignment as u64);
+            let address = interpreter.memory.allocate(size, align)?;
+            interpreter.globals.insert(&var.name, address);
+        }
+        for alias in &module.global_aliases {
+            let address = match interpreter.eval_constant(&alias.aliasee)? {
+                Value::Pointer(address) => address,
+                value => {
+                    return Err(InterpreterError::Malformed(format!(
+                        "aliasee of {} is {}, not a pointer",
+                        alias.name, value
+                    )))
+                },
+            };
+            interpreter.globals.insert(&alias.name, address);
+        }
+        for var in &module.global_vars {
+            if let Some(initializer) = &var.initializer {
+                let value = interpreter.eval_constant(initializer)?;
+                interpreter.store(
+                    interpreter.globals[var.name.as_str()],
+                    &var.value_type,
+                    &value,
+                )?;
+            }
+        }
+        Ok(interpreter)
+    }
+
+    /// Register a Rust implementation for the function with the given name.
+    /// Calls to that function (if it is only declared in the `Module`) or to
+    /// the intrinsic with that name will go to `f`.
+    pub fn register_external(
+        &mut self,
+        name: impl Into<String>,
+        f: impl FnMut(&mut Memory, &[Value]) -> Result<Option<Value>> + 'm,
+    ) {
+        self.externals.insert(name.into(), Box::new(f));
+    }
+
+    /// Call the function with the given name, returning its result (`None`
+    /// for a `void` function)
+    pub fn call(&mut self, name: &str, args: &[Value]) -> Result<Option<Value>> {
+        match self.index.get_func_by_name(name) {
+            Some(func) => self.run(PendingCall {
+                func,
+                args: args.to_vec(),
+                dest: None,
+            }),
+            None => self.call_external(name, args),
+        }
+    }
+
+    /// Get the address of the global variable, global alias, or function
+    /// with the given name
+    pub fn global_address(&self, name: &str) -> Option<u64> {
+        self.globals.get(name).copied()
+    }
+
+    /// Get the interpreter's `Memory`
+    pub fn memory(&self) -> &Memory {
+        &self.memory
+    }
+
+    /// Get the interpreter's `Memory`, mutably
+    pub fn memory_mut(&mut self) -> &mut Memory {
+        &mut self.memory
+    }
+
+    /// Load a value of type `ty` from `address`
+    pub fn load(&self, address: u64, ty: &Type) -> Result<Value> {
+        let layout = self.layout();
+        let bytes = self.memory.read(address, layout.value_size(ty)?)?;
+        layout.decode(ty, bytes)
+    }
+
+    /// Store `value`, which has type `ty`, to `address`
+    pub fn store(&mut self, address: u64, ty: &Type, value: &Value) -> Result<()> {
+        let layout = self.layout();
+        let mut bytes = vec![0; layout.value_size(ty)? as usize];
+        layout.encode(ty, value, &mut bytes)?;
+        self.memory.write(address, &bytes)
+    }
+
+    fn layout(&self) -> Layout<'m> {
+        Layout {
+            data_layout: &self.module.data_layout,
+            types: &self.module.types,
+        }
+    }
+
+    fn call_external(&mut self, name: &str, args: &[Value]) -> Result<Option<Value>> {
+        match self.externals.get_mut(name) {
+            Some(f) => f(&mut self.memory, args),
+            None => Err(InterpreterError::UnknownFunction(name.into())),
+        }
+    }
+
+    /// Execute the given call, and any calls it makes in turn, until it
+    /// returns. Calls are kept on a stack of `Frame`s, rather than on the
+    /// native stack, so deep recursion in the IR is reported as
+    /// `InterpreterError::StackOverflow` rather than overflowing the native
+    /// stack.
+    fn run(&mut self, call: PendingCall<'m>) -> Result<Option<Value>> {
+        let mut stack = vec![self.enter(call)?];
+        let result = self.run_stack(&mut stack);
+        // after an error, the frames which didn't return are still there
+        for frame in stack {
+            self.release_allocas(frame);
+        }
+        result
+    }
+
+    fn run_stack(&mut self, stack: &mut Vec<Frame<'m>>) -> Result<Option<Value>> {
+        loop {
+            let depth = stack.len();
+            let frame = stack
+                .last_mut()
+                .expect("stack should hold the running call");
+            if let Some(inst) = frame.bb.instrs.get(frame.next_inst) {
+                frame.next_inst += 1;
+                if let Some(call) = self.exec_instruction(inst, frame)? {
+                    if depth >= MAX_CALL_DEPTH {
+                        return Err(InterpreterError::StackOverflow);
+                    }
+                    let callee = self.enter(call)?;
+                    stack.push(callee);
+                }
+                continue;
+            }
+            if let Some(result) = self.exec_terminator(frame)? {
+                let frame = stack.pop().expect("stack should hold the running call");
+                let call_dest = frame.call_dest;
+                self.release_allocas(frame);
+                match stack.last_mut() {
+                    None => return Ok(result),
+                    Some(caller) => {
+                        if let (Some(dest), Some(value)) = (call_dest, result) {
+                            caller.locals.insert(dest.clone(), value);
+                        }
+                    },
+                }
+            }
+        }
+    }
+
+    /// Create the `Frame` for a call, at the start of the called function
+    fn enter(&mut self, call: PendingCall<'m>) -> Result<Frame<'m>> {
+        let func = call.func;
+        let arity_ok = if func.is_var_arg {
+            call.args.len() >= func.parameters.len()
+        } else {
+            call.args.len() == func.parameters.len()
+        };
+        if !arity_ok {
+            return Err(InterpreterError::WrongNumberOfArguments {
+                function: func.name.clone(),
+                expected: func.parameters.len(),
+                got: call.args.len(),
+            });
+        }
+        let entry = func.basic_blocks.first().ok_or_else(|| {
+            InterpreterError::Unsupported(format!("function {} without a body", func.name))
+        })?;
+        if let Some(Instruction::Phi(phi)) = entry.instrs.first() {
+            return Err(InterpreterError::Malformed(format!(
+                "Phi {} in the entry block of {}",
+                phi.dest, func.name
+            )));
+        }
+        self.blocks
+            .entry(func.name.as_str())
+            .or_insert_with(|| func.basic_blocks.iter().map(|bb| (&bb.name, bb)).collect());
+        Ok(Frame {
+            func,
+            bb: entry,
+            next_inst: 0,
+            call_dest: call.dest,
+            locals: func
+                .parameters
+                .iter()
+                .map(|param| param.name.clone())
+                .zip(call.args)
+                .collect(),
+            allocas: vec![],
+        })
+    }
+
+    /// Free the `alloca`s of a function which has returned (or unwound due to
+    /// an error)
+    fn release_allocas(&mut self, frame: Frame<'m>) {
+        for address in frame.allocas {
+            // an external function may have already (wrongly) freed it
+            let _ = self.memory.free(address);
+        }
+    }
+
+    /// Execute the terminator of the frame's current block. Returns the
+    /// function's result (`Some(None)` for a `void` function) if it returns,
+    /// or `None` if it branches to another block.
+    fn exec_terminator(&mut self, frame: &mut Frame<'m>) -> Result<Option<Option<Value>>> {
+        let next = match &frame.bb.term {
+            Terminator::Ret(ret) => {
+                return match &ret.return_operand {
+                    Some(op) => Ok(Some(Some(self.eval_operand(op, frame)?))),
+                    None => Ok(Some(None)),
+                };
+            },
+            Terminator::Br(br) => &br.dest,
+            Terminator::CondBr(condbr) => {
+                match self.eval_operand(&condbr.condition, frame)?.as_u128() {
+                    Some(0) => &condbr.false_dest,
+                    Some(_) => &condbr.true_dest,
+                    None => {
+                        return Err(InterpreterError::Malformed(format!(
+                            "CondBr condition {} isn't an i1",
+                            condbr.condition
+                        )))
+                    },
+                }
+            },
+            Terminator::Switch(switch) => {
+                let value = self.eval_operand(&switch.operand, frame)?;
+                let mut dest = &switch.default_dest;
+                for (case, case_dest) in &switch.dests {
+                    if self.eval_constant(case)? == value {
+                        dest = case_dest;
+                        break;
+                    }
+                }
+                dest
+            },
+            Terminator::Unreachable(_) => return Err(InterpreterError::ReachedUnreachable),
+            term => return Err(InterpreterError::Unsupported(format!("{}", term))),
+        };
+        self.branch(frame, next)?;
+        Ok(None)
+    }
+
+    /// Move to the start of the block named `next`, evaluating its `Phi`s
+    fn branch(&mut self, frame: &mut Frame<'m>, next: &Name) -> Result<()> {
+        let func = frame.func;
+        let bb = *self.blocks[func.name.as_str()].get(next).ok_or_else(|| {
+            InterpreterError::Malformed(format!("function {} has no block {}", func.name, next))
+        })?;
+        // the `Phi`s at the start of a block are evaluated simultaneously
+        let pred = &frame.bb.name;
+        let mut phi_values = vec![];
+        for inst in &bb.instrs {
+            let phi = match inst {
+                Instruction::Phi(phi) => phi,
+                _ => break,
+            };
+            let (op, _) = phi
+                .incoming_values
+                .iter()
+                .find(|(_, from)| from == pred)
+                .ok_or_else(|| {
+                    InterpreterError::Malformed(format!(
+                        "Phi {} has no value for {}",
+                        phi.dest, pred
+                    ))
+                })?;
+            phi_values.push((phi.dest.clone(), self.eval_operand(op, frame)?));
+        }
+        frame.next_inst = phi_values.len();
+        frame.locals.extend(phi_values);
+        frame.bb = bb;
+        Ok(())
+    }
+
+    /// Execute a non-terminator instruction. Returns the call to enter, if
+    /// it's a call to a function defined in the `Module`.
+    fn exec_instruction(
+        &mut self,
+        inst: &'m Instruction,
+        frame: &mut Frame<'m>,
+    ) -> Result<Option<PendingCall<'m>>> {
+        let layout = self.layout();
+        let (dest, value) = match inst {
+            Instruction::Add(i) => (
+                &i.dest,
+                self.binop(BinOp::Add, &i.operand0, &i.operand1, frame)?,
+            ),
+            Instruction::Sub(i) => (
+                &i.dest,
+                self.binop(BinOp::Sub, &i.operand0, &i.operand1, frame)?,
+            ),
+            Instruction::Mul(i) => (
+                &i.dest,
+                self.binop(BinOp::Mul, &i.operand0, &i.operand1, frame)?,
+            ),
+            Instruction::UDiv(i) => (
+                &i.dest,
+                self.binop(BinOp::UDiv, &i.operand0, &i.operand1, frame)?,
+            ),
+            Instruction::SDiv(i) => (
+                &i.dest,
+                self.binop(BinOp::SDiv, &i.operand0, &i.operand1, frame)?,
+            ),
+            Instruction::URem(i) => (
+                &i.dest,
+                self.binop(BinOp::URem, &i.operand0, &i.operand1, frame)?,
+            ),
+            Instruction::SRem(i) => (
+                &i.dest,
+                self.binop(BinOp::SRem, &i.operand0, &i.operand1, frame)?,
+            ),
+            Instruction::And(i) => (
+                &i.dest,
+                self.binop(BinOp::And, &i.operand0, &i.operand1, frame)?,
+            ),
+            Instruction::Or(i) => (
+                &i.dest,
+                self.binop(BinOp::Or, &i.operand0, &i.operand1, frame)?,
+            ),
+            Instruction::Xor(i) => (
+                &i.dest,
+                self.binop(BinOp::Xor, &i.operand0, &i.operand1, frame)?,
+            ),
+            Instruction::Shl(i) => (
+                &i.dest,
+                self.binop(BinOp::Shl, &i.operand0, &i.operand1, frame)?,
+            ),
+            Instruction::LShr(i) => (
+                &i.dest,
+                self.binop(BinOp::LShr, &i.operand0, &i.operand1, frame)?,
+            ),
+            Instruction::AShr(i) => (
+                &i.dest,
+                self.binop(BinOp::AShr, &i.operand0, &i.operand1, frame)?,
+            ),
+            Instruction::FAdd(i) => (
+                &i.dest,
+                self.binop(BinOp::FAdd, &i.operand0, &i.operand1, frame)?,
+            ),
+            Instruction::FSub(i) => (
+                &i.dest,
+                self.binop(BinOp::FSub, &i.operand0, &i.operand1, frame)?,
+            ),
+            Instruction::FMul(i) => (
+                &i.dest,
+                self.binop(BinOp::FMul, &i.operand0, &i.operand1, frame)?,
+            ),
+            Instruction::FDiv(i) => (
+                &i.dest,
+                self.binop(BinOp::FDiv, &i.operand0, &i.operand1, frame)?,
+            ),
+            Instruction::FRem(i) => (
+                &i.dest,
+                self.binop(BinOp::FRem, &i.operand0, &i.operand1, frame)?,
+            ),
+            Instruction::FNeg(i) => (&i.dest, fneg(self.eval_operand(&i.operand, frame)?)),
+            Instruction::ExtractElement(i) => (
+                &i.dest,
+                extract_element(
+                    self.eval_operand(&i.vector, frame)?,
+                    self.eval_operand(&i.index, frame)?,
+                )?,
+            ),
+            Instruction::InsertElement(i) => (
+                &i.dest,
+                insert_element(
+                    self.eval_operand(&i.vector, frame)?,
+                    self.eval_operand(&i.element, frame)?,
+                    self.eval_operand(&i.index, frame)?,
+                )?,
+            ),
+            Instruction::ShuffleVector(i) => (
+                &i.dest,
+                shuffle_vector(
+                    self.eval_operand(&i.operand0, frame)?,
+                    self.eval_operand(&i.operand1, frame)?,
+                    self.eval_constant(&i.mask)?,
+                )?,
+            ),
+            Instruction::ExtractValue(i) => (
+                &i.dest,
+                extract_value(self.eval_operand(&i.aggregate, frame)?, &i.indices),
+            ),
+            Instruction::InsertValue(i) => (
+                &i.dest,
+                insert_value(
+                    self.eval_operand(&i.aggregate, frame)?,
+                    self.eval_operand(&i.element, frame)?,
+                    &i.indices,
+                ),
+            ),
+            Instruction::Alloca(i) => {
+                let count = self.eval_u64(&i.num_elements, frame)?;
+                let size = layout
+                    .alloc_size(&i.allocated_type)?
+                    .checked_mul(count)
+                    .ok_or(InterpreterError::OutOfMemory)?;
+                let align = layout.abi_align(&i.allocated_type)?.max(i.alignment as u64);
+                let address = self.memory.allocate(size, align)?;
+                frame.allocas.push(address);
+                (&i.dest, Value::Pointer(address))
+            },
+            Instruction::Load(i) => {
+                let address = pointer(self.eval_operand(&i.address, frame)?);
+                (&i.dest, self.load(address, &i.loaded_ty)?)
+            },
+            Instruction::Store(i) => {
+                let address = pointer(self.eval_operand(&i.address, frame)?);
+                let value = self.eval_operand(&i.value, frame)?;
+                self.store(address, &self.module.type_of(&i.value), &value)?;
+                return Ok(None);
+            },
+            Instruction::Fence(_) => return Ok(None),
+            Instruction::CmpXchg(i) => {
+                let address = pointer(self.eval_operand(&i.address, frame)?);
+                let expected = self.eval_operand(&i.expected, frame)?;
+                let replacement = self.eval_operand(&i.replacement, frame)?;
+                let ty = self.module.type_of(&i.expected);
+                let old = self.load(address, &ty)?;
+                let success = old == expected;
+                if success {
+                    self.store(address, &ty, &replacement)?;
+                }
+                (&i.dest, Value::Aggregate(vec![old, Value::bool(success)]))
+            },
+            Instruction::AtomicRMW(i) => {
+                let address = pointer(self.eval_operand(&i.address, frame)?);
+                let value = self.eval_operand(&i.value, frame)?;
+                let ty = self.module.type_of(&i.value);
+                let old = self.load(address, &ty)?;
+                let new = atomic_rmw(i.operation, old.clone(), value)?;
+                self.store(address, &ty, &new)?;
+                (&i.dest, old)
+            },
+            Instruction::GetElementPtr(i) => {
+                let address = self.eval_operand(&i.address, frame)?;
+                let indices = i
+                    .indices
+                    .iter()
+                    .map(|index| self.eval_operand(index, frame))
+                    .collect::<Result<Vec<_>>>()?;
+                (
+                    &i.dest,
+                    layout.gep(address, &indices, &i.source_element_type)?,
+                )
+            },
+            Instruction::Trunc(i) => (
+                &i.dest,
+                self.cast(CastOp::Trunc, &i.operand, &i.to_type, frame)?,
+            ),
+            Instruction::ZExt(i) => (
+                &i.dest,
+                self.cast(CastOp::ZExt, &i.operand, &i.to_type, frame)?,
+            ),
+            Instruction::SExt(i) => (
+                &i.dest,
+                self.cast(CastOp::SExt, &i.operand, &i.to_type, frame)?,
+            ),
+            Instruction::FPTrunc(i) => (
+                &i.dest,
+                self.cast(CastOp::FPTrunc, &i.operand, &i.to_type, frame)?,
+            ),
+            Instruction::FPExt(i) => (
+                &i.dest,
+                self.cast(CastOp::FPExt, &i.operand, &i.to_type, frame)?,
+            ),
+            Instruction::FPToUI(i) => (
+                &i.dest,
+                self.cast(CastOp::FPToUI, &i.operand, &i.to_type, frame)?,
+            ),
+            Instruction::FPToSI(i) => (
+                &i.dest,
+                self.cast(CastOp::FPToSI, &i.operand, &i.to_type, frame)?,
+            ),
+            Instruction::UIToFP(i) => (
+                &i.dest,
+                self.cast(CastOp::UIToFP, &i.operand, &i.to_type, frame)?,
+            ),
+            Instruction::SIToFP(i) => (
+                &i.dest,
+                self.cast(CastOp::SIToFP, &i.operand, &i.to_type, frame)?,
+            ),
+            Instruction::PtrToInt(i) => (
+                &i.dest,
+                self.cast(CastOp::PtrToInt, &i.operand, &i.to_type, frame)?,
+            ),
+            Instruction::IntToPtr(i) => (
+                &i.dest,
+                self.cast(CastOp::IntToPtr, &i.operand, &i.to_type, frame)?,
+            ),
+            Instruction::BitCast(i) => (
+                &i.dest,
+                self.cast(CastOp::BitCast, &i.operand, &i.to_type, frame)?,
+            ),
+            Instruction::AddrSpaceCast(i) => (
+                &i.dest,
+                self.cast(CastOp::AddrSpaceCast, &i.operand, &i.to_type, frame)?,
+            ),
+            Instruction::ICmp(i) => (
+                &i.dest,
+                icmp(
+                    i.predicate,
+                    self.eval_operand(&i.operand0, frame)?,
+                    self.eval_operand(&i.operand1, frame)?,
+                ),
+            ),
+            Instruction::FCmp(i) => (
+                &i.dest,
+                fcmp(
+                    i.predicate,
+                    self.eval_operand(&i.operand0, frame)?,
+                    self.eval_operand(&i.operand1, frame)?,
+                ),
+            ),
+            Instruction::Phi(phi) => {
+                return Err(InterpreterError::Malformed(format!(
+                    "Phi {} is not at the start of its block",
+                    phi.dest
+                )))
+            },
+            Instruction::Select(i) => (
+                &i.dest,
+                select(
+                    self.eval_operand(&i.condition, frame)?,
+                    self.eval_operand(&i.true_value, frame)?,
+                    self.eval_operand(&i.false_value, frame)?,
+                ),
+            ),
+            Instruction::Freeze(i) => (&i.dest, self.eval_operand(&i.operand, frame)?),
+            Instruction::Call(call) => match self.exec_call(call, frame)? {
+                Either::Left(pending) => return Ok(Some(pending)),
+                Either::Right(Some(value)) => match &call.dest {
+                    Some(dest) => (dest, value),
+                    None => return Ok(None),
+                },
+                Either::Right(None) => return Ok(None),
+            },
+            inst => return Err(InterpreterError::Unsupported(format!("{}", inst))),
+        };
+        frame.locals.insert(dest.clone(), value);
+        Ok(None)
+    }
+
+    /// Execute a call to an intrinsic or an external function, giving its
+    /// result; or, for a call to a function defined in the `Module`, give the
+    /// call to enter
+    fn exec_call(
+        &mut self,
+        call: &'m Call,
+        frame: &Frame<'m>,
+    ) -> Result<Either<PendingCall<'m>, Option<Value>>> {
+        let function = match &call.function {
+            Either::Left(_) => return Err(InterpreterError::Unsupported("inline assembly".into())),
+            Either::Right(function) => function,
+        };
+        match call.as_intrinsic() {
+            Some(Intrinsic::Other { .. }) | None => {},
+            Some(intrinsic) => return Ok(Either::Right(self.exec_intrinsic(intrinsic, frame)?)),
+        }
+        let args = call
+            .arguments
+            .iter()
+            .map(|(arg, _)| self.eval_operand(arg, frame))
+            .collect::<Result<Vec<_>>>()?;
+        let address = pointer(self.eval_operand(function, frame)?);
+        let name = *self
+            .functions
+            .get(&address)
+            .ok_or(InterpreterError::NotAFunction { address })?;
+        match self.index.get_func_by_name(name) {
+            Some(func) => Ok(Either::Left(PendingCall {
+                func,
+                args,
+                dest: call.dest.as_ref(),
+            })),
+            None => Ok(Either::Right(self.call_external(name, &args)?)),
+        }
+    }
+
+    fn exec_intrinsic(&mut self, intrinsic: Intrinsic, frame: &Frame) -> Result<Option<Value>> {
+        match intrinsic {
+            Intrinsic::MemCpy { dest, src, len, .. }
+            | Intrinsic::MemCpyInline { dest, src, len, .. }
+            | Intrinsic::MemMove { dest, src, len, .. } => {
+                let dest = pointer(self.eval_operand(dest, frame)?);
+                let src = pointer(self.eval_operand(src, frame)?);
+                let len = self.eval_u64(len, frame)?;
+                self.memory.copy(dest, src, len)?;
+                Ok(None)
+            },
+            Intrinsic::MemSet { dest, val, len, .. }
+            | Intrinsic::MemSetInline { dest, val, len, .. } => {
+                let dest = pointer(self.eval_operand(dest, frame)?);
+                let val = self.eval_int(val, frame)? as u8;
+                let len = self.eval_u64(len, frame)?;
+                self.memory.fill(dest, val, len)?;
+                Ok(None)
+            },
+            Intrinsic::LifetimeStart { .. } | Intrinsic::LifetimeEnd { .. } => Ok(None),
+            Intrinsic::Dbg(_) | Intrinsic::StackRestore { .. } | Intrinsic::DebugTrap => Ok(None),
+            Intrinsic::WithOverflow { op, lhs, rhs, .. } => {
+                let lhs = self.eval_operand(lhs, frame)?;
+                let rhs = self.eval_operand(rhs, frame)?;
+                Ok(Some(with_overflow(op, lhs, rhs)))
+            },
+            Intrinsic::Assume { cond } => match self.eval_operand(cond, frame)?.as_u128() {
+                Some(0) => Err(InterpreterError::AssumptionViolated),
+                _ => Ok(None),
+            },
+            Intrinsic::Expect { val, .. } => Ok(Some(self.eval_operand(val, frame)?)),
+            // there's no real stack to save
+            Intrinsic::StackSave => Ok(Some(Value::Pointer(0))),
+            Intrinsic::Trap => Err(InterpreterError::Trap),
+            Intrinsic::Constrained { op, .. } => Err(InterpreterError::Unsupported(format!(
+                "llvm.experimental.constrained.{}",
+                op
+            ))),
+            Intrinsic::VaStart { .. } | Intrinsic::VaEnd { .. } | Intrinsic::VaCopy { .. } => {
+                Err(InterpreterError::Unsupported("variadic arguments".into()))
+            },
+            Intrinsic::Other { name } => unreachable!("{} should be called as an external", name),
+        }
+    }
+
+    fn binop(&self, op: BinOp, op0: &Operand, op1: &Operand, frame: &Frame) -> Result<Value> {
+        binop(
+            op,
+            self.eval_operand(op0, frame)?,
+            self.eval_operand(op1, frame)?,
+        )
+    }
+
+    fn cast(&self, op: CastOp, operand: &Operand, to_type: &Type, frame: &Frame) -> Result<Value> {
+        let value = self.eval_operand(operand, frame)?;
+        self.layout()
+            .cast(op, value, &self.module.type_of(operand), to_type)
+    }
+
+    fn eval_operand(&self, op: &Operand, frame: &Frame) -> Result<Value> {
+        match op {
+            Operand::LocalOperand { name, .. } => match frame.locals.get(name) {
+                Some(value) => Ok(value.clone()),
+                None => Err(InterpreterError::Malformed(format!(
+                    "use of undefined local {}",
+                    name
+                ))),
+            },
+            Operand::ConstantOperand(constant) => self.eval_constant(constant),
+            Operand::MetadataOperand => {
+                Err(InterpreterError::Unsupported("metadata operand".into()))
+            },
+        }
+    }
+
+    /// Evaluate an operand which should be an integer, as unsigned
+    fn eval_int(&self, op: &Operand, frame: &Frame) -> Result<u128> {
+        let value = self.eval_operand(op, frame)?;
+        value
+            .as_u128()
+            .ok_or_else(|| InterpreterError::Malformed(format!("{} isn't an integer", value)))
+    }
+
+    /// Evaluate an operand which is a size or a count of some memory. One
+    /// which doesn't fit in a `u64` certainly won't fit in memory.
+    fn eval_u64(&self, op: &Operand, frame: &Frame) -> Result<u64> {
+        u64::try_from(self.eval_int(op, frame)?).map_err(|_| InterpreterError::OutOfMemory)
+    }
+
+    fn eval_constant(&self, constant: &Constant) -> Result<Value> {
+        let layout = self.layout();
+        let eval = |c: &ConstantRef| self.eval_constant(c);
+        let cast = |op: CastOp, c: &ConstantRef, to_type: &TypeRef| {
+            layout.cast(op, eval(c)?, &self.module.type_of(c), to_type)
+        };
+        Ok(match constant {
+            Constant::Int { bits, value } => {
+                check_int_bits(*bits)?;
+                Value::int(*bits, *value as u128)
+            },
+            Constant::Float(Float::Single(s)) => Value::Single(*s),
+            Constant::Float(Float::Double(d)) => Value::Double(*d),
+            Constant::Float(f) => return Err(InterpreterError::Unsupported(format!("{}", f))),
+            Constant::Null(_) => Value::Pointer(0),
+            Constant::AggregateZero(ty) | Constant::Undef(ty) => layout.zero_value(ty)?,
+            Constant::Poison(ty) => layout.zero_value(ty)?,
+            Constant::Struct { values, .. } => {
+                Value::Aggregate(values.iter().map(eval).collect::<Result<_>>()?)
+            },
+            Constant::Array { elements, .. } => {
+                Value::Aggregate(elements.iter().map(eval).collect::<Result<_>>()?)
+            },
+            Constant::Vector(elements) => {
+                Value::Vector(elements.iter().map(eval).collect::<Result<_>>()?)
+            },
+            Constant::GlobalReference { name, .. } => match self.globals.get(name.as_str()) {
+                Some(address) => Value::Pointer(*address),
+                None => {
+                    return Err(InterpreterError::Unsupported(format!(
+                        "reference to {}",
+                        name
+                    )))
+                },
+            },
+            Constant::BlockAddress | Constant::TokenNone => {
+                return Err(InterpreterError::Unsupported(format!("{}", constant)))
+            },
+            Constant::Add(a) => binop(BinOp::Add, eval(&a.operand0)?, eval(&a.operand1)?)?,
+            Constant::Sub(s) => binop(BinOp::Sub, eval(&s.operand0)?, eval(&s.operand1)?)?,
+            Constant::Mul(m) => binop(BinOp::Mul, eval(&m.operand0)?, eval(&m.operand1)?)?,
+            Constant::UDiv(d) => binop(BinOp::UDiv, eval(&d.operand0)?, eval(&d.operand1)?)?,
+            Constant::SDiv(d) => binop(BinOp::SDiv, eval(&d.operand0)?, eval(&d.operand1)?)?,
+            Constant::URem(r) => binop(BinOp::URem, eval(&r.operand0)?, eval(&r.operand1)?)?,
+            Constant::SRem(r) => binop(BinOp::SRem, eval(&r.operand0)?, eval(&r.operand1)?)?,
+            Constant::And(a) => binop(BinOp::And, eval(&a.operand0)?, eval(&a.operand1)?)?,
+            Constant::Or(o) => binop(BinOp::Or, eval(&o.operand0)?, eval(&o.operand1)?)?,
+            Constant::Xor(x) => binop(BinOp::Xor, eval(&x.operand0)?, eval(&x.operand1)?)?,
+            Constant::Shl(s) => binop(BinOp::Shl, eval(&s.operand0)?, eval(&s.operand1)?)?,
+            Constant::LShr(s) => binop(BinOp::LShr, eval(&s.operand0)?, eval(&s.operand1)?)?,
+            Constant::AShr(s) => binop(BinOp::AShr, eval(&s.operand0)?, eval(&s.operand1)?)?,
+            Constant::FAdd(f) => binop(BinOp::FAdd, eval(&f.operand0)?, eval(&f.operand1)?)?,
+            Constant::FSub(f) => binop(BinOp::FSub, eval(&f.operand0)?, eval(&f.operand1)?)?,
+            Constant::FMul(f) => binop(BinOp::FMul, eval(&f.operand0)?, eval(&f.operand1)?)?,
+            Constant::FDiv(f) => binop(BinOp::FDiv, eval(&f.operand0)?, eval(&f.operand1)?)?,
+            Constant::FRem(f) => binop(BinOp::FRem, eval(&f.operand0)?, eval(&f.operand1)?)?,
+            Constant::ExtractElement(e) => extract_element(eval(&e.vector)?, eval(&e.index)?)?,
+            Constant::InsertElement(i) => {
+                insert_element(eval(&i.vector)?, eval(&i.element)?, eval(&i.index)?)?
+            },
+            Constant::ShuffleVector(s) => {
+                shuffle_vector(eval(&s.operand0)?, eval(&s.operand1)?, eval(&s.mask)?)?
+            },
+            Constant::ExtractValue(e) => extract_value(eval(&e.aggregate)?, &e.indices),
+            Constant::InsertValue(i) => {
+                insert_value(eval(&i.aggregate)?, eval(&i.element)?, &i.indices)
+            },
+            Constant::GetElementPtr(g) => {
+                let indices = g.indices.iter().map(eval).collect::<Result<Vec<_>>>()?;
+                layout.gep(eval(&g.address)?, &indices, &g.source_element_type)?
+            },
+            Constant::Trunc(c) => cast(CastOp::Trunc, &c.operand, &c.to_type)?,
+            Constant::ZExt(c) => cast(CastOp::ZExt, &c.operand, &c.to_type)?,
+            Constant::SExt(c) => cast(CastOp::SExt, &c.operand, &c.to_type)?,
+            Constant::FPTrunc(c) => cast(CastOp::FPTrunc, &c.operand, &c.to_type)?,
+            Constant::FPExt(c) => cast(CastOp::FPExt, &c.operand, &c.to_type)?,
+            Constant::FPToUI(c) => cast(CastOp::FPToUI, &c.operand, &c.to_type)?,
+            Constant::FPToSI(c) => cast(CastOp::FPToSI, &c.operand, &c.to_type)?,
+            Constant::UIToFP(c) => cast(CastOp::UIToFP, &c.operand, &c.to_type)?,
+            Constant::SIToFP(c) => cast(CastOp::SIToFP, &c.operand, &c.to_type)?,
+            Constant::PtrToInt(c) => cast(CastOp::PtrToInt, &c.operand, &c.to_type)?,
+            Constant::IntToPtr(c) => cast(CastOp::IntToPtr, &c.operand, &c.to_type)?,
+            Constant::BitCast(c) => cast(CastOp::BitCast, &c.operand, &c.to_type)?,
+            Constant::AddrSpaceCast(c) => cast(CastOp::AddrSpaceCast, &c.operand, &c.to_type)?,
+            Constant::ICmp(c) => icmp(c.predicate, eval(&c.operand0)?, eval(&c.operand1)?),
+            Constant::FCmp(c) => fcmp(c.predicate, eval(&c.operand0)?, eval(&c.operand1)?),
+            Constant::Select(s) => select(
+                eval(&s.condition)?,
+                eval(&s.true_value)?,
+                eval(&s.false_value)?,
+            ),
+        })
+    }
+}
+
+/// Computes sizes and offsets of types, and converts values to and from
+/// bytes, according to a `DataLayout`
+#[derive(Clone, Copy)]
+struct Layout<'m> {
+    data_layout: &'m DataLayout,
+    types: &'m Types,
+}
+
+impl<'m> Layout<'m> {
+    /// Get the definition of a named struct type; other types are returned
+    /// unchanged
+    fn resolve<'a>(&self, ty: &'a Type) -> Result<&'a Type>
+    where
+        'm: 'a,
+    {
+        match ty {
+            Type::NamedStructType { name } => match self.types.named_struct_def(name) {
+                Some(NamedStructDef::Defined(def)) => Ok(def),
+                Some(NamedStructDef::Opaque) => Err(InterpreterError::Unsupported(format!(
+                    "opaque struct type %{}",
+                    name
+                ))),
+                None => Err(InterpreterError::Malformed(format!(
+                    "named struct type %{} has no definition",
+                    name
+                ))),
+            },
+            ty => Ok(ty),
+        }
+    }
+
+    fn pointer_size(&self, addr_space: u32) -> u64 {
+        u64::from(self.data_layout.alignments.ptr_alignment(addr_space).size) / 8
+    }
+
+    /// Number of bytes which a store of the given type may write
+    fn store_size(&self, ty: &Type) -> Result<u64> {
+        Ok(match self.resolve(ty)? {
+            Type::IntegerType { bits } => u64::from(*bits + 7) / 8,
+            Type::FPType(fpt) => match fpt {
+                FPType::Half => 2,
+                FPType::BFloat => 2,
+                FPType::Single => 4,
+                FPType::Double => 8,
+                FPType::X86_FP80 => 10,
+                FPType::FP128 | FPType::PPC_FP128 => 16,
+            },
+            Type::PointerType { addr_space, .. } => self.pointer_size(*addr_space),
+            Type::OpaquePointerType { addr_space } => self.pointer_size(*addr_space),
+            Type::VectorType {
+                element_type,
+                num_elements,
+                ..
+            } => self
+                .vector_element_size(ty, element_type)?
+                .checked_mul(*num_elements as u64)
+                .ok_or(InterpreterError::OutOfMemory)?,
+            Type::ArrayType {
+                element_type,
+                num_elements,
+            } => self
+                .alloc_size(element_type)?
+                .checked_mul(*num_elements as u64)
+                .ok_or(InterpreterError::OutOfMemory)?,
+            Type::StructType {
+                element_types,
+                is_packed,
+            } => self.struct_layout(element_types, *is_packed)?.1,
+            ty => return Err(InterpreterError::Unsupported(format!("size of {}", ty))),
+        })
+    }
+
+    /// Number of bytes between successive values of the given type in memory
+    fn alloc_size(&self, ty: &Type) -> Result<u64> {
+        let align = self.abi_align(ty)?;
+        round_up(self.store_size(ty)?, align)
+    }
+
+    /// Like `store_size()`, for values the interpreter will hold: it's an
+    /// error if the type is larger than `MAX_VALUE_SIZE`
+    fn value_size(&self, ty: &Type) -> Result<u64> {
+        let size = self.store_size(ty)?;
+        if size > MAX_VALUE_SIZE {
+            Err(InterpreterError::OutOfMemory)
+        } else {
+            Ok(size)
+        }
+    }
+
+    /// ABI alignment of the given type, in bytes
+    fn abi_align(&self, ty: &Type) -> Result<u64> {
+        let alignments = &self.data_layout.alignments;
+        Ok(match self.resolve(ty)? {
+            Type::ArrayType { element_type, .. } => self.abi_align(element_type)?,
+            Type::StructType {
+                element_types,
+                is_packed,
+            } => self.struct_layout(element_types, *is_packed)?.2,
+            Type::VectorType { element_type, .. } => {
+                // `type_alignment()` only handles vectors of integers and floats
+                self.vector_element_size(ty, element_type)?;
+                u64::from(alignments.type_alignment(ty).abi / 8).max(1)
+            },
+            ty @ Type::IntegerType { .. }
+            | ty @ Type::FPType(_)
+            | ty @ Type::PointerType { .. } => {
+                u64::from(alignments.type_alignment(ty).abi / 8).max(1)
+            },
+            ty @ Type::OpaquePointerType { .. } => {
+                u64::from(alignments.type_alignment(ty).abi / 8).max(1)
+            },
+            ty => {
+                return Err(InterpreterError::Unsupported(format!(
+                    "alignment of {}",
+                    ty
+                )))
+            },
+        })
+    }
+
+    /// Size of an element of the given vector type. Only vectors of
+    /// byte-sized integers and floats are supported.
+    fn vector_element_size(&self, vector_ty: &Type, element_type: &Type) -> Result<u64> {
+        if let Type::VectorType { scalable: true, .. } = vector_ty {
+            return Err(InterpreterError::Unsupported(format!("{}", vector_ty)));
+        }
+        match element_type {
+            Type::IntegerType { bits } if bits % 8 == 0 => self.store_size(element_type),
+            Type::FPType(_) => self.store_size(element_type),
+            _ => Err(InterpreterError::Unsupported(format!("{}", vector_ty))),
+        }
+    }
+
+    /// Offset of each field, plus the total size and the alignment of the
+    /// struct with the given element types
+    fn struct_layout(
+        &self,
+        element_types: &[TypeRef],
+        is_packed: bool,
+    ) -> Result<(Vec<u64>, u64, u64)> {
+        let mut offsets = Vec::with_capacity(element_types.len());
+        let mut size = 0;
+        let mut struct_align = if is_packed {
+            1
+        } else {
+            u64::from(self.data_layout.alignments.agg_alignment().abi / 8).max(1)
+        };
+        for element_type in element_types {
+            if !is_packed {
+                let align = self.abi_align(element_type)?;
+                size = round_up(size, align)?;
+                struct_align = struct_align.max(align);
+            }
+            offsets.push(size);
+            size = size
+                .checked_add(self.alloc_size(element_type)?)
+                .ok_or(InterpreterError::OutOfMemory)?;
+        }
+        size = round_up(size, struct_align)?;
+        Ok((offsets, size, struct_align))
+    }
+
+    /// Zero value of the given type
+    fn zero_value(&self, ty: &Type) -> Result<Value> {
+        self.value_size(ty)?;
+        Ok(match self.resolve(ty)? {
+            Type::IntegerType { bits } => {
+                check_int_bits(*bits)?;
+                Value::int(*bits, 0)
+            },
+            Type::FPType(FPType::Single) => Value::Single(0.0),
+            Type::FPType(FPType::Double) => Value::Double(0.0),
+            Type::PointerType { .. } => Value::Pointer(0),
+            Type::OpaquePointerType { .. } => Value::Pointer(0),
+            Type::VectorType {
+                element_type,
+                num_elements,
+                ..
+            } => Value::Vector(vec![self.zero_value(element_type)?; *num_elements]),
+            Type::ArrayType {
+                element_type,
+                num_elements,
+            } => Value::Aggregate(vec![self.zero_value(element_type)?; *num_elements]),
+            Type::StructType { element_types, .. } => Value::Aggregate(
+                element_types
+                    .iter()
+                    .map(|ty| self.zero_value(ty))
+                    .collect::<Result<_>>()?,
+            ),
+            ty => {
+                return Err(InterpreterError::Unsupported(format!(
+                    "value of type {}",
+                    ty
+                )))
+            },
+        })
+    }
+
+    /// Write `value`, of type `ty`, into `bytes`, which is `store_size(ty)`
+    /// bytes long
+    fn encode(&self, ty: &Type, value: &Value, bytes: &mut [u8]) -> Result<()> {
+        match (self.resolve(ty)?, value) {
+            (Type::IntegerType { .. }, Value::Int { value, .. }) => self.put_uint(*value, bytes),
+            (Type::FPType(FPType::Single), Value::Single(s)) => {
+                self.put_uint(s.to_bits().into(), bytes)
+            },
+            (Type::FPType(FPType::Double), Value::Double(d)) => {
+                self.put_uint(d.to_bits().into(), bytes)
+            },
+            (_, Value::Pointer(address)) => self.put_uint((*address).into(), bytes),
+            (Type::VectorType { element_type, .. }, Value::Vector(elements)) => {
+                let size = self.store_size(element_type)? as usize;
+                for (i, element) in elements.iter().enumerate() {
+                    self.encode(element_type, element, &mut bytes[i * size .. (i + 1) * size])?;
+                }
+            },
+            (Type::ArrayType { element_type, .. }, Value::Aggregate(elements)) => {
+                let stride = self.alloc_size(element_type)? as usize;
+                let size = self.store_size(element_type)? as usize;
+                for (i, element) in elements.iter().enumerate() {
+                    self.encode(
+                        element_type,
+                        element,
+                        &mut bytes[i * stride .. i * stride + size],
+                    )?;
+                }
+            },
+            (
+                Type::StructType {
+                    element_types,
+                    is_packed,
+                },
+                Value::Aggregate(elements),
+            ) => {
+                let (offsets, _, _) = self.struct_layout(element_types, *is_packed)?;
+                for ((element_type, element), offset) in
+                    element_types.iter().zip(elements).zip(offsets)
+                {
+                    let offset = offset as usize;
+                    let size = self.store_size(element_type)? as usize;
+                    self.encode(element_type, element, &mut bytes[offset .. offset + size])?;
+                }
+            },
+            (ty, value) => panic!("Can't store {} as a value of type {}", value, ty),
+        }
+        Ok(())
+    }
+
+    /// Read a value of type `ty` from `bytes`, which is `store_size(ty)`
+    /// bytes long
+    fn decode(&self, ty: &Type, bytes: &[u8]) -> Result<Value> {
+        Ok(match self.resolve(ty)? {
+            Type::IntegerType { bits } => {
+                check_int_bits(*bits)?;
+                Value::int(*bits, self.get_uint(bytes))
+            },
+            Type::FPType(FPType::Single) => {
+                Value::Single(f32::from_bits(self.get_uint(bytes) as u32))
+            },
+            Type::FPType(FPType::Double) => {
+                Value::Double(f64::from_bits(self.get_uint(bytes) as u64))
+            },
+            Type::PointerType { .. } => Value::Pointer(self.get_uint(bytes) as u64),
+            Type::OpaquePointerType { .. } => Value::Pointer(self.get_uint(bytes) as u64),
+            Type::VectorType { element_type, .. } => {
+                let size = self.store_size(element_type)? as usize;
+                Value::Vector(
+                    bytes
+                        .chunks(size)
+                        .map(|chunk| self.decode(element_type, chunk))
+                        .collect::<Result<_>>()?,
+                )
+            },
+            Type::ArrayType {
+                element_type,
+                num_elements,
+            } => {
+                let stride = self.alloc_size(element_type)? as usize;
+                let size = self.store_size(element_type)? as usize;
+                Value::Aggregate(
+                    (0 .. *num_elements)
+                        .map(|i| self.decode(element_type, &bytes[i * stride .. i * stride + size]))
+                        .collect::<Result<_>>()?,
+                )
+            },
+            Type::StructType {
+                element_types,
+                is_packed,
+            } => {
+                let (offsets, _, _) = self.struct_layout(element_types, *is_packed)?;
+                Value::Aggregate(
+                    element_types
+                        .iter()
+                        .zip(offsets)
+                        .map(|(element_type, offset)| {
+                            let offset = offset as usize;
+                            let size = self.store_size(element_type)? as usize;
+                            self.decode(element_type, &bytes[offset .. offset + size])
+                        })
+                        .collect::<Result<_>>()?,
+                )
+            },
+            ty => {
+                return Err(InterpreterError::Unsupported(format!(
+                    "value of type {}",
+                    ty
+                )))
+            },
+        })
+    }
+
+    fn put_uint(&self, value: u128, bytes: &mut [u8]) {
+        let len = bytes.len();
+        bytes.copy_from_slice(&value.to_le_bytes()[.. len]);
+        if self.data_layout.endianness == Endianness::BigEndian {
+            bytes.reverse();
+        }
+    }
+
+    fn get_uint(&self, bytes: &[u8]) -> u128 {
+        let mut le_bytes = [0; 16];
+        le_bytes[.. bytes.len()].copy_from_slice(bytes);
+        if self.data_layout.endianness == Endianness::BigEndian {
+            le_bytes[.. bytes.len()].reverse();
+        }
+        u128::from_le_bytes(le_bytes)
+    }
+
+    /// Compute the address for a `getelementptr`
+    fn gep(&self, address: Value, indices: &[Value], source_element_type: &Type) -> Result<Value> {
+        let mut address = match address {
+            Value::Pointer(address) => address,
+            _ => return Err(InterpreterError::Unsupported("vector getelementptr".into())),
+        };
+        let mut ty = source_element_type;
+        for (i, index) in indices.iter().enumerate() {
+            let index = match index.as_i128() {
+                Some(index) => index,
+                None => return Err(InterpreterError::Unsupported("vector getelementptr".into())),
+            };
+            let offset = if i == 0 {
+                index.wrapping_mul(self.alloc_size(ty)? as i128)
+            } else {
+                match self.resolve(ty)? {
+                    Type::StructType {
+                        element_types,
+                        is_packed,
+                    } => {
+                        let (offsets, _, _) = self.struct_layout(element_types, *is_packed)?;
+                        let field = usize::try_from(index)
+                            .ok()
+                            .filter(|&field| field < element_types.len())
+                            .ok_or_else(|| {
+                                InterpreterError::Malformed(format!(
+                                    "getelementptr index {} into a struct with {} fields",
+                                    index,
+                                    element_types.len()
+                                ))
+                            })?;
+                        ty = &element_types[field];
+                        offsets[field] as i128
+                    },
+                    Type::ArrayType { element_type, .. }
+                    | Type::VectorType { element_type, .. } => {
+                        ty = element_type;
+                        index.wrapping_mul(self.alloc_size(ty)? as i128)
+                    },
+                    ty => panic!("Can't index into {} with getelementptr", ty),
+                }
+            };
+            address = address.wrapping_add(offset as u64);
+        }
+        Ok(Value::Pointer(address))
+    }
+
+    fn cast(&self, op: CastOp, value: Value, from_type: &Type, to_type: &Type) -> Result<Value> {
+        if op == CastOp::BitCast {
+            return match value {
+                Value::Pointer(_) => Ok(value),
+                value => {
+                    let mut bytes = vec![0; self.value_size(from_type)? as usize];
+                    self.encode(from_type, &value, &mut bytes)?;
+                    self.decode(to_type, &bytes)
+                },
+            };
+        }
+        match (value, to_type) {
+            (Value::Vector(elements), Type::VectorType { element_type, .. }) => Ok(Value::Vector(
+                elements
+                    .into_iter()
+                    .map(|element| scalar_cast(op, element, element_type))
+                    .collect::<Result<_>>()?,
+            )),
+            (value, to_type) => scalar_cast(op, value, to_type),
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+enum BinOp {
+    Add,
+    Sub,
+    Mul,
+    UDiv,
+    SDiv,
+    URem,
+    SRem,
+    And,
+    Or,
+    Xor,
+    Shl,
+    LShr,
+    AShr,
+    FAdd,
+    FSub,
+    FMul,
+    FDiv,
+    FRem,
+}
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+enum CastOp {
+    Trunc,
+    ZExt,
+    SExt,
+    FPTrunc,
+    FPExt,
+    FPToUI,
+    FPToSI,
+    UIToFP,
+    SIToFP,
+    PtrToInt,
+    IntToPtr,
+    BitCast,
+    AddrSpaceCast,
+}
+
+/// Bits `0 .. bits` set
+fn mask(bits: u32) -> u128 {
+    if bits >= 128 {
+        u128::MAX
+    } else {
+        (1 << bits) - 1
+    }
+}
+
+/// Sign-extend the `bits`-bit integer `value`
+fn sext(bits: u32, value: u128) -> i128 {
+    let shift = 128 - bits;
+    ((value << shift) as i128) >> shift
+}
+
+/// Round `value` up to a multiple of `align`, or give
+/// `InterpreterError::OutOfMemory` if the result doesn't fit in a `u64`
+fn round_up(value: u64, align: u64) -> Result<u64> {
+    match value % align {
+        0 => Ok(value),
+        rem => value
+            .checked_add(align - rem)
+            .ok_or(InterpreterError::OutOfMemory),
+    }
+}
+
+fn check_int_bits(bits: u32) -> Result<()> {
+    if bits > 128 {
+        Err(InterpreterError::Unsupported(format!("i{}", bits)))
+    } else {
+        Ok(())
+    }
+}
+
+fn pointer(value: Value) -> u64 {
+    match value {
+        Value::Pointer(address) => address,
+        value => panic!("Expected a pointer, got {}", value),
+    }
+}
+
+fn as_f64(value: &Value) -> f64 {
+    match value {
+        Value::Single(s) => f64::from(*s),
+        Value::Double(d) => *d,
+        value => panic!("Expected a floating-point value, got {}", value),
+    }
+}
+
+fn binop(op: BinOp, a: Value, b: Value) -> Result<Value> {
+    match (a, b) {
+        (Value::Vector(a), Value::Vector(b)) => Ok(Value::Vector(
+            a.into_iter()
+                .zip(b)
+                .map(|(a, b)| binop(op, a, b))
+                .collect::<Result<_>>()?,
+        )),
+        (Value::Int { bits, value: x }, Value::Int { value: y, .. }) => int_binop(op, bits, x, y),
+        // computing in `f64` and rounding gives the correctly-rounded `f32` result
+        (Value::Single(x), Value::Single(y)) => {
+            Ok(Value::Single(float_binop(op, x.into(), y.into()) as f32))
+        },
+        (Value::Double(x), Value::Double(y)) => Ok(Value::Double(float_binop(op, x, y))),
+        (a, b) => panic!("Invalid operands for {:?}: {} and {}", op, a, b),
+    }
+}
+
+fn int_binop(op: BinOp, bits: u32, x: u128, y: u128) -> Result<Value> {
+    let (sx, sy) = (sext(bits, x), sext(bits, y));
+    let signed_min = sext(bits, 1 << (bits - 1));
+    let check_shift = || {
+        if y >= u128::from(bits) {
+            Err(InterpreterError::OversizedShift { amount: y, bits })
+        } else {
+            Ok(y as u32)
+        }
+    };
+    let result = match op {
+        BinOp::Add => x.wrapping_add(y),
+        BinOp::Sub => x.wrapping_sub(y),
+        BinOp::Mul => x.wrapping_mul(y),
+        BinOp::UDiv | BinOp::URem | BinOp::SDiv | BinOp::SRem if y == 0 => {
+            return Err(InterpreterError::DivisionByZero)
+        },
+        BinOp::SDiv | BinOp::SRem if sx == signed_min && sy == -1 => {
+            return Err(InterpreterError::DivisionOverflow)
+        },
+        BinOp::UDiv => x / y,
+        BinOp::URem => x % y,
+        BinOp::SDiv => (sx / sy) as u128,
+        BinOp::SRem => (sx % sy) as u128,
+        BinOp::And => x & y,
+        BinOp::Or => x | y,
+        BinOp::Xor => x ^ y,
+        BinOp::Shl => x << check_shift()?,
+        BinOp::LShr => x >> check_shift()?,
+        BinOp::AShr => (sx >> check_shift()?) as u128,
+        _ => panic!("Invalid operands for {:?}: integers", op),
+    };
+    Ok(Value::int(bits, result))
+}
+
+fn float_binop(op: BinOp, x: f64, y: f64) -> f64 {
+    match op {
+        BinOp::FAdd => x + y,
+        BinOp::FSub => x - y,
+        BinOp::FMul => x * y,
+        BinOp::FDiv => x / y,
+        BinOp::FRem => x % y,
+        _ => panic!("Invalid operands for {:?}: floating-point values", op),
+    }
+}
+
+fn fneg(value: Value) -> Value {
+    match value {
+        Value::Single(s) => Value::Single(-s),
+        Value::Double(d) => Value::Double(-d),
+        Value::Vector(elements) => Value::Vector(elements.into_iter().map(fneg).collect()),
+        value => panic!("Invalid operand for FNeg: {}", value),
+    }
+}
+
+fn icmp(predicate: IntPredicate, a: Value, b: Value) -> Value {
+    let (x, y, sx, sy) = match (a, b) {
+        (Value::Vector(a), Value::Vector(b)) => {
+            return Value::Vector(
+                a.into_iter()
+                    .zip(b)
+                    .map(|(a, b)| icmp(predicate, a, b))
+                    .collect(),
+            )
+        },
+        (Value::Int { bits, value: x }, Value::Int { value: y, .. }) => {
+            (x, y, sext(bits, x), sext(bits, y))
+        },
+        (Value::Pointer(x), Value::Pointer(y)) => {
+            (x.into(), y.into(), (x as i64).into(), (y as i64).into())
+        },
+        (a, b) => panic!("Invalid operands for ICmp: {} and {}", a, b),
+    };
+    Value::bool(match predicate {
+        IntPredicate::EQ => x == y,
+        IntPredicate::NE => x != y,
+        IntPredicate::UGT => x > y,
+        IntPredicate::UGE => x >= y,
+        IntPredicate::ULT => x < y,
+        IntPredicate::ULE => x <= y,
+        IntPredicate::SGT => sx > sy,
+        IntPredicate::SGE => sx >= sy,
+        IntPredicate::SLT => sx < sy,
+        IntPredicate::SLE => sx <= sy,
+    })
+}
+
+fn fcmp(predicate: FPPredicate, a: Value, b: Value) -> Value {
+    if let (Value::Vector(a), Value::Vector(b)) = (&a, &b) {
+        return Value::Vector(
+            a.iter()
+                .zip(b)
+                .map(|(a, b)| fcmp(predicate, a.clone(), b.clone()))
+                .collect(),
+        );
+    }
+    let (x, y) = (as_f64(&a), as_f64(&b));
+    let unordered = x.is_nan() || y.is_nan();
+    Value::bool(match predicate {
+        FPPredicate::False => false,
+        FPPredicate::OEQ => !unordered && x == y,
+        FPPredicate::OGT => !unordered && x > y,
+        FPPredicate::OGE => !unordered && x >= y,
+        FPPredicate::OLT => !unordered && x < y,
+        FPPredicate::OLE => !unordered && x <= y,
+        FPPredicate::ONE => !unordered && x != y,
+        FPPredicate::ORD => !unordered,
+        FPPredicate::UNO => unordered,
+        FPPredicate::UEQ => unordered || x == y,
+        FPPredicate::UGT => unordered || x > y,
+        FPPredicate::UGE => unordered || x >= y,
+        FPPredicate::ULT => unordered || x < y,
+        FPPredicate::ULE => unordered || x <= y,
+        FPPredicate::UNE => unordered || x != y,
+        FPPredicate::True => true,
+    })
+}
+
+fn select(condition: Value, true_value: Value, false_value: Value) -> Value {
+    match (condition, true_value, false_value) {
+        (Value::Vector(conditions), Value::Vector(trues), Value::Vector(falses)) => Value::Vector(
+            conditions
+                .into_iter()
+                .zip(trues)
+                .zip(falses)
+                .map(|((c, t), f)| select(c, t, f))
+                .collect(),
+        ),
+        (Value::Int { value: 0, .. }, _, false_value) => false_value,
+        (Value::Int { .. }, true_value, _) => true_value,
+        (condition, _, _) => panic!("Invalid condition for Select: {}", condition),
+    }
+}
+
+fn vector_elements(vector: Value) -> Vec<Value> {
+    match vector {
+        Value::Vector(elements) => elements,
+        value => panic!("Expected a vector, got {}", value),
+    }
+}
+
+/// Index of a vector element, checked against `num_elements`
+fn element_index(index: &Value, num_elements: usize) -> Result<usize> {
+    let index = index.as_u128().expect("Expected an integer vector index");
+    if index < num_elements as u128 {
+        Ok(index as usize)
+    } else {
+        Err(InterpreterError::ElementIndexOutOfRange {
+            index,
+            num_elements,
+        })
+    }
+}
+
+fn extract_element(vector: Value, index: Value) -> Result<Value> {
+    let mut elements = vector_elements(vector);
+    let index = element_index(&index, elements.len())?;
+    Ok(elements.swap_remove(index))
+}
+
+fn insert_element(vector: Value, element: Value, index: Value) -> Result<Value> {
+    let mut elements = vector_elements(vector);
+    let index = element_index(&index, elements.len())?;
+    elements[index] = element;
+    Ok(Value::Vector(elements))
+}
+
+fn shuffle_vector(a: Value, b: Value, mask: Value) -> Result<Value> {
+    let mut elements = vector_elements(a);
+    elements.extend(vector_elements(b));
+    Ok(Value::Vector(
+        vector_elements(mask)
+            .iter()
+            .map(|index| Ok(elements[element_index(index, elements.len())?].clone()))
+            .collect::<Result<_>>()?,
+    ))
+}
+
+fn extract_value(aggregate: Value, indices: &[u32]) -> Value {
+    indices
+        .iter()
+        .fold(aggregate, |aggregate, &index| match aggregate {
+            Value::Aggregate(mut elements) => elements.swap_remove(index as usize),
+            value => panic!("Expected an aggregate, got {}", value),
+        })
+}
+
+fn insert_value(aggregate: Value, element: Value, indices: &[u32]) -> Value {
+    match indices.split_first() {
+        None => element,
+        Some((&index, rest)) => match aggregate {
+            Value::Aggregate(mut elements) => {
+                let inner = std::mem::replace(&mut elements[index as usize], Value::bool(false));
+                elements[index as usize] = insert_value(inner, element, rest);
+                Value::Aggregate(elements)
+            },
+            value => panic!("Expected an aggregate, got {}", value),
+        },
+    }
+}
+
+fn int_bits(ty: &Type) -> Result<u32> {
+    match ty {
+        Type::IntegerType { bits } => {
+            check_int_bits(*bits)?;
+            Ok(*bits)
+        },
+        ty => panic!("Expected an integer type, got {}", ty),
+    }
+}
+
+/// A floating-point value of type `ty` with the value `x`
+fn float_value(ty: &Type, x: f64) -> Result<Value> {
+    match ty {
+        Type::FPType(FPType::Single) => Ok(Value::Single(x as f32)),
+        Type::FPType(FPType::Double) => Ok(Value::Double(x)),
+        ty => Err(InterpreterError::Unsupported(format!(
+            "value of type {}",
+            ty
+        ))),
+    }
+}
+
+fn scalar_cast(op: CastOp, value: Value, to_type: &Type) -> Result<Value> {
+    Ok(match (op, value) {
+        (CastOp::Trunc, Value::Int { value, .. }) | (CastOp::ZExt, Value::Int { value, .. }) => {
+            Value::int(int_bits(to_type)?, value)
+        },
+        (CastOp::SExt, Value::Int { bits, value }) => {
+            Value::int(int_bits(to_type)?, sext(bits, value) as u128)
+        },
+        (CastOp::FPTrunc, value) | (CastOp::FPExt, value) => float_value(to_type, as_f64(&value))?,
+        (CastOp::FPToUI, value) => Value::int(int_bits(to_type)?, as_f64(&value) as u128),
+        (CastOp::FPToSI, value) => Value::int(int_bits(to_type)?, as_f64(&value) as i128 as u128),
+        (CastOp::UIToFP, Value::Int { value, .. }) => match to_type {
+            // convert directly, to avoid rounding twice
+            Type::FPType(FPType::Single) => Value::Single(value as f32),
+            _ => float_value(to_type, value as f64)?,
+        },
+        (CastOp::SIToFP, Value::Int { bits, value }) => match to_type {
+            Type::FPType(FPType::Single) => Value::Single(sext(bits, value) as f32),
+            _ => float_value(to_type, sext(bits, value) as f64)?,
+        },
+        (CastOp::PtrToInt, Value::Pointer(address)) => {
+            Value::int(int_bits(to_type)?, address.into())
+        },
+        (CastOp::IntToPtr, Value::Int { value, .. }) => Value::Pointer(value as u64),
+        (CastOp::AddrSpaceCast, value @ Value::Pointer(_)) => value,
+        (op, value) => panic!("Invalid operand for {:?}: {}", op, value),
+    })
+}
+
+fn with_overflow(op: OverflowOp, lhs: Value, rhs: Value) -> Value {
+    let (bits, x, y) = match (lhs, rhs) {
+        (Value::Int { bits, value: x }, Value::Int { value: y, .. }) => (bits, x, y),
+        (lhs, rhs) => panic!("Invalid operands for {:?}: {} and {}", op, lhs, rhs),
+    };
+    let unsigned = |result: Option<u128>, wrapped: u128| match result {
+        Some(result) => (result, result > mask(bits)),
+        None => (wrapped, true),
+    };
+    let signed = |result: Option<i128>, wrapped: i128| match result {
+        Some(result) => (result as u128, result != sext(bits, result as u128)),
+        None => (wrapped as u128, true),
+    };
+    let (sx, sy) = (sext(bits, x), sext(bits, y));
+    let (result, overflow) = match op {
+        OverflowOp::UAdd => unsigned(x.checked_add(y), x.wrapping_add(y)),
+        OverflowOp::USub => unsigned(x.checked_sub(y), x.wrapping_sub(y)),
+        OverflowOp::UMul => unsigned(x.checked_mul(y), x.wrapping_mul(y)),
+        OverflowOp::SAdd => signed(sx.checked_add(sy), sx.wrapping_add(sy)),
+        OverflowOp::SSub => signed(sx.checked_sub(sy), sx.wrapping_sub(sy)),
+        OverflowOp::SMul => signed(sx.checked_mul(sy), sx.wrapping_mul(sy)),
+    };
+    Value::Aggregate(vec![Value::int(bits, result), Value::bool(overflow)])
+}
+
+fn atomic_rmw(op: crate::instruction::RMWBinOp, old: Value, value: Value) -> Result<Value> {
+    use crate::instruction::RMWBinOp;
+    let pick = |predicate, old: Value, value: Value| {
+        if icmp(predicate, old.clone(), value.clone()) == Value::bool(true) {
+            old
+        } else {
+            value
+        }
+    };
+    Ok(match op {
+        RMWBinOp::Xchg => value,
+        RMWBinOp::Add => binop(BinOp::Add, old, value)?,
+        RMWBinOp::Sub => binop(BinOp::Sub, old, value)?,
+        RMWBinOp::And => binop(BinOp::And, old, value)?,
+        RMWBinOp::Nand => {
+            let bits = match old {
+                Value::Int { bits, .. } => bits,
+                ref old => panic!("Invalid operand for atomicrmw nand: {}", old),
+            };
+            binop(
+                BinOp::Xor,
+                binop(BinOp::And, old, value)?,
+                Value::int(bits, u128::MAX),
+            )?
+        },
+        RMWBinOp::Or => binop(BinOp::Or, old, value)?,
+        RMWBinOp::Xor => binop(BinOp::Xor, old, value)?,
+        RMWBinOp::Max => pick(IntPredicate::SGT, old, value),
+        RMWBinOp::Min => pick(IntPredicate::SLT, old, value),
+        RMWBinOp::UMax => pick(IntPredicate::UGT, old, value),
+        RMWBinOp::UMin => pick(IntPredicate::ULT, old, value),
+        RMWBinOp::FAdd => binop(BinOp::FAdd, old, value)?,
+        RMWBinOp::FSub => binop(BinOp::FSub, old, value)?,
+        RMWBinOp::FMax => {
+            if as_f64(&value) > as_f64(&old) || as_f64(&old).is_nan() {
+                value
+            } else {
+                old
+            }
+        },
+        RMWBinOp::FMin => {
+            if as_f64(&value) < as_f64(&old) || as_f64(&old).is_nan() {
+                value
+            } else {
+                old
+            }
+        },
+        RMWBinOp::UIncWrap => {
+            // old >= value ? 0 : old + 1
+            let bits = match old {
+                Value::Int { bits, .. } => bits,
+                ref old => panic!("Invalid operand for atomicrmw uinc_wrap: {}", old),
+            };
+            if icmp(IntPredicate::UGE, old.clone(), value) == Value::bool(true) {
+                Value::int(bits, 0)
+            } else {
+                binop(BinOp::Add, old, Value::int(bits, 1))?
+            }
+        },
+        RMWBinOp::UDecWrap => {
+            // (old == 0 || old > value) ? value : old - 1
+            let bits = match old {
+                Value::Int { bits, .. } => bits,
+                ref old => panic!("Invalid operand for atomicrmw udec_wrap: {}", old),
+            };
+            if old == Value::int(bits, 0)
+                || icmp(IntPredicate::UGT, old.clone(), value.clone()) == Value::bool(true)
+            {
+                value
+            } else {
+                binop(BinOp::Sub, old, Value::int(bits, 1))?
+            }
+        },
+    })
+}
@@ -0,0 +1,354 @@
+//! A typed view of calls to LLVM intrinsic functions.
+//!
+//! `llvm-ir` represents a call to an intrinsic like any other `Call`, whose
+//! `function` is a `GlobalReference` to e.g. `llvm.memcpy.p0i8.p0i8.i64`.
+//! [`Call::as_intrinsic()`](../instruction/struct.Call.html#method.as_intrinsic)
+//! recognizes the common intrinsics and gives named access to their
+//! arguments.
+
+use crate::constant::Constant;
+use crate::instruction::Call;
+use crate::operand::Operand;
+use crate::types::{Type, TypeRef};
+use either::Either;
+
+/// A call to an LLVM intrinsic function, with its arguments decoded.
+/// See [LLVM 14 docs on Intrinsic Functions](https://releases.llvm.org/14.0.0/docs/LangRef.html#intrinsic-functions)
+///
+/// For overloaded intrinsics, the overloaded types (the ones mangled into the
+/// intrinsic's name, e.g. the `i32` in `llvm.sadd.with.overflow.i32`) are
+/// given as `TypeRef`s, recovered from the call's `function_ty`.
+#[derive(PartialEq, Clone, Debug)]
+pub enum Intrinsic<'a> {
+    /// `llvm.memcpy.*`
+    MemCpy {
+        dest: &'a Operand,
+        src: &'a Operand,
+        len: &'a Operand,
+        is_volatile: bool,
+    },
+    /// `llvm.memcpy.inline.*`
+    MemCpyInline {
+        dest: &'a Operand,
+        src: &'a Operand,
+        len: &'a Operand,
+        is_volatile: bool,
+    },
+    /// `llvm.memmove.*`
+    MemMove {
+        dest: &'a Operand,
+        src: &'a Operand,
+        len: &'a Operand,
+        is_volatile: bool,
+    },
+    /// `llvm.memset.*`
+    MemSet {
+        dest: &'a Operand,
+        val: &'a Operand,
+        len: &'a Operand,
+        is_volatile: bool,
+    },
+    /// `llvm.memset.inline.*`
+    MemSetInline {
+        dest: &'a Operand,
+        val: &'a Operand,
+        len: &'a Operand,
+        is_volatile: bool,
+    },
+    /// `llvm.lifetime.start.*`
+    LifetimeStart {
+        /// Size of the object in bytes, or `None` if it is variable-sized
+        size: Option<u64>,
+        ptr: &'a Operand,
+    },
+    /// `llvm.lifetime.end.*`
+    LifetimeEnd {
+        /// Size of the object in bytes, or `None` if it is variable-sized
+        size: Option<u64>,
+        ptr: &'a Operand,
+    },
+    /// `llvm.dbg.*`. The arguments of these are all metadata, which
+    /// `llvm-ir` doesn't represent, so only the kind is given.
+    Dbg(DbgKind),
+    /// `llvm.{s,u}{add,sub,mul}.with.overflow.*`
+    WithOverflow {
+        op: OverflowOp,
+        lhs: &'a Operand,
+        rhs: &'a Operand,
+        /// The (overloaded) integer type of the operands
+        ty: TypeRef,
+    },
+    /// `llvm.experimental.constrained.*`, e.g.
+    /// `llvm.experimental.constrained.fadd.f32`
+    Constrained {
+        /// The operation, e.g. `"fadd"` or `"sqrt"`
+        op: &'a str,
+        /// The non-metadata arguments. The rounding mode and exception
+        /// behavior are metadata, which `llvm-ir` doesn't represent.
+        operands: Vec<&'a Operand>,
+        /// The (overloaded) result type
+        result_ty: TypeRef,
+    },
+    /// `llvm.assume`
+    Assume { cond: &'a Operand },
+    /// `llvm.expect.*`
+    Expect {
+        val: &'a Operand,
+        expected: &'a Operand,
+        /// The (overloaded) integer type of `val` and `expected`
+        ty: TypeRef,
+    },
+    /// `llvm.stacksave`
+    StackSave,
+    /// `llvm.stackrestore`
+    StackRestore { ptr: &'a Operand },
+    /// `llvm.va_start`
+    VaStart { list: &'a Operand },
+    /// `llvm.va_end`
+    VaEnd { list: &'a Operand },
+    /// `llvm.va_copy`
+    VaCopy { dest: &'a Operand, src: &'a Operand },
+    /// `llvm.trap`
+    Trap,
+    /// `llvm.debugtrap`
+    DebugTrap,
+    /// Any other intrinsic. `name` is the full name, including any overloaded
+    /// type suffixes.
+    Other { name: &'a str },
+}
+
+/// Which `llvm.dbg.*` intrinsic; see `Intrinsic::Dbg`
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Hash)]
+pub enum DbgKind {
+    Declare,
+    Value,
+    Addr,
+    Label,
+    Assign,
+}
+
+/// The operation of a `llvm.*.with.overflow.*` intrinsic; see
+/// `Intrinsic::WithOverflow`
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Hash)]
+pub enum OverflowOp {
+    SAdd,
+    UAdd,
+    SSub,
+    USub,
+    SMul,
+    UMul,
+}
+
+impl<'a> Intrinsic<'a> {
+    /// Get the `Intrinsic` for the given `Call`, or `None` if it isn't a
+    /// call to an intrinsic. A call to a known intrinsic whose arguments
+    /// aren't as expected (e.g., an `is_volatile` which isn't an `i1`
+    /// constant) is given as `Intrinsic::Other`.
+    pub(crate) fn from_call(call: &'a Call) -> Option<Self> {
+        let name = match &call.function {
+            Either::Right(Operand::ConstantOperand(cref)) => match cref.as_ref() {
+                Constant::GlobalReference { name, .. } if name.starts_with("llvm.") => name,
+                _ => return None,
+            },
+            _ => return None,
+        };
+        Some(Self::decode(call, name).unwrap_or(Self::Other { name }))
+    }
+
+    /// Decode a call to the intrinsic `name`, or `None` if it isn't one we
+    /// know or its arguments aren't as expected
+    fn decode(call: &'a Call, name: &'a str) -> Option<Self> {
+        let arg = |i: usize| call.arguments.get(i).map(|(op, _)| op);
+        let (param_types, result_type) = match call.function_ty.as_ref() {
+            Type::FuncType {
+                param_types,
+                result_type,
+                ..
+            } => (param_types, result_type),
+            _ => return None,
+        };
+
+        if has_base(name, "llvm.memcpy.inline") {
+            Some(Self::MemCpyInline {
+                dest: arg(0)?,
+                src: arg(1)?,
+                len: arg(2)?,
+                is_volatile: const_bool(arg(3)?)?,
+            })
+        } else if has_base(name, "llvm.memcpy") {
+            Some(Self::MemCpy {
+                dest: arg(0)?,
+                src: arg(1)?,
+                len: arg(2)?,
+                is_volatile: const_bool(arg(3)?)?,
+            })
+        } else if has_base(name, "llvm.memmove") {
+            Some(Self::MemMove {
+                dest: arg(0)?,
+                src: arg(1)?,
+                len: arg(2)?,
+                is_volatile: const_bool(arg(3)?)?,
+            })
+        } else if has_base(name, "llvm.memset.inline") {
+            Some(Self::MemSetInline {
+                dest: arg(0)?,
+                val: arg(1)?,
+                len: arg(2)?,
+                is_volatile: const_bool(arg(3)?)?,
+            })
+        } else if has_base(name, "llvm.memset") {
+            Some(Self::MemSet {
+                dest: arg(0)?,
+                val: arg(1)?,
+                len: arg(2)?,
+                is_volatile: const_bool(arg(3)?)?,
+            })
+        } else if has_base(name, "llvm.lifetime.start") {
+            Some(Self::LifetimeStart {
+                size: lifetime_size(arg(0)?)?,
+                ptr: arg(1)?,
+            })
+        } else if has_base(name, "llvm.lifetime.end") {
+            Some(Self::LifetimeEnd {
+                size: lifetime_size(arg(0)?)?,
+                ptr: arg(1)?,
+            })
+        } else if let Some(kind) = name.strip_prefix("llvm.dbg.") {
+            match kind {
+                "declare" => Some(Self::Dbg(DbgKind::Declare)),
+                "value" => Some(Self::Dbg(DbgKind::Value)),
+                "addr" => Some(Self::Dbg(DbgKind::Addr)),
+                "label" => Some(Self::Dbg(DbgKind::Label)),
+                "assign" => Some(Self::Dbg(DbgKind::Assign)),
+                _ => None,
+            }
+        } else if let Some(op) = OverflowOp::from_intrinsic_name(name) {
+            Some(Self::WithOverflow {
+                op,
+                lhs: arg(0)?,
+                rhs: arg(1)?,
+                ty: param_types.first()?.clone(),
+            })
+        } else if let Some(rest) = name.strip_prefix("llvm.experimental.constrained.") {
+            Some(Self::Constrained {
+                op: rest.split('.').next().filter(|op| !op.is_empty())?,
+                operands: call
+                    .arguments
+                    .iter()
+                    .map(|(op, _)| op)
+                    .filter(|op| !matches!(op, Operand::MetadataOperand))
+                    .collect(),
+                result_ty: result_type.clone(),
+            })
+        } else if name == "llvm.assume" {
+            Some(Self::Assume { cond: arg(0)? })
+        } else if has_base(name, "llvm.expect") {
+            Some(Self::Expect {
+                val: arg(0)?,
+                expected: arg(1)?,
+                ty: param_types.first()?.clone(),
+            })
+        } else if has_base(name, "llvm.stacksave") {
+            Some(Self::StackSave)
+        } else if has_base(name, "llvm.stackrestore") {
+            Some(Self::StackRestore { ptr: arg(0)? })
+        } else if has_base(name, "llvm.va_start") {
+            Some(Self::VaStart { list: arg(0)? })
+        } else if has_base(name, "llvm.va_end") {
+            Some(Self::VaEnd { list: arg(0)? })
+        } else if has_base(name, "llvm.va_copy") {
+            Some(Self::VaCopy {
+                dest: arg(0)?,
+                src: arg(1)?,
+            })
+        } else if name == "llvm.trap" {
+            Some(Self::Trap)
+        } else if name == "llvm.debugtrap" {
+            Some(Self::DebugTrap)
+        } else {
+            None
+        }
+    }
+}
+
+impl OverflowOp {
+    fn from_intrinsic_name(name: &str) -> Option<Self> {
+        [
+            ("llvm.sadd.with.overflow", Self::SAdd),
+            ("llvm.uadd.with.overflow", Self::UAdd),
+            ("llvm.ssub.with.overflow", Self::SSub),
+            ("llvm.usub.with.overflow", Self::USub),
+            ("llvm.smul.with.overflow", Self::SMul),
+            ("llvm.umul.with.overflow", Self::UMul),
+        ]
+        .iter()
+        .find(|(base, _)| has_base(name, base))
+        .map(|&(_, op)| op)
+    }
+}
+
+/// Is `name` the intrinsic `base`, possibly followed by overloaded type
+/// suffixes (e.g., `llvm.memcpy.p0.p0.i64` has base `llvm.memcpy`). Other
+/// intrinsics whose names merely start with `base` (e.g.,
+/// `llvm.memcpy.element.unordered.atomic.*` or `llvm.expect.with.probability.*`)
+/// don't count.
+fn has_base(name: &str, base: &str) -> bool {
+    match name.strip_prefix(base) {
+        Some("") => true,
+        Some(rest) => match rest.strip_prefix('.') {
+            Some(suffixes) => are_type_suffixes(suffixes),
+            None => false,
+        },
+        None => false,
+    }
+}
+
+/// Are these `.`-separated parts of an intrinsic's name all mangled types,
+/// e.g. `p0i8.i64` or `v4f32`
+fn are_type_suffixes(suffixes: &str) -> bool {
+    for part in suffixes.split('.') {
+        // named struct types (`s_struct.foo`) may themselves contain `.`, so
+        // anything after one is part of its name
+        if part.starts_with("s_") || part.starts_with("sl_") {
+            return true;
+        }
+        let is_type = ["bf16", "ppcf128", "x86", "isVoid", "Metadata", "f_"]
+            .iter()
+            .any(|prefix| part.starts_with(prefix))
+            || ["p", "i", "f", "v", "nxv", "a"].iter().any(|prefix| {
+                match part.strip_prefix(prefix) {
+                    Some(rest) => rest.starts_with(|c: char| c.is_ascii_digit()),
+                    None => false,
+                }
+            });
+        if !is_type {
+            return false;
+        }
+    }
+    true
+}
+
+/// Decode an `i1` immediate argument
+fn const_bool(op: &Operand) -> Option<bool> {
+    match op {
+        Operand::ConstantOperand(cref) => match cref.as_ref() {
+            Constant::Int { bits: 1, value } => Some(*value != 0),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Decode the size argument of `llvm.lifetime.start` or `llvm.lifetime.end`,
+/// which is -1 for variable-sized objects. Gives `None` if it isn't an integer
+/// constant.
+fn lifetime_size(op: &Operand) -> Option<Option<u64>> {
+    match op {
+        Operand::ConstantOperand(cref) => match cref.as_ref() {
+            Constant::Int { bits: 64, value } if *value == u64::MAX => Some(None),
+            Constant::Int { value, .. } => Some(Some(*value)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
@@ -11,6 +11,11 @@ pub fn get_declared_functions(module: LLVMModuleRef) -> impl Iterator<Item = LLV
     FunctionIterator::new(module).filter(|&f| !is_defined(f))
 }
 
+/// All functions, defined and declared, in the order they appear in the module
+pub fn get_functions(module: LLVMModuleRef) -> impl Iterator<Item = LLVMValueRef> {
+    FunctionIterator::new(module)
+}
+
 pub fn get_globals(module: LLVMModuleRef) -> impl Iterator<Item = LLVMValueRef> {
     GlobalIterator::new(module)
 }
@@ -19,6 +24,11 @@ pub fn get_global_aliases(module: LLVMModuleRef) -> impl Iterator<Item = LLVMVal
     GlobalAliasIterator::new(module)
 }
 
+#[cfg(feature = "llvm-9-or-greater")]
+pub fn get_global_ifuncs(module: LLVMModuleRef) -> impl Iterator<Item = LLVMValueRef> {
+    GlobalIFuncIterator::new(module)
+}
+
 pub fn get_parameters(func: LLVMValueRef) -> impl Iterator<Item = LLVMValueRef> {
     ParamIterator::new(func)
 }
@@ -82,6 +92,14 @@ iterator!(
     LLVMGetFirstGlobalAlias,
     LLVMGetNextGlobalAlias
 );
+#[cfg(feature = "llvm-9-or-greater")]
+iterator!(
+    GlobalIFuncIterator,
+    LLVMModuleRef,
+    LLVMValueRef,
+    LLVMGetFirstGlobalIFunc,
+    LLVMGetNextGlobalIFunc
+);
 iterator!(
     ParamIterator,
     LLVMValueRef,
@@ -0,0 +1,218 @@
+//! Lazily converting the functions of a bitcode file.
+//!
+//! `Module::from_bc_path()` converts every function body in the file up
+//! front. For a large file of which only a few functions are interesting,
+//! a `LazyModule` converts the module-level data (types, global variables,
+//! function declarations, etc) up front, but each function body only when
+//! it is first asked for. LLVM itself also reads each function body from the
+//! bitcode only at that point.
+
+use crate::constant::Constants;
+use crate::from_llvm::*;
+use crate::function::Function;
+use crate::llvm_sys::*;
+use crate::module::{Module, ModuleContext};
+use llvm_sys::bit_reader::LLVMGetBitcodeModuleInContext2;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// A `Module` whose function bodies are converted on demand.
+///
+/// Everything else in the module is converted when the `LazyModule` is
+/// created, and is available through [`module()`](LazyModule::module). The
+/// LLVM module (and its context) is kept alive until the `LazyModule` is
+/// dropped or turned into a plain `Module` with
+/// [`into_module()`](LazyModule::into_module).
+pub struct LazyModule {
+    /// The module-level data, and the functions converted so far
+    module: Module,
+    ctx: ModuleContext,
+    /// The defined functions, in the order they appear in the module
+    defined_functions: Vec<(String, LLVMValueRef)>,
+    /// Map from a function's name to its index in `module.functions`, for the
+    /// functions converted so far
+    converted: HashMap<String, usize>,
+    llvm: LLVMModule,
+}
+
+impl LazyModule {
+    /// Read the LLVM bitcode (.bc) file at the given path, converting
+    /// everything but the function bodies
+    pub fn from_bc_path(path: impl AsRef<Path>) -> Result<Self, String> {
+        use std::ffi::{CStr, CString};
+
+        let path = CString::new(
+            path.as_ref()
+                .to_str()
+                .expect("Did not find a valid Unicode path string"),
+        )
+        .expect("Failed to convert to CString");
+        debug!("Creating a LazyModule from path {:?}", path);
+
+        let memory_buffer = unsafe {
+            let mut memory_buffer = std::ptr::null_mut();
+            let mut err_string = std::mem::zeroed();
+            let return_code = LLVMCreateMemoryBufferWithContentsOfFile(
+                path.as_ptr() as *const _,
+                &mut memory_buffer,
+                &mut err_string,
+            );
+            if return_code != 0 {
+                return Err(CStr::from_ptr(err_string)
+                    .to_str()
+                    .expect("Failed to convert CStr")
+                    .to_owned());
+            }
+            memory_buffer
+        };
+
+        let context = Context::new();
+        let module = unsafe {
+            let mut module = std::ptr::null_mut();
+            // on success, the module takes ownership of the buffer, as it
+            // reads function bodies from it later; on failure, LLVM frees it
+            if LLVMGetBitcodeModuleInContext2(context.ctx, memory_buffer, &mut module) != 0 {
+                return Err("Failed to parse bitcode".to_string());
+            }
+            module
+        };
+        debug!("Lazily parsed bitcode to llvm_sys module");
+        // this takes ownership of the context, so that it's disposed of even
+        // if conversion panics
+        let llvm = LLVMModule::new(context, module);
+
+        let mut ctx = ModuleContext::new(module);
+        let converted = Module::from_llvm_ref_without_functions(module, &mut ctx);
+        let defined_functions = get_defined_functions(module)
+            .map(|f| (unsafe { get_value_name(f) }, f))
+            .collect();
+        let mut lazy = Self {
+            module: converted,
+            ctx,
+            defined_functions,
+            converted: HashMap::new(),
+            llvm,
+        };
+        lazy.update_module();
+        Ok(lazy)
+    }
+
+    /// The module-level data, along with the functions converted so far.
+    ///
+    /// `module().functions` holds only the functions which have been
+    /// converted, in the order they were converted.
+    pub fn module(&self) -> &Module {
+        &self.module
+    }
+
+    /// The names of all the functions defined in the module, whether
+    /// converted yet or not
+    pub fn function_names(&self) -> impl Iterator<Item = &str> {
+        self.defined_functions.iter().map(|(name, _)| name.as_str())
+    }
+
+    /// Get the defined function with the given name, converting it if this is
+    /// the first time it has been asked for. Returns `None` if the module
+    /// defines no such function.
+    ///
+    /// If the function's bitcode is malformed, LLVM aborts the process.
+    pub fn get_function(&mut self, name: &str) -> Option<&Function> {
+        if !self.converted.contains_key(name) {
+            let func = self
+                .defined_functions
+                .iter()
+                .find(|(func_name, _)| func_name == name)
+                .map(|&(_, func)| func)?;
+            self.convert(vec![func]);
+        }
+        let index = self.converted[name];
+        Some(&self.module.functions[index])
+    }
+
+    /// Convert all of the defined functions whose names satisfy the given
+    /// predicate, and which haven't been converted yet
+    pub fn convert_functions(&mut self, mut filter: impl FnMut(&str) -> bool) {
+        let pending: Vec<LLVMValueRef> = self
+            .defined_functions
+            .iter()
+            .filter(|(name, _)| !self.converted.contains_key(name) && filter(name))
+            .map(|&(_, func)| func)
+            .collect();
+        if !pending.is_empty() {
+            self.convert(pending);
+        }
+    }
+
+    /// Convert all of the defined functions which haven't been converted yet
+    pub fn convert_all_functions(&mut self) {
+        self.convert_functions(|_| true)
+    }
+
+    /// Get the `Module`, containing only the functions converted so far. The
+    /// LLVM module and context are disposed of.
+    pub fn into_module(self) -> Module {
+        self.module
+    }
+
+    fn convert(&mut self, funcs: Vec<LLVMValueRef>) {
+        // let go of the module's constants first, so that `ctx` can add to
+        // them without copying them
+        self.module.constants = Constants::default();
+        for &func in &funcs {
+            self.llvm.materialize(func);
+        }
+        for func in Function::from_llvm_refs(funcs, &mut self.ctx) {
+            self.converted
+                .insert(func.name.clone(), self.module.functions.len());
+            self.module.functions.push(func);
+        }
+        self.update_module();
+    }
+
+    /// Bring the parts of `module` which converting a function can add to
+    /// up to date with `ctx`
+    fn update_module(&mut self) {
+        self.module.function_attribute_groups = self.ctx.function_attribute_groups.clone();
+        self.module.comdats = self.ctx.comdats.clone();
+        self.module.types = self.ctx.types.clone().build();
+        self.module.constants = self.ctx.constants.clone().build();
+    }
+}
+
+/// Owns a lazily-read LLVM module and its context
+struct LLVMModule {
+    /// Function pass manager with no passes. Running it on a function is the
+    /// only way the LLVM C API offers to read that function's body.
+    pass_manager: LLVMPassManagerRef,
+    // disposing of the context also disposes of the module
+    _context: Context,
+}
+
+impl LLVMModule {
+    fn new(context: Context, module: LLVMModuleRef) -> Self {
+        let pass_manager = unsafe {
+            let pass_manager = LLVMCreateFunctionPassManagerForModule(module);
+            LLVMInitializeFunctionPassManager(pass_manager);
+            pass_manager
+        };
+        Self {
+            pass_manager,
+            _context: context,
+        }
+    }
+
+    fn materialize(&self, func: LLVMValueRef) {
+        unsafe {
+            LLVMRunFunctionPassManager(self.pass_manager, func);
+        }
+    }
+}
+
+impl Drop for LLVMModule {
+    fn drop(&mut self) {
+        unsafe {
+            LLVMFinalizeFunctionPassManager(self.pass_manager);
+            LLVMDisposePassManager(self.pass_manager);
+        }
+    }
+}
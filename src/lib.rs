@@ -5,32 +5,52 @@
 // sure if this is necessary or helpful anymore
 #![doc(html_root_url = "https://docs.rs/llvm-ir/0.8.2")]
 
+#[cfg(any(feature = "llvm-sys", feature = "ll-parser"))]
+mod escape;
+#[cfg(feature = "llvm-sys")]
 #[macro_use]
 mod from_llvm;
+#[cfg(feature = "llvm-sys")]
 mod iterators;
+#[cfg(feature = "llvm-sys")]
 #[rustfmt::skip]
 mod llvm_sys;
 
 pub mod basicblock;
 pub use basicblock::BasicBlock;
+#[cfg(feature = "bc-reader")]
+pub mod bc_reader;
 pub mod constant;
 pub use constant::{Constant, ConstantRef};
-#[cfg(feature = "llvm-9-or-greater")]
+pub mod dataflow;
 pub mod debugloc;
-#[cfg(feature = "llvm-9-or-greater")]
 pub use debugloc::{DebugLoc, HasDebugLoc};
 pub mod function;
 pub use function::Function;
 pub mod instruction;
 pub use instruction::Instruction;
+pub mod interpreter;
+pub use interpreter::Interpreter;
+pub mod intrinsic;
+pub use intrinsic::Intrinsic;
+#[cfg(feature = "llvm-sys")]
+pub mod lazy_module;
+#[cfg(feature = "llvm-sys")]
+pub use lazy_module::LazyModule;
+#[cfg(feature = "ll-parser")]
+pub mod ll_parser;
 // pub mod metadata;
 // pub use metadata::Metadata;
 pub mod module;
-pub use module::Module;
+pub use module::{LLVMVersion, Module};
 pub mod name;
 pub use name::Name;
 pub mod operand;
 pub use operand::Operand;
+#[cfg(feature = "llvm-sys")]
+pub mod parse_options;
+#[cfg(feature = "llvm-sys")]
+pub use parse_options::ParseOptions;
 pub mod predicates;
 pub use predicates::{FPPredicate, IntPredicate};
 pub mod terminator;
@@ -38,9 +58,14 @@ pub use terminator::Terminator;
 pub mod types;
 pub use types::{Type, TypeRef};
 
+/// The `llvm-sys` crate for the selected LLVM version, e.g. for building an
+/// `LLVMModuleRef` to pass to `Module::from_llvm_module_ref()`
+#[cfg(feature = "llvm-sys")]
+pub use crate::llvm_sys::llvm_sys as sys;
+
 macro_rules! case {
-    ($feature:expr) => {
-        if cfg!(feature = $feature) {
+    ($feature:expr, $no_llvm_sys_feature:expr) => {
+        if cfg!(any(feature = $feature, feature = $no_llvm_sys_feature)) {
             return $feature.strip_prefix("llvm-").unwrap();
         }
     };
@@ -48,12 +73,15 @@ macro_rules! case {
 
 /// Returns the LLVM version for which `llvm-ir` was configured.
 pub fn llvm_version() -> &'static str {
-    case!("llvm-8");
-    case!("llvm-9");
-    case!("llvm-10");
-    case!("llvm-11");
-    case!("llvm-12");
-    case!("llvm-13");
-    case!("llvm-14");
+    case!("llvm-8", "llvm-8-no-llvm-sys");
+    case!("llvm-9", "llvm-9-no-llvm-sys");
+    case!("llvm-10", "llvm-10-no-llvm-sys");
+    case!("llvm-11", "llvm-11-no-llvm-sys");
+    case!("llvm-12", "llvm-12-no-llvm-sys");
+    case!("llvm-13", "llvm-13-no-llvm-sys");
+    case!("llvm-14", "llvm-14-no-llvm-sys");
+    case!("llvm-15", "llvm-15-no-llvm-sys");
+    case!("llvm-16", "llvm-16-no-llvm-sys");
+    case!("llvm-17", "llvm-17-no-llvm-sys");
     unreachable!()
 }
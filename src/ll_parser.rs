@@ -0,0 +1,1531 @@
+//! A parser for LLVM's textual IR (`.ll` files), written in pure Rust.
+//!
+//! [`Module::from_ir_path()`](../module/struct.Module.html#method.from_ir_path)
+//! parses textual IR by calling into LLVM, which requires a system LLVM
+//! matching the selected LLVM version. The parser in this module instead
+//! produces the same `Module` (with the same `Types` and `Constant`s) without
+//! calling into LLVM at all, which is convenient in CI, when
+//! cross-compiling, or when targeting WASM. It is enabled with the
+//! `ll-parser` feature.
+//!
+//! The IR is interpreted as the selected LLVM version would interpret it;
+//! for instance, whether pointers are opaque follows that version's rules.
+//! A few things differ from what `Module::from_ir_path()` gives:
+//!
+//! - `AttachedMetadata::Other` holds the metadata node as written in the
+//!   source, with references to other nodes as `!N`, rather than as LLVM
+//!   prints it.
+//! - LLVM folds some constant expressions as it parses them. This parser
+//!   only folds a `bitcast` of a value to its own type, and a
+//!   `getelementptr` with all-zero indices which doesn't change the type.
+//! - LLVM gives declarations of intrinsics (e.g., `llvm.memcpy.*`) the
+//!   intrinsic's built-in attributes; this parser only gives them the
+//!   attributes written in the source.
+//! - LLVM upgrades calls to some outdated intrinsics (e.g., adding
+//!   arguments); this parser only renames intrinsics for opaque pointers.
+//! - The IR isn't verified, beyond what is needed to build the `Module`:
+//!   operands must have the types their instructions expect, and the types
+//!   of local values must match how they're used, but LLVM's verifier isn't
+//!   run (e.g., a value may be used where its definition doesn't dominate).
+//!
+//! As with LLVM, an integer literal too wide for its type is truncated; e.g.,
+//! `i8 300` is `i8 44`.
+
+mod attributes;
+mod constants;
+mod instructions;
+#[cfg(feature = "llvm-11-or-greater")]
+mod layout;
+mod lexer;
+mod metadata;
+mod types;
+
+use crate::constant::{Constant, ConstantRef, ConstantsBuilder};
+use crate::function::{
+    CallingConvention, Function, FunctionAttribute, FunctionDeclaration, GroupID, Parameter,
+    ParameterAttribute,
+};
+use crate::instruction::Instruction;
+use crate::module::*;
+use crate::name::{Name, SymbolTable};
+use crate::terminator::Terminator;
+use crate::types::{Type, TypeRef, TypesBuilder};
+use attributes::RawAttribute;
+use either::Either;
+use lexer::{Tok, Token};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+/// An error encountered while parsing textual LLVM IR
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct ParseError {
+    /// 1-based line number of the problem
+    pub line: usize,
+    /// 1-based column (in characters) of the problem
+    pub column: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.message)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+type ParseResult<T> = Result<T, ParseError>;
+
+/// Parse a `Module` from the given textual LLVM IR. The `Module`'s `name` is
+/// empty.
+pub fn parse_str(text: &str) -> Result<Module, ParseError> {
+    Parser::new(text)?.parse_module(String::new())
+}
+
+/// Parse a `Module` from the textual LLVM IR (`.ll`) file at the given path.
+/// This is a drop-in replacement for `Module::from_ir_path()`.
+///
+/// Errors are of the form `path:line:column: message`.
+pub fn parse_path(path: impl AsRef<Path>) -> Result<Module, String> {
+    let path = path
+        .as_ref()
+        .to_str()
+        .expect("Did not find a valid Unicode path string");
+    let text = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+    Parser::new(&text)
+        .and_then(|parser| parser.parse_module(path.to_owned()))
+        .map_err(|e| format!("{}:{}", path, e))
+}
+
+/// Parse the textual IR which `bc_reader` produces from bitcode. Metadata
+/// kinds which aren't built into LLVM get IDs in the order given by
+/// `custom_metadata_kinds` (the order the bitcode lists them in), rather than
+/// in the order they're first used. Whether pointers are opaque is given by
+/// `opaque_pointers`, as the text may not mention any pointer types.
+#[cfg(feature = "bc-reader")]
+pub(crate) fn parse_generated(
+    text: &str,
+    name: String,
+    custom_metadata_kinds: Vec<String>,
+    opaque_pointers: bool,
+) -> Result<Module, ParseError> {
+    let mut parser = Parser::new(text)?;
+    #[cfg(feature = "llvm-14-or-greater")]
+    {
+        parser.known_opaque_pointers = Some(opaque_pointers);
+    }
+    #[cfg(feature = "llvm-13-or-lower")]
+    let _ = opaque_pointers;
+    parser.custom_metadata_kinds = custom_metadata_kinds
+        .into_iter()
+        .filter(|kind| !metadata::FIXED_METADATA_KINDS.contains(&kind.as_str()))
+        .collect();
+    parser.parse_module(name)
+}
+
+/// The items of a module, in the order they appear in the text
+#[derive(Default)]
+struct ModuleItems {
+    source_file_name: Option<String>,
+    target_triple: Option<String>,
+    functions: Vec<Function>,
+    func_declarations: Vec<FunctionDeclaration>,
+    /// Declarations of intrinsics which LLVM renames, replacing them with
+    /// new declarations at the end of the module
+    upgraded_intrinsics: Vec<FunctionDeclaration>,
+    global_vars: Vec<GlobalVariable>,
+    global_aliases: Vec<GlobalAlias>,
+    global_ifuncs: Vec<GlobalIFunc>,
+    inline_assembly: String,
+}
+
+/// How the values (and basic blocks) local to a function are numbered
+#[derive(Default, Clone)]
+struct LocalNumbering {
+    /// For each number used in the text (e.g., `%3`), the number LLVM gives
+    /// that value. These can differ, because LLVM also numbers the result of
+    /// a void `invoke` or `callbr`.
+    numbers: Vec<usize>,
+    /// Names of the named local values and basic blocks
+    names: HashSet<String>,
+}
+
+/// Either a name or a number given to a local value in the text
+enum LocalId {
+    Name(String),
+    Number(usize),
+}
+
+/// Parsing is done in two passes over the tokens. The first pass collects
+/// information that may be used before it is defined (e.g., the types of
+/// global values, attribute groups, and the numbering of local values), and
+/// tolerates references it can't resolve yet. The second pass builds the
+/// `Module`.
+struct Parser<'a> {
+    text: &'a str,
+    tokens: Vec<Token>,
+    pos: usize,
+    first_pass: bool,
+    types: TypesBuilder,
+    constants: ConstantsBuilder,
+    data_layout: DataLayout,
+    /// Value types of functions, global variables, aliases, and ifuncs
+    global_types: HashMap<String, TypeRef>,
+    /// Whether the module uses opaque pointers
+    opaque_pointers: bool,
+    /// Whether the module uses opaque pointers, if that's known without
+    /// looking at the text
+    #[cfg(feature = "llvm-14-or-greater")]
+    known_opaque_pointers: Option<bool>,
+    /// Token positions of the bodies of named types (`%foo = type`)
+    named_types: HashMap<String, usize>,
+    /// Token positions of the bodies of numbered types (`%0 = type`)
+    numbered_types: HashMap<usize, usize>,
+    /// Numbered struct types whose bodies we're in the middle of parsing
+    numbered_types_in_progress: Vec<usize>,
+    /// The contents of each attribute group (`attributes #0 = { ... }`)
+    attribute_groups: HashMap<u32, Vec<RawAttribute>>,
+    /// Token positions of numbered metadata nodes (`!0 = ...`)
+    metadata_nodes: HashMap<usize, usize>,
+    /// Metadata attachment kinds which aren't built into LLVM, in the order
+    /// they're first used
+    custom_metadata_kinds: Vec<String>,
+    /// Selection kind of each comdat (`$foo = comdat any`)
+    comdat_kinds: HashMap<String, SelectionKind>,
+    /// The numbering of each defined function's local values, from the first
+    /// pass
+    local_numberings: Vec<LocalNumbering>,
+    /// Index (among defined functions) of the function we're parsing
+    func_index: usize,
+    /// The numbering of the current function's local values, from the first
+    /// pass (or empty during the first pass)
+    known_locals: LocalNumbering,
+    /// The numbering of the current function's local values defined so far
+    locals: LocalNumbering,
+    /// The next number LLVM would give to an unnamed local value
+    local_ctr: usize,
+    /// The return type of the function we're parsing
+    return_type: TypeRef,
+    /// The types of the current function's local values (and basic blocks,
+    /// whose type is `label`) defined so far. Only filled in on the second
+    /// pass.
+    local_types: HashMap<Name, TypeRef>,
+    /// Uses of local values (and basic blocks) in the current function: the
+    /// name, the type the use expects, and the token position of the use.
+    /// These are checked against `local_types` at the end of the function,
+    /// as a value may be used before it's defined. Only filled in on the
+    /// second pass.
+    local_uses: Vec<(Name, TypeRef, usize)>,
+    /// Interns the string names of local values and basic blocks
+    symbols: SymbolTable,
+    function_attribute_groups: Vec<FunctionAttributeGroup>,
+    function_attribute_group_ids: HashMap<Vec<FunctionAttribute>, GroupID>,
+}
+
+impl<'a> Parser<'a> {
+    fn new(text: &'a str) -> ParseResult<Self> {
+        let types = TypesBuilder::new();
+        Ok(Self {
+            text,
+            tokens: lexer::tokenize(text)?,
+            pos: 0,
+            first_pass: true,
+            return_type: types.void(),
+            types,
+            constants: ConstantsBuilder::new(),
+            data_layout: DataLayout::default(),
+            global_types: HashMap::new(),
+            opaque_pointers: false,
+            #[cfg(feature = "llvm-14-or-greater")]
+            known_opaque_pointers: None,
+            named_types: HashMap::new(),
+            numbered_types: HashMap::new(),
+            numbered_types_in_progress: Vec::new(),
+            attribute_groups: HashMap::new(),
+            metadata_nodes: HashMap::new(),
+            custom_metadata_kinds: Vec::new(),
+            comdat_kinds: HashMap::new(),
+            local_numberings: Vec::new(),
+            func_index: 0,
+            known_locals: LocalNumbering::default(),
+            locals: LocalNumbering::default(),
+            local_ctr: 0,
+            local_types: HashMap::new(),
+            local_uses: Vec::new(),
+            symbols: SymbolTable::new(),
+            function_attribute_groups: Vec::new(),
+            function_attribute_group_ids: HashMap::new(),
+        })
+    }
+
+    fn parse_module(mut self, name: String) -> ParseResult<Module> {
+        self.scan_module_properties()?;
+        self.define_types()?;
+
+        self.first_pass = true;
+        self.parse_items()?;
+
+        self.first_pass = false;
+        self.pos = 0;
+        self.func_index = 0;
+        self.function_attribute_groups.clear();
+        self.function_attribute_group_ids.clear();
+        self.constants = ConstantsBuilder::new();
+        let items = self.parse_items()?;
+
+        let mut module = Module {
+            source_file_name: items.source_file_name.unwrap_or_else(|| name.clone()),
+            name,
+            data_layout: self.data_layout,
+            target_triple: Some(items.target_triple.unwrap_or_default()),
+            functions: items.functions,
+            func_declarations: items
+                .func_declarations
+                .into_iter()
+                .chain(items.upgraded_intrinsics)
+                .collect(),
+            global_vars: items.global_vars,
+            global_aliases: items.global_aliases,
+            global_ifuncs: items.global_ifuncs,
+            function_attribute_groups: self.function_attribute_groups,
+            inline_assembly: items.inline_assembly,
+            comdats: vec![],
+            types: self.types.build(),
+            constants: self.constants.build(),
+            llvm_version: LLVMVersion::selected(),
+        };
+        renumber_function_attribute_groups(&mut module);
+        module.comdats = collect_comdats(&module);
+        Ok(module)
+    }
+
+    /// Find the properties of the module which affect how everything else is
+    /// parsed: the data layout, and whether pointers are opaque
+    fn scan_module_properties(&mut self) -> ParseResult<()> {
+        for (i, window) in self.tokens.windows(4).enumerate() {
+            if let [Token {
+                tok: Tok::Keyword(target),
+                ..
+            }, Token {
+                tok: Tok::Keyword(datalayout),
+                ..
+            }, Token {
+                tok: Tok::Equal, ..
+            }, Token {
+                tok: Tok::Str(layout),
+                ..
+            }] = window
+            {
+                if target == "target" && datalayout == "datalayout" {
+                    self.data_layout = DataLayout::parse_layout_str(layout.clone())
+                        .map_err(|e| self.error_at(i + 3, e))?;
+                }
+            }
+        }
+        #[cfg(feature = "llvm-14-or-greater")]
+        {
+            let uses_ptr = self
+                .tokens
+                .iter()
+                .any(|t| matches!(&t.tok, Tok::Keyword(kw) if kw == "ptr"));
+            // LLVM 14 only uses opaque pointers when asked to (e.g., by the
+            // IR using `ptr`); LLVM 15 uses them unless the IR uses typed
+            // pointers (and not `ptr`); LLVM 16+ always uses them
+            #[cfg(all(feature = "llvm-14-or-greater", feature = "llvm-14-or-lower"))]
+            let opaque_pointers = uses_ptr;
+            #[cfg(all(feature = "llvm-15-or-greater", feature = "llvm-15-or-lower"))]
+            let opaque_pointers = uses_ptr || !self.tokens.iter().any(|t| t.tok == Tok::Star);
+            #[cfg(feature = "llvm-16-or-greater")]
+            let opaque_pointers = {
+                let _ = uses_ptr;
+                true
+            };
+            let opaque_pointers = self.known_opaque_pointers.unwrap_or(opaque_pointers);
+            self.opaque_pointers = opaque_pointers;
+            self.types.set_opaque_pointers(opaque_pointers);
+        }
+        Ok(())
+    }
+
+    // ****** //
+    // cursor //
+    // ****** //
+
+    fn peek(&self) -> &Tok {
+        &self.tokens[self.pos].tok
+    }
+
+    /// Look `n` tokens ahead of the current one
+    fn peek_at(&self, n: usize) -> &Tok {
+        let i = std::cmp::min(self.pos + n, self.tokens.len() - 1);
+        &self.tokens[i].tok
+    }
+
+    /// Consume the current token and return it. At the end of the input, this
+    /// keeps returning `Tok::Eof`.
+    fn bump(&mut self) -> Tok {
+        let tok = self.tokens[self.pos].tok.clone();
+        if self.pos + 1 < self.tokens.len() {
+            self.pos += 1;
+        }
+        tok
+    }
+
+    fn at_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Tok::Keyword(kw) if kw == keyword)
+    }
+
+    /// Consume the current token if it's the given keyword
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        let found = self.at_keyword(keyword);
+        if found {
+            self.bump();
+        }
+        found
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> ParseResult<()> {
+        if self.eat_keyword(keyword) {
+            Ok(())
+        } else {
+            self.error(format!("expected '{}'", keyword))
+        }
+    }
+
+    /// Consume the current token if it's `tok`
+    fn eat(&mut self, tok: &Tok) -> bool {
+        let found = self.peek() == tok;
+        if found {
+            self.bump();
+        }
+        found
+    }
+
+    fn expect(&mut self, tok: &Tok) -> ParseResult<()> {
+        if self.eat(tok) {
+            Ok(())
+        } else {
+            self.error(format!("expected {}", tok.describe()))
+        }
+    }
+
+    /// An error at the current token
+    fn error<T>(&self, message: impl Into<String>) -> ParseResult<T> {
+        Err(self.error_at(self.pos, message))
+    }
+
+    /// An error at the token with the given position. Messages of the form
+    /// "expected ..." also say what was found instead.
+    fn error_at(&self, pos: usize, message: impl Into<String>) -> ParseError {
+        let token = &self.tokens[pos];
+        let mut message = message.into();
+        if message.starts_with("expected") && !message.contains(", found ") {
+            message = format!("{}, found {}", message, token.tok.describe());
+        }
+        ParseError {
+            line: token.line,
+            column: token.col,
+            message,
+        }
+    }
+
+    /// The source text from the token at `start` up to (but not including)
+    /// the current token
+    fn source_since(&self, start: usize) -> &'a str {
+        let end = self.tokens[self.pos - 1].span.1;
+        &self.text[self.tokens[start].span.0 .. end]
+    }
+
+    fn parse_uint<T: FromStr>(&mut self) -> ParseResult<T> {
+        match self.peek() {
+            Tok::Int(i) if !i.starts_with('-') => match i.parse() {
+                Ok(n) => {
+                    self.bump();
+                    Ok(n)
+                },
+                Err(_) => self.error("integer is too large"),
+            },
+            _ => self.error("expected an unsigned integer"),
+        }
+    }
+
+    fn parse_string(&mut self) -> ParseResult<String> {
+        match self.bump() {
+            Tok::Str(s) => Ok(s),
+            _ => {
+                self.pos -= 1;
+                self.error("expected a string constant")
+            },
+        }
+    }
+
+    /// Skip over a balanced group of tokens starting with `(`, `[`, `{`, or
+    /// `<`, or a single other token
+    fn skip_balanced(&mut self) -> ParseResult<()> {
+        let mut depth = 0usize;
+        loop {
+            match self.bump() {
+                Tok::LParen | Tok::LSquare | Tok::LBrace | Tok::Less => depth += 1,
+                Tok::RParen | Tok::RSquare | Tok::RBrace | Tok::Greater => {
+                    depth = depth.saturating_sub(1)
+                },
+                Tok::Eof => return self.error("unexpected end of file"),
+                _ => {},
+            }
+            if depth == 0 {
+                return Ok(());
+            }
+        }
+    }
+
+    // ************ //
+    // module items //
+    // ************ //
+
+    fn parse_items(&mut self) -> ParseResult<ModuleItems> {
+        let mut items = ModuleItems::default();
+        loop {
+            match self.peek().clone() {
+                Tok::Eof => return Ok(items),
+                Tok::Keyword(kw) => match kw.as_str() {
+                    "target" => self.parse_target(&mut items)?,
+                    "source_filename" => {
+                        self.bump();
+                        self.expect(&Tok::Equal)?;
+                        items.source_file_name = Some(self.parse_string()?);
+                    },
+                    "module" => {
+                        self.bump();
+                        self.expect_keyword("asm")?;
+                        items.inline_assembly.push_str(&self.parse_string()?);
+                        items.inline_assembly.push('\n');
+                    },
+                    "define" => {
+                        let func = self.parse_define()?;
+                        items.functions.push(func);
+                    },
+                    "declare" => {
+                        let decl = self.parse_declare()?;
+                        match self.upgraded_intrinsic_name(&decl.name) {
+                            Some(name) => items
+                                .upgraded_intrinsics
+                                .push(FunctionDeclaration { name, ..decl }),
+                            None => items.func_declarations.push(decl),
+                        }
+                    },
+                    "attributes" => self.parse_attribute_group()?,
+                    "uselistorder" | "uselistorder_bb" => {
+                        // these only affect the order of uses, which we don't
+                        // represent
+                        let line = self.tokens[self.pos].line;
+                        while self.tokens[self.pos].line == line && *self.peek() != Tok::Eof {
+                            self.bump();
+                        }
+                    },
+                    _ => return self.error("expected top-level entity"),
+                },
+                Tok::LocalVar(_) | Tok::LocalId(_) => {
+                    self.bump();
+                    self.expect(&Tok::Equal)?;
+                    self.expect_keyword("type")?;
+                    self.skip_type_def_body()?;
+                },
+                Tok::GlobalVar(name) => {
+                    self.bump();
+                    self.expect(&Tok::Equal)?;
+                    self.parse_global_value(name, &mut items)?;
+                },
+                Tok::GlobalId(_) => {
+                    return self.error("numbered global values are not supported");
+                },
+                Tok::ComdatVar(name) => {
+                    self.bump();
+                    self.expect(&Tok::Equal)?;
+                    self.expect_keyword("comdat")?;
+                    let kind = match self.bump() {
+                        Tok::Keyword(kw) => match kw.as_str() {
+                            "any" => SelectionKind::Any,
+                            "exactmatch" => SelectionKind::ExactMatch,
+                            "largest" => SelectionKind::Largest,
+                            "nodeduplicate" | "noduplicates" => SelectionKind::NoDuplicates,
+                            "samesize" => SelectionKind::SameSize,
+                            _ => {
+                                self.pos -= 1;
+                                return self.error("unknown selection kind");
+                            },
+                        },
+                        _ => {
+                            self.pos -= 1;
+                            return self.error("expected comdat selection kind");
+                        },
+                    };
+                    self.comdat_kinds.insert(name, kind);
+                },
+                Tok::MetadataVar(_) => {
+                    // named metadata, e.g. `!llvm.module.flags = !{!0, !1}`
+                    self.bump();
+                    self.expect(&Tok::Equal)?;
+                    self.expect(&Tok::Exclaim)?;
+                    if *self.peek() != Tok::LBrace {
+                        return self.error("expected '{' here");
+                    }
+                    self.skip_balanced()?;
+                },
+                Tok::SummaryId(_) => {
+                    // module summary entries, e.g. `^0 = module: (...)`, don't
+                    // affect the `Module`
+                    self.bump();
+                    self.expect(&Tok::Equal)?;
+                    match self.bump() {
+                        Tok::LabelStr(_) => {},
+                        _ => {
+                            self.pos -= 1;
+                            return self.error("expected summary entry kind");
+                        },
+                    }
+                    self.skip_balanced()?;
+                },
+                Tok::Exclaim => {
+                    self.bump();
+                    let id = self.parse_uint()?;
+                    self.expect(&Tok::Equal)?;
+                    self.metadata_nodes.insert(id, self.pos);
+                    self.skip_metadata_node()?;
+                },
+                _ => return self.error("expected top-level entity"),
+            }
+        }
+    }
+
+    fn parse_target(&mut self, items: &mut ModuleItems) -> ParseResult<()> {
+        self.bump();
+        match self.bump() {
+            Tok::Keyword(kw) if kw == "triple" => {
+                self.expect(&Tok::Equal)?;
+                items.target_triple = Some(self.parse_string()?);
+            },
+            Tok::Keyword(kw) if kw == "datalayout" => {
+                // this was already handled by `scan_module_properties()`
+                self.expect(&Tok::Equal)?;
+                self.parse_string()?;
+            },
+            _ => {
+                self.pos -= 1;
+                return self.error("unknown target property");
+            },
+        }
+        Ok(())
+    }
+
+    /// Parse a global variable, alias, or ifunc, after the `@name =`
+    fn parse_global_value(&mut self, name: String, items: &mut ModuleItems) -> ParseResult<()> {
+        let (linkage, has_linkage) = match self.parse_linkage() {
+            Some(linkage) => (linkage, true),
+            None => (Linkage::External, false),
+        };
+        self.parse_preemption_specifier();
+        let visibility = self.parse_visibility();
+        let dll_storage_class = self.parse_dll_storage_class();
+        let thread_local_mode = self.parse_thread_local_mode()?;
+        let unnamed_addr = self.parse_unnamed_addr();
+
+        if self.at_keyword("alias") || self.at_keyword("ifunc") {
+            let is_alias = self.at_keyword("alias");
+            self.bump();
+            let value_type = self.parse_type()?;
+            self.expect(&Tok::Comma)?;
+            let implied_type = ["bitcast", "getelementptr", "addrspacecast", "inttoptr"]
+                .iter()
+                .any(|kw| self.at_keyword(kw));
+            let (addr_space, target) = if implied_type {
+                // the type of a constant expression aliasee may be left out,
+                // as it's implied by the expression
+                let placeholder = self.pointer_type(value_type.clone(), 0);
+                let target = self.parse_constant(&placeholder)?;
+                (constant_addr_space(&target), target)
+            } else {
+                let (target_ty, target) = self.parse_typed_constant()?;
+                let addr_space = match target_ty.as_ref() {
+                    Type::PointerType { addr_space, .. } => *addr_space,
+                    Type::OpaquePointerType { addr_space } => *addr_space,
+                    _ => return self.error("expected a pointer type for the aliasee or resolver"),
+                };
+                (addr_space, target)
+            };
+            // an optional partition, which `GlobalAlias` doesn't represent
+            while self.eat(&Tok::Comma) {
+                if self.eat_keyword("partition") {
+                    self.parse_string()?;
+                } else {
+                    return self.error("unknown alias or ifunc property");
+                }
+            }
+            let ty = self.pointer_type(value_type.clone(), addr_space);
+            self.global_types.insert(name.clone(), value_type.clone());
+            if is_alias {
+                items.global_aliases.push(GlobalAlias {
+                    name,
+                    aliasee: target,
+                    linkage,
+                    visibility,
+                    ty,
+                    value_type,
+                    addr_space,
+                    dll_storage_class,
+                    thread_local_mode,
+                    unnamed_addr,
+                });
+            } else {
+                #[cfg(feature = "llvm-8-or-lower")]
+                {
+                    let _ = (target, ty);
+                    return self.error("ifuncs are not supported with LLVM 8");
+                }
+                #[cfg(feature = "llvm-9-or-greater")]
+                items.global_ifuncs.push(GlobalIFunc {
+                    name,
+                    linkage,
+                    visibility,
+                    ty,
+                    value_type,
+                    addr_space,
+                    resolver: target,
+                });
+            }
+            return Ok(());
+        }
+
+        let addr_space = self.parse_optional_addr_space()?;
+        let externally_initialized = self.eat_keyword("externally_initialized");
+        let is_constant = if self.eat_keyword("constant") {
+            true
+        } else if self.eat_keyword("global") {
+            false
+        } else {
+            return self.error("expected 'global' or 'constant'");
+        };
+        let value_type = self.parse_type()?;
+        self.global_types.insert(name.clone(), value_type.clone());
+        let initializer = match linkage {
+            Linkage::External | Linkage::ExternalWeak if has_linkage => None,
+            _ => Some(self.parse_constant(&value_type)?),
+        };
+
+        let mut global = GlobalVariable {
+            ty: self.pointer_type(value_type.clone(), addr_space),
+            name,
+            linkage,
+            visibility,
+            is_constant,
+            value_type,
+            addr_space,
+            dll_storage_class,
+            thread_local_mode,
+            unnamed_addr,
+            initializer,
+            section: None,
+            comdat: None,
+            alignment: 0,
+            externally_initialized,
+            partition: None,
+            attributes: vec![],
+            sanitizer_metadata: SanitizerMetadata::default(),
+            debugloc: None,
+            metadata: vec![],
+        };
+        let mut attachments = vec![];
+        while self.eat(&Tok::Comma) {
+            match self.peek().clone() {
+                Tok::MetadataVar(_) => attachments.push(self.parse_metadata_attachment()?),
+                Tok::Keyword(kw) => {
+                    self.bump();
+                    match kw.as_str() {
+                        "section" => global.section = Some(self.parse_string()?),
+                        "partition" => {
+                            let partition = self.parse_string()?;
+                            #[cfg(feature = "llvm-9-or-greater")]
+                            {
+                                global.partition = Some(partition);
+                            }
+                            #[cfg(feature = "llvm-8-or-lower")]
+                            let _ = partition;
+                        },
+                        "align" => global.alignment = self.parse_uint()?,
+                        "comdat" => {
+                            global.comdat = Some(self.parse_comdat(&global.name)?);
+                        },
+                        #[cfg(feature = "llvm-15-or-greater")]
+                        "no_sanitize_address" => global.sanitizer_metadata.no_address = true,
+                        #[cfg(feature = "llvm-15-or-greater")]
+                        "no_sanitize_hwaddress" => global.sanitizer_metadata.no_hwaddress = true,
+                        #[cfg(feature = "llvm-15-or-greater")]
+                        "sanitize_memtag" => global.sanitizer_metadata.memtag = true,
+                        #[cfg(feature = "llvm-15-or-greater")]
+                        "sanitize_address_dyninit" => global.sanitizer_metadata.is_dyn_init = true,
+                        _ => {
+                            self.pos -= 1;
+                            return self.error("unknown global variable property");
+                        },
+                    }
+                },
+                _ => return self.error("expected global variable property"),
+            }
+        }
+        // string attributes, e.g., `"bss-section"="foo"`, or attribute groups
+        let attrs = self.parse_attributes(true)?;
+        let mut attributes: Vec<(String, String)> = vec![];
+        for attr in self.resolve_attribute_groups(attrs) {
+            if let RawAttribute::Str(kind, value) = attr {
+                attributes.retain(|(k, _)| *k != kind);
+                attributes.push((kind, value));
+            }
+        }
+        attributes.sort();
+        global.attributes = attributes;
+        #[cfg(feature = "llvm-9-or-greater")]
+        {
+            global.debugloc = attachments
+                .iter()
+                .find(|(kind, _)| kind == "dbg")
+                .and_then(|(_, node)| self.global_variable_debugloc(*node));
+        }
+        global.metadata = self.metadata_attachments(attachments);
+        items.global_vars.push(global);
+        Ok(())
+    }
+
+    /// Parse `comdat` or `comdat($name)`, after the `comdat` keyword, for a
+    /// global named `global_name`
+    fn parse_comdat(&mut self, global_name: &str) -> ParseResult<Comdat> {
+        let pos = self.pos;
+        let name = if self.eat(&Tok::LParen) {
+            let name = match self.bump() {
+                Tok::ComdatVar(name) => name,
+                _ => {
+                    self.pos -= 1;
+                    return self.error("expected comdat variable");
+                },
+            };
+            self.expect(&Tok::RParen)?;
+            name
+        } else {
+            global_name.to_owned()
+        };
+        match self.comdat_kinds.get(&name) {
+            Some(kind) => Ok(Comdat {
+                name,
+                selection_kind: *kind,
+            }),
+            None if self.first_pass => Ok(Comdat {
+                name,
+                selection_kind: SelectionKind::Any,
+            }),
+            None => Err(self.error_at(pos, format!("use of undefined comdat '${}'", name))),
+        }
+    }
+
+    fn parse_linkage(&mut self) -> Option<Linkage> {
+        let linkage = match self.peek() {
+            Tok::Keyword(kw) => match kw.as_str() {
+                "private" => Linkage::Private,
+                "internal" => Linkage::Internal,
+                "weak" => Linkage::WeakAny,
+                "weak_odr" => Linkage::WeakODR,
+                "linkonce" => Linkage::LinkOnceAny,
+                "linkonce_odr" => Linkage::LinkOnceODR,
+                "available_externally" => Linkage::AvailableExternally,
+                "appending" => Linkage::Appending,
+                "common" => Linkage::Common,
+                "extern_weak" => Linkage::ExternalWeak,
+                "external" => Linkage::External,
+                _ => return None,
+            },
+            _ => return None,
+        };
+        self.bump();
+        Some(linkage)
+    }
+
+    /// `dso_local` and `dso_preemptable`, which `llvm-ir` doesn't represent
+    fn parse_preemption_specifier(&mut self) {
+        let _ = self.eat_keyword("dso_local") || self.eat_keyword("dso_preemptable");
+    }
+
+    fn parse_visibility(&mut self) -> Visibility {
+        if self.eat_keyword("hidden") {
+            Visibility::Hidden
+        } else if self.eat_keyword("protected") {
+            Visibility::Protected
+        } else {
+            self.eat_keyword("default");
+            Visibility::Default
+        }
+    }
+
+    fn parse_dll_storage_class(&mut self) -> DLLStorageClass {
+        if self.eat_keyword("dllimport") {
+            DLLStorageClass::Import
+        } else if self.eat_keyword("dllexport") {
+            DLLStorageClass::Export
+        } else {
+            DLLStorageClass::Default
+        }
+    }
+
+    fn parse_thread_local_mode(&mut self) -> ParseResult<ThreadLocalMode> {
+        if !self.eat_keyword("thread_local") {
+            return Ok(ThreadLocalMode::NotThreadLocal);
+        }
+        if !self.eat(&Tok::LParen) {
+            return Ok(ThreadLocalMode::GeneralDynamic);
+        }
+        let mode = match self.peek() {
+            Tok::Keyword(kw) => match kw.as_str() {
+                "localdynamic" => ThreadLocalMode::LocalDynamic,
+                "initialexec" => ThreadLocalMode::InitialExec,
+                "localexec" => ThreadLocalMode::LocalExec,
+                "generaldynamic" => ThreadLocalMode::GeneralDynamic,
+                _ => return self.error("expected localdynamic, initialexec or localexec"),
+            },
+            _ => return self.error("expected localdynamic, initialexec or localexec"),
+        };
+        self.bump();
+        self.expect(&Tok::RParen)?;
+        Ok(mode)
+    }
+
+    fn parse_unnamed_addr(&mut self) -> Option<UnnamedAddr> {
+        if self.eat_keyword("unnamed_addr") {
+            Some(UnnamedAddr::Global)
+        } else if self.eat_keyword("local_unnamed_addr") {
+            Some(UnnamedAddr::Local)
+        } else {
+            None
+        }
+    }
+
+    /// Parse an optional `addrspace(N)`
+    fn parse_optional_addr_space(&mut self) -> ParseResult<AddrSpace> {
+        if !self.eat_keyword("addrspace") {
+            return Ok(0);
+        }
+        self.expect(&Tok::LParen)?;
+        let addr_space = self.parse_uint()?;
+        self.expect(&Tok::RParen)?;
+        Ok(addr_space)
+    }
+
+    fn parse_calling_convention(&mut self) -> ParseResult<CallingConvention> {
+        let id = match self.peek() {
+            Tok::Keyword(kw) => match kw.as_str() {
+                "ccc" => 0,
+                "fastcc" => 8,
+                "coldcc" => 9,
+                "ghccc" => 10,
+                "webkit_jscc" => 12,
+                "anyregcc" => 13,
+                "preserve_mostcc" => 14,
+                "preserve_allcc" => 15,
+                "swiftcc" => 16,
+                "cxx_fast_tlscc" => 17,
+                "tailcc" => 18,
+                "cfguard_checkcc" => 19,
+                "swifttailcc" => 20,
+                "x86_stdcallcc" => 64,
+                "x86_fastcallcc" => 65,
+                "arm_apcscc" => 66,
+                "arm_aapcscc" => 67,
+                "arm_aapcs_vfpcc" => 68,
+                "msp430_intrcc" => 69,
+                "x86_thiscallcc" => 70,
+                "ptx_kernel" => 71,
+                "ptx_device" => 72,
+                "spir_func" => 75,
+                "spir_kernel" => 76,
+                "intel_ocl_bicc" => 77,
+                "x86_64_sysvcc" => 78,
+                "win64cc" => 79,
+                "x86_vectorcallcc" => 80,
+                "hhvmcc" => 81,
+                "hhvm_ccc" => 82,
+                "x86_intrcc" => 83,
+                "avr_intrcc" => 84,
+                "avr_signalcc" => 85,
+                "amdgpu_vs" => 87,
+                "amdgpu_gs" => 88,
+                "amdgpu_ps" => 89,
+                "amdgpu_cs" => 90,
+                "amdgpu_kernel" => 91,
+                "x86_regcallcc" => 92,
+                "amdgpu_hs" => 93,
+                "amdgpu_ls" => 95,
+                "amdgpu_es" => 96,
+                "aarch64_vector_pcs" => 97,
+                "aarch64_sve_vector_pcs" => 98,
+                "amdgpu_gfx" => 100,
+                "m68k_intrcc" => 101,
+                "cc" => {
+                    self.bump();
+                    return Ok(CallingConvention::from_u32(self.parse_uint()?));
+                },
+                // `cc11` is the same as `cc 11`
+                kw => match kw.strip_prefix("cc").and_then(|n| n.parse().ok()) {
+                    Some(id) => id,
+                    None => return Ok(CallingConvention::C),
+                },
+            },
+            _ => return Ok(CallingConvention::C),
+        };
+        self.bump();
+        Ok(CallingConvention::from_u32(id))
+    }
+
+    /// Get the pointer type to `pointee` in the given address space, which is
+    /// an opaque pointer type if the module uses opaque pointers
+    fn pointer_type(&mut self, pointee: TypeRef, addr_space: AddrSpace) -> TypeRef {
+        #[cfg(feature = "llvm-14-or-greater")]
+        {
+            if self.opaque_pointers() {
+                return self.types.opaque_pointer_in_addr_space(addr_space);
+            }
+        }
+        self.types.pointer_in_addr_space(pointee, addr_space)
+    }
+
+    // ********* //
+    // functions //
+    // ********* //
+
+    fn parse_declare(&mut self) -> ParseResult<FunctionDeclaration> {
+        self.bump();
+        let mut header = self.parse_function_header(false)?;
+        let attachments = self.parse_function_metadata()?;
+        #[cfg(feature = "llvm-9-or-greater")]
+        let debugloc = self.function_debugloc(&attachments);
+        #[cfg(feature = "llvm-8-or-lower")]
+        let debugloc = {
+            let _ = attachments;
+            None
+        };
+        let mut unnamed_params = 0;
+        Ok(FunctionDeclaration {
+            name: header.name,
+            parameters: header
+                .parameters
+                .drain(..)
+                .map(|(p, id, _)| Parameter {
+                    // declarations don't number their locals, so unnamed
+                    // parameters are just counted
+                    name: match id {
+                        Some(LocalId::Name(name)) => self.symbols.name(&name),
+                        _ => {
+                            unnamed_params += 1;
+                            Name::Number(unnamed_params - 1)
+                        },
+                    },
+                    ..p
+                })
+                .collect(),
+            is_var_arg: header.is_var_arg,
+            return_type: header.return_type,
+            function_attribute_group: header.function_attribute_group,
+            return_attributes: header.return_attributes,
+            linkage: header.linkage,
+            visibility: header.visibility,
+            dll_storage_class: header.dll_storage_class,
+            calling_convention: header.calling_convention,
+            alignment: header.alignment,
+            garbage_collector_name: header.garbage_collector_name,
+            debugloc,
+        })
+    }
+
+    fn parse_define(&mut self) -> ParseResult<Function> {
+        self.bump();
+        self.known_locals = if self.first_pass {
+            LocalNumbering::default()
+        } else {
+            self.local_numberings
+                .get(self.func_index)
+                .cloned()
+                .unwrap_or_default()
+        };
+        self.locals = LocalNumbering::default();
+        self.local_ctr = 0;
+        self.local_types.clear();
+        self.local_uses.clear();
+
+        let header = self.parse_function_header(true)?;
+        self.return_type = header.return_type.clone();
+        let attachments = self.parse_function_metadata()?;
+        let mut parameters = Vec::with_capacity(header.parameters.len());
+        for (mut param, id, pos) in header.parameters {
+            param.name = self.define_local(id, pos, "argument", &param.ty)?;
+            parameters.push(param);
+        }
+
+        self.expect(&Tok::LBrace)?;
+        let mut basic_blocks = vec![];
+        while *self.peek() != Tok::RBrace {
+            basic_blocks.push(self.parse_basic_block()?);
+        }
+        self.bump();
+        if basic_blocks.is_empty() {
+            return self.error("function body requires at least one basic block");
+        }
+        self.check_local_uses()?;
+
+        if self.first_pass {
+            self.local_numberings.push(std::mem::take(&mut self.locals));
+        }
+        self.func_index += 1;
+
+        #[cfg(feature = "llvm-9-or-greater")]
+        let debugloc = self.function_debugloc(&attachments);
+        #[cfg(feature = "llvm-8-or-lower")]
+        let debugloc = None;
+        Ok(Function {
+            name: header.name,
+            parameters,
+            is_var_arg: header.is_var_arg,
+            return_type: header.return_type,
+            basic_blocks,
+            function_attribute_group: header.function_attribute_group,
+            return_attributes: header.return_attributes,
+            linkage: header.linkage,
+            visibility: header.visibility,
+            dll_storage_class: header.dll_storage_class,
+            calling_convention: header.calling_convention,
+            section: header.section,
+            comdat: header.comdat,
+            alignment: header.alignment,
+            garbage_collector_name: header.garbage_collector_name,
+            prefix: header.prefix,
+            prologue: header.prologue,
+            personality_function: header.personality_function,
+            debugloc,
+            metadata: self.metadata_attachments(attachments),
+        })
+    }
+
+    /// Parse the header of a function definition or declaration, after the
+    /// `define` or `declare`
+    fn parse_function_header(&mut self, is_definition: bool) -> ParseResult<FunctionHeader> {
+        let linkage = self.parse_linkage().unwrap_or(Linkage::External);
+        self.parse_preemption_specifier();
+        let visibility = self.parse_visibility();
+        let dll_storage_class = self.parse_dll_storage_class();
+        let calling_convention = self.parse_calling_convention()?;
+        let return_attrs = self.parse_attributes(false)?;
+        let return_type = self.parse_type()?;
+        let name = match self.bump() {
+            Tok::GlobalVar(name) => name,
+            Tok::GlobalId(_) => {
+                self.pos -= 1;
+                return self.error("numbered global values are not supported");
+            },
+            _ => {
+                self.pos -= 1;
+                return self.error("expected function name");
+            },
+        };
+
+        // the argument list
+        self.expect(&Tok::LParen)?;
+        let mut parameters = vec![];
+        let mut is_var_arg = false;
+        while *self.peek() != Tok::RParen {
+            if !parameters.is_empty() || is_var_arg {
+                self.expect(&Tok::Comma)?;
+            }
+            if self.eat(&Tok::DotDotDot) {
+                is_var_arg = true;
+                break;
+            }
+            let ty = self.parse_type()?;
+            let attrs = self.parse_attributes(false)?;
+            let pos = self.pos;
+            let id = match self.peek().clone() {
+                Tok::LocalVar(name) => {
+                    self.bump();
+                    Some(LocalId::Name(name))
+                },
+                Tok::LocalId(n) => {
+                    self.bump();
+                    Some(LocalId::Number(n))
+                },
+                _ => None,
+            };
+            let attributes = self.param_attributes(attrs, &ty);
+            parameters.push((
+                Parameter {
+                    name: Name::Number(0), // filled in by `parse_define()`
+                    ty,
+                    attributes,
+                },
+                id,
+                pos,
+            ));
+        }
+        self.expect(&Tok::RParen)?;
+
+        let func_type = self.types.func_type(
+            return_type.clone(),
+            parameters.iter().map(|(p, _, _)| p.ty.clone()).collect(),
+            is_var_arg,
+        );
+        self.global_types.insert(name.clone(), func_type);
+
+        self.parse_unnamed_addr();
+        self.parse_optional_addr_space()?;
+        let attrs = self.parse_attributes(true)?;
+        let (function_attrs, group_alignment) = self.function_attributes(attrs)?;
+        let mut header = FunctionHeader {
+            name,
+            parameters,
+            is_var_arg,
+            return_attributes: self.param_attributes(return_attrs, &return_type),
+            return_type,
+            function_attribute_group: self.intern_function_attributes(function_attrs),
+            linkage,
+            visibility,
+            dll_storage_class,
+            calling_convention,
+            section: None,
+            comdat: None,
+            alignment: 0,
+            garbage_collector_name: None,
+            prefix: None,
+            prologue: None,
+            personality_function: None,
+        };
+        if self.eat_keyword("section") {
+            header.section = Some(self.parse_string()?);
+        }
+        if self.eat_keyword("partition") {
+            self.parse_string()?;
+        }
+        if self.eat_keyword("comdat") {
+            if !is_definition {
+                return self.error("declarations cannot be in comdats");
+            }
+            header.comdat = Some(self.parse_comdat(&header.name)?);
+        }
+        if self.eat_keyword("align") {
+            header.alignment = self.parse_uint()?;
+        }
+        if let Some(alignment) = group_alignment {
+            header.alignment = alignment;
+        }
+        if self.eat_keyword("gc") {
+            header.garbage_collector_name = Some(self.parse_string()?);
+        }
+        if self.eat_keyword("prefix") {
+            header.prefix = Some(self.parse_typed_constant()?.1);
+        }
+        if self.eat_keyword("prologue") {
+            header.prologue = Some(self.parse_typed_constant()?.1);
+        }
+        if self.eat_keyword("personality") {
+            header.personality_function = Some(self.parse_typed_constant()?.1);
+        }
+        Ok(header)
+    }
+
+    /// Parse the metadata attachments of a function, e.g. `!dbg !12`
+    fn parse_function_metadata(&mut self) -> ParseResult<Vec<(String, usize)>> {
+        let mut attachments = vec![];
+        // a named metadata definition (`!name = ...`) can directly follow a
+        // declaration
+        while matches!(self.peek(), Tok::MetadataVar(_)) && *self.peek_at(1) != Tok::Equal {
+            attachments.push(self.parse_metadata_attachment()?);
+        }
+        Ok(attachments)
+    }
+
+    fn parse_basic_block(&mut self) -> ParseResult<crate::BasicBlock> {
+        let pos = self.pos;
+        let id = match self.peek().clone() {
+            Tok::LabelStr(name) => {
+                self.bump();
+                Some(LocalId::Name(name))
+            },
+            Tok::LabelId(n) => {
+                self.bump();
+                Some(LocalId::Number(n))
+            },
+            _ => None,
+        };
+        let label = self.types.label_type();
+        let name = self.define_local(id, pos, "label", &label)?;
+        let mut instrs = vec![];
+        loop {
+            match self.parse_instruction()? {
+                Either::Left(instr) => instrs.push(instr),
+                Either::Right(term) => {
+                    return Ok(crate::BasicBlock { name, instrs, term });
+                },
+            }
+        }
+    }
+
+    /// Record the definition of a local value or basic block (a `what`) of
+    /// type `ty`, and get its `Name`. `id` is the name or number it has in the
+    /// text, if any, and `pos` is the position of the token to report errors
+    /// at.
+    fn define_local(
+        &mut self,
+        id: Option<LocalId>,
+        pos: usize,
+        what: &str,
+        ty: &TypeRef,
+    ) -> ParseResult<Name> {
+        let name = self.define_local_name(id, pos, what)?;
+        if !self.first_pass {
+            self.local_types.insert(name.clone(), ty.clone());
+        }
+        Ok(name)
+    }
+
+    fn define_local_name(
+        &mut self,
+        id: Option<LocalId>,
+        pos: usize,
+        what: &str,
+    ) -> ParseResult<Name> {
+        match id {
+            Some(LocalId::Name(name)) => {
+                if !self.locals.names.insert(name.clone()) {
+                    return Err(self.error_at(
+                        pos,
+                        format!("multiple definition of local value named '{}'", name),
+                    ));
+                }
+                Ok(self.symbols.name(&name))
+            },
+            id => {
+                let expected = self.locals.numbers.len();
+                if let Some(LocalId::Number(n)) = id {
+                    if n != expected {
+                        return Err(self.error_at(
+                            pos,
+                            format!("{} expected to be numbered '%{}'", what, expected),
+                        ));
+                    }
+                }
+                self.locals.numbers.push(self.local_ctr);
+                Ok(self.next_local_number())
+            },
+        }
+    }
+
+    /// Get the next number LLVM would give to an unnamed local value
+    fn next_local_number(&mut self) -> Name {
+        let name = Name::Number(self.local_ctr);
+        self.local_ctr += 1;
+        name
+    }
+
+    /// Parse a reference to a local value or basic block, e.g. `%foo` or `%3`
+    fn parse_local_name(&mut self) -> ParseResult<Name> {
+        let name = match self.peek().clone() {
+            Tok::LocalVar(name) => {
+                if !self.first_pass && !self.known_locals.names.contains(&name) {
+                    return self.error(format!("use of undefined value '%{}'", name));
+                }
+                self.symbols.name(&name)
+            },
+            Tok::LocalId(n) => {
+                if self.first_pass {
+                    Name::Number(n)
+                } else {
+                    match self.known_locals.numbers.get(n) {
+                        Some(&number) => Name::Number(number),
+                        None => return self.error(format!("use of undefined value '%{}'", n)),
+                    }
+                }
+            },
+            _ => return self.error("expected a local value"),
+        };
+        self.bump();
+        Ok(name)
+    }
+
+    /// Parse a use of a local value or basic block, which is expected to
+    /// have type `ty`
+    fn parse_local_use(&mut self, ty: &TypeRef) -> ParseResult<Name> {
+        let pos = self.pos;
+        let name = self.parse_local_name()?;
+        if !self.first_pass {
+            self.local_uses.push((name.clone(), ty.clone(), pos));
+        }
+        Ok(name)
+    }
+
+    /// Parse `label %foo`
+    fn parse_label(&mut self) -> ParseResult<Name> {
+        self.expect_keyword("label")?;
+        self.parse_block_name()
+    }
+
+    /// Parse a reference to a basic block, e.g. `%foo` in `label %foo`
+    fn parse_block_name(&mut self) -> ParseResult<Name> {
+        let label = self.types.label_type();
+        self.parse_local_use(&label)
+    }
+
+    /// Check that each use of a local value (or basic block) in the current
+    /// function expects the type it was defined with
+    fn check_local_uses(&mut self) -> ParseResult<()> {
+        for (name, ty, pos) in std::mem::take(&mut self.local_uses) {
+            match self.local_types.get(&name) {
+                Some(def_ty) if *def_ty != ty => {
+                    let (start, end) = self.tokens[pos].span;
+                    return Err(self.error_at(
+                        pos,
+                        format!(
+                            "'{}' defined with type '{}' but expected '{}'",
+                            &self.text[start .. end],
+                            def_ty,
+                            ty
+                        ),
+                    ));
+                },
+                _ => {},
+            }
+        }
+        Ok(())
+    }
+
+    /// Get the `GroupID` for the given set of function attributes, creating a
+    /// new group if we haven't seen this set before. Returns `None` for an
+    /// empty set.
+    fn intern_function_attributes(&mut self, attrs: Vec<FunctionAttribute>) -> Option<GroupID> {
+        if attrs.is_empty() {
+            return None;
+        }
+        if let Some(&group_id) = self.function_attribute_group_ids.get(&attrs) {
+            return Some(group_id);
+        }
+        let group_id = self.function_attribute_groups.len();
+        self.function_attribute_group_ids
+            .insert(attrs.clone(), group_id);
+        self.function_attribute_groups
+            .push(FunctionAttributeGroup { group_id, attrs });
+        Some(group_id)
+    }
+}
+
+/// The parts of a function definition or declaration before its body
+struct FunctionHeader {
+    name: String,
+    /// Each parameter, with its name or number in the text (if any) and the
+    /// position of that. The `name` of the `Parameter` isn't filled in yet.
+    parameters: Vec<(Parameter, Option<LocalId>, usize)>,
+    is_var_arg: bool,
+    return_type: TypeRef,
+    function_attribute_group: Option<GroupID>,
+    return_attributes: Vec<ParameterAttribute>,
+    linkage: Linkage,
+    visibility: Visibility,
+    dll_storage_class: DLLStorageClass,
+    calling_convention: CallingConvention,
+    section: Option<String>,
+    comdat: Option<Comdat>,
+    alignment: u32,
+    garbage_collector_name: Option<String>,
+    prefix: Option<ConstantRef>,
+    prologue: Option<ConstantRef>,
+    personality_function: Option<ConstantRef>,
+}
+
+/// LLVM numbers the function attribute groups in the order it comes across
+/// them when converting the module: for each defined function, its own
+/// attributes and then those of its call sites; then the declarations. We
+/// numbered them in the order they appear in the text, so fix that up here.
+fn renumber_function_attribute_groups(module: &mut Module) {
+    let mut new_ids: HashMap<GroupID, GroupID> = HashMap::new();
+    let mut renumber = |id: &mut Option<GroupID>| {
+        if let Some(id) = id {
+            let next = new_ids.len();
+            *id = *new_ids.entry(*id).or_insert(next);
+        }
+    };
+    for func in &mut module.functions {
+        renumber(&mut func.function_attribute_group);
+        for bb in &mut func.basic_blocks {
+            for instr in &mut bb.instrs {
+                if let Instruction::Call(call) = instr {
+                    renumber(&mut call.function_attribute_group);
+                }
+            }
+            match &mut bb.term {
+                Terminator::Invoke(invoke) => renumber(&mut invoke.function_attribute_group),
+                Terminator::CallBr(callbr) => renumber(&mut callbr.function_attribute_group),
+                _ => {},
+            }
+        }
+    }
+    for decl in &mut module.func_declarations {
+        renumber(&mut decl.function_attribute_group);
+    }
+    let mut groups = std::mem::take(&mut module.function_attribute_groups);
+    for group in &mut groups {
+        group.group_id = new_ids[&group.group_id];
+    }
+    groups.sort_by_key(|group| group.group_id);
+    module.function_attribute_groups = groups;
+}
+
+/// The address space of the pointer produced by the given constant expression
+fn constant_addr_space(constant: &Constant) -> AddrSpace {
+    let to_type = match constant {
+        Constant::BitCast(cast) => &cast.to_type,
+        Constant::AddrSpaceCast(cast) => &cast.to_type,
+        Constant::IntToPtr(cast) => &cast.to_type,
+        Constant::GetElementPtr(gep) => return constant_addr_space(&gep.address),
+        _ => return 0,
+    };
+    match to_type.as_ref() {
+        Type::PointerType { addr_space, .. } => *addr_space,
+        Type::OpaquePointerType { addr_space } => *addr_space,
+        _ => 0,
+    }
+}
+
+/// Collect the comdats used by the module's functions and global variables,
+/// with their members
+fn collect_comdats(module: &Module) -> Vec<ComdatGroup> {
+    let mut comdats: Vec<ComdatGroup> = vec![];
+    let members = module
+        .functions
+        .iter()
+        .filter_map(|f| f.comdat.as_ref().map(|c| (c, &f.name, true)))
+        .chain(
+            module
+                .global_vars
+                .iter()
+                .filter_map(|g| g.comdat.as_ref().map(|c| (c, &g.name, false))),
+        );
+    for (comdat, name, is_function) in members {
+        let idx = match comdats.iter().position(|g| g.comdat.name == comdat.name) {
+            Some(idx) => idx,
+            None => {
+                comdats.push(ComdatGroup {
+                    comdat: comdat.clone(),
+                    functions: vec![],
+                    global_vars: vec![],
+                });
+                comdats.len() - 1
+            },
+        };
+        if is_function {
+            comdats[idx].functions.push(name.clone());
+        } else {
+            comdats[idx].global_vars.push(name.clone());
+        }
+    }
+    comdats
+}
@@ -0,0 +1,621 @@
+use super::lexer::Tok;
+use super::{ParseResult, Parser};
+use crate::function::{FunctionAttribute, ParameterAttribute};
+#[cfg(feature = "llvm-12-or-greater")]
+use crate::types::Type;
+use crate::types::TypeRef;
+
+/// An attribute as written in the IR, before we know whether it applies to a
+/// function or a parameter
+#[derive(Clone, Debug)]
+pub(super) enum RawAttribute {
+    /// An enum or integer attribute, with its integer value encoded the way
+    /// LLVM encodes it (or 0 for attributes without a value)
+    Enum(String, u64),
+    /// A type attribute, e.g. `byval(i32)`. The type is `None` if it was
+    /// omitted (e.g. plain `byval`).
+    Type(String, Option<TypeRef>),
+    /// A string attribute, e.g. `"frame-pointer"="all"`, or `"foo"` (whose
+    /// value is empty)
+    Str(String, String),
+    /// A reference to an attribute group, e.g. `#0`
+    Group(u32),
+}
+
+/// The kinds of enum-style attributes, as LLVM categorizes them
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Debug)]
+enum AttrClass {
+    Enum,
+    Type,
+    Int,
+}
+
+/// For each enum-style attribute: its name in the IR, the name of its
+/// definition in LLVM's `Attributes.td` (which determines the order LLVM
+/// keeps attributes in), and its kind
+const ATTRIBUTE_DEFS: &[(&str, &str, AttrClass)] = &[
+    ("align", "Alignment", AttrClass::Int),
+    ("alignstack", "StackAlignment", AttrClass::Int),
+    ("allocalign", "AllocAlign", AttrClass::Enum),
+    ("allockind", "AllocKind", AttrClass::Int),
+    ("allocptr", "AllocatedPointer", AttrClass::Enum),
+    ("allocsize", "AllocSize", AttrClass::Int),
+    ("alwaysinline", "AlwaysInline", AttrClass::Enum),
+    ("argmemonly", "ArgMemOnly", AttrClass::Enum),
+    ("builtin", "Builtin", AttrClass::Enum),
+    ("byref", "ByRef", AttrClass::Type),
+    ("byval", "ByVal", AttrClass::Type),
+    ("cold", "Cold", AttrClass::Enum),
+    ("convergent", "Convergent", AttrClass::Enum),
+    ("dereferenceable", "Dereferenceable", AttrClass::Int),
+    (
+        "dereferenceable_or_null",
+        "DereferenceableOrNull",
+        AttrClass::Int,
+    ),
+    (
+        "disable_sanitizer_instrumentation",
+        "DisableSanitizerInstrumentation",
+        AttrClass::Enum,
+    ),
+    ("elementtype", "ElementType", AttrClass::Type),
+    ("fn_ret_thunk_extern", "FnRetThunkExtern", AttrClass::Enum),
+    ("hot", "Hot", AttrClass::Enum),
+    ("immarg", "ImmArg", AttrClass::Enum),
+    (
+        "inaccessiblemem_or_argmemonly",
+        "InaccessibleMemOrArgMemOnly",
+        AttrClass::Enum,
+    ),
+    (
+        "inaccessiblememonly",
+        "InaccessibleMemOnly",
+        AttrClass::Enum,
+    ),
+    ("inalloca", "InAlloca", AttrClass::Type),
+    ("inlinehint", "InlineHint", AttrClass::Enum),
+    ("inreg", "InReg", AttrClass::Enum),
+    ("jumptable", "JumpTable", AttrClass::Enum),
+    ("memory", "Memory", AttrClass::Int),
+    ("minsize", "MinSize", AttrClass::Enum),
+    ("mustprogress", "MustProgress", AttrClass::Enum),
+    ("naked", "Naked", AttrClass::Enum),
+    ("nest", "Nest", AttrClass::Enum),
+    ("noalias", "NoAlias", AttrClass::Enum),
+    ("nobuiltin", "NoBuiltin", AttrClass::Enum),
+    ("nocallback", "NoCallback", AttrClass::Enum),
+    ("nocapture", "NoCapture", AttrClass::Enum),
+    ("nocf_check", "NoCfCheck", AttrClass::Enum),
+    ("noduplicate", "NoDuplicate", AttrClass::Enum),
+    ("nofpclass", "NoFPClass", AttrClass::Int),
+    ("nofree", "NoFree", AttrClass::Enum),
+    ("noimplicitfloat", "NoImplicitFloat", AttrClass::Enum),
+    ("noinline", "NoInline", AttrClass::Enum),
+    ("nomerge", "NoMerge", AttrClass::Enum),
+    ("nonlazybind", "NonLazyBind", AttrClass::Enum),
+    ("nonnull", "NonNull", AttrClass::Enum),
+    ("noprofile", "NoProfile", AttrClass::Enum),
+    ("norecurse", "NoRecurse", AttrClass::Enum),
+    ("noredzone", "NoRedZone", AttrClass::Enum),
+    ("noreturn", "NoReturn", AttrClass::Enum),
+    ("nosanitize_bounds", "NoSanitizeBounds", AttrClass::Enum),
+    ("nosanitize_coverage", "NoSanitizeCoverage", AttrClass::Enum),
+    ("nosync", "NoSync", AttrClass::Enum),
+    ("noundef", "NoUndef", AttrClass::Enum),
+    ("nounwind", "NoUnwind", AttrClass::Enum),
+    (
+        "null_pointer_is_valid",
+        "NullPointerIsValid",
+        AttrClass::Enum,
+    ),
+    ("optforfuzzing", "OptForFuzzing", AttrClass::Enum),
+    ("optnone", "OptimizeNone", AttrClass::Enum),
+    ("optsize", "OptimizeForSize", AttrClass::Enum),
+    ("preallocated", "Preallocated", AttrClass::Type),
+    ("presplitcoroutine", "PresplitCoroutine", AttrClass::Enum),
+    ("readnone", "ReadNone", AttrClass::Enum),
+    ("readonly", "ReadOnly", AttrClass::Enum),
+    ("returned", "Returned", AttrClass::Enum),
+    ("returns_twice", "ReturnsTwice", AttrClass::Enum),
+    ("safestack", "SafeStack", AttrClass::Enum),
+    ("sanitize_address", "SanitizeAddress", AttrClass::Enum),
+    ("sanitize_hwaddress", "SanitizeHWAddress", AttrClass::Enum),
+    ("sanitize_memory", "SanitizeMemory", AttrClass::Enum),
+    ("sanitize_memtag", "SanitizeMemTag", AttrClass::Enum),
+    ("sanitize_thread", "SanitizeThread", AttrClass::Enum),
+    ("shadowcallstack", "ShadowCallStack", AttrClass::Enum),
+    ("signext", "SExt", AttrClass::Enum),
+    ("skipprofile", "SkipProfile", AttrClass::Enum),
+    ("speculatable", "Speculatable", AttrClass::Enum),
+    (
+        "speculative_load_hardening",
+        "SpeculativeLoadHardening",
+        AttrClass::Enum,
+    ),
+    ("sret", "StructRet", AttrClass::Type),
+    ("ssp", "StackProtect", AttrClass::Enum),
+    ("sspreq", "StackProtectReq", AttrClass::Enum),
+    ("sspstrong", "StackProtectStrong", AttrClass::Enum),
+    ("strictfp", "StrictFP", AttrClass::Enum),
+    ("swiftasync", "SwiftAsync", AttrClass::Enum),
+    ("swifterror", "SwiftError", AttrClass::Enum),
+    ("swiftself", "SwiftSelf", AttrClass::Enum),
+    ("uwtable", "UWTable", AttrClass::Enum),
+    ("vscale_range", "VScaleRange", AttrClass::Int),
+    ("willreturn", "WillReturn", AttrClass::Enum),
+    ("writeonly", "WriteOnly", AttrClass::Enum),
+    ("zeroext", "ZExt", AttrClass::Enum),
+];
+
+fn attribute_def(name: &str) -> Option<(&'static str, AttrClass)> {
+    ATTRIBUTE_DEFS
+        .iter()
+        .find(|(ir_name, _, _)| *ir_name == name)
+        .map(|&(_, def_name, class)| {
+            // a few attributes changed kind over time
+            let class = match def_name {
+                "UWTable" if cfg!(feature = "llvm-15-or-greater") => AttrClass::Int,
+                "InAlloca" if cfg!(feature = "llvm-12-or-lower") => AttrClass::Enum,
+                _ => class,
+            };
+            (def_name, class)
+        })
+}
+
+/// The position of the attribute in the order LLVM keeps attributes in: by
+/// kind (enum attributes before type attributes before integer attributes,
+/// since LLVM 13), then by the name of the definition, with all string
+/// attributes last, ordered by key and value
+fn sort_key(attr: &RawAttribute) -> (u8, &str, &str) {
+    let enum_key = |name: &'static str| -> (u8, &'static str) {
+        match attribute_def(name) {
+            Some((def_name, class)) => {
+                if cfg!(feature = "llvm-13-or-greater") {
+                    (class as u8, def_name)
+                } else {
+                    (0, def_name)
+                }
+            },
+            None => (0, ""),
+        }
+    };
+    match attr {
+        RawAttribute::Enum(name, _) | RawAttribute::Type(name, _) => {
+            let (class, def_name) = enum_key(static_name(name));
+            (class, if def_name.is_empty() { name } else { def_name }, "")
+        },
+        RawAttribute::Str(kind, value) => (3, kind, value),
+        RawAttribute::Group(_) => (4, "", ""),
+    }
+}
+
+/// The `&'static str` IR name of the enum-style attribute with the given
+/// name, or `""` if it isn't one we know about
+fn static_name(name: &str) -> &'static str {
+    ATTRIBUTE_DEFS
+        .iter()
+        .find(|(ir_name, _, _)| *ir_name == name)
+        .map_or("", |(ir_name, _, _)| ir_name)
+}
+
+/// Remove duplicate attributes (keeping the last of each kind), and sort the
+/// rest the way LLVM does
+fn dedupe_and_sort(attrs: Vec<RawAttribute>) -> Vec<RawAttribute> {
+    let mut deduped: Vec<RawAttribute> = vec![];
+    for attr in attrs {
+        deduped.retain(|other| match (other, &attr) {
+            (RawAttribute::Enum(a, _), RawAttribute::Enum(b, _))
+            | (RawAttribute::Enum(a, _), RawAttribute::Type(b, _))
+            | (RawAttribute::Type(a, _), RawAttribute::Enum(b, _))
+            | (RawAttribute::Type(a, _), RawAttribute::Type(b, _))
+            | (RawAttribute::Str(a, _), RawAttribute::Str(b, _)) => a != b,
+            _ => true,
+        });
+        deduped.push(attr);
+    }
+    deduped.sort_by(|a, b| sort_key(a).cmp(&sort_key(b)));
+    deduped
+}
+
+/// Bits of LLVM's `MemoryEffects` encoding which mean "may read and write
+/// any memory"
+#[cfg(feature = "llvm-16-or-greater")]
+const MEMORY_UNKNOWN: u64 = 0b11_11_11;
+
+impl<'a> Parser<'a> {
+    /// Is the current token the start of an attribute?
+    fn at_attribute(&self, allow_groups: bool) -> bool {
+        match self.peek() {
+            Tok::Keyword(kw) => !static_name(kw).is_empty(),
+            Tok::Str(_) => true,
+            Tok::AttrGrpId(_) => allow_groups,
+            _ => false,
+        }
+    }
+
+    /// Parse any number of attributes (of any kind). If `allow_groups`,
+    /// references to attribute groups (e.g. `#0`) are allowed too.
+    pub(super) fn parse_attributes(
+        &mut self,
+        allow_groups: bool,
+    ) -> ParseResult<Vec<RawAttribute>> {
+        let mut attrs = vec![];
+        while self.at_attribute(allow_groups) {
+            attrs.push(self.parse_attribute()?);
+        }
+        Ok(attrs)
+    }
+
+    /// Parse an attribute group definition, e.g. `attributes #0 = { nounwind }`
+    pub(super) fn parse_attribute_group(&mut self) -> ParseResult<()> {
+        self.bump();
+        let id = match self.bump() {
+            Tok::AttrGrpId(id) => id,
+            _ => {
+                self.pos -= 1;
+                return self.error("expected attribute group id");
+            },
+        };
+        self.expect(&Tok::Equal)?;
+        self.expect(&Tok::LBrace)?;
+        let mut attrs = vec![];
+        while *self.peek() != Tok::RBrace {
+            if !self.at_attribute(false) {
+                return self.error("unterminated attribute group");
+            }
+            attrs.push(self.parse_attribute()?);
+        }
+        self.bump();
+        if self.first_pass && self.attribute_groups.insert(id, attrs).is_some() {
+            return self.error(format!("attribute group #{} redefined", id));
+        }
+        Ok(())
+    }
+
+    fn parse_attribute(&mut self) -> ParseResult<RawAttribute> {
+        let name = match self.bump() {
+            Tok::AttrGrpId(id) => return Ok(RawAttribute::Group(id)),
+            Tok::Str(kind) => {
+                let value = if self.eat(&Tok::Equal) {
+                    self.parse_string()?
+                } else {
+                    String::new()
+                };
+                return Ok(RawAttribute::Str(kind, value));
+            },
+            Tok::Keyword(kw) => kw,
+            _ => {
+                self.pos -= 1;
+                return self.error("expected attribute");
+            },
+        };
+        let value = match name.as_str() {
+            "align" | "alignstack" => {
+                // `align 8`, `align(8)`, or (in attribute groups) `align=8`
+                if self.eat(&Tok::LParen) {
+                    let value = self.parse_uint()?;
+                    self.expect(&Tok::RParen)?;
+                    value
+                } else {
+                    self.eat(&Tok::Equal);
+                    self.parse_uint()?
+                }
+            },
+            "dereferenceable" | "dereferenceable_or_null" => {
+                self.expect(&Tok::LParen)?;
+                let value = self.parse_uint()?;
+                self.expect(&Tok::RParen)?;
+                value
+            },
+            "allocsize" => {
+                self.expect(&Tok::LParen)?;
+                let elt_size: u64 = self.parse_uint()?;
+                let num_elts: u64 = if self.eat(&Tok::Comma) {
+                    self.parse_uint()?
+                } else {
+                    0xFFFF_FFFF
+                };
+                self.expect(&Tok::RParen)?;
+                (elt_size << 32) | num_elts
+            },
+            "vscale_range" => {
+                self.expect(&Tok::LParen)?;
+                let min: u64 = self.parse_uint()?;
+                let max: u64 = if self.eat(&Tok::Comma) {
+                    self.parse_uint()?
+                } else {
+                    min
+                };
+                self.expect(&Tok::RParen)?;
+                (min << 32) | max
+            },
+            "uwtable" => {
+                let mut value = 2; // async
+                if self.eat(&Tok::LParen) {
+                    if self.eat_keyword("sync") {
+                        value = 1;
+                    } else {
+                        self.expect_keyword("async")?;
+                    }
+                    self.expect(&Tok::RParen)?;
+                }
+                if cfg!(feature = "llvm-15-or-greater") {
+                    value
+                } else {
+                    0
+                }
+            },
+            "allockind" => {
+                self.expect(&Tok::LParen)?;
+                let kinds = self.parse_string()?;
+                self.expect(&Tok::RParen)?;
+                let mut value = 0;
+                for kind in kinds.split(',') {
+                    value |= match kind {
+                        "alloc" => 1,
+                        "realloc" => 2,
+                        "free" => 4,
+                        "uninitialized" => 8,
+                        "zeroed" => 16,
+                        "aligned" => 32,
+                        _ => return self.error(format!("unknown allockind {:?}", kind)),
+                    };
+                }
+                value
+            },
+            "memory" => self.parse_memory_effects()?,
+            "nofpclass" => self.parse_nofpclass()?,
+            "byval" | "byref" | "sret" | "preallocated" | "inalloca" | "elementtype" => {
+                let ty = if self.eat(&Tok::LParen) {
+                    let ty = self.parse_type()?;
+                    self.expect(&Tok::RParen)?;
+                    Some(ty)
+                } else {
+                    None
+                };
+                return Ok(RawAttribute::Type(name, ty));
+            },
+            _ => 0,
+        };
+        Ok(RawAttribute::Enum(name, value))
+    }
+
+    /// Parse the arguments of a `memory` attribute, e.g.
+    /// `(read, argmem: readwrite)`, encoded the way LLVM encodes them
+    fn parse_memory_effects(&mut self) -> ParseResult<u64> {
+        self.expect(&Tok::LParen)?;
+        let mut value = 0;
+        loop {
+            let location = match self.peek().clone() {
+                Tok::LabelStr(location) => {
+                    self.bump();
+                    match location.as_str() {
+                        "argmem" => Some(0),
+                        "inaccessiblemem" => Some(2),
+                        _ => return self.error("expected memory location"),
+                    }
+                },
+                _ => None,
+            };
+            let effect = match self.peek() {
+                Tok::Keyword(kw) => match kw.as_str() {
+                    "none" => 0,
+                    "read" => 1,
+                    "write" => 2,
+                    "readwrite" => 3,
+                    _ => return self.error("expected memory effect"),
+                },
+                _ => return self.error("expected memory effect"),
+            };
+            self.bump();
+            match location {
+                Some(shift) => value = (value & !(0b11 << shift)) | (effect << shift),
+                None => value = effect | (effect << 2) | (effect << 4),
+            }
+            if !self.eat(&Tok::Comma) {
+                break;
+            }
+        }
+        self.expect(&Tok::RParen)?;
+        Ok(value)
+    }
+
+    /// Parse the arguments of a `nofpclass` attribute, e.g. `(nan pinf)`, as
+    /// a bitmask of LLVM's `FPClassTest`
+    fn parse_nofpclass(&mut self) -> ParseResult<u64> {
+        self.expect(&Tok::LParen)?;
+        let mut value = 0;
+        while *self.peek() != Tok::RParen {
+            value |= match self.peek() {
+                Tok::Int(_) => self.parse_uint()?,
+                Tok::Keyword(kw) => {
+                    let bits = match kw.as_str() {
+                        "all" => 1023,
+                        "nan" => 3,
+                        "snan" => 1,
+                        "qnan" => 2,
+                        "inf" => 516,
+                        "ninf" => 4,
+                        "pinf" => 512,
+                        "norm" => 264,
+                        "nnorm" => 8,
+                        "pnorm" => 256,
+                        "sub" => 144,
+                        "nsub" => 16,
+                        "psub" => 128,
+                        "zero" => 96,
+                        "nzero" => 32,
+                        "pzero" => 64,
+                        _ => return self.error("expected nofpclass test mask"),
+                    };
+                    self.bump();
+                    bits
+                },
+                _ => return self.error("expected nofpclass test mask"),
+            };
+        }
+        self.bump();
+        Ok(value)
+    }
+
+    /// Replace references to attribute groups with the attributes in those
+    /// groups. LLVM adds the groups' attributes after the others.
+    pub(super) fn resolve_attribute_groups(&self, attrs: Vec<RawAttribute>) -> Vec<RawAttribute> {
+        let (groups, mut attrs): (Vec<RawAttribute>, Vec<RawAttribute>) = attrs
+            .into_iter()
+            .partition(|attr| matches!(attr, RawAttribute::Group(_)));
+        for group in groups {
+            if let RawAttribute::Group(id) = group {
+                // during the first pass, groups may not be defined yet
+                if let Some(group_attrs) = self.attribute_groups.get(&id) {
+                    attrs.extend(group_attrs.iter().cloned());
+                }
+            }
+        }
+        attrs
+    }
+
+    /// Check that all the attribute groups referenced by `attrs` exist
+    fn check_attribute_groups(&self, attrs: &[RawAttribute]) -> ParseResult<()> {
+        if self.first_pass {
+            return Ok(());
+        }
+        for attr in attrs {
+            if let RawAttribute::Group(id) = attr {
+                if !self.attribute_groups.contains_key(id) {
+                    return self.error(format!("use of undefined attribute group '#{}'", id));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Get the `ParameterAttribute`s for the attributes of a parameter (or
+    /// return value) of type `ty`
+    pub(super) fn param_attributes(
+        &mut self,
+        attrs: Vec<RawAttribute>,
+        ty: &TypeRef,
+    ) -> Vec<ParameterAttribute> {
+        dedupe_and_sort(attrs)
+            .into_iter()
+            .filter_map(|attr| match attr {
+                RawAttribute::Enum(name, value) => Some(
+                    ParameterAttribute::from_enum_name(&name, value)
+                        .unwrap_or(ParameterAttribute::UnknownAttribute { kind: name, value }),
+                ),
+                RawAttribute::Type(name, attr_ty) => Some(self.type_attribute(name, attr_ty, ty)),
+                RawAttribute::Str(kind, value) => {
+                    Some(ParameterAttribute::StringAttribute { kind, value })
+                },
+                RawAttribute::Group(_) => None,
+            })
+            .collect()
+    }
+
+    #[cfg(feature = "llvm-11-or-lower")]
+    fn type_attribute(
+        &mut self,
+        name: String,
+        _attr_ty: Option<TypeRef>,
+        _param_ty: &TypeRef,
+    ) -> ParameterAttribute {
+        // these attributes don't carry their types in this LLVM version
+        ParameterAttribute::from_enum_name(&name, 0).unwrap_or(
+            ParameterAttribute::UnknownAttribute {
+                kind: name,
+                value: 0,
+            },
+        )
+    }
+
+    #[cfg(feature = "llvm-12-or-greater")]
+    fn type_attribute(
+        &mut self,
+        name: String,
+        attr_ty: Option<TypeRef>,
+        param_ty: &TypeRef,
+    ) -> ParameterAttribute {
+        if attribute_def(&name).map(|(_, class)| class) != Some(AttrClass::Type) {
+            // e.g. `inalloca` before LLVM 13
+            return ParameterAttribute::from_enum_name(&name, 0).unwrap_or(
+                ParameterAttribute::UnknownAttribute {
+                    kind: name,
+                    value: 0,
+                },
+            );
+        }
+        // without an explicit type, the attribute gets the pointee type
+        let ty = attr_ty.unwrap_or_else(|| match param_ty.as_ref() {
+            Type::PointerType { pointee_type, .. } => pointee_type.clone(),
+            _ => self.types.i8(),
+        });
+        ParameterAttribute::from_type_name(&name, ty.clone())
+            .unwrap_or(ParameterAttribute::UnknownTypeAttribute { kind: name, ty })
+    }
+
+    /// Get the `FunctionAttribute`s for the attributes of a function (or call
+    /// site), along with the function alignment given among them (if any)
+    pub(super) fn function_attributes(
+        &mut self,
+        attrs: Vec<RawAttribute>,
+    ) -> ParseResult<(Vec<FunctionAttribute>, Option<u32>)> {
+        self.check_attribute_groups(&attrs)?;
+        let mut alignment = None;
+        #[cfg_attr(feature = "llvm-15-or-lower", allow(unused_mut))]
+        let mut attrs: Vec<RawAttribute> = self
+            .resolve_attribute_groups(attrs)
+            .into_iter()
+            .filter(|attr| match attr {
+                RawAttribute::Enum(name, value) if name == "align" => {
+                    alignment = Some(*value as u32);
+                    false
+                },
+                _ => true,
+            })
+            .collect();
+        #[cfg(feature = "llvm-16-or-greater")]
+        {
+            // the legacy memory attributes are folded into `memory`
+            let mut memory = None;
+            attrs.retain(|attr| {
+                let effects = match attr {
+                    RawAttribute::Enum(name, value) => match name.as_str() {
+                        "memory" => *value,
+                        "readnone" => 0,
+                        "readonly" => 0b01_01_01,
+                        "writeonly" => 0b10_10_10,
+                        "argmemonly" => 0b00_00_11,
+                        "inaccessiblememonly" => 0b00_11_00,
+                        "inaccessiblemem_or_argmemonly" => 0b00_11_11,
+                        _ => return true,
+                    },
+                    _ => return true,
+                };
+                memory = Some(memory.unwrap_or(MEMORY_UNKNOWN) & effects);
+                false
+            });
+            if let Some(memory) = memory {
+                attrs.push(RawAttribute::Enum("memory".into(), memory));
+            }
+        }
+        let attrs = dedupe_and_sort(attrs)
+            .into_iter()
+            .filter_map(|attr| match attr {
+                RawAttribute::Enum(name, value) => Some(
+                    FunctionAttribute::from_enum_name(&name, value)
+                        .unwrap_or(FunctionAttribute::UnknownAttribute { kind: name, value }),
+                ),
+                RawAttribute::Type(name, _) => Some(FunctionAttribute::UnknownAttribute {
+                    kind: name,
+                    value: 0,
+                }),
+                RawAttribute::Str(kind, value) => {
+                    Some(FunctionAttribute::StringAttribute { kind, value })
+                },
+                RawAttribute::Group(_) => None,
+            })
+            .collect();
+        Ok((attrs, alignment))
+    }
+}
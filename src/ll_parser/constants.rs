@@ -0,0 +1,760 @@
+use super::lexer::Tok;
+use super::types::constant_index;
+use super::{ParseResult, Parser};
+use crate::constant::*;
+use crate::predicates::{FPPredicate, IntPredicate};
+use crate::types::{FPType, NamedStructDef, Type, TypeRef};
+
+impl<'a> Parser<'a> {
+    /// Parse a type followed by a constant of that type, e.g. `i32 7`
+    pub(super) fn parse_typed_constant(&mut self) -> ParseResult<(TypeRef, ConstantRef)> {
+        let ty = self.parse_type()?;
+        let constant = self.parse_constant(&ty)?;
+        Ok((ty, constant))
+    }
+
+    /// Parse a constant of the given type
+    pub(super) fn parse_constant(&mut self, ty: &TypeRef) -> ParseResult<ConstantRef> {
+        let start = self.pos;
+        let constant = match self.peek().clone() {
+            Tok::Int(i) => {
+                self.bump();
+                match ty.as_ref() {
+                    Type::IntegerType { bits } => Constant::Int {
+                        bits: *bits,
+                        value: parse_int_value(&i, *bits),
+                    },
+                    Type::FPType(fpt) => self.float_constant(*fpt, &i, start)?,
+                    _ => {
+                        return Err(self.error_at(start, "integer constant must have integer type"))
+                    },
+                }
+            },
+            Tok::Float(f) => {
+                self.bump();
+                match ty.as_ref() {
+                    Type::FPType(fpt) => self.float_constant(*fpt, &f, start)?,
+                    _ => {
+                        return Err(self.error_at(start, "floating point constant invalid for type"))
+                    },
+                }
+            },
+            Tok::CStr(bytes) => {
+                self.bump();
+                let i8_ty = self.types.i8();
+                match ty.as_ref() {
+                    Type::ArrayType {
+                        element_type,
+                        num_elements,
+                    } if *element_type == i8_ty && *num_elements == bytes.len() => {
+                        let elements = bytes
+                            .iter()
+                            .map(|&b| {
+                                self.constants.intern(Constant::Int {
+                                    bits: 8,
+                                    value: b as u64,
+                                })
+                            })
+                            .collect();
+                        canonical_array(ty, i8_ty, elements)
+                    },
+                    _ => {
+                        return Err(self.error_at(
+                            start,
+                            format!(
+                                "constant expression type mismatch: got type '[{} x i8]'",
+                                bytes.len()
+                            ),
+                        ))
+                    },
+                }
+            },
+            Tok::GlobalVar(name) => {
+                self.bump();
+                Constant::GlobalReference {
+                    ty: self.global_value_type(&name, ty, start)?,
+                    name: self.upgraded_intrinsic_name(&name).unwrap_or(name),
+                }
+            },
+            Tok::GlobalId(_) => return self.error("numbered global values are not supported"),
+            Tok::LBrace => {
+                self.bump();
+                let values = self.parse_constant_list(&Tok::RBrace)?;
+                self.struct_constant(ty, values, false, start)?
+            },
+            Tok::Less if *self.peek_at(1) == Tok::LBrace => {
+                self.bump();
+                self.bump();
+                let values = self.parse_constant_list(&Tok::RBrace)?;
+                self.expect(&Tok::Greater)?;
+                self.struct_constant(ty, values, true, start)?
+            },
+            Tok::LSquare => {
+                self.bump();
+                let elements = self.parse_constant_list(&Tok::RSquare)?;
+                match ty.as_ref() {
+                    Type::ArrayType {
+                        element_type,
+                        num_elements,
+                    } if *num_elements == elements.len() => {
+                        canonical_array(ty, element_type.clone(), elements)
+                    },
+                    _ => return Err(self.error_at(start, "invalid type for array constant")),
+                }
+            },
+            Tok::Less => {
+                self.bump();
+                let elements = self.parse_constant_list(&Tok::Greater)?;
+                match ty.as_ref() {
+                    Type::VectorType { num_elements, .. } if *num_elements == elements.len() => {
+                        canonical_vector(ty, elements)
+                    },
+                    _ => return Err(self.error_at(start, "invalid type for vector constant")),
+                }
+            },
+            Tok::Keyword(kw) => {
+                self.bump();
+                match kw.as_str() {
+                    "true" | "false" => match ty.as_ref() {
+                        Type::IntegerType { bits: 1 } => Constant::Int {
+                            bits: 1,
+                            value: (kw == "true") as u64,
+                        },
+                        _ => return Err(self.error_at(start, "boolean constant must have i1 type")),
+                    },
+                    "null" => match ty.as_ref() {
+                        Type::PointerType { .. } => Constant::Null(ty.clone()),
+                        #[cfg(feature = "llvm-14-or-greater")]
+                        Type::OpaquePointerType { .. } => Constant::Null(ty.clone()),
+                        _ => return Err(self.error_at(start, "null must be a pointer type")),
+                    },
+                    "none" => Constant::TokenNone,
+                    "undef" => Constant::Undef(ty.clone()),
+                    #[cfg(feature = "llvm-12-or-greater")]
+                    "poison" => Constant::Poison(ty.clone()),
+                    "zeroinitializer" => self.null_value(ty),
+                    "blockaddress" => {
+                        // `blockaddress(@function, %block)`
+                        self.expect(&Tok::LParen)?;
+                        match self.bump() {
+                            Tok::GlobalVar(_) => {},
+                            _ => return Err(self.error_at(self.pos - 1, "expected function name")),
+                        }
+                        self.expect(&Tok::Comma)?;
+                        match self.bump() {
+                            Tok::LocalVar(_) | Tok::LocalId(_) => {},
+                            _ => {
+                                return Err(self.error_at(self.pos - 1, "expected basic block name"))
+                            },
+                        }
+                        self.expect(&Tok::RParen)?;
+                        Constant::BlockAddress
+                    },
+                    _ => {
+                        self.pos = start;
+                        self.parse_constant_expr(ty)?
+                    },
+                }
+            },
+            _ => return self.error("expected constant"),
+        };
+        Ok(self.constants.intern(constant))
+    }
+
+    /// Parse a comma-separated list of typed constants, up to and including
+    /// `close`
+    fn parse_constant_list(&mut self, close: &Tok) -> ParseResult<Vec<ConstantRef>> {
+        let mut constants = vec![];
+        while self.peek() != close {
+            if !constants.is_empty() {
+                self.expect(&Tok::Comma)?;
+            }
+            constants.push(self.parse_typed_constant()?.1);
+        }
+        self.bump();
+        Ok(constants)
+    }
+
+    /// Get the value type of the global value with the given name, which is
+    /// being used as a constant of type `ty`
+    pub(super) fn global_value_type(
+        &self,
+        name: &str,
+        ty: &TypeRef,
+        pos: usize,
+    ) -> ParseResult<TypeRef> {
+        match self.global_types.get(name) {
+            Some(value_type) => Ok(value_type.clone()),
+            // during the first pass, the global may not have been defined yet
+            None if self.first_pass => Ok(ty.clone()),
+            None => Err(self.error_at(pos, format!("use of undefined value '@{}'", name))),
+        }
+    }
+
+    fn float_constant(&self, fpt: FPType, text: &str, pos: usize) -> ParseResult<Constant> {
+        let bad_value = || self.error_at(pos, "floating point constant invalid for type");
+        let value = if let Some(hex) = text.strip_prefix("0x") {
+            match hex.chars().next() {
+                // half, bfloat, x86_fp80, fp128, and ppc_fp128 constants
+                Some('H') | Some('R') | Some('K') | Some('L') | Some('M') => None,
+                _ => Some(f64::from_bits(
+                    u64::from_str_radix(hex, 16).map_err(|_| bad_value())?,
+                )),
+            }
+        } else {
+            Some(text.parse::<f64>().map_err(|_| bad_value())?)
+        };
+        Ok(Constant::Float(match fpt {
+            FPType::Half => Float::Half,
+            FPType::BFloat => Float::BFloat,
+            FPType::Single => Float::Single(value.ok_or_else(bad_value)? as f32),
+            FPType::Double => Float::Double(value.ok_or_else(bad_value)?),
+            FPType::FP128 => Float::Quadruple,
+            FPType::X86_FP80 => Float::X86_FP80,
+            FPType::PPC_FP128 => Float::PPC_FP128,
+        }))
+    }
+
+    fn struct_constant(
+        &self,
+        ty: &TypeRef,
+        values: Vec<ConstantRef>,
+        is_packed: bool,
+        pos: usize,
+    ) -> ParseResult<Constant> {
+        let (name, def) = match ty.as_ref() {
+            Type::NamedStructType { name } => match self.types.named_struct_def(name) {
+                NamedStructDef::Defined(def) => (Some(name.clone()), def.clone()),
+                NamedStructDef::Opaque => {
+                    return Err(self.error_at(pos, "invalid type for struct constant"))
+                },
+            },
+            _ => (None, ty.clone()),
+        };
+        match def.as_ref() {
+            Type::StructType {
+                element_types,
+                is_packed: def_packed,
+            } if element_types.len() == values.len() && *def_packed == is_packed => {},
+            _ => return Err(self.error_at(pos, "invalid type for struct constant")),
+        }
+        Ok(canonical_struct(ty, name, values, is_packed))
+    }
+
+    /// The null value of the given type, as given by `zeroinitializer`
+    pub(super) fn null_value(&self, ty: &TypeRef) -> Constant {
+        match ty.as_ref() {
+            Type::IntegerType { bits } => Constant::Int {
+                bits: *bits,
+                value: 0,
+            },
+            Type::FPType(fpt) => Constant::Float(match fpt {
+                FPType::Half => Float::Half,
+                FPType::BFloat => Float::BFloat,
+                FPType::Single => Float::Single(0.0),
+                FPType::Double => Float::Double(0.0),
+                FPType::FP128 => Float::Quadruple,
+                FPType::X86_FP80 => Float::X86_FP80,
+                FPType::PPC_FP128 => Float::PPC_FP128,
+            }),
+            Type::PointerType { .. } => Constant::Null(ty.clone()),
+            #[cfg(feature = "llvm-14-or-greater")]
+            Type::OpaquePointerType { .. } => Constant::Null(ty.clone()),
+            Type::TokenType => Constant::TokenNone,
+            _ => Constant::AggregateZero(ty.clone()),
+        }
+    }
+
+    /// Parse a constant expression, e.g. `getelementptr (i8, i8* @x, i64 1)`
+    fn parse_constant_expr(&mut self, ty: &TypeRef) -> ParseResult<Constant> {
+        let start = self.pos;
+        let opcode = match self.bump() {
+            Tok::Keyword(kw) => kw,
+            _ => unreachable!("parse_constant_expr should only be called at a keyword"),
+        };
+        let expr = match opcode.as_str() {
+            "add" | "sub" | "mul" | "shl" => {
+                let mut nuw = false;
+                let mut nsw = false;
+                loop {
+                    if self.eat_keyword("nuw") {
+                        nuw = true;
+                    } else if self.eat_keyword("nsw") {
+                        nsw = true;
+                    } else {
+                        break;
+                    }
+                }
+                let (operand0, operand1) = self.parse_constant_pair()?;
+                match opcode.as_str() {
+                    "add" => Constant::Add(Add {
+                        operand0,
+                        operand1,
+                        nsw,
+                        nuw,
+                    }),
+                    "sub" => Constant::Sub(Sub {
+                        operand0,
+                        operand1,
+                        nsw,
+                        nuw,
+                    }),
+                    "mul" => Constant::Mul(Mul {
+                        operand0,
+                        operand1,
+                        nsw,
+                        nuw,
+                    }),
+                    _ => Constant::Shl(Shl {
+                        operand0,
+                        operand1,
+                        nsw,
+                        nuw,
+                    }),
+                }
+            },
+            "lshr" | "ashr" => {
+                let exact = self.eat_keyword("exact");
+                let (operand0, operand1) = self.parse_constant_pair()?;
+                if opcode == "lshr" {
+                    Constant::LShr(LShr {
+                        operand0,
+                        operand1,
+                        exact,
+                    })
+                } else {
+                    Constant::AShr(AShr {
+                        operand0,
+                        operand1,
+                        exact,
+                    })
+                }
+            },
+            #[cfg(feature = "llvm-14-or-lower")]
+            "udiv" | "sdiv" => {
+                let exact = self.eat_keyword("exact");
+                let (operand0, operand1) = self.parse_constant_pair()?;
+                if opcode == "udiv" {
+                    Constant::UDiv(UDiv {
+                        operand0,
+                        operand1,
+                        exact,
+                    })
+                } else {
+                    Constant::SDiv(SDiv {
+                        operand0,
+                        operand1,
+                        exact,
+                    })
+                }
+            },
+            "and" | "or" | "xor" => {
+                let (operand0, operand1) = self.parse_constant_pair()?;
+                match opcode.as_str() {
+                    "and" => Constant::And(And { operand0, operand1 }),
+                    "or" => Constant::Or(Or { operand0, operand1 }),
+                    _ => Constant::Xor(Xor { operand0, operand1 }),
+                }
+            },
+            #[cfg(feature = "llvm-14-or-lower")]
+            "urem" | "srem" | "fadd" | "fsub" | "fmul" | "fdiv" | "frem" => {
+                let (operand0, operand1) = self.parse_constant_pair()?;
+                match opcode.as_str() {
+                    "urem" => Constant::URem(URem { operand0, operand1 }),
+                    "srem" => Constant::SRem(SRem { operand0, operand1 }),
+                    "fadd" => Constant::FAdd(FAdd { operand0, operand1 }),
+                    "fsub" => Constant::FSub(FSub { operand0, operand1 }),
+                    "fmul" => Constant::FMul(FMul { operand0, operand1 }),
+                    "fdiv" => Constant::FDiv(FDiv { operand0, operand1 }),
+                    _ => Constant::FRem(FRem { operand0, operand1 }),
+                }
+            },
+            "trunc" | "zext" | "sext" | "fptrunc" | "fpext" | "fptoui" | "fptosi" | "uitofp"
+            | "sitofp" | "ptrtoint" | "inttoptr" | "bitcast" | "addrspacecast" => {
+                self.expect(&Tok::LParen)?;
+                let (from_type, operand) = self.parse_typed_constant()?;
+                self.expect_keyword("to")?;
+                let to_type = self.parse_type()?;
+                self.expect(&Tok::RParen)?;
+                if opcode == "bitcast" && from_type == to_type {
+                    // LLVM folds a bitcast to the same type, which is common
+                    // when typed pointers are read as opaque pointers
+                    return Ok(operand.as_ref().clone());
+                }
+                match opcode.as_str() {
+                    "trunc" => Constant::Trunc(Trunc { operand, to_type }),
+                    "zext" => Constant::ZExt(ZExt { operand, to_type }),
+                    "sext" => Constant::SExt(SExt { operand, to_type }),
+                    "fptrunc" => Constant::FPTrunc(FPTrunc { operand, to_type }),
+                    "fpext" => Constant::FPExt(FPExt { operand, to_type }),
+                    "fptoui" => Constant::FPToUI(FPToUI { operand, to_type }),
+                    "fptosi" => Constant::FPToSI(FPToSI { operand, to_type }),
+                    "uitofp" => Constant::UIToFP(UIToFP { operand, to_type }),
+                    "sitofp" => Constant::SIToFP(SIToFP { operand, to_type }),
+                    "ptrtoint" => Constant::PtrToInt(PtrToInt { operand, to_type }),
+                    "inttoptr" => Constant::IntToPtr(IntToPtr { operand, to_type }),
+                    "bitcast" => Constant::BitCast(BitCast { operand, to_type }),
+                    _ => Constant::AddrSpaceCast(AddrSpaceCast { operand, to_type }),
+                }
+            },
+            "getelementptr" => {
+                let in_bounds = self.eat_keyword("inbounds");
+                self.expect(&Tok::LParen)?;
+                let source_element_type = self.parse_type()?;
+                self.expect(&Tok::Comma)?;
+                let (address_ty, address) = self.parse_typed_constant()?;
+                let mut index_types = vec![];
+                let mut indices = vec![];
+                let mut in_range = false;
+                while self.eat(&Tok::Comma) {
+                    in_range |= self.eat_keyword("inrange");
+                    let (index_ty, index) = self.parse_typed_constant()?;
+                    index_types.push(index_ty);
+                    indices.push(index);
+                }
+                self.expect(&Tok::RParen)?;
+                self.gep_result_type(
+                    &source_element_type,
+                    &address_ty,
+                    &index_types,
+                    indices.iter().map(|index| constant_index(index)),
+                    start,
+                )?;
+                // LLVM folds a GEP with all-zero indices to its address, as
+                // long as that doesn't change the type: with typed pointers,
+                // only a GEP with a single index
+                #[cfg(feature = "llvm-15-or-greater")]
+                let same_type = indices.len() == 1 || self.opaque_pointers();
+                #[cfg(feature = "llvm-14-or-lower")]
+                let same_type = indices.len() == 1;
+                let zero_indices = indices
+                    .iter()
+                    .all(|i| matches!(i.as_ref(), Constant::Int { value: 0, .. }));
+                if same_type && zero_indices && !indices.is_empty() && !in_range {
+                    return Ok(address.as_ref().clone());
+                }
+                Constant::GetElementPtr(GetElementPtr {
+                    address,
+                    indices,
+                    in_bounds,
+                    source_element_type,
+                })
+            },
+            "extractelement" => {
+                self.expect(&Tok::LParen)?;
+                let (vector_ty, vector) = self.parse_typed_constant()?;
+                self.expect(&Tok::Comma)?;
+                let (index_ty, index) = self.parse_typed_constant()?;
+                self.expect(&Tok::RParen)?;
+                match (vector_ty.as_ref(), index_ty.as_ref()) {
+                    (Type::VectorType { .. }, Type::IntegerType { .. }) => {},
+                    _ => return Err(self.error_at(start, "invalid extractelement operands")),
+                }
+                Constant::ExtractElement(ExtractElement { vector, index })
+            },
+            "insertelement" => {
+                self.expect(&Tok::LParen)?;
+                let (vector_ty, vector) = self.parse_typed_constant()?;
+                self.expect(&Tok::Comma)?;
+                let (element_ty, element) = self.parse_typed_constant()?;
+                self.expect(&Tok::Comma)?;
+                let (index_ty, index) = self.parse_typed_constant()?;
+                self.expect(&Tok::RParen)?;
+                match (vector_ty.as_ref(), index_ty.as_ref()) {
+                    (Type::VectorType { element_type, .. }, Type::IntegerType { .. })
+                        if *element_type == element_ty => {},
+                    _ => return Err(self.error_at(start, "invalid insertelement operands")),
+                }
+                Constant::InsertElement(InsertElement {
+                    vector,
+                    element,
+                    index,
+                })
+            },
+            "shufflevector" => {
+                self.expect(&Tok::LParen)?;
+                let (ty, operand0) = self.parse_typed_constant()?;
+                self.expect(&Tok::Comma)?;
+                let operand1 = self.parse_typed_constant()?.1;
+                self.expect(&Tok::Comma)?;
+                let (mask_ty, mask) = self.parse_typed_constant()?;
+                self.expect(&Tok::RParen)?;
+                if self.shuffle_result_type(&ty, &mask_ty).is_none() {
+                    return Err(self.error_at(start, "invalid shufflevector operands"));
+                }
+                Constant::ShuffleVector(ShuffleVector {
+                    operand0,
+                    operand1,
+                    mask: self.shuffle_mask(&mask_ty, mask),
+                })
+            },
+            #[cfg(feature = "llvm-14-or-lower")]
+            "extractvalue" | "insertvalue" => {
+                self.expect(&Tok::LParen)?;
+                let (aggregate_ty, aggregate) = self.parse_typed_constant()?;
+                let element = if opcode == "insertvalue" {
+                    self.expect(&Tok::Comma)?;
+                    Some(self.parse_typed_constant()?)
+                } else {
+                    None
+                };
+                let mut indices = vec![];
+                while self.eat(&Tok::Comma) {
+                    indices.push(self.parse_uint()?);
+                }
+                self.expect(&Tok::RParen)?;
+                let field_ty = match self.aggregate_element_type(&aggregate_ty, &indices) {
+                    Some(field_ty) if !indices.is_empty() => field_ty,
+                    _ => {
+                        return Err(self.error_at(start, format!("invalid indices for {}", opcode)))
+                    },
+                };
+                let element = match element {
+                    Some((element_ty, _)) if element_ty != field_ty => {
+                        let msg = format!(
+                            "insertvalue operand and field disagree in type: '{}' instead of '{}'",
+                            element_ty, field_ty
+                        );
+                        return Err(self.error_at(start, msg));
+                    },
+                    element => element.map(|(_, element)| element),
+                };
+                match element {
+                    Some(element) => Constant::InsertValue(InsertValue {
+                        aggregate,
+                        element,
+                        indices,
+                    }),
+                    None => Constant::ExtractValue(ExtractValue { aggregate, indices }),
+                }
+            },
+            "icmp" => {
+                let predicate = self.parse_int_predicate()?;
+                let (operand0, operand1) = self.parse_constant_pair()?;
+                Constant::ICmp(ICmp {
+                    predicate,
+                    operand0,
+                    operand1,
+                })
+            },
+            "fcmp" => {
+                let predicate = self.parse_fp_predicate()?;
+                let (operand0, operand1) = self.parse_constant_pair()?;
+                Constant::FCmp(FCmp {
+                    predicate,
+                    operand0,
+                    operand1,
+                })
+            },
+            #[cfg(feature = "llvm-16-or-lower")]
+            "select" => {
+                self.expect(&Tok::LParen)?;
+                let condition = self.parse_typed_constant()?.1;
+                self.expect(&Tok::Comma)?;
+                let true_value = self.parse_typed_constant()?.1;
+                self.expect(&Tok::Comma)?;
+                let false_value = self.parse_typed_constant()?.1;
+                self.expect(&Tok::RParen)?;
+                Constant::Select(Select {
+                    condition,
+                    true_value,
+                    false_value,
+                })
+            },
+            _ => return Err(self.error_at(start, format!("expected constant of type '{}'", ty))),
+        };
+        Ok(expr)
+    }
+
+    /// Parse `(ty1 c1, ty2 c2)`
+    fn parse_constant_pair(&mut self) -> ParseResult<(ConstantRef, ConstantRef)> {
+        self.expect(&Tok::LParen)?;
+        let operand0 = self.parse_typed_constant()?.1;
+        self.expect(&Tok::Comma)?;
+        let operand1 = self.parse_typed_constant()?.1;
+        self.expect(&Tok::RParen)?;
+        Ok((operand0, operand1))
+    }
+
+    pub(super) fn parse_int_predicate(&mut self) -> ParseResult<IntPredicate> {
+        let predicate = match self.peek() {
+            Tok::Keyword(kw) => match kw.as_str() {
+                "eq" => IntPredicate::EQ,
+                "ne" => IntPredicate::NE,
+                "ugt" => IntPredicate::UGT,
+                "uge" => IntPredicate::UGE,
+                "ult" => IntPredicate::ULT,
+                "ule" => IntPredicate::ULE,
+                "sgt" => IntPredicate::SGT,
+                "sge" => IntPredicate::SGE,
+                "slt" => IntPredicate::SLT,
+                "sle" => IntPredicate::SLE,
+                _ => return self.error("expected icmp predicate"),
+            },
+            _ => return self.error("expected icmp predicate"),
+        };
+        self.bump();
+        Ok(predicate)
+    }
+
+    pub(super) fn parse_fp_predicate(&mut self) -> ParseResult<FPPredicate> {
+        let predicate = match self.peek() {
+            Tok::Keyword(kw) => match kw.as_str() {
+                "false" => FPPredicate::False,
+                "oeq" => FPPredicate::OEQ,
+                "ogt" => FPPredicate::OGT,
+                "oge" => FPPredicate::OGE,
+                "olt" => FPPredicate::OLT,
+                "ole" => FPPredicate::OLE,
+                "one" => FPPredicate::ONE,
+                "ord" => FPPredicate::ORD,
+                "uno" => FPPredicate::UNO,
+                "ueq" => FPPredicate::UEQ,
+                "ugt" => FPPredicate::UGT,
+                "uge" => FPPredicate::UGE,
+                "ult" => FPPredicate::ULT,
+                "ule" => FPPredicate::ULE,
+                "une" => FPPredicate::UNE,
+                "true" => FPPredicate::True,
+                _ => return self.error("expected fcmp predicate"),
+            },
+            _ => return self.error("expected fcmp predicate"),
+        };
+        self.bump();
+        Ok(predicate)
+    }
+
+    /// Convert a `shufflevector` mask (of type `mask_ty`) to the form LLVM
+    /// gives it: since LLVM 11, a vector of `i32`s or `undef`s
+    #[cfg(feature = "llvm-11-or-greater")]
+    pub(super) fn shuffle_mask(&mut self, mask_ty: &TypeRef, mask: ConstantRef) -> ConstantRef {
+        let num_elements = match mask_ty.as_ref() {
+            Type::VectorType { num_elements, .. } => *num_elements,
+            _ => return mask,
+        };
+        let i32_ty = self.types.i32();
+        let constants = &mut self.constants;
+        let undef = constants.intern(Constant::Undef(i32_ty.clone()));
+        let mut element = |c: &Constant| match c {
+            Constant::Int { value, .. } => constants.intern(Constant::Int {
+                bits: 32,
+                value: *value,
+            }),
+            _ => undef.clone(),
+        };
+        let elements = match mask.as_ref() {
+            Constant::Vector(elements) => elements.iter().map(|e| element(e)).collect(),
+            Constant::AggregateZero(_) => (0 .. num_elements)
+                .map(|_| element(&Constant::Int { bits: 32, value: 0 }))
+                .collect(),
+            _ => vec![undef.clone(); num_elements],
+        };
+        self.constants.intern(Constant::Vector(elements))
+    }
+
+    #[cfg(feature = "llvm-10-or-lower")]
+    pub(super) fn shuffle_mask(&mut self, _mask_ty: &TypeRef, mask: ConstantRef) -> ConstantRef {
+        mask
+    }
+}
+
+/// Parse the decimal digits of an integer literal into the low 64 bits of its
+/// two's-complement value, truncated to `bits` bits
+fn parse_int_value(text: &str, bits: u32) -> u64 {
+    let (negative, digits) = match text.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, text),
+    };
+    let mut value: u64 = 0;
+    for digit in digits.bytes() {
+        value = value.wrapping_mul(10).wrapping_add(u64::from(digit - b'0'));
+    }
+    if negative {
+        value = value.wrapping_neg();
+    }
+    if bits < 64 {
+        value &= (1 << bits) - 1;
+    }
+    value
+}
+
+/// Is this constant the null value of its type?
+fn is_null(c: &Constant) -> bool {
+    match c {
+        Constant::Int { value, .. } => *value == 0,
+        Constant::Float(Float::Single(f)) => f.to_bits() == 0,
+        Constant::Float(Float::Double(f)) => f.to_bits() == 0,
+        Constant::Null(_) | Constant::AggregateZero(_) | Constant::TokenNone => true,
+        _ => false,
+    }
+}
+
+fn is_poison(c: &Constant) -> bool {
+    #[cfg(feature = "llvm-12-or-greater")]
+    {
+        matches!(c, Constant::Poison(_))
+    }
+    #[cfg(feature = "llvm-11-or-lower")]
+    {
+        let _ = c;
+        false
+    }
+}
+
+/// An array or vector constant is replaced by a simpler one if all of its
+/// elements are the same `undef`, `poison`, or null value, as LLVM does
+fn canonical_elements(ty: &TypeRef, elements: &[ConstantRef]) -> Option<Constant> {
+    let first = match elements.first() {
+        Some(first) => first,
+        None => return Some(Constant::AggregateZero(ty.clone())),
+    };
+    if elements.iter().any(|e| e != first) {
+        return None;
+    }
+    if is_poison(first) {
+        #[cfg(feature = "llvm-12-or-greater")]
+        return Some(Constant::Poison(ty.clone()));
+    }
+    match first.as_ref() {
+        Constant::Undef(_) => Some(Constant::Undef(ty.clone())),
+        c if is_null(c) => Some(Constant::AggregateZero(ty.clone())),
+        _ => None,
+    }
+}
+
+fn canonical_array(ty: &TypeRef, element_type: TypeRef, elements: Vec<ConstantRef>) -> Constant {
+    canonical_elements(ty, &elements).unwrap_or(Constant::Array {
+        element_type,
+        elements,
+    })
+}
+
+fn canonical_vector(ty: &TypeRef, elements: Vec<ConstantRef>) -> Constant {
+    canonical_elements(ty, &elements).unwrap_or(Constant::Vector(elements))
+}
+
+fn canonical_struct(
+    ty: &TypeRef,
+    name: Option<String>,
+    values: Vec<ConstantRef>,
+    is_packed: bool,
+) -> Constant {
+    if values.iter().all(|v| is_null(v)) {
+        return Constant::AggregateZero(ty.clone());
+    }
+    if values.iter().all(|v| is_poison(v)) {
+        #[cfg(feature = "llvm-12-or-greater")]
+        return Constant::Poison(ty.clone());
+    }
+    if values
+        .iter()
+        .all(|v| matches!(v.as_ref(), Constant::Undef(_)) || is_poison(v))
+    {
+        return Constant::Undef(ty.clone());
+    }
+    Constant::Struct {
+        name,
+        values,
+        is_packed,
+    }
+}
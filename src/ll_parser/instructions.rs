@@ -0,0 +1,1451 @@
+use super::lexer::Tok;
+use super::types::constant_index;
+use super::{LocalId, ParseResult, Parser};
+use crate::constant::Constant;
+#[cfg(feature = "llvm-9-or-greater")]
+use crate::debugloc::DebugLoc;
+use crate::function::{CallingConvention, GroupID, ParameterAttribute};
+use crate::instruction::*;
+use crate::name::Name;
+use crate::operand::Operand;
+use crate::terminator::*;
+use crate::types::{Type, TypeRef};
+use either::Either;
+
+/// The parts of a `call`, `invoke`, or `callbr` common to all three
+struct CallSite {
+    function: Either<InlineAssembly, Operand>,
+    function_ty: TypeRef,
+    arguments: Vec<(Operand, Vec<ParameterAttribute>)>,
+    return_attributes: Vec<ParameterAttribute>,
+    function_attribute_group: Option<GroupID>,
+    calling_convention: CallingConvention,
+    operand_bundles: Vec<OperandBundle>,
+}
+
+impl CallSite {
+    fn result_type(&self) -> TypeRef {
+        match self.function_ty.as_ref() {
+            Type::FuncType { result_type, .. } => result_type.clone(),
+            _ => unreachable!("function_ty should be a FuncType"),
+        }
+    }
+
+    fn returns_void(&self) -> bool {
+        *self.result_type() == Type::VoidType
+    }
+}
+
+impl<'a> Parser<'a> {
+    /// Parse an instruction or terminator, including its metadata attachments
+    pub(super) fn parse_instruction(&mut self) -> ParseResult<Either<Instruction, Terminator>> {
+        let id_pos = self.pos;
+        let id = match (self.peek().clone(), self.peek_at(1)) {
+            (Tok::LocalVar(name), Tok::Equal) => Some(LocalId::Name(name)),
+            (Tok::LocalId(n), Tok::Equal) => Some(LocalId::Number(n)),
+            _ => None,
+        };
+        if id.is_some() {
+            self.bump();
+            self.bump();
+        }
+        #[allow(unused_mut)]
+        let mut result = self.parse_instruction_body(id, id_pos)?;
+        let mut attachments = vec![];
+        while *self.peek() == Tok::Comma {
+            if let Tok::MetadataVar(_) = self.peek_at(1) {
+                self.bump();
+                attachments.push(self.parse_metadata_attachment()?);
+            } else {
+                return self.error("expected instruction metadata");
+            }
+        }
+        #[cfg(feature = "llvm-9-or-greater")]
+        {
+            if !self.first_pass {
+                let dbg = attachments.iter().rev().find(|(kind, _)| kind == "dbg");
+                if let Some(debugloc) = dbg.and_then(|(_, node)| self.instruction_debugloc(*node)) {
+                    *debugloc_mut(&mut result) = Some(debugloc);
+                }
+            }
+        }
+        #[cfg(feature = "llvm-8-or-lower")]
+        let _ = attachments;
+        Ok(result)
+    }
+
+    /// Parse an instruction or terminator, after the `%name =` (if any).
+    /// `id` is the name or number the result is given in the text.
+    fn parse_instruction_body(
+        &mut self,
+        id: Option<LocalId>,
+        id_pos: usize,
+    ) -> ParseResult<Either<Instruction, Terminator>> {
+        let opcode_pos = self.pos;
+        let opcode = match self.bump() {
+            Tok::Keyword(kw) => kw,
+            _ => {
+                self.pos -= 1;
+                return self.error("expected instruction opcode");
+            },
+        };
+        // the position to report errors about the result's name at
+        let dest_pos = if id.is_some() { id_pos } else { opcode_pos };
+        // define the result, of the given type
+        macro_rules! dest {
+            ($ty:expr) => {{
+                let ty: TypeRef = $ty;
+                self.define_local(id, dest_pos, "instruction", &ty)?
+            }};
+        }
+        macro_rules! no_dest {
+            () => {
+                if id.is_some() {
+                    return Err(
+                        self.error_at(id_pos, "instructions returning void cannot have a name")
+                    );
+                }
+            };
+        }
+
+        let instr = match opcode.as_str() {
+            "add" | "sub" | "mul" | "shl" => {
+                let (nuw, nsw) = self.parse_wrap_flags();
+                let (ty, operand0, operand1) = self.parse_binary_operands()?;
+                let dest = dest!(ty);
+                match opcode.as_str() {
+                    "add" => Instruction::Add(Add {
+                        operand0,
+                        operand1,
+                        dest,
+                        nsw,
+                        nuw,
+                        debugloc: None,
+                    }),
+                    "sub" => Instruction::Sub(Sub {
+                        operand0,
+                        operand1,
+                        dest,
+                        nsw,
+                        nuw,
+                        debugloc: None,
+                    }),
+                    "mul" => Instruction::Mul(Mul {
+                        operand0,
+                        operand1,
+                        dest,
+                        nsw,
+                        nuw,
+                        debugloc: None,
+                    }),
+                    _ => Instruction::Shl(Shl {
+                        operand0,
+                        operand1,
+                        dest,
+                        nsw,
+                        nuw,
+                        debugloc: None,
+                    }),
+                }
+            },
+            "udiv" | "sdiv" | "lshr" | "ashr" => {
+                let exact = self.eat_keyword("exact");
+                let (ty, operand0, operand1) = self.parse_binary_operands()?;
+                let dest = dest!(ty);
+                match opcode.as_str() {
+                    "udiv" => Instruction::UDiv(UDiv {
+                        operand0,
+                        operand1,
+                        dest,
+                        exact,
+                        debugloc: None,
+                    }),
+                    "sdiv" => Instruction::SDiv(SDiv {
+                        operand0,
+                        operand1,
+                        dest,
+                        exact,
+                        debugloc: None,
+                    }),
+                    "lshr" => Instruction::LShr(LShr {
+                        operand0,
+                        operand1,
+                        dest,
+                        exact,
+                        debugloc: None,
+                    }),
+                    _ => Instruction::AShr(AShr {
+                        operand0,
+                        operand1,
+                        dest,
+                        exact,
+                        debugloc: None,
+                    }),
+                }
+            },
+            "urem" | "srem" | "and" | "or" | "xor" => {
+                let (ty, operand0, operand1) = self.parse_binary_operands()?;
+                let dest = dest!(ty);
+                match opcode.as_str() {
+                    "urem" => Instruction::URem(URem {
+                        operand0,
+                        operand1,
+                        dest,
+                        debugloc: None,
+                    }),
+                    "srem" => Instruction::SRem(SRem {
+                        operand0,
+                        operand1,
+                        dest,
+                        debugloc: None,
+                    }),
+                    "and" => Instruction::And(And {
+                        operand0,
+                        operand1,
+                        dest,
+                        debugloc: None,
+                    }),
+                    "or" => Instruction::Or(Or {
+                        operand0,
+                        operand1,
+                        dest,
+                        debugloc: None,
+                    }),
+                    _ => Instruction::Xor(Xor {
+                        operand0,
+                        operand1,
+                        dest,
+                        debugloc: None,
+                    }),
+                }
+            },
+            "fadd" | "fsub" | "fmul" | "fdiv" | "frem" => {
+                let fast_math_flags = self.parse_fast_math_flags();
+                let (ty, operand0, operand1) = self.parse_binary_operands()?;
+                let dest = dest!(ty);
+                match opcode.as_str() {
+                    "fadd" => Instruction::FAdd(FAdd {
+                        operand0,
+                        operand1,
+                        dest,
+                        fast_math_flags,
+                        debugloc: None,
+                    }),
+                    "fsub" => Instruction::FSub(FSub {
+                        operand0,
+                        operand1,
+                        dest,
+                        fast_math_flags,
+                        debugloc: None,
+                    }),
+                    "fmul" => Instruction::FMul(FMul {
+                        operand0,
+                        operand1,
+                        dest,
+                        fast_math_flags,
+                        debugloc: None,
+                    }),
+                    "fdiv" => Instruction::FDiv(FDiv {
+                        operand0,
+                        operand1,
+                        dest,
+                        fast_math_flags,
+                        debugloc: None,
+                    }),
+                    _ => Instruction::FRem(FRem {
+                        operand0,
+                        operand1,
+                        dest,
+                        fast_math_flags,
+                        debugloc: None,
+                    }),
+                }
+            },
+            "fneg" => {
+                let fast_math_flags = self.parse_fast_math_flags();
+                let (ty, operand) = self.parse_typed_value()?;
+                Instruction::FNeg(FNeg {
+                    operand,
+                    dest: dest!(ty),
+                    fast_math_flags,
+                    debugloc: None,
+                })
+            },
+            "extractelement" => {
+                let (vector_ty, vector) = self.parse_typed_value()?;
+                self.expect(&Tok::Comma)?;
+                let (index_ty, index) = self.parse_typed_value()?;
+                let element_ty = match (vector_ty.as_ref(), index_ty.as_ref()) {
+                    (Type::VectorType { element_type, .. }, Type::IntegerType { .. }) => {
+                        element_type.clone()
+                    },
+                    _ => return Err(self.error_at(opcode_pos, "invalid extractelement operands")),
+                };
+                Instruction::ExtractElement(ExtractElement {
+                    vector,
+                    index,
+                    dest: dest!(element_ty),
+                    debugloc: None,
+                })
+            },
+            "insertelement" => {
+                let (vector_ty, vector) = self.parse_typed_value()?;
+                self.expect(&Tok::Comma)?;
+                let (element_ty, element) = self.parse_typed_value()?;
+                self.expect(&Tok::Comma)?;
+                let (index_ty, index) = self.parse_typed_value()?;
+                match (vector_ty.as_ref(), index_ty.as_ref()) {
+                    (Type::VectorType { element_type, .. }, Type::IntegerType { .. })
+                        if *element_type == element_ty => {},
+                    _ => return Err(self.error_at(opcode_pos, "invalid insertelement operands")),
+                }
+                Instruction::InsertElement(InsertElement {
+                    vector,
+                    element,
+                    index,
+                    dest: dest!(vector_ty),
+                    debugloc: None,
+                })
+            },
+            "shufflevector" => {
+                let (ty, operand0) = self.parse_typed_value()?;
+                self.expect(&Tok::Comma)?;
+                let operand1 = self.parse_typed_value()?.1;
+                self.expect(&Tok::Comma)?;
+                let (mask_ty, mask) = self.parse_typed_constant()?;
+                let result_ty = match self.shuffle_result_type(&ty, &mask_ty) {
+                    Some(result_ty) => result_ty,
+                    None => return Err(self.error_at(opcode_pos, "invalid shufflevector operands")),
+                };
+                Instruction::ShuffleVector(ShuffleVector {
+                    operand0,
+                    operand1,
+                    dest: dest!(result_ty),
+                    mask: self.shuffle_mask(&mask_ty, mask),
+                    debugloc: None,
+                })
+            },
+            "extractvalue" => {
+                let (aggregate_ty, aggregate) = self.parse_typed_value()?;
+                let indices = self.parse_aggregate_indices()?;
+                let element_ty = match self.aggregate_element_type(&aggregate_ty, &indices) {
+                    Some(element_ty) => element_ty,
+                    None => {
+                        return Err(self.error_at(opcode_pos, "invalid indices for extractvalue"))
+                    },
+                };
+                Instruction::ExtractValue(ExtractValue {
+                    aggregate,
+                    indices,
+                    dest: dest!(element_ty),
+                    debugloc: None,
+                })
+            },
+            "insertvalue" => {
+                let (aggregate_ty, aggregate) = self.parse_typed_value()?;
+                self.expect(&Tok::Comma)?;
+                let (element_ty, element) = self.parse_typed_value()?;
+                let indices = self.parse_aggregate_indices()?;
+                match self.aggregate_element_type(&aggregate_ty, &indices) {
+                    Some(field_ty) if field_ty == element_ty => {},
+                    Some(field_ty) => {
+                        let msg = format!(
+                            "insertvalue operand and field disagree in type: '{}' instead of '{}'",
+                            element_ty, field_ty
+                        );
+                        return Err(self.error_at(opcode_pos, msg));
+                    },
+                    None => {
+                        return Err(self.error_at(opcode_pos, "invalid indices for insertvalue"))
+                    },
+                }
+                Instruction::InsertValue(InsertValue {
+                    aggregate,
+                    element,
+                    indices,
+                    dest: dest!(aggregate_ty),
+                    debugloc: None,
+                })
+            },
+            "alloca" => {
+                self.eat_keyword("inalloca");
+                self.eat_keyword("swifterror");
+                let allocated_type = self.parse_type()?;
+                let mut num_elements = None;
+                let mut alignment = None;
+                let mut addr_space = 0;
+                while *self.peek() == Tok::Comma {
+                    match self.peek_at(1).clone() {
+                        Tok::MetadataVar(_) => break,
+                        Tok::Keyword(kw) if kw == "align" => {
+                            self.bump();
+                            alignment = Some(self.parse_alignment()?);
+                        },
+                        Tok::Keyword(kw) if kw == "addrspace" => {
+                            self.bump();
+                            addr_space = self.parse_optional_addr_space()?;
+                        },
+                        _ => {
+                            self.bump();
+                            num_elements = Some(self.parse_typed_value()?.1);
+                        },
+                    }
+                }
+                let num_elements = match num_elements {
+                    Some(num_elements) => num_elements,
+                    None => Operand::ConstantOperand(
+                        self.constants.intern(Constant::Int { bits: 32, value: 1 }),
+                    ),
+                };
+                let alignment =
+                    alignment.unwrap_or_else(|| self.default_alignment(&allocated_type, false));
+                let ty = self.pointer_type(allocated_type.clone(), addr_space);
+                Instruction::Alloca(Alloca {
+                    allocated_type,
+                    num_elements,
+                    dest: dest!(ty),
+                    alignment,
+                    debugloc: None,
+                })
+            },
+            "load" => {
+                let atomic = self.eat_keyword("atomic");
+                let volatile = self.eat_keyword("volatile");
+                let loaded_ty = self.parse_type()?;
+                self.expect(&Tok::Comma)?;
+                let address_pos = self.pos;
+                let (address_ty, address) = self.parse_typed_value()?;
+                self.check_pointer(&address_ty, &loaded_ty, "load", address_pos)?;
+                let atomicity = if atomic {
+                    Some(self.parse_atomicity()?)
+                } else {
+                    None
+                };
+                let alignment = match self.parse_optional_alignment()? {
+                    Some(alignment) => alignment,
+                    None => self.default_alignment(&loaded_ty, true),
+                };
+                Instruction::Load(Load {
+                    address,
+                    dest: dest!(loaded_ty.clone()),
+                    loaded_ty,
+                    volatile,
+                    atomicity,
+                    alignment,
+                    debugloc: None,
+                })
+            },
+            "store" => {
+                let atomic = self.eat_keyword("atomic");
+                let volatile = self.eat_keyword("volatile");
+                let (value_ty, value) = self.parse_typed_value()?;
+                self.expect(&Tok::Comma)?;
+                let address_pos = self.pos;
+                let (address_ty, address) = self.parse_typed_value()?;
+                self.check_pointer(&address_ty, &value_ty, "store", address_pos)?;
+                let atomicity = if atomic {
+                    Some(self.parse_atomicity()?)
+                } else {
+                    None
+                };
+                let alignment = match self.parse_optional_alignment()? {
+                    Some(alignment) => alignment,
+                    None => self.default_alignment(&value_ty, true),
+                };
+                no_dest!();
+                Instruction::Store(Store {
+                    address,
+                    value,
+                    volatile,
+                    atomicity,
+                    alignment,
+                    debugloc: None,
+                })
+            },
+            "fence" => {
+                let atomicity = self.parse_atomicity()?;
+                no_dest!();
+                Instruction::Fence(Fence {
+                    atomicity,
+                    debugloc: None,
+                })
+            },
+            "cmpxchg" => {
+                let weak = self.eat_keyword("weak");
+                let volatile = self.eat_keyword("volatile");
+                let address_pos = self.pos;
+                let (address_ty, address) = self.parse_typed_value()?;
+                self.expect(&Tok::Comma)?;
+                let (ty, expected) = self.parse_typed_value()?;
+                self.expect(&Tok::Comma)?;
+                let replacement = self.parse_typed_value()?.1;
+                self.check_pointer(&address_ty, &ty, "cmpxchg", address_pos)?;
+                let bool_ty = self.types.bool();
+                let result_ty = self.types.struct_of(vec![ty, bool_ty], false);
+                let atomicity = self.parse_atomicity()?;
+                let failure_memory_ordering = self.parse_memory_ordering()?;
+                self.parse_optional_alignment()?;
+                Instruction::CmpXchg(CmpXchg {
+                    address,
+                    expected,
+                    replacement,
+                    dest: dest!(result_ty),
+                    volatile,
+                    atomicity,
+                    failure_memory_ordering,
+                    weak,
+                    debugloc: None,
+                })
+            },
+            "atomicrmw" => {
+                let volatile = self.eat_keyword("volatile");
+                let operation = self.parse_rmw_bin_op()?;
+                let address_pos = self.pos;
+                let (address_ty, address) = self.parse_typed_value()?;
+                self.expect(&Tok::Comma)?;
+                let (ty, value) = self.parse_typed_value()?;
+                self.check_pointer(&address_ty, &ty, "atomicrmw", address_pos)?;
+                let atomicity = self.parse_atomicity()?;
+                self.parse_optional_alignment()?;
+                Instruction::AtomicRMW(AtomicRMW {
+                    operation,
+                    address,
+                    value,
+                    dest: dest!(ty),
+                    volatile,
+                    atomicity,
+                    debugloc: None,
+                })
+            },
+            "getelementptr" => {
+                let in_bounds = self.eat_keyword("inbounds");
+                let source_element_type = self.parse_type()?;
+                self.expect(&Tok::Comma)?;
+                let (address_ty, address) = self.parse_typed_value()?;
+                let mut index_types = vec![];
+                let mut indices = vec![];
+                while *self.peek() == Tok::Comma {
+                    if let Tok::MetadataVar(_) = self.peek_at(1) {
+                        break;
+                    }
+                    self.bump();
+                    self.eat_keyword("inrange");
+                    let (index_ty, index) = self.parse_typed_value()?;
+                    index_types.push(index_ty);
+                    indices.push(index);
+                }
+                let index_values = indices.iter().map(|index| match index {
+                    Operand::ConstantOperand(c) => constant_index(c),
+                    _ => None,
+                });
+                let ty = self.gep_result_type(
+                    &source_element_type,
+                    &address_ty,
+                    &index_types,
+                    index_values,
+                    opcode_pos,
+                )?;
+                Instruction::GetElementPtr(GetElementPtr {
+                    address,
+                    indices,
+                    dest: dest!(ty),
+                    in_bounds,
+                    source_element_type,
+                    debugloc: None,
+                })
+            },
+            "trunc" | "zext" | "sext" | "fptrunc" | "fpext" | "fptoui" | "fptosi" | "uitofp"
+            | "sitofp" | "ptrtoint" | "inttoptr" | "bitcast" | "addrspacecast" => {
+                let operand = self.parse_typed_value()?.1;
+                self.expect_keyword("to")?;
+                let to_type = self.parse_type()?;
+                let dest = dest!(to_type.clone());
+                macro_rules! cast {
+                    ($variant:ident) => {
+                        Instruction::$variant($variant {
+                            operand,
+                            to_type,
+                            dest,
+                            debugloc: None,
+                        })
+                    };
+                }
+                match opcode.as_str() {
+                    "trunc" => cast!(Trunc),
+                    "zext" => cast!(ZExt),
+                    "sext" => cast!(SExt),
+                    "fptrunc" => cast!(FPTrunc),
+                    "fpext" => cast!(FPExt),
+                    "fptoui" => cast!(FPToUI),
+                    "fptosi" => cast!(FPToSI),
+                    "uitofp" => cast!(UIToFP),
+                    "sitofp" => cast!(SIToFP),
+                    "ptrtoint" => cast!(PtrToInt),
+                    "inttoptr" => cast!(IntToPtr),
+                    "bitcast" => cast!(BitCast),
+                    _ => cast!(AddrSpaceCast),
+                }
+            },
+            "icmp" => {
+                let predicate = self.parse_int_predicate()?;
+                let (ty, operand0, operand1) = self.parse_binary_operands()?;
+                let result_ty = self.comparison_result_type(&ty);
+                Instruction::ICmp(ICmp {
+                    predicate,
+                    operand0,
+                    operand1,
+                    dest: dest!(result_ty),
+                    debugloc: None,
+                })
+            },
+            "fcmp" => {
+                let fast_math_flags = self.parse_fast_math_flags();
+                let predicate = self.parse_fp_predicate()?;
+                let (ty, operand0, operand1) = self.parse_binary_operands()?;
+                let result_ty = self.comparison_result_type(&ty);
+                Instruction::FCmp(FCmp {
+                    predicate,
+                    operand0,
+                    operand1,
+                    dest: dest!(result_ty),
+                    fast_math_flags,
+                    debugloc: None,
+                })
+            },
+            "phi" => {
+                // `Phi` doesn't represent fast-math flags
+                self.parse_fast_math_flags();
+                let to_type = self.parse_type()?;
+                let mut incoming_values = vec![];
+                loop {
+                    self.expect(&Tok::LSquare)?;
+                    let value = self.parse_value(&to_type)?;
+                    self.expect(&Tok::Comma)?;
+                    let block = self.parse_block_name()?;
+                    self.expect(&Tok::RSquare)?;
+                    incoming_values.push((value, block));
+                    if *self.peek() != Tok::Comma || *self.peek_at(1) != Tok::LSquare {
+                        break;
+                    }
+                    self.bump();
+                }
+                Instruction::Phi(Phi {
+                    incoming_values,
+                    dest: dest!(to_type.clone()),
+                    to_type,
+                    debugloc: None,
+                })
+            },
+            "select" => {
+                // `Select` doesn't represent fast-math flags
+                self.parse_fast_math_flags();
+                let condition = self.parse_typed_value()?.1;
+                self.expect(&Tok::Comma)?;
+                let (ty, true_value) = self.parse_typed_value()?;
+                self.expect(&Tok::Comma)?;
+                let false_value = self.parse_typed_value()?.1;
+                Instruction::Select(Select {
+                    condition,
+                    true_value,
+                    false_value,
+                    dest: dest!(ty),
+                    debugloc: None,
+                })
+            },
+            #[cfg(feature = "llvm-10-or-greater")]
+            "freeze" => {
+                let (ty, operand) = self.parse_typed_value()?;
+                Instruction::Freeze(Freeze {
+                    operand,
+                    dest: dest!(ty),
+                    debugloc: None,
+                })
+            },
+            "tail" | "musttail" | "notail" | "call" => {
+                let tail_call_kind = match opcode.as_str() {
+                    "tail" => Some(TailCallKind::Tail),
+                    "musttail" => Some(TailCallKind::MustTail),
+                    "notail" => Some(TailCallKind::NoTail),
+                    _ => None,
+                };
+                if tail_call_kind.is_some() {
+                    self.expect_keyword("call")?;
+                }
+                let fast_math_flags = self.parse_fast_math_flags();
+                let call = self.parse_call_site()?;
+                let dest = if call.returns_void() {
+                    no_dest!();
+                    None
+                } else {
+                    Some(dest!(call.result_type()))
+                };
+                Instruction::Call(Call {
+                    function: call.function,
+                    function_ty: call.function_ty,
+                    arguments: call.arguments,
+                    return_attributes: call.return_attributes,
+                    dest,
+                    function_attribute_group: call.function_attribute_group,
+                    tail_call_kind,
+                    fast_math_flags,
+                    operand_bundles: call.operand_bundles,
+                    calling_convention: call.calling_convention,
+                    debugloc: None,
+                })
+            },
+            "va_arg" => {
+                let arg_list = self.parse_typed_value()?.1;
+                self.expect(&Tok::Comma)?;
+                let cur_type = self.parse_type()?;
+                Instruction::VAArg(VAArg {
+                    arg_list,
+                    dest: dest!(cur_type.clone()),
+                    cur_type,
+                    debugloc: None,
+                })
+            },
+            "landingpad" => {
+                let result_type = self.parse_type()?;
+                let cleanup = self.eat_keyword("cleanup");
+                let mut clauses = vec![];
+                while self.eat_keyword("catch") || self.eat_keyword("filter") {
+                    self.parse_typed_constant()?;
+                    clauses.push(LandingPadClause {});
+                }
+                Instruction::LandingPad(LandingPad {
+                    dest: dest!(result_type.clone()),
+                    result_type,
+                    clauses,
+                    cleanup,
+                    debugloc: None,
+                })
+            },
+            "catchpad" => {
+                self.expect_keyword("within")?;
+                let catch_switch = self.parse_pad_operand()?;
+                let args = self.parse_pad_args()?;
+                Instruction::CatchPad(CatchPad {
+                    catch_switch,
+                    args,
+                    dest: dest!(self.types.token_type()),
+                    debugloc: None,
+                })
+            },
+            "cleanuppad" => {
+                self.expect_keyword("within")?;
+                let parent_pad = self.parse_pad_operand()?;
+                let args = self.parse_pad_args()?;
+                Instruction::CleanupPad(CleanupPad {
+                    parent_pad,
+                    args,
+                    dest: dest!(self.types.token_type()),
+                    debugloc: None,
+                })
+            },
+            _ => {
+                self.pos = opcode_pos;
+                return self
+                    .parse_terminator(id, id_pos, dest_pos)
+                    .map(Either::Right);
+            },
+        };
+        Ok(Either::Left(instr))
+    }
+
+    fn parse_terminator(
+        &mut self,
+        id: Option<LocalId>,
+        id_pos: usize,
+        dest_pos: usize,
+    ) -> ParseResult<Terminator> {
+        let opcode_pos = self.pos;
+        let opcode = match self.bump() {
+            Tok::Keyword(kw) => kw,
+            _ => unreachable!("parse_terminator should only be called at a keyword"),
+        };
+        let returns_void = matches!(
+            opcode.as_str(),
+            "ret"
+                | "br"
+                | "switch"
+                | "indirectbr"
+                | "resume"
+                | "unreachable"
+                | "cleanupret"
+                | "catchret"
+        );
+        if returns_void && id.is_some() {
+            return Err(self.error_at(id_pos, "instructions returning void cannot have a name"));
+        }
+        let term = match opcode.as_str() {
+            "ret" => {
+                let ty_pos = self.pos;
+                let ty = self.parse_type()?;
+                if ty != self.return_type {
+                    return Err(self.error_at(
+                        ty_pos,
+                        format!(
+                            "value doesn't match function result type '{}'",
+                            self.return_type
+                        ),
+                    ));
+                }
+                let return_operand = if *ty == Type::VoidType {
+                    None
+                } else {
+                    Some(self.parse_value(&ty)?)
+                };
+                Terminator::Ret(Ret {
+                    return_operand,
+                    debugloc: None,
+                })
+            },
+            "br" => {
+                if self.at_keyword("label") {
+                    Terminator::Br(Br {
+                        dest: self.parse_label()?,
+                        debugloc: None,
+                    })
+                } else {
+                    let condition = self.parse_typed_value()?.1;
+                    self.expect(&Tok::Comma)?;
+                    let true_dest = self.parse_label()?;
+                    self.expect(&Tok::Comma)?;
+                    let false_dest = self.parse_label()?;
+                    Terminator::CondBr(CondBr {
+                        condition,
+                        true_dest,
+                        false_dest,
+                        debugloc: None,
+                    })
+                }
+            },
+            "switch" => {
+                let operand = self.parse_typed_value()?.1;
+                self.expect(&Tok::Comma)?;
+                let default_dest = self.parse_label()?;
+                self.expect(&Tok::LSquare)?;
+                let mut dests = vec![];
+                while !self.eat(&Tok::RSquare) {
+                    let value = self.parse_typed_constant()?.1;
+                    self.expect(&Tok::Comma)?;
+                    dests.push((value, self.parse_label()?));
+                }
+                Terminator::Switch(Switch {
+                    operand,
+                    dests,
+                    default_dest,
+                    debugloc: None,
+                })
+            },
+            "indirectbr" => {
+                let operand = self.parse_typed_value()?.1;
+                self.expect(&Tok::Comma)?;
+                self.expect(&Tok::LSquare)?;
+                let mut possible_dests = vec![];
+                while *self.peek() != Tok::RSquare {
+                    if !possible_dests.is_empty() {
+                        self.expect(&Tok::Comma)?;
+                    }
+                    possible_dests.push(self.parse_label()?);
+                }
+                self.bump();
+                Terminator::IndirectBr(IndirectBr {
+                    operand,
+                    possible_dests,
+                    debugloc: None,
+                })
+            },
+            "invoke" => {
+                let call = self.parse_call_site()?;
+                self.expect_keyword("to")?;
+                let return_label = self.parse_label()?;
+                self.expect_keyword("unwind")?;
+                let exception_label = self.parse_label()?;
+                Terminator::Invoke(Invoke {
+                    result: self.call_site_result(&call, id, id_pos, dest_pos)?,
+                    function: call.function,
+                    function_ty: call.function_ty,
+                    arguments: call.arguments,
+                    return_attributes: call.return_attributes,
+                    return_label,
+                    exception_label,
+                    function_attribute_group: call.function_attribute_group,
+                    operand_bundles: call.operand_bundles,
+                    calling_convention: call.calling_convention,
+                    debugloc: None,
+                })
+            },
+            #[cfg(feature = "llvm-9-or-greater")]
+            "callbr" => {
+                let call = self.parse_call_site()?;
+                self.expect_keyword("to")?;
+                let return_label = self.parse_label()?;
+                // `CallBr` doesn't represent the other labels yet
+                self.expect(&Tok::LSquare)?;
+                let mut num_labels = 0;
+                while *self.peek() != Tok::RSquare {
+                    if num_labels > 0 {
+                        self.expect(&Tok::Comma)?;
+                    }
+                    self.parse_label()?;
+                    num_labels += 1;
+                }
+                self.bump();
+                Terminator::CallBr(CallBr {
+                    result: self.call_site_result(&call, id, id_pos, dest_pos)?,
+                    function: call.function,
+                    function_ty: call.function_ty,
+                    arguments: call.arguments,
+                    return_attributes: call.return_attributes,
+                    return_label,
+                    other_labels: (),
+                    function_attribute_group: call.function_attribute_group,
+                    calling_convention: call.calling_convention,
+                    debugloc: None,
+                })
+            },
+            "resume" => Terminator::Resume(Resume {
+                operand: self.parse_typed_value()?.1,
+                debugloc: None,
+            }),
+            "unreachable" => Terminator::Unreachable(Unreachable { debugloc: None }),
+            "cleanupret" => {
+                self.expect_keyword("from")?;
+                let cleanup_pad = self.parse_pad_operand()?;
+                self.expect_keyword("unwind")?;
+                let unwind_dest = self.parse_unwind_dest()?;
+                Terminator::CleanupRet(CleanupRet {
+                    cleanup_pad,
+                    unwind_dest,
+                    debugloc: None,
+                })
+            },
+            "catchret" => {
+                self.expect_keyword("from")?;
+                let catch_pad = self.parse_pad_operand()?;
+                self.expect_keyword("to")?;
+                let successor = self.parse_label()?;
+                Terminator::CatchRet(CatchRet {
+                    catch_pad,
+                    successor,
+                    debugloc: None,
+                })
+            },
+            "catchswitch" => {
+                self.expect_keyword("within")?;
+                let parent_pad = self.parse_pad_operand()?;
+                self.expect(&Tok::LSquare)?;
+                let mut catch_handlers = vec![];
+                while *self.peek() != Tok::RSquare {
+                    if !catch_handlers.is_empty() {
+                        self.expect(&Tok::Comma)?;
+                    }
+                    catch_handlers.push(self.parse_label()?);
+                }
+                self.bump();
+                self.expect_keyword("unwind")?;
+                let default_unwind_dest = self.parse_unwind_dest()?;
+                let token_type = self.types.token_type();
+                Terminator::CatchSwitch(CatchSwitch {
+                    parent_pad,
+                    catch_handlers,
+                    default_unwind_dest,
+                    result: self.define_local(id, dest_pos, "instruction", &token_type)?,
+                    debugloc: None,
+                })
+            },
+            _ => return Err(self.error_at(opcode_pos, "expected instruction opcode")),
+        };
+        Ok(term)
+    }
+
+    /// The `Name` of the result of an `invoke` or `callbr`. LLVM numbers
+    /// these even if they return void.
+    fn call_site_result(
+        &mut self,
+        call: &CallSite,
+        id: Option<LocalId>,
+        id_pos: usize,
+        dest_pos: usize,
+    ) -> ParseResult<Name> {
+        if call.returns_void() {
+            if id.is_some() {
+                return Err(self.error_at(id_pos, "instructions returning void cannot have a name"));
+            }
+            Ok(self.next_local_number())
+        } else {
+            self.define_local(id, dest_pos, "instruction", &call.result_type())
+        }
+    }
+
+    /// Parse the part of a `call`, `invoke`, or `callbr` from the calling
+    /// convention up to the operand bundles, e.g.
+    /// `fastcc i32 @foo(i32 %x) #0 [ "deopt"() ]`
+    fn parse_call_site(&mut self) -> ParseResult<CallSite> {
+        let calling_convention = self.parse_calling_convention()?;
+        let return_attrs = self.parse_attributes(false)?;
+        let addr_space = self.parse_optional_addr_space()?;
+        let ty_pos = self.pos;
+        let ty = self.parse_type()?;
+
+        // the callee, which may be inline assembly; its type isn't known until
+        // we've seen the arguments
+        enum Callee {
+            Asm,
+            Value(usize),
+        }
+        let callee = if self.eat_keyword("asm") {
+            self.eat_keyword("sideeffect");
+            self.eat_keyword("alignstack");
+            self.eat_keyword("inteldialect");
+            self.eat_keyword("unwind");
+            self.parse_string()?;
+            self.expect(&Tok::Comma)?;
+            self.parse_string()?;
+            Callee::Asm
+        } else {
+            let callee_pos = self.pos;
+            self.skip_callee()?;
+            Callee::Value(callee_pos)
+        };
+
+        self.expect(&Tok::LParen)?;
+        let mut arguments = vec![];
+        while *self.peek() != Tok::RParen {
+            if !arguments.is_empty() {
+                self.expect(&Tok::Comma)?;
+            }
+            let arg_ty = self.parse_type()?;
+            let attrs = self.parse_attributes(false)?;
+            let operand = self.parse_value(&arg_ty)?;
+            let attrs = self.param_attributes(attrs, &arg_ty);
+            arguments.push((arg_ty, operand, attrs));
+        }
+        self.bump();
+
+        let function_ty = match ty.as_ref() {
+            Type::FuncType { .. } => ty.clone(),
+            Type::PointerType { pointee_type, .. }
+                if matches!(pointee_type.as_ref(), Type::FuncType { .. }) =>
+            {
+                pointee_type.clone()
+            },
+            _ => {
+                let param_types = arguments.iter().map(|(ty, _, _)| ty.clone()).collect();
+                self.types.func_type(ty.clone(), param_types, false)
+            },
+        };
+        if let Type::FuncType { result_type, .. } = function_ty.as_ref() {
+            if let Type::FuncType { .. } = result_type.as_ref() {
+                return Err(self.error_at(ty_pos, "invalid type for function call"));
+            }
+        }
+        let callee_ty = self.pointer_type(function_ty.clone(), addr_space);
+        let function = match callee {
+            Callee::Asm => Either::Left(InlineAssembly { ty: callee_ty }),
+            Callee::Value(callee_pos) => {
+                let pos = self.pos;
+                self.pos = callee_pos;
+                let operand = self.parse_value(&callee_ty)?;
+                self.pos = pos;
+                Either::Right(operand)
+            },
+        };
+
+        let attrs = self.parse_attributes(true)?;
+        let (function_attrs, _) = self.function_attributes(attrs)?;
+        let function_attribute_group = self.intern_function_attributes(function_attrs);
+
+        let mut operand_bundles = vec![];
+        if *self.peek() == Tok::LSquare {
+            self.bump();
+            while *self.peek() != Tok::RSquare {
+                if !operand_bundles.is_empty() {
+                    self.expect(&Tok::Comma)?;
+                }
+                let tag = self.parse_string()?;
+                self.expect(&Tok::LParen)?;
+                let mut inputs = vec![];
+                while *self.peek() != Tok::RParen {
+                    if !inputs.is_empty() {
+                        self.expect(&Tok::Comma)?;
+                    }
+                    inputs.push(self.parse_typed_value()?.1);
+                }
+                self.bump();
+                operand_bundles.push(OperandBundle { tag, inputs });
+            }
+            self.bump();
+        }
+
+        Ok(CallSite {
+            function,
+            return_attributes: self.param_attributes(
+                return_attrs,
+                &match function_ty.as_ref() {
+                    Type::FuncType { result_type, .. } => result_type.clone(),
+                    _ => ty,
+                },
+            ),
+            function_ty,
+            arguments: arguments
+                .into_iter()
+                .map(|(_, operand, attrs)| (operand, attrs))
+                .collect(),
+            function_attribute_group,
+            calling_convention,
+            operand_bundles,
+        })
+    }
+
+    /// Skip over the function called by a `call`, `invoke`, or `callbr`,
+    /// which is parsed once its type is known
+    fn skip_callee(&mut self) -> ParseResult<()> {
+        match self.peek() {
+            Tok::LocalVar(_) | Tok::LocalId(_) | Tok::GlobalVar(_) | Tok::GlobalId(_) => {
+                self.bump();
+                Ok(())
+            },
+            Tok::Keyword(kw)
+                if ["null", "undef", "poison", "zeroinitializer"].contains(&kw.as_str()) =>
+            {
+                self.bump();
+                Ok(())
+            },
+            Tok::Keyword(_) => {
+                // a constant expression, e.g. `bitcast (ptr @f to ptr)`
+                while let Tok::Keyword(_) = self.peek() {
+                    self.bump();
+                }
+                if *self.peek() != Tok::LParen {
+                    return self.error("expected '(' here");
+                }
+                self.skip_balanced()
+            },
+            _ => self.error("expected function to call"),
+        }
+    }
+
+    // ******** //
+    // operands //
+    // ******** //
+
+    /// Parse a value of the given type, e.g. `%x`, `7`, or `!12`
+    pub(super) fn parse_value(&mut self, ty: &TypeRef) -> ParseResult<Operand> {
+        match self.peek() {
+            // checked first, as in `metadata %struct.T* %x` the `%struct.T`
+            // is a type, not a value
+            _ if **ty == Type::MetadataType => {
+                self.skip_metadata_value()?;
+                Ok(Operand::MetadataOperand)
+            },
+            Tok::LocalVar(_) | Tok::LocalId(_) => Ok(Operand::LocalOperand {
+                name: self.parse_local_use(ty)?,
+                ty: ty.clone(),
+            }),
+            _ => Ok(Operand::ConstantOperand(self.parse_constant(ty)?)),
+        }
+    }
+
+    /// Parse a type followed by a value of that type, e.g. `i32 %x`
+    fn parse_typed_value(&mut self) -> ParseResult<(TypeRef, Operand)> {
+        let ty = self.parse_type()?;
+        let value = self.parse_value(&ty)?;
+        Ok((ty, value))
+    }
+
+    /// Parse the operands of a binary operation, e.g. `i32 %x, 1`, giving
+    /// their type and the operands
+    fn parse_binary_operands(&mut self) -> ParseResult<(TypeRef, Operand, Operand)> {
+        let (ty, operand0) = self.parse_typed_value()?;
+        self.expect(&Tok::Comma)?;
+        let operand1 = self.parse_value(&ty)?;
+        Ok((ty, operand0, operand1))
+    }
+
+    /// Check that the address operand of a `load`, `store`, `cmpxchg`, or
+    /// `atomicrmw` (a `what`), at `pos`, is a pointer to values of type
+    /// `value_ty`
+    fn check_pointer(
+        &self,
+        address_ty: &TypeRef,
+        value_ty: &TypeRef,
+        what: &str,
+        pos: usize,
+    ) -> ParseResult<()> {
+        match address_ty.as_ref() {
+            Type::PointerType { pointee_type, .. } if pointee_type != value_ty => {
+                Err(self.error_at(pos, format!("{} value and pointer type do not match", what)))
+            },
+            Type::PointerType { .. } | Type::OpaquePointerType { .. } => Ok(()),
+            _ => Err(self.error_at(pos, format!("{} operand must be a pointer", what))),
+        }
+    }
+
+    /// Parse the indices of an `extractvalue` or `insertvalue`, e.g. `, 0, 1`
+    fn parse_aggregate_indices(&mut self) -> ParseResult<Vec<u32>> {
+        let mut indices = vec![];
+        while *self.peek() == Tok::Comma {
+            if let Tok::MetadataVar(_) = self.peek_at(1) {
+                break;
+            }
+            self.bump();
+            indices.push(self.parse_uint()?);
+        }
+        if indices.is_empty() {
+            return self.error("expected index");
+        }
+        Ok(indices)
+    }
+
+    /// Parse the parent pad of a `catchpad`, `cleanuppad`, or `catchswitch`,
+    /// or the pad of a `cleanupret` or `catchret`: a token value, or `none`
+    fn parse_pad_operand(&mut self) -> ParseResult<Operand> {
+        let token_type = self.types.token_type();
+        self.parse_value(&token_type)
+    }
+
+    /// Parse the arguments of a `catchpad` or `cleanuppad`, e.g. `[ptr @x]`
+    fn parse_pad_args(&mut self) -> ParseResult<Vec<Operand>> {
+        self.expect(&Tok::LSquare)?;
+        let mut args = vec![];
+        while *self.peek() != Tok::RSquare {
+            if !args.is_empty() {
+                self.expect(&Tok::Comma)?;
+            }
+            args.push(self.parse_typed_value()?.1);
+        }
+        self.bump();
+        Ok(args)
+    }
+
+    /// Parse `to caller` or `label %foo`, after an `unwind`
+    fn parse_unwind_dest(&mut self) -> ParseResult<Option<Name>> {
+        if self.eat_keyword("to") {
+            self.expect_keyword("caller")?;
+            Ok(None)
+        } else {
+            Ok(Some(self.parse_label()?))
+        }
+    }
+
+    // ***** //
+    // flags //
+    // ***** //
+
+    /// Parse `nuw` and/or `nsw`, giving `(nuw, nsw)`
+    fn parse_wrap_flags(&mut self) -> (bool, bool) {
+        let mut nuw = false;
+        let mut nsw = false;
+        loop {
+            if self.eat_keyword("nuw") {
+                nuw = true;
+            } else if self.eat_keyword("nsw") {
+                nsw = true;
+            } else {
+                return (nuw, nsw);
+            }
+        }
+    }
+
+    fn parse_fast_math_flags(&mut self) -> FastMathFlags {
+        let mut keywords = vec![];
+        while let Tok::Keyword(kw) = self.peek() {
+            match kw.as_str() {
+                "fast" | "reassoc" | "nnan" | "ninf" | "nsz" | "arcp" | "contract" | "afn" => {
+                    keywords.push(kw.clone());
+                    self.bump();
+                },
+                _ => break,
+            }
+        }
+        FastMathFlags::from_keywords(&keywords)
+    }
+
+    /// Parse an optional `, align N`
+    fn parse_optional_alignment(&mut self) -> ParseResult<Option<u32>> {
+        if *self.peek() == Tok::Comma && self.peek_at(1) == &Tok::Keyword("align".into()) {
+            self.bump();
+            Ok(Some(self.parse_alignment()?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Parse `align N`
+    fn parse_alignment(&mut self) -> ParseResult<u32> {
+        self.expect_keyword("align")?;
+        let pos = self.pos;
+        let alignment: u32 = self.parse_uint()?;
+        if !alignment.is_power_of_two() {
+            return Err(self.error_at(pos, "alignment is not a power of two"));
+        }
+        Ok(alignment)
+    }
+
+    /// The alignment LLVM gives a `load` or `store` (`abi`) or `alloca` (not
+    /// `abi`) of the given type without an explicit alignment
+    #[cfg(feature = "llvm-11-or-greater")]
+    fn default_alignment(&self, ty: &TypeRef, abi: bool) -> u32 {
+        if abi {
+            self.abi_alignment(ty)
+        } else {
+            self.pref_alignment(ty)
+        }
+    }
+
+    #[cfg(feature = "llvm-10-or-lower")]
+    fn default_alignment(&self, _ty: &TypeRef, _abi: bool) -> u32 {
+        0
+    }
+
+    /// Parse the synchronization scope and memory ordering of an atomic
+    /// instruction, e.g. `syncscope("singlethread") acquire`
+    fn parse_atomicity(&mut self) -> ParseResult<Atomicity> {
+        let synch_scope = if self.eat_keyword("syncscope") {
+            self.expect(&Tok::LParen)?;
+            let scope = self.parse_string()?;
+            self.expect(&Tok::RParen)?;
+            if scope == "singlethread" {
+                SynchronizationScope::SingleThread
+            } else {
+                SynchronizationScope::System
+            }
+        } else {
+            SynchronizationScope::System
+        };
+        Ok(Atomicity {
+            synch_scope,
+            mem_ordering: self.parse_memory_ordering()?,
+        })
+    }
+
+    fn parse_memory_ordering(&mut self) -> ParseResult<MemoryOrdering> {
+        let ordering = match self.peek() {
+            Tok::Keyword(kw) => match kw.as_str() {
+                "unordered" => MemoryOrdering::Unordered,
+                "monotonic" => MemoryOrdering::Monotonic,
+                "acquire" => MemoryOrdering::Acquire,
+                "release" => MemoryOrdering::Release,
+                "acq_rel" => MemoryOrdering::AcquireRelease,
+                "seq_cst" => MemoryOrdering::SequentiallyConsistent,
+                _ => return self.error("expected ordering"),
+            },
+            _ => return self.error("expected ordering"),
+        };
+        self.bump();
+        Ok(ordering)
+    }
+
+    fn parse_rmw_bin_op(&mut self) -> ParseResult<RMWBinOp> {
+        let op = match self.peek() {
+            Tok::Keyword(kw) => match kw.as_str() {
+                "xchg" => RMWBinOp::Xchg,
+                "add" => RMWBinOp::Add,
+                "sub" => RMWBinOp::Sub,
+                "and" => RMWBinOp::And,
+                "nand" => RMWBinOp::Nand,
+                "or" => RMWBinOp::Or,
+                "xor" => RMWBinOp::Xor,
+                "max" => RMWBinOp::Max,
+                "min" => RMWBinOp::Min,
+                "umax" => RMWBinOp::UMax,
+                "umin" => RMWBinOp::UMin,
+                #[cfg(feature = "llvm-10-or-greater")]
+                "fadd" => RMWBinOp::FAdd,
+                #[cfg(feature = "llvm-10-or-greater")]
+                "fsub" => RMWBinOp::FSub,
+                #[cfg(feature = "llvm-15-or-greater")]
+                "fmax" => RMWBinOp::FMax,
+                #[cfg(feature = "llvm-15-or-greater")]
+                "fmin" => RMWBinOp::FMin,
+                #[cfg(feature = "llvm-16-or-greater")]
+                "uinc_wrap" => RMWBinOp::UIncWrap,
+                #[cfg(feature = "llvm-16-or-greater")]
+                "udec_wrap" => RMWBinOp::UDecWrap,
+                _ => return self.error("expected binary operation in atomicrmw"),
+            },
+            _ => return self.error("expected binary operation in atomicrmw"),
+        };
+        self.bump();
+        Ok(op)
+    }
+}
+
+/// The `debugloc` field of an instruction or terminator
+#[cfg(feature = "llvm-9-or-greater")]
+fn debugloc_mut(instr: &mut Either<Instruction, Terminator>) -> &mut Option<DebugLoc> {
+    match instr {
+        Either::Left(instr) => match instr {
+            Instruction::Add(i) => &mut i.debugloc,
+            Instruction::Sub(i) => &mut i.debugloc,
+            Instruction::Mul(i) => &mut i.debugloc,
+            Instruction::UDiv(i) => &mut i.debugloc,
+            Instruction::SDiv(i) => &mut i.debugloc,
+            Instruction::URem(i) => &mut i.debugloc,
+            Instruction::SRem(i) => &mut i.debugloc,
+            Instruction::And(i) => &mut i.debugloc,
+            Instruction::Or(i) => &mut i.debugloc,
+            Instruction::Xor(i) => &mut i.debugloc,
+            Instruction::Shl(i) => &mut i.debugloc,
+            Instruction::LShr(i) => &mut i.debugloc,
+            Instruction::AShr(i) => &mut i.debugloc,
+            Instruction::FAdd(i) => &mut i.debugloc,
+            Instruction::FSub(i) => &mut i.debugloc,
+            Instruction::FMul(i) => &mut i.debugloc,
+            Instruction::FDiv(i) => &mut i.debugloc,
+            Instruction::FRem(i) => &mut i.debugloc,
+            Instruction::FNeg(i) => &mut i.debugloc,
+            Instruction::ExtractElement(i) => &mut i.debugloc,
+            Instruction::InsertElement(i) => &mut i.debugloc,
+            Instruction::ShuffleVector(i) => &mut i.debugloc,
+            Instruction::ExtractValue(i) => &mut i.debugloc,
+            Instruction::InsertValue(i) => &mut i.debugloc,
+            Instruction::Alloca(i) => &mut i.debugloc,
+            Instruction::Load(i) => &mut i.debugloc,
+            Instruction::Store(i) => &mut i.debugloc,
+            Instruction::Fence(i) => &mut i.debugloc,
+            Instruction::CmpXchg(i) => &mut i.debugloc,
+            Instruction::AtomicRMW(i) => &mut i.debugloc,
+            Instruction::GetElementPtr(i) => &mut i.debugloc,
+            Instruction::Trunc(i) => &mut i.debugloc,
+            Instruction::ZExt(i) => &mut i.debugloc,
+            Instruction::SExt(i) => &mut i.debugloc,
+            Instruction::FPTrunc(i) => &mut i.debugloc,
+            Instruction::FPExt(i) => &mut i.debugloc,
+            Instruction::FPToUI(i) => &mut i.debugloc,
+            Instruction::FPToSI(i) => &mut i.debugloc,
+            Instruction::UIToFP(i) => &mut i.debugloc,
+            Instruction::SIToFP(i) => &mut i.debugloc,
+            Instruction::PtrToInt(i) => &mut i.debugloc,
+            Instruction::IntToPtr(i) => &mut i.debugloc,
+            Instruction::BitCast(i) => &mut i.debugloc,
+            Instruction::AddrSpaceCast(i) => &mut i.debugloc,
+            Instruction::ICmp(i) => &mut i.debugloc,
+            Instruction::FCmp(i) => &mut i.debugloc,
+            Instruction::Phi(i) => &mut i.debugloc,
+            Instruction::Select(i) => &mut i.debugloc,
+            Instruction::Freeze(i) => &mut i.debugloc,
+            Instruction::Call(i) => &mut i.debugloc,
+            Instruction::VAArg(i) => &mut i.debugloc,
+            Instruction::LandingPad(i) => &mut i.debugloc,
+            Instruction::CatchPad(i) => &mut i.debugloc,
+            Instruction::CleanupPad(i) => &mut i.debugloc,
+        },
+        Either::Right(term) => match term {
+            Terminator::Ret(t) => &mut t.debugloc,
+            Terminator::Br(t) => &mut t.debugloc,
+            Terminator::CondBr(t) => &mut t.debugloc,
+            Terminator::Switch(t) => &mut t.debugloc,
+            Terminator::IndirectBr(t) => &mut t.debugloc,
+            Terminator::Invoke(t) => &mut t.debugloc,
+            Terminator::Resume(t) => &mut t.debugloc,
+            Terminator::Unreachable(t) => &mut t.debugloc,
+            Terminator::CleanupRet(t) => &mut t.debugloc,
+            Terminator::CatchRet(t) => &mut t.debugloc,
+            Terminator::CatchSwitch(t) => &mut t.debugloc,
+            Terminator::CallBr(t) => &mut t.debugloc,
+        },
+    }
+}
@@ -0,0 +1,110 @@
+//! The default alignments LLVM gives to `load`, `store`, and `alloca`
+//! instructions without an explicit `align` (before LLVM 11, these had no
+//! alignment)
+
+use super::Parser;
+use crate::module::{Alignment, Alignments};
+use crate::types::{NamedStructDef, Type};
+
+impl<'a> Parser<'a> {
+    /// ABI alignment of the given type, in bytes
+    pub(super) fn abi_alignment(&self, ty: &Type) -> u32 {
+        self.alignment(ty, true)
+    }
+
+    /// Preferred alignment of the given type, in bytes
+    pub(super) fn pref_alignment(&self, ty: &Type) -> u32 {
+        self.alignment(ty, false)
+    }
+
+    fn alignment(&self, ty: &Type, abi: bool) -> u32 {
+        let alignments = &self.data_layout.alignments;
+        let pick = |alignment: &Alignment| {
+            let bits = if abi { alignment.abi } else { alignment.pref };
+            std::cmp::max(bits / 8, 1)
+        };
+        match ty {
+            Type::IntegerType { bits } => pick(alignments.int_alignment(*bits)),
+            Type::FPType(fpt) => {
+                let size = Alignments::fpt_size(*fpt);
+                match alignments.explicit_fp_alignment(size) {
+                    Some(alignment) => pick(alignment),
+                    None => natural_alignment(size),
+                }
+            },
+            Type::VectorType {
+                element_type,
+                num_elements,
+                ..
+            } => {
+                let size = self.scalar_size_in_bits(element_type) * (*num_elements as u32);
+                match alignments.explicit_vec_alignment(size) {
+                    Some(alignment) => pick(alignment),
+                    None => natural_alignment(size),
+                }
+            },
+            Type::X86_MMXType => match alignments.explicit_vec_alignment(64) {
+                Some(alignment) => pick(alignment),
+                None => 8,
+            },
+            #[cfg(feature = "llvm-12-or-greater")]
+            Type::X86_AMXType => 64,
+            Type::PointerType { addr_space, .. } => {
+                pick(&alignments.ptr_alignment(*addr_space).alignment)
+            },
+            #[cfg(feature = "llvm-14-or-greater")]
+            Type::OpaquePointerType { addr_space } => {
+                pick(&alignments.ptr_alignment(*addr_space).alignment)
+            },
+            Type::ArrayType { element_type, .. } => self.alignment(element_type, abi),
+            Type::StructType {
+                element_types,
+                is_packed,
+            } => {
+                if *is_packed && abi {
+                    return 1;
+                }
+                // the alignment of the struct's layout is the largest ABI
+                // alignment of its members
+                let layout_alignment = if *is_packed {
+                    1
+                } else {
+                    element_types
+                        .iter()
+                        .map(|ty| self.alignment(ty, true))
+                        .max()
+                        .unwrap_or(1)
+                };
+                std::cmp::max(pick(alignments.agg_alignment()), layout_alignment)
+            },
+            Type::NamedStructType { name } => match self.types.named_struct_def(name) {
+                NamedStructDef::Defined(ty) => self.alignment(ty, abi),
+                NamedStructDef::Opaque => 1,
+            },
+            _ => 1,
+        }
+    }
+
+    /// Size of the given scalar (vector element) type, in bits
+    fn scalar_size_in_bits(&self, ty: &Type) -> u32 {
+        match ty {
+            Type::IntegerType { bits } => *bits,
+            Type::FPType(fpt) => Alignments::fpt_size(*fpt),
+            Type::PointerType { addr_space, .. } => {
+                self.data_layout.alignments.ptr_alignment(*addr_space).size
+            },
+            #[cfg(feature = "llvm-14-or-greater")]
+            Type::OpaquePointerType { addr_space } => {
+                self.data_layout.alignments.ptr_alignment(*addr_space).size
+            },
+            _ => 0,
+        }
+    }
+}
+
+/// The natural alignment of a type of the given size in bits, in bytes: its
+/// store size, rounded up to a power of two
+fn natural_alignment(size_in_bits: u32) -> u32 {
+    let store_size = (size_in_bits + 7) >> 3;
+    std::cmp::max(store_size.next_power_of_two(), 1)
+}
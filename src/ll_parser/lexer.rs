@@ -0,0 +1,465 @@
+use super::ParseError;
+use crate::escape::{unescape, unescape_bytes};
+
+/// A token of LLVM's textual IR
+#[derive(PartialEq, Clone, Debug)]
+pub(super) enum Tok {
+    Eof,
+    Equal,
+    Comma,
+    Star,
+    LParen,
+    RParen,
+    LSquare,
+    RSquare,
+    LBrace,
+    RBrace,
+    Less,
+    Greater,
+    Exclaim,
+    Bar,
+    /// `...`
+    DotDotDot,
+    /// `%name` or `%"name"`
+    LocalVar(String),
+    /// `%12`
+    LocalId(usize),
+    /// `@name` or `@"name"`
+    GlobalVar(String),
+    /// `@12`
+    GlobalId(usize),
+    /// `$name` or `$"name"`
+    ComdatVar(String),
+    /// `!name`, as in `!dbg` or `!llvm.module.flags`
+    MetadataVar(String),
+    /// `#12`
+    AttrGrpId(u32),
+    /// `^12`, which starts a module summary entry
+    SummaryId(usize),
+    /// `name:` or `"name":`, which starts a basic block
+    LabelStr(String),
+    /// `12:`, which starts a numbered basic block
+    LabelId(usize),
+    /// Any bare word, such as `define`, `i32`, or `nounwind`
+    Keyword(String),
+    /// An integer literal, such as `12` or `-1`, or a hex integer literal
+    /// such as `u0xFF`, already converted to its decimal digits
+    Int(String),
+    /// A floating-point literal, such as `1.5e+10` or `0x3FF0000000000000`
+    Float(String),
+    /// A string constant, with its escapes already processed
+    Str(String),
+    /// A `c"..."` constant, with its escapes already processed
+    CStr(Vec<u8>),
+}
+
+impl Tok {
+    /// How the token is described in error messages
+    pub(super) fn describe(&self) -> String {
+        match self {
+            Tok::Eof => "end of file".to_owned(),
+            Tok::Equal => "'='".to_owned(),
+            Tok::Comma => "','".to_owned(),
+            Tok::Star => "'*'".to_owned(),
+            Tok::LParen => "'('".to_owned(),
+            Tok::RParen => "')'".to_owned(),
+            Tok::LSquare => "'['".to_owned(),
+            Tok::RSquare => "']'".to_owned(),
+            Tok::LBrace => "'{'".to_owned(),
+            Tok::RBrace => "'}'".to_owned(),
+            Tok::Less => "'<'".to_owned(),
+            Tok::Greater => "'>'".to_owned(),
+            Tok::Exclaim => "'!'".to_owned(),
+            Tok::Bar => "'|'".to_owned(),
+            Tok::DotDotDot => "'...'".to_owned(),
+            Tok::LocalVar(name) => format!("'%{}'", name),
+            Tok::LocalId(id) => format!("'%{}'", id),
+            Tok::GlobalVar(name) => format!("'@{}'", name),
+            Tok::GlobalId(id) => format!("'@{}'", id),
+            Tok::ComdatVar(name) => format!("'${}'", name),
+            Tok::MetadataVar(name) => format!("'!{}'", name),
+            Tok::AttrGrpId(id) => format!("'#{}'", id),
+            Tok::SummaryId(id) => format!("'^{}'", id),
+            Tok::LabelStr(name) => format!("label '{}:'", name),
+            Tok::LabelId(id) => format!("label '{}:'", id),
+            Tok::Keyword(kw) => format!("'{}'", kw),
+            Tok::Int(i) => format!("'{}'", i),
+            Tok::Float(f) => format!("'{}'", f),
+            Tok::Str(s) => format!("string {:?}", s),
+            Tok::CStr(_) => "c\"...\" string".to_owned(),
+        }
+    }
+}
+
+#[derive(PartialEq, Clone, Debug)]
+pub(super) struct Token {
+    pub tok: Tok,
+    /// 1-based line number where the token starts
+    pub line: usize,
+    /// 1-based column (in characters) where the token starts
+    pub col: usize,
+    /// Byte offsets of the start and end of the token in the source text
+    pub span: (usize, usize),
+}
+
+/// Split the given text into `Token`s. The last token is always `Tok::Eof`.
+pub(super) fn tokenize(text: &str) -> Result<Vec<Token>, ParseError> {
+    let mut lexer = Lexer {
+        src: text.as_bytes(),
+        text,
+        pos: 0,
+        line: 1,
+        line_start: 0,
+    };
+    let mut tokens = Vec::new();
+    loop {
+        let token = lexer.next_token()?;
+        let done = token.tok == Tok::Eof;
+        tokens.push(token);
+        if done {
+            return Ok(tokens);
+        }
+    }
+}
+
+struct Lexer<'a> {
+    src: &'a [u8],
+    text: &'a str,
+    pos: usize,
+    line: usize,
+    /// byte offset of the start of the current line
+    line_start: usize,
+}
+
+/// Can this character appear in an unquoted name, e.g., `%foo.bar$1`?
+fn is_name_char(c: u8) -> bool {
+    c.is_ascii_alphanumeric() || c == b'-' || c == b'$' || c == b'.' || c == b'_'
+}
+
+/// Can this character start an unquoted name? (Names starting with digits are
+/// numbered values instead.)
+fn is_name_start(c: u8) -> bool {
+    is_name_char(c) && !c.is_ascii_digit()
+}
+
+impl<'a> Lexer<'a> {
+    fn peek(&self, offset: usize) -> Option<u8> {
+        self.src.get(self.pos + offset).copied()
+    }
+
+    /// The 1-based column of the given byte offset, which must be on the
+    /// current line
+    fn col_of(&self, offset: usize) -> usize {
+        self.text[self.line_start .. offset].chars().count() + 1
+    }
+
+    fn error_at(&self, offset: usize, message: impl Into<String>) -> ParseError {
+        ParseError {
+            line: self.line,
+            column: self.col_of(offset),
+            message: message.into(),
+        }
+    }
+
+    fn skip_whitespace_and_comments(&mut self) {
+        while let Some(c) = self.peek(0) {
+            match c {
+                b'\n' => {
+                    self.pos += 1;
+                    self.line += 1;
+                    self.line_start = self.pos;
+                },
+                b' ' | b'\t' | b'\r' => self.pos += 1,
+                b';' => {
+                    while let Some(c) = self.peek(0) {
+                        if c == b'\n' {
+                            break;
+                        }
+                        self.pos += 1;
+                    }
+                },
+                _ => break,
+            }
+        }
+    }
+
+    fn next_token(&mut self) -> Result<Token, ParseError> {
+        self.skip_whitespace_and_comments();
+        let start = self.pos;
+        let line = self.line;
+        let col = self.col_of(start);
+        let tok = match self.peek(0) {
+            None => Tok::Eof,
+            Some(c) => self.lex_token(c, start)?,
+        };
+        Ok(Token {
+            tok,
+            line,
+            col,
+            span: (start, self.pos),
+        })
+    }
+
+    fn lex_token(&mut self, c: u8, start: usize) -> Result<Tok, ParseError> {
+        let punct = match c {
+            b'=' => Some(Tok::Equal),
+            b',' => Some(Tok::Comma),
+            b'*' => Some(Tok::Star),
+            b'(' => Some(Tok::LParen),
+            b')' => Some(Tok::RParen),
+            b'[' => Some(Tok::LSquare),
+            b']' => Some(Tok::RSquare),
+            b'{' => Some(Tok::LBrace),
+            b'}' => Some(Tok::RBrace),
+            b'<' => Some(Tok::Less),
+            b'>' => Some(Tok::Greater),
+            b'|' => Some(Tok::Bar),
+            _ => None,
+        };
+        if let Some(tok) = punct {
+            self.pos += 1;
+            return Ok(tok);
+        }
+        match c {
+            b'%' => self.lex_var(start, Tok::LocalVar, Tok::LocalId),
+            b'@' => self.lex_var(start, Tok::GlobalVar, Tok::GlobalId),
+            b'$' => {
+                self.pos += 1;
+                match self.peek(0) {
+                    Some(b'"') => Ok(Tok::ComdatVar(self.lex_quoted(start)?)),
+                    Some(c) if is_name_char(c) => Ok(Tok::ComdatVar(self.lex_name())),
+                    _ => Err(self.error_at(start, "expected a comdat name after '$'")),
+                }
+            },
+            b'!' => {
+                self.pos += 1;
+                match self.peek(0) {
+                    // `!"foo"`, `!{`, and `!12` are lexed as `!` followed by
+                    // a separate token, as LLVM does
+                    Some(c) if is_name_start(c) || c == b'\\' => {
+                        let name_start = self.pos;
+                        while let Some(c) = self.peek(0) {
+                            if is_name_char(c) || c == b'\\' {
+                                self.pos += 1;
+                            } else {
+                                break;
+                            }
+                        }
+                        Ok(Tok::MetadataVar(unescape(&self.src[name_start .. self.pos])))
+                    },
+                    _ => Ok(Tok::Exclaim),
+                }
+            },
+            b'#' => {
+                self.pos += 1;
+                let digits = self.take_digits();
+                digits.parse().map(Tok::AttrGrpId).map_err(|_| {
+                    self.error_at(start, "expected an attribute group number after '#'")
+                })
+            },
+            b'^' => {
+                self.pos += 1;
+                let digits = self.take_digits();
+                digits
+                    .parse()
+                    .map(Tok::SummaryId)
+                    .map_err(|_| self.error_at(start, "expected a summary entry number after '^'"))
+            },
+            b'"' => {
+                let s = self.lex_quoted(start)?;
+                if self.peek(0) == Some(b':') {
+                    self.pos += 1;
+                    Ok(Tok::LabelStr(s))
+                } else {
+                    Ok(Tok::Str(s))
+                }
+            },
+            b'.' if self.src[self.pos ..].starts_with(b"...") => {
+                self.pos += 3;
+                Ok(Tok::DotDotDot)
+            },
+            b'-' | b'+' | b'0' ..= b'9' => self.lex_number(start),
+            c if is_name_start(c) => {
+                if c == b'c' && self.peek(1) == Some(b'"') {
+                    self.pos += 1;
+                    let bytes = self.lex_quoted_bytes(start)?;
+                    return Ok(Tok::CStr(bytes));
+                }
+                if (c == b's' || c == b'u') && self.src[self.pos + 1 ..].starts_with(b"0x") {
+                    return self.lex_hex_int(start);
+                }
+                let name = self.lex_name();
+                if self.peek(0) == Some(b':') {
+                    self.pos += 1;
+                    Ok(Tok::LabelStr(name))
+                } else {
+                    Ok(Tok::Keyword(name))
+                }
+            },
+            _ => {
+                let c = self.text[start ..].chars().next().unwrap_or('?');
+                Err(self.error_at(start, format!("unexpected character {:?}", c)))
+            },
+        }
+    }
+
+    /// Lex `%name`, `%"name"`, or `%12` (or the same with `@`)
+    fn lex_var(
+        &mut self,
+        start: usize,
+        named: fn(String) -> Tok,
+        numbered: fn(usize) -> Tok,
+    ) -> Result<Tok, ParseError> {
+        self.pos += 1;
+        match self.peek(0) {
+            Some(b'"') => Ok(named(self.lex_quoted(start)?)),
+            Some(c) if c.is_ascii_digit() => {
+                let digits = self.take_digits();
+                digits
+                    .parse()
+                    .map(numbered)
+                    .map_err(|_| self.error_at(start, "value number is too large"))
+            },
+            Some(c) if is_name_start(c) => Ok(named(self.lex_name())),
+            _ => Err(self.error_at(start, "expected a name after the sigil")),
+        }
+    }
+
+    fn take_digits(&mut self) -> &'a str {
+        let digits_start = self.pos;
+        while let Some(c) = self.peek(0) {
+            if c.is_ascii_digit() {
+                self.pos += 1;
+            } else {
+                break;
+            }
+        }
+        &self.text[digits_start .. self.pos]
+    }
+
+    fn lex_name(&mut self) -> String {
+        let name_start = self.pos;
+        while let Some(c) = self.peek(0) {
+            if is_name_char(c) {
+                self.pos += 1;
+            } else {
+                break;
+            }
+        }
+        self.text[name_start .. self.pos].to_owned()
+    }
+
+    fn lex_quoted(&mut self, start: usize) -> Result<String, ParseError> {
+        let bytes = self.lex_quoted_bytes(start)?;
+        Ok(String::from_utf8_lossy(&bytes).into_owned())
+    }
+
+    /// Lex a string in double quotes, starting at the opening quote
+    fn lex_quoted_bytes(&mut self, start: usize) -> Result<Vec<u8>, ParseError> {
+        self.pos += 1; // the opening quote
+        let contents_start = self.pos;
+        loop {
+            match self.peek(0) {
+                None => return Err(self.error_at(start, "unterminated string constant")),
+                Some(b'"') => break,
+                Some(b'\n') => {
+                    self.pos += 1;
+                    self.line += 1;
+                    self.line_start = self.pos;
+                },
+                Some(_) => self.pos += 1,
+            }
+        }
+        let bytes = unescape_bytes(&self.src[contents_start .. self.pos]);
+        self.pos += 1; // the closing quote
+        Ok(bytes)
+    }
+
+    /// Lex `s0x...` or `u0x...`
+    fn lex_hex_int(&mut self, start: usize) -> Result<Tok, ParseError> {
+        let signed = self.peek(0) == Some(b's');
+        self.pos += 3;
+        let digits_start = self.pos;
+        while let Some(c) = self.peek(0) {
+            if c.is_ascii_hexdigit() {
+                self.pos += 1;
+            } else {
+                break;
+            }
+        }
+        let digits = &self.text[digits_start .. self.pos];
+        let value = u128::from_str_radix(digits, 16)
+            .map_err(|_| self.error_at(start, "invalid hexadecimal integer"))?;
+        // For `s0x`, the most significant hex digit gives the sign
+        let bits = 4 * digits.len() as u32;
+        if signed && bits < 128 && value >> (bits - 1) & 1 == 1 {
+            let negated = (1u128 << bits) - value;
+            Ok(Tok::Int(format!("-{}", negated)))
+        } else {
+            Ok(Tok::Int(value.to_string()))
+        }
+    }
+
+    fn lex_number(&mut self, start: usize) -> Result<Tok, ParseError> {
+        // hexadecimal floating-point constants, e.g., `0x3FF0000000000000` or
+        // `0xK4000C000000000000000`
+        if self.src[self.pos ..].starts_with(b"0x") {
+            self.pos += 2;
+            if let Some(b'K') | Some(b'L') | Some(b'M') | Some(b'H') | Some(b'R') = self.peek(0) {
+                self.pos += 1;
+            }
+            while let Some(c) = self.peek(0) {
+                if c.is_ascii_hexdigit() {
+                    self.pos += 1;
+                } else {
+                    break;
+                }
+            }
+            return Ok(Tok::Float(self.text[start .. self.pos].to_owned()));
+        }
+        if let Some(b'-') | Some(b'+') = self.peek(0) {
+            self.pos += 1;
+        }
+        let first_digit = self.pos;
+        self.take_digits();
+        if self.pos == first_digit {
+            // e.g. `-foo`, which can only be a (strange) label
+            self.pos = start;
+            let name = self.lex_name();
+            if self.peek(0) == Some(b':') {
+                self.pos += 1;
+                return Ok(Tok::LabelStr(name));
+            }
+            return Err(self.error_at(start, format!("unexpected token {:?}", name)));
+        }
+        match self.peek(0) {
+            Some(b'.') => {
+                self.pos += 1;
+                self.take_digits();
+                if let Some(b'e') | Some(b'E') = self.peek(0) {
+                    let save = self.pos;
+                    self.pos += 1;
+                    if let Some(b'-') | Some(b'+') = self.peek(0) {
+                        self.pos += 1;
+                    }
+                    if self.take_digits().is_empty() {
+                        self.pos = save;
+                    }
+                }
+                Ok(Tok::Float(self.text[start .. self.pos].to_owned()))
+            },
+            Some(b':') if self.src[start] != b'-' && self.src[start] != b'+' => {
+                let label = self.text[start .. self.pos]
+                    .parse()
+                    .map_err(|_| self.error_at(start, "label number is too large"))?;
+                self.pos += 1;
+                Ok(Tok::LabelId(label))
+            },
+            _ => Ok(Tok::Int(
+                self.text[start .. self.pos]
+                    .trim_start_matches('+')
+                    .to_owned(),
+            )),
+        }
+    }
+}
@@ -0,0 +1,392 @@
+//! Metadata is only parsed as far as `llvm-ir` represents it: metadata
+//! attachments (as `AttachedMetadata`), and debug locations
+
+use super::lexer::Tok;
+use super::{ParseResult, Parser};
+#[cfg(feature = "llvm-9-or-greater")]
+use crate::debugloc::DebugLoc;
+use crate::module::{AttachedMetadata, MetadataAttachment};
+
+/// The metadata kinds built into LLVM, in the order of their kind IDs. Other
+/// kinds get IDs after these, in the order they're first used.
+pub(super) const FIXED_METADATA_KINDS: &[&str] = &[
+    "dbg",
+    "tbaa",
+    "prof",
+    "fpmath",
+    "range",
+    "tbaa.struct",
+    "invariant.load",
+    "alias.scope",
+    "noalias",
+    "nontemporal",
+    "llvm.mem.parallel_loop_access",
+    "nonnull",
+    "dereferenceable",
+    "dereferenceable_or_null",
+    "make.implicit",
+    "unpredictable",
+    "invariant.group",
+    "align",
+    "llvm.loop",
+    "type",
+    "section_prefix",
+    "absolute_symbol",
+    "associated",
+    "callees",
+    "irr_loop",
+    "llvm.access.group",
+    "callback",
+    "llvm.preserve.access.index",
+    "vcall_visibility",
+    "noundef",
+    "annotation",
+    "nosanitize",
+    "func_sanitize",
+    "exclude",
+    "memprof",
+    "callsite",
+    "kcfi_type",
+    "pcsections",
+    "DIAssignID",
+    "coro.outside.frame",
+];
+
+impl<'a> Parser<'a> {
+    /// Parse a metadata attachment, e.g. `!dbg !12`, giving the kind and the
+    /// number of the attached node
+    pub(super) fn parse_metadata_attachment(&mut self) -> ParseResult<(String, usize)> {
+        let kind = match self.bump() {
+            Tok::MetadataVar(kind) => kind,
+            _ => {
+                self.pos -= 1;
+                return self.error("expected metadata attachment");
+            },
+        };
+        self.expect(&Tok::Exclaim)?;
+        let node = self.parse_uint()?;
+        if self.first_pass
+            && !FIXED_METADATA_KINDS.contains(&kind.as_str())
+            && !self.custom_metadata_kinds.contains(&kind)
+        {
+            self.custom_metadata_kinds.push(kind.clone());
+        }
+        Ok((kind, node))
+    }
+
+    /// The ID LLVM gives to the metadata kind with the given name
+    fn metadata_kind_id(&self, kind: &str) -> usize {
+        match FIXED_METADATA_KINDS.iter().position(|k| *k == kind) {
+            Some(id) => id,
+            None => {
+                FIXED_METADATA_KINDS.len()
+                    + self
+                        .custom_metadata_kinds
+                        .iter()
+                        .position(|k| k == kind)
+                        .unwrap_or(usize::MAX - FIXED_METADATA_KINDS.len())
+            },
+        }
+    }
+
+    /// Convert the metadata attachments of a function or global variable.
+    /// LLVM keeps one attachment of each kind, ordered by kind ID.
+    pub(super) fn metadata_attachments(
+        &mut self,
+        attachments: Vec<(String, usize)>,
+    ) -> Vec<MetadataAttachment> {
+        if self.first_pass {
+            // the nodes may not have been seen yet
+            return vec![];
+        }
+        let mut deduped: Vec<(String, usize)> = vec![];
+        for (kind, node) in attachments {
+            deduped.retain(|(k, _)| *k != kind);
+            deduped.push((kind, node));
+        }
+        deduped.sort_by_key(|(kind, _)| self.metadata_kind_id(kind));
+        deduped
+            .into_iter()
+            .map(|(kind, node)| MetadataAttachment {
+                node: self.attached_metadata_node(node, &mut vec![]),
+                kind,
+            })
+            .collect()
+    }
+
+    /// Convert the numbered metadata node `id`. `path` holds the nodes we're
+    /// in the middle of converting, so that cyclic nodes don't loop forever.
+    fn attached_metadata_node(&mut self, id: usize, path: &mut Vec<usize>) -> AttachedMetadata {
+        let node_pos = match self.metadata_nodes.get(&id) {
+            Some(&pos) => pos,
+            None => return AttachedMetadata::Other(format!("!{}", id)),
+        };
+        let saved_pos = self.pos;
+        self.pos = node_pos;
+        let node = if path.contains(&id) {
+            self.metadata_node_source()
+        } else {
+            path.push(id);
+            let node = self.parse_attached_metadata(path);
+            path.pop();
+            node
+        };
+        self.pos = saved_pos;
+        node
+    }
+
+    /// Parse a metadata node (or operand of one) at the current position
+    fn parse_attached_metadata(&mut self, path: &mut Vec<usize>) -> AttachedMetadata {
+        let start = self.pos;
+        let distinct = self.eat_keyword("distinct");
+        match (self.peek().clone(), self.peek_at(1).clone()) {
+            (Tok::Exclaim, Tok::LBrace) => {
+                self.bump();
+                self.bump();
+                let mut operands = vec![];
+                while *self.peek() != Tok::RBrace && *self.peek() != Tok::Eof {
+                    if !operands.is_empty() && !self.eat(&Tok::Comma) {
+                        break;
+                    }
+                    if self.eat_keyword("null") {
+                        operands.push(None);
+                    } else {
+                        operands.push(Some(self.parse_attached_metadata(path)));
+                    }
+                }
+                self.bump();
+                AttachedMetadata::Tuple(operands)
+            },
+            (Tok::Exclaim, Tok::Str(s)) if !distinct => {
+                self.bump();
+                self.bump();
+                AttachedMetadata::String(s)
+            },
+            (Tok::Exclaim, Tok::Int(_)) if !distinct => {
+                self.bump();
+                match self.parse_uint() {
+                    Ok(id) => self.attached_metadata_node(id, path),
+                    Err(_) => AttachedMetadata::Other(self.source_since(start).to_owned()),
+                }
+            },
+            (Tok::MetadataVar(_), _) => {
+                self.pos = start;
+                self.metadata_node_source()
+            },
+            _ => match self.parse_typed_constant() {
+                Ok((_, constant)) => AttachedMetadata::Constant(constant),
+                Err(_) => {
+                    self.pos = start;
+                    self.metadata_node_source()
+                },
+            },
+        }
+    }
+
+    /// Skip over the metadata node at the current position, and get its source
+    /// text
+    fn metadata_node_source(&mut self) -> AttachedMetadata {
+        let start = self.pos;
+        let _ = self.skip_metadata_node();
+        AttachedMetadata::Other(self.source_since(start).to_owned())
+    }
+
+    /// Skip over a metadata node definition, e.g. `!{!1, !2}` or
+    /// `distinct !DISubprogram(...)`
+    pub(super) fn skip_metadata_node(&mut self) -> ParseResult<()> {
+        self.eat_keyword("distinct");
+        match self.peek() {
+            Tok::Exclaim => {
+                self.bump();
+                self.skip_balanced()
+            },
+            Tok::MetadataVar(_) => {
+                self.bump();
+                if *self.peek() != Tok::LParen {
+                    return self.error("expected '(' here");
+                }
+                self.skip_balanced()
+            },
+            _ => self.error("expected metadata node"),
+        }
+    }
+
+    /// Skip over a metadata operand of an instruction, e.g. `!12`, `!"foo"`,
+    /// `!{}`, `!DIExpression()`, or `i32 %x`
+    pub(super) fn skip_metadata_value(&mut self) -> ParseResult<()> {
+        match self.peek() {
+            Tok::Exclaim | Tok::MetadataVar(_) => self.skip_metadata_node(),
+            Tok::Keyword(kw) if kw == "distinct" => self.skip_metadata_node(),
+            _ => {
+                let ty = self.parse_type()?;
+                self.parse_value(&ty)?;
+                Ok(())
+            },
+        }
+    }
+
+    // ********** //
+    // debug info //
+    // ********** //
+
+    /// The kind (e.g. `DILocation`) and fields of the specialized metadata
+    /// node `id`, with the token position of each field's value
+    #[cfg(feature = "llvm-9-or-greater")]
+    fn specialized_node(&self, id: usize) -> Option<(&str, Vec<(&str, usize)>)> {
+        let mut pos = *self.metadata_nodes.get(&id)?;
+        if self.tokens[pos].tok == Tok::Keyword("distinct".into()) {
+            pos += 1;
+        }
+        let kind = match &self.tokens[pos].tok {
+            Tok::MetadataVar(kind) => kind.as_str(),
+            _ => return None,
+        };
+        pos += 1;
+        if self.tokens[pos].tok != Tok::LParen {
+            return None;
+        }
+        pos += 1;
+        let mut fields = vec![];
+        loop {
+            match &self.tokens[pos].tok {
+                Tok::LabelStr(name) => {
+                    fields.push((name.as_str(), pos + 1));
+                    pos += 1;
+                    // skip the value, up to the next ',' or ')' at this level
+                    let mut depth = 0usize;
+                    loop {
+                        match &self.tokens[pos].tok {
+                            Tok::LParen | Tok::LSquare | Tok::LBrace | Tok::Less => depth += 1,
+                            Tok::RParen | Tok::RSquare | Tok::RBrace | Tok::Greater
+                                if depth > 0 =>
+                            {
+                                depth -= 1
+                            },
+                            Tok::Comma | Tok::RParen if depth == 0 => break,
+                            Tok::Eof => return None,
+                            _ => {},
+                        }
+                        pos += 1;
+                    }
+                },
+                Tok::Comma => pos += 1,
+                _ => return Some((kind, fields)),
+            }
+        }
+    }
+
+    /// The value of an integer field of a specialized metadata node
+    #[cfg(feature = "llvm-9-or-greater")]
+    fn uint_field(&self, fields: &[(&str, usize)], name: &str) -> Option<u32> {
+        let pos = fields.iter().find(|(n, _)| *n == name)?.1;
+        match &self.tokens[pos].tok {
+            Tok::Int(i) => i.parse().ok(),
+            _ => None,
+        }
+    }
+
+    /// The node number referenced by a field of a specialized metadata node,
+    /// e.g. `file: !3`
+    #[cfg(feature = "llvm-9-or-greater")]
+    fn node_field(&self, fields: &[(&str, usize)], name: &str) -> Option<usize> {
+        let pos = fields.iter().find(|(n, _)| *n == name)?.1;
+        match (&self.tokens[pos].tok, &self.tokens[pos + 1].tok) {
+            (Tok::Exclaim, Tok::Int(i)) => i.parse().ok(),
+            _ => None,
+        }
+    }
+
+    #[cfg(feature = "llvm-9-or-greater")]
+    fn string_field(&self, fields: &[(&str, usize)], name: &str) -> Option<String> {
+        let pos = fields.iter().find(|(n, _)| *n == name)?.1;
+        match &self.tokens[pos].tok {
+            Tok::Str(s) => Some(s.clone()),
+            _ => None,
+        }
+    }
+
+    /// The filename and directory of the scope `scope` (or of the scope whose
+    /// fields are `fields`, if it's a `DIFile`, its own), or `None` if LLVM
+    /// wouldn't report a filename
+    #[cfg(feature = "llvm-9-or-greater")]
+    fn scope_file(&self, kind: &str, fields: &[(&str, usize)]) -> Option<(String, Option<String>)> {
+        let file_fields = if kind == "DIFile" {
+            fields.to_vec()
+        } else {
+            match self.node_field(fields, "file") {
+                Some(file) => self.specialized_node(file)?.1,
+                // a scope without a file has an empty (but present)
+                // filename and directory
+                None => return Some((String::new(), Some(String::new()))),
+            }
+        };
+        let filename = self
+            .string_field(&file_fields, "filename")
+            .filter(|f| !f.is_empty())?;
+        let directory = self
+            .string_field(&file_fields, "directory")
+            .filter(|d| !d.is_empty());
+        Some((filename, directory))
+    }
+
+    /// The `DebugLoc` of an instruction with the given `!dbg` attachment,
+    /// which should be a `DILocation`
+    #[cfg(feature = "llvm-9-or-greater")]
+    pub(super) fn instruction_debugloc(&self, node: usize) -> Option<DebugLoc> {
+        let (kind, fields) = self.specialized_node(node)?;
+        if kind != "DILocation" {
+            return None;
+        }
+        let (scope_kind, scope_fields) =
+            self.specialized_node(self.node_field(&fields, "scope")?)?;
+        let (filename, directory) = self.scope_file(scope_kind, &scope_fields)?;
+        Some(DebugLoc {
+            line: self.uint_field(&fields, "line").unwrap_or(0),
+            col: Some(self.uint_field(&fields, "column").unwrap_or(0)),
+            filename,
+            directory,
+        })
+    }
+
+    /// The `DebugLoc` of a function with the given metadata attachments,
+    /// from its `DISubprogram`
+    #[cfg(feature = "llvm-9-or-greater")]
+    pub(super) fn function_debugloc(&self, attachments: &[(String, usize)]) -> Option<DebugLoc> {
+        if self.first_pass {
+            return None;
+        }
+        let node = attachments.iter().rev().find(|(kind, _)| kind == "dbg")?.1;
+        self.variable_debugloc(node, "DISubprogram")
+    }
+
+    /// The `DebugLoc` of a global variable whose (first) `!dbg` attachment is
+    /// the given `DIGlobalVariableExpression`
+    #[cfg(feature = "llvm-9-or-greater")]
+    pub(super) fn global_variable_debugloc(&self, node: usize) -> Option<DebugLoc> {
+        if self.first_pass {
+            return None;
+        }
+        let (kind, fields) = self.specialized_node(node)?;
+        if kind != "DIGlobalVariableExpression" {
+            return None;
+        }
+        self.variable_debugloc(self.node_field(&fields, "var")?, "DIGlobalVariable")
+    }
+
+    /// The `DebugLoc` (without a column) given by the `line` and `file` of
+    /// the node `node`, which should be of kind `expected_kind`
+    #[cfg(feature = "llvm-9-or-greater")]
+    fn variable_debugloc(&self, node: usize, expected_kind: &str) -> Option<DebugLoc> {
+        let (kind, fields) = self.specialized_node(node)?;
+        if kind != expected_kind {
+            return None;
+        }
+        let (filename, directory) = self.scope_file(kind, &fields)?;
+        Some(DebugLoc {
+            line: self.uint_field(&fields, "line").unwrap_or(0),
+            col: None,
+            filename,
+            directory,
+        })
+    }
+}
@@ -0,0 +1,545 @@
+use super::lexer::Tok;
+use super::{ParseResult, Parser};
+use crate::constant::Constant;
+use crate::types::{FPType, NamedStructDef, Type, TypeRef};
+use std::collections::HashSet;
+
+impl<'a> Parser<'a> {
+    /// Does the module use opaque pointers?
+    pub(super) fn opaque_pointers(&self) -> bool {
+        self.opaque_pointers
+    }
+
+    /// Record where each named or numbered type is defined, and add the
+    /// definitions of the named struct types. This is done before anything
+    /// else, because types may be used before they are defined.
+    pub(super) fn define_types(&mut self) -> ParseResult<()> {
+        for pos in 0 .. self.tokens.len().saturating_sub(2) {
+            let is_def = self.tokens[pos].col == 1
+                && self.tokens[pos + 1].tok == Tok::Equal
+                && self.tokens[pos + 2].tok == Tok::Keyword("type".into());
+            if !is_def {
+                continue;
+            }
+            match &self.tokens[pos].tok {
+                Tok::LocalVar(name) => {
+                    let previous = self.named_types.insert(name.clone(), pos + 3);
+                    if previous.is_some() {
+                        return Err(
+                            self.error_at(pos, format!("redefinition of type named '%{}'", name))
+                        );
+                    }
+                },
+                Tok::LocalId(id) => {
+                    let previous = self.numbered_types.insert(*id, pos + 3);
+                    if previous.is_some() {
+                        return Err(self.error_at(pos, format!("redefinition of type '%{}'", id)));
+                    }
+                },
+                _ => {},
+            }
+        }
+        let mut named_types: Vec<(String, usize)> = self
+            .named_types
+            .iter()
+            .map(|(name, pos)| (name.clone(), *pos))
+            .collect();
+        named_types.sort_by_key(|(_, pos)| *pos);
+        for (name, pos) in &named_types {
+            self.pos = *pos;
+            let def = if self.eat_keyword("opaque") {
+                NamedStructDef::Opaque
+            } else {
+                match self.peek() {
+                    Tok::LBrace | Tok::Less => NamedStructDef::Defined(self.parse_type()?),
+                    _ => return self.error("only struct types can be named"),
+                }
+            };
+            self.types.add_named_struct_def(name.clone(), def);
+        }
+        // a struct may contain itself only through a pointer
+        for (name, pos) in named_types {
+            if let NamedStructDef::Defined(def) = self.types.named_struct_def(&name) {
+                if self.contains_named_type(def, &name, &mut HashSet::new()) {
+                    return Err(self.error_at(pos - 3, "invalid recursive type"));
+                }
+            }
+        }
+        self.pos = 0;
+        Ok(())
+    }
+
+    /// Does `ty` contain the named struct type `name` by value, i.e., other
+    /// than through a pointer? `visited` holds the named struct types already
+    /// looked into.
+    fn contains_named_type(&self, ty: &TypeRef, name: &str, visited: &mut HashSet<String>) -> bool {
+        match ty.as_ref() {
+            Type::NamedStructType { name: other } => {
+                if other == name {
+                    return true;
+                }
+                if !visited.insert(other.clone()) {
+                    return false;
+                }
+                match self.types.named_struct_def(other) {
+                    NamedStructDef::Defined(def) => self.contains_named_type(def, name, visited),
+                    NamedStructDef::Opaque => false,
+                }
+            },
+            Type::StructType { element_types, .. } => element_types
+                .iter()
+                .any(|element| self.contains_named_type(element, name, visited)),
+            Type::ArrayType { element_type, .. } | Type::VectorType { element_type, .. } => {
+                self.contains_named_type(element_type, name, visited)
+            },
+            _ => false,
+        }
+    }
+
+    /// The type of the element of the aggregate type `ty` with the given
+    /// index, or `None` if there is no such element. `index` is `None` if it
+    /// isn't a constant, which is only allowed for arrays and vectors. Only
+    /// `getelementptr` (`gep`) can index into vectors, and may index past the
+    /// end of an array.
+    fn element_type(&self, ty: &TypeRef, index: Option<u64>, gep: bool) -> Option<TypeRef> {
+        match ty.as_ref() {
+            Type::NamedStructType { name } => match self.types.named_struct_def(name) {
+                NamedStructDef::Defined(def) => self.element_type(def, index, gep),
+                NamedStructDef::Opaque => None,
+            },
+            Type::StructType { element_types, .. } => {
+                let index = index?;
+                if index < element_types.len() as u64 {
+                    Some(element_types[index as usize].clone())
+                } else {
+                    None
+                }
+            },
+            Type::ArrayType {
+                element_type,
+                num_elements,
+            } => {
+                if gep || index? < *num_elements as u64 {
+                    Some(element_type.clone())
+                } else {
+                    None
+                }
+            },
+            Type::VectorType { element_type, .. } if gep => Some(element_type.clone()),
+            _ => None,
+        }
+    }
+
+    /// The type of the element of the aggregate type `ty` given by the indices
+    /// of an `extractvalue` or `insertvalue`, or `None` if they're invalid
+    pub(super) fn aggregate_element_type(&self, ty: &TypeRef, indices: &[u32]) -> Option<TypeRef> {
+        let mut ty = ty.clone();
+        for &index in indices {
+            ty = self.element_type(&ty, Some(u64::from(index)), false)?;
+        }
+        Some(ty)
+    }
+
+    /// The type of the result of a `getelementptr` (at `pos`) with the given
+    /// source element type and address type, and indices of the given types.
+    /// `index_values` gives the value of each index which is a constant
+    /// integer (or a splat of one).
+    pub(super) fn gep_result_type(
+        &mut self,
+        source_element_type: &TypeRef,
+        address_ty: &TypeRef,
+        index_types: &[TypeRef],
+        index_values: impl Iterator<Item = Option<u64>>,
+        pos: usize,
+    ) -> ParseResult<TypeRef> {
+        // with a vector of addresses or of indices, the result is a vector
+        // of pointers
+        let (pointer_ty, mut vector) = match address_ty.as_ref() {
+            Type::VectorType {
+                element_type,
+                num_elements,
+                scalable,
+            } => (element_type.clone(), Some((*num_elements, *scalable))),
+            _ => (address_ty.clone(), None),
+        };
+        let addr_space = match pointer_ty.as_ref() {
+            Type::PointerType {
+                pointee_type,
+                addr_space,
+            } => {
+                if pointee_type != source_element_type {
+                    return Err(self.error_at(
+                        pos,
+                        "explicit pointee type doesn't match operand's pointee type",
+                    ));
+                }
+                *addr_space
+            },
+            Type::OpaquePointerType { addr_space } => *addr_space,
+            _ => return Err(self.error_at(pos, "base of getelementptr must be a pointer")),
+        };
+        for index_ty in index_types {
+            match index_ty.as_ref() {
+                Type::IntegerType { .. } => {},
+                Type::VectorType {
+                    element_type,
+                    num_elements,
+                    scalable,
+                } if matches!(element_type.as_ref(), Type::IntegerType { .. }) => {
+                    vector = vector.or(Some((*num_elements, *scalable)));
+                },
+                _ => return Err(self.error_at(pos, "getelementptr index must be an integer")),
+            }
+        }
+        // the first index steps over the source element type, and the rest
+        // index into it
+        let mut ty = source_element_type.clone();
+        for index in index_values.skip(1) {
+            ty = match self.element_type(&ty, index, true) {
+                Some(element_ty) => element_ty,
+                None => return Err(self.error_at(pos, "invalid getelementptr indices")),
+            };
+        }
+        let ty = self.pointer_type(ty, addr_space);
+        Ok(match vector {
+            Some((num_elements, scalable)) => self.types.vector_of(ty, num_elements, scalable),
+            None => ty,
+        })
+    }
+
+    /// The type of the result of a `shufflevector` of vectors of type `ty`
+    /// with a mask of type `mask_ty`, or `None` if those aren't vectors
+    pub(super) fn shuffle_result_type(
+        &mut self,
+        ty: &TypeRef,
+        mask_ty: &TypeRef,
+    ) -> Option<TypeRef> {
+        match (ty.as_ref(), mask_ty.as_ref()) {
+            (
+                Type::VectorType { element_type, .. },
+                Type::VectorType {
+                    num_elements,
+                    scalable,
+                    ..
+                },
+            ) => Some(
+                self.types
+                    .vector_of(element_type.clone(), *num_elements, *scalable),
+            ),
+            _ => None,
+        }
+    }
+
+    /// The type of the result of an `icmp` or `fcmp` of operands of type `ty`
+    pub(super) fn comparison_result_type(&mut self, ty: &TypeRef) -> TypeRef {
+        let bool_ty = self.types.bool();
+        match ty.as_ref() {
+            Type::VectorType {
+                num_elements,
+                scalable,
+                ..
+            } => self.types.vector_of(bool_ty, *num_elements, *scalable),
+            _ => bool_ty,
+        }
+    }
+
+    /// Skip the body of a type definition (after `%name = type`), which was
+    /// already handled by `define_types()`
+    pub(super) fn skip_type_def_body(&mut self) -> ParseResult<()> {
+        if !self.eat_keyword("opaque") {
+            self.parse_type()?;
+        }
+        Ok(())
+    }
+
+    pub(super) fn parse_type(&mut self) -> ParseResult<TypeRef> {
+        let mut ty = self.parse_base_type()?;
+        // suffixes: pointers and function types
+        loop {
+            match self.peek() {
+                Tok::Star => {
+                    self.bump();
+                    ty = self.pointer_type(ty, 0);
+                },
+                Tok::Keyword(kw) if kw == "addrspace" => {
+                    let addr_space = self.parse_optional_addr_space()?;
+                    self.expect(&Tok::Star)?;
+                    ty = self.pointer_type(ty, addr_space);
+                },
+                Tok::LParen => {
+                    self.bump();
+                    let mut param_types = vec![];
+                    let mut is_var_arg = false;
+                    while *self.peek() != Tok::RParen {
+                        if !param_types.is_empty() {
+                            self.expect(&Tok::Comma)?;
+                        }
+                        if self.eat(&Tok::DotDotDot) {
+                            is_var_arg = true;
+                            break;
+                        }
+                        param_types.push(self.parse_type()?);
+                        // parameter attributes are allowed (and ignored) here
+                        self.parse_attributes(false)?;
+                    }
+                    self.expect(&Tok::RParen)?;
+                    ty = self.types.func_type(ty, param_types, is_var_arg);
+                },
+                _ => return Ok(ty),
+            }
+        }
+    }
+
+    fn parse_base_type(&mut self) -> ParseResult<TypeRef> {
+        let ty = match self.peek().clone() {
+            Tok::Keyword(kw) => {
+                if let Some(bits) = kw.strip_prefix('i').and_then(|b| b.parse().ok()) {
+                    self.bump();
+                    return Ok(self.types.int(bits));
+                }
+                let ty = match kw.as_str() {
+                    "void" => self.types.void(),
+                    "half" => self.types.fp(FPType::Half),
+                    #[cfg(feature = "llvm-11-or-greater")]
+                    "bfloat" => self.types.fp(FPType::BFloat),
+                    "float" => self.types.fp(FPType::Single),
+                    "double" => self.types.fp(FPType::Double),
+                    "fp128" => self.types.fp(FPType::FP128),
+                    "x86_fp80" => self.types.fp(FPType::X86_FP80),
+                    "ppc_fp128" => self.types.fp(FPType::PPC_FP128),
+                    "x86_mmx" => self.types.x86_mmx(),
+                    #[cfg(feature = "llvm-12-or-greater")]
+                    "x86_amx" => self.types.x86_amx(),
+                    "label" => self.types.label_type(),
+                    "metadata" => self.types.metadata_type(),
+                    "token" => self.types.token_type(),
+                    #[cfg(feature = "llvm-14-or-greater")]
+                    "ptr" => {
+                        self.bump();
+                        let addr_space = self.parse_optional_addr_space()?;
+                        return Ok(self.types.opaque_pointer_in_addr_space(addr_space));
+                    },
+                    #[cfg(feature = "llvm-16-or-greater")]
+                    "target" => {
+                        self.bump();
+                        return self.parse_target_ext_type();
+                    },
+                    _ => return self.error("expected type"),
+                };
+                self.bump();
+                ty
+            },
+            Tok::LBrace => {
+                self.bump();
+                let element_types = self.parse_struct_body(&Tok::RBrace)?;
+                self.types.struct_of(element_types, false)
+            },
+            Tok::Less if *self.peek_at(1) == Tok::LBrace => {
+                self.bump();
+                self.bump();
+                let element_types = self.parse_struct_body(&Tok::RBrace)?;
+                self.expect(&Tok::Greater)?;
+                self.types.struct_of(element_types, true)
+            },
+            Tok::Less => {
+                self.bump();
+                let scalable = self.eat_keyword("vscale");
+                if scalable {
+                    self.expect_keyword("x")?;
+                }
+                let num_elements = self.parse_uint()?;
+                self.expect_keyword("x")?;
+                let element_type = self.parse_type()?;
+                self.expect(&Tok::Greater)?;
+                if scalable && cfg!(feature = "llvm-10-or-lower") {
+                    return self.error("scalable vectors require LLVM 11 or greater");
+                }
+                self.types.vector_of(element_type, num_elements, scalable)
+            },
+            Tok::LSquare => {
+                self.bump();
+                let num_elements = self.parse_uint()?;
+                self.expect_keyword("x")?;
+                let element_type = self.parse_type()?;
+                self.expect(&Tok::RSquare)?;
+                self.types.array_of(element_type, num_elements)
+            },
+            Tok::LocalVar(name) => {
+                if !self.named_types.contains_key(&name) {
+                    return self.error(format!("use of undefined type named '%{}'", name));
+                }
+                self.bump();
+                self.types.named_struct(name)
+            },
+            Tok::LocalId(id) => {
+                let def_pos = match self.numbered_types.get(&id) {
+                    Some(&def_pos) => def_pos,
+                    None => return self.error(format!("use of undefined type '%{}'", id)),
+                };
+                if self.numbered_types_in_progress.contains(&id) {
+                    return self.error("recursive numbered types are not supported");
+                }
+                // LLVM gives numbered struct types no name, so they look like
+                // literal struct types
+                self.bump();
+                let pos = self.pos;
+                self.pos = def_pos;
+                self.numbered_types_in_progress.push(id);
+                let ty = if self.eat_keyword("opaque") {
+                    Ok(self.types.struct_of(vec![], false))
+                } else {
+                    self.parse_type()
+                };
+                self.numbered_types_in_progress.pop();
+                self.pos = pos;
+                ty?
+            },
+            _ => return self.error("expected type"),
+        };
+        Ok(ty)
+    }
+
+    /// Parse the element types of a struct, after the `{`
+    fn parse_struct_body(&mut self, close: &Tok) -> ParseResult<Vec<TypeRef>> {
+        let mut element_types = vec![];
+        while self.peek() != close {
+            if !element_types.is_empty() {
+                self.expect(&Tok::Comma)?;
+            }
+            element_types.push(self.parse_type()?);
+        }
+        self.bump();
+        Ok(element_types)
+    }
+
+    /// Parse a target extension type, e.g. `target("foo", i8, 1)`, after the
+    /// `target`
+    #[cfg(feature = "llvm-16-or-greater")]
+    fn parse_target_ext_type(&mut self) -> ParseResult<TypeRef> {
+        self.expect(&Tok::LParen)?;
+        let name = self.parse_string()?;
+        let mut type_params = vec![];
+        let mut int_params = vec![];
+        while self.eat(&Tok::Comma) {
+            if let Tok::Int(_) = self.peek() {
+                int_params.push(self.parse_uint()?);
+            } else if int_params.is_empty() {
+                type_params.push(self.parse_type()?);
+            } else {
+                return self.error("expected integer parameter");
+            }
+        }
+        self.expect(&Tok::RParen)?;
+        Ok(self.types.target_ext_type(name, type_params, int_params))
+    }
+
+    /// The name LLVM gives the intrinsic `name`, if that differs from
+    /// `name`. With opaque pointers, LLVM renames intrinsics whose names
+    /// mangle pointee types; e.g., `llvm.memset.p0i8.i64` becomes
+    /// `llvm.memset.p0.i64`.
+    pub(super) fn upgraded_intrinsic_name(&self, name: &str) -> Option<String> {
+        if !self.opaque_pointers() || !name.starts_with("llvm.") {
+            return None;
+        }
+        let mut upgraded = String::with_capacity(name.len());
+        let mut rest = name;
+        while let Some(i) = rest.find(".p") {
+            upgraded.push_str(&rest[.. i + 2]);
+            rest = &rest[i + 2 ..];
+            let digits = rest
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(rest.len());
+            if digits == 0 {
+                continue;
+            }
+            upgraded.push_str(&rest[.. digits]);
+            rest = &rest[digits ..];
+            // drop the pointee type, if it's followed by the end of this
+            // part of the name
+            if let Some(len) = self.mangled_type_len(rest) {
+                if rest[len ..].is_empty() || rest[len ..].starts_with('.') {
+                    rest = &rest[len ..];
+                }
+            }
+        }
+        upgraded.push_str(rest);
+        if upgraded == name {
+            None
+        } else {
+            Some(upgraded)
+        }
+    }
+
+    /// The length of the type mangled (as in intrinsic names) at the start of
+    /// `s`, if there is one
+    fn mangled_type_len(&self, s: &str) -> Option<usize> {
+        let digits = |s: &str| s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+        for fixed in &[
+            "isVoid", "Metadata", "x86mmx", "x86amx", "token", "ppcf128", "bf16", "f128", "f16",
+            "f32", "f64", "f80",
+        ] {
+            if s.starts_with(fixed) {
+                return Some(fixed.len());
+            }
+        }
+        // types with a number and then an element type
+        for prefix in &["p", "a", "nxv", "v"] {
+            if let Some(rest) = s.strip_prefix(prefix) {
+                let n = digits(rest);
+                if n > 0 {
+                    let element = self.mangled_type_len(&rest[n ..])?;
+                    return Some(prefix.len() + n + element);
+                }
+            }
+        }
+        if let Some(rest) = s.strip_prefix('i') {
+            let n = digits(rest);
+            return if n > 0 { Some(1 + n) } else { None };
+        }
+        if let Some(mut rest) = s.strip_prefix("sl_") {
+            // literal struct: the element types, then `s`
+            while !rest.starts_with('s') || self.mangled_type_len(rest).is_some() {
+                rest = &rest[self.mangled_type_len(rest)? ..];
+            }
+            return Some(s.len() - rest.len() + 1);
+        }
+        if let Some(rest) = s.strip_prefix("s_") {
+            // named struct: the longest name of a named type
+            return self
+                .named_types
+                .keys()
+                .filter(|name| rest.starts_with(name.as_str()))
+                .map(|name| 2 + name.len())
+                .max();
+        }
+        if let Some(mut rest) = s.strip_prefix("f_") {
+            // function: the return and parameter types, then `f`
+            while !rest.starts_with('f') || self.mangled_type_len(rest).is_some() {
+                rest = match rest.strip_prefix("vararg") {
+                    Some(rest) => rest,
+                    None => &rest[self.mangled_type_len(rest)? ..],
+                };
+            }
+            return Some(s.len() - rest.len() + 1);
+        }
+        None
+    }
+}
+
+/// The value of a constant index, if it's an integer or a vector whose
+/// elements are all the same integer
+pub(super) fn constant_index(c: &Constant) -> Option<u64> {
+    match c {
+        Constant::Int { value, .. } => Some(*value),
+        Constant::AggregateZero(_) => Some(0),
+        Constant::Vector(elements) => {
+            let first = constant_index(elements.first()?)?;
+            if elements.iter().all(|e| constant_index(e) == Some(first)) {
+                Some(first)
+            } else {
+                None
+            }
+        },
+        _ => None,
+    }
+}
@@ -550,8 +550,10 @@ pub enum Virtuality {
 // from_llvm //
 // ********* //
 
+#[cfg(feature = "llvm-sys")]
 use crate::from_llvm::*;
 
+#[cfg(feature = "llvm-sys")]
 impl Metadata {
     pub(crate) fn from_llvm_ref(md: LLVMValueRef) -> Self {
         unimplemented!("Metadata::from_llvm_ref");
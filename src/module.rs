@@ -1,12 +1,16 @@
-use crate::constant::ConstantRef;
-#[cfg(feature = "llvm-9-or-greater")]
+use crate::constant::{ConstantRef, Constants};
 use crate::debugloc::*;
 use crate::function::{Function, FunctionAttribute, FunctionDeclaration, GroupID};
+#[cfg(feature = "llvm-sys")]
 use crate::llvm_sys::*;
-use crate::name::Name;
-use crate::types::{FPType, Type, TypeRef, Typed, Types, TypesBuilder};
+#[cfg(feature = "llvm-sys")]
+use crate::parse_options::ParseOptions;
+use crate::types::{FPType, Type, TypeRef, Typed, Types};
 use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+#[cfg(feature = "llvm-sys")]
 use std::path::Path;
+#[cfg(feature = "llvm-sys")]
 use std::ptr::null_mut;
 
 /// See [LLVM 14 docs on Module Structure](https://releases.llvm.org/14.0.0/docs/LangRef.html#module-structure)
@@ -30,15 +34,31 @@ pub struct Module {
     pub global_vars: Vec<GlobalVariable>,
     /// See [LLVM 14 docs on Global Aliases](https://releases.llvm.org/14.0.0/docs/LangRef.html#aliases)
     pub global_aliases: Vec<GlobalAlias>,
-    // --TODO not yet implemented-- pub function_attribute_groups: Vec<FunctionAttributeGroup>,
+    /// See [LLVM 14 docs on IFuncs](https://releases.llvm.org/14.0.0/docs/LangRef.html#ifuncs)
+    pub global_ifuncs: Vec<GlobalIFunc>,
+    /// Each distinct set of function attributes used by a function or call
+    /// site in this `Module`. Functions and calls refer to these by `GroupID`,
+    /// which is the index of the group in this `Vec`.
+    /// See [LLVM 14 docs on Attribute Groups](https://releases.llvm.org/14.0.0/docs/LangRef.html#attribute-groups)
+    pub function_attribute_groups: Vec<FunctionAttributeGroup>,
     /// See [LLVM 14 docs on Module-Level Inline Assembly](https://releases.llvm.org/14.0.0/docs/LangRef.html#moduleasm)
     pub inline_assembly: String,
     // --TODO not yet implemented-- pub metadata_nodes: Vec<(MetadataNodeID, MetadataNode)>,
     // --TODO not yet implemented-- pub named_metadatas: Vec<NamedMetadata>,
-    // --TODO not yet implemented-- pub comdats: Vec<Comdat>,
+    /// Each comdat used by a function or global variable in this `Module`,
+    /// with its members.
+    /// See [LLVM 14 docs on Comdats](https://releases.llvm.org/14.0.0/docs/LangRef.html#langref-comdats)
+    pub comdats: Vec<ComdatGroup>,
     /// Holds a reference to all of the `Type`s used in the `Module`, and
     /// facilitates lookups so you can get a `TypeRef` to the `Type` you want.
     pub types: Types,
+    /// Holds a reference to all of the `Constant`s used in the `Module`, so
+    /// that equal `Constant`s share one `ConstantRef`.
+    pub constants: Constants,
+    /// The LLVM version that produced this `Module`. Variants and fields
+    /// which only exist in other LLVM versions won't appear in it; e.g., a
+    /// `Module` from LLVM 15 never contains `FunctionAttribute::Memory`.
+    pub llvm_version: LLVMVersion,
 }
 
 impl Module {
@@ -65,6 +85,26 @@ impl Module {
         self.func_declarations.iter().find(|decl| decl.name == name)
     }
 
+    /// Index this `Module`'s functions and function declarations by name.
+    /// `get_func_by_name()` and `get_func_decl_by_name()` search through all
+    /// of them, so use this when looking up many functions, e.g. the callees
+    /// of all the calls in the `Module`.
+    pub fn function_index(&self) -> FunctionIndex<'_> {
+        FunctionIndex {
+            module: self,
+            functions: self
+                .functions
+                .iter()
+                .map(|func| (func.name.as_str(), func))
+                .collect(),
+            declarations: self
+                .func_declarations
+                .iter()
+                .map(|decl| (decl.name.as_str(), decl))
+                .collect(),
+        }
+    }
+
     /// Get the `GlobalVariable` having the given `name` (if any).
     /// Note that `GlobalVariable`s are named with `String`s and not `Name`s.
     pub fn get_global_var_by_name(&self, name: &str) -> Option<&GlobalVariable> {
@@ -77,8 +117,49 @@ impl Module {
         self.global_aliases.iter().find(|global| &global.name == name)
     }
 
+    /// Get the `GlobalIFunc` having the given `name` (if any).
+    /// Note that `GlobalIFunc`s are named with `String`s and not `Name`s.
+    pub fn get_global_ifunc_by_name(&self, name: &str) -> Option<&GlobalIFunc> {
+        self.global_ifuncs.iter().find(|global| global.name == name)
+    }
+
+    /// Get the `ComdatGroup` for the comdat having the given `name` (if any).
+    /// Note that comdats are named with `String`s and not `Name`s.
+    pub fn get_comdat_by_name(&self, name: &str) -> Option<&ComdatGroup> {
+        self.comdats.iter().find(|group| group.comdat.name == name)
+    }
+
+    /// Get the `FunctionAttributeGroup` having the given `GroupID` (if any).
+    pub fn get_function_attribute_group(
+        &self,
+        group_id: GroupID,
+    ) -> Option<&FunctionAttributeGroup> {
+        self.function_attribute_groups.get(group_id)
+    }
+
+    /// Get the function attributes referred to by a `function_attribute_group`
+    /// field of a `Function`, `Call`, `Invoke`, or `CallBr`. Returns an empty
+    /// slice for `None`.
+    pub fn get_function_attributes(&self, group_id: Option<GroupID>) -> &[FunctionAttribute] {
+        match group_id.and_then(|id| self.get_function_attribute_group(id)) {
+            Some(group) => &group.attrs,
+            None => &[],
+        }
+    }
+
     /// Parse the LLVM bitcode (.bc) file at the given path to create a `Module`
+    #[cfg(feature = "llvm-sys")]
     pub fn from_bc_path(path: impl AsRef<Path>) -> Result<Self, String> {
+        Self::from_bc_path_with_options(path, &ParseOptions::default())
+    }
+
+    /// Parse the LLVM bitcode (.bc) file at the given path to create a
+    /// `Module`, converting only the parts selected by `options`
+    #[cfg(feature = "llvm-sys")]
+    pub fn from_bc_path_with_options(
+        path: impl AsRef<Path>,
+        options: &ParseOptions,
+    ) -> Result<Self, String> {
         unsafe fn parse_bc(
             context_ref: LLVMContextRef,
             mem_buf: LLVMMemoryBufferRef,
@@ -89,11 +170,69 @@ impl Module {
             LLVMDisposeMemoryBuffer(mem_buf);
             result
         }
-        Self::from_path(path, parse_bc)
+        Self::from_path(path, parse_bc, options)
     }
 
     /// Parse the LLVM text IR (.ll) file at the given path to create a `Module`
+    #[cfg(feature = "llvm-sys")]
     pub fn from_ir_path(path: impl AsRef<Path>) -> Result<Self, String> {
+        Self::from_ir_path_with_options(path, &ParseOptions::default())
+    }
+
+    /// Parse the LLVM text IR (.ll) file at the given path to create a
+    /// `Module`, converting only the parts selected by `options`
+    #[cfg(feature = "llvm-sys")]
+    pub fn from_ir_path_with_options(
+        path: impl AsRef<Path>,
+        options: &ParseOptions,
+    ) -> Result<Self, String> {
+        unsafe fn parse_ir(
+            context_ref: LLVMContextRef,
+            mem_buf: LLVMMemoryBufferRef,
+            out_module: *mut LLVMModuleRef,
+        ) -> LLVMBool {
+            // This call takes ownership of the buffer, so we don't free it.
+            llvm_sys::ir_reader::LLVMParseIRInContext(context_ref, mem_buf, out_module, null_mut())
+        }
+        Self::from_path(path, parse_ir, options)
+    }
+
+    /// Parse the LLVM bitcode (.bc) file at the given path, and call `f` on
+    /// each of its defined functions in turn, rather than collecting them all
+    /// into a `Module`. Each `Function` is converted just before it is passed
+    /// to `f`, so only one is held in memory at a time (unless `f` keeps them).
+    ///
+    /// `f` also gets the module-level data: everything but the functions
+    /// (whose `functions` is always empty). Its `types`, `constants`,
+    /// `function_attribute_groups`, and `comdats` cover at least everything
+    /// the function passed alongside it refers to. The attribute groups may be
+    /// numbered differently than by `Module::from_bc_path()`.
+    #[cfg(feature = "llvm-sys")]
+    pub fn stream_from_bc_path(
+        path: impl AsRef<Path>,
+        f: impl FnMut(&Module, Function),
+    ) -> Result<(), String> {
+        unsafe fn parse_bc(
+            context_ref: LLVMContextRef,
+            mem_buf: LLVMMemoryBufferRef,
+            out_module: *mut LLVMModuleRef,
+        ) -> LLVMBool {
+            let result =
+                llvm_sys::bit_reader::LLVMParseBitcodeInContext2(context_ref, mem_buf, out_module);
+            LLVMDisposeMemoryBuffer(mem_buf);
+            result
+        }
+        Self::stream_from_path(path, parse_bc, f)
+    }
+
+    /// Parse the LLVM text IR (.ll) file at the given path, and call `f` on
+    /// each of its defined functions in turn. See
+    /// [`stream_from_bc_path()`](Module::stream_from_bc_path).
+    #[cfg(feature = "llvm-sys")]
+    pub fn stream_from_ir_path(
+        path: impl AsRef<Path>,
+        f: impl FnMut(&Module, Function),
+    ) -> Result<(), String> {
         unsafe fn parse_ir(
             context_ref: LLVMContextRef,
             mem_buf: LLVMMemoryBufferRef,
@@ -102,8 +241,52 @@ impl Module {
             // This call takes ownership of the buffer, so we don't free it.
             llvm_sys::ir_reader::LLVMParseIRInContext(context_ref, mem_buf, out_module, null_mut())
         }
-        Self::from_path(path, parse_ir)
+        Self::stream_from_path(path, parse_ir, f)
     }
+
+    /// Create a `Module` from an LLVM module which is already in memory, for
+    /// instance one built by a JIT or handed out by another LLVM binding.
+    ///
+    /// The `LLVMModuleRef` type is the one from `llvm_ir::sys`,
+    /// the `llvm-sys` crate for the selected LLVM version.
+    ///
+    /// # Safety
+    ///
+    /// `module` must be a valid, non-null reference to an LLVM module, and
+    /// neither the module nor its `LLVMContext` may be modified or disposed of
+    /// by anyone else while this function runs. The module is only read:
+    /// it still belongs to the caller afterwards, who remains responsible for
+    /// disposing of it.
+    #[cfg(feature = "llvm-sys")]
+    pub unsafe fn from_llvm_module_ref(module: LLVMModuleRef) -> Self {
+        Self::from_llvm_module_ref_with_options(module, &ParseOptions::default())
+    }
+
+    /// Like [`from_llvm_module_ref()`](Module::from_llvm_module_ref), but
+    /// converting only the parts selected by `options`
+    ///
+    /// # Safety
+    ///
+    /// As for [`from_llvm_module_ref()`](Module::from_llvm_module_ref).
+    #[cfg(feature = "llvm-sys")]
+    pub unsafe fn from_llvm_module_ref_with_options(
+        module: LLVMModuleRef,
+        options: &ParseOptions,
+    ) -> Self {
+        Self::from_llvm_ref(module, options)
+    }
+
+    /// Create a `Module` from an `inkwell` module.
+    ///
+    /// This requires the `inkwell` feature, and `inkwell` must be built for
+    /// the same LLVM version as `llvm-ir`.
+    #[cfg(feature = "inkwell")]
+    pub fn from_inkwell(module: &inkwell::module::Module) -> Self {
+        // an `inkwell::module::Module` owns a valid module, and borrowing it
+        // keeps it (and its context) alive and unmodified during the conversion
+        unsafe { Self::from_llvm_module_ref(module.as_mut_ptr()) }
+    }
+    #[cfg(feature = "llvm-sys")]
     fn from_path(
         path: impl AsRef<Path>,
         parse: unsafe fn(
@@ -111,7 +294,38 @@ impl Module {
             mem_buf: LLVMMemoryBufferRef,
             out_module: *mut LLVMModuleRef,
         ) -> LLVMBool,
+        options: &ParseOptions,
     ) -> Result<Self, String> {
+        let (_context, module) = Self::parse_path(path, parse)?;
+        Ok(Self::from_llvm_ref(module, options))
+    }
+
+    #[cfg(feature = "llvm-sys")]
+    fn stream_from_path(
+        path: impl AsRef<Path>,
+        parse: unsafe fn(
+            context_ref: LLVMContextRef,
+            mem_buf: LLVMMemoryBufferRef,
+            out_module: *mut LLVMModuleRef,
+        ) -> LLVMBool,
+        f: impl FnMut(&Module, Function),
+    ) -> Result<(), String> {
+        let (_context, module) = Self::parse_path(path, parse)?;
+        Self::stream_from_llvm_ref(module, f);
+        Ok(())
+    }
+
+    /// Parse the file at the given path into an LLVM module, which lives as
+    /// long as the returned `Context`
+    #[cfg(feature = "llvm-sys")]
+    fn parse_path(
+        path: impl AsRef<Path>,
+        parse: unsafe fn(
+            context_ref: LLVMContextRef,
+            mem_buf: LLVMMemoryBufferRef,
+            out_module: *mut LLVMModuleRef,
+        ) -> LLVMBool,
+    ) -> Result<(crate::from_llvm::Context, LLVMModuleRef), String> {
         // implementation here inspired by the `inkwell` crate's `Module::parse_bitcode_from_path`
         use std::ffi::{CStr, CString};
         use std::mem;
@@ -153,7 +367,99 @@ impl Module {
             module.assume_init()
         };
         debug!("Parsed bitcode to llvm_sys module");
-        Ok(Self::from_llvm_ref(module))
+        Ok((context, module))
+    }
+}
+
+/// The functions and function declarations of a `Module`, indexed by name.
+/// See `Module::function_index()`.
+#[derive(Clone)]
+pub struct FunctionIndex<'m> {
+    module: &'m Module,
+    functions: HashMap<&'m str, &'m Function>,
+    declarations: HashMap<&'m str, &'m FunctionDeclaration>,
+}
+
+impl<'m> FunctionIndex<'m> {
+    /// The `Module` this indexes
+    pub fn module(&self) -> &'m Module {
+        self.module
+    }
+
+    /// Get the `Function` having the given `name` (if any). As with
+    /// `Module::get_func_by_name()`, this only finds _fully defined_
+    /// functions.
+    pub fn get_func_by_name(&self, name: &str) -> Option<&'m Function> {
+        self.functions.get(name).copied()
+    }
+
+    /// Get the `FunctionDeclaration` having the given `name` (if any). As
+    /// with `Module::get_func_decl_by_name()`, this only finds function
+    /// _declarations_.
+    pub fn get_func_decl_by_name(&self, name: &str) -> Option<&'m FunctionDeclaration> {
+        self.declarations.get(name).copied()
+    }
+}
+
+/// A major version of LLVM supported by `llvm-ir`.
+///
+/// The data model of `llvm-ir` is the same whichever LLVM version it was
+/// built for; items which exist only in some LLVM versions are always
+/// present (as enum variants, or as `Option`s), and each `Module` records
+/// the version which produced it in `Module::llvm_version`.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Debug, Hash)]
+pub enum LLVMVersion {
+    V8,
+    V9,
+    V10,
+    V11,
+    V12,
+    V13,
+    V14,
+    V15,
+    V16,
+    V17,
+}
+
+impl LLVMVersion {
+    /// The LLVM version `llvm-ir` was configured for, by its `llvm-N` (or
+    /// `llvm-N-no-llvm-sys`) feature
+    pub fn selected() -> Self {
+        match crate::llvm_version() {
+            "8" => Self::V8,
+            "9" => Self::V9,
+            "10" => Self::V10,
+            "11" => Self::V11,
+            "12" => Self::V12,
+            "13" => Self::V13,
+            "14" => Self::V14,
+            "15" => Self::V15,
+            "16" => Self::V16,
+            "17" => Self::V17,
+            v => unreachable!("unsupported LLVM version {}", v),
+        }
+    }
+
+    /// The major version number, e.g. `14` for `LLVMVersion::V14`
+    pub fn major(self) -> u32 {
+        match self {
+            Self::V8 => 8,
+            Self::V9 => 9,
+            Self::V10 => 10,
+            Self::V11 => 11,
+            Self::V12 => 12,
+            Self::V13 => 13,
+            Self::V14 => 14,
+            Self::V15 => 15,
+            Self::V16 => 16,
+            Self::V17 => 17,
+        }
+    }
+}
+
+impl fmt::Display for LLVMVersion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "LLVM {}", self.major())
     }
 }
 
@@ -165,7 +471,11 @@ pub struct GlobalVariable {
     pub linkage: Linkage,
     pub visibility: Visibility,
     pub is_constant: bool,
+    /// Type of the global variable itself, which is always a pointer type
     pub ty: TypeRef,
+    /// Type of the value stored in the global variable (i.e., of its
+    /// initializer). With opaque pointers, this can't be recovered from `ty`.
+    pub value_type: TypeRef,
     pub addr_space: AddrSpace,
     pub dll_storage_class: DLLStorageClass,
     pub thread_local_mode: ThreadLocalMode,
@@ -174,9 +484,19 @@ pub struct GlobalVariable {
     pub section: Option<String>,
     pub comdat: Option<Comdat>, // llvm-hs-pure has Option<String> for some reason
     pub alignment: u32,
-    #[cfg(feature = "llvm-9-or-greater")]
+    /// Whether the global may be initialized by something outside the
+    /// module (in which case its initializer can't be relied on)
+    pub externally_initialized: bool,
+    /// See [LLVM 14 docs on Partitions](https://releases.llvm.org/14.0.0/docs/LangRef.html#partitions)
+    pub partition: Option<String>,
+    /// String attributes of the global, such as `"bss-section"`, as
+    /// (kind, value) pairs. For attributes with no value, the value is `""`.
+    pub attributes: Vec<(String, String)>,
+    pub sanitizer_metadata: SanitizerMetadata,
+    // (the `code_model` of a global variable was introduced in LLVM 18)
     pub debugloc: Option<DebugLoc>,
-    // --TODO not yet implemented-- pub metadata: Vec<(String, MetadataRef<MetadataNode>)>,
+    /// Metadata attached to the global, e.g., `!type`
+    pub metadata: Vec<MetadataAttachment>,
 }
 
 impl Typed for GlobalVariable {
@@ -185,13 +505,52 @@ impl Typed for GlobalVariable {
     }
 }
 
-#[cfg(feature = "llvm-9-or-greater")]
 impl HasDebugLoc for GlobalVariable {
     fn get_debug_loc(&self) -> &Option<DebugLoc> {
         &self.debugloc
     }
 }
 
+/// Which sanitizer instrumentation applies to a global variable.
+/// See [LLVM 15 docs on Global Variables](https://releases.llvm.org/15.0.0/docs/LangRef.html#global-variables)
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default)]
+pub struct SanitizerMetadata {
+    /// `no_sanitize_address`
+    pub no_address: bool,
+    /// `no_sanitize_hwaddress`
+    pub no_hwaddress: bool,
+    /// `sanitize_memtag`
+    pub memtag: bool,
+    /// `sanitize_address_dyninit`
+    pub is_dyn_init: bool,
+}
+
+/// A metadata attachment on a function or global variable, e.g., `!type !0`
+#[derive(PartialEq, Clone, Debug)]
+pub struct MetadataAttachment {
+    /// The kind of the attachment, e.g., `type` for `!type`
+    pub kind: String,
+    pub node: AttachedMetadata,
+}
+
+/// The contents of a `MetadataAttachment`. `llvm-ir` doesn't fully represent
+/// metadata (see the README), so this is a simplified view, which covers the
+/// tuples used by attachments like `!type`, `!prof`, or `!callback`.
+#[derive(PartialEq, Clone, Debug)]
+pub enum AttachedMetadata {
+    /// A metadata string, e.g., `!"foo"`
+    String(String),
+    /// A constant, e.g., `i64 0`
+    Constant(ConstantRef),
+    /// A tuple, e.g., `!{i64 0, !"foo"}`. `None` represents `null`.
+    Tuple(Vec<Option<AttachedMetadata>>),
+    /// Any other metadata (e.g., debug-info nodes like `!DISubprogram`), in
+    /// LLVM's textual form. Nodes referenced from inside this node are given
+    /// as `!N` where LLVM numbers them, and otherwise as `!?` (LLVM would
+    /// print their addresses).
+    Other(String),
+}
+
 /// See [LLVM 14 docs on Global Aliases](https://releases.llvm.org/14.0.0/docs/LangRef.html#aliases)
 #[derive(PartialEq, Clone, Debug)]
 pub struct GlobalAlias {
@@ -200,7 +559,11 @@ pub struct GlobalAlias {
     pub aliasee: ConstantRef,
     pub linkage: Linkage,
     pub visibility: Visibility,
+    /// Type of the alias itself, which is always a pointer type
     pub ty: TypeRef,
+    /// Type of the value the alias refers to. With opaque pointers, this
+    /// can't be recovered from `ty`.
+    pub value_type: TypeRef,
     pub addr_space: AddrSpace,
     pub dll_storage_class: DLLStorageClass,
     pub thread_local_mode: ThreadLocalMode,
@@ -213,6 +576,30 @@ impl Typed for GlobalAlias {
     }
 }
 
+/// See [LLVM 14 docs on IFuncs](https://releases.llvm.org/14.0.0/docs/LangRef.html#ifuncs)
+#[derive(PartialEq, Clone, Debug)]
+pub struct GlobalIFunc {
+    /// Globals' names must be strings, so this is `String` not `Name`
+    pub name: String,
+    pub linkage: Linkage,
+    pub visibility: Visibility,
+    /// Type of the ifunc itself, which is always a pointer type
+    pub ty: TypeRef,
+    /// Type of the function which the ifunc resolves to. With opaque
+    /// pointers, this can't be recovered from `ty`.
+    pub value_type: TypeRef,
+    pub addr_space: AddrSpace,
+    /// The resolver function, which is called (by the dynamic loader) to get
+    /// the address of the function this ifunc resolves to
+    pub resolver: ConstantRef,
+}
+
+impl Typed for GlobalIFunc {
+    fn get_type(&self, _types: &Types) -> TypeRef {
+        self.ty.clone()
+    }
+}
+
 #[derive(PartialEq, Eq, Clone, Copy, Debug)]
 pub enum UnnamedAddr {
     Local,
@@ -293,6 +680,18 @@ pub struct Comdat {
     pub selection_kind: SelectionKind,
 }
 
+/// A `Comdat` along with all of the functions and global variables in the
+/// `Module` which belong to it. If one member of a comdat is discarded by the
+/// linker, they all are.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct ComdatGroup {
+    pub comdat: Comdat,
+    /// Names of the `Function`s which belong to this comdat
+    pub functions: Vec<String>,
+    /// Names of the `GlobalVariable`s which belong to this comdat
+    pub global_vars: Vec<String>,
+}
+
 #[derive(PartialEq, Eq, Clone, Copy, Debug)]
 pub enum SelectionKind {
     Any,
@@ -354,7 +753,6 @@ pub struct Alignment {
 
 /// Alignment details for function pointers.
 /// See [LLVM 14 docs on Data Layout](https://releases.llvm.org/14.0.0/docs/LangRef.html#data-layout)
-#[cfg(feature = "llvm-9-or-greater")]
 #[derive(Clone, PartialEq, Eq, Debug)]
 pub struct FunctionPtrAlignment {
     /// If `true`, function pointer alignment is independent of function alignment.
@@ -393,10 +791,8 @@ pub struct Alignments {
     /// Alignment for aggregate types (structs, arrays)
     agg_alignment: Alignment,
     /// Alignment for function pointers
-    #[cfg(feature = "llvm-9-or-greater")]
     fptr_alignment: FunctionPtrAlignment,
     /// Alignment for function pointers, as an `Alignment`
-    #[cfg(feature = "llvm-9-or-greater")]
     fptr_alignment_as_alignment: Alignment,
     /// Layout details for (non-function-pointer) pointers, by address space
     pointer_layouts: HashMap<AddrSpace, PointerLayout>,
@@ -427,10 +823,11 @@ impl Alignments {
                 pointee_type,
                 addr_space,
             } => match pointee_type.as_ref() {
-                #[cfg(feature = "llvm-9-or-greater")]
                 Type::FuncType { .. } => &self.fptr_alignment_as_alignment,
                 _ => &self.ptr_alignment(*addr_space).alignment,
             },
+            // with opaque pointers we can't tell whether this is a function pointer
+            Type::OpaquePointerType { addr_space } => &self.ptr_alignment(*addr_space).alignment,
             _ => panic!("Don't know how to get the alignment of {:?}", ty),
         }
     }
@@ -496,7 +893,6 @@ impl Alignments {
     }
 
     /// Alignment of function pointers
-    #[cfg(feature = "llvm-9-or-greater")]
     pub fn fptr_alignment(&self) -> &FunctionPtrAlignment {
         &self.fptr_alignment
     }
@@ -512,11 +908,24 @@ impl Alignments {
         }
     }
 
+    /// for internal use: the explicit alignment entry for vectors of the given
+    /// total size (in bits), if there is one
+    #[cfg(all(feature = "ll-parser", feature = "llvm-11-or-greater"))]
+    pub(crate) fn explicit_vec_alignment(&self, size: u32) -> Option<&Alignment> {
+        self.vec_alignments.get(&size)
+    }
+
+    /// for internal use: the explicit alignment entry for floating-point types
+    /// of the given size (in bits), if there is one
+    #[cfg(all(feature = "ll-parser", feature = "llvm-11-or-greater"))]
+    pub(crate) fn explicit_fp_alignment(&self, size: u32) -> Option<&Alignment> {
+        self.fp_alignments.get(&size)
+    }
+
     /// for internal use: size of an `FPType`, in bits
-    fn fpt_size(fpt: FPType) -> u32 {
+    pub(crate) fn fpt_size(fpt: FPType) -> u32 {
         match fpt {
             FPType::Half => 16,
-            #[cfg(feature = "llvm-11-or-greater")]
             FPType::BFloat => 16,
             FPType::Single => 32,
             FPType::Double => 64,
@@ -535,314 +944,106 @@ pub enum Mangling {
     MachO,
     WindowsX86COFF,
     WindowsCOFF,
-    #[cfg(feature = "llvm-11-or-greater")]
     XCOFF,
 }
 
-// ********* //
-// from_llvm //
-// ********* //
-
-use crate::constant::Constant;
-use crate::from_llvm::*;
-use crate::function::AttributesData;
-use llvm_sys::comdat::*;
-use llvm_sys::{
-    LLVMDLLStorageClass,
-    LLVMLinkage,
-    LLVMThreadLocalMode,
-    LLVMUnnamedAddr,
-    LLVMVisibility,
-};
-
-/// This struct contains data used when translating llvm-sys objects into our
-/// data structures
-pub(crate) struct ModuleContext<'a> {
-    pub types: TypesBuilder,
-    pub attrsdata: AttributesData,
-    /// Map from an llvm-sys constant to the corresponding llvm-ir `ConstantRef`
-    // We use LLVMValueRef as a *const, even though it's technically a *mut
-    #[allow(clippy::mutable_key_type)]
-    pub constants: HashMap<LLVMValueRef, ConstantRef>,
-    /// Map from an llvm-sys global to its `Name`
-    // We use LLVMValueRef as a *const, even though it's technically a *mut
-    #[allow(clippy::mutable_key_type)]
-    pub global_names: &'a HashMap<LLVMValueRef, Name>,
+impl Default for DataLayout {
+    fn default() -> Self {
+        Self {
+            layout_str: String::new(),
+            endianness: Endianness::BigEndian,
+            stack_alignment: None,
+            program_address_space: 0,
+            alloca_address_space: 0,
+            alignments: Alignments::default(),
+            mangling: None,
+            native_int_widths: None,
+            non_integral_ptr_types: HashSet::new(),
+        }
+    }
 }
 
-impl<'a> ModuleContext<'a> {
-    // We use LLVMValueRef as a *const, even though it's technically a *mut
-    #[allow(clippy::mutable_key_type)]
-    fn new(global_names: &'a HashMap<LLVMValueRef, Name>) -> Self {
+impl Default for Alignments {
+    fn default() -> Self {
         Self {
-            types: TypesBuilder::new(),
-            attrsdata: AttributesData::create(),
-            constants: HashMap::new(),
-            global_names,
+            /// Explicit alignments for various sizes of integers (in bits). Sizes not
+            /// specified here are determined according to the rules described in the
+            /// Data Layout docs.
+            int_alignments: vec![
+                (1, Alignment { abi: 8, pref: 8 }),
+                (8, Alignment { abi: 8, pref: 8 }),
+                (16, Alignment { abi: 16, pref: 16 }),
+                (32, Alignment { abi: 32, pref: 32 }),
+                (64, Alignment { abi: 32, pref: 64 }),
+            ]
+            .into_iter()
+            .collect(),
+            /// Explicit alignments for various sizes of vectors (in bits). Sizes not
+            /// specified here are determined according to the rules described in the
+            /// Data Layout docs.
+            vec_alignments: vec![
+                (64, Alignment { abi: 64, pref: 64 }),
+                (
+                    128,
+                    Alignment {
+                        abi: 128,
+                        pref: 128,
+                    },
+                ),
+            ]
+            .into_iter()
+            .collect(),
+            /// Alignment for floating-point types, by size (in bits)
+            fp_alignments: vec![
+                (16, Alignment { abi: 16, pref: 16 }),
+                (32, Alignment { abi: 32, pref: 32 }),
+                (64, Alignment { abi: 64, pref: 64 }),
+                (
+                    128,
+                    Alignment {
+                        abi: 128,
+                        pref: 128,
+                    },
+                ),
+            ]
+            .into_iter()
+            .collect(),
+            /// Alignment for aggregate types (structs, arrays)
+            agg_alignment: Alignment { abi: 0, pref: 64 },
+            /// Alignment for function pointers
+            fptr_alignment: FunctionPtrAlignment {
+                independent: true,
+                abi: 64,
+            },
+            /// Alignment for function pointers, as an `Alignment`
+            fptr_alignment_as_alignment: Alignment { abi: 64, pref: 64 },
+            /// Layout details for (non-function-pointer) pointers, by address space
+            pointer_layouts: vec![(
+                0,
+                PointerLayout {
+                    size: 64,
+                    alignment: Alignment { abi: 64, pref: 64 },
+                    index_size: 64,
+                },
+            )]
+            .into_iter()
+            .collect(),
         }
     }
 }
 
-impl Module {
-    pub(crate) fn from_llvm_ref(module: LLVMModuleRef) -> Self {
-        debug!("Creating a Module from an LLVMModuleRef");
-        let mut global_ctr = 0; // this ctr is used to number global objects that aren't named
+impl DataLayout {
+    /// Parse a data layout string, e.g., `e-m:e-i64:64-n8:16:32:64-S128`.
+    /// LLVM only gives us valid ones, so any error is a bug.
+    #[cfg(feature = "llvm-sys")]
+    pub(crate) fn from_layout_str(layout_str: String) -> Self {
+        Self::parse_layout_str(layout_str).unwrap_or_else(|e| panic!("{}", e))
+    }
 
-        // Modules require two passes over their contents.
-        // First we make a pass just to map global objects -- in particular, Functions,
-        //   GlobalVariables, and GlobalAliases -- to Names; then we do the actual
-        //   detailed pass.
-        // This is necessary because these structures may reference each other in a
-        //   circular fashion, and we need to be able to fill in the Name of the
-        //   referenced object from having only its `LLVMValueRef`.
-        // We use LLVMValueRef as a *const, even though it's technically a *mut
-        #[allow(clippy::mutable_key_type)]
-        let global_names: HashMap<LLVMValueRef, Name> = get_defined_functions(module)
-            .chain(get_declared_functions(module))
-            .chain(get_globals(module))
-            .chain(get_global_aliases(module))
-            .map(|g| {
-                (
-                    g,
-                    Name::name_or_num(unsafe { get_value_name(g) }, &mut global_ctr),
-                )
-            })
-            .collect();
-        global_ctr = 0; // reset the global_ctr; the second pass should number everything exactly the same though
-
-        let mut ctx = ModuleContext::new(&global_names);
-
-        Self {
-            name: unsafe { get_module_identifier(module) },
-            source_file_name: unsafe { get_source_file_name(module) },
-            data_layout: DataLayout::from_module_ref(module),
-            target_triple: unsafe { get_target(module) },
-            functions: get_defined_functions(module)
-                .map(|f| Function::from_llvm_ref(f, &mut ctx))
-                .collect(),
-            func_declarations: get_declared_functions(module)
-                .map(|f| FunctionDeclaration::from_llvm_ref(f, &mut ctx))
-                .collect(),
-            global_vars: get_globals(module)
-                .map(|g| GlobalVariable::from_llvm_ref(g, &mut global_ctr, &mut ctx))
-                .collect(),
-            global_aliases: get_global_aliases(module)
-                .map(|g| GlobalAlias::from_llvm_ref(g, &mut global_ctr, &mut ctx))
-                .collect(),
-            // function_attribute_groups: unimplemented!("function_attribute_groups"),  // llvm-hs collects these in the decoder monad or something
-            inline_assembly: unsafe { get_module_inline_asm(module) },
-            // metadata_nodes: unimplemented!("metadata_nodes"),
-            // named_metadatas: unimplemented!("named_metadatas"),
-            // comdats: unimplemented!("comdats"),  // I think llvm-hs also collects these along the way
-            types: ctx.types.build(),
-        }
-    }
-}
-
-impl GlobalVariable {
-    pub(crate) fn from_llvm_ref(
-        global: LLVMValueRef,
-        ctr: &mut usize,
-        ctx: &mut ModuleContext,
-    ) -> Self {
-        let ty = ctx.types.type_from_llvm_ref(unsafe { LLVMTypeOf(global) });
-        let addr_space = match ty.as_ref() {
-            Type::PointerType { addr_space, .. } => *addr_space,
-            _ => panic!("GlobalVariable has a non-pointer type, {:?}", ty),
-        };
-        debug!("Processing a GlobalVariable with type {:?}", ty);
-        Self {
-            name: {
-                match Name::name_or_num(unsafe { get_value_name(global) }, ctr) {
-                    Name::Name(s) => *s,
-                    Name::Number(n) => panic!("expected global variable to have a string name, but instead it has the number {}", n),
-                }
-            },
-            linkage: Linkage::from_llvm(unsafe { LLVMGetLinkage(global) }),
-            visibility: Visibility::from_llvm(unsafe { LLVMGetVisibility(global) }),
-            is_constant: unsafe { LLVMIsGlobalConstant(global) } != 0,
-            ty,
-            addr_space,
-            dll_storage_class: DLLStorageClass::from_llvm(unsafe {
-                LLVMGetDLLStorageClass(global)
-            }),
-            thread_local_mode: ThreadLocalMode::from_llvm(unsafe {
-                LLVMGetThreadLocalMode(global)
-            }),
-            unnamed_addr: UnnamedAddr::from_llvm(unsafe { LLVMGetUnnamedAddress(global) }),
-            initializer: {
-                let it = unsafe { LLVMGetInitializer(global) };
-                if it.is_null() {
-                    None
-                } else {
-                    Some(Constant::from_llvm_ref(it, ctx))
-                }
-            },
-            section: unsafe { get_section(global) },
-            comdat: {
-                let comdat = unsafe { LLVMGetComdat(global) };
-                if comdat.is_null() {
-                    None
-                } else {
-                    Some(Comdat::from_llvm_ref(unsafe { LLVMGetComdat(global) }))
-                }
-            },
-            alignment: unsafe { LLVMGetAlignment(global) },
-            #[cfg(feature = "llvm-9-or-greater")]
-            debugloc: DebugLoc::from_llvm_no_col(global),
-            // metadata: unimplemented!("metadata"),
-        }
-    }
-}
-
-impl GlobalAlias {
-    pub(crate) fn from_llvm_ref(
-        alias: LLVMValueRef,
-        ctr: &mut usize,
-        ctx: &mut ModuleContext,
-    ) -> Self {
-        let ty = ctx.types.type_from_llvm_ref(unsafe { LLVMTypeOf(alias) });
-        let addr_space = match ty.as_ref() {
-            Type::PointerType { addr_space, .. } => *addr_space,
-            _ => panic!("GlobalAlias has a non-pointer type, {:?}", ty),
-        };
-        Self {
-            name: match Name::name_or_num(unsafe { get_value_name(alias) }, ctr) {
-                Name::Name(s) => *s,
-                Name::Number(n) => panic!("expected global alias to have a string name, but instead it has the number {}", n),
-            },
-            aliasee: Constant::from_llvm_ref(unsafe { LLVMAliasGetAliasee(alias) }, ctx),
-            linkage: Linkage::from_llvm(unsafe { LLVMGetLinkage(alias) }),
-            visibility: Visibility::from_llvm(unsafe { LLVMGetVisibility(alias) }),
-            ty,
-            addr_space,
-            dll_storage_class: DLLStorageClass::from_llvm(unsafe { LLVMGetDLLStorageClass(alias) }),
-            thread_local_mode: ThreadLocalMode::from_llvm(unsafe { LLVMGetThreadLocalMode(alias) }),
-            unnamed_addr: UnnamedAddr::from_llvm(unsafe { LLVMGetUnnamedAddress(alias) }),
-        }
-    }
-}
-
-/* --TODO not yet implemented: metadata
-impl NamedMetadata {
-    pub(crate) fn from_llvm_ref(nm: LLVMNamedMDNodeRef) -> Self {
-        unimplemented!("NamedMetadata::from_llvm_ref")
-    }
-}
-*/
-
-impl UnnamedAddr {
-    pub(crate) fn from_llvm(ua: LLVMUnnamedAddr) -> Option<Self> {
-        use LLVMUnnamedAddr::*;
-        match ua {
-            LLVMNoUnnamedAddr => None,
-            LLVMLocalUnnamedAddr => Some(UnnamedAddr::Local),
-            LLVMGlobalUnnamedAddr => Some(UnnamedAddr::Global),
-        }
-    }
-}
-
-impl Linkage {
-    pub(crate) fn from_llvm(linkage: LLVMLinkage) -> Self {
-        use LLVMLinkage::*;
-        match linkage {
-            LLVMExternalLinkage => Linkage::External,
-            LLVMAvailableExternallyLinkage => Linkage::AvailableExternally,
-            LLVMLinkOnceAnyLinkage => Linkage::LinkOnceAny,
-            LLVMLinkOnceODRLinkage => Linkage::LinkOnceODR,
-            LLVMLinkOnceODRAutoHideLinkage => Linkage::LinkOnceODRAutoHide,
-            LLVMWeakAnyLinkage => Linkage::WeakAny,
-            LLVMWeakODRLinkage => Linkage::WeakODR,
-            LLVMAppendingLinkage => Linkage::Appending,
-            LLVMInternalLinkage => Linkage::Internal,
-            LLVMPrivateLinkage => Linkage::Private,
-            LLVMDLLImportLinkage => Linkage::DLLImport,
-            LLVMDLLExportLinkage => Linkage::DLLExport,
-            LLVMExternalWeakLinkage => Linkage::ExternalWeak,
-            LLVMGhostLinkage => Linkage::Ghost,
-            LLVMCommonLinkage => Linkage::Common,
-            LLVMLinkerPrivateLinkage => Linkage::LinkerPrivate,
-            LLVMLinkerPrivateWeakLinkage => Linkage::LinkerPrivateWeak,
-        }
-    }
-}
-
-impl Visibility {
-    pub(crate) fn from_llvm(visibility: LLVMVisibility) -> Self {
-        use LLVMVisibility::*;
-        match visibility {
-            LLVMDefaultVisibility => Visibility::Default,
-            LLVMHiddenVisibility => Visibility::Hidden,
-            LLVMProtectedVisibility => Visibility::Protected,
-        }
-    }
-}
-
-impl DLLStorageClass {
-    pub(crate) fn from_llvm(dllsc: LLVMDLLStorageClass) -> Self {
-        use LLVMDLLStorageClass::*;
-        match dllsc {
-            LLVMDefaultStorageClass => DLLStorageClass::Default,
-            LLVMDLLImportStorageClass => DLLStorageClass::Import,
-            LLVMDLLExportStorageClass => DLLStorageClass::Export,
-        }
-    }
-}
-
-impl ThreadLocalMode {
-    pub(crate) fn from_llvm(tlm: LLVMThreadLocalMode) -> Self {
-        use LLVMThreadLocalMode::*;
-        match tlm {
-            LLVMNotThreadLocal => ThreadLocalMode::NotThreadLocal,
-            LLVMGeneralDynamicTLSModel => ThreadLocalMode::GeneralDynamic,
-            LLVMLocalDynamicTLSModel => ThreadLocalMode::LocalDynamic,
-            LLVMInitialExecTLSModel => ThreadLocalMode::InitialExec,
-            LLVMLocalExecTLSModel => ThreadLocalMode::LocalExec,
-        }
-    }
-}
-
-impl Comdat {
-    pub(crate) fn from_llvm_ref(comdat: LLVMComdatRef) -> Self {
-        Self {
-            name: "error: not yet implemented: Comdat.name".to_owned(), // there appears to not be a getter for this in the LLVM C API?  I could be misunderstanding something
-            selection_kind: SelectionKind::from_llvm(unsafe { LLVMGetComdatSelectionKind(comdat) }),
-        }
-    }
-}
-
-impl SelectionKind {
-    pub(crate) fn from_llvm(sk: LLVMComdatSelectionKind) -> Self {
-        use LLVMComdatSelectionKind::*;
-        match sk {
-            LLVMAnyComdatSelectionKind => SelectionKind::Any,
-            LLVMExactMatchComdatSelectionKind => SelectionKind::ExactMatch,
-            LLVMLargestComdatSelectionKind => SelectionKind::Largest,
-            LLVMNoDuplicatesComdatSelectionKind => SelectionKind::NoDuplicates,
-            LLVMSameSizeComdatSelectionKind => SelectionKind::SameSize,
-        }
-    }
-}
-
-impl Default for DataLayout {
-    fn default() -> Self {
-        Self {
-            layout_str: String::new(),
-            endianness: Endianness::BigEndian,
-            stack_alignment: None,
-            program_address_space: 0,
-            alloca_address_space: 0,
-            alignments: Alignments::default(),
-            mangling: None,
-            native_int_widths: None,
-            non_integral_ptr_types: HashSet::new(),
-        }
-    }
-}
-
-impl DataLayout {
-    pub(crate) fn from_module_ref(module: LLVMModuleRef) -> Self {
-        let layout_str = unsafe { get_data_layout_str(module) };
+    /// Parse a data layout string, e.g., `e-m:e-i64:64-n8:16:32:64-S128`,
+    /// giving an error if it's malformed
+    #[cfg(any(feature = "llvm-sys", feature = "ll-parser", feature = "bc-reader"))]
+    pub(crate) fn parse_layout_str(layout_str: String) -> Result<Self, String> {
         let mut data_layout = DataLayout {
             layout_str,
             ..Default::default()
@@ -853,14 +1054,19 @@ impl DataLayout {
             } else if spec == "e" {
                 data_layout.endianness = Endianness::LittleEndian;
             } else if let Some(stripped) = spec.strip_prefix('S') {
-                data_layout.stack_alignment =
-                    Some(stripped.parse().expect("datalayout 'S': Failed to parse"));
+                data_layout.stack_alignment = Some(
+                    stripped
+                        .parse()
+                        .map_err(|_| "datalayout 'S': Failed to parse")?,
+                );
             } else if let Some(stripped) = spec.strip_prefix('P') {
-                data_layout.program_address_space =
-                    stripped.parse().expect("datalayout 'P': Failed to parse");
+                data_layout.program_address_space = stripped
+                    .parse()
+                    .map_err(|_| "datalayout 'P': Failed to parse")?;
             } else if let Some(stripped) = spec.strip_prefix('A') {
-                data_layout.alloca_address_space =
-                    stripped.parse().expect("datalayout 'A': Failed to parse");
+                data_layout.alloca_address_space = stripped
+                    .parse()
+                    .map_err(|_| "datalayout 'A': Failed to parse")?;
             } else if spec.starts_with('p') {
                 let mut chunks = spec.split(':');
                 let first_chunk = chunks.next().unwrap();
@@ -869,35 +1075,37 @@ impl DataLayout {
                 } else {
                     first_chunk[1 ..]
                         .parse()
-                        .expect("datalayout 'p': Failed to parse address space")
+                        .map_err(|_| "datalayout 'p': Failed to parse address space")?
                 };
                 let second_chunk = chunks
                     .next()
-                    .expect("datalayout 'p' spec should have a size chunk");
+                    .ok_or("datalayout 'p' spec should have a size chunk")?;
                 let size: u32 = second_chunk
                     .parse()
-                    .expect("datalayout 'p': Failed to parse pointer size");
+                    .map_err(|_| "datalayout 'p': Failed to parse pointer size")?;
                 let third_chunk = chunks
                     .next()
-                    .expect("datalayout 'p' spec should have an abi chunk");
+                    .ok_or("datalayout 'p' spec should have an abi chunk")?;
                 let abi: u32 = third_chunk
                     .parse()
-                    .expect("datalayout 'p': Failed to parse abi");
+                    .map_err(|_| "datalayout 'p': Failed to parse abi")?;
                 let pref: u32 = if let Some(fourth_chunk) = chunks.next() {
                     fourth_chunk
                         .parse()
-                        .expect("datalayout 'p': Failed to parse pref")
+                        .map_err(|_| "datalayout 'p': Failed to parse pref")?
                 } else {
                     abi
                 };
                 let idx: u32 = if let Some(fifth_chunk) = chunks.next() {
                     fifth_chunk
                         .parse()
-                        .expect("datalayout 'p': Failed to parse idx")
+                        .map_err(|_| "datalayout 'p': Failed to parse idx")?
                 } else {
                     size
                 };
-                assert!(chunks.next().is_none(), "datalayout 'p': Too many chunks");
+                if chunks.next().is_some() {
+                    return Err("datalayout 'p': Too many chunks".into());
+                }
                 data_layout.alignments.pointer_layouts.insert(
                     addr_space,
                     PointerLayout {
@@ -911,21 +1119,23 @@ impl DataLayout {
                 let first_chunk = chunks.next().unwrap();
                 let size: u32 = first_chunk[1 ..]
                     .parse()
-                    .expect("datalayout 'i': Failed to parse size");
+                    .map_err(|_| "datalayout 'i': Failed to parse size")?;
                 let second_chunk = chunks
                     .next()
-                    .expect("datalayout 'i' spec should have an abi chunk");
+                    .ok_or("datalayout 'i' spec should have an abi chunk")?;
                 let abi: u32 = second_chunk
                     .parse()
-                    .expect("datalayout 'i': Failed to parse abi");
+                    .map_err(|_| "datalayout 'i': Failed to parse abi")?;
                 let pref = if let Some(third_chunk) = chunks.next() {
                     third_chunk
                         .parse()
-                        .expect("datalayout 'i': Failed to parse pref")
+                        .map_err(|_| "datalayout 'i': Failed to parse pref")?
                 } else {
                     abi
                 };
-                assert!(chunks.next().is_none(), "datalayout 'i': Too many chunks");
+                if chunks.next().is_some() {
+                    return Err("datalayout 'i': Too many chunks".into());
+                }
                 data_layout
                     .alignments
                     .int_alignments
@@ -935,21 +1145,23 @@ impl DataLayout {
                 let first_chunk = chunks.next().unwrap();
                 let size: u32 = first_chunk[1 ..]
                     .parse()
-                    .expect("datalayout 'v': Failed to parse size");
+                    .map_err(|_| "datalayout 'v': Failed to parse size")?;
                 let second_chunk = chunks
                     .next()
-                    .expect("datalayout 'v' spec should have an abi chunk");
+                    .ok_or("datalayout 'v' spec should have an abi chunk")?;
                 let abi: u32 = second_chunk
                     .parse()
-                    .expect("datalayout 'v': Failed to parse abi");
+                    .map_err(|_| "datalayout 'v': Failed to parse abi")?;
                 let pref = if let Some(third_chunk) = chunks.next() {
                     third_chunk
                         .parse()
-                        .expect("datalayout 'v': Failed to parse pref")
+                        .map_err(|_| "datalayout 'v': Failed to parse pref")?
                 } else {
                     abi
                 };
-                assert!(chunks.next().is_none(), "datalayout 'v': Too many chunks");
+                if chunks.next().is_some() {
+                    return Err("datalayout 'v': Too many chunks".into());
+                }
                 data_layout
                     .alignments
                     .vec_alignments
@@ -959,21 +1171,23 @@ impl DataLayout {
                 let first_chunk = chunks.next().unwrap();
                 let size: u32 = first_chunk[1 ..]
                     .parse()
-                    .expect("datalayout 'f': Failed to parse size");
+                    .map_err(|_| "datalayout 'f': Failed to parse size")?;
                 let second_chunk = chunks
                     .next()
-                    .expect("datalayout 'f' spec should have an abi chunk");
+                    .ok_or("datalayout 'f' spec should have an abi chunk")?;
                 let abi: u32 = second_chunk
                     .parse()
-                    .expect("datalayout 'f': Failed to parse abi");
+                    .map_err(|_| "datalayout 'f': Failed to parse abi")?;
                 let pref = if let Some(third_chunk) = chunks.next() {
                     third_chunk
                         .parse()
-                        .expect("datalayout 'f': Failed to parse pref")
+                        .map_err(|_| "datalayout 'f': Failed to parse pref")?
                 } else {
                     abi
                 };
-                assert!(chunks.next().is_none(), "datalayout 'f': Too many chunks");
+                if chunks.next().is_some() {
+                    return Err("datalayout 'f': Too many chunks".into());
+                }
                 data_layout
                     .alignments
                     .fp_alignments
@@ -981,84 +1195,84 @@ impl DataLayout {
             } else if spec.starts_with('a') {
                 let mut chunks = spec.split(':');
                 let first_chunk = chunks.next().unwrap();
-                assert!(first_chunk == "a" || first_chunk == "a0");
+                if first_chunk != "a" && first_chunk != "a0" {
+                    return Err(format!("datalayout 'a': Unknown spec {:?}", spec));
+                }
                 let second_chunk = chunks
                     .next()
-                    .expect("datalayout 'a' spec should have an abi chunk");
+                    .ok_or("datalayout 'a' spec should have an abi chunk")?;
                 let abi: u32 = second_chunk
                     .parse()
-                    .expect("datalayout 'a': Failed to parse abi");
+                    .map_err(|_| "datalayout 'a': Failed to parse abi")?;
                 let pref = if let Some(third_chunk) = chunks.next() {
                     third_chunk
                         .parse()
-                        .expect("datalayout 'a': Failed to parse pref")
+                        .map_err(|_| "datalayout 'a': Failed to parse pref")?
                 } else {
                     abi
                 };
-                assert!(chunks.next().is_none(), "datalayout 'a': Too many chunks");
+                if chunks.next().is_some() {
+                    return Err("datalayout 'a': Too many chunks".into());
+                }
                 data_layout.alignments.agg_alignment = Alignment { abi, pref };
             } else if let Some(stripped) = spec.strip_prefix("Fi") {
-                #[cfg(feature = "llvm-8-or-lower")]
-                {
-                    panic!("datalayout: Unknown spec {:?}", spec);
-                }
-                #[cfg(feature = "llvm-9-or-greater")]
-                {
-                    let abi: u32 = stripped
-                        .parse()
-                        .expect("datalayout 'Fi': Failed to parse abi");
-                    data_layout.alignments.fptr_alignment = FunctionPtrAlignment {
-                        independent: true,
-                        abi,
-                    };
-                    data_layout.alignments.fptr_alignment_as_alignment =
-                        Alignment { abi, pref: abi };
-                }
+                let abi: u32 = stripped
+                    .parse()
+                    .map_err(|_| "datalayout 'Fi': Failed to parse abi")?;
+                data_layout.alignments.fptr_alignment = FunctionPtrAlignment {
+                    independent: true,
+                    abi,
+                };
+                data_layout.alignments.fptr_alignment_as_alignment = Alignment { abi, pref: abi };
             } else if let Some(stripped) = spec.strip_prefix("Fn") {
-                #[cfg(feature = "llvm-8-or-lower")]
-                {
-                    panic!("datalayout: Unknown spec {:?}", spec);
-                }
-                #[cfg(feature = "llvm-9-or-greater")]
-                {
-                    let abi: u32 = stripped
-                        .parse()
-                        .expect("datalayout 'Fn': Failed to parse abi");
-                    data_layout.alignments.fptr_alignment = FunctionPtrAlignment {
-                        independent: false,
-                        abi,
-                    };
-                    data_layout.alignments.fptr_alignment_as_alignment =
-                        Alignment { abi, pref: abi };
-                }
+                let abi: u32 = stripped
+                    .parse()
+                    .map_err(|_| "datalayout 'Fn': Failed to parse abi")?;
+                data_layout.alignments.fptr_alignment = FunctionPtrAlignment {
+                    independent: false,
+                    abi,
+                };
+                data_layout.alignments.fptr_alignment_as_alignment = Alignment { abi, pref: abi };
             } else if spec.starts_with('m') {
                 let mut chunks = spec.split(':');
                 let first_chunk = chunks.next().unwrap();
-                assert_eq!(first_chunk, "m");
+                if first_chunk != "m" {
+                    return Err(format!("datalayout 'm': Unknown spec {:?}", spec));
+                }
                 let second_chunk = chunks
                     .next()
-                    .expect("datalayout 'm' spec should have a mangling chunk");
+                    .ok_or("datalayout 'm' spec should have a mangling chunk")?;
                 let mangling = match second_chunk {
                     "e" => Mangling::ELF,
                     "m" => Mangling::MIPS,
                     "o" => Mangling::MachO,
                     "x" => Mangling::WindowsX86COFF,
                     "w" => Mangling::WindowsCOFF,
-                    #[cfg(feature = "llvm-11-or-greater")]
                     "a" => Mangling::XCOFF,
-                    _ => panic!("datalayout 'm': Unknown mangling {:?}", second_chunk),
+                    _ => {
+                        return Err(format!(
+                            "datalayout 'm': Unknown mangling {:?}",
+                            second_chunk
+                        ))
+                    },
                 };
-                assert!(chunks.next().is_none(), "datalayout 'm': Too many chunks");
+                if chunks.next().is_some() {
+                    return Err("datalayout 'm': Too many chunks".into());
+                }
                 data_layout.mangling = Some(mangling);
             } else if spec.starts_with("ni") {
                 let mut chunks = spec.split(':');
                 let first_chunk = chunks.next().unwrap();
-                assert_eq!(first_chunk, "ni");
+                if first_chunk != "ni" {
+                    return Err(format!("datalayout 'ni': Unknown spec {:?}", spec));
+                }
                 for chunk in chunks {
                     let addr_space: AddrSpace = chunk
                         .parse()
-                        .expect("datalayout 'ni': Failed to parse addr space");
-                    assert_ne!(addr_space, 0, "LLVM spec does not allow address space 0 to have non-integral pointer types");
+                        .map_err(|_| "datalayout 'ni': Failed to parse addr space")?;
+                    if addr_space == 0 {
+                        return Err("LLVM spec does not allow address space 0 to have non-integral pointer types".into());
+                    }
                     data_layout.non_integral_ptr_types.insert(addr_space);
                 }
             } else if spec.starts_with('n') {
@@ -1069,89 +1283,866 @@ impl DataLayout {
                 let first_chunk = chunks.next().unwrap();
                 let size = first_chunk[1 ..]
                     .parse()
-                    .expect("datalayout 'n': Failed to parse first size");
+                    .map_err(|_| "datalayout 'n': Failed to parse first size")?;
                 native_int_widths.insert(size);
                 for chunk in chunks {
-                    let size = chunk.parse().expect("datalayout 'n': Failed to parse size");
+                    let size = chunk
+                        .parse()
+                        .map_err(|_| "datalayout 'n': Failed to parse size")?;
                     native_int_widths.insert(size);
                 }
+            } else if spec.starts_with('s') {
+                // deprecated; LLVM ignores it
             } else if spec.is_empty() {
                 // do nothing
             } else {
-                panic!("datalayout: Unknown spec {:?}", spec);
+                return Err(format!("datalayout: Unknown spec {:?}", spec));
             }
         }
-        data_layout
+        Ok(data_layout)
     }
 }
 
-impl Default for Alignments {
-    fn default() -> Self {
-        Self {
-            /// Explicit alignments for various sizes of integers (in bits). Sizes not
-            /// specified here are determined according to the rules described in the
-            /// Data Layout docs.
-            int_alignments: vec![
-                (1, Alignment { abi: 8, pref: 8 }),
-                (8, Alignment { abi: 8, pref: 8 }),
-                (16, Alignment { abi: 16, pref: 16 }),
-                (32, Alignment { abi: 32, pref: 32 }),
-                (64, Alignment { abi: 32, pref: 64 }),
-            ]
-            .into_iter()
-            .collect(),
-            /// Explicit alignments for various sizes of vectors (in bits). Sizes not
-            /// specified here are determined according to the rules described in the
-            /// Data Layout docs.
-            vec_alignments: vec![
-                (64, Alignment { abi: 64, pref: 64 }),
-                (
-                    128,
-                    Alignment {
-                        abi: 128,
-                        pref: 128,
-                    },
-                ),
-            ]
-            .into_iter()
-            .collect(),
-            /// Alignment for floating-point types, by size (in bits)
-            fp_alignments: vec![
-                (16, Alignment { abi: 16, pref: 16 }),
-                (32, Alignment { abi: 32, pref: 32 }),
-                (64, Alignment { abi: 64, pref: 64 }),
+// ********* //
+// from_llvm //
+// ********* //
+
+#[cfg(feature = "llvm-sys")]
+use crate::constant::{Constant, ConstantsBuilder};
+#[cfg(feature = "llvm-sys")]
+use crate::from_llvm::*;
+#[cfg(feature = "llvm-sys")]
+use crate::function::AttributesData;
+#[cfg(feature = "llvm-sys")]
+use crate::name::Name;
+#[cfg(feature = "llvm-sys")]
+use crate::types::TypesBuilder;
+#[cfg(feature = "llvm-sys")]
+use llvm_sys::comdat::*;
+#[cfg(feature = "llvm-sys")]
+use llvm_sys::{
+    LLVMDLLStorageClass,
+    LLVMLinkage,
+    LLVMThreadLocalMode,
+    LLVMUnnamedAddr,
+    LLVMVisibility,
+};
+#[cfg(feature = "llvm-sys")]
+use std::sync::{Arc, Mutex};
+
+/// This struct contains data used when translating llvm-sys objects into our
+/// data structures
+#[cfg(feature = "llvm-sys")]
+pub(crate) struct ModuleContext {
+    pub types: TypesBuilder,
+    pub constants: ConstantsBuilder,
+    pub attrsdata: AttributesData,
+    /// Map from an llvm-sys constant to the corresponding llvm-ir `ConstantRef`
+    // We use LLVMValueRef as a *const, even though it's technically a *mut
+    #[allow(clippy::mutable_key_type)]
+    pub llvm_constant_map: HashMap<LLVMValueRef, ConstantRef>,
+    /// Map from an llvm-sys global to its `Name`
+    // We use LLVMValueRef as a *const, even though it's technically a *mut
+    #[allow(clippy::mutable_key_type)]
+    pub global_names: Arc<HashMap<LLVMValueRef, Name>>,
+    /// The `FunctionAttributeGroup`s seen so far, indexed by `GroupID`
+    pub function_attribute_groups: Vec<FunctionAttributeGroup>,
+    /// Map from a set of function attributes to the `GroupID` of its group
+    pub function_attribute_group_ids: HashMap<Vec<FunctionAttribute>, GroupID>,
+    /// The comdats seen so far, with their members
+    pub comdats: Vec<ComdatGroup>,
+    /// Map from a comdat name to its index in `comdats`
+    pub comdat_indices: HashMap<String, usize>,
+    /// Map from an attribute group (e.g., `#0`) to its string attributes, for
+    /// the attributes of global variables. This is only filled in (from the
+    /// module's textual form) once we come across a global with attributes.
+    pub global_attribute_groups: Option<HashMap<String, Vec<(String, String)>>>,
+    /// Which parts of the module to convert
+    pub options: ParseOptions,
+    /// LLVM's textual form of the module, for the few properties the LLVM C
+    /// API has no getters for. It's printed up front, as LLVM can't safely
+    /// print while other threads are converting functions.
+    pub text: Arc<ModuleText>,
+    /// Held while calling into LLVM in ways which modify the `LLVMContext`;
+    /// shared with any contexts `fork()`ed from this one
+    context_lock: Arc<Mutex<()>>,
+}
+
+#[cfg(feature = "llvm-sys")]
+impl ModuleContext {
+    // `global_names` isn't `Send` or `Sync`, but is only shared between
+    // threads by `Function::from_llvm_refs()`, which takes care of that
+    #[allow(clippy::arc_with_non_send_sync)]
+    pub(crate) fn new(module: LLVMModuleRef) -> Self {
+        let mut global_ctr = 0; // this ctr is used to number global objects that aren't named
+
+        // Modules require two passes over their contents.
+        // First we make a pass just to map global objects -- in particular, Functions,
+        //   GlobalVariables, and GlobalAliases -- to Names; then we do the actual
+        //   detailed pass.
+        // This is necessary because these structures may reference each other in a
+        //   circular fashion, and we need to be able to fill in the Name of the
+        //   referenced object from having only its `LLVMValueRef`.
+        // We use LLVMValueRef as a *const, even though it's technically a *mut
+        #[allow(clippy::mutable_key_type)]
+        let global_names: HashMap<LLVMValueRef, Name> = get_defined_functions(module)
+            .chain(get_declared_functions(module))
+            .chain(get_globals(module))
+            .chain(get_global_aliases(module))
+            .chain(get_global_ifuncs_if_supported(module))
+            .map(|g| {
                 (
-                    128,
-                    Alignment {
-                        abi: 128,
-                        pref: 128,
-                    },
-                ),
-            ]
-            .into_iter()
-            .collect(),
-            /// Alignment for aggregate types (structs, arrays)
-            agg_alignment: Alignment { abi: 0, pref: 64 },
-            /// Alignment for function pointers
-            #[cfg(feature = "llvm-9-or-greater")]
-            fptr_alignment: FunctionPtrAlignment {
-                independent: true,
-                abi: 64,
+                    g,
+                    Name::name_or_num(unsafe { get_value_name(g) }, &mut global_ctr),
+                )
+            })
+            .collect();
+
+        #[allow(unused_mut)]
+        let mut types = TypesBuilder::new();
+        #[cfg(feature = "llvm-14-or-greater")]
+        types.set_opaque_pointers_from_llvm_context(unsafe { LLVMGetModuleContext(module) });
+
+        Self {
+            types,
+            constants: ConstantsBuilder::new(),
+            attrsdata: AttributesData::create(),
+            llvm_constant_map: HashMap::new(),
+            global_names: Arc::new(global_names),
+            function_attribute_groups: Vec::new(),
+            function_attribute_group_ids: HashMap::new(),
+            comdats: Vec::new(),
+            comdat_indices: HashMap::new(),
+            global_attribute_groups: None,
+            options: ParseOptions::default(),
+            text: Arc::new(unsafe { ModuleText::print(module) }),
+            context_lock: Arc::new(Mutex::new(())),
+        }
+    }
+
+    /// Call `f`, which calls into LLVM in a way that modifies the
+    /// `LLVMContext` (e.g., by creating a constant or a metadata value), while
+    /// no other thread converting this module is doing the same. Most of the
+    /// LLVM C API we use only reads the IR, and needs no such care.
+    pub fn with_context_lock<T>(&self, f: impl FnOnce() -> T) -> T {
+        let _guard = self
+            .context_lock
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        f()
+    }
+
+    /// Get the textual form of the given function. If the module's textual
+    /// form doesn't include the function's body (because the body was read
+    /// after the module was printed; see `LazyModule`), this prints the
+    /// function now.
+    pub fn function_text(&self, func: LLVMValueRef) -> Arc<FunctionText> {
+        match self.text.function(func) {
+            Some(text) if text.has_body() || unsafe { LLVMCountBasicBlocks(func) } == 0 => {
+                Arc::clone(text)
             },
-            /// Alignment for function pointers, as an `Alignment`
+            _ => Arc::new(FunctionText::new(
+                self.with_context_lock(|| unsafe { print_to_owned_string(func) }),
+            )),
+        }
+    }
+
+    /// Get the line defining the given global variable in the module's textual
+    /// form
+    pub fn global_text(&self, global: LLVMValueRef) -> String {
+        match self.text.global(global) {
+            Some(text) => text.to_owned(),
+            None => self.with_context_lock(|| unsafe { print_to_owned_string(global) }),
+        }
+    }
+
+    /// Get a new context for converting some of the module's functions on
+    /// another thread. It starts with the types and constants seen so far, but
+    /// no function attribute groups or comdats; `join()` merges those back in
+    /// afterwards.
+    #[cfg(feature = "parallel")]
+    pub fn fork(&self) -> Self {
+        Self {
+            types: self.types.clone(),
+            constants: self.constants.clone(),
+            attrsdata: self.attrsdata.clone(),
+            llvm_constant_map: HashMap::new(),
+            global_names: Arc::clone(&self.global_names),
+            function_attribute_groups: Vec::new(),
+            function_attribute_group_ids: HashMap::new(),
+            comdats: Vec::new(),
+            comdat_indices: HashMap::new(),
+            global_attribute_groups: None,
+            options: self.options.clone(),
+            text: Arc::clone(&self.text),
+            context_lock: Arc::clone(&self.context_lock),
+        }
+    }
+
+    /// Merge back a context from `fork()`, which converted `functions`.
+    ///
+    /// This renumbers the functions' attribute groups, and records their
+    /// comdats, exactly as if this context had converted them itself.
+    #[cfg(feature = "parallel")]
+    pub fn join(&mut self, forked: Self, functions: &mut [Function]) {
+        self.types.absorb(forked.types);
+        self.constants.absorb(forked.constants);
+        let forked_groups = forked.function_attribute_groups;
+        for func in functions {
+            func.for_each_function_attribute_group(|group_id| {
+                if let Some(id) = *group_id {
+                    let attrs = forked_groups[id].attrs.clone();
+                    *group_id = self.intern_function_attributes(attrs);
+                }
+            });
+            if let Some(comdat) = &func.comdat {
+                self.add_comdat_member(comdat, &func.name, true);
+            }
+        }
+    }
+
+    /// Get the string attributes in the given attribute group (e.g., `#0`),
+    /// which is referenced by a global variable
+    pub fn global_attributes(&mut self, group: &str) -> Vec<(String, String)> {
+        let text = &self.text;
+        self.global_attribute_groups
+            .get_or_insert_with(|| {
+                // The LLVM C API has no getters for the attributes of global
+                // variables, so we get them from the module's textual form
+                text.attribute_groups()
+                    .filter_map(|line| {
+                        let tokens = tokens_outside_quotes(line);
+                        let group = tokens.get(1)?.to_string();
+                        let attrs = tokens
+                            .iter()
+                            .filter_map(|tok| {
+                                // string attributes look like `"kind"="value"` or just `"kind"`
+                                let tok = tok.strip_prefix('"')?.strip_suffix('"')?;
+                                Some(match tok.find("\"=\"") {
+                                    Some(i) => (unescape(&tok[.. i]), unescape(&tok[i + 3 ..])),
+                                    None => (unescape(tok), String::new()),
+                                })
+                            })
+                            .collect();
+                        Some((group, attrs))
+                    })
+                    .collect()
+            })
+            .get(group)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Record that the function or global variable named `global_name` belongs
+    /// to the given `Comdat`
+    pub fn add_comdat_member(&mut self, comdat: &Comdat, global_name: &str, is_function: bool) {
+        let comdats = &mut self.comdats;
+        let idx = *self
+            .comdat_indices
+            .entry(comdat.name.clone())
+            .or_insert_with(|| {
+                comdats.push(ComdatGroup {
+                    comdat: comdat.clone(),
+                    functions: vec![],
+                    global_vars: vec![],
+                });
+                comdats.len() - 1
+            });
+        let group = &mut self.comdats[idx];
+        if is_function {
+            group.functions.push(global_name.to_owned());
+        } else {
+            group.global_vars.push(global_name.to_owned());
+        }
+    }
+
+    /// Get the `GroupID` for the given set of function attributes, creating a
+    /// new group if we haven't seen this set before. Returns `None` for an
+    /// empty set.
+    pub fn intern_function_attributes(&mut self, attrs: Vec<FunctionAttribute>) -> Option<GroupID> {
+        if attrs.is_empty() {
+            return None;
+        }
+        if let Some(&group_id) = self.function_attribute_group_ids.get(&attrs) {
+            return Some(group_id);
+        }
+        let group_id = self.function_attribute_groups.len();
+        self.function_attribute_group_ids
+            .insert(attrs.clone(), group_id);
+        self.function_attribute_groups
+            .push(FunctionAttributeGroup { group_id, attrs });
+        Some(group_id)
+    }
+}
+
+#[cfg(feature = "llvm-sys")]
+impl Module {
+    pub(crate) fn from_llvm_ref(module: LLVMModuleRef, options: &ParseOptions) -> Self {
+        debug!("Creating a Module from an LLVMModuleRef");
+        let mut ctx = ModuleContext::new(module);
+        ctx.options = options.clone();
+        let functions = Function::from_llvm_refs(get_defined_functions(module).collect(), &mut ctx);
+        let mut converted = Self::from_llvm_ref_without_functions(module, &mut ctx);
+        converted.functions = functions;
+        // these must come after all the functions and global variables have
+        // been processed, so that we've seen all the attribute sets, comdat
+        // members, types, and constants
+        converted.function_attribute_groups = std::mem::take(&mut ctx.function_attribute_groups);
+        converted.comdats = std::mem::take(&mut ctx.comdats);
+        converted.types = ctx.types.build();
+        converted.constants = ctx.constants.build();
+        converted
+    }
+
+    fn stream_from_llvm_ref(module: LLVMModuleRef, mut f: impl FnMut(&Module, Function)) {
+        debug!("Streaming the functions of an LLVMModuleRef");
+        let mut ctx = ModuleContext::new(module);
+        let mut converted = Self::from_llvm_ref_without_functions(module, &mut ctx);
+        converted.function_attribute_groups = ctx.function_attribute_groups.clone();
+        converted.comdats = ctx.comdats.clone();
+        converted.types = ctx.types.clone().build();
+        let mut num_types = ctx.types.num_types();
+        for func in get_defined_functions(module) {
+            // let go of `converted`'s constants first, so that `ctx` can add
+            // to them without copying them
+            converted.constants = Constants::default();
+            let func = Function::from_llvm_ref(func, &mut ctx);
+            // bring `converted` up to date with anything the function added
+            let num_groups = converted.function_attribute_groups.len();
+            converted
+                .function_attribute_groups
+                .extend_from_slice(&ctx.function_attribute_groups[num_groups ..]);
+            if let Some(comdat) = &func.comdat {
+                let idx = ctx.comdat_indices[&comdat.name];
+                match converted.comdats.get_mut(idx) {
+                    Some(group) => group.functions.push(func.name.clone()),
+                    None => converted.comdats.push(ctx.comdats[idx].clone()),
+                }
+            }
+            if ctx.types.num_types() != num_types {
+                num_types = ctx.types.num_types();
+                converted.types = ctx.types.clone().build();
+            }
+            converted.constants = ctx.constants.clone().build();
+            f(&converted, func);
+        }
+    }
+
+    /// Convert everything in the module except the bodies of its defined
+    /// functions. This leaves `functions`, `function_attribute_groups`,
+    /// `comdats`, `types`, and `constants` empty; the caller fills them in
+    /// from `ctx` once it has converted whichever functions it wants.
+    pub(crate) fn from_llvm_ref_without_functions(
+        module: LLVMModuleRef,
+        ctx: &mut ModuleContext,
+    ) -> Self {
+        let mut global_ctr = 0; // this ctr is used to number global objects that aren't named
+        Self {
+            name: unsafe { get_module_identifier(module) },
+            source_file_name: unsafe { get_source_file_name(module) },
+            data_layout: DataLayout::from_module_ref(module),
+            target_triple: unsafe { get_target(module) },
+            functions: vec![],
+            func_declarations: get_declared_functions(module)
+                .map(|f| FunctionDeclaration::from_llvm_ref(f, ctx))
+                .collect(),
+            global_vars: get_globals(module)
+                .map(|g| GlobalVariable::from_llvm_ref(g, &mut global_ctr, ctx))
+                .collect(),
+            global_aliases: get_global_aliases(module)
+                .map(|g| GlobalAlias::from_llvm_ref(g, &mut global_ctr, ctx))
+                .collect(),
             #[cfg(feature = "llvm-9-or-greater")]
-            fptr_alignment_as_alignment: Alignment { abi: 64, pref: 64 },
-            /// Layout details for (non-function-pointer) pointers, by address space
-            pointer_layouts: vec![(
-                0,
-                PointerLayout {
-                    size: 64,
-                    alignment: Alignment { abi: 64, pref: 64 },
-                    index_size: 64,
+            global_ifuncs: get_global_ifuncs(module)
+                .map(|g| GlobalIFunc::from_llvm_ref(g, &mut global_ctr, ctx))
+                .collect(),
+            // the LLVM C API has no access to ifuncs before LLVM 9
+            #[cfg(feature = "llvm-8-or-lower")]
+            global_ifuncs: vec![],
+            function_attribute_groups: vec![],
+            inline_assembly: unsafe { get_module_inline_asm(module) },
+            // metadata_nodes: unimplemented!("metadata_nodes"),
+            // named_metadatas: unimplemented!("named_metadatas"),
+            comdats: vec![],
+            types: TypesBuilder::new().build(),
+            constants: Constants::default(),
+            llvm_version: LLVMVersion::selected(),
+        }
+    }
+}
+
+#[cfg(feature = "llvm-sys")]
+impl GlobalVariable {
+    pub(crate) fn from_llvm_ref(
+        global: LLVMValueRef,
+        ctr: &mut usize,
+        ctx: &mut ModuleContext,
+    ) -> Self {
+        let ty = ctx.types.type_from_llvm_ref(unsafe { LLVMTypeOf(global) });
+        let addr_space = match ty.as_ref() {
+            Type::PointerType { addr_space, .. } => *addr_space,
+            #[cfg(feature = "llvm-14-or-greater")]
+            Type::OpaquePointerType { addr_space } => *addr_space,
+            _ => panic!("GlobalVariable has a non-pointer type, {:?}", ty),
+        };
+        debug!("Processing a GlobalVariable with type {:?}", ty);
+        let name = match Name::name_or_num(unsafe { get_value_name(global) }, ctr) {
+            Name::Name(s) => s.to_string(),
+            Name::Number(n) => panic!(
+                "expected global variable to have a string name, but instead it has the number {}",
+                n
+            ),
+        };
+        let comdat = Comdat::from_llvm_global(global, &name, || ctx.global_text(global));
+        if let Some(comdat) = &comdat {
+            ctx.add_comdat_member(comdat, &name, false);
+        }
+        // Partitions, attributes, and sanitizer metadata of global variables
+        // aren't exposed in the LLVM C API, so we get them from the global's
+        // textual form
+        let text = ctx.global_text(global);
+        let tokens = tokens_outside_quotes(&text);
+        let has_keyword = |keyword: &str| {
+            tokens
+                .iter()
+                .any(|tok| tok.trim_end_matches(',') == keyword)
+        };
+        Self {
+            name,
+            linkage: Linkage::from_llvm(unsafe { LLVMGetLinkage(global) }),
+            visibility: Visibility::from_llvm(unsafe { LLVMGetVisibility(global) }),
+            is_constant: unsafe { LLVMIsGlobalConstant(global) } != 0,
+            ty,
+            value_type: ctx
+                .types
+                .type_from_llvm_ref(unsafe { LLVMGlobalGetValueType(global) }),
+            addr_space,
+            dll_storage_class: DLLStorageClass::from_llvm(unsafe {
+                LLVMGetDLLStorageClass(global)
+            }),
+            thread_local_mode: ThreadLocalMode::from_llvm(unsafe {
+                LLVMGetThreadLocalMode(global)
+            }),
+            unnamed_addr: UnnamedAddr::from_llvm(unsafe { LLVMGetUnnamedAddress(global) }),
+            initializer: {
+                let it = unsafe { LLVMGetInitializer(global) };
+                if it.is_null() {
+                    None
+                } else {
+                    Some(Constant::from_llvm_ref(it, ctx))
+                }
+            },
+            section: unsafe { get_section(global) },
+            comdat,
+            alignment: unsafe { LLVMGetAlignment(global) },
+            externally_initialized: unsafe { LLVMIsExternallyInitialized(global) } != 0,
+            partition: tokens
+                .iter()
+                .position(|&tok| tok == "partition")
+                .and_then(|i| tokens.get(i + 1))
+                .and_then(|tok| unquote(tok)),
+            attributes: match tokens.last() {
+                Some(tok) if tok.starts_with('#') && !ctx.options.skip_attributes => {
+                    ctx.global_attributes(tok)
                 },
-            )]
-            .into_iter()
-            .collect(),
+                _ => vec![],
+            },
+            sanitizer_metadata: SanitizerMetadata {
+                no_address: has_keyword("no_sanitize_address"),
+                no_hwaddress: has_keyword("no_sanitize_hwaddress"),
+                memtag: has_keyword("sanitize_memtag"),
+                is_dyn_init: has_keyword("sanitize_address_dyninit"),
+            },
+            debugloc: if ctx.options.skip_debug_info {
+                None
+            } else {
+                DebugLoc::from_llvm_no_col(global)
+            },
+            metadata: MetadataAttachment::from_llvm_global(global, &text, ctx),
+        }
+    }
+}
+
+#[cfg(feature = "llvm-sys")]
+impl GlobalAlias {
+    pub(crate) fn from_llvm_ref(
+        alias: LLVMValueRef,
+        ctr: &mut usize,
+        ctx: &mut ModuleContext,
+    ) -> Self {
+        let ty = ctx.types.type_from_llvm_ref(unsafe { LLVMTypeOf(alias) });
+        let addr_space = match ty.as_ref() {
+            Type::PointerType { addr_space, .. } => *addr_space,
+            #[cfg(feature = "llvm-14-or-greater")]
+            Type::OpaquePointerType { addr_space } => *addr_space,
+            _ => panic!("GlobalAlias has a non-pointer type, {:?}", ty),
+        };
+        Self {
+            name: match Name::name_or_num(unsafe { get_value_name(alias) }, ctr) {
+                Name::Name(s) => s.to_string(),
+                Name::Number(n) => panic!("expected global alias to have a string name, but instead it has the number {}", n),
+            },
+            aliasee: Constant::from_llvm_ref(unsafe { LLVMAliasGetAliasee(alias) }, ctx),
+            linkage: Linkage::from_llvm(unsafe { LLVMGetLinkage(alias) }),
+            visibility: Visibility::from_llvm(unsafe { LLVMGetVisibility(alias) }),
+            ty,
+            value_type: ctx
+                .types
+                .type_from_llvm_ref(unsafe { LLVMGlobalGetValueType(alias) }),
+            addr_space,
+            dll_storage_class: DLLStorageClass::from_llvm(unsafe { LLVMGetDLLStorageClass(alias) }),
+            thread_local_mode: ThreadLocalMode::from_llvm(unsafe { LLVMGetThreadLocalMode(alias) }),
+            unnamed_addr: UnnamedAddr::from_llvm(unsafe { LLVMGetUnnamedAddress(alias) }),
+        }
+    }
+}
+
+#[cfg(all(feature = "llvm-sys", feature = "llvm-9-or-greater"))]
+impl GlobalIFunc {
+    pub(crate) fn from_llvm_ref(
+        ifunc: LLVMValueRef,
+        ctr: &mut usize,
+        ctx: &mut ModuleContext,
+    ) -> Self {
+        let ty = ctx.types.type_from_llvm_ref(unsafe { LLVMTypeOf(ifunc) });
+        let addr_space = match ty.as_ref() {
+            Type::PointerType { addr_space, .. } => *addr_space,
+            #[cfg(feature = "llvm-14-or-greater")]
+            Type::OpaquePointerType { addr_space } => *addr_space,
+            _ => panic!("GlobalIFunc has a non-pointer type, {:?}", ty),
+        };
+        Self {
+            name: match Name::name_or_num(unsafe { get_value_name(ifunc) }, ctr) {
+                Name::Name(s) => s.to_string(),
+                Name::Number(n) => panic!(
+                    "expected global ifunc to have a string name, but instead it has the number {}",
+                    n
+                ),
+            },
+            linkage: Linkage::from_llvm(unsafe { LLVMGetLinkage(ifunc) }),
+            visibility: Visibility::from_llvm(unsafe { LLVMGetVisibility(ifunc) }),
+            ty,
+            value_type: ctx
+                .types
+                .type_from_llvm_ref(unsafe { LLVMGlobalGetValueType(ifunc) }),
+            addr_space,
+            resolver: Constant::from_llvm_ref(unsafe { LLVMGetGlobalIFuncResolver(ifunc) }, ctx),
+        }
+    }
+}
+
+/// IFuncs can't be read with the LLVM C API before LLVM 9, so for those
+/// versions we act as though the module has none
+#[cfg(all(feature = "llvm-sys", feature = "llvm-9-or-greater"))]
+fn get_global_ifuncs_if_supported(module: LLVMModuleRef) -> impl Iterator<Item = LLVMValueRef> {
+    get_global_ifuncs(module)
+}
+#[cfg(all(feature = "llvm-sys", feature = "llvm-8-or-lower"))]
+fn get_global_ifuncs_if_supported(_module: LLVMModuleRef) -> impl Iterator<Item = LLVMValueRef> {
+    std::iter::empty()
+}
+
+#[cfg(feature = "llvm-sys")]
+impl MetadataAttachment {
+    /// Get the metadata attached to the given function or global variable.
+    /// `header` is the header of the function, or the line defining the
+    /// global, from which we get the names of the attachments' kinds, as the
+    /// LLVM C API has no getter for those.
+    pub(crate) fn from_llvm_global(
+        global: LLVMValueRef,
+        header: &str,
+        ctx: &mut ModuleContext,
+    ) -> Vec<Self> {
+        let mut num_entries = 0;
+        let entries = unsafe { LLVMGlobalCopyAllMetadata(global, &mut num_entries) };
+        let llvm_ctx = unsafe { LLVMGetModuleContext(LLVMGetGlobalParent(global)) };
+        let kind_names: HashMap<u32, String> = if num_entries > 0 {
+            attachment_kind_names(header)
+                .into_iter()
+                .map(|name| {
+                    let id = ctx.with_context_lock(|| unsafe {
+                        LLVMGetMDKindIDInContext(
+                            llvm_ctx,
+                            name.as_ptr() as *const _,
+                            name.len() as u32,
+                        )
+                    });
+                    (id, name)
+                })
+                .collect()
+        } else {
+            HashMap::new()
+        };
+        let skip_debug_info = ctx.options.skip_debug_info;
+        let attachments = (0 .. num_entries as u32)
+            .filter(|&i| {
+                // `!dbg` attachments are debug info
+                !skip_debug_info
+                    || kind_names
+                        .get(&unsafe { LLVMValueMetadataEntriesGetKind(entries, i) })
+                        .map(String::as_str)
+                        != Some("dbg")
+            })
+            .filter_map(|i| {
+                let kind = unsafe { LLVMValueMetadataEntriesGetKind(entries, i) };
+                let node = ctx.with_context_lock(|| unsafe {
+                    LLVMMetadataAsValue(llvm_ctx, LLVMValueMetadataEntriesGetMetadata(entries, i))
+                });
+                // an attachment whose kind we didn't find in the text is
+                // skipped, rather than given a made-up kind
+                let kind = kind_names.get(&kind)?.clone();
+                Some(Self {
+                    kind,
+                    node: AttachedMetadata::from_llvm_ref(node, ctx, &mut vec![]),
+                })
+            })
+            .collect();
+        unsafe { LLVMDisposeValueMetadataEntries(entries) };
+        attachments
+    }
+
+    /// Does the given function or global variable have any metadata attached
+    pub(crate) fn has_any(global: LLVMValueRef) -> bool {
+        let mut num_entries = 0;
+        unsafe {
+            let entries = LLVMGlobalCopyAllMetadata(global, &mut num_entries);
+            LLVMDisposeValueMetadataEntries(entries);
+        }
+        num_entries > 0
+    }
+}
+
+/// Get the names of the kinds of the metadata attachments (e.g., `type` for
+/// `!type !0`) in the header of a function, or the line defining a global
+/// variable
+#[cfg(feature = "llvm-sys")]
+fn attachment_kind_names(header: &str) -> Vec<String> {
+    let tokens = tokens_outside_quotes(header);
+    tokens
+        .iter()
+        .zip(tokens.iter().skip(1))
+        .filter_map(|(tok, next)| {
+            let kind = tok.strip_prefix('!')?;
+            let is_kind = !kind.starts_with(|c: char| c.is_ascii_digit() || c == '{' || c == '"')
+                && next.starts_with('!')
+                && next[1 ..].starts_with(|c: char| c.is_ascii_digit());
+            if is_kind {
+                Some(unescape(kind))
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+#[cfg(feature = "llvm-sys")]
+impl AttachedMetadata {
+    /// `md` is a metadata value, i.e., a `MetadataAsValue`, or a constant
+    /// operand of a metadata node. `path` holds the nodes we're in the middle
+    /// of converting, so that we don't loop forever on cyclic nodes.
+    pub(crate) fn from_llvm_ref(
+        md: LLVMValueRef,
+        ctx: &mut ModuleContext,
+        path: &mut Vec<LLVMValueRef>,
+    ) -> Self {
+        use llvm_sys::debuginfo::{LLVMGetMetadataKind, LLVMMetadataKind};
+        if unsafe { !LLVMIsAMDString(md).is_null() } {
+            let mut len = 0;
+            let ptr = unsafe { LLVMGetMDString(md, &mut len) };
+            let bytes = unsafe { std::slice::from_raw_parts(ptr as *const u8, len as usize) };
+            Self::String(String::from_utf8_lossy(bytes).into_owned())
+        } else if unsafe { !LLVMIsAConstant(md).is_null() } {
+            Self::Constant(Constant::from_llvm_ref(md, ctx))
+        } else if unsafe { !LLVMIsAMDNode(md).is_null() }
+            && matches!(
+                unsafe { LLVMGetMetadataKind(LLVMValueAsMetadata(md)) },
+                LLVMMetadataKind::LLVMMDTupleMetadataKind
+            )
+            && !path.contains(&md)
+        {
+            path.push(md);
+            let num_operands = unsafe { LLVMGetMDNodeNumOperands(md) };
+            let mut operands: Vec<LLVMValueRef> = Vec::with_capacity(num_operands as usize);
+            // this creates `MetadataAsValue`s for the operands
+            ctx.with_context_lock(|| unsafe {
+                LLVMGetMDNodeOperands(md, operands.as_mut_ptr());
+                operands.set_len(num_operands as usize);
+            });
+            let tuple = operands
+                .into_iter()
+                .map(|op| {
+                    if op.is_null() {
+                        None
+                    } else {
+                        Some(Self::from_llvm_ref(op, ctx, path))
+                    }
+                })
+                .collect();
+            path.pop();
+            Self::Tuple(tuple)
+        } else {
+            // LLVM prints metadata nodes as `<0x...> = !{...}`, or as
+            // `!N = !{...}` if it finds a module to number them in
+            let text = ctx.with_context_lock(|| unsafe { print_to_owned_string(md) });
+            let text = match text.find(" = ") {
+                Some(i) if text.starts_with('<') || text.starts_with('!') => &text[i + 3 ..],
+                _ => &text,
+            };
+            Self::Other(without_node_addresses(text))
+        }
+    }
+}
+
+/* --TODO not yet implemented: metadata
+#[cfg(feature = "llvm-sys")]
+impl NamedMetadata {
+    pub(crate) fn from_llvm_ref(nm: LLVMNamedMDNodeRef) -> Self {
+        unimplemented!("NamedMetadata::from_llvm_ref")
+    }
+}
+*/
+
+#[cfg(feature = "llvm-sys")]
+impl UnnamedAddr {
+    pub(crate) fn from_llvm(ua: LLVMUnnamedAddr) -> Option<Self> {
+        use LLVMUnnamedAddr::*;
+        match ua {
+            LLVMNoUnnamedAddr => None,
+            LLVMLocalUnnamedAddr => Some(UnnamedAddr::Local),
+            LLVMGlobalUnnamedAddr => Some(UnnamedAddr::Global),
+        }
+    }
+}
+
+#[cfg(feature = "llvm-sys")]
+impl Linkage {
+    pub(crate) fn from_llvm(linkage: LLVMLinkage) -> Self {
+        use LLVMLinkage::*;
+        match linkage {
+            LLVMExternalLinkage => Linkage::External,
+            LLVMAvailableExternallyLinkage => Linkage::AvailableExternally,
+            LLVMLinkOnceAnyLinkage => Linkage::LinkOnceAny,
+            LLVMLinkOnceODRLinkage => Linkage::LinkOnceODR,
+            LLVMLinkOnceODRAutoHideLinkage => Linkage::LinkOnceODRAutoHide,
+            LLVMWeakAnyLinkage => Linkage::WeakAny,
+            LLVMWeakODRLinkage => Linkage::WeakODR,
+            LLVMAppendingLinkage => Linkage::Appending,
+            LLVMInternalLinkage => Linkage::Internal,
+            LLVMPrivateLinkage => Linkage::Private,
+            LLVMDLLImportLinkage => Linkage::DLLImport,
+            LLVMDLLExportLinkage => Linkage::DLLExport,
+            LLVMExternalWeakLinkage => Linkage::ExternalWeak,
+            LLVMGhostLinkage => Linkage::Ghost,
+            LLVMCommonLinkage => Linkage::Common,
+            LLVMLinkerPrivateLinkage => Linkage::LinkerPrivate,
+            LLVMLinkerPrivateWeakLinkage => Linkage::LinkerPrivateWeak,
+        }
+    }
+}
+
+#[cfg(feature = "llvm-sys")]
+impl Visibility {
+    pub(crate) fn from_llvm(visibility: LLVMVisibility) -> Self {
+        use LLVMVisibility::*;
+        match visibility {
+            LLVMDefaultVisibility => Visibility::Default,
+            LLVMHiddenVisibility => Visibility::Hidden,
+            LLVMProtectedVisibility => Visibility::Protected,
+        }
+    }
+}
+
+#[cfg(feature = "llvm-sys")]
+impl DLLStorageClass {
+    pub(crate) fn from_llvm(dllsc: LLVMDLLStorageClass) -> Self {
+        use LLVMDLLStorageClass::*;
+        match dllsc {
+            LLVMDefaultStorageClass => DLLStorageClass::Default,
+            LLVMDLLImportStorageClass => DLLStorageClass::Import,
+            LLVMDLLExportStorageClass => DLLStorageClass::Export,
+        }
+    }
+}
+
+#[cfg(feature = "llvm-sys")]
+impl ThreadLocalMode {
+    pub(crate) fn from_llvm(tlm: LLVMThreadLocalMode) -> Self {
+        use LLVMThreadLocalMode::*;
+        match tlm {
+            LLVMNotThreadLocal => ThreadLocalMode::NotThreadLocal,
+            LLVMGeneralDynamicTLSModel => ThreadLocalMode::GeneralDynamic,
+            LLVMLocalDynamicTLSModel => ThreadLocalMode::LocalDynamic,
+            LLVMInitialExecTLSModel => ThreadLocalMode::InitialExec,
+            LLVMLocalExecTLSModel => ThreadLocalMode::LocalExec,
+        }
+    }
+}
+
+#[cfg(feature = "llvm-sys")]
+impl Comdat {
+    /// Get the `Comdat` of the given function or global variable (named
+    /// `global_name`), if it has one. `header` gets the line defining the
+    /// global, or the header of the function, in the module's textual form.
+    pub(crate) fn from_llvm_global(
+        global: LLVMValueRef,
+        global_name: &str,
+        header: impl FnOnce() -> String,
+    ) -> Option<Self> {
+        let comdat = unsafe { LLVMGetComdat(global) };
+        if comdat.is_null() {
+            return None;
+        }
+        // The LLVM C API has no getter for the comdat's name, so we get it
+        // from the textual form of the global
+        Some(Self {
+            name: comdat_name_from_text(&header()).unwrap_or_else(|| global_name.to_owned()),
+            selection_kind: SelectionKind::from_llvm(unsafe { LLVMGetComdatSelectionKind(comdat) }),
+        })
+    }
+}
+
+/// Given the header of a function or global variable which belongs to a
+/// comdat, get the name of the comdat from its `comdat($name)`; or `None` if it
+/// is just `comdat`, meaning the comdat has the same name as the global.
+#[cfg(feature = "llvm-sys")]
+fn comdat_name_from_text(header: &str) -> Option<String> {
+    // skip over quoted strings (e.g., names and section names) which might
+    // happen to contain ` comdat`. Quoted strings can't contain a raw `"`
+    // (that's escaped as `\22`)
+    let mut in_quotes = false;
+    for (i, c) in header.char_indices() {
+        if c == '"' {
+            in_quotes = !in_quotes;
+        } else if !in_quotes && header[i ..].starts_with(" comdat($") {
+            let name = &header[i + " comdat($".len() ..];
+            return Some(if let Some(quoted) = name.strip_prefix('"') {
+                unescape(&quoted[.. quoted.find('"')?])
+            } else {
+                name[.. name.find(')')?].to_owned()
+            });
+        }
+    }
+    None
+}
+
+#[cfg(feature = "llvm-sys")]
+impl SelectionKind {
+    pub(crate) fn from_llvm(sk: LLVMComdatSelectionKind) -> Self {
+        use LLVMComdatSelectionKind::*;
+        match sk {
+            LLVMAnyComdatSelectionKind => SelectionKind::Any,
+            LLVMExactMatchComdatSelectionKind => SelectionKind::ExactMatch,
+            LLVMLargestComdatSelectionKind => SelectionKind::Largest,
+            LLVMNoDuplicatesComdatSelectionKind => SelectionKind::NoDuplicates,
+            LLVMSameSizeComdatSelectionKind => SelectionKind::SameSize,
         }
     }
 }
+
+#[cfg(feature = "llvm-sys")]
+impl DataLayout {
+    pub(crate) fn from_module_ref(module: LLVMModuleRef) -> Self {
+        Self::from_layout_str(unsafe { get_data_layout_str(module) })
+    }
+}
+
@@ -1,42 +1,219 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
 use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::ops::Deref;
+use std::sync::Arc;
 
 /// Many LLVM objects have a `Name`, which is either a string name, or just a
 /// sequential numbering (e.g. `%3`).
 #[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Debug, Hash)]
 pub enum Name {
     /// has a string name
-    // with `Box`, the enum `Name` has size 16 bytes, vs with a `String`
-    // directly, the enum `Name` has size 32 bytes. This has implications also
-    // for the size of other important structures, such as `Operand`.
-    // `Name::Name` should be the less common case, so the `Box` shouldn't hurt
-    // much, and we'll have much better memory consumption and maybe better
-    // cache performance.
-    Name(Box<String>),
+    // a `Symbol` is a single pointer, so the enum `Name` has size 16 bytes,
+    // vs 32 bytes with a `String` directly. This has implications also for
+    // the size of other important structures, such as `Operand`.
+    Name(Symbol),
     /// doesn't have a string name and was given this sequential number
     Number(usize),
 }
 
 impl Name {
+    #[cfg(feature = "llvm-sys")]
     pub(crate) fn name_or_num(s: String, ctr: &mut usize) -> Self {
         if s.is_empty() {
             let rval = Name::Number(*ctr);
             *ctr += 1;
             rval
         } else {
-            Name::Name(Box::new(s))
+            Name::from(s)
         }
     }
+
+    /// Like `name_or_num()`, but interning the string name in `symbols`. If
+    /// `keep_numeric_names` is `false`, a name made only of digits (e.g.
+    /// `%"5"`) is treated as no name at all.
+    #[cfg(feature = "llvm-sys")]
+    pub(crate) fn local_name_or_num(
+        s: String,
+        ctr: &mut usize,
+        keep_numeric_names: bool,
+        symbols: &mut SymbolTable,
+    ) -> Self {
+        if s.is_empty() || (!keep_numeric_names && s.bytes().all(|b| b.is_ascii_digit())) {
+            Self::name_or_num(String::new(), ctr)
+        } else {
+            Name::Name(symbols.intern(&s))
+        }
+    }
+}
+
+/// The string name of a `Name::Name`.
+///
+/// A `Symbol` is a shared pointer to its string, so cloning one is cheap.
+/// Its hash is computed once, when it's created, so hashing is O(1). Two
+/// `Symbol`s interned by the same `SymbolTable` are equal exactly when they
+/// are the same pointer, so comparing them is O(1) too; `Symbol`s from
+/// different tables (or created with `Symbol::from()`) are compared by their
+/// hashes and then by their strings.
+///
+/// `Symbol` derefs to `str`.
+#[derive(Clone)]
+pub struct Symbol(Arc<SymbolData>);
+
+struct SymbolData {
+    hash: u64,
+    string: Box<str>,
+}
+
+impl Symbol {
+    /// Get the string
+    pub fn as_str(&self) -> &str {
+        &self.0.string
+    }
+
+    fn hash_str(s: &str) -> u64 {
+        // `DefaultHasher::new()` always uses the same keys, so equal strings
+        // get equal hashes, even in different `SymbolTable`s
+        let mut hasher = DefaultHasher::new();
+        s.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    fn with_hash(s: &str, hash: u64) -> Self {
+        Self(Arc::new(SymbolData {
+            hash,
+            string: s.into(),
+        }))
+    }
+}
+
+impl Deref for Symbol {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl AsRef<str> for Symbol {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl PartialEq for Symbol {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+            || (self.0.hash == other.0.hash && self.0.string == other.0.string)
+    }
+}
+
+impl Eq for Symbol {}
+
+impl Hash for Symbol {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u64(self.0.hash)
+    }
+}
+
+impl PartialOrd for Symbol {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Symbol {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.as_str().cmp(other.as_str())
+    }
+}
+
+impl From<&str> for Symbol {
+    /// Create a `Symbol` which isn't interned in any `SymbolTable`
+    fn from(s: &str) -> Self {
+        Self::with_hash(s, Self::hash_str(s))
+    }
+}
+
+impl fmt::Debug for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Interns `Symbol`s, so that each distinct string is stored only once, and
+/// all the `Name`s with that string share it.
+#[derive(Clone, Default)]
+pub struct SymbolTable {
+    /// Map from the hash of a string to its `Symbol`
+    symbols: HashMap<u64, Symbol>,
+}
+
+impl SymbolTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get the `Symbol` for the given string, creating it if this is the
+    /// first time this table has seen the string
+    pub fn intern(&mut self, s: &str) -> Symbol {
+        let hash = Symbol::hash_str(s);
+        match self.symbols.get(&hash) {
+            Some(symbol) if symbol.as_str() == s => symbol.clone(),
+            // in the unlikely event of a hash collision, the second string
+            // just isn't interned; `Symbol` equality still works
+            Some(_) => Symbol::with_hash(s, hash),
+            None => {
+                let symbol = Symbol::with_hash(s, hash);
+                self.symbols.insert(hash, symbol.clone());
+                symbol
+            },
+        }
+    }
+
+    /// Get a `Name::Name` with the interned `Symbol` for the given string
+    pub fn name(&mut self, s: &str) -> Name {
+        Name::Name(self.intern(s))
+    }
+
+    /// Number of distinct strings interned so far
+    pub fn len(&self) -> usize {
+        self.symbols.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.symbols.is_empty()
+    }
+}
+
+impl fmt::Debug for SymbolTable {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_set().entries(self.symbols.values()).finish()
+    }
 }
 
 impl From<String> for Name {
     fn from(s: String) -> Self {
-        Name::Name(Box::new(s))
+        Name::Name(Symbol::from(s.as_str()))
     }
 }
 
 impl From<&str> for Name {
     fn from(s: &str) -> Self {
-        Name::Name(Box::new(s.into()))
+        Name::Name(Symbol::from(s))
+    }
+}
+
+impl From<Symbol> for Name {
+    fn from(symbol: Symbol) -> Self {
+        Name::Name(symbol)
     }
 }
 
@@ -1,5 +1,5 @@
 use crate::types::{TypeRef, Typed, Types};
-use crate::{ConstantRef, Name};
+use crate::{Constant, ConstantRef, Name};
 use std::fmt::{self, Display};
 
 #[derive(PartialEq, Clone, Debug)]
@@ -59,12 +59,16 @@ impl Display for Operand {
 // from_llvm //
 // ********* //
 
-use crate::constant::Constant;
+#[cfg(feature = "llvm-sys")]
 use crate::function::FunctionContext;
+#[cfg(feature = "llvm-sys")]
 use crate::llvm_sys::*;
+#[cfg(feature = "llvm-sys")]
 use crate::module::ModuleContext;
+#[cfg(feature = "llvm-sys")]
 use llvm_sys::LLVMValueKind;
 
+#[cfg(feature = "llvm-sys")]
 impl Operand {
     pub(crate) fn from_llvm_ref(
         operand: LLVMValueRef,
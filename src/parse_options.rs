@@ -0,0 +1,97 @@
+//! Options for converting only part of an LLVM module.
+//!
+//! By default, `Module::from_bc_path()` and friends convert everything: every
+//! function body, every attribute, and every debug location. A tool which only
+//! needs, say, the signatures of the functions and the global variables can
+//! pass `ParseOptions` to [`Module::from_bc_path_with_options()`] or
+//! [`Module::from_ir_path_with_options()`] to skip the rest, which is usually
+//! most of the work.
+//!
+//! [`Module::from_bc_path_with_options()`]: crate::Module::from_bc_path_with_options
+//! [`Module::from_ir_path_with_options()`]: crate::Module::from_ir_path_with_options
+
+use std::fmt;
+use std::sync::Arc;
+
+/// A predicate on function names, for
+/// [`ParseOptions::function_filter`](ParseOptions::function_filter)
+pub type FunctionFilter = Arc<dyn Fn(&str) -> bool + Send + Sync>;
+
+/// Which parts of an LLVM module to convert.
+///
+/// `ParseOptions::default()` converts everything, just as
+/// `Module::from_bc_path()` does. Skipped parts are left empty (or `None`) in
+/// the resulting `Module`.
+#[derive(Clone)]
+pub struct ParseOptions {
+    /// If `true`, all `debugloc`s are `None`, and functions and global
+    /// variables have no `!dbg` metadata attachments.
+    pub skip_debug_info: bool,
+    /// If `true`, no function bodies are converted: each `Function` in
+    /// `Module::functions` has its signature, linkage, etc as usual, but no
+    /// `basic_blocks`.
+    pub declarations_only: bool,
+    /// If `true`, the attributes of functions, parameters, return values,
+    /// calls, and global variables are all left empty, and every
+    /// `function_attribute_group` is `None`.
+    pub skip_attributes: bool,
+    /// If set, only the bodies of the functions whose names satisfy this
+    /// predicate are converted; the other functions are converted as with
+    /// `declarations_only`. Has no effect if `declarations_only` is `true`.
+    pub function_filter: Option<FunctionFilter>,
+    /// LLVM allows a local value or basic block to be given a name made only
+    /// of digits, such as `%"5"`, which is distinct from the unnamed value
+    /// `%5`. If `true` (the default), such names are kept as `Name::Name`s.
+    /// If `false`, they are dropped, and the values numbered in sequence with
+    /// the unnamed ones, as `Name::Number`s.
+    pub keep_numeric_names: bool,
+}
+
+impl ParseOptions {
+    /// Convert only the bodies of the functions whose names satisfy `filter`;
+    /// see [`function_filter`](ParseOptions::function_filter).
+    pub fn with_function_filter(
+        mut self,
+        filter: impl Fn(&str) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.function_filter = Some(Arc::new(filter));
+        self
+    }
+
+    /// Whether the body of the function with the given name should be
+    /// converted
+    pub(crate) fn converts_body_of(&self, func_name: &str) -> bool {
+        !self.declarations_only
+            && match &self.function_filter {
+                Some(filter) => filter(func_name),
+                None => true,
+            }
+    }
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        Self {
+            skip_debug_info: false,
+            declarations_only: false,
+            skip_attributes: false,
+            function_filter: None,
+            keep_numeric_names: true,
+        }
+    }
+}
+
+impl fmt::Debug for ParseOptions {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ParseOptions")
+            .field("skip_debug_info", &self.skip_debug_info)
+            .field("declarations_only", &self.declarations_only)
+            .field("skip_attributes", &self.skip_attributes)
+            .field(
+                "function_filter",
+                &self.function_filter.as_ref().map(|_| "<function>"),
+            )
+            .field("keep_numeric_names", &self.keep_numeric_names)
+            .finish()
+    }
+}
@@ -1,6 +1,6 @@
 use std::fmt::{self, Display};
 
-#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Hash)]
 pub enum IntPredicate {
     EQ,
     NE,
@@ -31,7 +31,7 @@ impl Display for IntPredicate {
     }
 }
 
-#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Hash)]
 pub enum FPPredicate {
     False,
     OEQ,
@@ -78,10 +78,14 @@ impl Display for FPPredicate {
 // from_llvm //
 // ********* //
 
+#[cfg(feature = "llvm-sys")]
 use crate::llvm_sys::*;
+#[cfg(feature = "llvm-sys")]
 use llvm_sys::LLVMIntPredicate;
+#[cfg(feature = "llvm-sys")]
 use llvm_sys::LLVMRealPredicate;
 
+#[cfg(feature = "llvm-sys")]
 impl IntPredicate {
     pub(crate) fn from_llvm(pred: LLVMIntPredicate) -> Self {
         match pred {
@@ -99,6 +103,7 @@ impl IntPredicate {
     }
 }
 
+#[cfg(feature = "llvm-sys")]
 impl FPPredicate {
     pub(crate) fn from_llvm(pred: LLVMRealPredicate) -> Self {
         match pred {
@@ -1,9 +1,8 @@
-#[cfg(feature = "llvm-9-or-greater")]
 use crate::debugloc::{DebugLoc, HasDebugLoc};
-use crate::function::{CallingConvention, FunctionAttribute, ParameterAttribute};
-use crate::instruction::{HasResult, InlineAssembly};
+use crate::function::{CallingConvention, GroupID, ParameterAttribute};
+use crate::instruction::{write_operand_bundles, HasResult, InlineAssembly, OperandBundle};
 use crate::types::{Typed, Types};
-use crate::{Constant, ConstantRef, Name, Operand, Type, TypeRef};
+use crate::{ConstantRef, Name, Operand, Type, TypeRef};
 use either::Either;
 use std::convert::TryFrom;
 use std::fmt::{self, Display};
@@ -23,7 +22,6 @@ pub enum Terminator {
     CleanupRet(CleanupRet),
     CatchRet(CatchRet),
     CatchSwitch(CatchSwitch),
-    #[cfg(feature = "llvm-9-or-greater")]
     CallBr(CallBr),
 }
 
@@ -47,13 +45,11 @@ impl Typed for Terminator {
             Terminator::CleanupRet(t) => types.type_of(t),
             Terminator::CatchRet(t) => types.type_of(t),
             Terminator::CatchSwitch(t) => types.type_of(t),
-            #[cfg(feature = "llvm-9-or-greater")]
             Terminator::CallBr(t) => types.type_of(t),
         }
     }
 }
 
-#[cfg(feature = "llvm-9-or-greater")]
 impl HasDebugLoc for Terminator {
     fn get_debug_loc(&self) -> &Option<DebugLoc> {
         match self {
@@ -68,7 +64,6 @@ impl HasDebugLoc for Terminator {
             Terminator::CleanupRet(t) => t.get_debug_loc(),
             Terminator::CatchRet(t) => t.get_debug_loc(),
             Terminator::CatchSwitch(t) => t.get_debug_loc(),
-            #[cfg(feature = "llvm-9-or-greater")]
             Terminator::CallBr(t) => t.get_debug_loc(),
         }
     }
@@ -88,7 +83,6 @@ impl Display for Terminator {
             Terminator::CleanupRet(t) => write!(f, "{}", t),
             Terminator::CatchRet(t) => write!(f, "{}", t),
             Terminator::CatchSwitch(t) => write!(f, "{}", t),
-            #[cfg(feature = "llvm-9-or-greater")]
             Terminator::CallBr(t) => write!(f, "{}", t),
         }
     }
@@ -109,7 +103,6 @@ impl Terminator {
             Terminator::CleanupRet(t) => &t.metadata,
             Terminator::CatchRet(t) => &t.metadata,
             Terminator::CatchSwitch(t) => &t.metadata,
-            #[cfg(feature="llvm-9-or-greater")]
             Terminator::CallBr(t) => &t.metadata,
         }
     }
@@ -132,7 +125,6 @@ impl Terminator {
             Terminator::CleanupRet(_) => None,
             Terminator::CatchRet(_) => None,
             Terminator::CatchSwitch(t) => Some(&t.result),
-            #[cfg(feature = "llvm-9-or-greater")]
             Terminator::CallBr(t) => Some(&t.result),
         }
     }
@@ -156,7 +148,6 @@ macro_rules! impl_term {
             }
         }
 
-        #[cfg(feature = "llvm-9-or-greater")]
         impl HasDebugLoc for $term {
             fn get_debug_loc(&self) -> &Option<DebugLoc> {
                 &self.debugloc
@@ -198,7 +189,6 @@ macro_rules! void_typed {
 pub struct Ret {
     /// The value being returned, or `None` if returning void.
     pub return_operand: Option<Operand>,
-    #[cfg(feature = "llvm-9-or-greater")]
     pub debugloc: Option<DebugLoc>,
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
@@ -216,7 +206,6 @@ impl Display for Ret {
                 Some(op) => format!("{}", op),
             },
         )?;
-        #[cfg(feature = "llvm-9-or-greater")]
         if self.debugloc.is_some() {
             write!(f, " (with debugloc)")?;
         }
@@ -231,7 +220,6 @@ impl Display for Ret {
 pub struct Br {
     /// The [`Name`](../enum.Name.html) of the [`BasicBlock`](../struct.BasicBlock.html) destination.
     pub dest: Name,
-    #[cfg(feature = "llvm-9-or-greater")]
     pub debugloc: Option<DebugLoc>,
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
@@ -242,7 +230,6 @@ void_typed!(Br);
 impl Display for Br {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "br label {}", &self.dest)?;
-        #[cfg(feature = "llvm-9-or-greater")]
         if self.debugloc.is_some() {
             write!(f, " (with debugloc)")?;
         }
@@ -261,7 +248,6 @@ pub struct CondBr {
     pub true_dest: Name,
     /// The [`Name`](../enum.Name.html) of the [`BasicBlock`](../struct.BasicBlock.html) destination if the `condition` is false.
     pub false_dest: Name,
-    #[cfg(feature = "llvm-9-or-greater")]
     pub debugloc: Option<DebugLoc>,
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
@@ -276,7 +262,6 @@ impl Display for CondBr {
             "br {}, label {}, label {}",
             &self.condition, &self.true_dest, &self.false_dest,
         )?;
-        #[cfg(feature = "llvm-9-or-greater")]
         if self.debugloc.is_some() {
             write!(f, " (with debugloc)")?;
         }
@@ -290,7 +275,6 @@ pub struct Switch {
     pub operand: Operand,
     pub dests: Vec<(ConstantRef, Name)>,
     pub default_dest: Name,
-    #[cfg(feature = "llvm-9-or-greater")]
     pub debugloc: Option<DebugLoc>,
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
@@ -309,7 +293,6 @@ impl Display for Switch {
             write!(f, "{}, label {}; ", val, label)?;
         }
         write!(f, "]")?;
-        #[cfg(feature = "llvm-9-or-greater")]
         if self.debugloc.is_some() {
             write!(f, " (with debugloc)")?;
         }
@@ -327,7 +310,6 @@ pub struct IndirectBr {
     /// [`BasicBlock`](../struct.BasicBlock.html)s in the current function;
     /// `IndirectBr` cannot be used to jump between functions.
     pub possible_dests: Vec<Name>,
-    #[cfg(feature = "llvm-9-or-greater")]
     pub debugloc: Option<DebugLoc>,
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
@@ -350,7 +332,6 @@ impl Display for IndirectBr {
             write!(f, ", label {}", dest)?;
         }
         write!(f, " ]")?;
-        #[cfg(feature = "llvm-9-or-greater")]
         if self.debugloc.is_some() {
             write!(f, " (with debugloc)")?;
         }
@@ -362,14 +343,18 @@ impl Display for IndirectBr {
 #[derive(PartialEq, Clone, Debug)]
 pub struct Invoke {
     pub function: Either<InlineAssembly, Operand>,
+    /// Type of the function being called. With opaque pointers, this can't be
+    /// recovered from the type of `function`.
+    pub function_ty: TypeRef,
     pub arguments: Vec<(Operand, Vec<ParameterAttribute>)>,
     pub return_attributes: Vec<ParameterAttribute>,
     pub result: Name, // The name of the variable that will get the result of the call (if the callee returns with 'ret')
     pub return_label: Name, // Should be the name of a basic block. If the callee returns normally (i.e., with 'ret'), control flow resumes here.
     pub exception_label: Name, // Should be the name of a basic block. If the callee returns with 'resume' or another exception-handling mechanism, control flow resumes here.
-    pub function_attributes: Vec<FunctionAttribute>, // llvm-hs has the equivalent of Vec<Either<GroupID, FunctionAttribute>>, but I'm not sure how the GroupID option comes up
+    /// Index into `Module::function_attribute_groups`, or `None` if there are no function attributes on this call
+    pub function_attribute_group: Option<GroupID>,
+    pub operand_bundles: Vec<OperandBundle>,
     pub calling_convention: CallingConvention,
-    #[cfg(feature = "llvm-9-or-greater")]
     pub debugloc: Option<DebugLoc>,
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
@@ -378,13 +363,13 @@ impl_term!(Invoke, Invoke);
 impl_hasresult!(Invoke);
 
 impl Typed for Invoke {
-    fn get_type(&self, types: &Types) -> TypeRef {
-        match types.type_of(&self.function).as_ref() {
-            Type::PointerType { pointee_type, .. } => match pointee_type.as_ref() {
-                Type::FuncType { result_type, .. } => result_type.clone(),
-                ty => panic!("Expected Invoke's function argument to be of type pointer-to-function, got pointer-to-{:?}", ty),
-            },
-            ty => panic!("Expected Invoke's function argument to be of type pointer-to-function, got {:?}", ty),
+    fn get_type(&self, _types: &Types) -> TypeRef {
+        match self.function_ty.as_ref() {
+            Type::FuncType { result_type, .. } => result_type.clone(),
+            ty => panic!(
+                "Expected Invoke's function_ty to be a FuncType, got {:?}",
+                ty
+            ),
         }
     }
 }
@@ -409,12 +394,13 @@ impl Display for Invoke {
                 write!(f, "{}, ", arg)?;
             }
         }
+        write!(f, ")")?;
+        write_operand_bundles(f, &self.operand_bundles)?;
         write!(
             f,
-            ") to label {} unwind label {}",
+            " to label {} unwind label {}",
             &self.return_label, &self.exception_label,
         )?;
-        #[cfg(feature = "llvm-9-or-greater")]
         if self.debugloc.is_some() {
             write!(f, " (with debugloc)")?;
         }
@@ -426,7 +412,6 @@ impl Display for Invoke {
 #[derive(PartialEq, Clone, Debug)]
 pub struct Resume {
     pub operand: Operand,
-    #[cfg(feature = "llvm-9-or-greater")]
     pub debugloc: Option<DebugLoc>,
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
@@ -437,7 +422,6 @@ void_typed!(Resume);
 impl Display for Resume {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "resume {}", &self.operand)?;
-        #[cfg(feature = "llvm-9-or-greater")]
         if self.debugloc.is_some() {
             write!(f, " (with debugloc)")?;
         }
@@ -448,7 +432,6 @@ impl Display for Resume {
 /// See [LLVM 14 docs on the 'unreachable' instruction](https://releases.llvm.org/14.0.0/docs/LangRef.html#unreachable-instruction)
 #[derive(PartialEq, Clone, Debug)]
 pub struct Unreachable {
-    #[cfg(feature = "llvm-9-or-greater")]
     pub debugloc: Option<DebugLoc>,
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
@@ -459,7 +442,6 @@ void_typed!(Unreachable);
 impl Display for Unreachable {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "unreachable")?;
-        #[cfg(feature = "llvm-9-or-greater")]
         if self.debugloc.is_some() {
             write!(f, " (with debugloc)")?;
         }
@@ -473,7 +455,6 @@ pub struct CleanupRet {
     pub cleanup_pad: Operand,
     /// `None` here indicates 'unwind to caller'
     pub unwind_dest: Option<Name>,
-    #[cfg(feature = "llvm-9-or-greater")]
     pub debugloc: Option<DebugLoc>,
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
@@ -492,7 +473,6 @@ impl Display for CleanupRet {
                 Some(dest) => format!("label {}", dest),
             },
         )?;
-        #[cfg(feature = "llvm-9-or-greater")]
         if self.debugloc.is_some() {
             write!(f, " (with debugloc)")?;
         }
@@ -505,7 +485,6 @@ impl Display for CleanupRet {
 pub struct CatchRet {
     pub catch_pad: Operand,
     pub successor: Name,
-    #[cfg(feature = "llvm-9-or-greater")]
     pub debugloc: Option<DebugLoc>,
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
@@ -520,7 +499,6 @@ impl Display for CatchRet {
             "catchret from {} to label {}",
             &self.catch_pad, &self.successor,
         )?;
-        #[cfg(feature = "llvm-9-or-greater")]
         if self.debugloc.is_some() {
             write!(f, " (with debugloc)")?;
         }
@@ -537,7 +515,6 @@ pub struct CatchSwitch {
     /// `None` here indicates 'unwind to caller'
     pub default_unwind_dest: Option<Name>,
     pub result: Name,
-    #[cfg(feature = "llvm-9-or-greater")]
     pub debugloc: Option<DebugLoc>,
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
@@ -575,7 +552,6 @@ impl Display for CatchSwitch {
                 Some(dest) => format!("label {}", dest),
             },
         )?;
-        #[cfg(feature = "llvm-9-or-greater")]
         if self.debugloc.is_some() {
             write!(f, " (with debugloc)")?;
         }
@@ -584,42 +560,40 @@ impl Display for CatchSwitch {
 }
 
 /// See [LLVM 14 docs on the 'callbr' instruction](https://releases.llvm.org/14.0.0/docs/LangRef.html#callbr-instruction)
-#[cfg(feature = "llvm-9-or-greater")]
 #[derive(PartialEq, Clone, Debug)]
 pub struct CallBr {
     pub function: Either<InlineAssembly, Operand>,
+    /// Type of the function being called. With opaque pointers, this can't be
+    /// recovered from the type of `function`.
+    pub function_ty: TypeRef,
     pub arguments: Vec<(Operand, Vec<ParameterAttribute>)>,
     pub return_attributes: Vec<ParameterAttribute>,
     pub result: Name, // The name of the variable that will get the result of the call (if the callee returns with 'ret')
     pub return_label: Name, // Should be the name of a basic block. If the callee returns normally (i.e., with 'ret'), control flow resumes here.
     /// `other_labels` should be `Vec<Name>`, but it appears there is no way to get this information with the LLVM C API (as opposed to the C++ API)
     pub other_labels: (), //Vec<Name>, // Should be names of basic blocks. The callee may use an inline-asm 'goto' to resume control flow at one of these places.
-    pub function_attributes: Vec<FunctionAttribute>,
+    /// Index into `Module::function_attribute_groups`, or `None` if there are no function attributes on this call
+    pub function_attribute_group: Option<GroupID>,
     pub calling_convention: CallingConvention,
-    #[cfg(feature = "llvm-9-or-greater")]
     pub debugloc: Option<DebugLoc>,
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
 
-#[cfg(feature = "llvm-9-or-greater")]
 impl_term!(CallBr, CallBr);
-#[cfg(feature = "llvm-9-or-greater")]
 impl_hasresult!(CallBr);
 
-#[cfg(feature = "llvm-9-or-greater")]
 impl Typed for CallBr {
-    fn get_type(&self, types: &Types) -> TypeRef {
-        match types.type_of(&self.function).as_ref() {
+    fn get_type(&self, _types: &Types) -> TypeRef {
+        match self.function_ty.as_ref() {
             Type::FuncType { result_type, .. } => result_type.clone(),
             ty => panic!(
-                "Expected the function argument of a CallBr to have type FuncType; got {:?}",
+                "Expected CallBr's function_ty to be a FuncType, got {:?}",
                 ty
             ),
         }
     }
 }
 
-#[cfg(feature = "llvm-9-or-greater")]
 impl Display for CallBr {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         // Like with `Call` and `Invoke, we choose not to include all the
@@ -642,7 +616,6 @@ impl Display for CallBr {
             }
         }
         write!(f, ") to label {}", &self.return_label)?;
-        #[cfg(feature = "llvm-9-or-greater")]
         if self.debugloc.is_some() {
             write!(f, " (with debugloc)")?;
         }
@@ -654,12 +627,20 @@ impl Display for CallBr {
 // from_llvm //
 // ********* //
 
+#[cfg(feature = "llvm-sys")]
+use crate::constant::Constant;
+#[cfg(feature = "llvm-sys")]
 use crate::from_llvm::*;
+#[cfg(feature = "llvm-sys")]
 use crate::function::FunctionContext;
+#[cfg(feature = "llvm-sys")]
 use crate::llvm_sys::*;
+#[cfg(feature = "llvm-sys")]
 use crate::module::ModuleContext;
+#[cfg(feature = "llvm-sys")]
 use llvm_sys::LLVMOpcode;
 
+#[cfg(feature = "llvm-sys")]
 impl Terminator {
     #[rustfmt::skip] // so we can keep all of the match arms consistent
     pub(crate) fn from_llvm_ref(
@@ -692,7 +673,7 @@ impl Terminator {
                 Terminator::Resume(Resume::from_llvm_ref(term, ctx, func_ctx))
             },
             LLVMOpcode::LLVMUnreachable => {
-                Terminator::Unreachable(Unreachable::from_llvm_ref(term))
+                Terminator::Unreachable(Unreachable::from_llvm_ref(term, func_ctx))
             },
             LLVMOpcode::LLVMCleanupRet => {
                 Terminator::CleanupRet(CleanupRet::from_llvm_ref(term, ctx, func_ctx))
@@ -715,6 +696,7 @@ impl Terminator {
     }
 }
 
+#[cfg(feature = "llvm-sys")]
 impl Ret {
     pub(crate) fn from_llvm_ref(
         term: LLVMValueRef,
@@ -731,13 +713,13 @@ impl Ret {
                 )),
                 n => panic!("Ret instruction with {} operands", n),
             },
-            #[cfg(feature = "llvm-9-or-greater")]
-            debugloc: DebugLoc::from_llvm_with_col(term),
+            debugloc: func_ctx.debugloc(term),
             // metadata: InstructionMetadata::from_llvm_inst(term),
         }
     }
 }
 
+#[cfg(feature = "llvm-sys")]
 impl Br {
     pub(crate) fn from_llvm_ref(term: LLVMValueRef, func_ctx: &mut FunctionContext) -> Self {
         assert_eq!(unsafe { LLVMGetNumOperands(term) }, 1);
@@ -747,13 +729,13 @@ impl Br {
                 .get(unsafe { &op_to_bb(LLVMGetOperand(term, 0)) })
                 .expect("Failed to find destination bb in map")
                 .clone(),
-            #[cfg(feature = "llvm-9-or-greater")]
-            debugloc: DebugLoc::from_llvm_with_col(term),
+            debugloc: func_ctx.debugloc(term),
             // metadata: InstructionMetadata::from_llvm_inst(term),
         }
     }
 }
 
+#[cfg(feature = "llvm-sys")]
 impl CondBr {
     pub(crate) fn from_llvm_ref(
         term: LLVMValueRef,
@@ -773,13 +755,13 @@ impl CondBr {
                 .get(unsafe { &op_to_bb(LLVMGetOperand(term, 1)) })
                 .expect("Failed to find false-destination in bb map")
                 .clone(),
-            #[cfg(feature = "llvm-9-or-greater")]
-            debugloc: DebugLoc::from_llvm_with_col(term),
+            debugloc: func_ctx.debugloc(term),
             // metadata: InstructionMetadata::from_llvm_inst(term),
         }
     }
 }
 
+#[cfg(feature = "llvm-sys")]
 impl Switch {
     pub(crate) fn from_llvm_ref(
         term: LLVMValueRef,
@@ -809,13 +791,13 @@ impl Switch {
                 .get(unsafe { &LLVMGetSwitchDefaultDest(term) })
                 .expect("Failed to find switch default destination in map")
                 .clone(),
-            #[cfg(feature = "llvm-9-or-greater")]
-            debugloc: DebugLoc::from_llvm_with_col(term),
+            debugloc: func_ctx.debugloc(term),
             // metadata: InstructionMetadata::from_llvm_inst(term),
         }
     }
 }
 
+#[cfg(feature = "llvm-sys")]
 impl IndirectBr {
     pub(crate) fn from_llvm_ref(
         term: LLVMValueRef,
@@ -836,13 +818,13 @@ impl IndirectBr {
                     })
                     .collect()
             },
-            #[cfg(feature = "llvm-9-or-greater")]
-            debugloc: DebugLoc::from_llvm_with_col(term),
+            debugloc: func_ctx.debugloc(term),
             // metadata: InstructionMetadata::from_llvm_inst(term),
         }
     }
 }
 
+#[cfg(feature = "llvm-sys")]
 impl Invoke {
     pub(crate) fn from_llvm_ref(
         term: LLVMValueRef,
@@ -853,9 +835,10 @@ impl Invoke {
         let callinfo = CallInfo::from_llvm_ref(term, ctx, func_ctx);
         Self {
             function: callinfo.function,
+            function_ty: callinfo.function_ty,
             arguments: callinfo.arguments,
             return_attributes: callinfo.return_attributes,
-            result: Name::name_or_num(unsafe { get_value_name(term) }, &mut func_ctx.ctr),
+            result: func_ctx.local_name(unsafe { get_value_name(term) }),
             return_label: func_ctx
                 .bb_names
                 .get(unsafe { &LLVMGetNormalDest(term) })
@@ -866,15 +849,16 @@ impl Invoke {
                 .get(unsafe { &LLVMGetUnwindDest(term) })
                 .expect("Failed to find invoke exception destination in map")
                 .clone(),
-            function_attributes: callinfo.function_attributes,
+            function_attribute_group: callinfo.function_attribute_group,
+            operand_bundles: callinfo.operand_bundles,
             calling_convention: callinfo.calling_convention,
-            #[cfg(feature = "llvm-9-or-greater")]
-            debugloc: DebugLoc::from_llvm_with_col(term),
+            debugloc: func_ctx.debugloc(term),
             // metadata: InstructionMetadata::from_llvm_inst(term),
         }
     }
 }
 
+#[cfg(feature = "llvm-sys")]
 impl Resume {
     pub(crate) fn from_llvm_ref(
         term: LLVMValueRef,
@@ -884,24 +868,24 @@ impl Resume {
         assert_eq!(unsafe { LLVMGetNumOperands(term) }, 1);
         Self {
             operand: Operand::from_llvm_ref(unsafe { LLVMGetOperand(term, 0) }, ctx, func_ctx),
-            #[cfg(feature = "llvm-9-or-greater")]
-            debugloc: DebugLoc::from_llvm_with_col(term),
+            debugloc: func_ctx.debugloc(term),
             // metadata: InstructionMetadata::from_llvm_inst(term),
         }
     }
 }
 
+#[cfg(feature = "llvm-sys")]
 impl Unreachable {
-    pub(crate) fn from_llvm_ref(term: LLVMValueRef) -> Self {
+    pub(crate) fn from_llvm_ref(term: LLVMValueRef, func_ctx: &mut FunctionContext) -> Self {
         assert_eq!(unsafe { LLVMGetNumOperands(term) }, 0);
         Self {
-            #[cfg(feature = "llvm-9-or-greater")]
-            debugloc: DebugLoc::from_llvm_with_col(term),
+            debugloc: func_ctx.debugloc(term),
             // metadata: InstructionMetadata::from_llvm_inst(term),
         }
     }
 }
 
+#[cfg(feature = "llvm-sys")]
 impl CleanupRet {
     pub(crate) fn from_llvm_ref(
         term: LLVMValueRef,
@@ -931,13 +915,13 @@ impl CleanupRet {
                     )
                 }
             },
-            #[cfg(feature = "llvm-9-or-greater")]
-            debugloc: DebugLoc::from_llvm_with_col(term),
+            debugloc: func_ctx.debugloc(term),
             // metadata: InstructionMetadata::from_llvm_inst(term),
         }
     }
 }
 
+#[cfg(feature = "llvm-sys")]
 impl CatchRet {
     pub(crate) fn from_llvm_ref(
         term: LLVMValueRef,
@@ -951,13 +935,13 @@ impl CatchRet {
                 .get(unsafe { &LLVMGetSuccessor(term, 0) })
                 .expect("Failed to find CatchRet successor in map")
                 .clone(),
-            #[cfg(feature = "llvm-9-or-greater")]
-            debugloc: DebugLoc::from_llvm_with_col(term),
+            debugloc: func_ctx.debugloc(term),
             // metadata: InstructionMetadata::from_llvm_inst(term),
         }
     }
 }
 
+#[cfg(feature = "llvm-sys")]
 impl CatchSwitch {
     pub(crate) fn from_llvm_ref(
         term: LLVMValueRef,
@@ -996,15 +980,14 @@ impl CatchSwitch {
                     )
                 }
             },
-            result: Name::name_or_num(unsafe { get_value_name(term) }, &mut func_ctx.ctr),
-            #[cfg(feature = "llvm-9-or-greater")]
-            debugloc: DebugLoc::from_llvm_with_col(term),
+            result: func_ctx.local_name(unsafe { get_value_name(term) }),
+            debugloc: func_ctx.debugloc(term),
             // metadata: InstructionMetadata::from_llvm_inst(term),
         }
     }
 }
 
-#[cfg(feature = "llvm-9-or-greater")]
+#[cfg(all(feature = "llvm-sys", feature = "llvm-9-or-greater"))]
 impl CallBr {
     pub(crate) fn from_llvm_ref(
         term: LLVMValueRef,
@@ -1015,19 +998,19 @@ impl CallBr {
         let callinfo = CallInfo::from_llvm_ref(term, ctx, func_ctx);
         Self {
             function: callinfo.function,
+            function_ty: callinfo.function_ty,
             arguments: callinfo.arguments,
             return_attributes: callinfo.return_attributes,
-            result: Name::name_or_num(unsafe { get_value_name(term) }, &mut func_ctx.ctr),
+            result: func_ctx.local_name(unsafe { get_value_name(term) }),
             return_label: func_ctx
                 .bb_names
                 .get(unsafe { &LLVMGetNormalDest(term) })
                 .expect("Failed to find invoke return destination in map")
                 .clone(),
             other_labels: (),
-            function_attributes: callinfo.function_attributes,
+            function_attribute_group: callinfo.function_attribute_group,
             calling_convention: callinfo.calling_convention,
-            #[cfg(feature = "llvm-9-or-greater")]
-            debugloc: DebugLoc::from_llvm_with_col(term),
+            debugloc: func_ctx.debugloc(term),
             // metadata: InstructionMetadata::from_llvm_inst(term),
         }
     }
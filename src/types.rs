@@ -1,6 +1,7 @@
 use crate::module::AddrSpace;
 use either::Either;
 use std::borrow::Borrow;
+use std::collections::hash_map::Entry;
 use std::collections::HashMap;
 use std::fmt::{self, Display};
 use std::hash::Hash;
@@ -15,11 +16,18 @@ pub enum Type {
     VoidType,
     /// See [LLVM 14 docs on Integer Type](https://releases.llvm.org/14.0.0/docs/LangRef.html#integer-type)
     IntegerType { bits: u32 },
+    /// A typed pointer, e.g. `i32*`. LLVM 17 no longer has typed pointers, so
+    /// with LLVM 17 all pointers are `OpaquePointerType`s.
     /// See [LLVM 14 docs on Pointer Type](https://releases.llvm.org/14.0.0/docs/LangRef.html#pointer-type)
     PointerType {
         pointee_type: TypeRef,
         addr_space: AddrSpace,
     },
+    /// An opaque pointer (`ptr`), which doesn't carry a pointee type. LLVM 14
+    /// uses these only when it is in opaque-pointers mode (e.g., with
+    /// `-opaque-pointers`); LLVM 15+ uses them by default.
+    /// See [LLVM 14 docs on Pointer Type](https://releases.llvm.org/14.0.0/docs/LangRef.html#pointer-type)
+    OpaquePointerType { addr_space: AddrSpace },
     /// See [LLVM 14 docs on Floating-Point Types](https://releases.llvm.org/14.0.0/docs/LangRef.html#floating-point-types)
     FPType(FPType),
     /// See [LLVM 14 docs on Function Type](https://releases.llvm.org/14.0.0/docs/LangRef.html#function-type)
@@ -34,7 +42,8 @@ pub enum Type {
     VectorType {
         element_type: TypeRef,
         num_elements: usize,
-        #[cfg(feature = "llvm-11-or-greater")]
+        /// Whether this is a scalable vector (`<vscale x n x ty>`). Always
+        /// `false` before LLVM 11.
         scalable: bool,
     },
     /// Struct and Array types (but not vector types) are "aggregate types" and cannot be produced by
@@ -61,7 +70,6 @@ pub enum Type {
     X86_MMXType,
     // As of this writing, although X86_AMX type definitely exists in LLVM 12+,
     // it doesn't appear to be documented in the LangRef
-    #[cfg(feature = "llvm-12-or-greater")]
     X86_AMXType,
     /// See [LLVM 14 docs on Metadata Type](https://releases.llvm.org/14.0.0/docs/LangRef.html#metadata-type)
     MetadataType,
@@ -70,6 +78,12 @@ pub enum Type {
     LabelType,
     /// See [LLVM 14 docs on Token Type](https://releases.llvm.org/14.0.0/docs/LangRef.html#token-type)
     TokenType,
+    /// See [LLVM 16 docs on Target Extension Type](https://releases.llvm.org/16.0.0/docs/LangRef.html#target-extension-type)
+    TargetExtType {
+        name: String,
+        type_params: Vec<TypeRef>,
+        int_params: Vec<u32>,
+    },
 }
 
 impl Display for Type {
@@ -78,6 +92,13 @@ impl Display for Type {
             Type::VoidType => write!(f, "void"),
             Type::IntegerType { bits } => write!(f, "i{}", bits),
             Type::PointerType { pointee_type, .. } => write!(f, "{}*", pointee_type),
+            Type::OpaquePointerType { addr_space } => {
+                if *addr_space == 0 {
+                    write!(f, "ptr")
+                } else {
+                    write!(f, "ptr addrspace({})", addr_space)
+                }
+            },
             Type::FPType(fpt) => write!(f, "{}", fpt),
             Type::FuncType {
                 result_type,
@@ -101,17 +122,13 @@ impl Display for Type {
             Type::VectorType {
                 element_type,
                 num_elements,
-                #[cfg(feature = "llvm-11-or-greater")]
                 scalable,
             } => {
-                #[cfg(feature = "llvm-11-or-greater")]
                 if *scalable {
                     write!(f, "<vscale x {} x {}>", num_elements, element_type)
                 } else {
                     write!(f, "<{} x {}>", num_elements, element_type)
                 }
-                #[cfg(feature = "llvm-10-or-lower")]
-                write!(f, "<{} x {}>", num_elements, element_type)
             },
             Type::ArrayType {
                 element_type,
@@ -140,11 +157,24 @@ impl Display for Type {
             },
             Type::NamedStructType { name } => write!(f, "%{}", name),
             Type::X86_MMXType => write!(f, "x86_mmx"),
-            #[cfg(feature = "llvm-12-or-greater")]
             Type::X86_AMXType => write!(f, "x86_amx"),
             Type::MetadataType => write!(f, "metadata"),
             Type::LabelType => write!(f, "label"),
             Type::TokenType => write!(f, "token"),
+            Type::TargetExtType {
+                name,
+                type_params,
+                int_params,
+            } => {
+                write!(f, "target(\"{}\"", name)?;
+                for ty in type_params {
+                    write!(f, ", {}", ty)?;
+                }
+                for param in int_params {
+                    write!(f, ", {}", param)?;
+                }
+                write!(f, ")")
+            },
         }
     }
 }
@@ -154,7 +184,6 @@ impl Display for Type {
 #[allow(non_camel_case_types)]
 pub enum FPType {
     Half,
-    #[cfg(feature = "llvm-11-or-greater")]
     BFloat,
     Single,
     Double,
@@ -173,7 +202,6 @@ impl Display for FPType {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             FPType::Half => write!(f, "half"),
-            #[cfg(feature = "llvm-11-or-greater")]
             FPType::BFloat => write!(f, "bfloat"),
             FPType::Single => write!(f, "float"),
             FPType::Double => write!(f, "double"),
@@ -269,6 +297,10 @@ pub(crate) struct TypesBuilder {
     int_types: TypeCache<u32>,
     /// Map of (pointee type, address space) to the corresponding `Type::PointerType`
     pointer_types: TypeCache<(TypeRef, AddrSpace)>,
+    /// Map of address space to the corresponding `Type::OpaquePointerType`
+    opaque_pointer_types: TypeCache<AddrSpace>,
+    /// Whether the `Module` uses opaque pointers
+    opaque_pointers: bool,
     /// Map of `FPType` to the corresponding `Type::FPType`
     fp_types: TypeCache<FPType>,
     /// Map of `(result_type, param_types, is_var_arg)` to the corresponding `Type::FunctionType`
@@ -283,10 +315,11 @@ pub(crate) struct TypesBuilder {
     named_struct_types: TypeCache<String>,
     /// Map of struct name to the corresponding `NamedStructDef`
     named_struct_defs: HashMap<String, NamedStructDef>,
+    /// Map of `(name, type_params, int_params)` to the corresponding `Type::TargetExtType`
+    target_ext_types: TypeCache<(String, Vec<TypeRef>, Vec<u32>)>,
     /// `TypeRef` to `Type::X86_MMXType`
     x86_mmx_type: TypeRef,
     /// `TypeRef` to `Type::X86_AMXType`
-    #[cfg(feature = "llvm-12-or-greater")]
     x86_amx_type: TypeRef,
     /// `TypeRef` to `Type::MetadataType`
     metadata_type: TypeRef,
@@ -296,6 +329,7 @@ pub(crate) struct TypesBuilder {
     token_type: TypeRef,
     /// internal cache of already-seen `LLVMTypeRef`s so we can quickly produce
     /// the corresponding `TypeRef` without re-parsing the type
+    #[cfg(feature = "llvm-sys")]
     llvm_type_map: HashMap<LLVMTypeRef, TypeRef>,
 }
 
@@ -305,6 +339,8 @@ impl TypesBuilder {
             void_type: TypeRef::new(Type::VoidType),
             int_types: TypeCache::new(),
             pointer_types: TypeCache::new(),
+            opaque_pointer_types: TypeCache::new(),
+            opaque_pointers: false,
             fp_types: TypeCache::new(),
             func_types: TypeCache::new(),
             vec_types: TypeCache::new(),
@@ -312,12 +348,13 @@ impl TypesBuilder {
             struct_types: TypeCache::new(),
             named_struct_types: TypeCache::new(),
             named_struct_defs: HashMap::new(),
+            target_ext_types: TypeCache::new(),
             x86_mmx_type: TypeRef::new(Type::X86_MMXType),
-            #[cfg(feature = "llvm-12-or-greater")]
             x86_amx_type: TypeRef::new(Type::X86_AMXType),
             metadata_type: TypeRef::new(Type::MetadataType),
             label_type: TypeRef::new(Type::LabelType),
             token_type: TypeRef::new(Type::TokenType),
+            #[cfg(feature = "llvm-sys")]
             llvm_type_map: HashMap::new(),
         }
     }
@@ -330,6 +367,8 @@ impl TypesBuilder {
             void_type: self.void_type,
             int_types: self.int_types,
             pointer_types: self.pointer_types,
+            opaque_pointer_types: self.opaque_pointer_types,
+            opaque_pointers: self.opaque_pointers,
             fp_types: self.fp_types,
             func_types: self.func_types,
             vec_types: self.vec_types,
@@ -337,8 +376,8 @@ impl TypesBuilder {
             struct_types: self.struct_types,
             named_struct_types: self.named_struct_types,
             named_struct_defs: self.named_struct_defs,
+            target_ext_types: self.target_ext_types,
             x86_mmx_type: self.x86_mmx_type,
-            #[cfg(feature = "llvm-12-or-greater")]
             x86_amx_type: self.x86_amx_type,
             metadata_type: self.metadata_type,
             label_type: self.label_type,
@@ -404,6 +443,17 @@ impl TypesBuilder {
             })
     }
 
+    /// Get an opaque pointer type in the default address space (`0`)
+    pub fn opaque_pointer(&mut self) -> TypeRef {
+        self.opaque_pointer_in_addr_space(0) // default to address space 0
+    }
+
+    /// Get an opaque pointer type in the specified address space
+    pub fn opaque_pointer_in_addr_space(&mut self, addr_space: AddrSpace) -> TypeRef {
+        self.opaque_pointer_types
+            .lookup_or_insert(addr_space, || Type::OpaquePointerType { addr_space })
+    }
+
     /// Get a floating-point type
     pub fn fp(&mut self, fpt: FPType) -> TypeRef {
         self.fp_types.lookup_or_insert(fpt, || Type::FPType(fpt))
@@ -437,7 +487,6 @@ impl TypesBuilder {
     }
 
     /// Get a vector type
-    #[cfg(feature = "llvm-11-or-greater")]
     pub fn vector_of(
         &mut self,
         element_type: TypeRef,
@@ -453,17 +502,6 @@ impl TypesBuilder {
                 }
             })
     }
-    /// Get a vector type
-    #[cfg(feature = "llvm-10-or-lower")]
-    pub fn vector_of(&mut self, element_type: TypeRef, num_elements: usize) -> TypeRef {
-        self.vec_types
-            .lookup_or_insert((element_type.clone(), num_elements, false), || {
-                Type::VectorType {
-                    element_type,
-                    num_elements,
-                }
-            })
-    }
 
     /// Get an array type
     pub fn array_of(&mut self, element_type: TypeRef, num_elements: usize) -> TypeRef {
@@ -519,13 +557,22 @@ impl TypesBuilder {
         }
     }
 
+    /// Has a definition been added for the struct with the given `name`?
+    pub fn has_named_struct_def(&self, name: &str) -> bool {
+        self.named_struct_defs.contains_key(name)
+    }
+
+    /// Record whether the `Module` uses opaque pointers
+    pub fn set_opaque_pointers(&mut self, opaque_pointers: bool) {
+        self.opaque_pointers = opaque_pointers;
+    }
+
     /// Get the X86_MMX type
     pub fn x86_mmx(&self) -> TypeRef {
         self.x86_mmx_type.clone()
     }
 
     /// Get the X86_AMX type
-    #[cfg(feature = "llvm-12-or-greater")]
     pub fn x86_amx(&self) -> TypeRef {
         self.x86_amx_type.clone()
     }
@@ -544,6 +591,23 @@ impl TypesBuilder {
     pub fn token_type(&self) -> TypeRef {
         self.token_type.clone()
     }
+
+    /// Get a target extension type with the given name and parameters
+    pub fn target_ext_type(
+        &mut self,
+        name: String,
+        type_params: Vec<TypeRef>,
+        int_params: Vec<u32>,
+    ) -> TypeRef {
+        self.target_ext_types.lookup_or_insert(
+            (name.clone(), type_params.clone(), int_params.clone()),
+            || Type::TargetExtType {
+                name,
+                type_params,
+                int_params,
+            },
+        )
+    }
 }
 
 #[derive(Clone, Debug, Hash)]
@@ -575,6 +639,10 @@ pub struct Types {
     int_types: TypeCache<u32>,
     /// Map of (pointee type, address space) to the corresponding `Type::PointerType`
     pointer_types: TypeCache<(TypeRef, AddrSpace)>,
+    /// Map of address space to the corresponding `Type::OpaquePointerType`
+    opaque_pointer_types: TypeCache<AddrSpace>,
+    /// Whether the `Module` uses opaque pointers
+    opaque_pointers: bool,
     /// Map of `FPType` to the corresponding `Type::FPType`
     fp_types: TypeCache<FPType>,
     /// Map of `(result_type, param_types, is_var_arg)` to the corresponding `Type::FunctionType`
@@ -590,10 +658,11 @@ pub struct Types {
     named_struct_types: TypeCache<String>,
     /// Map of struct name to the corresponding `NamedStructDef`
     named_struct_defs: HashMap<String, NamedStructDef>,
+    /// Map of `(name, type_params, int_params)` to the corresponding `Type::TargetExtType`
+    target_ext_types: TypeCache<(String, Vec<TypeRef>, Vec<u32>)>,
     /// `TypeRef` to `Type::X86_MMXType`
     x86_mmx_type: TypeRef,
     /// `TypeRef` to `Type::X86_AMXType`
-    #[cfg(feature = "llvm-12-or-greater")]
     x86_amx_type: TypeRef,
     /// `TypeRef` to `Type::MetadataType`
     metadata_type: TypeRef,
@@ -663,6 +732,40 @@ impl Types {
             })
     }
 
+    /// Get an opaque pointer type in the default address space (`0`)
+    pub fn opaque_pointer(&self) -> TypeRef {
+        self.opaque_pointer_in_addr_space(0)
+    }
+
+    /// Get an opaque pointer type in the specified address space
+    pub fn opaque_pointer_in_addr_space(&self, addr_space: AddrSpace) -> TypeRef {
+        self.opaque_pointer_types
+            .lookup(&addr_space)
+            .unwrap_or_else(|| TypeRef::new(Type::OpaquePointerType { addr_space }))
+    }
+
+    /// Does the `Module` use opaque pointers (`ptr`) rather than typed
+    /// pointers (e.g., `i32*`)?
+    ///
+    /// LLVM uses one or the other for an entire `Module`.
+    pub fn uses_opaque_pointers(&self) -> bool {
+        self.opaque_pointers
+    }
+
+    /// Get the type of a pointer to a value of type `pointee_type`, in the
+    /// specified address space. This is a `PointerType` if the `Module` uses
+    /// typed pointers, or an `OpaquePointerType` if it uses opaque pointers.
+    pub fn pointer_to_value_in_addr_space(
+        &self,
+        pointee_type: TypeRef,
+        addr_space: AddrSpace,
+    ) -> TypeRef {
+        if self.opaque_pointers {
+            return self.opaque_pointer_in_addr_space(addr_space);
+        }
+        self.pointer_in_addr_space(pointee_type, addr_space)
+    }
+
     /// Get a floating-point type
     pub fn fp(&self, fpt: FPType) -> TypeRef {
         self.fp_types
@@ -699,7 +802,6 @@ impl Types {
     }
 
     /// Get a vector type
-    #[cfg(feature = "llvm-11-or-greater")]
     pub fn vector_of(&self, element_type: TypeRef, num_elements: usize, scalable: bool) -> TypeRef {
         self.vec_types
             .lookup(&(element_type.clone(), num_elements, scalable))
@@ -711,17 +813,6 @@ impl Types {
                 })
             })
     }
-    #[cfg(feature = "llvm-10-or-lower")]
-    pub fn vector_of(&self, element_type: TypeRef, num_elements: usize) -> TypeRef {
-        self.vec_types
-            .lookup(&(element_type.clone(), num_elements, false))
-            .unwrap_or_else(|| {
-                TypeRef::new(Type::VectorType {
-                    element_type,
-                    num_elements,
-                })
-            })
-    }
 
     /// Get an array type
     pub fn array_of(&self, element_type: TypeRef, num_elements: usize) -> TypeRef {
@@ -800,7 +891,6 @@ impl Types {
     }
 
     /// Get the X86_AMX type
-    #[cfg(feature = "llvm-12-or-greater")]
     pub fn x86_amx(&self) -> TypeRef {
         self.x86_amx_type.clone()
     }
@@ -820,6 +910,24 @@ impl Types {
         self.token_type.clone()
     }
 
+    /// Get a target extension type with the given name and parameters
+    pub fn target_ext_type(
+        &self,
+        name: String,
+        type_params: Vec<TypeRef>,
+        int_params: Vec<u32>,
+    ) -> TypeRef {
+        self.target_ext_types
+            .lookup(&(name.clone(), type_params.clone(), int_params.clone()))
+            .unwrap_or_else(|| {
+                TypeRef::new(Type::TargetExtType {
+                    name,
+                    type_params,
+                    int_params,
+                })
+            })
+    }
+
     /// Get a `TypeRef` for the given `Type`
     #[rustfmt::skip] // so we can keep each of the match arms more consistent with each other
     pub fn get_for_type(&self, ty: &Type) -> TypeRef {
@@ -829,18 +937,14 @@ impl Types {
             Type::PointerType { pointee_type, addr_space } => {
                 self.pointer_in_addr_space(pointee_type.clone(), *addr_space)
             },
+            Type::OpaquePointerType { addr_space } => self.opaque_pointer_in_addr_space(*addr_space),
             Type::FPType(fpt) => self.fp(*fpt),
             Type::FuncType { result_type, param_types, is_var_arg } => {
                 self.func_type(result_type.clone(), param_types.clone(), *is_var_arg)
             },
-            #[cfg(feature="llvm-11-or-greater")]
             Type::VectorType { element_type, num_elements, scalable } => {
                 self.vector_of(element_type.clone(), *num_elements, *scalable)
             },
-            #[cfg(feature="llvm-10-or-lower")]
-            Type::VectorType { element_type, num_elements } => {
-                self.vector_of(element_type.clone(), *num_elements)
-            },
             Type::ArrayType { element_type, num_elements } => {
                 self.array_of(element_type.clone(), *num_elements)
             },
@@ -849,11 +953,13 @@ impl Types {
             },
             Type::NamedStructType { name  } => self.named_struct(name),
             Type::X86_MMXType => self.x86_mmx(),
-            #[cfg(feature="llvm-12-or-greater")]
             Type::X86_AMXType => self.x86_amx(),
             Type::MetadataType => self.metadata_type(),
             Type::LabelType => self.label_type(),
             Type::TokenType => self.token_type(),
+            Type::TargetExtType { name, type_params, int_params } => {
+                self.target_ext_type(name.clone(), type_params.clone(), int_params.clone())
+            },
         }
     }
 }
@@ -904,18 +1010,67 @@ impl<K: Eq + Hash + Clone> TypeCache<K> {
     fn contains_key(&self, key: &K) -> bool {
         self.map.contains_key(key)
     }
+
+    /// Add all the `Type`s in `other` which aren't in this cache yet
+    fn absorb(&mut self, other: Self) {
+        for (key, typeref) in other.map {
+            self.map.entry(key).or_insert(typeref);
+        }
+    }
 }
 
 // ********* //
 // from_llvm //
 // ********* //
 
+#[cfg(feature = "llvm-sys")]
 use crate::from_llvm::*;
+#[cfg(feature = "llvm-sys")]
 use crate::llvm_sys::*;
+#[cfg(feature = "llvm-sys")]
 use llvm_sys::LLVMTypeKind;
-use std::collections::hash_map::Entry;
 
+#[cfg(feature = "llvm-sys")]
 impl TypesBuilder {
+    /// Add all the types in `other`, a clone of this `TypesBuilder` which
+    /// has been used to convert functions on another thread, which aren't in
+    /// this `TypesBuilder` yet
+    #[cfg(feature = "parallel")]
+    pub(crate) fn absorb(&mut self, other: Self) {
+        self.int_types.absorb(other.int_types);
+        self.pointer_types.absorb(other.pointer_types);
+        self.opaque_pointer_types.absorb(other.opaque_pointer_types);
+        self.fp_types.absorb(other.fp_types);
+        self.func_types.absorb(other.func_types);
+        self.vec_types.absorb(other.vec_types);
+        self.arr_types.absorb(other.arr_types);
+        self.struct_types.absorb(other.struct_types);
+        self.named_struct_types.absorb(other.named_struct_types);
+        for (name, def) in other.named_struct_defs {
+            self.named_struct_defs.entry(name).or_insert(def);
+        }
+        self.target_ext_types.absorb(other.target_ext_types);
+        for (ty, typeref) in other.llvm_type_map {
+            self.llvm_type_map.entry(ty).or_insert(typeref);
+        }
+    }
+
+    /// A count of the types seen so far, which grows whenever a new type is
+    /// added
+    pub(crate) fn num_types(&self) -> usize {
+        self.int_types.map.len()
+            + self.pointer_types.map.len()
+            + self.opaque_pointer_types.map.len()
+            + self.fp_types.map.len()
+            + self.func_types.map.len()
+            + self.vec_types.map.len()
+            + self.arr_types.map.len()
+            + self.struct_types.map.len()
+            + self.named_struct_types.map.len()
+            + self.named_struct_defs.len()
+            + self.target_ext_types.map.len()
+    }
+
     pub(crate) fn type_from_llvm_ref(&mut self, ty: LLVMTypeRef) -> TypeRef {
         if let Some(typeref) = self.llvm_type_map.get(&ty) {
             return typeref.clone();
@@ -930,25 +1085,32 @@ impl TypesBuilder {
         match kind {
             LLVMTypeKind::LLVMVoidTypeKind => self.void(),
             LLVMTypeKind::LLVMIntegerTypeKind => self.int(unsafe { LLVMGetIntTypeWidth(ty) }),
+            #[cfg(feature = "llvm-14-or-greater")]
+            LLVMTypeKind::LLVMPointerTypeKind if unsafe { LLVMGetNumContainedTypes(ty) } == 0 => {
+                // opaque pointers have no pointee type
+                self.opaque_pointer_in_addr_space(unsafe { LLVMGetPointerAddressSpace(ty) })
+            },
             LLVMTypeKind::LLVMPointerTypeKind => {
                 let pointee_type = self.type_from_llvm_ref(unsafe { LLVMGetElementType(ty) });
                 self.pointer_in_addr_space(pointee_type, unsafe { LLVMGetPointerAddressSpace(ty) })
             },
             LLVMTypeKind::LLVMArrayTypeKind => {
                 let element_type = self.type_from_llvm_ref(unsafe { LLVMGetElementType(ty) });
-                self.array_of(element_type, unsafe { LLVMGetArrayLength(ty) as usize })
+                // `LLVMGetArrayLength` is deprecated (it truncates to 32 bits)
+                // in favor of `LLVMGetArrayLength2` as of LLVM 17
+                #[cfg(feature = "llvm-17-or-greater")]
+                let num_elements = unsafe { LLVMGetArrayLength2(ty) as usize };
+                #[cfg(feature = "llvm-16-or-lower")]
+                let num_elements = unsafe { LLVMGetArrayLength(ty) as usize };
+                self.array_of(element_type, num_elements)
             },
             LLVMTypeKind::LLVMVectorTypeKind => {
                 let element_type = self.type_from_llvm_ref(unsafe { LLVMGetElementType(ty) });
-                #[cfg(feature = "llvm-11-or-greater")]
-                let ret = self.vector_of(
+                self.vector_of(
                     element_type,
                     unsafe { LLVMGetVectorSize(ty) as usize },
                     false,
-                );
-                #[cfg(feature = "llvm-10-or-lower")]
-                let ret = self.vector_of(element_type, unsafe { LLVMGetVectorSize(ty) as usize });
-                ret
+                )
             },
             #[cfg(feature = "llvm-11-or-greater")]
             LLVMTypeKind::LLVMScalableVectorTypeKind => {
@@ -1027,9 +1189,46 @@ impl TypesBuilder {
             LLVMTypeKind::LLVMMetadataTypeKind => self.metadata_type(),
             LLVMTypeKind::LLVMLabelTypeKind => self.label_type(),
             LLVMTypeKind::LLVMTokenTypeKind => self.token_type(),
+            #[cfg(feature = "llvm-16-or-greater")]
+            LLVMTypeKind::LLVMTargetExtTypeKind => {
+                let type_params = {
+                    let num_types = unsafe { LLVMGetNumContainedTypes(ty) };
+                    let mut types: Vec<LLVMTypeRef> = Vec::with_capacity(num_types as usize);
+                    unsafe {
+                        LLVMGetSubtypes(ty, types.as_mut_ptr());
+                        types.set_len(num_types as usize);
+                    };
+                    types
+                        .into_iter()
+                        .map(|t| self.type_from_llvm_ref(t))
+                        .collect()
+                };
+                // The LLVM 16/17 C API has no getters for the name or the
+                // integer parameters, so we recover those from the type's
+                // textual form, `target("name", <types...>, <ints...>)`
+                let text = unsafe {
+                    let ptr = LLVMPrintTypeToString(ty);
+                    let text = raw_to_string(ptr);
+                    LLVMDisposeMessage(ptr);
+                    text
+                };
+                let (name, int_params) = parse_target_ext_type_text(&text);
+                self.target_ext_type(name, type_params, int_params)
+            },
         }
     }
 
+    /// Record whether the given LLVM context is in opaque-pointers mode, in
+    /// which case all pointer types in the `Module` are opaque pointers
+    #[cfg(feature = "llvm-14-or-greater")]
+    pub(crate) fn set_opaque_pointers_from_llvm_context(&mut self, context: LLVMContextRef) {
+        // There's no getter for this in the LLVM 14 C API, but in
+        // opaque-pointers mode, LLVM turns any request for a typed pointer
+        // into an opaque pointer
+        let ptr = unsafe { LLVMPointerType(LLVMInt8TypeInContext(context), 0) };
+        self.opaque_pointers = unsafe { LLVMGetNumContainedTypes(ptr) } == 0;
+    }
+
     /// creates an actual `StructType`, regardless of whether the struct is named or not
     ///
     /// Caller is responsible for ensuring that `ty` is not an opaque struct type
@@ -1054,3 +1253,26 @@ impl TypesBuilder {
         self.struct_of(element_types, unsafe { LLVMIsPackedStruct(ty) } != 0)
     }
 }
+
+/// Parse the name and the integer parameters out of the textual form of a
+/// target extension type, e.g. `target("spirv.Image", void, 0, 1)`. The
+/// integer parameters always come after all the type parameters.
+#[cfg(all(feature = "llvm-sys", feature = "llvm-16-or-greater"))]
+fn parse_target_ext_type_text(text: &str) -> (String, Vec<u32>) {
+    let body = text
+        .trim()
+        .strip_prefix("target(\"")
+        .and_then(|body| body.strip_suffix(')'))
+        .unwrap_or_else(|| panic!("Unexpected textual form of target extension type: {:?}", text));
+    let name_end = body
+        .find('"')
+        .unwrap_or_else(|| panic!("Unexpected textual form of target extension type: {:?}", text));
+    let mut int_params: Vec<u32> = body[name_end ..]
+        .rsplit(", ")
+        .map(|param| param.parse().ok())
+        .take_while(Option::is_some)
+        .flatten()
+        .collect();
+    int_params.reverse();
+    (body[.. name_end].into(), int_params)
+}
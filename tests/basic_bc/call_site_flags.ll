@@ -0,0 +1,27 @@
+; This file written directly as .ll, for the purpose of testing tail-call
+; kinds, call-site fast-math flags, and operand bundles on calls and invokes
+
+declare float @f(float)
+declare void @g(i32)
+declare i32 @__gxx_personality_v0(...)
+
+define float @tail_calls(float %x) {
+  %a = tail call fast float @f(float %x)
+  %b = notail call nnan ninf float @f(float %a)
+  %c = call float @f(float %b) [ "deopt"(i32 1, i64 2), "gc-live"(float %a), "custom"() ]
+  %d = musttail call float @f(float %c)
+  ret float %d
+}
+
+define void @invoke_bundles() personality i32 (...)* @__gxx_personality_v0 {
+entry:
+  invoke void @g(i32 1) [ "deopt"(i32 7) ] to label %cont unwind label %cleanup
+cont:
+  ret void
+cleanup:
+  %cp = cleanuppad within none []
+  call void @g(i32 2) #0 [ "funclet"(token %cp) ]
+  cleanupret from %cp unwind to caller
+}
+
+attributes #0 = { nounwind }
@@ -0,0 +1,28 @@
+; This file written directly as .ll, mostly for the purpose of testing
+; comdats and their members
+
+$f = comdat any
+$data = comdat largest
+$"odd name" = comdat nodeduplicate
+$g = comdat exactmatch
+
+@data = global i32 0, comdat
+@data.extra = global i32 1, comdat($data)
+@g = linkonce_odr global i64 0, section "x comdat($h)", comdat
+@quoted = global i8 0, comdat($"odd name")
+
+define linkonce_odr i32 @f(i32 %x) comdat {
+  ret i32 %x
+}
+
+define linkonce_odr i32 @f.helper(i32 %x) comdat($f) {
+  ret i32 %x
+}
+
+define i32 @in.odd.name() comdat($"odd name") {
+  ret i32 0
+}
+
+define i32 @no_comdat() {
+  ret i32 0
+}
@@ -0,0 +1,34 @@
+; Functions for exercising `llvm_ir::dataflow`
+
+define i32 @loop(i32 %n, i32 %unused) {
+entry:
+  br label %header
+header:
+  %i = phi i32 [ 0, %entry ], [ %i.next, %body ]
+  %sum = phi i32 [ 0, %entry ], [ %sum.next, %body ]
+  %done = icmp sge i32 %i, %n
+  br i1 %done, label %exit, label %body
+body:
+  %sum.next = add i32 %sum, %i
+  %i.next = add i32 %i, 1
+  br label %header
+exit:
+  ret i32 %sum
+}
+
+define i32 @constant_branches(i32 %x) {
+entry:
+  br i1 true, label %live, label %dead
+live:
+  switch i32 1, label %dead2 [ i32 0, label %dead3
+                               i32 1, label %ret ]
+dead:
+  br label %dead2
+dead2:
+  br label %ret
+dead3:
+  br label %ret
+ret:
+  %r = phi i32 [ %x, %live ], [ 2, %dead2 ], [ 3, %dead3 ]
+  ret i32 %r
+}
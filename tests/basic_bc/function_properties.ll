@@ -0,0 +1,27 @@
+; This file written directly as .ll, mostly for the purpose of testing
+; prefix data, prologue data, and metadata attachments on functions
+
+define i32 @plain(i32 %x) {
+  ret i32 %x
+}
+
+define i32 @with_prefix(i32 %x) prefix i32 123 {
+  ret i32 %x
+}
+
+define i32 @with_prologue(i32 %x) prologue i8 144 {
+  ret i32 %x
+}
+
+declare i32 @personality(...)
+
+define i32 @everything(i32 %x) prefix i32 7 prologue i8 144 personality i32 (...)* @personality !type !0 !prof !1 {
+  ret i32 %x
+}
+
+define i32 @with_md(i32 %x) !type !0 {
+  ret i32 %x
+}
+
+!0 = !{i64 0, !"typeid"}
+!1 = !{!"function_entry_count", i64 42}
@@ -0,0 +1,15 @@
+; This file written directly as .ll, mostly for the purpose of testing
+; properties of global variables
+
+@plain = global i32 0
+@ext_init = externally_initialized global i32 0
+@in_partition = global i32 0, partition "part1"
+@with_attrs = global i32 0 #0
+@with_md = global i32 0, !type !0, !custom !1
+@everything = externally_initialized global i32 0, section "data", partition "part2", align 4, !type !0 #1
+
+attributes #0 = { "bss-section"="my_bss" "rodata-section"="my_rodata" }
+attributes #1 = { "data-section"="my_data" "no-value" }
+
+!0 = !{i64 0, !"typeid"}
+!1 = !{!"custom node"}
@@ -0,0 +1,23 @@
+; This file written directly as .ll, mostly for the purpose of testing
+; ifuncs and references to them
+
+@foo = ifunc i32 (i32), i32 (i32)* ()* @resolve_foo
+@bar = internal ifunc void (), void ()* ()* @resolve_bar
+@foo_ptr = global i32 (i32)* @foo
+
+define internal i32 @foo_impl(i32 %x) {
+  ret i32 %x
+}
+
+define internal i32 (i32)* @resolve_foo() {
+  ret i32 (i32)* @foo_impl
+}
+
+define internal void ()* @resolve_bar() {
+  ret void ()* null
+}
+
+define i32 @call_foo(i32 %x) {
+  %r = call i32 @foo(i32 %x)
+  ret i32 %r
+}
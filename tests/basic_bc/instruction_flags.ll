@@ -0,0 +1,39 @@
+; This file written directly as .ll, for the purpose of testing poison-generating
+; flags (`nuw`, `nsw`, `exact`, `inbounds`) and fast-math flags on instructions
+; and constant expressions
+
+@g = global [4 x i32] zeroinitializer
+@add_const = global i64 add nuw nsw (i64 ptrtoint ([4 x i32]* @g to i64), i64 1)
+@udiv_const = global i64 udiv exact (i64 ptrtoint ([4 x i32]* @g to i64), i64 4)
+@shl_const = global i64 shl (i64 ptrtoint ([4 x i32]* @g to i64), i64 1)
+@gep_const = global i32* getelementptr inbounds ([4 x i32], [4 x i32]* @g, i64 0, i64 2)
+
+define i32 @int_flags(i32 %a, i32 %b) {
+  %1 = add nsw i32 %a, %b
+  %2 = sub nuw i32 %1, %b
+  %3 = mul nuw nsw i32 %2, %a
+  %4 = shl nuw i32 %3, 2
+  %5 = udiv exact i32 %4, %b
+  %6 = sdiv i32 %5, %b
+  %7 = lshr exact i32 %6, 1
+  %8 = ashr i32 %7, 1
+  %9 = add i32 %8, %a
+  ret i32 %9
+}
+
+define i1 @fp_flags(float %a, float %b) {
+  %1 = fadd fast float %a, %b
+  %2 = fsub nnan float %1, %b
+  %3 = fmul ninf nsz float %2, %a
+  %4 = fdiv arcp float %3, %b
+  %5 = frem float %4, %b
+  %6 = fneg reassoc contract afn float %5
+  %7 = fcmp nnan oeq float %6, %a
+  ret i1 %7
+}
+
+define i32* @gep_flags(i32* %p) {
+  %1 = getelementptr inbounds i32, i32* %p, i64 1
+  %2 = getelementptr i32, i32* %1, i64 1
+  ret i32* %2
+}
@@ -0,0 +1,186 @@
+; Functions for exercising `llvm_ir::Interpreter`
+target datalayout = "e-m:e-p270:32:32-p271:32:32-p272:64:64-i64:64-f80:128-n8:16:32:64-S128"
+target triple = "x86_64-unknown-linux-gnu"
+
+%struct.Point = type { i32, i64 }
+
+@counter = global i32 10, align 4
+@table = constant [4 x i16] [i16 1, i16 2, i16 3, i16 4], align 2
+@str = private constant [6 x i8] c"hello\00", align 1
+
+declare i32 @host_add(i32, i32)
+declare i64 @host_strlen(i8*)
+declare void @llvm.memcpy.p0i8.p0i8.i64(i8*, i8*, i64, i1)
+declare void @llvm.memset.p0i8.i64(i8*, i8, i64, i1)
+declare { i32, i1 } @llvm.sadd.with.overflow.i32(i32, i32)
+
+define i32 @factorial(i32 %n) {
+entry:
+  %cmp = icmp sle i32 %n, 1
+  br i1 %cmp, label %base, label %rec
+base:
+  ret i32 1
+rec:
+  %m = sub i32 %n, 1
+  %r = call i32 @factorial(i32 %m)
+  %p = mul i32 %n, %r
+  ret i32 %p
+}
+
+; the phis in %loop must be evaluated simultaneously
+define i32 @fib(i32 %n) {
+entry:
+  br label %loop
+loop:
+  %a = phi i32 [ 0, %entry ], [ %b, %body ]
+  %b = phi i32 [ 1, %entry ], [ %c, %body ]
+  %i = phi i32 [ 0, %entry ], [ %i.next, %body ]
+  %done = icmp eq i32 %i, %n
+  br i1 %done, label %exit, label %body
+body:
+  %c = add i32 %a, %b
+  %i.next = add i32 %i, 1
+  br label %loop
+exit:
+  ret i32 %a
+}
+
+define i64 @point_sum(i32 %x, i64 %y) {
+entry:
+  %p = alloca %struct.Point, align 8
+  %px = getelementptr inbounds %struct.Point, %struct.Point* %p, i32 0, i32 0
+  store i32 %x, i32* %px, align 8
+  %py = getelementptr inbounds %struct.Point, %struct.Point* %p, i32 0, i32 1
+  store i64 %y, i64* %py, align 8
+  %v = load %struct.Point, %struct.Point* %p, align 8
+  %vx = extractvalue %struct.Point %v, 0
+  %vy = extractvalue %struct.Point %v, 1
+  %vx64 = sext i32 %vx to i64
+  %s = add i64 %vx64, %vy
+  ret i64 %s
+}
+
+define i16 @lookup(i32 %i) {
+entry:
+  switch i32 %i, label %oob [ i32 0, label %ok
+                              i32 1, label %ok
+                              i32 2, label %ok
+                              i32 3, label %ok ]
+ok:
+  %idx = sext i32 %i to i64
+  %ptr = getelementptr inbounds [4 x i16], [4 x i16]* @table, i64 0, i64 %idx
+  %v = load i16, i16* %ptr, align 2
+  ret i16 %v
+oob:
+  ret i16 -1
+}
+
+define i16 @lookup_unchecked(i64 %idx) {
+entry:
+  %ptr = getelementptr [4 x i16], [4 x i16]* @table, i64 0, i64 %idx
+  %v = load i16, i16* %ptr, align 2
+  ret i16 %v
+}
+
+define i32 @divide(i32 %a, i32 %b) {
+entry:
+  %q = sdiv i32 %a, %b
+  ret i32 %q
+}
+
+define void @bump(i32 %by) {
+entry:
+  %old = load i32, i32* @counter, align 4
+  %new = add i32 %old, %by
+  store i32 %new, i32* @counter, align 4
+  ret void
+}
+
+define i32 @call_host(i32 %x) {
+entry:
+  %r = call i32 @host_add(i32 %x, i32 1)
+  %len = call i64 @host_strlen(i8* getelementptr inbounds ([6 x i8], [6 x i8]* @str, i64 0, i64 0))
+  %len32 = trunc i64 %len to i32
+  %s = add i32 %r, %len32
+  ret i32 %s
+}
+
+define double @sum_of_squares(float %x, double %y) {
+entry:
+  %xd = fpext float %x to double
+  %x2 = fmul double %xd, %xd
+  %y2 = fmul double %y, %y
+  %s = fadd double %x2, %y2
+  ret double %s
+}
+
+define i32 @float_bits(float %f) {
+entry:
+  %b = bitcast float %f to i32
+  ret i32 %b
+}
+
+define i32 @dot(<4 x i32> %a, <4 x i32> %b) {
+entry:
+  %m = mul <4 x i32> %a, %b
+  %s = shufflevector <4 x i32> %m, <4 x i32> undef, <4 x i32> <i32 2, i32 3, i32 undef, i32 undef>
+  %t = add <4 x i32> %m, %s
+  %e0 = extractelement <4 x i32> %t, i32 0
+  %e1 = extractelement <4 x i32> %t, i32 1
+  %r = add i32 %e0, %e1
+  ret i32 %r
+}
+
+define i32 @copy_and_add_max(i32 %x) {
+entry:
+  %src = alloca i32, align 4
+  %dst = alloca i32, align 4
+  store i32 %x, i32* %src, align 4
+  %s8 = bitcast i32* %src to i8*
+  %d8 = bitcast i32* %dst to i8*
+  call void @llvm.memcpy.p0i8.p0i8.i64(i8* %d8, i8* %s8, i64 4, i1 false)
+  %v = load i32, i32* %dst, align 4
+  %r = call { i32, i1 } @llvm.sadd.with.overflow.i32(i32 %v, i32 2147483647)
+  %sum = extractvalue { i32, i1 } %r, 0
+  %ov = extractvalue { i32, i1 } %r, 1
+  %res = select i1 %ov, i32 -1, i32 %sum
+  ret i32 %res
+}
+
+define i32* @escape() {
+entry:
+  %p = alloca i32, align 4
+  store i32 1, i32* %p, align 4
+  ret i32* %p
+}
+
+define i32 @use_after_return() {
+entry:
+  %p = call i32* @escape()
+  %v = load i32, i32* %p, align 4
+  ret i32 %v
+}
+
+define void @unreachable_fn() {
+entry:
+  unreachable
+}
+
+define void @recurse_forever() {
+entry:
+  call void @recurse_forever()
+  ret void
+}
+
+define void @dynamic_alloca(i64 %n) {
+entry:
+  %p = alloca i8, i64 %n, align 1
+  ret void
+}
+
+define void @memset_alloca(i64 %n) {
+entry:
+  %p = alloca i8, align 1
+  call void @llvm.memset.p0i8.i64(i8* %p, i8 0, i64 %n, i1 false)
+  ret void
+}
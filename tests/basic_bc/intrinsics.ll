@@ -0,0 +1,57 @@
+; This file written directly as .ll, mostly for the purpose of testing
+; recognition of calls to intrinsics
+
+declare void @llvm.memcpy.p0i8.p0i8.i64(i8*, i8*, i64, i1)
+declare void @llvm.memmove.p0i8.p0i8.i64(i8*, i8*, i64, i1)
+declare void @llvm.memset.p0i8.i32(i8*, i8, i32, i1)
+declare void @llvm.lifetime.start.p0i8(i64, i8*)
+declare void @llvm.lifetime.end.p0i8(i64, i8*)
+declare void @llvm.dbg.value(metadata, metadata, metadata)
+declare { i32, i1 } @llvm.sadd.with.overflow.i32(i32, i32)
+declare { i64, i1 } @llvm.umul.with.overflow.i64(i64, i64)
+declare float @llvm.experimental.constrained.fadd.f32(float, float, metadata, metadata)
+declare void @llvm.assume(i1)
+declare i64 @llvm.expect.i64(i64, i64)
+declare i8* @llvm.stacksave()
+declare void @llvm.stackrestore(i8*)
+declare void @llvm.trap()
+declare i32 @llvm.ctpop.i32(i32)
+declare void @llvm.memcpy.element.unordered.atomic.p0i8.p0i8.i64(i8*, i8*, i64, i32)
+declare i64 @llvm.expect.with.probability.i64(i64, i64, double)
+declare void @not_an_intrinsic()
+
+define void @intrinsics(i8* %a, i8* %b, i64 %n, i32 %x, float %f) strictfp {
+  call void @llvm.memcpy.p0i8.p0i8.i64(i8* %a, i8* %b, i64 %n, i1 false)
+  call void @llvm.memmove.p0i8.p0i8.i64(i8* %a, i8* %b, i64 16, i1 true)
+  call void @llvm.memset.p0i8.i32(i8* %a, i8 0, i32 %x, i1 false)
+  call void @llvm.lifetime.start.p0i8(i64 8, i8* %a)
+  call void @llvm.lifetime.end.p0i8(i64 -1, i8* %a)
+  call void @llvm.dbg.value(metadata i32 %x, metadata !2, metadata !DIExpression()), !dbg !8
+  %s = call { i32, i1 } @llvm.sadd.with.overflow.i32(i32 %x, i32 1)
+  %m = call { i64, i1 } @llvm.umul.with.overflow.i64(i64 %n, i64 %n)
+  %c = call float @llvm.experimental.constrained.fadd.f32(float %f, float 1.0, metadata !"round.dynamic", metadata !"fpexcept.strict") strictfp
+  %cond = icmp ne i32 %x, 0
+  call void @llvm.assume(i1 %cond)
+  %e = call i64 @llvm.expect.i64(i64 %n, i64 0)
+  %sp = call i8* @llvm.stacksave()
+  call void @llvm.stackrestore(i8* %sp)
+  %p = call i32 @llvm.ctpop.i32(i32 %x)
+  call void @not_an_intrinsic()
+  call void @llvm.trap()
+  call void @llvm.memcpy.element.unordered.atomic.p0i8.p0i8.i64(i8* align 4 %a, i8* align 4 %b, i64 16, i32 4)
+  %ep = call i64 @llvm.expect.with.probability.i64(i64 %n, i64 0, double 0.5)
+  ret void
+}
+
+!llvm.dbg.cu = !{!0}
+!llvm.module.flags = !{!9}
+
+!0 = distinct !DICompileUnit(language: DW_LANG_C99, file: !1, emissionKind: FullDebug)
+!1 = !DIFile(filename: "intrinsics.c", directory: "/tmp")
+!2 = !DILocalVariable(name: "x", scope: !3, file: !1, line: 1, type: !6)
+!3 = distinct !DISubprogram(name: "intrinsics", scope: !1, file: !1, line: 1, type: !4, unit: !0)
+!4 = !DISubroutineType(types: !5)
+!5 = !{null}
+!6 = !DIBasicType(name: "int", size: 32, encoding: DW_ATE_signed)
+!8 = !DILocation(line: 1, column: 1, scope: !3)
+!9 = !{i32 2, !"Debug Info Version", i32 3}
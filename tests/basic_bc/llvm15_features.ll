@@ -0,0 +1,33 @@
+; This file written directly as .ll, for the purpose of testing IR features
+; which are new in LLVM 15: opaque pointers by default, the `allockind`
+; function attribute, the `allocalign` and `allocptr` parameter attributes,
+; the `fmax` and `fmin` atomicrmw operations, sanitizer metadata on globals,
+; and the `uwtable(sync)`, `presplitcoroutine`, and `fn_ret_thunk_extern`
+; function attributes
+
+@g = global i32 0
+@p = global ptr @g
+@no_asan = global i32 0, no_sanitize_address, sanitize_address_dyninit
+@tagged = global i32 0, sanitize_memtag
+
+define noalias ptr @my_malloc(i64 %size, i64 allocalign %align) allockind("alloc,uninitialized,aligned") {
+  ret ptr null
+}
+
+define void @my_free(ptr allocptr %ptr) allockind("free") {
+  ret void
+}
+
+define float @atomics(ptr %f) {
+  %1 = atomicrmw fmax ptr %f, float 1.0 seq_cst
+  %2 = atomicrmw fmin ptr %f, float %1 monotonic
+  ret float %2
+}
+
+define void @sync_unwind() uwtable(sync) presplitcoroutine fn_ret_thunk_extern {
+  ret void
+}
+
+define void @async_unwind() uwtable {
+  ret void
+}
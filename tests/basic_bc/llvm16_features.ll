@@ -0,0 +1,33 @@
+; This file written directly as .ll, for the purpose of testing IR features
+; which are new in LLVM 16: the `memory` function attribute, the `uinc_wrap`
+; and `udec_wrap` atomicrmw operations, and target extension types
+
+define i32 @pure(i32 %x) memory(none) {
+  ret i32 %x
+}
+
+define i32 @reads_args(ptr %p) memory(argmem: read) {
+  %1 = load i32, ptr %p
+  ret i32 %1
+}
+
+define void @mixed(ptr %p) memory(read, argmem: readwrite, inaccessiblemem: write) {
+  ret void
+}
+
+define i32 @atomics(ptr %p) {
+  %1 = atomicrmw uinc_wrap ptr %p, i32 10 seq_cst
+  %2 = atomicrmw volatile udec_wrap ptr %p, i32 %1 acquire
+  ret i32 %2
+}
+
+define void @calls(ptr %p) {
+  %1 = call i32 @pure(i32 0)
+  %2 = call i32 @reads_args(ptr %p)
+  call void @mixed(ptr %p)
+  ret void
+}
+
+define void @target_ext(target("spirv.Image", i8, 0, 1) %image, target("spirv.Event") %event) {
+  ret void
+}
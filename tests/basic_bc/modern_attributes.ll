@@ -0,0 +1,18 @@
+; This file written directly as .ll, mostly for the purpose of testing
+; attributes introduced in LLVM 12 through 14
+
+define void @progress() mustprogress hot nocallback noprofile nosanitize_coverage disable_sanitizer_instrumentation {
+  ret void
+}
+
+define void @vscale_bounded() vscale_range(1,16) {
+  ret void
+}
+
+define void @vscale_unbounded() vscale_range(2,0) {
+  ret void
+}
+
+define void @params(i32* readonly %a, i32* readnone %b, i32* writeonly %c, i32* byref(i32) %d, i8* swiftasync %e) {
+  ret void
+}
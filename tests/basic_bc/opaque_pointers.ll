@@ -0,0 +1,23 @@
+; This file written directly as .ll, for the purpose of testing opaque pointers
+; (`ptr`). LLVM 14 must be in opaque-pointers mode to assemble or read it, e.g.
+; `llvm-as -opaque-pointers`
+
+%pair = type { i32, i64 }
+
+@g = global i32 5
+@h = global ptr @g
+@p = addrspace(1) global %pair zeroinitializer
+@q = global %pair zeroinitializer
+@elt = global ptr getelementptr inbounds (%pair, ptr @q, i64 0, i32 1)
+@alias = alias i32, ptr @g
+
+declare i32 @callee(ptr, i64)
+
+define i64 @opaque(ptr %p, i64 %i) {
+  %a = alloca i32, align 4
+  %q = getelementptr inbounds %pair, ptr %p, i64 %i, i32 1
+  %v = load i64, ptr %q, align 8
+  %r = call i32 @callee(ptr %a, i64 %v)
+  %old = atomicrmw add ptr %a, i32 %r seq_cst
+  ret i64 %v
+}
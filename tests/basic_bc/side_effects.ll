@@ -0,0 +1,33 @@
+; This file written directly as .ll, mostly for the purpose of testing
+; classification of instructions' memory and side effects
+
+declare i32 @pure(i32) readnone nounwind willreturn
+declare i32 @reads(i32*) readonly nounwind willreturn
+declare void @writes(i32*) writeonly nounwind willreturn
+declare void @may_loop() readnone nounwind
+declare void @unknown()
+declare void @llvm.memset.p0i8.i64(i8*, i8, i64, i1)
+
+define i32 @defined_pure(i32 %x) readnone nounwind willreturn {
+  ret i32 %x
+}
+
+define void @side_effects(i32* %p, i8* %q, i32 %x) {
+  %a = add i32 %x, 1
+  %alloca = alloca i32
+  %l = load i32, i32* %p
+  %vl = load volatile i32, i32* %p
+  %al = load atomic i32, i32* %p seq_cst, align 4
+  %ul = load atomic i32, i32* %p unordered, align 4
+  store i32 %x, i32* %p
+  fence seq_cst
+  %c1 = call i32 @pure(i32 %x)
+  %c2 = call i32 @reads(i32* %p)
+  call void @writes(i32* %p)
+  call void @may_loop()
+  call void @unknown()
+  call void @unknown() readnone nounwind willreturn
+  call void @llvm.memset.p0i8.i64(i8* %q, i8 0, i64 4, i1 false)
+  %c3 = call i32 @defined_pure(i32 %x)
+  ret void
+}
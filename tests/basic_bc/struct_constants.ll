@@ -0,0 +1,10 @@
+; This file written directly as .ll, mostly for the purpose of testing
+; constants of named and literal struct types
+
+%struct.Config = type { i32, i8* }
+%struct.Packed = type <{ i8, i32 }>
+
+@name = constant [4 x i8] c"foo\00"
+@config = global %struct.Config { i32 3, i8* getelementptr inbounds ([4 x i8], [4 x i8]* @name, i32 0, i32 0) }
+@packed = global %struct.Packed <{ i8 1, i32 2 }>
+@literal = global { i32, i32 } { i32 4, i32 5 }
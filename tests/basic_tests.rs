@@ -1,8 +1,24 @@
+// These tests use bitcode which was compiled (mostly by clang) with each
+// supported LLVM version. We don't have that bitcode for LLVM 15+ yet; for
+// those versions, see `llvm_15_tests.rs` and `llvm_16_tests.rs`.
+#![cfg(feature = "llvm-14-or-lower")]
+#![cfg(feature = "llvm-sys")]
+
 use either::Either;
 use itertools::Itertools;
-use llvm_ir::function::{FunctionAttribute, ParameterAttribute};
+use llvm_ir::function::{FunctionAttribute, ParameterAttribute, UWTableKind};
 use llvm_ir::instruction;
-use llvm_ir::module::{Alignment, Endianness, Mangling, PointerLayout};
+use llvm_ir::module::{
+    Alignment,
+    AttachedMetadata,
+    Endianness,
+    Linkage,
+    Mangling,
+    MetadataAttachment,
+    PointerLayout,
+    SelectionKind,
+    Visibility,
+};
 use llvm_ir::terminator;
 use llvm_ir::types::{FPType, NamedStructDef, Typed};
 #[cfg(feature = "llvm-9-or-greater")]
@@ -357,6 +373,7 @@ fn loopbc() {
                 module.type_of(&lifetimestart.function),
                 module.types.pointer_to(ty.clone())
             ); // lifetimestart.function should be a constant function pointer
+            assert_eq!(&lifetimestart.function_ty, ty);
             assert_eq!(name.as_str(), "llvm.lifetime.start.p0i8");
             if let Type::FuncType {
                 result_type,
@@ -527,7 +544,7 @@ fn loopbc() {
         );
         assert_eq!(add.dest, Name::Number(7));
         assert_eq!(module.type_of(add), module.types.i32());
-        assert_eq!(&add.to_string(), "%7 = add i32 %0, i32 3");
+        assert_eq!(&add.to_string(), "%7 = add nsw i32 %0, i32 3");
     }
     #[cfg(feature = "llvm-14-or-greater")]
     {
@@ -548,7 +565,7 @@ fn loopbc() {
         );
         assert_eq!(add.dest, Name::Number(8));
         assert_eq!(module.type_of(add), module.types.i32());
-        assert_eq!(&add.to_string(), "%8 = add i32 %0, i32 3");
+        assert_eq!(&add.to_string(), "%8 = add nsw i32 %0, i32 3");
     }
     #[cfg(feature = "llvm-12-or-lower")]
     {
@@ -935,6 +952,7 @@ fn loopbc() {
     };
     assert_eq!(gep.dest, gep_dest);
     assert_eq!(gep.in_bounds, true);
+    assert_eq!(gep.source_element_type, allocated_type);
     let index = if cfg!(feature = "llvm-9-or-lower") {
         Name::Number(11)
     } else if cfg!(feature = "llvm-10") {
@@ -1272,6 +1290,7 @@ fn variablesbc() {
     assert_eq!(var.name, "global");
     assert_eq!(var.is_constant, false);
     assert_eq!(var.ty, module.types.pointer_to(module.types.i32()));
+    assert_eq!(var.value_type, module.types.i32());
     assert_eq!(
         var.initializer,
         Some(ConstantRef::new(Constant::Int { bits: 32, value: 5 }))
@@ -1306,6 +1325,7 @@ fn variablesbc() {
         }
     );
     assert_eq!(module.type_of(load), module.types.i32());
+    assert_eq!(load.loaded_ty, module.types.i32());
     assert_eq!(&load.to_string(), "%8 = load volatile i32* %4, align 4");
     let global_load: &instruction::Load =
         &bb.instrs[14].clone().try_into().expect("Should be a load");
@@ -1386,6 +1406,18 @@ fn variablesbcg() {
         debugloc.directory.as_ref().map(|s| s.as_str()),
         Some(debug_directory)
     );
+
+    // the attached `DIGlobalVariableExpression` doesn't depend on where LLVM
+    // put the node it references
+    assert_eq!(
+        var.metadata,
+        vec![MetadataAttachment {
+            kind: "dbg".to_owned(),
+            node: AttachedMetadata::Other(
+                "!DIGlobalVariableExpression(var: !?, expr: !DIExpression())".to_owned()
+            ),
+        }]
+    );
 }
 
 // this test checks for regression on issue #4
@@ -1398,6 +1430,7 @@ fn issue4() {
     let func = &module.functions[0];
 
     // not part of issue 4 proper, but let's check that we have the correct number of function attributes
+    let function_attributes = module.get_function_attributes(func.function_attribute_group);
     let expected_num_function_attributes = if cfg!(feature = "llvm-8-or-lower") {
         21
     } else if cfg!(feature = "llvm-9") {
@@ -1428,12 +1461,12 @@ fn issue4() {
         panic!("Shouldn't reach this")
     };
     assert_eq!(
-        func.function_attributes.len(),
+        function_attributes.len(),
         expected_num_function_attributes,
         "Expected {} function attributes but have {}: {:?}",
         expected_num_function_attributes,
-        func.function_attributes.len(),
-        func.function_attributes
+        function_attributes.len(),
+        function_attributes
     );
     // and that all but 6 of them are StringAttributes (5 of them for LLVM 8; 7 for LLVM 12; 9 for LLVM 13+)
     let expected_num_enum_attrs = if cfg!(feature = "llvm-8-or-lower") {
@@ -1447,7 +1480,7 @@ fn issue4() {
     } else {
         panic!("Shouldn't reach this")
     };
-    let string_attrs = func.function_attributes.iter().filter(|attr| {
+    let string_attrs = function_attributes.iter().filter(|attr| {
         if let FunctionAttribute::StringAttribute { .. } = attr {
             true
         } else {
@@ -1475,12 +1508,6 @@ fn issue4() {
     assert_eq!(second_param_attrs.len(), 1); // LLVM 14+ adds 'noundef' to the second param
 
     // and that one of the parameter attributes is SRet
-    #[cfg(feature = "llvm-11-or-lower")]
-    let is_sret = |attr: &ParameterAttribute| match attr {
-        ParameterAttribute::SRet => true,
-        _ => false,
-    };
-    #[cfg(feature = "llvm-12-or-greater")]
     let is_sret = |attr: &ParameterAttribute| match attr {
         ParameterAttribute::SRet(_) => true,
         _ => false,
@@ -2053,13 +2080,11 @@ fn param_and_func_attributes() {
     assert_eq!(f.parameters.len(), 1);
     let param = &f.parameters[0];
     assert_eq!(param.attributes.len(), 1);
-    #[cfg(feature = "llvm-8-or-lower")]
-    assert_eq!(param.attributes[0], ParameterAttribute::ByVal);
-    #[cfg(all(feature = "llvm-9-or-greater", feature = "llvm-11-or-lower"))]
-    assert_eq!(param.attributes[0], ParameterAttribute::UnknownAttribute);
+    #[cfg(feature = "llvm-11-or-lower")]
+    assert_eq!(param.attributes[0], ParameterAttribute::ByVal(None));
     #[cfg(feature = "llvm-12-or-greater")]
     match &param.attributes[0] {
-        ParameterAttribute::ByVal(ty) => match ty.as_ref() {
+        ParameterAttribute::ByVal(Some(ty)) => match ty.as_ref() {
             Type::StructType {
                 element_types,
                 is_packed: false,
@@ -2075,10 +2100,10 @@ fn param_and_func_attributes() {
     let param = &f.parameters[0];
     assert_eq!(param.attributes.len(), 1);
     #[cfg(feature = "llvm-12-or-lower")]
-    assert_eq!(param.attributes[0], ParameterAttribute::InAlloca);
+    assert_eq!(param.attributes[0], ParameterAttribute::InAlloca(None));
     #[cfg(feature = "llvm-13-or-greater")]
     match &param.attributes[0] {
-        ParameterAttribute::InAlloca(ty) => match ty.as_ref() {
+        ParameterAttribute::InAlloca(Some(ty)) => match ty.as_ref() {
             Type::IntegerType { bits: 8 } => {},
             ty => panic!("Expected i8, got {:?}", ty),
         },
@@ -2089,10 +2114,10 @@ fn param_and_func_attributes() {
     let param = &f.parameters[0];
     assert_eq!(param.attributes.len(), 1);
     #[cfg(feature = "llvm-11-or-lower")]
-    assert_eq!(param.attributes[0], ParameterAttribute::SRet);
+    assert_eq!(param.attributes[0], ParameterAttribute::SRet(None));
     #[cfg(feature = "llvm-12-or-greater")]
     match &param.attributes[0] {
-        ParameterAttribute::SRet(ty) => match ty.as_ref() {
+        ParameterAttribute::SRet(Some(ty)) => match ty.as_ref() {
             Type::IntegerType { bits: 8 } => {},
             ty => panic!("Expected i8, got {:?}", ty),
         },
@@ -2141,134 +2166,184 @@ fn param_and_func_attributes() {
 
     // Function attributes
     let f = module.get_func_by_name("f.alignstack4").unwrap();
-    assert_eq!(f.function_attributes.len(), 1);
-    assert_eq!(f.function_attributes[0], FunctionAttribute::AlignStack(4));
+    let attrs = module.get_function_attributes(f.function_attribute_group);
+    assert_eq!(attrs.len(), 1);
+    assert_eq!(attrs[0], FunctionAttribute::AlignStack(4));
     let f = module.get_func_by_name("f.alignstack8").unwrap();
-    assert_eq!(f.function_attributes.len(), 1);
-    assert_eq!(f.function_attributes[0], FunctionAttribute::AlignStack(8));
+    let attrs = module.get_function_attributes(f.function_attribute_group);
+    assert_eq!(attrs.len(), 1);
+    assert_eq!(attrs[0], FunctionAttribute::AlignStack(8));
     let f = module.get_func_by_name("f.alwaysinline").unwrap();
-    assert_eq!(f.function_attributes.len(), 1);
-    assert_eq!(f.function_attributes[0], FunctionAttribute::AlwaysInline);
+    let attrs = module.get_function_attributes(f.function_attribute_group);
+    assert_eq!(attrs.len(), 1);
+    assert_eq!(attrs[0], FunctionAttribute::AlwaysInline);
     let f = module.get_func_by_name("f.cold").unwrap();
-    assert_eq!(f.function_attributes.len(), 1);
-    assert_eq!(f.function_attributes[0], FunctionAttribute::Cold);
+    let attrs = module.get_function_attributes(f.function_attribute_group);
+    assert_eq!(attrs.len(), 1);
+    assert_eq!(attrs[0], FunctionAttribute::Cold);
     let f = module.get_func_by_name("f.convergent").unwrap();
-    assert_eq!(f.function_attributes.len(), 1);
-    assert_eq!(f.function_attributes[0], FunctionAttribute::Convergent);
+    let attrs = module.get_function_attributes(f.function_attribute_group);
+    assert_eq!(attrs.len(), 1);
+    assert_eq!(attrs[0], FunctionAttribute::Convergent);
     let f = module.get_func_by_name("f.inlinehint").unwrap();
-    assert_eq!(f.function_attributes.len(), 1);
-    assert_eq!(f.function_attributes[0], FunctionAttribute::InlineHint);
+    let attrs = module.get_function_attributes(f.function_attribute_group);
+    assert_eq!(attrs.len(), 1);
+    assert_eq!(attrs[0], FunctionAttribute::InlineHint);
     let f = module.get_func_by_name("f.jumptable").unwrap();
-    assert_eq!(f.function_attributes.len(), 1);
-    assert_eq!(f.function_attributes[0], FunctionAttribute::JumpTable);
+    let attrs = module.get_function_attributes(f.function_attribute_group);
+    assert_eq!(attrs.len(), 1);
+    assert_eq!(attrs[0], FunctionAttribute::JumpTable);
     let f = module.get_func_by_name("f.minsize").unwrap();
-    assert_eq!(f.function_attributes.len(), 1);
-    assert_eq!(f.function_attributes[0], FunctionAttribute::MinimizeSize);
+    let attrs = module.get_function_attributes(f.function_attribute_group);
+    assert_eq!(attrs.len(), 1);
+    assert_eq!(attrs[0], FunctionAttribute::MinimizeSize);
     let f = module.get_func_by_name("f.naked").unwrap();
-    assert_eq!(f.function_attributes.len(), 1);
-    assert_eq!(f.function_attributes[0], FunctionAttribute::Naked);
+    let attrs = module.get_function_attributes(f.function_attribute_group);
+    assert_eq!(attrs.len(), 1);
+    assert_eq!(attrs[0], FunctionAttribute::Naked);
     let f = module.get_func_by_name("f.nobuiltin").unwrap();
-    assert_eq!(f.function_attributes.len(), 1);
-    assert_eq!(f.function_attributes[0], FunctionAttribute::NoBuiltin);
+    let attrs = module.get_function_attributes(f.function_attribute_group);
+    assert_eq!(attrs.len(), 1);
+    assert_eq!(attrs[0], FunctionAttribute::NoBuiltin);
     let f = module.get_func_by_name("f.noduplicate").unwrap();
-    assert_eq!(f.function_attributes.len(), 1);
-    assert_eq!(f.function_attributes[0], FunctionAttribute::NoDuplicate);
+    let attrs = module.get_function_attributes(f.function_attribute_group);
+    assert_eq!(attrs.len(), 1);
+    assert_eq!(attrs[0], FunctionAttribute::NoDuplicate);
     let f = module.get_func_by_name("f.noimplicitfloat").unwrap();
-    assert_eq!(f.function_attributes.len(), 1);
-    assert_eq!(f.function_attributes[0], FunctionAttribute::NoImplicitFloat);
+    let attrs = module.get_function_attributes(f.function_attribute_group);
+    assert_eq!(attrs.len(), 1);
+    assert_eq!(attrs[0], FunctionAttribute::NoImplicitFloat);
     let f = module.get_func_by_name("f.nonlazybind").unwrap();
-    assert_eq!(f.function_attributes.len(), 1);
-    assert_eq!(f.function_attributes[0], FunctionAttribute::NonLazyBind);
+    let attrs = module.get_function_attributes(f.function_attribute_group);
+    assert_eq!(attrs.len(), 1);
+    assert_eq!(attrs[0], FunctionAttribute::NonLazyBind);
     let f = module.get_func_by_name("f.noredzone").unwrap();
-    assert_eq!(f.function_attributes.len(), 1);
-    assert_eq!(f.function_attributes[0], FunctionAttribute::NoRedZone);
+    let attrs = module.get_function_attributes(f.function_attribute_group);
+    assert_eq!(attrs.len(), 1);
+    assert_eq!(attrs[0], FunctionAttribute::NoRedZone);
     let f = module.get_func_by_name("f.noreturn").unwrap();
-    assert_eq!(f.function_attributes.len(), 1);
-    assert_eq!(f.function_attributes[0], FunctionAttribute::NoReturn);
+    let attrs = module.get_function_attributes(f.function_attribute_group);
+    assert_eq!(attrs.len(), 1);
+    assert_eq!(attrs[0], FunctionAttribute::NoReturn);
     let f = module.get_func_by_name("f.nounwind").unwrap();
-    assert_eq!(f.function_attributes.len(), 1);
-    assert_eq!(f.function_attributes[0], FunctionAttribute::NoUnwind);
+    let attrs = module.get_function_attributes(f.function_attribute_group);
+    assert_eq!(attrs.len(), 1);
+    assert_eq!(attrs[0], FunctionAttribute::NoUnwind);
     let f = module.get_func_by_name("f.optnone").unwrap();
-    assert_eq!(f.function_attributes.len(), 2);
-    assert_eq!(f.function_attributes[0], FunctionAttribute::NoInline);
-    assert_eq!(f.function_attributes[1], FunctionAttribute::OptNone);
+    let attrs = module.get_function_attributes(f.function_attribute_group);
+    assert_eq!(attrs.len(), 2);
+    assert_eq!(attrs[0], FunctionAttribute::NoInline);
+    assert_eq!(attrs[1], FunctionAttribute::OptNone);
     let f = module.get_func_by_name("f.optsize").unwrap();
-    assert_eq!(f.function_attributes.len(), 1);
-    assert_eq!(f.function_attributes[0], FunctionAttribute::OptSize);
+    let attrs = module.get_function_attributes(f.function_attribute_group);
+    assert_eq!(attrs.len(), 1);
+    assert_eq!(attrs[0], FunctionAttribute::OptSize);
     let f = module.get_func_by_name("f.readnone").unwrap();
-    assert_eq!(f.function_attributes.len(), 1);
-    assert_eq!(f.function_attributes[0], FunctionAttribute::ReadNone);
+    let attrs = module.get_function_attributes(f.function_attribute_group);
+    assert_eq!(attrs.len(), 1);
+    assert_eq!(attrs[0], FunctionAttribute::ReadNone);
     let f = module.get_func_by_name("f.readonly").unwrap();
-    assert_eq!(f.function_attributes.len(), 1);
-    assert_eq!(f.function_attributes[0], FunctionAttribute::ReadOnly);
+    let attrs = module.get_function_attributes(f.function_attribute_group);
+    assert_eq!(attrs.len(), 1);
+    assert_eq!(attrs[0], FunctionAttribute::ReadOnly);
     let f = module.get_func_by_name("f.returns_twice").unwrap();
-    assert_eq!(f.function_attributes.len(), 1);
-    assert_eq!(f.function_attributes[0], FunctionAttribute::ReturnsTwice);
+    let attrs = module.get_function_attributes(f.function_attribute_group);
+    assert_eq!(attrs.len(), 1);
+    assert_eq!(attrs[0], FunctionAttribute::ReturnsTwice);
     let f = module.get_func_by_name("f.safestack").unwrap();
-    assert_eq!(f.function_attributes.len(), 1);
-    assert_eq!(f.function_attributes[0], FunctionAttribute::SafeStack);
+    let attrs = module.get_function_attributes(f.function_attribute_group);
+    assert_eq!(attrs.len(), 1);
+    assert_eq!(attrs[0], FunctionAttribute::SafeStack);
     let f = module.get_func_by_name("f.sanitize_address").unwrap();
-    assert_eq!(f.function_attributes.len(), 1);
-    assert_eq!(f.function_attributes[0], FunctionAttribute::SanitizeAddress);
+    let attrs = module.get_function_attributes(f.function_attribute_group);
+    assert_eq!(attrs.len(), 1);
+    assert_eq!(attrs[0], FunctionAttribute::SanitizeAddress);
     let f = module.get_func_by_name("f.sanitize_memory").unwrap();
-    assert_eq!(f.function_attributes.len(), 1);
-    assert_eq!(f.function_attributes[0], FunctionAttribute::SanitizeMemory);
+    let attrs = module.get_function_attributes(f.function_attribute_group);
+    assert_eq!(attrs.len(), 1);
+    assert_eq!(attrs[0], FunctionAttribute::SanitizeMemory);
     let f = module.get_func_by_name("f.sanitize_thread").unwrap();
-    assert_eq!(f.function_attributes.len(), 1);
-    assert_eq!(f.function_attributes[0], FunctionAttribute::SanitizeThread);
+    let attrs = module.get_function_attributes(f.function_attribute_group);
+    assert_eq!(attrs.len(), 1);
+    assert_eq!(attrs[0], FunctionAttribute::SanitizeThread);
     let f = module.get_func_by_name("f.ssp").unwrap();
-    assert_eq!(f.function_attributes.len(), 1);
-    assert_eq!(f.function_attributes[0], FunctionAttribute::StackProtect);
+    let attrs = module.get_function_attributes(f.function_attribute_group);
+    assert_eq!(attrs.len(), 1);
+    assert_eq!(attrs[0], FunctionAttribute::StackProtect);
     let f = module.get_func_by_name("f.sspreq").unwrap();
-    assert_eq!(f.function_attributes.len(), 1);
-    assert_eq!(f.function_attributes[0], FunctionAttribute::StackProtectReq);
+    let attrs = module.get_function_attributes(f.function_attribute_group);
+    assert_eq!(attrs.len(), 1);
+    assert_eq!(attrs[0], FunctionAttribute::StackProtectReq);
     let f = module.get_func_by_name("f.sspstrong").unwrap();
-    assert_eq!(f.function_attributes.len(), 1);
-    assert_eq!(
-        f.function_attributes[0],
-        FunctionAttribute::StackProtectStrong
-    );
+    let attrs = module.get_function_attributes(f.function_attribute_group);
+    assert_eq!(attrs.len(), 1);
+    assert_eq!(attrs[0], FunctionAttribute::StackProtectStrong);
     let f = module.get_func_by_name("f.thunk").unwrap();
-    assert_eq!(f.function_attributes.len(), 1);
+    let attrs = module.get_function_attributes(f.function_attribute_group);
+    assert_eq!(attrs.len(), 1);
     assert_eq!(
-        f.function_attributes[0],
+        attrs[0],
         FunctionAttribute::StringAttribute {
             kind: "thunk".into(),
             value: "".into()
         }
     );
     let f = module.get_func_by_name("f.uwtable").unwrap();
-    assert_eq!(f.function_attributes.len(), 1);
-    assert_eq!(f.function_attributes[0], FunctionAttribute::UWTable);
+    let attrs = module.get_function_attributes(f.function_attribute_group);
+    assert_eq!(attrs.len(), 1);
+    assert_eq!(attrs[0], FunctionAttribute::UWTable(UWTableKind::Async));
     let f = module.get_func_by_name("f.kvpair").unwrap();
-    assert_eq!(f.function_attributes.len(), 1);
+    let attrs = module.get_function_attributes(f.function_attribute_group);
+    assert_eq!(attrs.len(), 1);
     assert_eq!(
-        f.function_attributes[0],
+        attrs[0],
         FunctionAttribute::StringAttribute {
             kind: "cpu".into(),
             value: "cortex-a8".into()
         }
     );
     let f = module.get_func_by_name("f.norecurse").unwrap();
-    assert_eq!(f.function_attributes.len(), 1);
-    assert_eq!(f.function_attributes[0], FunctionAttribute::NoRecurse);
+    let attrs = module.get_function_attributes(f.function_attribute_group);
+    assert_eq!(attrs.len(), 1);
+    assert_eq!(attrs[0], FunctionAttribute::NoRecurse);
     let f = module.get_func_by_name("f.inaccessiblememonly").unwrap();
-    assert_eq!(f.function_attributes.len(), 1);
-    assert_eq!(
-        f.function_attributes[0],
-        FunctionAttribute::InaccessibleMemOnly
-    );
+    let attrs = module.get_function_attributes(f.function_attribute_group);
+    assert_eq!(attrs.len(), 1);
+    assert_eq!(attrs[0], FunctionAttribute::InaccessibleMemOnly);
     let f = module
         .get_func_by_name("f.inaccessiblemem_or_argmemonly")
         .unwrap();
-    assert_eq!(f.function_attributes.len(), 1);
-    assert_eq!(
-        f.function_attributes[0],
-        FunctionAttribute::InaccessibleMemOrArgMemOnly
-    );
+    let attrs = module.get_function_attributes(f.function_attribute_group);
+    assert_eq!(attrs.len(), 1);
+    assert_eq!(attrs[0], FunctionAttribute::InaccessibleMemOrArgMemOnly);
     let f = module.get_func_by_name("f.strictfp").unwrap();
-    assert_eq!(f.function_attributes.len(), 1);
-    assert_eq!(f.function_attributes[0], FunctionAttribute::StrictFP);
+    let attrs = module.get_function_attributes(f.function_attribute_group);
+    assert_eq!(attrs.len(), 1);
+    assert_eq!(attrs[0], FunctionAttribute::StrictFP);
+
+    // Functions share a `FunctionAttributeGroup` exactly when they have the same
+    // function attributes
+    for (i, group) in module.function_attribute_groups.iter().enumerate() {
+        assert_eq!(group.group_id, i);
+        assert!(!group.attrs.is_empty());
+    }
+    for f in &module.functions {
+        for g in &module.functions {
+            assert_eq!(
+                f.function_attribute_group == g.function_attribute_group,
+                module.get_function_attributes(f.function_attribute_group)
+                    == module.get_function_attributes(g.function_attribute_group),
+                "{} and {}",
+                f.name,
+                g.name,
+            );
+        }
+    }
+    let f = module.get_func_by_name("f.zeroext").unwrap();
+    assert_eq!(f.function_attribute_group, None);
+    assert!(module
+        .get_function_attributes(f.function_attribute_group)
+        .is_empty());
 }
 
 #[cfg(feature = "llvm-11-or-greater")]
@@ -2351,6 +2426,997 @@ fn float_types() {
     );
 }
 
+#[cfg(feature = "llvm-14-or-greater")]
+#[test]
+fn comdats() {
+    init_logging();
+    let path = llvm_bc_dir().join("comdats.ll.bc");
+    let module = Module::from_bc_path(&path).expect("Failed to parse module");
+    assert_eq!(module.comdats.len(), 4);
+
+    let f = module.get_comdat_by_name("f").unwrap();
+    assert_eq!(f.comdat.selection_kind, SelectionKind::Any);
+    assert_eq!(f.functions, vec!["f".to_owned(), "f.helper".to_owned()]);
+    assert!(f.global_vars.is_empty());
+    let func = module.get_func_by_name("f.helper").unwrap();
+    assert_eq!(func.comdat.as_ref(), Some(&f.comdat));
+
+    let data = module.get_comdat_by_name("data").unwrap();
+    assert_eq!(data.comdat.selection_kind, SelectionKind::Largest);
+    assert!(data.functions.is_empty());
+    assert_eq!(
+        data.global_vars,
+        vec!["data".to_owned(), "data.extra".to_owned()]
+    );
+
+    let odd = module.get_comdat_by_name("odd name").unwrap();
+    assert_eq!(odd.comdat.selection_kind, SelectionKind::NoDuplicates);
+    assert_eq!(odd.functions, vec!["in.odd.name".to_owned()]);
+    assert_eq!(odd.global_vars, vec!["quoted".to_owned()]);
+
+    // `@g`'s section name mentions another comdat, but that's not its comdat
+    let g = module.get_comdat_by_name("g").unwrap();
+    assert_eq!(g.comdat.selection_kind, SelectionKind::ExactMatch);
+    assert_eq!(g.global_vars, vec!["g".to_owned()]);
+    assert!(module.get_comdat_by_name("h").is_none());
+    assert!(module
+        .get_func_by_name("no_comdat")
+        .unwrap()
+        .comdat
+        .is_none());
+}
+
+#[cfg(feature = "llvm-14-or-greater")]
+#[test]
+fn ifuncs() {
+    init_logging();
+    let path = llvm_bc_dir().join("ifuncs.ll.bc");
+    let module = Module::from_bc_path(&path).expect("Failed to parse module");
+    assert_eq!(module.global_ifuncs.len(), 2);
+
+    let foo = module.get_global_ifunc_by_name("foo").unwrap();
+    assert_eq!(foo.linkage, Linkage::External);
+    assert_eq!(foo.visibility, Visibility::Default);
+    let foo_ty = module
+        .types
+        .func_type(module.types.i32(), vec![module.types.i32()], false);
+    assert_eq!(foo.value_type, foo_ty);
+    assert_eq!(foo.ty, module.types.pointer_to(foo_ty.clone()));
+    assert_eq!(foo.addr_space, 0);
+    match foo.resolver.as_ref() {
+        Constant::GlobalReference { name, .. } => assert_eq!(name, "resolve_foo"),
+        c => panic!("Expected resolver to be a GlobalReference, got {:?}", c),
+    }
+    let bar = module.get_global_ifunc_by_name("bar").unwrap();
+    assert_eq!(bar.linkage, Linkage::Internal);
+    assert!(module.get_global_ifunc_by_name("resolve_foo").is_none());
+
+    // references to ifuncs resolve to the `GlobalIFunc`, just like references
+    // to aliases
+    let var = module.get_global_var_by_name("foo_ptr").unwrap();
+    match var.initializer.as_ref().map(|c| c.as_ref()) {
+        Some(Constant::GlobalReference { name, ty }) => {
+            assert_eq!(name, "foo");
+            assert_eq!(ty, &foo_ty);
+            assert!(module.get_global_ifunc_by_name(name).is_some());
+        },
+        c => panic!("Expected initializer to be a GlobalReference, got {:?}", c),
+    }
+    let func = module.get_func_by_name("call_foo").unwrap();
+    let call: &instruction::Call = &func.basic_blocks[0].instrs[0]
+        .clone()
+        .try_into()
+        .expect("Should be a call");
+    match &call.function {
+        Either::Right(Operand::ConstantOperand(cref)) => match cref.as_ref() {
+            Constant::GlobalReference { name, .. } => assert_eq!(name, "foo"),
+            c => panic!("Expected callee to be a GlobalReference, got {:?}", c),
+        },
+        f => panic!("Expected callee to be a constant, got {:?}", f),
+    }
+}
+
+#[cfg(feature = "llvm-14-or-greater")]
+#[test]
+fn global_properties() {
+    init_logging();
+    let path = llvm_bc_dir().join("global_properties.ll.bc");
+    let module = Module::from_bc_path(&path).expect("Failed to parse module");
+
+    let var = module.get_global_var_by_name("plain").unwrap();
+    assert!(!var.externally_initialized);
+    assert_eq!(var.partition, None);
+    assert!(var.attributes.is_empty());
+    assert!(var.metadata.is_empty());
+
+    let var = module.get_global_var_by_name("ext_init").unwrap();
+    assert!(var.externally_initialized);
+
+    let var = module.get_global_var_by_name("in_partition").unwrap();
+    assert_eq!(var.partition.as_deref(), Some("part1"));
+
+    let var = module.get_global_var_by_name("with_attrs").unwrap();
+    assert_eq!(
+        var.attributes,
+        vec![
+            ("bss-section".to_owned(), "my_bss".to_owned()),
+            ("rodata-section".to_owned(), "my_rodata".to_owned()),
+        ]
+    );
+
+    let type_md = MetadataAttachment {
+        kind: "type".to_owned(),
+        node: AttachedMetadata::Tuple(vec![
+            Some(AttachedMetadata::Constant(ConstantRef::new(
+                Constant::Int { bits: 64, value: 0 },
+            ))),
+            Some(AttachedMetadata::String("typeid".to_owned())),
+        ]),
+    };
+    let var = module.get_global_var_by_name("with_md").unwrap();
+    assert_eq!(
+        var.metadata,
+        vec![
+            type_md.clone(),
+            MetadataAttachment {
+                kind: "custom".to_owned(),
+                node: AttachedMetadata::Tuple(vec![Some(AttachedMetadata::String(
+                    "custom node".to_owned()
+                ))]),
+            },
+        ]
+    );
+
+    let var = module.get_global_var_by_name("everything").unwrap();
+    assert!(var.externally_initialized);
+    assert_eq!(var.section.as_deref(), Some("data"));
+    assert_eq!(var.partition.as_deref(), Some("part2"));
+    assert_eq!(var.alignment, 4);
+    assert_eq!(
+        var.attributes,
+        vec![
+            ("data-section".to_owned(), "my_data".to_owned()),
+            ("no-value".to_owned(), String::new()),
+        ]
+    );
+    assert_eq!(var.metadata, vec![type_md]);
+}
+
+#[cfg(feature = "llvm-14-or-greater")]
+#[test]
+fn function_properties() {
+    init_logging();
+    let path = llvm_bc_dir().join("function_properties.ll.bc");
+    let module = Module::from_bc_path(&path).expect("Failed to parse module");
+
+    let f = module.get_func_by_name("plain").unwrap();
+    assert_eq!(f.prefix, None);
+    assert_eq!(f.prologue, None);
+    assert!(f.metadata.is_empty());
+
+    let f = module.get_func_by_name("with_prefix").unwrap();
+    assert_eq!(
+        f.prefix,
+        Some(ConstantRef::new(Constant::Int {
+            bits: 32,
+            value: 123
+        }))
+    );
+    assert_eq!(f.prologue, None);
+
+    let f = module.get_func_by_name("with_prologue").unwrap();
+    assert_eq!(f.prefix, None);
+    assert_eq!(
+        f.prologue,
+        Some(ConstantRef::new(Constant::Int {
+            bits: 8,
+            value: 144
+        }))
+    );
+
+    let type_md = MetadataAttachment {
+        kind: "type".to_owned(),
+        node: AttachedMetadata::Tuple(vec![
+            Some(AttachedMetadata::Constant(ConstantRef::new(
+                Constant::Int { bits: 64, value: 0 },
+            ))),
+            Some(AttachedMetadata::String("typeid".to_owned())),
+        ]),
+    };
+    let f = module.get_func_by_name("everything").unwrap();
+    assert_eq!(
+        f.prefix,
+        Some(ConstantRef::new(Constant::Int { bits: 32, value: 7 }))
+    );
+    assert_eq!(
+        f.prologue,
+        Some(ConstantRef::new(Constant::Int {
+            bits: 8,
+            value: 144
+        }))
+    );
+    assert!(f.personality_function.is_some());
+    assert_eq!(
+        f.metadata,
+        vec![
+            MetadataAttachment {
+                kind: "prof".to_owned(),
+                node: AttachedMetadata::Tuple(vec![
+                    Some(AttachedMetadata::String("function_entry_count".to_owned())),
+                    Some(AttachedMetadata::Constant(ConstantRef::new(
+                        Constant::Int {
+                            bits: 64,
+                            value: 42
+                        },
+                    ))),
+                ]),
+            },
+            type_md.clone(),
+        ]
+    );
+
+    let f = module.get_func_by_name("with_md").unwrap();
+    assert_eq!(f.prefix, None);
+    assert_eq!(f.prologue, None);
+    assert_eq!(f.metadata, vec![type_md]);
+}
+
+#[cfg(feature = "llvm-14-or-greater")]
+#[test]
+fn modern_attributes() {
+    init_logging();
+    let path = llvm_bc_dir().join("modern_attributes.ll.bc");
+    let module = Module::from_bc_path(&path).expect("Failed to parse module");
+
+    let f = module.get_func_by_name("progress").unwrap();
+    let attrs = module.get_function_attributes(f.function_attribute_group);
+    assert_eq!(attrs.len(), 6);
+    assert!(attrs.contains(&FunctionAttribute::MustProgress));
+    assert!(attrs.contains(&FunctionAttribute::Hot));
+    assert!(attrs.contains(&FunctionAttribute::NoCallback));
+    assert!(attrs.contains(&FunctionAttribute::NoProfile));
+    assert!(attrs.contains(&FunctionAttribute::NoSanitizeCoverage));
+    assert!(attrs.contains(&FunctionAttribute::DisableSanitizerInstrumentation));
+
+    let f = module.get_func_by_name("vscale_bounded").unwrap();
+    assert_eq!(
+        module.get_function_attributes(f.function_attribute_group),
+        vec![FunctionAttribute::VScaleRange {
+            min: 1,
+            max: Some(16)
+        }]
+    );
+    let f = module.get_func_by_name("vscale_unbounded").unwrap();
+    assert_eq!(
+        module.get_function_attributes(f.function_attribute_group),
+        vec![FunctionAttribute::VScaleRange { min: 2, max: None }]
+    );
+
+    let f = module.get_func_by_name("params").unwrap();
+    assert_eq!(
+        f.parameters[0].attributes,
+        vec![ParameterAttribute::ReadOnly]
+    );
+    assert_eq!(
+        f.parameters[1].attributes,
+        vec![ParameterAttribute::ReadNone]
+    );
+    assert_eq!(
+        f.parameters[2].attributes,
+        vec![ParameterAttribute::WriteOnly]
+    );
+    assert_eq!(
+        f.parameters[3].attributes,
+        vec![ParameterAttribute::ByRef(Some(module.types.i32()))]
+    );
+    assert_eq!(
+        f.parameters[4].attributes,
+        vec![ParameterAttribute::SwiftAsync]
+    );
+}
+
+#[cfg(feature = "llvm-14-or-greater")]
+#[test]
+fn struct_constants() {
+    init_logging();
+    let path = llvm_bc_dir().join("struct_constants.ll.bc");
+    let module = Module::from_bc_path(&path).expect("Failed to parse module");
+
+    let var = module.get_global_var_by_name("config").unwrap();
+    let init = var.initializer.as_ref().unwrap();
+    match init.as_ref() {
+        Constant::Struct {
+            name,
+            values,
+            is_packed,
+        } => {
+            assert_eq!(name.as_deref(), Some("struct.Config"));
+            assert_eq!(values.len(), 2);
+            assert!(!is_packed);
+        },
+        c => panic!("Expected a Constant::Struct, got {:?}", c),
+    }
+    assert_eq!(
+        module.type_of(init),
+        module.types.named_struct("struct.Config")
+    );
+
+    let var = module.get_global_var_by_name("packed").unwrap();
+    let init = var.initializer.as_ref().unwrap();
+    match init.as_ref() {
+        Constant::Struct {
+            name, is_packed, ..
+        } => {
+            assert_eq!(name.as_deref(), Some("struct.Packed"));
+            assert!(is_packed);
+        },
+        c => panic!("Expected a Constant::Struct, got {:?}", c),
+    }
+
+    let var = module.get_global_var_by_name("literal").unwrap();
+    let init = var.initializer.as_ref().unwrap();
+    match init.as_ref() {
+        Constant::Struct { name, .. } => assert_eq!(name, &None),
+        c => panic!("Expected a Constant::Struct, got {:?}", c),
+    }
+    assert_eq!(
+        module.type_of(init),
+        module
+            .types
+            .struct_of(vec![module.types.i32(), module.types.i32()], false)
+    );
+}
+
+#[cfg(feature = "llvm-14-or-greater")]
+#[test]
+fn intrinsics() {
+    use llvm_ir::intrinsic::{DbgKind, OverflowOp};
+    use llvm_ir::Intrinsic;
+    init_logging();
+    let path = llvm_bc_dir().join("intrinsics.ll.bc");
+    let module = Module::from_bc_path(&path).expect("Failed to parse module");
+    let types = &module.types;
+
+    let f = module.get_func_by_name("intrinsics").unwrap();
+    let calls: Vec<&instruction::Call> = f.basic_blocks[0]
+        .instrs
+        .iter()
+        .filter_map(|inst| match inst {
+            Instruction::Call(call) => Some(call),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(calls.len(), 18);
+    let a = Operand::LocalOperand {
+        name: Name::from("a"),
+        ty: types.pointer_to(types.i8()),
+    };
+    let b = Operand::LocalOperand {
+        name: Name::from("b"),
+        ty: types.pointer_to(types.i8()),
+    };
+
+    match calls[0].as_intrinsic() {
+        Some(Intrinsic::MemCpy {
+            dest,
+            src,
+            len,
+            is_volatile,
+        }) => {
+            assert_eq!(dest, &a);
+            assert_eq!(src, &b);
+            assert_eq!(
+                len,
+                &Operand::LocalOperand {
+                    name: Name::from("n"),
+                    ty: types.i64()
+                }
+            );
+            assert!(!is_volatile);
+        },
+        i => panic!("Expected a MemCpy, got {:?}", i),
+    }
+    match calls[1].as_intrinsic() {
+        Some(Intrinsic::MemMove {
+            len, is_volatile, ..
+        }) => {
+            assert_eq!(
+                len,
+                &Operand::ConstantOperand(ConstantRef::new(Constant::Int {
+                    bits: 64,
+                    value: 16
+                }))
+            );
+            assert!(is_volatile);
+        },
+        i => panic!("Expected a MemMove, got {:?}", i),
+    }
+    match calls[2].as_intrinsic() {
+        Some(Intrinsic::MemSet { dest, .. }) => assert_eq!(dest, &a),
+        i => panic!("Expected a MemSet, got {:?}", i),
+    }
+    assert_eq!(
+        calls[3].as_intrinsic(),
+        Some(Intrinsic::LifetimeStart {
+            size: Some(8),
+            ptr: &a
+        })
+    );
+    assert_eq!(
+        calls[4].as_intrinsic(),
+        Some(Intrinsic::LifetimeEnd {
+            size: None,
+            ptr: &a
+        })
+    );
+    assert_eq!(
+        calls[5].as_intrinsic(),
+        Some(Intrinsic::Dbg(DbgKind::Value))
+    );
+    match calls[6].as_intrinsic() {
+        Some(Intrinsic::WithOverflow { op, ty, .. }) => {
+            assert_eq!(op, OverflowOp::SAdd);
+            assert_eq!(ty, types.i32());
+        },
+        i => panic!("Expected a WithOverflow, got {:?}", i),
+    }
+    match calls[7].as_intrinsic() {
+        Some(Intrinsic::WithOverflow { op, ty, .. }) => {
+            assert_eq!(op, OverflowOp::UMul);
+            assert_eq!(ty, types.i64());
+        },
+        i => panic!("Expected a WithOverflow, got {:?}", i),
+    }
+    match calls[8].as_intrinsic() {
+        Some(Intrinsic::Constrained {
+            op,
+            operands,
+            result_ty,
+        }) => {
+            assert_eq!(op, "fadd");
+            assert_eq!(operands.len(), 2);
+            assert_eq!(result_ty, types.single());
+        },
+        i => panic!("Expected a Constrained, got {:?}", i),
+    }
+    match calls[9].as_intrinsic() {
+        Some(Intrinsic::Assume { .. }) => {},
+        i => panic!("Expected an Assume, got {:?}", i),
+    }
+    match calls[10].as_intrinsic() {
+        Some(Intrinsic::Expect { ty, .. }) => assert_eq!(ty, types.i64()),
+        i => panic!("Expected an Expect, got {:?}", i),
+    }
+    assert_eq!(calls[11].as_intrinsic(), Some(Intrinsic::StackSave));
+    match calls[12].as_intrinsic() {
+        Some(Intrinsic::StackRestore { .. }) => {},
+        i => panic!("Expected a StackRestore, got {:?}", i),
+    }
+    assert_eq!(
+        calls[13].as_intrinsic(),
+        Some(Intrinsic::Other {
+            name: "llvm.ctpop.i32"
+        })
+    );
+    assert_eq!(calls[14].as_intrinsic(), None);
+    assert_eq!(calls[15].as_intrinsic(), Some(Intrinsic::Trap));
+    // intrinsics whose names merely start with that of a known one
+    assert_eq!(
+        calls[16].as_intrinsic(),
+        Some(Intrinsic::Other {
+            name: "llvm.memcpy.element.unordered.atomic.p0i8.p0i8.i64"
+        })
+    );
+    assert_eq!(
+        calls[17].as_intrinsic(),
+        Some(Intrinsic::Other {
+            name: "llvm.expect.with.probability.i64"
+        })
+    );
+}
+
+#[cfg(feature = "llvm-14-or-greater")]
+#[test]
+fn side_effects() {
+    init_logging();
+    let path = llvm_bc_dir().join("side_effects.ll.bc");
+    let module = Module::from_bc_path(&path).expect("Failed to parse module");
+
+    let functions = module.function_index();
+    let f = functions.get_func_by_name("side_effects").unwrap();
+    let instrs = &f.basic_blocks[0].instrs;
+    // for each instruction: may read, may write, may throw, may have side effects, trivially dead
+    let expected = [
+        (false, false, false, false, true), // add
+        (false, false, false, false, true), // alloca
+        (true, false, false, false, true),  // load
+        (true, true, false, true, false),   // load volatile
+        (true, true, false, true, false),   // load atomic seq_cst
+        (true, false, false, false, true),  // load atomic unordered
+        (false, true, false, true, false),  // store
+        (true, true, false, true, false),   // fence
+        (false, false, false, false, true), // call @pure
+        (true, false, false, false, true),  // call @reads
+        (false, true, false, true, false),  // call @writes
+        (false, false, false, true, false), // call @may_loop
+        (true, true, true, true, false),    // call @unknown
+        (false, false, false, false, true), // call @unknown with call-site attributes
+        (false, true, false, true, false),  // call @llvm.memset
+        (false, false, false, false, true), // call @defined_pure
+    ];
+    assert_eq!(instrs.len(), expected.len());
+    for (inst, &(reads, writes, throws, side_effects, dead)) in instrs.iter().zip(expected.iter()) {
+        assert_eq!(inst.may_read_memory(&functions), reads, "{}", inst);
+        assert_eq!(inst.may_write_memory(&functions), writes, "{}", inst);
+        assert_eq!(inst.may_throw(&functions), throws, "{}", inst);
+        assert_eq!(
+            inst.may_have_side_effects(&functions),
+            side_effects,
+            "{}",
+            inst
+        );
+        assert_eq!(inst.is_trivially_dead(&functions), dead, "{}", inst);
+    }
+}
+
+#[cfg(feature = "llvm-14-or-greater")]
+#[test]
+fn interpreter() {
+    use llvm_ir::interpreter::{InterpreterError, Value};
+    use llvm_ir::Interpreter;
+    init_logging();
+    let path = llvm_bc_dir().join("interpreter.ll.bc");
+    let module = Module::from_bc_path(&path).expect("Failed to parse module");
+    let mut interp = Interpreter::new(&module).expect("Failed to create interpreter");
+    let i32 = |value: i32| Value::int(32, value as u128);
+    let i64 = |value: i64| Value::int(64, value as u128);
+
+    assert_eq!(interp.call("factorial", &[i32(5)]), Ok(Some(i32(120))));
+    assert_eq!(interp.call("fib", &[i32(10)]), Ok(Some(i32(55))));
+    assert_eq!(
+        interp.call("point_sum", &[i32(-5), i64(100)]),
+        Ok(Some(i64(95)))
+    );
+    assert_eq!(
+        interp.call("lookup", &[i32(2)]),
+        Ok(Some(Value::int(16, 3)))
+    );
+    assert_eq!(
+        interp.call("lookup", &[i32(4)]),
+        Ok(Some(Value::int(16, 0xffff)))
+    );
+    match interp.call("lookup_unchecked", &[i64(4)]) {
+        Err(InterpreterError::OutOfBounds { size: 2, .. }) => {},
+        result => panic!("Expected an out-of-bounds error, got {:?}", result),
+    }
+    assert_eq!(interp.call("divide", &[i32(-7), i32(2)]), Ok(Some(i32(-3))));
+    assert_eq!(
+        interp.call("divide", &[i32(7), i32(0)]),
+        Err(InterpreterError::DivisionByZero)
+    );
+    assert_eq!(
+        interp.call("divide", &[i32(i32::MIN), i32(-1)]),
+        Err(InterpreterError::DivisionOverflow)
+    );
+
+    // globals are initialized, and keep their values between calls
+    let counter = interp.global_address("counter").unwrap();
+    assert_eq!(interp.load(counter, &module.types.i32()), Ok(i32(10)));
+    assert_eq!(interp.call("bump", &[i32(5)]), Ok(None));
+    assert_eq!(interp.call("bump", &[i32(-1)]), Ok(None));
+    assert_eq!(interp.load(counter, &module.types.i32()), Ok(i32(14)));
+
+    // declared functions go to registered closures
+    assert_eq!(
+        interp.call("call_host", &[i32(10)]),
+        Err(InterpreterError::UnknownFunction("host_add".into()))
+    );
+    interp.register_external("host_add", |_, args| {
+        let sum = args[0].as_u128().unwrap() + args[1].as_u128().unwrap();
+        Ok(Some(Value::int(32, sum)))
+    });
+    interp.register_external("host_strlen", |memory, args| {
+        let string = memory.read_c_string(args[0].as_pointer().unwrap())?;
+        Ok(Some(Value::int(64, string.len() as u128)))
+    });
+    assert_eq!(interp.call("call_host", &[i32(10)]), Ok(Some(i32(16))));
+
+    assert_eq!(
+        interp.call("sum_of_squares", &[Value::Single(3.0), Value::Double(4.0)]),
+        Ok(Some(Value::Double(25.0)))
+    );
+    assert_eq!(
+        interp.call("float_bits", &[Value::Single(1.0)]),
+        Ok(Some(i32(0x3f80_0000)))
+    );
+    let vector = |values: [i32; 4]| Value::Vector(values.iter().map(|&v| i32(v)).collect());
+    assert_eq!(
+        interp.call("dot", &[vector([1, 2, 3, 4]), vector([5, 6, 7, 8])]),
+        Ok(Some(i32(70)))
+    );
+    assert_eq!(
+        interp.call("copy_and_add_max", &[i32(0)]),
+        Ok(Some(i32(i32::MAX)))
+    );
+    assert_eq!(
+        interp.call("copy_and_add_max", &[i32(1)]),
+        Ok(Some(i32(-1)))
+    );
+    // calls don't use the native stack, so deep recursion is fine, and
+    // unbounded recursion is an error rather than a crash
+    assert!(interp.call("factorial", &[i32(5000)]).is_ok());
+    assert_eq!(
+        interp.call("recurse_forever", &[]),
+        Err(InterpreterError::StackOverflow)
+    );
+    assert_eq!(
+        interp.call("factorial", &[]),
+        Err(InterpreterError::WrongNumberOfArguments {
+            function: "factorial".into(),
+            expected: 1,
+            got: 0
+        })
+    );
+
+    // sizes are checked before anything is allocated
+    assert_eq!(
+        interp.call("dynamic_alloca", &[i64(-1)]),
+        Err(InterpreterError::OutOfMemory)
+    );
+    assert_eq!(
+        interp.call("dynamic_alloca", &[i64(1 << 40)]),
+        Err(InterpreterError::OutOfMemory)
+    );
+    match interp.call("memset_alloca", &[i64(1 << 40)]) {
+        Err(InterpreterError::OutOfBounds { size, .. }) => assert_eq!(size, 1 << 40),
+        result => panic!("Expected an out-of-bounds error, got {:?}", result),
+    }
+    assert_eq!(interp.call("memset_alloca", &[i64(1)]), Ok(None));
+    // freed memory doesn't count towards the limit
+    for _ in 0 .. 8 {
+        assert_eq!(interp.call("dynamic_alloca", &[i64(1 << 28)]), Ok(None));
+        let memory = interp.memory_mut();
+        let address = memory.allocate(1 << 28, 8).expect("Failed to allocate");
+        memory.free(address).expect("Failed to free");
+    }
+
+    match interp.call("use_after_return", &[]) {
+        Err(InterpreterError::UseAfterFree { .. }) => {},
+        result => panic!("Expected a use-after-free error, got {:?}", result),
+    }
+    assert_eq!(
+        interp.call("unreachable_fn", &[]),
+        Err(InterpreterError::ReachedUnreachable)
+    );
+}
+
+#[cfg(feature = "llvm-14-or-greater")]
+#[test]
+fn dataflow() {
+    use llvm_ir::dataflow::{
+        analyze,
+        Analysis,
+        Direction,
+        Edge,
+        EdgeKind,
+        Liveness,
+        ReachingDefinitions,
+    };
+    use llvm_ir::Function;
+    use std::collections::HashSet;
+    init_logging();
+    let path = llvm_bc_dir().join("dataflow.ll.bc");
+    let module = Module::from_bc_path(&path).expect("Failed to parse module");
+    let names =
+        |names: &[&str]| -> HashSet<Name> { names.iter().map(|&n| Name::from(n)).collect() };
+    let owned =
+        |set: &HashSet<&Name>| -> HashSet<Name> { set.iter().map(|&n| n.clone()).collect() };
+
+    let f = module.get_func_by_name("loop").unwrap();
+    let live = analyze(f, Liveness);
+    assert_eq!(owned(live.block_entry(&Name::from("entry"))), names(&["n"]));
+    // the phis' incoming values from %body are live only on that edge
+    assert_eq!(
+        owned(live.block_entry(&Name::from("header"))),
+        names(&["i", "sum", "n"])
+    );
+    assert_eq!(
+        owned(live.block_exit(&Name::from("body"))),
+        names(&["i.next", "sum.next", "n"])
+    );
+    assert_eq!(
+        owned(live.block_entry(&Name::from("exit"))),
+        names(&["sum"])
+    );
+    assert_eq!(
+        owned(&live.before(&Name::from("body"), 1)),
+        names(&["i", "sum.next", "n"])
+    );
+    assert_eq!(
+        owned(&live.after(&Name::from("body"), 1)),
+        names(&["i.next", "sum.next", "n"])
+    );
+
+    let reaching = analyze(f, ReachingDefinitions);
+    assert_eq!(
+        owned(reaching.block_exit(&Name::from("entry"))),
+        names(&["n", "unused"])
+    );
+    assert_eq!(
+        owned(reaching.block_entry(&Name::from("header"))),
+        names(&["n", "unused", "i", "sum", "done", "sum.next", "i.next"])
+    );
+
+    /// Which blocks are reachable, given that branches on constants only go
+    /// one way
+    struct Feasibility;
+    impl<'a> Analysis<'a> for Feasibility {
+        type Domain = bool;
+        fn direction(&self) -> Direction {
+            Direction::Forward
+        }
+        fn boundary(&self, _func: &'a Function) -> bool {
+            true
+        }
+        fn bottom(&self, _func: &'a Function) -> bool {
+            false
+        }
+        fn transfer_instruction(&self, _inst: &'a Instruction, _state: &mut bool) {}
+        fn transfer_edge(&self, edge: &Edge<'a>, state: &mut bool) {
+            let constant = |op: &Operand| match op {
+                Operand::ConstantOperand(c) => Some(c.clone()),
+                _ => None,
+            };
+            let infeasible = match &edge.kind {
+                EdgeKind::Branch { condition, taken } => constant(condition).map_or(false, |c| {
+                    *c != Constant::Int {
+                        bits: 1,
+                        value: *taken as u64,
+                    }
+                }),
+                EdgeKind::SwitchCase { operand, value } => {
+                    constant(operand).map_or(false, |c| &c != *value)
+                },
+                EdgeKind::SwitchDefault {
+                    operand,
+                    case_values,
+                } => constant(operand).map_or(false, |c| case_values.contains(&&c)),
+                _ => false,
+            };
+            if infeasible {
+                *state = false;
+            }
+        }
+    }
+    let f = module.get_func_by_name("constant_branches").unwrap();
+    let feasible = analyze(f, Feasibility);
+    for (block, expected) in &[
+        ("entry", true),
+        ("live", true),
+        ("dead", false),
+        ("dead2", false),
+        ("dead3", false),
+        ("ret", true),
+    ] {
+        assert_eq!(
+            *feasible.block_entry(&Name::from(*block)),
+            *expected,
+            "{}",
+            block
+        );
+    }
+    // %x is live at the exit of %live only, since it only flows into the phi from there
+    let live = analyze(f, Liveness);
+    assert_eq!(owned(live.block_exit(&Name::from("live"))), names(&["x"]));
+    assert!(live.block_exit(&Name::from("dead2")).is_empty());
+}
+
+#[cfg(feature = "llvm-14-or-greater")]
+#[test]
+fn call_site_flags() {
+    use llvm_ir::instruction::TailCallKind;
+    init_logging();
+    let path = llvm_bc_dir().join("call_site_flags.ll.bc");
+    let module = Module::from_bc_path(&path).expect("Failed to parse module");
+
+    let f = module.get_func_by_name("tail_calls").unwrap();
+    let instrs = &f.basic_blocks[0].instrs;
+    let a: &instruction::Call = &instrs[0].clone().try_into().expect("Should be a call");
+    assert_eq!(a.tail_call_kind, Some(TailCallKind::Tail));
+    assert!(a.fast_math_flags.is_fast());
+    assert!(a.operand_bundles.is_empty());
+    assert_eq!(&format!("{}", a), "%a = tail call fast @f(float %x)");
+    let b: &instruction::Call = &instrs[1].clone().try_into().expect("Should be a call");
+    assert_eq!(b.tail_call_kind, Some(TailCallKind::NoTail));
+    assert!(b.fast_math_flags.no_NaNs);
+    assert!(b.fast_math_flags.no_Infs);
+    assert!(!b.fast_math_flags.allow_reassoc);
+    assert!(!b.fast_math_flags.is_fast());
+    let c: &instruction::Call = &instrs[2].clone().try_into().expect("Should be a call");
+    assert_eq!(c.tail_call_kind, None);
+    assert!(c.fast_math_flags.is_empty());
+    assert_eq!(c.arguments.len(), 1);
+    assert_eq!(c.operand_bundles.len(), 3);
+    assert_eq!(c.operand_bundles[0].tag, "deopt");
+    assert_eq!(
+        c.operand_bundles[0].inputs,
+        vec![
+            Operand::ConstantOperand(ConstantRef::new(Constant::Int { bits: 32, value: 1 })),
+            Operand::ConstantOperand(ConstantRef::new(Constant::Int { bits: 64, value: 2 })),
+        ]
+    );
+    assert_eq!(c.operand_bundles[1].tag, "gc-live");
+    assert_eq!(
+        c.operand_bundles[1].inputs,
+        vec![Operand::LocalOperand {
+            name: Name::from("a"),
+            ty: module.types.fp(FPType::Single),
+        }]
+    );
+    assert_eq!(c.operand_bundles[2].tag, "custom");
+    assert!(c.operand_bundles[2].inputs.is_empty());
+    assert_eq!(
+        &format!("{}", c),
+        "%c = call @f(float %b) [ \"deopt\"(i32 1, i64 2), \"gc-live\"(float %a), \"custom\"() ]"
+    );
+    let d: &instruction::Call = &instrs[3].clone().try_into().expect("Should be a call");
+    assert_eq!(d.tail_call_kind, Some(TailCallKind::MustTail));
+
+    let f = module.get_func_by_name("invoke_bundles").unwrap();
+    let invoke: &terminator::Invoke = &f.basic_blocks[0]
+        .term
+        .clone()
+        .try_into()
+        .expect("Should be an invoke");
+    assert_eq!(invoke.arguments.len(), 1);
+    assert_eq!(invoke.operand_bundles.len(), 1);
+    assert_eq!(invoke.operand_bundles[0].tag, "deopt");
+    assert_eq!(
+        invoke.operand_bundles[0].inputs,
+        vec![Operand::ConstantOperand(ConstantRef::new(Constant::Int {
+            bits: 32,
+            value: 7
+        }))]
+    );
+    let call: &instruction::Call = &f.basic_blocks[2].instrs[1]
+        .clone()
+        .try_into()
+        .expect("Should be a call");
+    assert_eq!(
+        module.get_function_attributes(call.function_attribute_group),
+        vec![FunctionAttribute::NoUnwind]
+    );
+    assert_eq!(call.operand_bundles.len(), 1);
+    assert_eq!(call.operand_bundles[0].tag, "funclet");
+    assert_eq!(
+        call.operand_bundles[0].inputs,
+        vec![Operand::LocalOperand {
+            name: Name::from("cp"),
+            ty: module.types.token_type(),
+        }]
+    );
+}
+
+#[cfg(feature = "llvm-14-or-greater")]
+#[test]
+fn instruction_flags() {
+    use llvm_ir::constant;
+    init_logging();
+    let path = llvm_bc_dir().join("instruction_flags.ll.bc");
+    let module = Module::from_bc_path(&path).expect("Failed to parse module");
+
+    let f = module.get_func_by_name("int_flags").unwrap();
+    let instrs = &f.basic_blocks[0].instrs;
+    let add: &instruction::Add = &instrs[0].clone().try_into().expect("Should be an add");
+    assert!(add.nsw);
+    assert!(!add.nuw);
+    let sub: &instruction::Sub = &instrs[1].clone().try_into().expect("Should be a sub");
+    assert!(!sub.nsw);
+    assert!(sub.nuw);
+    let mul: &instruction::Mul = &instrs[2].clone().try_into().expect("Should be a mul");
+    assert!(mul.nsw);
+    assert!(mul.nuw);
+    assert_eq!(&format!("{}", mul), "%3 = mul nuw nsw i32 %2, i32 %a");
+    let shl: &instruction::Shl = &instrs[3].clone().try_into().expect("Should be a shl");
+    assert!(!shl.nsw);
+    assert!(shl.nuw);
+    let udiv: &instruction::UDiv = &instrs[4].clone().try_into().expect("Should be a udiv");
+    assert!(udiv.exact);
+    assert_eq!(&format!("{}", udiv), "%5 = udiv exact i32 %4, i32 %b");
+    let sdiv: &instruction::SDiv = &instrs[5].clone().try_into().expect("Should be an sdiv");
+    assert!(!sdiv.exact);
+    let lshr: &instruction::LShr = &instrs[6].clone().try_into().expect("Should be an lshr");
+    assert!(lshr.exact);
+    let ashr: &instruction::AShr = &instrs[7].clone().try_into().expect("Should be an ashr");
+    assert!(!ashr.exact);
+    let add: &instruction::Add = &instrs[8].clone().try_into().expect("Should be an add");
+    assert!(!add.nsw);
+    assert!(!add.nuw);
+    assert_eq!(&format!("{}", add), "%9 = add i32 %8, i32 %a");
+
+    let f = module.get_func_by_name("fp_flags").unwrap();
+    let instrs = &f.basic_blocks[0].instrs;
+    let fadd: &instruction::FAdd = &instrs[0].clone().try_into().expect("Should be an fadd");
+    assert!(fadd.fast_math_flags.is_fast());
+    assert_eq!(&format!("{}", fadd), "%1 = fadd fast float %a, float %b");
+    let fsub: &instruction::FSub = &instrs[1].clone().try_into().expect("Should be an fsub");
+    assert!(fsub.fast_math_flags.no_NaNs);
+    assert!(!fsub.fast_math_flags.no_Infs);
+    let fmul: &instruction::FMul = &instrs[2].clone().try_into().expect("Should be an fmul");
+    assert!(fmul.fast_math_flags.no_Infs);
+    assert!(fmul.fast_math_flags.no_signed_zeros);
+    assert_eq!(
+        &format!("{}", fmul),
+        "%3 = fmul ninf nsz float %2, float %a"
+    );
+    let fdiv: &instruction::FDiv = &instrs[3].clone().try_into().expect("Should be an fdiv");
+    assert!(fdiv.fast_math_flags.allow_reciprocal);
+    let frem: &instruction::FRem = &instrs[4].clone().try_into().expect("Should be an frem");
+    assert!(frem.fast_math_flags.is_empty());
+    let fneg: &instruction::FNeg = &instrs[5].clone().try_into().expect("Should be an fneg");
+    assert!(fneg.fast_math_flags.allow_reassoc);
+    assert!(fneg.fast_math_flags.allow_contract);
+    assert!(fneg.fast_math_flags.approx_func);
+    assert!(!fneg.fast_math_flags.no_NaNs);
+    let fcmp: &instruction::FCmp = &instrs[6].clone().try_into().expect("Should be an fcmp");
+    assert!(fcmp.fast_math_flags.no_NaNs);
+    assert_eq!(
+        &format!("{}", fcmp),
+        "%7 = fcmp nnan oeq float %6, float %a"
+    );
+
+    let f = module.get_func_by_name("gep_flags").unwrap();
+    let instrs = &f.basic_blocks[0].instrs;
+    let gep: &instruction::GetElementPtr = &instrs[0].clone().try_into().expect("Should be a gep");
+    assert!(gep.in_bounds);
+    let gep: &instruction::GetElementPtr = &instrs[1].clone().try_into().expect("Should be a gep");
+    assert!(!gep.in_bounds);
+
+    let var = module.get_global_var_by_name("add_const").unwrap();
+    let add: constant::Add = var
+        .initializer
+        .as_ref()
+        .unwrap()
+        .as_ref()
+        .clone()
+        .try_into()
+        .expect("Should be an add");
+    assert!(add.nuw);
+    assert!(add.nsw);
+    assert!(format!("{}", add).starts_with("add nuw nsw ("));
+    let var = module.get_global_var_by_name("udiv_const").unwrap();
+    let udiv: constant::UDiv = var
+        .initializer
+        .as_ref()
+        .unwrap()
+        .as_ref()
+        .clone()
+        .try_into()
+        .expect("Should be a udiv");
+    assert!(udiv.exact);
+    let var = module.get_global_var_by_name("shl_const").unwrap();
+    let shl: constant::Shl = var
+        .initializer
+        .as_ref()
+        .unwrap()
+        .as_ref()
+        .clone()
+        .try_into()
+        .expect("Should be a shl");
+    assert!(!shl.nuw);
+    assert!(!shl.nsw);
+    let var = module.get_global_var_by_name("gep_const").unwrap();
+    let gep: constant::GetElementPtr = var
+        .initializer
+        .as_ref()
+        .unwrap()
+        .as_ref()
+        .clone()
+        .try_into()
+        .expect("Should be a gep");
+    assert!(gep.in_bounds);
+}
+
 #[test]
 fn datalayouts() {
     init_logging();
@@ -0,0 +1,419 @@
+#![cfg(feature = "bc-reader")]
+
+//! Tests of the pure-Rust bitcode reader. Where LLVM itself can read the
+//! bitcode in `tests/basic_bc/` and `tests/llvm_bc/`, the reader's output is
+//! checked against `Module::from_bc_path()`.
+
+use llvm_ir::bc_reader;
+use llvm_ir::LLVMVersion;
+#[cfg(feature = "llvm-sys")]
+use llvm_ir::{Instruction, Module, Terminator};
+#[cfg(feature = "llvm-sys")]
+use std::collections::HashMap;
+#[cfg(feature = "llvm-sys")]
+use std::fmt::Debug;
+use std::path::{Path, PathBuf};
+
+fn init_logging() {
+    // capture log messages with test harness
+    let _ = env_logger::builder().is_test(true).try_init();
+}
+
+/// Files which are invalid on purpose
+const INVALID: &[&str] = &[
+    "invalid.ll.bc",
+    "invalid-functionptr-align.ll.bc",
+    "invalid-type-for-null-constant.ll.bc",
+    "null-type.ll.bc",
+    "pr18704.ll.bc",
+];
+
+/// Files with features which only the given range of LLVM versions have.
+/// Like LLVM, the bitcode reader can't read them with other versions.
+const VERSION_RANGES: &[(&str, u32, u32)] = &[
+    // bfloat
+    ("aarch64-bf16-upgrade.ll.bc", 11, 17),
+    ("arm-bf16-upgrade.ll.bc", 11, 17),
+    // callbr
+    ("callbr.ll.bc", 9, 17),
+    // ifuncs (and in `compatibility.ll`, scalable vectors)
+    ("compatibility-3.9.ll.bc", 9, 17),
+    ("compatibility-4.0.ll.bc", 9, 17),
+    ("compatibility-5.0.ll.bc", 9, 17),
+    ("compatibility-6.0.ll.bc", 9, 17),
+    ("compatibility.ll.bc", 11, 17),
+    // poison
+    ("compatibility-as-of-llvm-12.bc", 12, 17),
+    // `udiv` constant expressions
+    ("instruction_flags.ll.bc", 8, 14),
+    // scalable vectors, and `select` constant expressions
+    ("vscale-round-trip.ll.bc", 11, 16),
+    ("vscale-shuffle.ll.bc", 11, 17),
+];
+
+/// Files which LLVM reads differently, because it upgrades or folds parts of
+/// them in ways the bitcode reader doesn't replicate, with the range of LLVM
+/// versions for which that's the case
+#[cfg(feature = "llvm-sys")]
+const NOT_COMPARED: &[(&str, u32, u32)] = &[
+    // calls to old intrinsics (or to functions LLVM replaces with
+    // intrinsics), which LLVM upgrades
+    ("aarch64-addp-upgrade.bc", 8, 17),
+    ("aarch64-bf16-upgrade.ll.bc", 8, 17),
+    ("arm-bf16-upgrade.ll.bc", 8, 17),
+    ("auto_upgrade_intrinsics.bc", 8, 17),
+    ("compatibility-3.6.ll.bc", 8, 17),
+    ("compatibility-3.7.ll.bc", 8, 17),
+    ("compatibility-3.8.ll.bc", 8, 17),
+    ("compatibility-3.9.ll.bc", 8, 17),
+    ("compatibility-4.0.ll.bc", 8, 17),
+    ("compatibility-5.0.ll.bc", 8, 17),
+    ("compatibility-6.0.ll.bc", 8, 17),
+    ("objectsize-upgrade-7.0.ll.bc", 8, 17),
+    ("standardCIntrinsic.3.2.ll.bc", 8, 17),
+    ("upgrade-arc-runtime-calls.bc", 8, 17),
+    ("upgrade-arc-runtime-calls-new.bc", 8, 17),
+    ("upgrade-clang-arc-use.ll.bc", 8, 17),
+    ("upgrade-dbg-value.ll.bc", 8, 17),
+    ("upgrade-mrr-runtime-calls.bc", 8, 17),
+    ("upgrade-ptr-annotation.ll.bc", 8, 17),
+    ("upgrade-var-annotation.ll.bc", 8, 17),
+    // `blockaddress` arguments of `callbr`, which LLVM 15+ turns into
+    // constraints
+    ("callbr.ll.bc", 8, 17),
+    // a function whose subprogram isn't distinct, for which LLVM's verifier
+    // strips the module's debug info
+    ("DISubprogram-v4.ll.bc", 8, 17),
+    // constant `shufflevector`s of scalable vectors, which LLVM folds (or
+    // can't convert at all)
+    ("vscale-round-trip.ll.bc", 8, 17),
+    ("vscale-shuffle.ll.bc", 8, 17),
+    // constant expressions which LLVM 15+ no longer supports
+    ("instruction_flags.ll.bc", 8, 17),
+    // opaque pointers, which LLVM 14 only reads in `-opaque-pointers` mode
+    ("opaque_pointers.ll.bc", 8, 14),
+];
+
+/// All the bitcode files in `tests/basic_bc/` and `tests/llvm_bc/`, except
+/// those for LLVM versions newer than the selected one
+fn bitcode_files() -> Vec<PathBuf> {
+    let mut files = vec![];
+    let mut dirs = vec![
+        PathBuf::from("tests/basic_bc"),
+        PathBuf::from("tests/llvm_bc"),
+    ];
+    while let Some(dir) = dirs.pop() {
+        for entry in std::fs::read_dir(&dir).expect("Failed to read directory") {
+            let path = entry.expect("Failed to read directory entry").path();
+            if path.is_dir() {
+                if is_too_new(&path) {
+                    continue;
+                }
+                dirs.push(path);
+            } else if path
+                .extension()
+                .map_or(false, |ext| ext == "bc" || ext == "bc-g")
+            {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    files
+}
+
+/// Whether `dir` holds bitcode produced by an LLVM newer than the selected
+/// one, e.g. `tests/basic_bc/llvm16/` with the `llvm-15` feature
+fn is_too_new(dir: &Path) -> bool {
+    let name = dir.file_name().and_then(|name| name.to_str()).unwrap_or("");
+    name.rsplit("llvm")
+        .next()
+        .and_then(|version| version.parse::<u32>().ok())
+        .map_or(false, |version| version > llvm_version())
+}
+
+fn llvm_version() -> u32 {
+    if cfg!(feature = "llvm-17-or-greater") {
+        17
+    } else if cfg!(feature = "llvm-16-or-greater") {
+        16
+    } else if cfg!(feature = "llvm-15-or-greater") {
+        15
+    } else if cfg!(feature = "llvm-14-or-greater") {
+        14
+    } else if cfg!(feature = "llvm-13-or-greater") {
+        13
+    } else if cfg!(feature = "llvm-12-or-greater") {
+        12
+    } else if cfg!(feature = "llvm-11-or-greater") {
+        11
+    } else if cfg!(feature = "llvm-10-or-greater") {
+        10
+    } else if cfg!(feature = "llvm-9-or-greater") {
+        9
+    } else {
+        8
+    }
+}
+
+fn file_name(path: &Path) -> &str {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .expect("Expected a Unicode path")
+}
+
+#[test]
+fn reads_all_bitcode() {
+    init_logging();
+    let mut read = 0;
+    for path in bitcode_files() {
+        let result = bc_reader::parse_path(&path);
+        let name = file_name(&path);
+        let unsupported = VERSION_RANGES
+            .iter()
+            .any(|&(file, first, last)| file == name && !(first ..= last).contains(&llvm_version()));
+        if INVALID.contains(&name) || unsupported {
+            assert!(result.is_err(), "{}: expected an error", path.display());
+            continue;
+        }
+        let module = result.unwrap_or_else(|e| panic!("{}", e));
+        assert_eq!(module.name, path.to_str().expect("Expected a Unicode path"));
+        read += 1;
+    }
+    assert!(read > 0, "No modules were read");
+}
+
+#[test]
+fn reads_bytes() {
+    init_logging();
+    let path = "tests/basic_bc/llvm8/hello.bc";
+    let bytes = std::fs::read(path).expect("Failed to read bitcode");
+    let module = bc_reader::parse_bytes(&bytes).expect("Failed to parse bitcode");
+    let from_path = bc_reader::parse_path(path).expect("Failed to parse bitcode");
+    assert_eq!(module.functions, from_path.functions);
+    assert_eq!(module.global_vars, from_path.global_vars);
+    assert_eq!(module.llvm_version, LLVMVersion::selected());
+    assert_eq!(module.llvm_version.major(), llvm_version());
+    assert!(bc_reader::parse_bytes(b"BC\xC0\xDE").is_err());
+    assert!(bc_reader::parse_bytes(b"not bitcode").is_err());
+}
+
+/// Corrupting a few bytes of a valid file must give an error or a module,
+/// not a panic, abort or stack overflow
+#[test]
+fn reads_corrupted_bitcode() {
+    init_logging();
+    // a fixed xorshift sequence, so that any failure is reproducible
+    let mut state = 0x2545_f491_4f6c_dd1d_u64;
+    let mut next = || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state
+    };
+    for path in bitcode_files() {
+        let bytes = std::fs::read(&path).expect("Failed to read bitcode");
+        for _ in 0 .. 8 {
+            let mut corrupted = bytes.clone();
+            for _ in 0 .. 1 + next() % 4 {
+                // keep the magic number, so that the reader gets further
+                let i = 4 + (next() % (corrupted.len() as u64 - 4)) as usize;
+                corrupted[i] ^= 1 << (next() % 8);
+            }
+
+            let _ = bc_reader::parse_bytes(&corrupted);
+        }
+    }
+}
+
+/// LLVM gives declarations of intrinsics their built-in attributes, which
+/// the pure-Rust reader doesn't know. Remove those attributes, and renumber
+/// the function attribute groups to match.
+#[cfg(feature = "llvm-sys")]
+fn strip_intrinsic_attributes(module: &mut Module) {
+    for decl in &mut module.func_declarations {
+        if decl.name.starts_with("llvm.") {
+            decl.function_attribute_group = None;
+            decl.return_attributes.clear();
+            for param in &mut decl.parameters {
+                param.attributes.clear();
+            }
+        }
+    }
+    let mut new_ids = HashMap::new();
+    let mut renumber = |id: &mut Option<usize>| {
+        if let Some(id) = id {
+            let next = new_ids.len();
+            *id = *new_ids.entry(*id).or_insert(next);
+        }
+    };
+    for func in &mut module.functions {
+        renumber(&mut func.function_attribute_group);
+        for bb in &mut func.basic_blocks {
+            for instr in &mut bb.instrs {
+                if let Instruction::Call(call) = instr {
+                    renumber(&mut call.function_attribute_group);
+                }
+            }
+            match &mut bb.term {
+                Terminator::Invoke(invoke) => renumber(&mut invoke.function_attribute_group),
+                #[cfg(feature = "llvm-9-or-greater")]
+                Terminator::CallBr(callbr) => renumber(&mut callbr.function_attribute_group),
+                _ => {},
+            }
+        }
+    }
+    for decl in &mut module.func_declarations {
+        renumber(&mut decl.function_attribute_group);
+    }
+    module
+        .function_attribute_groups
+        .retain(|group| new_ids.contains_key(&group.group_id));
+    for group in &mut module.function_attribute_groups {
+        group.group_id = new_ids[&group.group_id];
+    }
+    module
+        .function_attribute_groups
+        .sort_by_key(|group| group.group_id);
+}
+
+/// The `Debug` representation of `value`, with references to metadata nodes
+/// (which LLVM prints as addresses, and the pure-Rust reader as `!N`)
+/// replaced by `!?`
+#[cfg(feature = "llvm-sys")]
+fn debug_without_node_refs(value: &impl Debug) -> String {
+    let text = format!("{:#?}", value);
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text.as_str();
+    while let Some(i) = rest.find(|c| c == '<' || c == '!') {
+        result.push_str(&rest[.. i]);
+        rest = &rest[i ..];
+        let ref_len = if rest.starts_with("<0x") {
+            rest.find('>').map(|j| j + 1)
+        } else if rest[1 ..].starts_with(|c: char| c.is_ascii_digit()) {
+            rest[1 ..].find(|c: char| !c.is_ascii_digit()).map(|j| j + 1)
+        } else {
+            None
+        };
+        match ref_len {
+            Some(len) => {
+                result.push_str("!?");
+                rest = &rest[len ..];
+            },
+            None => {
+                result.push_str(&rest[.. 1]);
+                rest = &rest[1 ..];
+            },
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+#[cfg(feature = "llvm-sys")]
+fn assert_same<T: Debug>(path: &str, what: &str, ffi: &T, ours: &T) {
+    let (ffi, ours) = (debug_without_node_refs(ffi), debug_without_node_refs(ours));
+    if ffi != ours {
+        let line = ffi
+            .lines()
+            .zip(ours.lines())
+            .position(|(a, b)| a != b)
+            .unwrap_or_else(|| ffi.lines().count().min(ours.lines().count()));
+        panic!(
+            "{}: {} differs at line {}:\nLLVM:\n{}\nbc_reader:\n{}",
+            path,
+            what,
+            line,
+            ffi.lines()
+                .skip(line.saturating_sub(5))
+                .take(10)
+                .collect::<Vec<_>>()
+                .join("\n"),
+            ours.lines()
+                .skip(line.saturating_sub(5))
+                .take(10)
+                .collect::<Vec<_>>()
+                .join("\n"),
+        );
+    }
+}
+
+#[cfg(feature = "llvm-sys")]
+#[test]
+fn same_as_llvm() {
+    init_logging();
+    let mut compared = 0;
+    for path in bitcode_files() {
+        let name = file_name(&path);
+        // LLVM reports errors in some of these files by exiting the process
+        let not_compared = NOT_COMPARED
+            .iter()
+            .any(|&(file, first, last)| file == name && (first ..= last).contains(&llvm_version()));
+        if INVALID.contains(&name) || not_compared {
+            continue;
+        }
+        let path = path.to_str().expect("Expected a Unicode path");
+        let mut ffi = match Module::from_bc_path(path) {
+            Ok(module) => module,
+            Err(_) => continue,
+        };
+        let mut ours = bc_reader::parse_path(path).unwrap_or_else(|e| panic!("{}", e));
+        strip_intrinsic_attributes(&mut ffi);
+        strip_intrinsic_attributes(&mut ours);
+
+        assert_same(path, "name", &ffi.name, &ours.name);
+        assert_same(
+            path,
+            "source_file_name",
+            &ffi.source_file_name,
+            &ours.source_file_name,
+        );
+        assert_eq!(
+            ffi.data_layout, ours.data_layout,
+            "{}: data_layout differs",
+            path
+        );
+        assert_same(
+            path,
+            "target_triple",
+            &ffi.target_triple,
+            &ours.target_triple,
+        );
+        assert_same(path, "functions", &ffi.functions, &ours.functions);
+        assert_same(
+            path,
+            "func_declarations",
+            &ffi.func_declarations,
+            &ours.func_declarations,
+        );
+        assert_same(path, "global_vars", &ffi.global_vars, &ours.global_vars);
+        assert_same(
+            path,
+            "global_aliases",
+            &ffi.global_aliases,
+            &ours.global_aliases,
+        );
+        #[cfg(feature = "llvm-9-or-greater")]
+        assert_same(
+            path,
+            "global_ifuncs",
+            &ffi.global_ifuncs,
+            &ours.global_ifuncs,
+        );
+        assert_same(
+            path,
+            "function_attribute_groups",
+            &ffi.function_attribute_groups,
+            &ours.function_attribute_groups,
+        );
+        assert_same(
+            path,
+            "inline_assembly",
+            &ffi.inline_assembly,
+            &ours.inline_assembly,
+        );
+        assert_same(path, "comdats", &ffi.comdats, &ours.comdats);
+        compared += 1;
+    }
+    assert!(compared > 0, "No modules were compared");
+}
@@ -0,0 +1,140 @@
+//! Tests of `ConstantRef`s, and their interning by a `Module`'s `Constants`.
+
+use llvm_ir::constant::Float;
+use llvm_ir::{Constant, ConstantRef};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+fn hash_of(value: &impl Hash) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+fn int(bits: u32, value: u64) -> ConstantRef {
+    ConstantRef::new(Constant::Int { bits, value })
+}
+
+#[test]
+fn constant_refs() {
+    // `ConstantRef`s to equal `Constant`s are equal (and hash the same) even
+    // if they aren't interned
+    let a = ConstantRef::new(Constant::Vector(vec![int(32, 0), int(32, 1)]));
+    let b = ConstantRef::new(Constant::Vector(vec![int(32, 0), int(32, 1)]));
+    assert!(!std::ptr::eq(&*a, &*b));
+    assert_eq!(a, b);
+    assert_eq!(hash_of(&a), hash_of(&b));
+    assert_ne!(
+        a,
+        ConstantRef::new(Constant::Vector(vec![int(32, 0), int(32, 2)]))
+    );
+    assert_ne!(int(32, 0), int(64, 0));
+    assert_eq!(a.to_string(), "< i32 0, i32 1 >");
+    assert_eq!(
+        format!("{:?}", int(8, 3)),
+        "ConstantRef(Int { bits: 8, value: 3 })"
+    );
+}
+
+#[test]
+fn float_equality() {
+    // floats are compared bitwise
+    let zero = Constant::Float(Float::Double(0.0));
+    let neg_zero = Constant::Float(Float::Double(-0.0));
+    let nan = Constant::Float(Float::Single(f32::NAN));
+    assert_ne!(zero, neg_zero);
+    assert_ne!(hash_of(&zero), hash_of(&neg_zero));
+    assert_eq!(nan, nan.clone());
+    assert_ne!(
+        Constant::Float(Float::Single(1.0)),
+        Constant::Float(Float::Double(1.0))
+    );
+    assert_eq!(Constant::Float(Float::Half), Constant::Float(Float::Half));
+}
+
+#[cfg(feature = "ll-parser")]
+#[test]
+fn interned_by_ll_parser() {
+    use llvm_ir::{Instruction, Operand, Terminator};
+
+    let module = llvm_ir::ll_parser::parse_str(
+        r#"
+@seven = global i32 7
+@sevens = global <2 x i32> <i32 7, i32 7>
+
+define i32 @f(i32 %x) {
+  %a = add i32 %x, 7
+  %b = mul i32 %a, 7
+  ret i32 %b
+}
+
+define i32 @g(i32 %x) {
+  %a = sub i32 %x, 7
+  ret i32 7
+}
+"#,
+    )
+    .expect("Failed to parse module");
+
+    let constant = |op: &Operand| match op {
+        Operand::ConstantOperand(c) => c.clone(),
+        op => panic!("expected a constant operand, got {:?}", op),
+    };
+    let f = &module.functions[0].basic_blocks[0];
+    let g = &module.functions[1].basic_blocks[0];
+    let mut sevens = vec![];
+    match (&f.instrs[0], &f.instrs[1], &g.instrs[0]) {
+        (Instruction::Add(add), Instruction::Mul(mul), Instruction::Sub(sub)) => {
+            sevens.push(constant(&add.operand1));
+            sevens.push(constant(&mul.operand1));
+            sevens.push(constant(&sub.operand1));
+        },
+        instrs => panic!("unexpected instructions {:?}", instrs),
+    }
+    match &g.term {
+        Terminator::Ret(ret) => sevens.push(constant(ret.return_operand.as_ref().unwrap())),
+        term => panic!("expected a ret, got {:?}", term),
+    }
+    sevens.push(module.global_vars[0].initializer.clone().unwrap());
+    match module.global_vars[1].initializer.as_deref() {
+        Some(Constant::Vector(elements)) => sevens.extend(elements.iter().cloned()),
+        init => panic!("expected a vector, got {:?}", init),
+    }
+
+    let interned = module
+        .constants
+        .get(&Constant::Int { bits: 32, value: 7 })
+        .expect("`i32 7` should be interned");
+    assert_eq!(sevens.len(), 7);
+    for seven in &sevens {
+        assert!(std::ptr::eq(&**seven, &*interned));
+    }
+    assert!(module
+        .constants
+        .get(&Constant::Int { bits: 32, value: 8 })
+        .is_none());
+    // a `Constant` not in the `Module` is still available, just not shared
+    let eight = module
+        .constants
+        .get_for_constant(&Constant::Int { bits: 32, value: 8 });
+    assert_eq!(eight, int(32, 8));
+}
+
+#[cfg(feature = "llvm-sys")]
+#[test]
+fn interned_by_from_bc_path() {
+    use llvm_ir::Module;
+
+    let module = Module::from_bc_path("tests/llvm_bc/constantsTest.3.2.ll.bc")
+        .expect("Failed to parse module");
+    assert!(!module.global_vars.is_empty());
+    for var in &module.global_vars {
+        if let Some(init) = &var.initializer {
+            let interned = module
+                .constants
+                .get(init)
+                .expect("initializer should be interned");
+            assert!(std::ptr::eq(&*interned, &**init));
+        }
+    }
+}
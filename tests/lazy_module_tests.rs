@@ -0,0 +1,94 @@
+#![cfg(feature = "llvm-sys")]
+
+//! Tests of `LazyModule`, checking that what it converts matches what
+//! `Module::from_bc_path()` converts from the same bitcode.
+
+use llvm_ir::{LazyModule, Module};
+
+fn init_logging() {
+    // capture log messages with test harness
+    let _ = env_logger::builder().is_test(true).try_init();
+}
+
+const BC_PATH: &str = "tests/llvm_bc/miscInstructions.3.2.ll.bc";
+
+#[test]
+fn module_level_data() {
+    init_logging();
+    let eager = Module::from_bc_path(BC_PATH).expect("Failed to parse module");
+    let lazy = LazyModule::from_bc_path(BC_PATH).expect("Failed to parse module");
+    let module = lazy.module();
+    assert!(module.functions.is_empty());
+    assert_eq!(module.name, eager.name);
+    assert_eq!(module.target_triple, eager.target_triple);
+    assert_eq!(module.data_layout, eager.data_layout);
+    assert_eq!(module.global_vars, eager.global_vars);
+    assert_eq!(module.func_declarations, eager.func_declarations);
+    assert_eq!(
+        lazy.function_names().collect::<Vec<_>>(),
+        eager
+            .functions
+            .iter()
+            .map(|f| f.name.as_str())
+            .collect::<Vec<_>>(),
+    );
+}
+
+#[test]
+fn functions_on_demand() {
+    init_logging();
+    let eager = Module::from_bc_path(BC_PATH).expect("Failed to parse module");
+    let mut lazy = LazyModule::from_bc_path(BC_PATH).expect("Failed to parse module");
+
+    let phi = lazy
+        .get_function("phiInstr")
+        .expect("Failed to find function");
+    assert_eq!(Some(phi), eager.get_func_by_name("phiInstr"));
+    assert_eq!(lazy.module().functions.len(), 1);
+    // asking again doesn't convert the function again
+    lazy.get_function("phiInstr")
+        .expect("Failed to find function");
+    assert_eq!(lazy.module().functions.len(), 1);
+
+    let fcmp = lazy.get_function("fcmp").expect("Failed to find function");
+    assert_eq!(Some(fcmp), eager.get_func_by_name("fcmp"));
+    assert_eq!(lazy.module().functions.len(), 2);
+
+    assert!(lazy.get_function("not_a_function").is_none());
+    // declared, but not defined
+    assert!(lazy
+        .get_function(&eager.func_declarations[0].name)
+        .is_none());
+    assert_eq!(lazy.module().functions.len(), 2);
+}
+
+#[test]
+fn functions_by_filter() {
+    init_logging();
+    let eager = Module::from_bc_path(BC_PATH).expect("Failed to parse module");
+    let mut lazy = LazyModule::from_bc_path(BC_PATH).expect("Failed to parse module");
+    lazy.convert_functions(|name| name.starts_with("landingpad"));
+    let module = lazy.into_module();
+    let names: Vec<&str> = module.functions.iter().map(|f| f.name.as_str()).collect();
+    assert_eq!(
+        names,
+        vec!["landingpadInstr1", "landingpadInstr2", "landingpadInstr3"]
+    );
+    for func in &module.functions {
+        assert_eq!(Some(func), eager.get_func_by_name(&func.name));
+    }
+}
+
+#[test]
+fn all_functions() {
+    init_logging();
+    let eager = Module::from_bc_path(BC_PATH).expect("Failed to parse module");
+    let mut lazy = LazyModule::from_bc_path(BC_PATH).expect("Failed to parse module");
+    lazy.get_function("test").expect("Failed to find function");
+    lazy.convert_all_functions();
+    let module = lazy.into_module();
+    assert_eq!(module.functions.len(), eager.functions.len());
+    for func in &eager.functions {
+        assert_eq!(module.get_func_by_name(&func.name), Some(func));
+    }
+}
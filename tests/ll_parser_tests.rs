@@ -0,0 +1,333 @@
+#![cfg(feature = "ll-parser")]
+
+//! Tests of the pure-Rust parser for textual IR. Where LLVM itself can parse
+//! the `.ll` files in `tests/basic_bc/`, the parser's output is checked
+//! against `Module::from_ir_path()`.
+
+use llvm_ir::ll_parser::{self, ParseError};
+#[cfg(feature = "llvm-sys")]
+use llvm_ir::{Instruction, Module, Terminator};
+#[cfg(feature = "llvm-sys")]
+use std::collections::HashMap;
+#[cfg(feature = "llvm-sys")]
+use std::fmt::Debug;
+
+fn init_logging() {
+    // capture log messages with test harness
+    let _ = env_logger::builder().is_test(true).try_init();
+}
+
+/// LLVM gives declarations of intrinsics their built-in attributes, which
+/// the pure-Rust parser doesn't know. Remove those attributes, and renumber
+/// the function attribute groups to match.
+#[cfg(feature = "llvm-sys")]
+fn strip_intrinsic_attributes(module: &mut Module) {
+    for decl in &mut module.func_declarations {
+        if decl.name.starts_with("llvm.") {
+            decl.function_attribute_group = None;
+            decl.return_attributes.clear();
+            for param in &mut decl.parameters {
+                param.attributes.clear();
+            }
+        }
+    }
+    let mut new_ids = HashMap::new();
+    let mut renumber = |id: &mut Option<usize>| {
+        if let Some(id) = id {
+            let next = new_ids.len();
+            *id = *new_ids.entry(*id).or_insert(next);
+        }
+    };
+    for func in &mut module.functions {
+        renumber(&mut func.function_attribute_group);
+        for bb in &mut func.basic_blocks {
+            for instr in &mut bb.instrs {
+                if let Instruction::Call(call) = instr {
+                    renumber(&mut call.function_attribute_group);
+                }
+            }
+            match &mut bb.term {
+                Terminator::Invoke(invoke) => renumber(&mut invoke.function_attribute_group),
+                #[cfg(feature = "llvm-9-or-greater")]
+                Terminator::CallBr(callbr) => renumber(&mut callbr.function_attribute_group),
+                _ => {},
+            }
+        }
+    }
+    for decl in &mut module.func_declarations {
+        renumber(&mut decl.function_attribute_group);
+    }
+    module
+        .function_attribute_groups
+        .retain(|group| new_ids.contains_key(&group.group_id));
+    for group in &mut module.function_attribute_groups {
+        group.group_id = new_ids[&group.group_id];
+    }
+    module
+        .function_attribute_groups
+        .sort_by_key(|group| group.group_id);
+}
+
+/// The `Debug` representation of `value`, with references to metadata nodes
+/// (which LLVM prints as addresses, and the pure-Rust parser as `!N`)
+/// replaced by `!?`
+#[cfg(feature = "llvm-sys")]
+fn debug_without_node_refs(value: &impl Debug) -> String {
+    let text = format!("{:#?}", value);
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text.as_str();
+    while let Some(i) = rest.find(|c| c == '<' || c == '!') {
+        result.push_str(&rest[.. i]);
+        rest = &rest[i ..];
+        let ref_len = if rest.starts_with("<0x") {
+            rest.find('>').map(|j| j + 1)
+        } else if rest[1 ..].starts_with(|c: char| c.is_ascii_digit()) {
+            rest[1 ..].find(|c: char| !c.is_ascii_digit()).map(|j| j + 1)
+        } else {
+            None
+        };
+        match ref_len {
+            Some(len) => {
+                result.push_str("!?");
+                rest = &rest[len ..];
+            },
+            None => {
+                result.push_str(&rest[.. 1]);
+                rest = &rest[1 ..];
+            },
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+#[cfg(feature = "llvm-sys")]
+fn assert_same<T: Debug>(path: &str, what: &str, ffi: &T, ours: &T) {
+    let (ffi, ours) = (debug_without_node_refs(ffi), debug_without_node_refs(ours));
+    if ffi != ours {
+        let line = ffi
+            .lines()
+            .zip(ours.lines())
+            .position(|(a, b)| a != b)
+            .unwrap_or_else(|| ffi.lines().count().min(ours.lines().count()));
+        panic!(
+            "{}: {} differs at line {}:\nLLVM:\n{}\nll_parser:\n{}",
+            path,
+            what,
+            line,
+            ffi.lines()
+                .skip(line.saturating_sub(5))
+                .take(10)
+                .collect::<Vec<_>>()
+                .join("\n"),
+            ours.lines()
+                .skip(line.saturating_sub(5))
+                .take(10)
+                .collect::<Vec<_>>()
+                .join("\n"),
+        );
+    }
+}
+
+#[cfg(feature = "llvm-sys")]
+#[test]
+fn same_as_llvm() {
+    init_logging();
+    let mut compared = 0;
+    for entry in std::fs::read_dir("tests/basic_bc/").expect("Failed to read tests/basic_bc/") {
+        let path = entry.expect("Failed to read directory entry").path();
+        if path.extension().map_or(true, |ext| ext != "ll") {
+            continue;
+        }
+        let path = path.to_str().expect("Expected a Unicode path");
+        // skip files which are too new for this LLVM version, saying so, as
+        // anything else LLVM can't parse is a problem with the file
+        let mut ffi = match Module::from_ir_path(path) {
+            Ok(module) => module,
+            Err(e) => {
+                println!("skipping {}, which LLVM can't parse: {}", path, e);
+                continue;
+            },
+        };
+        let mut ours = ll_parser::parse_path(path).unwrap_or_else(|e| panic!("{}", e));
+        strip_intrinsic_attributes(&mut ffi);
+        strip_intrinsic_attributes(&mut ours);
+
+        assert_same(path, "name", &ffi.name, &ours.name);
+        assert_same(
+            path,
+            "source_file_name",
+            &ffi.source_file_name,
+            &ours.source_file_name,
+        );
+        assert_eq!(
+            ffi.data_layout, ours.data_layout,
+            "{}: data_layout differs",
+            path
+        );
+        assert_same(
+            path,
+            "target_triple",
+            &ffi.target_triple,
+            &ours.target_triple,
+        );
+        assert_same(path, "functions", &ffi.functions, &ours.functions);
+        assert_same(
+            path,
+            "func_declarations",
+            &ffi.func_declarations,
+            &ours.func_declarations,
+        );
+        assert_same(path, "global_vars", &ffi.global_vars, &ours.global_vars);
+        assert_same(
+            path,
+            "global_aliases",
+            &ffi.global_aliases,
+            &ours.global_aliases,
+        );
+        #[cfg(feature = "llvm-9-or-greater")]
+        assert_same(
+            path,
+            "global_ifuncs",
+            &ffi.global_ifuncs,
+            &ours.global_ifuncs,
+        );
+        assert_same(
+            path,
+            "function_attribute_groups",
+            &ffi.function_attribute_groups,
+            &ours.function_attribute_groups,
+        );
+        assert_same(
+            path,
+            "inline_assembly",
+            &ffi.inline_assembly,
+            &ours.inline_assembly,
+        );
+        assert_same(path, "comdats", &ffi.comdats, &ours.comdats);
+        compared += 1;
+    }
+    assert!(compared > 0, "No modules were compared");
+}
+
+#[test]
+fn opaque_and_named_types() {
+    init_logging();
+    let module = ll_parser::parse_str(
+        "%pair = type { i32, %pair* }\n\
+         %opaque = type opaque\n\
+         @g = global %pair zeroinitializer\n\
+         @h = external global %opaque\n",
+    )
+    .expect("Failed to parse module");
+    assert_eq!(module.name, "");
+    assert_eq!(module.global_vars.len(), 2);
+    assert!(module.types.named_struct_def("pair").is_some());
+    assert!(module.types.named_struct_def("opaque").is_some());
+}
+
+#[test]
+fn error_positions() {
+    init_logging();
+    let err = |text: &str| match ll_parser::parse_str(text) {
+        Ok(_) => panic!("Expected a parse error for {:?}", text),
+        Err(e) => e,
+    };
+
+    assert_eq!(
+        err("define i32 @f() {\n  ret i32 %x\n}\n"),
+        ParseError {
+            line: 2,
+            column: 11,
+            message: "use of undefined value '%x'".into(),
+        },
+    );
+    let e = err("@g = global i32 0\n@h = global i32 ? 1\n");
+    assert_eq!((e.line, e.column), (2, 17));
+    let e = err("define void @f() {\n  %x = add i32 1, 2\n  %x = add i32 3, 4\n  ret void\n}\n");
+    assert_eq!((e.line, e.column), (3, 3));
+    let e = err("define void @f() {\n  br label %nowhere\n}\n");
+    assert_eq!(e.line, 2);
+    let e = err("@g = global i32 0,\n");
+    assert_eq!(e.line, 2);
+}
+
+#[test]
+fn type_errors() {
+    init_logging();
+    let err = |text: &str| match ll_parser::parse_str(text) {
+        Ok(_) => panic!("Expected a parse error for {:?}", text),
+        Err(e) => (e.line, e.column, e.message),
+    };
+
+    assert_eq!(
+        err("define i64 @f(i32 %x) {\n  ret i32 %x\n}\n"),
+        (
+            2,
+            7,
+            "value doesn't match function result type 'i64'".into()
+        ),
+    );
+    assert_eq!(
+        err("define i32 @f(i64 %x) {\n  %y = add i32 %x, 1\n  ret i32 %y\n}\n"),
+        (
+            2,
+            16,
+            "'%x' defined with type 'i64' but expected 'i32'".into()
+        ),
+    );
+    // a use before the definition is checked too
+    assert_eq!(
+        err("define i32 @f() {\na:\n  br label %b\nc:\n  ret i32 %x\nb:\n  %x = add i64 1, 2\n  br label %c\n}\n"),
+        (5, 11, "'%x' defined with type 'i64' but expected 'i32'".into()),
+    );
+    assert_eq!(
+        err("define i32 @f({ i32, i32 } %x) {\n  %y = extractvalue { i32, i32 } %x, 2\n  ret i32 %y\n}\n"),
+        (2, 8, "invalid indices for extractvalue".into()),
+    );
+    assert_eq!(
+        err("%s = type { i32 }\ndefine void @f(%s* %p) {\n  %q = getelementptr %s, %s* %p, i64 0, i32 1\n  ret void\n}\n"),
+        (3, 8, "invalid getelementptr indices".into()),
+    );
+    assert_eq!(
+        err("%s = type { i32 }\n@g = global i32* getelementptr (%s, %s* null, i64 0, i32 1)\n"),
+        (2, 18, "invalid getelementptr indices".into()),
+    );
+    assert_eq!(
+        err("define i32 @f(i32 %x) {\n  %y = extractelement i32 %x, i32 0\n  ret i32 %y\n}\n"),
+        (2, 8, "invalid extractelement operands".into()),
+    );
+    assert_eq!(
+        err("define i32 @f(i32 %x) {\n  %y = load i32, i32 %x\n  ret i32 %y\n}\n"),
+        (2, 18, "load operand must be a pointer".into()),
+    );
+    assert_eq!(
+        err("define void @f(i32 %x) {\n  br label %x\n}\n"),
+        (
+            2,
+            12,
+            "'%x' defined with type 'i32' but expected 'label'".into()
+        ),
+    );
+    assert_eq!(
+        err("define void @f() {\nentry:\n  %y = add i32 %entry, 1\n  ret void\n}\n"),
+        (
+            3,
+            16,
+            "'%entry' defined with type 'label' but expected 'i32'".into()
+        ),
+    );
+    assert_eq!(
+        err("%T = type { i32, [2 x %T] }\n@g = external global %T\n"),
+        (1, 1, "invalid recursive type".into()),
+    );
+
+    // a struct may contain itself through a pointer
+    assert!(ll_parser::parse_str("%T = type { i32, %T* }\n@g = external global %T\n").is_ok());
+    // as with LLVM, an integer literal too wide for its type is truncated
+    let module = ll_parser::parse_str("@g = global i8 300\n").expect("Failed to parse module");
+    assert_eq!(
+        module.global_vars[0].initializer.as_deref(),
+        Some(&llvm_ir::Constant::Int { bits: 8, value: 44 }),
+    );
+}
@@ -1,4 +1,5 @@
 #![cfg(feature = "llvm-12-or-greater")]
+#![cfg(feature = "llvm-sys")]
 
 //! These tests simply ensure that we can parse all of the `.bc` files in LLVM 12's `test/Bitcode` directory without crashing.
 //! We only include the `.bc` files which are new or have changed since LLVM 11 (older ones are covered in other llvm_*_tests.rs).
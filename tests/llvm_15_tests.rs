@@ -0,0 +1,114 @@
+#![cfg(feature = "llvm-15-or-greater")]
+#![cfg(feature = "llvm-sys")]
+
+//! Tests of IR features which are new in LLVM 15, using `.ll` files written
+//! directly for that purpose (see `tests/basic_bc/llvm15_features.ll`).
+
+use llvm_ir::function::{FunctionAttribute, ParameterAttribute, UWTableKind};
+use llvm_ir::instruction::{self, RMWBinOp};
+use llvm_ir::module::SanitizerMetadata;
+use llvm_ir::Module;
+use std::convert::TryInto;
+use std::path::Path;
+
+fn init_logging() {
+    // capture log messages with test harness
+    let _ = env_logger::builder().is_test(true).try_init();
+}
+
+#[test]
+fn llvm15_features() {
+    init_logging();
+    let path = Path::new("tests/basic_bc/llvm15/llvm15_features.ll.bc");
+    let module = Module::from_bc_path(path).expect("Failed to parse module");
+    let types = &module.types;
+
+    // opaque pointers are the default
+    assert!(types.uses_opaque_pointers());
+    let var = module.get_global_var_by_name("p").unwrap();
+    assert_eq!(var.ty, types.opaque_pointer());
+    assert_eq!(var.value_type, types.opaque_pointer());
+
+    let malloc = module.get_func_by_name("my_malloc").unwrap();
+    assert_eq!(
+        module.get_function_attributes(malloc.function_attribute_group),
+        vec![FunctionAttribute::AllocKind {
+            alloc: true,
+            realloc: false,
+            free: false,
+            uninitialized: true,
+            zeroed: false,
+            aligned: true,
+        }]
+    );
+    assert_eq!(
+        malloc.parameters[1].attributes,
+        vec![ParameterAttribute::AllocAlign]
+    );
+    let free = module.get_func_by_name("my_free").unwrap();
+    assert_eq!(
+        module.get_function_attributes(free.function_attribute_group),
+        vec![FunctionAttribute::AllocKind {
+            alloc: false,
+            realloc: false,
+            free: true,
+            uninitialized: false,
+            zeroed: false,
+            aligned: false,
+        }]
+    );
+    assert_eq!(
+        free.parameters[0].attributes,
+        vec![ParameterAttribute::AllocPtr]
+    );
+
+    let func = module.get_func_by_name("atomics").unwrap();
+    let instrs = &func.basic_blocks[0].instrs;
+    let fmax: instruction::AtomicRMW = instrs[0]
+        .clone()
+        .try_into()
+        .expect("Should be an atomicrmw");
+    assert_eq!(fmax.operation, RMWBinOp::FMax);
+    assert_eq!(module.type_of(&fmax), types.single());
+    assert_eq!(
+        &fmax.to_string(),
+        "%1 = atomicrmw fmax ptr %f, float 1 seq_cst"
+    );
+    let fmin: instruction::AtomicRMW = instrs[1]
+        .clone()
+        .try_into()
+        .expect("Should be an atomicrmw");
+    assert_eq!(fmin.operation, RMWBinOp::FMin);
+
+    let var = module.get_global_var_by_name("no_asan").unwrap();
+    assert_eq!(
+        var.sanitizer_metadata,
+        SanitizerMetadata {
+            no_address: true,
+            is_dyn_init: true,
+            ..Default::default()
+        }
+    );
+    let var = module.get_global_var_by_name("tagged").unwrap();
+    assert_eq!(
+        var.sanitizer_metadata,
+        SanitizerMetadata {
+            memtag: true,
+            ..Default::default()
+        }
+    );
+    let var = module.get_global_var_by_name("g").unwrap();
+    assert_eq!(var.sanitizer_metadata, SanitizerMetadata::default());
+
+    let func = module.get_func_by_name("sync_unwind").unwrap();
+    let attrs = module.get_function_attributes(func.function_attribute_group);
+    assert_eq!(attrs.len(), 3);
+    assert!(attrs.contains(&FunctionAttribute::UWTable(UWTableKind::Sync)));
+    assert!(attrs.contains(&FunctionAttribute::PresplitCoroutine));
+    assert!(attrs.contains(&FunctionAttribute::FnRetThunkExtern));
+    let func = module.get_func_by_name("async_unwind").unwrap();
+    assert_eq!(
+        module.get_function_attributes(func.function_attribute_group),
+        vec![FunctionAttribute::UWTable(UWTableKind::Async)]
+    );
+}
@@ -0,0 +1,103 @@
+#![cfg(feature = "llvm-16-or-greater")]
+#![cfg(feature = "llvm-sys")]
+
+//! Tests of IR features which are new in LLVM 16, using `.ll` files written
+//! directly for that purpose (see `tests/basic_bc/llvm16_features.ll`).
+
+use llvm_ir::function::{FunctionAttribute, MemoryEffect};
+use llvm_ir::instruction::{self, RMWBinOp};
+use llvm_ir::{Module, Type};
+use std::convert::TryInto;
+use std::path::Path;
+
+fn init_logging() {
+    // capture log messages with test harness
+    let _ = env_logger::builder().is_test(true).try_init();
+}
+
+#[test]
+fn llvm16_features() {
+    init_logging();
+    let path = Path::new("tests/basic_bc/llvm16/llvm16_features.ll.bc");
+    let module = Module::from_bc_path(path).expect("Failed to parse module");
+    let types = &module.types;
+
+    let func = module.get_func_by_name("pure").unwrap();
+    assert_eq!(
+        module.get_function_attributes(func.function_attribute_group),
+        vec![FunctionAttribute::Memory {
+            argmem: MemoryEffect::None,
+            inaccessiblemem: MemoryEffect::None,
+            other: MemoryEffect::None,
+        }]
+    );
+    let func = module.get_func_by_name("reads_args").unwrap();
+    assert_eq!(
+        module.get_function_attributes(func.function_attribute_group),
+        vec![FunctionAttribute::Memory {
+            argmem: MemoryEffect::Read,
+            inaccessiblemem: MemoryEffect::None,
+            other: MemoryEffect::None,
+        }]
+    );
+    let func = module.get_func_by_name("mixed").unwrap();
+    assert_eq!(
+        module.get_function_attributes(func.function_attribute_group),
+        vec![FunctionAttribute::Memory {
+            argmem: MemoryEffect::ReadWrite,
+            inaccessiblemem: MemoryEffect::Write,
+            other: MemoryEffect::Read,
+        }]
+    );
+
+    let func = module.get_func_by_name("atomics").unwrap();
+    let instrs = &func.basic_blocks[0].instrs;
+    let inc: instruction::AtomicRMW = instrs[0]
+        .clone()
+        .try_into()
+        .expect("Should be an atomicrmw");
+    assert_eq!(inc.operation, RMWBinOp::UIncWrap);
+    assert_eq!(module.type_of(&inc), types.i32());
+    let dec: instruction::AtomicRMW = instrs[1]
+        .clone()
+        .try_into()
+        .expect("Should be an atomicrmw");
+    assert_eq!(dec.operation, RMWBinOp::UDecWrap);
+    assert!(dec.volatile);
+    assert_eq!(
+        &dec.to_string(),
+        "%2 = atomicrmw volatile udec_wrap ptr %p, i32 %1 acquire"
+    );
+
+    // the `memory` attribute of the called function determines whether a call
+    // may read or write memory
+    let functions = module.function_index();
+    let func = functions.get_func_by_name("calls").unwrap();
+    let instrs = &func.basic_blocks[0].instrs;
+    assert!(!instrs[0].may_read_memory(&functions));
+    assert!(!instrs[0].may_write_memory(&functions));
+    assert!(instrs[1].may_read_memory(&functions));
+    assert!(!instrs[1].may_write_memory(&functions));
+    assert!(instrs[2].may_read_memory(&functions));
+    assert!(instrs[2].may_write_memory(&functions));
+
+    let func = module.get_func_by_name("target_ext").unwrap();
+    let image = &func.parameters[0].ty;
+    match image.as_ref() {
+        Type::TargetExtType {
+            name,
+            type_params,
+            int_params,
+        } => {
+            assert_eq!(name, "spirv.Image");
+            assert_eq!(type_params, &vec![types.i8()]);
+            assert_eq!(int_params, &vec![0, 1]);
+        },
+        ty => panic!("Expected a target extension type, got {:?}", ty),
+    }
+    assert_eq!(&image.to_string(), "target(\"spirv.Image\", i8, 0, 1)");
+    assert_eq!(
+        func.parameters[1].ty,
+        types.target_ext_type("spirv.Event".into(), vec![], vec![])
+    );
+}
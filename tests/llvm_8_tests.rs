@@ -1,3 +1,5 @@
+#![cfg(feature = "llvm-sys")]
+
 //! These tests simply ensure that we can parse all of the `.bc` files in LLVM 8's `test/Bitcode` directory without crashing.
 //! Human-readable `.ll` versions of these files can be found in the LLVM repo at `test/Bitcode` on the git branch `release_80`.
 
@@ -207,6 +209,30 @@ use llvm_ir::types::NamedStructDef;
 use llvm_ir::*;
 use std::convert::TryInto;
 
+/// LLVM 16+ upgrades the typed pointers in these old `.bc` files to opaque
+/// pointers. This adjusts the expected textual form of an instruction to
+/// match, replacing each pointer type (e.g., `i8*`) with `ptr`.
+fn expected_text(module: &Module, text: &str) -> String {
+    if !module.types.uses_opaque_pointers() {
+        return text.into();
+    }
+    text.split(' ')
+        .map(|token| {
+            let (token, comma) = match token.strip_suffix(',') {
+                Some(token) => (token, ","),
+                None => (token, ""),
+            };
+            if token.ends_with('*') {
+                let start = token.rfind('(').map_or(0, |i| i + 1);
+                format!("{}ptr{}", &token[.. start], comma)
+            } else {
+                format!("{}{}", token, comma)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
 /// Additionally ensure that certain constructs were parsed correctly
 /// (these constructs don't currently appear in any of the basic_tests)
 #[test]
@@ -260,6 +286,9 @@ fn DILocation_implicit_code_extra_checks() {
             } else {
                 panic!("Expected invoke.arguments[0].0 to be a pointer to a Type::NamedStructType; instead it was a pointer to a {:?}", **pointee_type);
             }
+        } else if module.types.uses_opaque_pointers() {
+            // LLVM 16+ upgrades the typed pointers in old bitcode to opaque
+            // pointers, so there's no pointee type to check
         } else {
             panic!(
                 "Exected invoke.arguments[0].0 to be of PointerType; instead it was {:?}",
@@ -281,12 +310,12 @@ fn DILocation_implicit_code_extra_checks() {
     #[cfg(feature = "llvm-8-or-lower")]
     assert_eq!(
         &format!("{}", invoke),
-        "%0 = invoke @_ZN1A3fooEi(%struct.A* %a, i32 0) to label %invoke.cont unwind label %lpad",
+        &expected_text(&module, "%0 = invoke @_ZN1A3fooEi(%struct.A* %a, i32 0) to label %invoke.cont unwind label %lpad"),
     );
     #[cfg(feature = "llvm-9-or-greater")]
     assert_eq!(
         &format!("{}", invoke),
-        "%0 = invoke @_ZN1A3fooEi(%struct.A* %a, i32 0) to label %invoke.cont unwind label %lpad (with debugloc)",
+        &expected_text(&module, "%0 = invoke @_ZN1A3fooEi(%struct.A* %a, i32 0) to label %invoke.cont unwind label %lpad (with debugloc)"),
     );
 
     // For the rest of the function, our numbered variables are one-off the
@@ -308,7 +337,9 @@ fn DILocation_implicit_code_extra_checks() {
         .unwrap_or_else(|_| panic!("Expected a landingpad, got {:?}", &lpad.instrs[0]));
     let expected_landingpad_resultty = module.types.struct_of(
         vec![
-            module.types.pointer_to(module.types.i8()),
+            module
+                .types
+                .pointer_to_value_in_addr_space(module.types.i8(), 0),
             module.types.i32(),
         ],
         false,
@@ -318,11 +349,14 @@ fn DILocation_implicit_code_extra_checks() {
     assert_eq!(landingpad.cleanup, false);
     assert_eq!(landingpad.dest, Name::Number(1));
     #[cfg(feature = "llvm-8-or-lower")]
-    assert_eq!(&format!("{}", landingpad), "%1 = landingpad { i8*, i32 }");
+    assert_eq!(
+        &format!("{}", landingpad),
+        &expected_text(&module, "%1 = landingpad { i8*, i32 }")
+    );
     #[cfg(feature = "llvm-9-or-greater")]
     assert_eq!(
         &format!("{}", landingpad),
-        "%1 = landingpad { i8*, i32 } (with debugloc)"
+        &expected_text(&module, "%1 = landingpad { i8*, i32 } (with debugloc)")
     );
     let eval: &instruction::ExtractValue = &lpad.instrs[1]
         .clone()
@@ -339,11 +373,17 @@ fn DILocation_implicit_code_extra_checks() {
     assert_eq!(eval.indices[0], 0);
     assert_eq!(eval.dest, Name::Number(2));
     #[cfg(feature = "llvm-8-or-lower")]
-    assert_eq!(&format!("{}", eval), "%2 = extractvalue { i8*, i32 } %1, 0");
+    assert_eq!(
+        &format!("{}", eval),
+        &expected_text(&module, "%2 = extractvalue { i8*, i32 } %1, 0")
+    );
     #[cfg(feature = "llvm-9-or-greater")]
     assert_eq!(
         &format!("{}", eval),
-        "%2 = extractvalue { i8*, i32 } %1, 0 (with debugloc)"
+        &expected_text(
+            &module,
+            "%2 = extractvalue { i8*, i32 } %1, 0 (with debugloc)"
+        )
     );
 
     // From this point on, our numbers are off by 2 instead of 1, due to
@@ -363,12 +403,15 @@ fn DILocation_implicit_code_extra_checks() {
     #[cfg(feature = "llvm-8-or-lower")]
     assert_eq!(
         &format!("{}", landingpad),
-        "%10 = landingpad { i8*, i32 } cleanup"
+        &expected_text(&module, "%10 = landingpad { i8*, i32 } cleanup")
     );
     #[cfg(feature = "llvm-9-or-greater")]
     assert_eq!(
         &format!("{}", landingpad),
-        "%10 = landingpad { i8*, i32 } cleanup (with debugloc)"
+        &expected_text(
+            &module,
+            "%10 = landingpad { i8*, i32 } cleanup (with debugloc)"
+        )
     );
     let eval: &instruction::ExtractValue = &lpad1.instrs[3]
         .clone()
@@ -387,12 +430,15 @@ fn DILocation_implicit_code_extra_checks() {
     #[cfg(feature = "llvm-8-or-lower")]
     assert_eq!(
         &format!("{}", eval),
-        "%12 = extractvalue { i8*, i32 } %10, 1"
+        &expected_text(&module, "%12 = extractvalue { i8*, i32 } %10, 1")
     );
     #[cfg(feature = "llvm-9-or-greater")]
     assert_eq!(
         &format!("{}", eval),
-        "%12 = extractvalue { i8*, i32 } %10, 1 (with debugloc)"
+        &expected_text(
+            &module,
+            "%12 = extractvalue { i8*, i32 } %10, 1 (with debugloc)"
+        )
     );
 
     let trycont = func
@@ -425,7 +471,9 @@ fn DILocation_implicit_code_extra_checks() {
         ival.element,
         Operand::LocalOperand {
             name: Name::from("exn4"),
-            ty: module.types.pointer_to(module.types.i8())
+            ty: module
+                .types
+                .pointer_to_value_in_addr_space(module.types.i8(), 0)
         }
     );
     assert_eq!(ival.indices.len(), 1);
@@ -434,12 +482,18 @@ fn DILocation_implicit_code_extra_checks() {
     #[cfg(feature = "llvm-8-or-lower")]
     assert_eq!(
         &format!("{}", ival),
-        "%lpad.val = insertvalue { i8*, i32 } undef, i8* %exn4, 0"
+        &expected_text(
+            &module,
+            "%lpad.val = insertvalue { i8*, i32 } undef, i8* %exn4, 0"
+        )
     );
     #[cfg(feature = "llvm-9-or-greater")]
     assert_eq!(
         &format!("{}", ival),
-        "%lpad.val = insertvalue { i8*, i32 } undef, i8* %exn4, 0 (with debugloc)"
+        &expected_text(
+            &module,
+            "%lpad.val = insertvalue { i8*, i32 } undef, i8* %exn4, 0 (with debugloc)"
+        )
     );
     let ival2: &instruction::InsertValue = &ehresume.instrs[3]
         .clone()
@@ -465,12 +519,18 @@ fn DILocation_implicit_code_extra_checks() {
     #[cfg(feature = "llvm-8-or-lower")]
     assert_eq!(
         &format!("{}", ival2),
-        "%lpad.val6 = insertvalue { i8*, i32 } %lpad.val, i32 %sel5, 1"
+        &expected_text(
+            &module,
+            "%lpad.val6 = insertvalue { i8*, i32 } %lpad.val, i32 %sel5, 1"
+        )
     );
     #[cfg(feature = "llvm-9-or-greater")]
     assert_eq!(
         &format!("{}", ival2),
-        "%lpad.val6 = insertvalue { i8*, i32 } %lpad.val, i32 %sel5, 1 (with debugloc)"
+        &expected_text(
+            &module,
+            "%lpad.val6 = insertvalue { i8*, i32 } %lpad.val, i32 %sel5, 1 (with debugloc)"
+        )
     );
     let resume: &terminator::Resume = &ehresume
         .term
@@ -485,11 +545,14 @@ fn DILocation_implicit_code_extra_checks() {
         }
     );
     #[cfg(feature = "llvm-8-or-lower")]
-    assert_eq!(&format!("{}", resume), "resume { i8*, i32 } %lpad.val6");
+    assert_eq!(
+        &format!("{}", resume),
+        &expected_text(&module, "resume { i8*, i32 } %lpad.val6")
+    );
     #[cfg(feature = "llvm-9-or-greater")]
     assert_eq!(
         &format!("{}", resume),
-        "resume { i8*, i32 } %lpad.val6 (with debugloc)"
+        &expected_text(&module, "resume { i8*, i32 } %lpad.val6 (with debugloc)")
     );
 }
 
@@ -510,7 +573,9 @@ fn atomics() {
         cmpxchg.address,
         Operand::LocalOperand {
             name: Name::from("word"),
-            ty: module.types.pointer_to(module.types.i32())
+            ty: module
+                .types
+                .pointer_to_value_in_addr_space(module.types.i32(), 0)
         }
     );
     assert_eq!(
@@ -533,7 +598,10 @@ fn atomics() {
     assert_eq!(cmpxchg.failure_memory_ordering, MemoryOrdering::Monotonic);
     assert_eq!(
         &format!("{}", cmpxchg),
-        "%cmpxchg.0 = cmpxchg i32* %word, i32 0, i32 4 monotonic monotonic"
+        &expected_text(
+            &module,
+            "%cmpxchg.0 = cmpxchg i32* %word, i32 0, i32 4 monotonic monotonic"
+        )
     );
     let atomicrmw: &instruction::AtomicRMW = &bb.instrs[8]
         .clone()
@@ -543,7 +611,9 @@ fn atomics() {
         atomicrmw.address,
         Operand::LocalOperand {
             name: Name::from("word"),
-            ty: module.types.pointer_to(module.types.i32())
+            ty: module
+                .types
+                .pointer_to_value_in_addr_space(module.types.i32(), 0)
         }
     );
     assert_eq!(
@@ -558,11 +628,17 @@ fn atomics() {
     #[cfg(feature = "llvm-9-or-lower")]
     assert_eq!(
         &format!("{}", atomicrmw),
-        "%atomicrmw.xchg = atomicrmw i32* %word, i32 12 not_atomic"
+        &expected_text(
+            &module,
+            "%atomicrmw.xchg = atomicrmw i32* %word, i32 12 not_atomic"
+        )
     ); // I'm not sure why it's not_atomic for LLVM 9 and lower, but monotonic for LLVM 10+
     #[cfg(feature = "llvm-10-or-greater")]
     assert_eq!(
         &format!("{}", atomicrmw),
-        "%atomicrmw.xchg = atomicrmw xchg i32* %word, i32 12 monotonic"
+        &expected_text(
+            &module,
+            "%atomicrmw.xchg = atomicrmw xchg i32* %word, i32 12 monotonic"
+        )
     );
 }
@@ -0,0 +1,86 @@
+#![cfg(feature = "llvm-sys")]
+
+//! Tests of creating a `Module` from an LLVM module which is already in
+//! memory, rather than from a file.
+
+use llvm_ir::sys::core::*;
+use llvm_ir::types::Typed;
+use llvm_ir::Module;
+use std::ffi::CString;
+
+fn init_logging() {
+    // capture log messages with test harness
+    let _ = env_logger::builder().is_test(true).try_init();
+}
+
+#[test]
+fn from_llvm_module_ref() {
+    init_logging();
+    let module_name = CString::new("in_memory").unwrap();
+    let func_name = CString::new("foo").unwrap();
+    let module = unsafe {
+        let context = LLVMContextCreate();
+        let module = LLVMModuleCreateWithNameInContext(module_name.as_ptr(), context);
+        let i32_ty = LLVMInt32TypeInContext(context);
+        let mut param_tys = [i32_ty, i32_ty];
+        let func_ty = LLVMFunctionType(i32_ty, param_tys.as_mut_ptr(), 2, 0);
+        LLVMAddFunction(module, func_name.as_ptr(), func_ty);
+
+        let converted = Module::from_llvm_module_ref(module);
+        LLVMDisposeModule(module);
+        LLVMContextDispose(context);
+        converted
+    };
+
+    assert_eq!(module.name, "in_memory");
+    assert!(module.functions.is_empty());
+    assert_eq!(module.func_declarations.len(), 1);
+    let decl = &module.func_declarations[0];
+    assert_eq!(decl.name, "foo");
+    assert_eq!(decl.return_type, module.types.i32());
+    assert_eq!(decl.parameters.len(), 2);
+    for param in &decl.parameters {
+        assert_eq!(param.get_type(&module.types), module.types.i32());
+    }
+}
+
+#[cfg(feature = "inkwell")]
+#[test]
+fn from_inkwell() {
+    use llvm_ir::{Instruction, Name, Terminator, Type};
+
+    init_logging();
+    let context = inkwell::context::Context::create();
+    let inkwell_module = context.create_module("inkwell_module");
+    let i32_ty = context.i32_type();
+    let func_ty = i32_ty.fn_type(&[i32_ty.into(), i32_ty.into()], false);
+    let func = inkwell_module.add_function("add", func_ty, None);
+    let builder = context.create_builder();
+    builder.position_at_end(context.append_basic_block(func, "entry"));
+    let x = func.get_nth_param(0).unwrap().into_int_value();
+    let y = func.get_nth_param(1).unwrap().into_int_value();
+    let sum = builder.build_int_add(x, y, "sum").unwrap();
+    builder.build_return(Some(&sum)).unwrap();
+
+    let module = Module::from_inkwell(&inkwell_module);
+    assert_eq!(module.name, "inkwell_module");
+    assert_eq!(module.functions.len(), 1);
+    let func = &module.functions[0];
+    assert_eq!(func.name, "add");
+    assert_eq!(func.basic_blocks.len(), 1);
+    let bb = &func.basic_blocks[0];
+    assert_eq!(bb.name, Name::from("entry"));
+    assert_eq!(bb.instrs.len(), 1);
+    match &bb.instrs[0] {
+        Instruction::Add(add) => assert_eq!(add.dest, Name::from("sum")),
+        instr => panic!("expected an add, got {:?}", instr),
+    }
+    match &bb.term {
+        Terminator::Ret(ret) => assert!(ret.return_operand.is_some()),
+        term => panic!("expected a ret, got {:?}", term),
+    }
+    assert_eq!(
+        *module.type_of(&module.functions[0].parameters[0]),
+        Type::IntegerType { bits: 32 }
+    );
+}
@@ -0,0 +1,76 @@
+//! Tests of `Name`s and their interned `Symbol`s.
+
+use llvm_ir::name::{Symbol, SymbolTable};
+use llvm_ir::Name;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashSet;
+use std::hash::{Hash, Hasher};
+
+fn hash_of(value: &impl Hash) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[test]
+fn name_size() {
+    // a `Name` is no bigger than it was with `Box<String>`
+    assert_eq!(std::mem::size_of::<Name>(), 16);
+}
+
+#[test]
+fn interning() {
+    let mut symbols = SymbolTable::new();
+    assert!(symbols.is_empty());
+    let foo = symbols.intern("foo");
+    let bar = symbols.intern("bar");
+    let foo_again = symbols.intern("foo");
+    assert_eq!(symbols.len(), 2);
+    assert_eq!(foo, foo_again);
+    assert_ne!(foo, bar);
+    assert_eq!(hash_of(&foo), hash_of(&foo_again));
+    assert_eq!(foo.as_str(), "foo");
+    assert_eq!(&*bar, "bar");
+    assert_eq!(symbols.name("bar"), Name::Name(bar));
+}
+
+#[test]
+fn across_tables() {
+    // `Symbol`s with the same string are equal (and hash the same) however
+    // they were created
+    let mut table_a = SymbolTable::new();
+    let mut table_b = SymbolTable::new();
+    let a = table_a.intern("_ZN4core3fmt9Formatter3pad17h0123456789abcdefE");
+    let b = table_b.intern("_ZN4core3fmt9Formatter3pad17h0123456789abcdefE");
+    let c = Symbol::from("_ZN4core3fmt9Formatter3pad17h0123456789abcdefE");
+    assert_eq!(a, b);
+    assert_eq!(a, c);
+    assert_eq!(hash_of(&a), hash_of(&b));
+    assert_eq!(hash_of(&a), hash_of(&c));
+    assert_eq!(
+        Name::Name(a),
+        Name::from("_ZN4core3fmt9Formatter3pad17h0123456789abcdefE")
+    );
+
+    let names: HashSet<Name> = vec![
+        table_a.name("x"),
+        table_b.name("x"),
+        Name::from("x"),
+        Name::from("y"),
+        Name::from(3),
+    ]
+    .into_iter()
+    .collect();
+    assert_eq!(names.len(), 3);
+}
+
+#[test]
+fn display_and_ordering() {
+    let mut symbols = SymbolTable::new();
+    let name = symbols.name("entry");
+    assert_eq!(name.to_string(), "%entry");
+    assert_eq!(format!("{:?}", name), "Name(\"entry\")");
+    assert_eq!(Name::Number(3).to_string(), "%3");
+    assert!(symbols.name("a") < symbols.name("b"));
+    assert!(Name::from("zzz") < symbols.name("zzzz"));
+}
@@ -0,0 +1,116 @@
+#![cfg(feature = "llvm-14-or-greater")]
+#![cfg(feature = "llvm-sys")]
+
+//! LLVM 14 reads opaque pointers (`ptr`) only in opaque-pointers mode, which
+//! is a process-wide LLVM option. These tests live in their own file (and
+//! thus their own test binary) so that turning that mode on doesn't affect
+//! the typed-pointer modules used in the other tests. LLVM 15+ uses opaque
+//! pointers by default.
+
+use llvm_ir::instruction;
+use llvm_ir::{Constant, Module, Type};
+use std::convert::TryInto;
+use std::path::Path;
+
+fn init_logging() {
+    // capture log messages with test harness
+    let _ = env_logger::builder().is_test(true).try_init();
+}
+
+#[cfg(feature = "llvm-15-or-greater")]
+fn enable_opaque_pointers() {}
+
+#[cfg(feature = "llvm-14")]
+fn enable_opaque_pointers() {
+    use std::ffi::CString;
+    use std::os::raw::{c_char, c_int};
+    use std::sync::Once;
+
+    extern "C" {
+        fn LLVMParseCommandLineOptions(
+            argc: c_int,
+            argv: *const *const c_char,
+            overview: *const c_char,
+        );
+    }
+
+    static ONCE: Once = Once::new();
+    ONCE.call_once(|| {
+        let args = [
+            CString::new("llvm-ir-tests").unwrap(),
+            CString::new("-opaque-pointers").unwrap(),
+        ];
+        let argv: Vec<*const c_char> = args.iter().map(|arg| arg.as_ptr()).collect();
+        unsafe {
+            LLVMParseCommandLineOptions(argv.len() as c_int, argv.as_ptr(), std::ptr::null())
+        };
+    });
+}
+
+#[test]
+fn opaque_pointers() {
+    init_logging();
+    enable_opaque_pointers();
+    let path = Path::new("tests/basic_bc/llvm14/opaque_pointers.ll.bc");
+    let module = Module::from_bc_path(&path).expect("Failed to parse module");
+    let types = &module.types;
+    assert!(types.uses_opaque_pointers());
+    let ptr = types.opaque_pointer();
+    let pair = types.named_struct("pair");
+    assert_eq!(&ptr.to_string(), "ptr");
+    assert_eq!(
+        &types.opaque_pointer_in_addr_space(1).to_string(),
+        "ptr addrspace(1)"
+    );
+
+    let func = module.get_func_by_name("opaque").unwrap();
+    assert_eq!(func.parameters[0].ty, ptr);
+    let instrs = &func.basic_blocks[0].instrs;
+    let alloca: &instruction::Alloca = &instrs[0].clone().try_into().expect("Should be an alloca");
+    assert_eq!(alloca.allocated_type, types.i32());
+    assert_eq!(module.type_of(alloca), ptr);
+    let gep: &instruction::GetElementPtr = &instrs[1].clone().try_into().expect("Should be a gep");
+    assert_eq!(gep.source_element_type, pair);
+    assert_eq!(module.type_of(gep), ptr);
+    let load: &instruction::Load = &instrs[2].clone().try_into().expect("Should be a load");
+    assert_eq!(load.loaded_ty, types.i64());
+    assert_eq!(module.type_of(load), types.i64());
+    let call: &instruction::Call = &instrs[3].clone().try_into().expect("Should be a call");
+    assert_eq!(
+        call.function_ty,
+        types.func_type(types.i32(), vec![ptr.clone(), types.i64()], false)
+    );
+    assert_eq!(module.type_of(call), types.i32());
+    assert_eq!(module.type_of(&call.function), ptr);
+    let rmw: &instruction::AtomicRMW = &instrs[4]
+        .clone()
+        .try_into()
+        .expect("Should be an atomicrmw");
+    assert_eq!(module.type_of(rmw), types.i32());
+
+    let var = module.get_global_var_by_name("g").unwrap();
+    assert_eq!(var.ty, ptr);
+    assert_eq!(var.value_type, types.i32());
+    let var = module.get_global_var_by_name("h").unwrap();
+    assert_eq!(var.value_type, ptr);
+    let var = module.get_global_var_by_name("p").unwrap();
+    assert_eq!(var.ty, types.opaque_pointer_in_addr_space(1));
+    assert_eq!(var.addr_space, 1);
+    assert_eq!(var.value_type, pair);
+    let var = module.get_global_var_by_name("elt").unwrap();
+    let initializer = var.initializer.as_ref().unwrap();
+    match initializer.as_ref() {
+        Constant::GetElementPtr(gep) => {
+            assert_eq!(gep.source_element_type, pair);
+            assert_eq!(module.type_of(gep), ptr);
+        },
+        c => panic!("Expected a GEP constant expression, got {:?}", c),
+    }
+    let alias = module.get_global_alias_by_name("alias").unwrap();
+    assert_eq!(alias.ty, ptr);
+    assert_eq!(alias.value_type, types.i32());
+    match module.type_of(&alias.aliasee).as_ref() {
+        Type::OpaquePointerType { addr_space: 0 } => {},
+        ty => panic!("Expected aliasee to be an opaque pointer, got {:?}", ty),
+    }
+}
@@ -0,0 +1,63 @@
+#![cfg(all(feature = "llvm-sys", feature = "parallel"))]
+
+//! Tests that converting function bodies in parallel gives the same `Module`
+//! however many threads do the work.
+
+use llvm_ir::Module;
+use std::path::PathBuf;
+
+fn init_logging() {
+    // capture log messages with test harness
+    let _ = env_logger::builder().is_test(true).try_init();
+}
+
+/// Bitcode from LLVM 3.2, which every LLVM version we support can read
+fn bitcode_files() -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = std::fs::read_dir("tests/llvm_bc")
+        .expect("Failed to read directory")
+        .map(|entry| entry.expect("Failed to read directory entry").path())
+        .filter(|path| {
+            path.to_str()
+                .map_or(false, |path| path.ends_with(".3.2.ll.bc"))
+        })
+        .collect();
+    files.sort();
+    files
+}
+
+fn convert_with_threads(path: &PathBuf, num_threads: usize) -> Module {
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(num_threads)
+        .build()
+        .expect("Failed to build thread pool")
+        .install(|| Module::from_bc_path(path))
+        .unwrap_or_else(|e| panic!("Failed to parse {}: {}", path.display(), e))
+}
+
+#[test]
+fn same_with_any_number_of_threads() {
+    init_logging();
+    let files = bitcode_files();
+    assert!(!files.is_empty(), "No bitcode files found");
+    for path in files {
+        let expected = convert_with_threads(&path, 1);
+        for &num_threads in &[2, 3, 8] {
+            let module = convert_with_threads(&path, num_threads);
+            let path = path.display();
+            assert_eq!(module.functions, expected.functions, "{}", path);
+            assert_eq!(
+                module.func_declarations, expected.func_declarations,
+                "{}",
+                path
+            );
+            assert_eq!(module.global_vars, expected.global_vars, "{}", path);
+            assert_eq!(module.global_aliases, expected.global_aliases, "{}", path);
+            assert_eq!(
+                module.function_attribute_groups, expected.function_attribute_groups,
+                "{}",
+                path
+            );
+            assert_eq!(module.comdats, expected.comdats, "{}", path);
+        }
+    }
+}
@@ -0,0 +1,252 @@
+#![cfg(feature = "llvm-sys")]
+
+//! Tests of `ParseOptions`, checking that each option leaves out what it
+//! should, and that everything else is the same as with
+//! `Module::from_bc_path()`.
+
+use llvm_ir::{
+    Function,
+    HasDebugLoc,
+    Instruction,
+    Module,
+    Name,
+    Operand,
+    ParseOptions,
+    Terminator,
+};
+
+fn init_logging() {
+    // capture log messages with test harness
+    let _ = env_logger::builder().is_test(true).try_init();
+}
+
+const BC_PATH: &str = "tests/llvm_bc/miscInstructions.3.2.ll.bc";
+const ATTRIBUTES_BC_PATH: &str = "tests/llvm_bc/attributes-3.3.ll.bc";
+const DEBUG_INFO_BC_PATH: &str = "tests/llvm_bc/DILocation-implicit-code.ll.bc";
+
+/// The given function, without its body
+fn without_body(func: &Function) -> Function {
+    Function {
+        basic_blocks: vec![],
+        ..func.clone()
+    }
+}
+
+#[test]
+fn default_options() {
+    init_logging();
+    // (not `DEBUG_INFO_BC_PATH`, as its metadata differs in node addresses
+    // from one parse to the next)
+    for path in &[BC_PATH, ATTRIBUTES_BC_PATH] {
+        let module = Module::from_bc_path(path).expect("Failed to parse module");
+        let with_options = Module::from_bc_path_with_options(path, &ParseOptions::default())
+            .expect("Failed to parse module");
+        assert_eq!(module.functions, with_options.functions);
+        assert_eq!(module.func_declarations, with_options.func_declarations);
+        assert_eq!(module.global_vars, with_options.global_vars);
+        assert_eq!(
+            module.function_attribute_groups,
+            with_options.function_attribute_groups
+        );
+    }
+}
+
+#[test]
+fn declarations_only() {
+    init_logging();
+    let full = Module::from_bc_path(BC_PATH).expect("Failed to parse module");
+    let options = ParseOptions {
+        declarations_only: true,
+        ..ParseOptions::default()
+    };
+    let module =
+        Module::from_bc_path_with_options(BC_PATH, &options).expect("Failed to parse module");
+    assert!(!full.functions.is_empty());
+    assert_eq!(
+        module.functions,
+        full.functions.iter().map(without_body).collect::<Vec<_>>(),
+    );
+    assert_eq!(module.func_declarations, full.func_declarations);
+    assert_eq!(module.global_vars, full.global_vars);
+}
+
+#[test]
+fn function_filter() {
+    init_logging();
+    let full = Module::from_bc_path(BC_PATH).expect("Failed to parse module");
+    let options = ParseOptions::default().with_function_filter(|name| name == "phiInstr");
+    let module =
+        Module::from_bc_path_with_options(BC_PATH, &options).expect("Failed to parse module");
+    assert_eq!(module.functions.len(), full.functions.len());
+    for (func, full_func) in module.functions.iter().zip(&full.functions) {
+        if func.name == "phiInstr" {
+            assert!(!func.basic_blocks.is_empty());
+            assert_eq!(func, full_func);
+        } else {
+            assert_eq!(*func, without_body(full_func));
+        }
+    }
+
+    // `declarations_only` wins over the filter
+    let options = ParseOptions {
+        declarations_only: true,
+        ..options
+    };
+    let module =
+        Module::from_bc_path_with_options(BC_PATH, &options).expect("Failed to parse module");
+    assert!(module.functions.iter().all(|f| f.basic_blocks.is_empty()));
+}
+
+#[test]
+fn skip_attributes() {
+    init_logging();
+    let full = Module::from_bc_path(ATTRIBUTES_BC_PATH).expect("Failed to parse module");
+    let options = ParseOptions {
+        skip_attributes: true,
+        ..ParseOptions::default()
+    };
+    let module = Module::from_bc_path_with_options(ATTRIBUTES_BC_PATH, &options)
+        .expect("Failed to parse module");
+    assert!(!full.function_attribute_groups.is_empty());
+    assert!(module.function_attribute_groups.is_empty());
+    for func in &module.functions {
+        assert_eq!(func.function_attribute_group, None);
+        assert!(func.return_attributes.is_empty());
+        for param in &func.parameters {
+            assert!(param.attributes.is_empty());
+        }
+        for bb in &func.basic_blocks {
+            for instr in &bb.instrs {
+                if let Instruction::Call(call) = instr {
+                    assert_eq!(call.function_attribute_group, None);
+                    assert!(call.return_attributes.is_empty());
+                    assert!(call.arguments.iter().all(|(_, attrs)| attrs.is_empty()));
+                }
+            }
+        }
+    }
+    for decl in &module.func_declarations {
+        assert_eq!(decl.function_attribute_group, None);
+        assert!(decl.return_attributes.is_empty());
+        for param in &decl.parameters {
+            assert!(param.attributes.is_empty());
+        }
+    }
+    assert_eq!(module.functions.len(), full.functions.len());
+    assert_eq!(module.global_vars, full.global_vars);
+}
+
+#[test]
+fn skip_debug_info() {
+    init_logging();
+    let full = Module::from_bc_path(DEBUG_INFO_BC_PATH).expect("Failed to parse module");
+    let options = ParseOptions {
+        skip_debug_info: true,
+        ..ParseOptions::default()
+    };
+    let module = Module::from_bc_path_with_options(DEBUG_INFO_BC_PATH, &options)
+        .expect("Failed to parse module");
+    // the LLVM C API can't get debug locations before LLVM 9
+    #[cfg(feature = "llvm-9-or-greater")]
+    assert!(full
+        .functions
+        .iter()
+        .flat_map(|f| &f.basic_blocks)
+        .flat_map(|bb| &bb.instrs)
+        .any(|instr| instr.get_debug_loc().is_some()));
+    assert_eq!(module.functions.len(), full.functions.len());
+    for func in &module.functions {
+        assert_eq!(*func.get_debug_loc(), None);
+        assert!(func
+            .metadata
+            .iter()
+            .all(|attachment| attachment.kind != "dbg"));
+        for bb in &func.basic_blocks {
+            for instr in &bb.instrs {
+                assert_eq!(*instr.get_debug_loc(), None);
+            }
+            assert_eq!(*bb.term.get_debug_loc(), None);
+        }
+    }
+    for var in &module.global_vars {
+        assert_eq!(*var.get_debug_loc(), None);
+    }
+}
+
+#[test]
+fn keep_numeric_names() {
+    use llvm_ir::sys::core::*;
+    use std::ffi::CString;
+
+    init_logging();
+    // build `define i32 @f(i32 %"7") { entry: %"1" = add i32 %"7", %"7"; ret i32 %"1" }`
+    let module_name = CString::new("numeric_names").unwrap();
+    let func_name = CString::new("f").unwrap();
+    let entry = CString::new("entry").unwrap();
+    let seven = CString::new("7").unwrap();
+    let one = CString::new("1").unwrap();
+    let convert = |keep_numeric_names: bool| unsafe {
+        let context = LLVMContextCreate();
+        let module = LLVMModuleCreateWithNameInContext(module_name.as_ptr(), context);
+        let i32_ty = LLVMInt32TypeInContext(context);
+        let mut param_tys = [i32_ty];
+        let func_ty = LLVMFunctionType(i32_ty, param_tys.as_mut_ptr(), 1, 0);
+        let func = LLVMAddFunction(module, func_name.as_ptr(), func_ty);
+        let param = LLVMGetParam(func, 0);
+        LLVMSetValueName2(param, seven.as_ptr(), 1);
+        let builder = LLVMCreateBuilderInContext(context);
+        LLVMPositionBuilderAtEnd(
+            builder,
+            LLVMAppendBasicBlockInContext(context, func, entry.as_ptr()),
+        );
+        let sum = LLVMBuildAdd(builder, param, param, one.as_ptr());
+        LLVMBuildRet(builder, sum);
+        LLVMDisposeBuilder(builder);
+
+        let options = ParseOptions {
+            keep_numeric_names,
+            ..ParseOptions::default()
+        };
+        let converted = Module::from_llvm_module_ref_with_options(module, &options);
+        LLVMDisposeModule(module);
+        LLVMContextDispose(context);
+        converted
+    };
+
+    let names = |module: &Module| {
+        let func = &module.functions[0];
+        let bb = &func.basic_blocks[0];
+        let ret_operand = match &bb.term {
+            Terminator::Ret(ret) => match &ret.return_operand {
+                Some(Operand::LocalOperand { name, .. }) => name.clone(),
+                op => panic!("expected a local operand, got {:?}", op),
+            },
+            term => panic!("expected a ret, got {:?}", term),
+        };
+        (
+            func.parameters[0].name.clone(),
+            bb.name.clone(),
+            bb.instrs[0].try_get_result().cloned(),
+            ret_operand,
+        )
+    };
+
+    assert_eq!(
+        names(&convert(true)),
+        (
+            Name::from("7"),
+            Name::from("entry"),
+            Some(Name::from("1")),
+            Name::from("1"),
+        ),
+    );
+    assert_eq!(
+        names(&convert(false)),
+        (
+            Name::Number(0),
+            Name::from("entry"),
+            Some(Name::Number(1)),
+            Name::Number(1),
+        ),
+    );
+}
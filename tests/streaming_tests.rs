@@ -0,0 +1,107 @@
+#![cfg(feature = "llvm-sys")]
+
+//! Tests of `Module::stream_from_bc_path()`, checking that it passes the
+//! same functions as `Module::from_bc_path()` converts.
+
+use llvm_ir::function::GroupID;
+use llvm_ir::{Function, Instruction, Module, Terminator};
+use std::path::PathBuf;
+
+fn init_logging() {
+    // capture log messages with test harness
+    let _ = env_logger::builder().is_test(true).try_init();
+}
+
+/// Bitcode from LLVM 3.2, which every LLVM version we support can read
+fn bitcode_files() -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = std::fs::read_dir("tests/llvm_bc")
+        .expect("Failed to read directory")
+        .map(|entry| entry.expect("Failed to read directory entry").path())
+        .filter(|path| {
+            path.to_str()
+                .map_or(false, |path| path.ends_with(".3.2.ll.bc"))
+        })
+        .collect();
+    files.sort();
+    files
+}
+
+/// Replace each `GroupID` in the function with `None`, after checking that
+/// it refers to the same attributes in `module` as the corresponding one in
+/// `expected` does in `expected_module`
+fn check_and_clear_groups(
+    func: &mut Function,
+    module: &Module,
+    expected: &mut Function,
+    expected_module: &Module,
+) {
+    fn group_ids(func: &mut Function) -> Vec<&mut Option<GroupID>> {
+        let mut groups = vec![&mut func.function_attribute_group];
+        for bb in &mut func.basic_blocks {
+            for instr in &mut bb.instrs {
+                if let Instruction::Call(call) = instr {
+                    groups.push(&mut call.function_attribute_group);
+                }
+            }
+            match &mut bb.term {
+                Terminator::Invoke(invoke) => groups.push(&mut invoke.function_attribute_group),
+                Terminator::CallBr(callbr) => groups.push(&mut callbr.function_attribute_group),
+                _ => {},
+            }
+        }
+        groups
+    }
+    let name = func.name.clone();
+    let groups = group_ids(func);
+    let expected_groups = group_ids(expected);
+    assert_eq!(groups.len(), expected_groups.len(), "{}", name);
+    for (group, expected_group) in groups.into_iter().zip(expected_groups) {
+        assert_eq!(
+            module.get_function_attributes(*group),
+            expected_module.get_function_attributes(*expected_group),
+            "{}",
+            name
+        );
+        *group = None;
+        *expected_group = None;
+    }
+}
+
+#[test]
+fn same_functions_as_from_bc_path() {
+    init_logging();
+    let files = bitcode_files();
+    assert!(!files.is_empty(), "No bitcode files found");
+    for path in files {
+        let mut eager = Module::from_bc_path(&path).expect("Failed to parse module");
+        let mut expected_functions = std::mem::take(&mut eager.functions).into_iter();
+        Module::stream_from_bc_path(&path, |module, mut func| {
+            assert!(module.functions.is_empty());
+            assert_eq!(module.global_vars, eager.global_vars);
+            assert_eq!(
+                module.func_declarations.len(),
+                eager.func_declarations.len()
+            );
+            let mut expected = expected_functions
+                .next()
+                .unwrap_or_else(|| panic!("{}: unexpected function {}", path.display(), func.name));
+            check_and_clear_groups(&mut func, module, &mut expected, &eager);
+            assert_eq!(func, expected, "{}", path.display());
+        })
+        .expect("Failed to parse module");
+        assert!(
+            expected_functions.next().is_none(),
+            "{}: missing functions",
+            path.display()
+        );
+    }
+}
+
+#[test]
+fn missing_file() {
+    init_logging();
+    let result = Module::stream_from_bc_path("tests/llvm_bc/no-such-file.bc", |_, _| {
+        panic!("No functions expected")
+    });
+    assert!(result.is_err());
+}